@@ -4,21 +4,27 @@
 use std::cell::RefCell;
 use std::cell::RefMut;
 use std::collections::BTreeMap;
+use std::collections::VecDeque;
 use std::fmt::Display;
 use std::path::PathBuf;
-use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::mpsc::Sender;
 use std::sync::mpsc::channel;
 use std::time::Duration;
+use std::time::Instant;
 
 #[cfg(windows)]
 use windows::Win32::System::Com::CoInitialize;
 use winit::event::ElementState;
 use winit::event::MouseButton;
+use winit::event::TouchPhase;
 use winit::event::VirtualKeyCode;
 use winit::event::WindowEvent;
+use winit::window::CursorIcon;
 use winit::window::Window;
 use winit::{
     event::{
@@ -28,6 +34,7 @@ use winit::{
     event_loop::EventLoopProxy,
 };
 
+use crate::file_watcher::FileWatcher;
 use crate::gui::Brush;
 use crate::gui::EventVisualReaction;
 use crate::gui::InteractionState;
@@ -37,6 +44,9 @@ use crate::gui::Rect;
 use crate::gui::painter::FontSpecification;
 use crate::gui::painter::FontWeight;
 use crate::gui::painter::PaintQuality;
+use crate::gui::painter::cached::CachedTextCalculator;
+use crate::gui::widget::CommentsPanel;
+use crate::gui::widget::SearchWidget;
 use crate::gui::widget::TabWidget;
 use crate::gui::widget::TabWidgetItem;
 use crate::gui::widget::Widget;
@@ -55,8 +65,20 @@ use crate::gui::{
     },
     scroll::Scroller,
     view::{
+        EditEvent,
+        EditInput,
+        SearchInput,
+        SearchResults,
         View,
-        document_view::VERTICAL_PAGE_MARGIN
+        document_view::{
+            VERTICAL_PAGE_MARGIN,
+            find_most_visible_page,
+            paint_partial_document,
+        },
+        welcome_view::{
+            WelcomeAction,
+            WelcomeView,
+        },
     },
 };
 use crate::user_settings::SettingChangeNotification;
@@ -64,6 +86,12 @@ use crate::user_settings::SettingChangeOrigin;
 use crate::user_settings::SettingChangeSubscriber;
 use crate::user_settings::SettingName;
 use crate::user_settings::UserSettings;
+use crate::wp::diagnostics::Diagnostic;
+use crate::wp::diagnostics::Severity;
+use crate::wp::revisions::RevisionView;
+use crate::wp::statistics::DocumentStatistics;
+use crate::word_processing::LoadObserver;
+use crate::word_processing::load::PartCache;
 
 /// The background color of the application. This is the color under the pages.
 const APPLICATION_BACKGROUND_COLOR: Color = Color::from_rgb(29, 28, 33);
@@ -80,17 +108,126 @@ const TOOLTIP_TIMEOUT: Duration = Duration::from_millis(500);
 const TOOLTIP_BACKGROUND_COLOR: Color = Color::from_rgb(211, 211, 211);
 const TOOLTIP_BORDER_COLOR: Color = Color::from_rgb(168, 168, 168);
 
-pub fn load_archive_file_to_string(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Option<Rc<String>> {
-    match archive.by_name(name) {
-        Ok(zip_document) => Some(Rc::new(std::io::read_to_string(zip_document)
-                .expect("Failed to read"))),
-        Err(e) => {
-            println!("Error: {} for name \"{}\"", e, name);
-            None
-        }
+/// How far below the mouse the tooltip is shown, so the cursor doesn't cover
+/// it.
+const TOOLTIP_CURSOR_OFFSET: f32 = 20.0;
+
+/// How many pixels of touchpad scrolling (while holding Control) double the
+/// zoom factor.
+const CONTINUOUS_ZOOM_PIXELS: f32 = 300.0;
+
+/// How much of the previous viewport stays visible when paging through the
+/// document using the space bar, so the reader doesn't lose their place.
+const VIEWPORT_SCROLL_OVERLAP: f32 = 40.0;
+
+/// How far (in pixels) the mouse may move between pressing and releasing the
+/// button for it to still count as a click, and not as a drag.
+const CLICK_MAX_MOVEMENT: f32 = 4.0;
+
+/// The width of the diagnostics panel, which is toggled using F12.
+const DIAGNOSTICS_PANEL_WIDTH: f32 = 400.0;
+
+/// The file extensions of the documents we are able to open.
+pub const SUPPORTED_DOCUMENT_EXTENSIONS: &[&str] = &["docx", "docm", "dotx", "dotm", "doc", "odt", "txt", "md"];
+
+/// Describes the count with the noun, e.g. "1 word" or "2 words".
+fn describe_count(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        count => format!("{} {}s", count, noun),
+    }
+}
+
+/// Checks if the path looks like a document we are able to open.
+fn is_supported_document_path(path: &std::path::Path) -> bool {
+    let Some(extension) = path.extension() else {
+        return false;
+    };
+
+    SUPPORTED_DOCUMENT_EXTENSIONS.iter()
+        .any(|supported| extension.to_string_lossy().eq_ignore_ascii_case(supported))
+}
+
+/// Finds a name for a new document in the directory, which isn't used by
+/// another file yet.
+fn find_unused_document_path(directory: &std::path::Path) -> PathBuf {
+    (1..)
+        .map(|number| directory.join(format!("Document{}.docx", number)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// The directory new documents are created in, which is the Documents folder
+/// of the user if it exists.
+fn new_documents_directory() -> PathBuf {
+    let home = std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from);
+    match home.map(|home| home.join("Documents")) {
+        Some(documents) if documents.is_dir() => documents,
+        _ => std::env::current_dir().unwrap_or_default(),
+    }
+}
+
+/// The signature of the Compound File Binary format (MS-CFB 2.2). Encrypted
+/// (password-protected) OOXML documents are stored in such a container,
+/// instead of a ZIP archive.
+const COMPOUND_FILE_BINARY_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// Checks if the file is a Compound File Binary container, by looking at the
+/// signature at the start of the file.
+fn is_compound_file_binary(path: &std::path::Path) -> bool {
+    use std::io::Read;
+
+    let mut signature = [0u8; COMPOUND_FILE_BINARY_SIGNATURE.len()];
+    match std::fs::File::open(path) {
+        Ok(mut file) => file.read_exact(&mut signature).is_ok() && signature == COMPOUND_FILE_BINARY_SIGNATURE,
+        Err(..) => false,
     }
 }
 
+/// Checks if the document is stored in an OOXML package, i.e. isn't one of the
+/// other formats we convert.
+fn is_office_open_xml_path(path: &std::path::Path) -> bool {
+    !is_compound_file_binary(path)
+        && !crate::word_processing::odf::is_open_document_text_path(path)
+        && crate::word_processing::text::TextFormat::from_path(path).is_none()
+}
+
+/// When the file was last changed, or None when it can't be read.
+fn modification_time(path: &std::path::Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Finds the tab which has the document at the given path open, comparing
+/// the canonical paths so `a/../b.docx` and `b.docx` are the same document.
+fn find_tab_id_by_path<'a, I>(tabs: I, path: &std::path::Path) -> Option<TabId>
+        where I: Iterator<Item = (TabId, &'a std::path::Path)> {
+    let path = path.canonicalize().unwrap_or(path.to_owned());
+    tabs.into_iter()
+        .find(|(_, tab_path)| *tab_path == path)
+        .map(|(tab_id, _)| tab_id)
+}
+
+/// How a document is opened by [App::add_tab].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TabOpening {
+    /// The document is already open in the tab, which is switched to.
+    SwitchTo(TabId),
+
+    /// The document is opened in a new tab with this id.
+    New(TabId),
+}
+
+/// Decides whether the document at the path is opened in a new tab with the
+/// given id, or was already opened in one of the `tabs`.
+fn tab_opening<'a, I>(tabs: I, path: &std::path::Path, new_tab_id: TabId) -> TabOpening
+        where I: Iterator<Item = (TabId, &'a std::path::Path)> {
+    match find_tab_id_by_path(tabs, path) {
+        Some(tab_id) => TabOpening::SwitchTo(tab_id),
+        None => TabOpening::New(new_tab_id),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum TooltipState {
     /// The mouse was moved but the timeout didn't expire yet.
     Unchecked,
@@ -126,6 +263,12 @@ pub enum TabState {
 enum TabEvent {
     Layout {
         painter: Arc<RefCell<dyn Painter>>,
+
+        /// The font to use when a font of the document isn't available.
+        fallback_font: String,
+
+        /// Which text of the tracked changes to show.
+        revision_view: RevisionView,
     },
     Paint {
         painter: Arc<RefCell<dyn Painter>>,
@@ -133,18 +276,87 @@ enum TabEvent {
 
         start_y: f32,
         zoom: f32,
+
+        /// The horizontal offset of the pages from the center.
+        horizontal_offset: f32,
+    },
+
+    /// The mouse moved inside the window, to the given position.
+    MouseMoved {
+        position: Position<f32>,
+    },
+
+    /// The user clicked on the document at the given position.
+    Click {
+        position: Position<f32>,
+
+        /// Whether Ctrl was held, which follows links.
+        follows_links: bool,
+    },
+
+    /// The user pressed the left mouse button on the document at the given
+    /// position.
+    MouseDown {
+        position: Position<f32>,
+    },
+
+    /// The mouse moved to the given position while the left mouse button is
+    /// pressed.
+    MouseDragged {
+        position: Position<f32>,
+    },
+
+    /// The user typed a character or pressed a key for editing the document.
+    Edit {
+        input: EditInput,
+    },
+
+    /// Save the document, with the edits, to the given path.
+    Save {
+        path: PathBuf,
+    },
+
+    /// Print the document, using the name for the print job.
+    Print {
+        document_name: String,
+    },
+
+    /// Write the text of the document to the given path, as plain text or
+    /// Markdown.
+    Export {
+        path: PathBuf,
+        format: crate::word_processing::text::TextFormat,
+    },
+
+    /// The user searched for text, or moved to another match.
+    Search {
+        input: SearchInput,
     },
 }
 
 unsafe impl Send for TabEvent {}
 
 pub struct TabFinishPaintInfo {
-    content_height: f32
+    content_height: f32,
+
+    /// The horizontal offset of the pages, limited to what is visible.
+    horizontal_offset: f32,
+
+    /// Where the text of every comment is on the screen, for showing the
+    /// comments next to it.
+    comment_rects: Vec<Option<Rect<f32>>>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum TabCrashKind {
-    Win32ComFailure(String)
+    Win32ComFailure(String),
+
+    /// The document is encrypted, and we don't support decrypting documents
+    /// yet.
+    PasswordProtected,
+
+    /// The document couldn't be loaded, with a description of what failed.
+    LoadFailed(String),
 }
 
 unsafe impl Send for TabCrashKind {}
@@ -158,26 +370,209 @@ pub struct TabCrashReason {
 
 unsafe impl Send for TabCrashReason {}
 
+/// Stops the loading of a document, e.g. when its tab is closed. The tab
+/// thread checks it between the children of the body.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Follows the loading of the document on the tab thread. The tab is painted
+/// between the children of the body, so the pages are shown as soon as they
+/// are laid out.
+struct TabLoadObserver<'a> {
+    tab_id: TabId,
+    proxy: &'a EventLoopProxy<AppEvent>,
+    tab_event_receiver: &'a Receiver<TabEvent>,
+    finished_paint_sender: &'a Sender<TabFinishPaintInfo>,
+    cancellation_token: &'a CancellationToken,
+
+    /// The font to use when a font of the document isn't available.
+    fallback_font: &'a str,
+
+    /// The events other than painting that arrived while loading, which are
+    /// handled once the document is loaded.
+    pending_events: &'a mut VecDeque<TabEvent>,
+
+    /// The number of pages that are completely laid out, which are painted.
+    page_count: usize,
+}
+
+impl<'a> LoadObserver for TabLoadObserver<'a> {
+    fn on_progress(&mut self, progress: f32, document: &mut crate::wp::Document, root_node: &mut crate::wp::Node) {
+        _ = self.proxy.send_event(AppEvent::TabProgressed { tab_id: self.tab_id, progress });
+
+        // The last page can still get more content.
+        if root_node.page_last > self.page_count {
+            self.page_count = root_node.page_last;
+            _ = self.proxy.send_event(AppEvent::TabPagesLaidOut { tab_id: self.tab_id, page_count: self.page_count });
+        }
+
+        while let Ok(event) = self.tab_event_receiver.try_recv() {
+            let TabEvent::Paint { painter, content_rect, start_y, zoom, mut horizontal_offset } = event else {
+                self.pending_events.push_back(event);
+                continue;
+            };
+
+            // Scope this so the painter borrow is dropped before sending the
+            // finish message.
+            let page_rects = {
+                let painter = &mut *painter.as_ref().borrow_mut();
+                let mut event = crate::gui::view::PaintEvent {
+                    content_rect,
+                    opaqueness: 1.0,
+                    painter,
+                    start_y,
+                    zoom,
+                    horizontal_offset,
+                };
+                let page_rects = paint_partial_document(&mut event, document, root_node, self.page_count, self.fallback_font);
+                horizontal_offset = event.horizontal_offset;
+                page_rects
+            };
+
+            let content_height = match page_rects.last() {
+                Some(page_rect) => page_rect.bottom - page_rects[0].top,
+                None => 0.0,
+            };
+
+            _ = self.proxy.send_event(AppEvent::TabPainted {
+                tab_id: self.tab_id,
+                total_content_height: content_height,
+                page_count: page_rects.len(),
+                current_page: find_most_visible_page(&page_rects, content_rect).unwrap_or(0),
+                page_tops: page_rects.iter()
+                    .map(|page_rect| page_rect.top - content_rect.top)
+                    .collect(),
+            });
+
+            assert!(painter.try_borrow_mut().is_ok(), "Borrow painter as mutable failed after finish paint?");
+            _ = self.finished_paint_sender.send(TabFinishPaintInfo {
+                content_height,
+                horizontal_offset,
+                comment_rects: Vec::new(),
+            });
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation_token.is_cancelled()
+    }
+}
+
 pub struct Tab {
+    id: TabId,
     state: TabState,
     join_handle: Option<std::thread::JoinHandle<Result<(), TabCrashReason>>>,
     crash_reason: Option<TabCrashReason>,
 
-    #[allow(dead_code)] // this will be used in the future for saving
+    /// Stops the document from loading any further when the tab is closed.
+    cancellation_token: CancellationToken,
+
+    /// Whether the tab thread was asked to lay out the document, which it
+    /// does once for every time the tab starts loading.
+    layout_requested: bool,
+
+    /// The package the document is loaded from.
     path: PathBuf,
 
+    /// The path suggested for saving a new document created from the template
+    /// at `path`, or None when the tab shows the file at `path` itself. Such
+    /// a document is untitled until the user chose where to save it.
+    new_document_path: Option<PathBuf>,
+
+    /// Where the document was last saved to, which is saved to again without
+    /// asking. Saving loses the content that isn't kept in the tree, so the
+    /// file the document was opened from is only overwritten when the user
+    /// chose it in the save dialog.
+    save_path: Option<PathBuf>,
+
+    /// Whether the document was edited since it was loaded or last saved.
+    is_modified: bool,
+
+    /// When the file was changed by the last save of the tab, so that the
+    /// watcher doesn't take the save for a change by another application.
+    saved_modification_time: Option<std::time::SystemTime>,
+
     scroller: Scroller,
     zoomer: Zoomer,
 
     tab_event_sender: Sender<TabEvent>,
+    event_loop_proxy: EventLoopProxy<AppEvent>,
+
+    /// Watches the file for changes, when the user enabled automatic reloading.
+    file_watcher: Option<FileWatcher>,
+
+    /// The font to use when a font of the document isn't available, see
+    /// [UserSettings::setting_fallback_font].
+    fallback_font: String,
 
     /// Sent when the event was finished.
     finished_paint_receiver: Receiver<TabFinishPaintInfo>,
 
+    /// The rect the document was painted in the last time.
+    content_rect: Rect<f32>,
+
+    /// The horizontal offset of the pages from the center of the content
+    /// rect, which is changed when zooming in on a point that isn't centered.
+    horizontal_offset: f32,
+
+    /// Where the left mouse button was pressed on the document, to detect
+    /// clicks when it is released.
+    mouse_press_position: Option<Position<f32>>,
+
+    /// Whether the document has a caret, in which case the keys that type a
+    /// character edit the document instead of e.g. scrolling.
+    has_caret: bool,
+
+    /// The problems encountered while loading the document.
+    diagnostics: Vec<Diagnostic>,
+
     /// How much of the document is loaded, between 0.0 and 1.0.
     loading_progress: f32,
+    /// The number of pages that are laid out while the document is loading,
+    /// which are shown before the rest is loaded.
+    laid_out_page_count: usize,
+    /// The number of words, characters and paragraphs, once the document is
+    /// laid out.
+    statistics: Option<DocumentStatistics>,
     /// How many pages were painted.
     page_count: usize,
+    /// The page that is the most visible, counting from 0, shown in the
+    /// status bar.
+    current_page: usize,
+
+    /// The scroll position and zoom level used for the last paint, which the
+    /// page positions of the painted event are relative to.
+    painted_scroll_position: f32,
+    painted_zoom: f32,
+
+    /// The scroll positions at which a page starts at the top of the view,
+    /// for snapping to pages.
+    page_snap_positions: Vec<f32>,
+
+    /// The box for finding text in the document, opened using Ctrl+F.
+    search_widget: SearchWidget,
+
+    /// The comments on the document, on the right of the document.
+    comments_panel: CommentsPanel,
+
+    /// Which text of the tracked changes is shown, switched using Ctrl+R.
+    revision_view: RevisionView,
+
+    /// What the mouse hovers over in the document, shown in the status bar
+    /// and in a tooltip when the mouse rests on it.
+    hover_text: Option<String>,
+
+    tooltip_state: TooltipState,
 }
 
 impl Tab {
@@ -185,6 +580,8 @@ impl Tab {
         let (proxy_tx, proxy_rx) = channel();
         let (tab_event_sender, tab_event_receiver) = channel();
         let (finished_paint_sender, finished_paint_receiver) = channel();
+        let cancellation_token = CancellationToken::default();
+        let load_cancellation_token = cancellation_token.clone();
 
         let path_str = path.to_str().unwrap().to_owned();
         let join_handle = std::thread::Builder::new()
@@ -196,6 +593,23 @@ impl Tab {
             let mut view = None;
             proxy.send_event(AppEvent::PainterRequest).unwrap();
 
+            // Reused when the document is reloaded.
+            let mut part_cache = PartCache::default();
+
+            // Kept for laying out the text again after it was edited.
+            let mut edit_text_calculator = None;
+
+            // The hover text last sent, so it is only sent when it changes.
+            let mut hover_text: Option<String> = None;
+
+            // When the mouse has rested long enough for the tooltip to be
+            // shown, if it hasn't moved since.
+            let mut tooltip_deadline: Option<Instant> = None;
+
+            // The events that arrived while the document was loading, other
+            // than painting.
+            let mut pending_events = VecDeque::new();
+
             #[cfg(windows)]
             unsafe {
                 if let Err(err) = CoInitialize(None) {
@@ -208,58 +622,277 @@ impl Tab {
                 }
             }
 
-            for event in tab_event_receiver {
+            loop {
+                let event = match pending_events.pop_front() {
+                    Some(event) => event,
+
+                    // Wake up when the caret blinks, so it is painted again,
+                    // or when the mouse rested for the tooltip to be shown.
+                    None => {
+                        let caret_timeout = view.as_ref().and_then(|view: &View| view.time_until_caret_blink());
+                        let tooltip_timeout = tooltip_deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+                        match caret_timeout.into_iter().chain(tooltip_timeout).min() {
+                            Some(timeout) => match tab_event_receiver.recv_timeout(timeout) {
+                                Ok(event) => event,
+                                Err(RecvTimeoutError::Timeout) if Some(timeout) == tooltip_timeout => {
+                                    tooltip_deadline = None;
+                                    _ = proxy.send_event(AppEvent::TabMouseRested { tab_id: id });
+                                    continue;
+                                }
+                                Err(RecvTimeoutError::Timeout) => {
+                                    _ = proxy.send_event(AppEvent::TabCaretBlinked { tab_id: id });
+                                    continue;
+                                }
+                                Err(RecvTimeoutError::Disconnected) => break,
+                            },
+                            None => match tab_event_receiver.recv() {
+                                Ok(event) => event,
+                                Err(..) => break,
+                            },
+                        }
+                    }
+                };
+
                 match event {
-                    TabEvent::Layout { painter } => {
+                    TabEvent::Layout { painter, fallback_font, revision_view } => {
                         // if view.is_some() {
                         //     continue;
                         // }
 
-                        let text_calculator = {
+                        // The same text is measured many times while laying out
+                        // the lines, so the sizes are cached for the document.
+                        // The paragraphs are measured ahead on multiple
+                        // threads, with text calculators of their own.
+                        let mut text_calculator = {
                             let painter = &mut *painter.as_ref().borrow_mut();
-                            painter.text_calculator()
+                            CachedTextCalculator::new(painter.text_calculator())
+                                .with_factory(painter.text_calculator_factory())
                         };
                         assert!(painter.try_borrow_mut().is_ok(), "Borrow painter as mutable failed after getting text calculator?");
-                        finished_paint_sender.send(TabFinishPaintInfo { content_height: 0.0 }).unwrap();
+                        finished_paint_sender.send(TabFinishPaintInfo { content_height: 0.0, horizontal_offset: 0.0, comment_rects: Vec::new() }).unwrap();
+
+                        // Word Binary Files are stored in the same container as
+                        // encrypted OOXML documents.
+                        let path = std::path::Path::new(&path_str);
+                        if is_compound_file_binary(path) && !crate::word_processing::doc::is_readable_word_binary_file(path) {
+                            _ = proxy.send_event(AppEvent::TabCrashed { tab_id: id });
+                            return Err(TabCrashReason {
+                                origin: "Layout",
+                                description: "This document is password-protected, which isn't supported yet.",
+                                kind: TabCrashKind::PasswordProtected,
+                            });
+                        }
+
+                        // The integrity of OOXML packages is checked first,
+                        // so the user sees what is wrong with a damaged
+                        // document while it loads, and isn't kept waiting
+                        // for one that can't be loaded at all.
+                        let mut package_diagnostics = Vec::new();
+                        if is_office_open_xml_path(path) {
+                            let report = crate::word_processing::validate::validate_file(path);
+                            package_diagnostics = report.issues.iter().map(Diagnostic::from).collect();
+                            if !package_diagnostics.is_empty() {
+                                _ = proxy.send_event(AppEvent::TabDiagnostics { tab_id: id, diagnostics: package_diagnostics.clone() });
+                            }
+
+                            if let Some(issue) = report.issues.iter().find(|issue| issue.fatal) {
+                                _ = proxy.send_event(AppEvent::TabCrashed { tab_id: id });
+                                return Err(TabCrashReason {
+                                    origin: "Layout",
+                                    description: "The document is damaged or isn't a supported document.",
+                                    kind: TabCrashKind::LoadFailed(format!("{}: {}", issue.part, issue.message)),
+                                });
+                            }
+                        }
+
+                        let mut observer = TabLoadObserver {
+                            tab_id: id,
+                            proxy: &proxy,
+                            tab_event_receiver: &tab_event_receiver,
+                            finished_paint_sender: &finished_paint_sender,
+                            cancellation_token: &load_cancellation_token,
+                            fallback_font: &fallback_font,
+                            pending_events: &mut pending_events,
+                            page_count: 0,
+                        };
+                        // The text calculator is only borrowed while measuring,
+                        // since the painter uses it as well to paint the pages
+                        // that are laid out in the meantime.
+                        let document_view = crate::gui::view::document_view::DocumentView::new(&path_str, &mut text_calculator,
+                            fallback_font.clone(), Some(&mut part_cache), revision_view, &mut observer);
+
+                        // The tab was closed, so nobody is waiting for the
+                        // rest of the document.
+                        if load_cancellation_token.is_cancelled() {
+                            break;
+                        }
 
-                        let mut text_calculator = text_calculator.as_ref().borrow_mut();
-                        view = Some(View::Document(crate::gui::view::document_view::DocumentView::new(&path_str, &mut *text_calculator,
-                            &|progress| {
-                                _ = proxy.send_event(AppEvent::TabProgressed { tab_id: id, progress });
+                        let document_view = match document_view {
+                            Ok(document_view) => document_view,
+                            Err(error) => {
+                                _ = proxy.send_event(AppEvent::TabCrashed { tab_id: id });
+                                return Err(TabCrashReason {
+                                    origin: "Layout",
+                                    description: "The document is damaged or isn't a supported document.",
+                                    kind: TabCrashKind::LoadFailed(error.to_string()),
+                                });
                             }
-                        )));
+                        };
+
+                        edit_text_calculator = Some(text_calculator);
+
+                        // The embedded fonts are loaded in the cache of the
+                        // tab, so other documents don't use them.
+                        if let Some(fonts) = document_view.document_fonts() {
+                            painter.as_ref().borrow_mut().register_document_fonts(PainterCache::Document(id.0), fonts.clone());
+                        }
+
+                        view = Some(View::Document(document_view));
 
+                        let mut diagnostics = package_diagnostics;
+                        diagnostics.extend_from_slice(view.as_ref().unwrap().diagnostics());
+                        proxy.send_event(AppEvent::TabDiagnostics { tab_id: id, diagnostics }).unwrap();
+                        let comments = view.as_ref().unwrap().comments().to_vec();
+                        _ = proxy.send_event(AppEvent::TabComments { tab_id: id, comments });
+                        if let Some(statistics) = view.as_ref().unwrap().statistics() {
+                            _ = proxy.send_event(AppEvent::TabStatistics { tab_id: id, statistics });
+                        }
                         proxy.send_event(AppEvent::TabBecameReady(id)).unwrap();
                     }
-                    TabEvent::Paint{ painter, content_rect, start_y, zoom } => {
+                    TabEvent::Paint{ painter, content_rect, start_y, zoom, mut horizontal_offset } => {
                         let mut content_height = 0.0;
+                        let mut comment_rects = Vec::new();
 
                         // Scope this so the painter borrow is dropped before
                         // sending the finish message.
                         if let Some(view) = &mut view {
                             let painter = &mut *painter.as_ref().borrow_mut();
-                            view.handle_event(&mut crate::gui::view::Event::Paint(crate::gui::view::PaintEvent {
+                            let mut event = crate::gui::view::Event::Paint(crate::gui::view::PaintEvent {
                                 content_rect,
                                 opaqueness: 1.0,
                                 painter,
                                 start_y,
-                                zoom
-                            }));
+                                zoom,
+                                horizontal_offset,
+                            });
+                            view.handle_event(&mut event);
+
+                            if let crate::gui::view::Event::Paint(event) = event {
+                                horizontal_offset = event.horizontal_offset;
+                            }
 
                             proxy.send_event(AppEvent::TabPainted{
                                 tab_id: id,
                                 total_content_height: view.calculate_content_height(),
                                 page_count: view.page_count().unwrap_or(0),
+                                current_page: view.current_page().unwrap_or(0),
+                                page_tops: view.page_rects().iter()
+                                    .map(|page_rect| page_rect.top - content_rect.top)
+                                    .collect(),
                             }).unwrap();
 
                             content_height = view.calculate_content_height();
+                            comment_rects = view.comment_rects();
                         }
 
                         assert!(painter.try_borrow_mut().is_ok(), "Borrow painter as mutable failed after finish paint?");
                         finished_paint_sender.send(TabFinishPaintInfo{
-                            content_height
+                            content_height,
+                            horizontal_offset,
+                            comment_rects,
                         }).unwrap();
                     }
+                    TabEvent::MouseMoved { position } => {
+                        if let Some(view) = &mut view {
+                            let mut cursor = None;
+                            view.handle_event(&mut crate::gui::view::Event::MouseMoved(position, &mut cursor));
+
+                            _ = proxy.send_event(AppEvent::TabCursorChanged { tab_id: id, cursor });
+
+                            if view.hover_text() != hover_text.as_deref() {
+                                hover_text = view.hover_text().map(String::from);
+                                _ = proxy.send_event(AppEvent::TabHoverTextChanged { tab_id: id, text: hover_text.clone() });
+                            }
+                        }
+
+                        tooltip_deadline = Some(Instant::now() + TOOLTIP_TIMEOUT);
+                    }
+                    TabEvent::Click { position, follows_links } => {
+                        if let Some(view) = &mut view {
+                            let mut scroll_target = None;
+                            view.handle_event(&mut crate::gui::view::Event::Click(position, follows_links, &mut scroll_target));
+
+                            if let Some(rect) = scroll_target {
+                                _ = proxy.send_event(AppEvent::TabNavigated { tab_id: id, rect });
+                            }
+                        }
+                    }
+                    TabEvent::MouseDown { position } | TabEvent::MouseDragged { position } => {
+                        if let Some(view) = &mut view {
+                            let previous_selection = view.selection();
+
+                            let mut event = match event {
+                                TabEvent::MouseDown { .. } => crate::gui::view::Event::MouseDown(position),
+                                _ => crate::gui::view::Event::MouseDragged(position),
+                            };
+                            view.handle_event(&mut event);
+
+                            if view.selection() != previous_selection {
+                                let has_caret = view.selection().is_some_and(|selection| selection.is_collapsed());
+                                _ = proxy.send_event(AppEvent::TabSelectionChanged { tab_id: id, has_caret });
+                            }
+                        }
+                    }
+                    TabEvent::Edit { input } => {
+                        if let (Some(view), Some(text_calculator)) = (&mut view, &mut edit_text_calculator) {
+                            view.handle_event(&mut crate::gui::view::Event::Edit(EditEvent { input, text_calculator }));
+
+                            // The text or the caret changed, so the tab is
+                            // painted again either way.
+                            let has_caret = view.selection().is_some_and(|selection| selection.is_collapsed());
+                            _ = proxy.send_event(AppEvent::TabSelectionChanged { tab_id: id, has_caret });
+
+                            if let Some(statistics) = view.statistics() {
+                                _ = proxy.send_event(AppEvent::TabStatistics { tab_id: id, statistics });
+                            }
+
+                            _ = proxy.send_event(AppEvent::TabModified { tab_id: id, is_modified: view.is_modified() });
+                        }
+                    }
+                    TabEvent::Save { path } => {
+                        if let Some(view) = &mut view {
+                            let event = match view.save_to(&path) {
+                                Ok(()) => AppEvent::TabSaved { tab_id: id, path },
+                                Err(e) => AppEvent::TabSaveFailed { tab_id: id, path, error: e.to_string() },
+                            };
+                            _ = proxy.send_event(event);
+                        }
+                    }
+                    TabEvent::Print { document_name } => {
+                        if let Some(view) = &mut view {
+                            let pages = view.print_pages();
+                            match crate::print::print_pages(&document_name, &pages) {
+                                Ok(()) => println!("[Tab] Printed {} pages of \"{}\"", pages.len(), document_name),
+                                Err(crate::print::PrintError::Cancelled) => (),
+                                Err(e) => println!("[Tab] Error: failed to print \"{}\": {:?}", document_name, e),
+                            }
+                        }
+                    }
+                    TabEvent::Export { path, format } => {
+                        if let Some(text) = view.as_ref().and_then(|view| view.export_text(format)) {
+                            match std::fs::write(&path, text) {
+                                Ok(()) => println!("[Tab] Exported to \"{}\"", path.display()),
+                                Err(e) => println!("[Tab] Error: failed to export to \"{}\": {:?}", path.display(), e),
+                            }
+                        }
+                    }
+                    TabEvent::Search { input } => {
+                        if let Some(view) = &mut view {
+                            view.handle_event(&mut crate::gui::view::Event::Search(input));
+                            _ = proxy.send_event(AppEvent::TabSearched { tab_id: id, results: view.search_results() });
+                        }
+                    }
                 }
             }
 
@@ -270,32 +903,86 @@ impl Tab {
         drop(proxy_tx);
 
         Self {
+            id,
             state: TabState::Loading,
             join_handle: Some(join_handle),
             crash_reason: None,
+            cancellation_token,
+            layout_requested: false,
             path,
+            new_document_path: None,
+            save_path: None,
+            is_modified: false,
+            saved_modification_time: None,
             scroller: Scroller::new(),
             zoomer: Zoomer::new(),
             tab_event_sender,
+            event_loop_proxy,
+            file_watcher: None,
+            fallback_font: String::new(),
             finished_paint_receiver,
+            content_rect: Rect::empty(),
+            horizontal_offset: 0.0,
+            mouse_press_position: None,
+            has_caret: false,
+            diagnostics: Vec::new(),
             loading_progress: 0.0,
+            laid_out_page_count: 0,
+            statistics: None,
             page_count: 0,
+            current_page: 0,
+            painted_scroll_position: 0.0,
+            painted_zoom: 1.0,
+            page_snap_positions: Vec::new(),
+            search_widget: SearchWidget::new(),
+            comments_panel: CommentsPanel::new(),
+            revision_view: RevisionView::default(),
+            hover_text: None,
+            tooltip_state: TooltipState::NotApplicable,
         }
     }
 
     pub fn on_became_ready(&mut self) {
         self.state = TabState::Ready;
+
+        // The document was loaded again, which discarded the edits.
+        self.is_modified = false;
+
+        // The document was loaded again, so the matches are gone.
+        if self.search_widget.is_open() && !self.search_widget.query().is_empty() {
+            self.on_search_input(SearchInput::Query(self.search_widget.query().to_owned()));
+        }
     }
 
-    pub fn on_tab_painted(&mut self, total_content_height: f32, page_count: usize) {
+    pub fn on_tab_painted(&mut self, total_content_height: f32, page_count: usize, current_page: usize, page_tops: Vec<f32>) {
         self.scroller.content_height = total_content_height;
         self.page_count = page_count;
+
+        // Moving a page to the top of the view means scrolling by its offset,
+        // see scroll_viewport.
+        self.page_snap_positions = if total_content_height > 0.0 {
+            page_tops.iter()
+                .map(|top| self.painted_scroll_position + top / self.painted_zoom / total_content_height)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        self.current_page = current_page;
     }
 
     pub fn on_tab_progressed(&mut self, progress: f32) {
         self.loading_progress = progress;
     }
 
+    pub fn on_pages_laid_out(&mut self, page_count: usize) {
+        self.laid_out_page_count = page_count;
+    }
+
+    pub fn on_diagnostics(&mut self, diagnostics: Vec<Diagnostic>) {
+        self.diagnostics = diagnostics;
+    }
+
     pub fn check_state(&mut self) -> TabState {
         if self.join_handle.is_some() {
             if self.state != TabState::Crashed && self.join_handle.as_ref().unwrap().is_finished(){
@@ -333,35 +1020,48 @@ impl Tab {
     }
 
     fn on_paint(&mut self, event: &crate::gui::app::PaintEvent, content_rect: Rect<f32>) {
-        if self.state == TabState::Loading {
+        // The pages are shown as soon as they are laid out.
+        if self.state == TabState::Loading && self.laid_out_page_count == 0 {
             self.paint_loading_screen(event, content_rect);
             return;
         }
 
         assert!(event.painter.try_borrow_mut().is_ok(), "Failed to painter borrow as mutable; we can never send the PaintEvent to the tab!");
 
+        self.content_rect = content_rect;
+
+        if self.scroller.take_pending_snap() {
+            self.scroller.snap_to_nearest(&self.page_snap_positions);
+        }
+
         let zoom_level = self.zoomer.zoom_factor() * GENERAL_ZOOM_MUTLIPLIER;
+        self.painted_scroll_position = self.scroller.position();
+        self.painted_zoom = zoom_level;
         self.tab_event_sender.send(TabEvent::Paint {
             painter: event.painter.clone(),
             content_rect,
-            start_y: (VERTICAL_PAGE_MARGIN - self.scroller.content_height * self.scroller.position()) * zoom_level,
-            zoom: zoom_level
+            start_y: (VERTICAL_PAGE_MARGIN - self.scroller.content_height * self.painted_scroll_position) * zoom_level,
+            zoom: zoom_level,
+            horizontal_offset: self.horizontal_offset,
         }).unwrap();
 
-        self.scroller.content_height = self.finished_paint_receiver.recv().unwrap().content_height;
+        let finish_info = self.finished_paint_receiver.recv().unwrap();
+        self.scroller.content_height = finish_info.content_height;
+        self.horizontal_offset = finish_info.horizontal_offset;
+        self.comments_panel.set_anchor_rects(finish_info.comment_rects);
         assert!(event.painter.try_borrow_mut().is_ok(), "Failed to painter borrow as mutable while finish_paint was received!");
     }
 
     /// Returns whether or not to repaint.
-    pub fn on_scroll(&mut self, delta: MouseScrollDelta, keyboard: &uffice_lib::Keyboard) -> bool {
+    pub fn on_scroll(&mut self, delta: MouseScrollDelta, keyboard: &uffice_lib::Keyboard, mouse_position: Position<f32>) -> bool {
         if let MouseScrollDelta::LineDelta(_left, top) = delta {
             if keyboard.is_control_key_down() {
                 if top > 0.2 {
-                    return self.zoomer.increase_zoom_level();
+                    return self.zoom_around(mouse_position, Zoomer::increase_zoom_level);
                 }
 
                 if top < -0.2 {
-                    return self.zoomer.decrease_zoom_level();
+                    return self.zoom_around(mouse_position, Zoomer::decrease_zoom_level);
                 }
 
                 return false;
@@ -370,20 +1070,295 @@ impl Tab {
             return self.scroller.scroll_lines(top);
         }
 
+        // Touchpads zoom using the pixel deltas of the window events, see
+        // on_zoom_gesture.
+        if let MouseScrollDelta::PixelDelta(delta) = delta {
+            if !keyboard.is_control_key_down() {
+                return self.scroller.scroll_pixels(delta.y as f32);
+            }
+        }
+
         return false;
     }
 
+    /// Changes the zoom level using the `change_zoom` function, and scrolls
+    /// such that the point of the document under the mouse stays in place.
+    /// Returns whether or not to repaint.
+    fn zoom_around(&mut self, mouse_position: Position<f32>, change_zoom: impl FnOnce(&mut Zoomer) -> bool) -> bool {
+        let old_zoom = self.zoomer.zoom_factor_unanimated() * GENERAL_ZOOM_MUTLIPLIER;
+        if !change_zoom(&mut self.zoomer) {
+            return false;
+        }
+        let new_zoom = self.zoomer.zoom_factor_unanimated() * GENERAL_ZOOM_MUTLIPLIER;
+
+        // The horizontal position of the point, relative to the center of the
+        // pages and unzoomed.
+        let center_x = self.content_rect.left + self.content_rect.width() / 2.0;
+        let document_x = (mouse_position.x() - center_x - self.horizontal_offset) / old_zoom;
+        self.horizontal_offset = mouse_position.x() - center_x - document_x * new_zoom;
+
+        // The content height scales with the zoom level, and the scroll offset
+        // in pixels is content_height * position * zoom (see on_paint).
+        let content_height = self.scroller.content_height;
+        if content_height > 0.0 {
+            let mouse_y = mouse_position.y() - self.content_rect.top;
+            let document_y = (mouse_y + content_height * self.scroller.position() * old_zoom) / old_zoom;
+
+            let new_content_height = content_height * new_zoom / old_zoom;
+            self.scroller.content_height = new_content_height;
+            self.scroller.scroll_to((document_y * new_zoom - mouse_y) / (new_content_height * new_zoom));
+        }
+
+        true
+    }
+
+    /// Zooms continuously in response to a touchpad gesture, and snaps to the
+    /// nearest zoom level when the gesture has ended. Returns whether or not to
+    /// repaint.
+    pub fn on_zoom_gesture(&mut self, pixels: f32, phase: TouchPhase, mouse_position: Position<f32>) -> bool {
+        match phase {
+            TouchPhase::Started | TouchPhase::Moved => {
+                let factor = 2.0f32.powf(pixels / CONTINUOUS_ZOOM_PIXELS);
+                self.zoom_around(mouse_position, |zoomer| zoomer.apply_delta(factor))
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.zoom_around(mouse_position, Zoomer::snap_to_nearest_level)
+            }
+        }
+    }
+
+    /// Scrolls by one viewport height (minus a small overlap), downwards for a
+    /// positive `direction` and upwards for a negative one. Returns whether or
+    /// not to repaint.
+    pub fn scroll_viewport(&mut self, direction: f32) -> bool {
+        let zoom_level = self.zoomer.zoom_factor_unanimated() * GENERAL_ZOOM_MUTLIPLIER;
+        let viewport_height = (self.scroller.view_height() - VIEWPORT_SCROLL_OVERLAP).max(0.0);
+
+        // The content is painted at the zoom level, see on_paint.
+        self.scroller.scroll_by_pixels(direction * viewport_height / zoom_level)
+    }
+
     pub fn has_running_animations(&mut self) -> bool {
         self.zoomer.has_running_animation() || self.scroller.has_running_animation()
     }
 
-    pub fn on_mouse_input(&mut self, mouse_position: Position<f32>, button: MouseButton, state: ElementState) {
+    /// Returns whether or not to repaint.
+    pub fn on_mouse_input(&mut self, mouse_position: Position<f32>, button: MouseButton, state: ElementState,
+                          keyboard: &uffice_lib::Keyboard) -> bool {
         self.scroller.on_mouse_input(mouse_position, button, state);
+
+        // The search box covers the document.
+        if self.search_widget.is_open() && self.search_widget.rect().is_inside_inclusive(mouse_position) {
+            self.mouse_press_position = None;
+            return false;
+        }
+
+        if self.comments_panel.is_visible() && self.comments_panel.rect().is_inside_inclusive(mouse_position) {
+            self.mouse_press_position = None;
+            let width = self.comments_panel.width();
+            self.comments_panel.on_mouse_input(mouse_position, button, state);
+            return self.comments_panel.width() != width;
+        }
+
+        if button != MouseButton::Left {
+            return false;
+        }
+
+        match state {
+            ElementState::Pressed => {
+                self.mouse_press_position = if self.scroller.bar_rect.is_inside_inclusive(mouse_position) {
+                    None
+                } else {
+                    Some(mouse_position)
+                };
+
+                if self.mouse_press_position.is_some() && self.state == TabState::Ready {
+                    _ = self.tab_event_sender.send(TabEvent::MouseDown { position: mouse_position });
+                }
+            }
+            ElementState::Released => {
+                let Some(press_position) = self.mouse_press_position.take() else {
+                    return false;
+                };
+
+                let movement = mouse_position - press_position;
+                if movement.x().hypot(movement.y()) > CLICK_MAX_MOVEMENT {
+                    return false;
+                }
+
+                if self.state == TabState::Ready {
+                    _ = self.tab_event_sender.send(TabEvent::Click {
+                        position: mouse_position,
+                        follows_links: keyboard.is_control_key_down(),
+                    });
+                }
+            }
+        }
+
+        false
     }
 
     pub fn on_mouse_move(&mut self, event: &mut MouseMoveEvent) {
+        // The tooltip follows the mouse, so it is hidden until the mouse rests
+        // again.
+        if self.tooltip_state == TooltipState::Visible {
+            event.reaction = EventVisualReaction::ContentUpdated;
+        }
+        self.tooltip_state = TooltipState::Unchecked;
+
         if self.scroller.bar_rect.is_inside_inclusive(event.position) || self.scroller.interaction_state != InteractionState::Default {
             self.scroller.on_mouse_move(event);
+            return;
+        }
+
+        if self.state == TabState::Ready {
+            _ = self.tab_event_sender.send(TabEvent::MouseMoved { position: event.position });
+
+            if self.mouse_press_position.is_some() {
+                _ = self.tab_event_sender.send(TabEvent::MouseDragged { position: event.position });
+            }
+        }
+    }
+
+    /// Sends the character or key to the document, for editing the text at
+    /// the caret.
+    pub fn on_edit_input(&mut self, input: EditInput) {
+        if self.state == TabState::Ready {
+            _ = self.tab_event_sender.send(TabEvent::Edit { input });
+        }
+    }
+
+    /// Saves the document, with the edits. The first time, the user chooses
+    /// where to save it, since the content that isn't kept in the tree is
+    /// lost, after which the tab shows that file. A document that wasn't
+    /// edited isn't saved, except for an untitled one.
+    pub fn save(&mut self) {
+        if self.state != TabState::Ready {
+            return;
+        }
+
+        if !self.is_modified && self.new_document_path.is_none() {
+            println!("[Tab] Not saving \"{}\", because it wasn't edited", self.path.display());
+            return;
+        }
+
+        let path = match &self.save_path {
+            Some(path) => path.clone(),
+            None => match crate::platform::choose_save_path(self.document_path()) {
+                Some(path) => path,
+                None => return,
+            },
+        };
+
+        _ = self.tab_event_sender.send(TabEvent::Save { path });
+    }
+
+    /// The document was saved to the path, which the tab shows from now on.
+    fn on_saved(&mut self, path: PathBuf) {
+        println!("[Tab] Saved \"{}\"", path.display());
+        self.path = path.clone();
+        self.new_document_path = None;
+        self.saved_modification_time = modification_time(&path);
+        self.save_path = Some(path);
+        self.is_modified = false;
+    }
+
+    /// Whether the file is still as the tab saved it, i.e. the change the
+    /// file watcher noticed was the save itself.
+    fn is_file_as_saved(&self) -> bool {
+        self.saved_modification_time.is_some() && modification_time(&self.path) == self.saved_modification_time
+    }
+
+    /// The path of the document as shown to the user, which for an untitled
+    /// document is where it is suggested to be saved.
+    fn document_path(&self) -> &std::path::Path {
+        self.new_document_path.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Prints the document, laid out like it is shown in the tab.
+    pub fn print(&mut self) {
+        if self.state == TabState::Ready {
+            _ = self.tab_event_sender.send(TabEvent::Print { document_name: self.title() });
+        }
+    }
+
+    /// Exports the text of the document, as plain text or Markdown, to where
+    /// the user chooses.
+    pub fn export(&mut self, format: crate::word_processing::text::TextFormat) {
+        if self.state != TabState::Ready {
+            return;
+        }
+
+        let extension = match format {
+            crate::word_processing::text::TextFormat::PlainText => "txt",
+            crate::word_processing::text::TextFormat::Markdown => "md",
+        };
+
+        let suggested_path = self.document_path().with_extension(extension);
+        let Some(path) = crate::platform::choose_save_path(&suggested_path) else {
+            return;
+        };
+
+        _ = self.tab_event_sender.send(TabEvent::Export { path, format });
+    }
+
+    /// Shows the search box, which receives the typed characters from now on.
+    pub fn open_search(&mut self) {
+        self.search_widget.open();
+    }
+
+    /// Hides the search box, and removes the highlights of the matches.
+    pub fn close_search(&mut self) {
+        self.search_widget.close();
+        self.on_search_input(SearchInput::Clear);
+    }
+
+    /// Types the character in the search box, and searches for the changed
+    /// query right away.
+    pub fn on_search_character(&mut self, character: char) {
+        if self.search_widget.on_character(character) {
+            self.on_search_input(SearchInput::Query(self.search_widget.query().to_owned()));
+        }
+    }
+
+    pub fn on_search_input(&mut self, input: SearchInput) {
+        if self.state == TabState::Ready {
+            _ = self.tab_event_sender.send(TabEvent::Search { input });
+        }
+    }
+
+    /// Shows the results in the search box, and scrolls to the current match
+    /// when it isn't visible. Returns whether or not to repaint.
+    pub fn on_search_results(&mut self, results: SearchResults) -> bool {
+        if !self.search_widget.is_open() {
+            return false;
+        }
+
+        if let Some(rect) = results.current_match_rect {
+            let is_visible = rect.top >= self.content_rect.top && rect.bottom <= self.content_rect.bottom;
+            if !is_visible {
+                // Show the match at a third of the view, so the text before
+                // it is visible as well.
+                self.scroll_by_screen_offset(rect.top - (self.content_rect.top + self.content_rect.height() / 3.0));
+            }
+        }
+
+        self.search_widget.set_results(results);
+        true
+    }
+
+    /// Scrolls such that the rect, which is on the screen as of the last
+    /// paint, is at the top of the view, e.g. after clicking a link to a
+    /// bookmark.
+    pub fn scroll_to_rect(&mut self, rect: Rect<f32>) {
+        self.scroll_by_screen_offset(rect.top - self.content_rect.top);
+    }
+
+    /// Scrolls by the offset in pixels as of the last paint, which is
+    /// converted to a scroll position like the page snap positions.
+    fn scroll_by_screen_offset(&mut self, offset: f32) {
+        if self.scroller.content_height > 0.0 {
+            self.scroller.scroll_to(self.painted_scroll_position + offset / self.painted_zoom / self.scroller.content_height);
         }
     }
 
@@ -394,12 +1369,45 @@ impl Tab {
     fn reload(&mut self) {
         assert!(self.state == TabState::Ready);
         self.state = TabState::Loading;
+        self.layout_requested = false;
+        self.laid_out_page_count = 0;
+    }
+
+    /// Starts or stops watching the file on disk, depending on the user
+    /// settings.
+    fn update_file_watcher(&mut self, settings: &UserSettings) {
+        // Changing the template doesn't change the documents created from it.
+        if !settings.setting_auto_reload_on_change() || self.new_document_path.is_some() {
+            self.file_watcher = None;
+            return;
+        }
+
+        if self.file_watcher.is_some() {
+            return;
+        }
+
+        let tab_id = self.id;
+        let proxy = self.event_loop_proxy.clone();
+        match FileWatcher::new(&self.path, move || {
+            _ = proxy.send_event(AppEvent::TabFileChanged { tab_id });
+        }) {
+            Ok(watcher) => self.file_watcher = Some(watcher),
+            Err(e) => println!("[Tab] Warning: failed to watch \"{}\": {:?}", self.path.display(), e),
+        }
+    }
+}
+
+impl Drop for Tab {
+    fn drop(&mut self) {
+        // The tab thread stops when the events are disconnected, but it only
+        // receives those after the document is loaded.
+        self.cancellation_token.cancel();
     }
 }
 
 impl TabWidgetItem for Tab {
     fn title(&self) -> String {
-        self.path.file_name().unwrap().to_string_lossy().to_string()
+        self.document_path().file_name().unwrap().to_string_lossy().to_string()
     }
 }
 
@@ -407,11 +1415,22 @@ impl SettingChangeSubscriber for Tab {
     fn setting_changed(&mut self, notification: &SettingChangeNotification) {
         self.scroller.setting_changed(notification);
         self.zoomer.setting_changed(notification);
+
+        match notification.setting_name {
+            SettingName::AutoReloadOnChange => self.update_file_watcher(notification.settings),
+
+            // Applied the next time the document is laid out.
+            SettingName::FallbackFont => self.fallback_font = notification.settings.setting_fallback_font().to_owned(),
+
+            _ => (),
+        }
     }
 
     fn settings_loaded(&mut self, settings: &UserSettings) {
         self.scroller.settings_loaded(settings);
         self.zoomer.settings_loaded(settings);
+        self.update_file_watcher(settings);
+        self.fallback_font = settings.setting_fallback_font().to_owned();
     }
 }
 
@@ -423,31 +1442,56 @@ pub struct App {
     tabs: BTreeMap<TabId, Tab>,
     tab_widget: TabWidget<Tab>,
 
+    /// Files dropped onto the window arrive as separate events, so they are
+    /// collected and opened as a batch once the events are cleared.
+    pending_dropped_files: Vec<PathBuf>,
+
     keyboard: uffice_lib::Keyboard,
     mouse_position: Position<f32>,
     mouse_inside_window: bool,
     user_settings: UserSettings,
 
+    /// The welcome page, which is shown when there is no tab to show.
+    welcome_view: View,
+
+    /// Whether or not the diagnostics of the current tab are shown.
+    diagnostics_panel_visible: bool,
+
     previous_frame_had_running_animations: bool,
 }
 
 impl App {
     pub fn new(window: &mut winit::window::Window, event_loop_proxy: EventLoopProxy<AppEvent>, files_to_open: Vec<String>) -> Self {
+        let user_settings = UserSettings::load();
+        let welcome_view = View::Welcome(WelcomeView::new(user_settings.recent_documents().to_vec()));
+
         let mut app = Self {
             event_loop_proxy,
             next_tab_id: 1000,
             current_visible_tab: None,
             tabs: Default::default(),
             tab_widget: TabWidget::new(),
+            pending_dropped_files: Vec::new(),
 
             keyboard: uffice_lib::Keyboard::new(),
             mouse_position: Position::new(0.0, 0.0),
             mouse_inside_window: false,
-            user_settings: UserSettings::load(),
+            user_settings,
+
+            welcome_view,
+
+            diagnostics_panel_visible: false,
 
             previous_frame_had_running_animations: false,
         };
 
+        app.tab_widget.settings_loaded(&app.user_settings);
+
+        let event_loop_proxy = app.event_loop_proxy.clone();
+        crate::platform::ipc::listen(move |files| {
+            _ = event_loop_proxy.send_event(AppEvent::OpenFiles(files));
+        });
+
         for file in files_to_open {
             app.add_tab(file.into(), window);
         }
@@ -455,9 +1499,26 @@ impl App {
         app
     }
 
+    /// Opens a tab for the document at the given path. When the document is
+    /// already open, that tab is switched to instead.
     fn add_tab(&mut self, path: PathBuf, window: &mut winit::window::Window) -> TabId {
         let path = path.canonicalize().unwrap_or(path);
-        let tab_id = TabId(self.next_tab_id);
+
+        self.user_settings.add_recent_document(&path);
+        #[cfg(windows)]
+        crate::platform::windows::add_to_jump_list(&path);
+        if let Some(welcome_view) = self.welcome_view.as_welcome_mut() {
+            welcome_view.set_recent_documents(self.user_settings.recent_documents().to_vec());
+        }
+
+        let opening = tab_opening(self.opened_files(), &path, TabId(self.next_tab_id));
+        let tab_id = match opening {
+            TabOpening::SwitchTo(tab_id) => {
+                self.switch_to_tab(tab_id, window);
+                return tab_id;
+            }
+            TabOpening::New(tab_id) => tab_id,
+        };
         self.next_tab_id += 1;
 
         let mut tab = Tab::new(tab_id, path, self.event_loop_proxy.clone());
@@ -473,6 +1534,50 @@ impl App {
         tab_id
     }
 
+    /// The tabs with the path of the file they show, except for the untitled
+    /// documents, of which the path is their template.
+    fn opened_files(&self) -> impl Iterator<Item = (TabId, &std::path::Path)> {
+        self.tabs.iter()
+            .filter(|(_, tab)| tab.new_document_path.is_none())
+            .map(|(tab_id, tab)| (*tab_id, tab.path.as_path()))
+    }
+
+    /// Opens the files that were dropped onto the window, and focuses the
+    /// first of them.
+    fn open_dropped_files(&mut self, window: &mut winit::window::Window) {
+        let mut first_tab = None;
+        let mut skipped_files = Vec::new();
+
+        for path in std::mem::take(&mut self.pending_dropped_files) {
+            if !is_supported_document_path(&path) {
+                skipped_files.push(path);
+                continue;
+            }
+
+            let tab_id = self.add_tab(path, window);
+
+            if first_tab.is_none() {
+                first_tab = Some(tab_id);
+            }
+        }
+
+        if let Some(tab_id) = first_tab {
+            self.switch_to_tab(tab_id, window);
+        }
+
+        if !skipped_files.is_empty() {
+            let files = skipped_files.iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\r\n");
+
+            crate::platform::show_message_box_blocking(
+                "Unsupported Files",
+                &format!("The following files aren't supported and weren't opened:\r\n{}", files),
+            );
+        }
+    }
+
     /// Check the digit key (1 - 9).
     fn check_key_digit(&mut self, digit: u8, window: &mut winit::window::Window) {
         if !self.keyboard.is_control_key_down() {
@@ -501,12 +1606,70 @@ impl App {
     }
 
     fn switch_to_tab(&mut self, tab_id: TabId, window: &mut winit::window::Window) {
-        window.set_title(&format!("{} - {}", crate::gui::app::formatted_base_title(), self.tabs.get(&tab_id).unwrap().path.display()));
+        window.set_title(&format!("{} - {}", crate::gui::app::formatted_base_title(), self.tabs.get(&tab_id).unwrap().document_path().display()));
 
         self.current_visible_tab = Some(tab_id);
         window.request_redraw();
     }
 
+    /// Creates a new, empty document and opens it in a tab.
+    fn create_new_document(&mut self, window: &mut winit::window::Window) {
+        let path = find_unused_document_path(&new_documents_directory());
+        if let Err(e) = crate::wp::save::create_blank_package(&path) {
+            crate::platform::show_message_box_blocking(
+                "Cannot Create Document",
+                &format!("Failed to create \"{}\":\r\n{:?}", path.display(), e),
+            );
+            return;
+        }
+
+        self.add_tab(path, window);
+    }
+
+    /// Opens a new, untitled document in a tab, which starts with the content,
+    /// styles, numbering and theme of the template. The template itself isn't
+    /// changed, since saving asks the user where to save the document.
+    fn create_document_from_template(&mut self, template_path: PathBuf, window: &mut winit::window::Window) {
+        let mut new_document_path = find_unused_document_path(&new_documents_directory());
+        if template_path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("dotm")) {
+            new_document_path.set_extension("docm");
+        }
+
+        let tab_id = TabId(self.next_tab_id);
+        self.next_tab_id += 1;
+
+        let mut tab = Tab::new(tab_id, template_path, self.event_loop_proxy.clone());
+        tab.new_document_path = Some(new_document_path);
+        tab.settings_loaded(&self.user_settings);
+        self.tabs.insert(tab_id, tab);
+
+        self.switch_to_tab(tab_id, window);
+    }
+
+    fn handle_welcome_view_mouse_move(&mut self, window: &mut winit::window::Window) {
+        let previous_hovered_item = self.welcome_view.as_welcome_mut().and_then(|view| view.hovered_item());
+
+        let mut cursor = None;
+        self.welcome_view.handle_event(&mut crate::gui::view::Event::MouseMoved(self.mouse_position, &mut cursor));
+        window.set_cursor_icon(cursor.unwrap_or(CursorIcon::Default));
+
+        if self.welcome_view.as_welcome_mut().and_then(|view| view.hovered_item()) != previous_hovered_item {
+            window.request_redraw();
+        }
+    }
+
+    fn handle_welcome_view_click(&mut self, window: &mut winit::window::Window) {
+        self.welcome_view.handle_event(&mut crate::gui::view::Event::Click(self.mouse_position, false, &mut None));
+
+        match self.welcome_view.as_welcome_mut().and_then(|view| view.take_clicked_action()) {
+            Some(WelcomeAction::CreateNewDocument) => self.create_new_document(window),
+            Some(WelcomeAction::OpenDocument(path)) => {
+                self.add_tab(path, window);
+            }
+            None => (),
+        }
+    }
+
     fn handle_tab_mouse_move(&mut self, event: &mut MouseMoveEvent) {
         let Some(tab_id) = self.current_visible_tab else {
             return;
@@ -516,38 +1679,171 @@ impl App {
             return;
         };
 
-        tab.on_mouse_move(event);
-    }
+        tab.on_mouse_move(event);
+    }
+
+    fn handle_user_event(&mut self, window: &mut winit::window::Window, event: AppEvent) {
+        match event {
+            AppEvent::TabBecameReady(tab_id) => {
+                let Some(tab) = self.tabs.get_mut(&tab_id) else {
+                    println!("[App] Warning: TabBecameReady: Tab not found/closed.");
+                    return;
+                };
+
+                tab.on_became_ready();
+
+                if Some(tab_id) == self.current_visible_tab {
+                    window.request_redraw();
+                }
+            }
+
+            AppEvent::TabPainted { tab_id, total_content_height, page_count, current_page, page_tops } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.on_tab_painted(total_content_height, page_count, current_page, page_tops);
+                } else {
+                    println!("[App] Warning: TabPainted: Tab not found/closed.");
+                }
+            }
+
+            AppEvent::TabProgressed { tab_id, progress } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.on_tab_progressed(progress);
+                    window.request_redraw();
+                } else {
+                    println!("[App] Warning: TabProgressed: Tab not found/closed.");
+                }
+            }
+
+            AppEvent::TabPagesLaidOut { tab_id, page_count } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.on_pages_laid_out(page_count);
+
+                    if Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
+                }
+            }
+
+            AppEvent::TabSearched { tab_id, results } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    if tab.on_search_results(results) && Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
+                } else {
+                    println!("[App] Warning: TabSearched: Tab not found/closed.");
+                }
+            }
+
+            AppEvent::TabModified { tab_id, is_modified } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.is_modified = is_modified;
+                }
+            }
+
+            AppEvent::TabSaved { tab_id, path } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.on_saved(path);
+                    window.request_redraw();
+                }
+            }
+
+            AppEvent::TabSaveFailed { tab_id, path, error } => {
+                println!("[App] Error: tab {} failed to save \"{}\": {}", tab_id, path.display(), error);
+                crate::platform::show_message_box_blocking(
+                    "Cannot Save Document",
+                    &format!("{}\r\n\r\nThe document couldn't be saved, because {}.", path.display(), error),
+                );
+            }
+
+            AppEvent::TabStatistics { tab_id, statistics } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.statistics = Some(statistics);
+
+                    if Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
+                } else {
+                    println!("[App] Warning: TabStatistics: Tab not found/closed.");
+                }
+            }
+
+            AppEvent::TabComments { tab_id, comments } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.comments_panel.set_comments(comments);
+
+                    if Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
+                } else {
+                    println!("[App] Warning: TabComments: Tab not found/closed.");
+                }
+            }
+
+            AppEvent::TabDiagnostics { tab_id, diagnostics } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.on_diagnostics(diagnostics);
+
+                    if self.diagnostics_panel_visible && Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
+                } else {
+                    println!("[App] Warning: TabDiagnostics: Tab not found/closed.");
+                }
+            }
 
-    fn handle_user_event(&mut self, window: &mut winit::window::Window, event: AppEvent) {
-        match event {
-            AppEvent::TabBecameReady(tab_id) => {
-                let Some(tab) = self.tabs.get_mut(&tab_id) else {
-                    println!("[App] Warning: TabBecameReady: Tab not found/closed.");
-                    return;
-                };
+            AppEvent::TabSelectionChanged { tab_id, has_caret } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.has_caret = has_caret;
+                }
 
-                tab.on_became_ready();
+                if Some(tab_id) == self.current_visible_tab {
+                    window.request_redraw();
+                }
+            }
 
+            AppEvent::TabCaretBlinked { tab_id } => {
                 if Some(tab_id) == self.current_visible_tab {
                     window.request_redraw();
                 }
             }
 
-            AppEvent::TabPainted { tab_id, total_content_height, page_count } => {
+            AppEvent::TabNavigated { tab_id, rect } => {
                 if let Some(tab) = self.tabs.get_mut(&tab_id) {
-                    tab.on_tab_painted(total_content_height, page_count);
+                    tab.scroll_to_rect(rect);
+
+                    if Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
                 } else {
-                    println!("[App] Warning: TabPainted: Tab not found/closed.");
+                    println!("[App] Warning: TabNavigated: Tab not found/closed.");
                 }
             }
 
-            AppEvent::TabProgressed { tab_id, progress } => {
+            AppEvent::TabCursorChanged { tab_id, cursor } => {
+                if Some(tab_id) == self.current_visible_tab {
+                    window.set_cursor_icon(cursor.unwrap_or(CursorIcon::Default));
+                }
+            }
+
+            AppEvent::TabMouseRested { tab_id } => {
                 if let Some(tab) = self.tabs.get_mut(&tab_id) {
-                    tab.on_tab_progressed(progress);
-                    window.request_redraw();
-                } else {
-                    println!("[App] Warning: TabProgressed: Tab not found/closed.");
+                    tab.tooltip_state = match tab.hover_text {
+                        Some(..) => TooltipState::Visible,
+                        None => TooltipState::NotApplicable,
+                    };
+
+                    if tab.tooltip_state == TooltipState::Visible && Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
+                }
+            }
+
+            AppEvent::TabHoverTextChanged { tab_id, text } => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.hover_text = text;
+                    if Some(tab_id) == self.current_visible_tab {
+                        window.request_redraw();
+                    }
                 }
             }
 
@@ -556,7 +1852,15 @@ impl App {
                 if tab.is_none() {
                     return;
                 }
-                let tab = tab.unwrap();
+                let mut tab = tab.unwrap();
+
+                // The thread of the tab sends this event right before it
+                // returns, so wait for it to retrieve the reason.
+                if let Some(join_handle) = tab.join_handle.take() {
+                    if let Ok(Err(reason)) = join_handle.join() {
+                        tab.crash_reason = Some(reason);
+                    }
+                }
 
                 if let Some(current_tab) = self.current_visible_tab {
                     if current_tab == tab_id {
@@ -568,10 +1872,71 @@ impl App {
                     }
                 }
 
-                crate::platform::show_message_box_blocking(
-                    "Tab Crashed",
-                    &format!("ID: {}\r\nReason: {:?}", tab_id, tab.crash_reason),
-                );
+                match &tab.crash_reason {
+                    Some(reason) if reason.kind == TabCrashKind::PasswordProtected => {
+                        crate::platform::show_message_box_blocking(
+                            "Cannot Open Document",
+                            &format!("{}\r\n\r\n{}", tab.title(), reason.description),
+                        );
+                    }
+                    Some(TabCrashReason { description, kind: TabCrashKind::LoadFailed(error), .. }) => {
+                        crate::platform::show_message_box_blocking(
+                            "Cannot Open Document",
+                            &format!("{}\r\n\r\n{}\r\n\r\nWhat failed: {}", tab.title(), description, error),
+                        );
+                    }
+                    _ => {
+                        crate::platform::show_message_box_blocking(
+                            "Tab Crashed",
+                            &format!("ID: {}\r\nReason: {:?}", tab_id, tab.crash_reason),
+                        );
+                    }
+                }
+            }
+
+            AppEvent::TabFileChanged { tab_id } => {
+                let Some(tab) = self.tabs.get_mut(&tab_id) else {
+                    return;
+                };
+
+                // When the tab is still loading, it'll pick up the latest
+                // version of the file anyway.
+                if tab.check_state() != TabState::Ready {
+                    return;
+                }
+
+                // Saving the document changed the file, but the tab already
+                // shows what was saved, with its history.
+                if tab.is_file_as_saved() {
+                    return;
+                }
+
+                // Reloading would discard the edits that weren't saved yet.
+                if tab.is_modified {
+                    println!("[App] Not reloading \"{}\", because it has unsaved edits", tab.path.display());
+                    return;
+                }
+
+                tab.reload();
+                _ = self.event_loop_proxy.send_event(AppEvent::PainterRequest);
+
+                if Some(tab_id) == self.current_visible_tab {
+                    window.request_redraw();
+                }
+            }
+
+            AppEvent::OpenFiles(files) => {
+                let tab_ids: Vec<TabId> = files.into_iter()
+                    .map(|path| self.add_tab(path, window))
+                    .collect();
+
+                if let Some(tab_id) = tab_ids.first() {
+                    self.switch_to_tab(*tab_id, window);
+                }
+
+                // The user expects the documents to appear in front of them.
+                window.set_minimized(false);
+                window.focus_window();
             }
 
             AppEvent::PainterRequest => ()
@@ -588,6 +1953,112 @@ impl App {
                 }
             }
 
+            // Creates a new document from the template shown in the current
+            // tab, or otherwise an empty document.
+            VirtualKeyCode::N => {
+                if self.keyboard.is_control_key_down() {
+                    let template_path = self.current_visible_tab
+                        .and_then(|tab_id| self.tabs.get(&tab_id))
+                        .filter(|tab| tab.new_document_path.is_none() && crate::package::is_template_path(&tab.path))
+                        .map(|tab| tab.path.clone());
+
+                    match template_path {
+                        Some(template_path) => self.create_document_from_template(template_path, window),
+                        None => self.create_new_document(window),
+                    }
+                    window.request_redraw();
+                }
+            }
+
+            VirtualKeyCode::S => {
+                if self.keyboard.is_control_key_down() {
+                    if let Some(tab_id) = self.current_visible_tab {
+                        self.tabs.get_mut(&tab_id).unwrap().save();
+                    }
+                }
+            }
+
+            VirtualKeyCode::F => {
+                if self.keyboard.is_control_key_down() {
+                    if let Some(tab_id) = self.current_visible_tab {
+                        self.tabs.get_mut(&tab_id).unwrap().open_search();
+                        window.request_redraw();
+                    }
+                }
+            }
+
+            VirtualKeyCode::Escape => {
+                if let Some(tab_id) = self.current_visible_tab {
+                    let tab = self.tabs.get_mut(&tab_id).unwrap();
+                    if tab.search_widget.is_open() {
+                        tab.close_search();
+                        window.request_redraw();
+                    }
+                }
+            }
+
+            // Enter goes to the next match while searching, like F3 does.
+            VirtualKeyCode::Return | VirtualKeyCode::F3 => {
+                if let Some(tab_id) = self.current_visible_tab {
+                    let tab = self.tabs.get_mut(&tab_id).unwrap();
+                    if key == VirtualKeyCode::F3 || tab.search_widget.is_open() {
+                        let input = if self.keyboard.is_shift_key_down() { SearchInput::Previous } else { SearchInput::Next };
+                        tab.on_search_input(input);
+                    }
+                }
+            }
+
+            // Ctrl+E exports the document to Markdown, and Ctrl+Shift+E to
+            // plain text.
+            VirtualKeyCode::E => {
+                if self.keyboard.is_control_key_down() {
+                    if let Some(tab_id) = self.current_visible_tab {
+                        let format = if self.keyboard.is_shift_key_down() {
+                            crate::word_processing::text::TextFormat::PlainText
+                        } else {
+                            crate::word_processing::text::TextFormat::Markdown
+                        };
+                        self.tabs.get_mut(&tab_id).unwrap().export(format);
+                    }
+                }
+            }
+
+            VirtualKeyCode::P => {
+                if self.keyboard.is_control_key_down() {
+                    if let Some(tab_id) = self.current_visible_tab {
+                        self.tabs.get_mut(&tab_id).unwrap().print();
+                    }
+                }
+            }
+
+            // Switches between showing the tracked changes marked up, and the
+            // document after or before the changes. The document is laid out
+            // again, like F5 does.
+            VirtualKeyCode::R => {
+                if self.keyboard.is_control_key_down() {
+                    if let Some(tab_id) = self.current_visible_tab {
+                        let tab = self.tabs.get_mut(&tab_id).unwrap();
+                        tab.revision_view = tab.revision_view.next();
+                        tab.reload();
+                        _ = self.event_loop_proxy.send_event(AppEvent::PainterRequest);
+                    }
+                }
+            }
+
+            VirtualKeyCode::Y | VirtualKeyCode::Z => {
+                if self.keyboard.is_control_key_down() {
+                    if let Some(tab_id) = self.current_visible_tab {
+                        // Ctrl+Shift+Z redoes as well.
+                        let input = if key == VirtualKeyCode::Y || self.keyboard.is_shift_key_down() {
+                            EditInput::Redo
+                        } else {
+                            EditInput::Undo
+                        };
+                        self.tabs.get_mut(&tab_id).unwrap().on_edit_input(input);
+                    }
+                }
+            }
+
             VirtualKeyCode::Minus => {
                 if self.keyboard.is_control_key_down() {
                     if let Some(current_tab_id) = self.current_visible_tab {
@@ -618,6 +2089,27 @@ impl App {
             VirtualKeyCode::Key8 => self.check_key_digit(8, window),
             VirtualKeyCode::Key9 => self.check_key_digit(9, window),
 
+            VirtualKeyCode::Left | VirtualKeyCode::Right => {
+                if let Some(tab_id) = self.current_visible_tab {
+                    self.tabs.get_mut(&tab_id).unwrap().on_edit_input(EditInput::Key(key));
+                }
+            }
+
+            VirtualKeyCode::Space => {
+                if let Some(tab_id) = self.current_visible_tab {
+                    // The space is typed at the caret or in the search box
+                    // instead.
+                    if self.tabs[&tab_id].has_caret || self.tabs[&tab_id].search_widget.is_open() {
+                        return;
+                    }
+
+                    let direction = if self.keyboard.is_shift_key_down() { -1.0 } else { 1.0 };
+                    if self.tabs.get_mut(&tab_id).unwrap().scroll_viewport(direction) {
+                        window.request_redraw();
+                    }
+                }
+            }
+
             VirtualKeyCode::F5 => {
                 if let Some(tab_id) = self.current_visible_tab {
                     let tab = self.tabs.get_mut(&tab_id).unwrap();
@@ -636,6 +2128,11 @@ impl App {
                 }
             }
 
+            VirtualKeyCode::F12 => {
+                self.diagnostics_panel_visible = !self.diagnostics_panel_visible;
+                window.request_redraw();
+            }
+
             #[cfg(debug_assertions)]
             VirtualKeyCode::Pause => {
                 loop {
@@ -651,6 +2148,7 @@ impl App {
         let notification = SettingChangeNotification {
             origin, setting_name, settings: &self.user_settings
         };
+        self.tab_widget.setting_changed(&notification);
         for tab in self.tabs.values_mut() {
             tab.setting_changed(&notification);
         }
@@ -660,7 +2158,12 @@ impl App {
     /// system is rebooted automatically.
     fn save_restore_point(&mut self) {
         crate::platform::save_restore_arguments(crate::CommandLineArguments{
-            files: self.tabs.values().map(|tab| tab.path.to_str().unwrap().to_owned()).collect(),
+            // Untitled documents can't be restored, since they were never
+            // saved.
+            files: self.tabs.values()
+                .filter(|tab| tab.new_document_path.is_none())
+                .map(|tab| tab.path.to_str().unwrap().to_owned())
+                .collect(),
 
             ..Default::default()
         })
@@ -690,13 +2193,115 @@ impl App {
         painter.paint_rect(Brush::SolidColor(Color::from_rgb(0x22, 0x22, 0x22)),
                 Rect::from_position_and_size(position, size));
 
-        let text = format!("1238 words,  {} pages,   {}% zoom", tab.page_count, tab.zoomer.zoom_factor_unanimated() * 100.0);
+        let mut text = String::new();
+        if let Some(statistics) = &tab.statistics {
+            text += &format!("{},  {},  {},   ", describe_count(statistics.words, "word"),
+                describe_count(statistics.characters, "character"), describe_count(statistics.paragraphs, "paragraph"));
+        }
+        text += &format!("Page {} of {},   {}% zoom", tab.current_page + 1, tab.page_count, tab.zoomer.zoom_factor_unanimated() * 100.0);
+        if tab.revision_view != RevisionView::Markup {
+            text += &format!(",   {}", tab.revision_view.description());
+        }
+        if let Some(hover_text) = &tab.hover_text {
+            text += &format!(",   {}", hover_text.replace('\n', ",   "));
+        }
+        if tab.state == TabState::Loading {
+            text += &format!(",   Loading... {:.1}%", tab.loading_progress * 100.0);
+        }
 
         painter.select_font(FontSpecification::new("Segoe UI", 8.0, FontWeight::Regular)).unwrap();
         painter.paint_text(Brush::SolidColor(Color::from_rgb(0xCC, 0xCC, 0xCC)), Position::new(padding, position.y()), &text, None);
 
         drop(painter);
     }
+
+    /// Paints the hover text of the current tab next to the mouse, when it
+    /// rested long enough.
+    fn paint_tooltip(&self, painter: &mut dyn Painter, window_size: Size<f32>) {
+        let Some(tab) = self.current_visible_tab.and_then(|tab_id| self.tabs.get(&tab_id)) else {
+            return;
+        };
+
+        let Some(hover_text) = &tab.hover_text else {
+            return;
+        };
+
+        if tab.tooltip_state != TooltipState::Visible {
+            return;
+        }
+
+        let padding = 4.0;
+        painter.select_font(FontSpecification::new("Segoe UI", 9.0, FontWeight::Regular)).unwrap();
+
+        let line_sizes: Vec<Size<f32>> = hover_text.lines()
+            .map(|line| painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), Position::new(0.0, 0.0), line, None))
+            .collect();
+        let text_size = Size::new(
+            line_sizes.iter().map(|size| size.width()).fold(0.0, f32::max),
+            line_sizes.iter().map(|size| size.height()).sum(),
+        );
+        let size = Size::new(text_size.width() + padding * 2.0, text_size.height() + padding * 2.0);
+
+        // Keep the tooltip inside the window, above the cursor when there is
+        // no room below it.
+        let mut x = self.mouse_position.x();
+        let mut y = self.mouse_position.y() + TOOLTIP_CURSOR_OFFSET;
+        if x + size.width() > window_size.width() {
+            x = (window_size.width() - size.width()).max(0.0);
+        }
+        if y + size.height() > window_size.height() {
+            y = (self.mouse_position.y() - size.height()).max(0.0);
+        }
+
+        let rect = Rect::from_position_and_size(Position::new(x, y), size);
+        painter.paint_rect(Brush::SolidColor(TOOLTIP_BORDER_COLOR), rect);
+        painter.paint_rect(Brush::SolidColor(TOOLTIP_BACKGROUND_COLOR),
+                Rect::from_position_and_size(Position::new(x + 1.0, y + 1.0), Size::new(size.width() - 2.0, size.height() - 2.0)));
+
+        let mut line_y = y + padding;
+        for (line, line_size) in hover_text.lines().zip(&line_sizes) {
+            painter.paint_text(Brush::SolidColor(Color::BLACK), Position::new(x + padding, line_y), line, None);
+            line_y += line_size.height();
+        }
+    }
+
+    /// Paints the problems encountered while loading the document of the
+    /// current tab, on the right side of the content.
+    fn paint_diagnostics_panel(&self, painter: &mut dyn Painter, content_rect: Rect<f32>) {
+        let Some(tab) = self.current_visible_tab.and_then(|tab_id| self.tabs.get(&tab_id)) else {
+            return;
+        };
+
+        let padding = 6.0;
+        let line_height = 14.0;
+
+        let position = Position::new(content_rect.right - DIAGNOSTICS_PANEL_WIDTH, content_rect.top);
+        let size = Size::new(DIAGNOSTICS_PANEL_WIDTH, content_rect.height());
+        painter.paint_rect(Brush::SolidColor(Color::from_rgb(0x22, 0x22, 0x22)),
+                Rect::from_position_and_size(position, size));
+
+        painter.select_font(FontSpecification::new("Segoe UI", 10.0, FontWeight::SemiBold)).unwrap();
+        painter.paint_text(Brush::SolidColor(Color::from_rgb(0xCC, 0xCC, 0xCC)),
+                Position::new(position.x() + padding, position.y() + padding),
+                &format!("Diagnostics ({})", tab.diagnostics.len()), None);
+
+        painter.select_font(FontSpecification::new("Segoe UI", 8.0, FontWeight::Regular)).unwrap();
+        let mut y = position.y() + padding + line_height * 2.0;
+        for diagnostic in &tab.diagnostics {
+            if y + line_height > content_rect.bottom {
+                break;
+            }
+
+            let color = match diagnostic.severity {
+                Severity::Info => Color::from_rgb(0xCC, 0xCC, 0xCC),
+                Severity::Warning => Color::from_rgb(0xE5, 0xC0, 0x7B),
+                Severity::Error => Color::from_rgb(0xE0, 0x6C, 0x75),
+            };
+
+            painter.paint_text(Brush::SolidColor(color), Position::new(position.x() + padding, y), &diagnostic.to_string(), None);
+            y += line_height;
+        }
+    }
 }
 
 impl crate::gui::app::GuiApp for App {
@@ -717,13 +2322,31 @@ impl crate::gui::app::GuiApp for App {
                 }
 
                 if let Some(current_tab_id) = self.current_visible_tab {
-                    let should_scroll = self.tabs.get_mut(&current_tab_id).unwrap().on_scroll(delta, &self.keyboard);
+                    let should_scroll = self.tabs.get_mut(&current_tab_id).unwrap().on_scroll(delta, &self.keyboard, self.mouse_position);
                     if should_scroll {
                         window.request_redraw();
                     }
                 }
             }
 
+            // Touchpads report precise (pixel) scrolling, which is used for
+            // smooth zooming, while mouse wheels keep zooming stepwise through
+            // the DeviceEvent above.
+            Event::WindowEvent {
+                event: WindowEvent::MouseWheel { delta: MouseScrollDelta::PixelDelta(delta), phase, .. }, ..
+            } => {
+                if !self.keyboard.is_control_key_down() {
+                    return;
+                }
+
+                if let Some(current_tab_id) = self.current_visible_tab {
+                    let pixels = delta.to_logical::<f32>(window.scale_factor()).y;
+                    if self.tabs.get_mut(&current_tab_id).unwrap().on_zoom_gesture(pixels, phase, self.mouse_position) {
+                        window.request_redraw();
+                    }
+                }
+            }
+
             Event::WindowEvent { event: WindowEvent::CursorLeft { .. }, .. } => {
                 self.mouse_inside_window = false;
             }
@@ -732,10 +2355,31 @@ impl crate::gui::app::GuiApp for App {
                 self.mouse_inside_window = false;
             }
 
+            Event::WindowEvent { event: WindowEvent::ReceivedCharacter(character), .. } => {
+                // Characters typed with control are shortcuts.
+                if self.keyboard.is_control_key_down() {
+                    return;
+                }
+
+                if let Some(tab_id) = self.current_visible_tab {
+                    let tab = self.tabs.get_mut(&tab_id).unwrap();
+                    if tab.search_widget.is_open() {
+                        tab.on_search_character(character);
+                        window.request_redraw();
+                    } else {
+                        tab.on_edit_input(EditInput::Character(character));
+                    }
+                }
+            }
+
             Event::WindowEvent { event: WindowEvent::DroppedFile(path), .. } => {
-                let new_tab = self.add_tab(path, window);
-                self.current_visible_tab = Some(new_tab);
-                window.request_redraw();
+                self.pending_dropped_files.push(path);
+            }
+
+            Event::MainEventsCleared => {
+                if !self.pending_dropped_files.is_empty() {
+                    self.open_dropped_files(window);
+                }
             }
 
             Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
@@ -783,6 +2427,10 @@ impl crate::gui::app::GuiApp for App {
                     self.tab_widget.on_mouse_move(&mut event);
                 }
 
+                if self.current_visible_tab.is_none() {
+                    self.handle_welcome_view_mouse_move(window);
+                }
+
                 self.handle_tab_mouse_move(&mut event);
 
                 if event.reaction == EventVisualReaction::ContentUpdated {
@@ -798,7 +2446,11 @@ impl crate::gui::app::GuiApp for App {
 
                 if let Some(tab_id) = self.current_visible_tab {
                     let tab = self.tabs.get_mut(&tab_id).unwrap();
-                    tab.on_mouse_input(self.mouse_position, button, state);
+                    if tab.on_mouse_input(self.mouse_position, button, state, &self.keyboard) {
+                        window.request_redraw();
+                    }
+                } else if button == MouseButton::Left && state == ElementState::Released {
+                    self.handle_welcome_view_click(window);
                 }
             }
 
@@ -840,10 +2492,12 @@ impl crate::gui::app::GuiApp for App {
             };
             event.painter.as_ref().borrow_mut().switch_cache(PainterCache::Document(current_tab_id.0), quality);
 
+            // The comments panel is between the document and the scroll bar.
+            let comments_panel_width = current_tab.comments_panel.width();
             let content_rect = Rect::from_position_and_size(
                 Position::new(0.0, self.tab_widget.rect().bottom),
                 Size::new(
-                    window_size.width() - current_tab.scroller.bar_rect.width(),
+                    window_size.width() - current_tab.scroller.bar_rect.width() - comments_panel_width,
                     window_size.height() - self.tab_widget.rect().height() - 15.0
                 )
             );
@@ -852,7 +2506,17 @@ impl crate::gui::app::GuiApp for App {
             let mut painter = event.painter.as_ref().borrow_mut();
             painter.switch_cache(PainterCache::UI, PaintQuality::Full);
 
-            current_tab.scroller.paint(&mut *painter, content_rect);
+            if current_tab.comments_panel.is_visible() {
+                current_tab.comments_panel.paint(&mut *painter, Rect::from_positions(content_rect.right,
+                    content_rect.right + comments_panel_width, content_rect.top, content_rect.bottom));
+            }
+
+            current_tab.scroller.paint(&mut *painter, Rect::from_positions(content_rect.left,
+                content_rect.right + comments_panel_width, content_rect.top, content_rect.bottom));
+
+            if current_tab.search_widget.is_open() {
+                current_tab.search_widget.paint(&mut *painter, content_rect);
+            }
 
             if has_animations_at_beginning_of_paint || current_tab.has_running_animations() {
                 event.should_redraw_again = true;
@@ -861,22 +2525,154 @@ impl crate::gui::app::GuiApp for App {
                 self.previous_frame_had_running_animations = false;
                 event.should_redraw_again = true;
             }
+
+            if self.diagnostics_panel_visible {
+                self.paint_diagnostics_panel(&mut *painter, content_rect);
+            }
+        } else {
+            let content_rect = Rect::from_position_and_size(
+                Position::new(0.0, self.tab_widget.rect().bottom),
+                Size::new(window_size.width(), window_size.height() - self.tab_widget.rect().height())
+            );
+
+            let mut painter = event.painter.as_ref().borrow_mut();
+            painter.switch_cache(PainterCache::UI, PaintQuality::Full);
+            self.welcome_view.handle_event(&mut crate::gui::view::Event::Paint(crate::gui::view::PaintEvent {
+                content_rect,
+                opaqueness: 1.0,
+                painter: &mut *painter,
+                start_y: 0.0,
+                zoom: 1.0,
+                horizontal_offset: 0.0,
+            }));
         }
 
         let mut painter = event.painter.borrow_mut();
         self.tab_widget.paint(&mut *painter, self.tabs.values(), self.selected_tab_to_index());
         self.paint_status_bar(painter, window_size);
+        self.paint_tooltip(&mut *event.painter.borrow_mut(), window_size);
     }
 
     /// This function is called in response to a `AppEvent::PainterRequest`.
     fn receive_painter(&mut self, painter: Arc<RefCell<dyn Painter>>) {
         for tab in self.tabs.values_mut() {
-            if tab.check_state() == TabState::Loading {
+            // A tab that is already laying out its document would only answer
+            // once it's done.
+            if tab.check_state() == TabState::Loading && !tab.layout_requested {
+                tab.layout_requested = true;
                 assert!(tab.finished_paint_receiver.try_recv().is_err());
-                tab.tab_event_sender.send(TabEvent::Layout { painter: painter.clone() }).unwrap();
+                tab.tab_event_sender.send(TabEvent::Layout {
+                    painter: painter.clone(),
+                    fallback_font: tab.fallback_font.clone(),
+                    revision_view: tab.revision_view,
+                }).unwrap();
                 tab.finished_paint_receiver.recv().unwrap();
             }
         }
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_tab_id_by_path_matches_canonical_path() {
+        let directory = std::env::temp_dir().join("uffice-find-tab-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("document.docx");
+        std::fs::write(&file, b"").unwrap();
+
+        let canonical = file.canonicalize().unwrap();
+        let tabs = [(TabId(1000), canonical.as_path())];
+
+        // Opening the same path twice should resolve to the already open tab.
+        assert_eq!(find_tab_id_by_path(tabs.into_iter(), &file), Some(TabId(1000)));
+        assert_eq!(find_tab_id_by_path(tabs.into_iter(), &directory.join("..").join("uffice-find-tab-test").join("document.docx")), Some(TabId(1000)));
+        assert_eq!(find_tab_id_by_path(tabs.into_iter(), &directory.join("other.docx")), None);
+    }
+
+    #[test]
+    fn adding_a_tab_twice_switches_to_the_open_tab() {
+        let directory = std::env::temp_dir().join("uffice-add-tab-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("document.docx");
+        std::fs::write(&file, b"").unwrap();
+
+        // Like App::add_tab, which adds a tab for every new opening.
+        let mut tabs: Vec<(TabId, PathBuf)> = Vec::new();
+        let mut openings = Vec::new();
+        for path in [file.clone(), directory.join("..").join("uffice-add-tab-test").join("document.docx")] {
+            let path = path.canonicalize().unwrap();
+            let opening = tab_opening(tabs.iter().map(|(tab_id, path)| (*tab_id, path.as_path())), &path, TabId(1000 + tabs.len()));
+            if let TabOpening::New(tab_id) = opening {
+                tabs.push((tab_id, path));
+            }
+            openings.push(opening);
+        }
+
+        assert_eq!(tabs.len(), 1);
+        assert_eq!(openings, vec![TabOpening::New(TabId(1000)), TabOpening::SwitchTo(TabId(1000))]);
+    }
+
+    #[test]
+    fn unused_document_path_skips_existing_documents() {
+        let directory = std::env::temp_dir().join("uffice-new-document-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("Document1.docx"), b"").unwrap();
+        _ = std::fs::remove_file(directory.join("Document2.docx"));
+
+        assert_eq!(find_unused_document_path(&directory), directory.join("Document2.docx"));
+    }
+
+    #[test]
+    fn supported_document_paths() {
+        assert!(is_supported_document_path(std::path::Path::new("test.docx")));
+        assert!(is_supported_document_path(std::path::Path::new("TEST.DOCX")));
+        assert!(is_supported_document_path(std::path::Path::new("template.dotx")));
+        assert!(is_supported_document_path(std::path::Path::new("macros.docm")));
+        assert!(is_supported_document_path(std::path::Path::new("legacy.doc")));
+        assert!(is_supported_document_path(std::path::Path::new("report.odt")));
+        assert!(is_supported_document_path(std::path::Path::new("notes.txt")));
+        assert!(is_supported_document_path(std::path::Path::new("README.md")));
+        assert!(!is_supported_document_path(std::path::Path::new("image.png")));
+        assert!(!is_supported_document_path(std::path::Path::new("docx")));
+    }
+
+    #[test]
+    fn counts_are_described_with_the_noun() {
+        assert_eq!(describe_count(0, "word"), "0 words");
+        assert_eq!(describe_count(1, "word"), "1 word");
+        assert_eq!(describe_count(1238, "paragraph"), "1238 paragraphs");
+    }
+
+    #[test]
+    fn compound_file_binary_detection() {
+        let directory = std::env::temp_dir().join("uffice-cfb-test");
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let encrypted = directory.join("encrypted.docx");
+        let mut contents = COMPOUND_FILE_BINARY_SIGNATURE.to_vec();
+        contents.extend_from_slice(&[0; 504]);
+        std::fs::write(&encrypted, contents).unwrap();
+        assert!(is_compound_file_binary(&encrypted));
+
+        // ZIP archives start with "PK".
+        let archive = directory.join("archive.docx");
+        std::fs::write(&archive, b"PK\x03\x04").unwrap();
+        assert!(!is_compound_file_binary(&archive));
+
+        assert!(!is_compound_file_binary(&directory.join("missing.docx")));
+    }
+
+    #[test]
+    fn cancelling_a_clone_cancels_the_token() {
+        let token = CancellationToken::default();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+}
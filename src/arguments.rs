@@ -0,0 +1,30 @@
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use clap::Parser;
+
+#[derive(Parser, Debug, Default, Clone)]
+pub struct CommandLineArguments {
+    /// The files to open.
+    pub files: Vec<String>,
+
+    /// Export the file to a PDF file at the given path, without opening a
+    /// window.
+    #[arg(long, value_name = "OUTPUT")]
+    pub export_pdf: Option<std::path::PathBuf>,
+
+    /// Export the text of the file to a plain text file at the given path,
+    /// without opening a window.
+    #[arg(long, value_name = "OUTPUT")]
+    pub export_text: Option<std::path::PathBuf>,
+
+    /// Export the file to a Markdown file at the given path, without opening
+    /// a window.
+    #[arg(long, value_name = "OUTPUT")]
+    pub export_markdown: Option<std::path::PathBuf>,
+
+    /// Register uffice as a handler of the document file types for the
+    /// current user (Windows only), without opening a window.
+    #[arg(long)]
+    pub register_file_types: bool,
+}
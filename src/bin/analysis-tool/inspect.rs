@@ -0,0 +1,221 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{fs::File, path::Path, rc::Rc};
+
+use roxmltree as xml;
+use zip::ZipArchive;
+
+use uffice_lib::{
+    drawing_ml::style::StyleSettings,
+    error::Error,
+    gui::painter::null::NullTextCalculator,
+    package::{self, load_archive_file_to_string},
+    relationships::{RelationshipType, Relationships},
+    style::StyleManager,
+    user_settings::UserSettings,
+    word_processing::{load, validate},
+    wp::{document_properties::DocumentProperties, numbering::NumberingManager, revisions::RevisionView, Node, NodeData},
+};
+
+/// The archive of a document, with the relationships of its main document
+/// part.
+struct Package {
+    archive: ZipArchive<File>,
+    main_document_part: String,
+    relationships: Relationships,
+}
+
+impl Package {
+    fn open(path: &Path) -> Result<Self, Error> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let main_document_part = package::find_main_document_part(&mut archive);
+        let relationships = load_relationships(&mut archive, &main_document_part)?;
+        Ok(Self { archive, main_document_part, relationships })
+    }
+
+    /// Loads the text of the part the main document part has a relationship
+    /// of the given type with.
+    fn load_related_part(&mut self, relation_type: RelationshipType) -> Result<Option<(String, Rc<String>)>, Error> {
+        let Some(relationship) = self.relationships.find_by_type(relation_type) else {
+            return Ok(None);
+        };
+        let part_name = package::resolve_relationship_target(&self.main_document_part, &relationship.borrow().target);
+        let text = load_archive_file_to_string(&mut self.archive, &part_name)?;
+        Ok(text.map(|text| (part_name, text)))
+    }
+}
+
+/// Loads the relationships of the part, or those of the package when the
+/// `source_part_name` is empty.
+fn load_relationships(archive: &mut ZipArchive<File>, source_part_name: &str) -> Result<Relationships, Error> {
+    let part_name = package::relationships_part_name(source_part_name);
+    let text = load_archive_file_to_string(archive, &part_name)?
+        .ok_or_else(|| Error::MissingPart(part_name.clone()))?;
+    let document = xml::Document::parse(&text).map_err(|e| Error::from(e).in_part(&part_name))?;
+    Relationships::load_xml(&document, archive, source_part_name).map_err(|e| e.in_part(&part_name))
+}
+
+/// Lays out the document like the application does, and prints the nodes
+/// with their pages, positions and sizes.
+pub fn print_tree(path: &Path) -> Result<(), Error> {
+    let fallback_font = UserSettings::load().setting_fallback_font().to_owned();
+
+    let mut text_calculator = NullTextCalculator::new();
+    let result = load::load_document(&path.to_string_lossy(), &mut text_calculator, &fallback_font, None, RevisionView::default(), &mut |_: f32| ())?;
+
+    print_node(&result.root_node, 0);
+    Ok(())
+}
+
+fn print_node(node: &Node, depth: usize) {
+    println!("{:indent$}{}  page {}-{}  at ({:.1}, {:.1})  size {:.1}x{:.1}", "", describe_node_data(&node.data),
+        node.page_first + 1, node.page_last + 1, node.position.x(), node.position.y(),
+        node.size.width(), node.size.height(), indent = depth * 2);
+
+    for child in &node.children {
+        print_node(child, depth + 1);
+    }
+}
+
+fn describe_node_data(data: &NodeData) -> String {
+    match data {
+        NodeData::BookmarkStart(bookmark) => format!("BookmarkStart \"{}\"", bookmark.name),
+        NodeData::BookmarkEnd(id) => format!("BookmarkEnd {}", id),
+        NodeData::Break(break_type) => format!("Break {:?}", break_type),
+        NodeData::CommentRangeStart(id) => format!("CommentRangeStart {}", id),
+        NodeData::CommentRangeEnd(id) => format!("CommentRangeEnd {}", id),
+        NodeData::Document => String::from("Document"),
+        NodeData::Drawing(..) => String::from("Drawing"),
+        NodeData::Hyperlink(hyperlink) => match (&hyperlink.relationship, &hyperlink.anchor) {
+            (Some(relationship), _) => format!("Hyperlink {}", relationship.borrow().target),
+            (None, Some(anchor)) => format!("Hyperlink #{}", anchor),
+            (None, None) => String::from("Hyperlink"),
+        },
+        NodeData::NumberingParent => String::from("NumberingParent"),
+        NodeData::Paragraph(..) => String::from("Paragraph"),
+        NodeData::Revision(revision) => format!("Revision {:?} by {}", revision.kind, revision.author),
+        NodeData::StructuredDocumentTag(..) => String::from("StructuredDocumentTag"),
+        NodeData::Table { .. } => String::from("Table"),
+        NodeData::TableRow => String::from("TableRow"),
+        NodeData::TableCell(..) => String::from("TableCell"),
+        NodeData::Text => String::from("Text"),
+        NodeData::TextPart(part) => format!("TextPart {:?}", part.text),
+        NodeData::TextRun(..) => String::from("TextRun"),
+    }
+}
+
+/// Prints the styles of the document by their ID, with their type and the
+/// style they are based on.
+pub fn print_styles(path: &Path) -> Result<(), Error> {
+    let mut package = Package::open(path)?;
+
+    let numbering_manager = match package.load_related_part(RelationshipType::Numbering)? {
+        Some((part_name, text)) => xml::Document::parse(&text).map_err(Error::from)
+            .and_then(|document| NumberingManager::from_xml(&document))
+            .map_err(|e| e.in_part(&part_name))?,
+        None => NumberingManager::new(),
+    };
+
+    let theme_settings = package.load_related_part(RelationshipType::Theme)?
+        .and_then(|(_, text)| {
+            let document = xml::Document::parse(&text).ok()?;
+            StyleSettings::from_xml(&document.root_element(), &mut Vec::new()).ok()
+        })
+        .unwrap_or_default();
+
+    let Some((part_name, text)) = package.load_related_part(RelationshipType::Styles)? else {
+        println!("The document has no style definitions, so it uses the default styles");
+        return Ok(());
+    };
+    let style_manager = xml::Document::parse(&text).map_err(Error::from)
+        .and_then(|document| StyleManager::from_document(&document, &numbering_manager, &theme_settings))
+        .map_err(|e| e.in_part(&part_name))?;
+
+    let mut style_ids: Vec<&str> = style_manager.style_ids().collect();
+    style_ids.sort_unstable();
+    for style_id in style_ids {
+        let style_type = style_manager.style_type(style_id).unwrap_or_default();
+        match style_manager.based_on(style_id) {
+            Some(based_on) => println!("{} ({}), based on {}", style_id, style_type.as_str(), based_on),
+            None => println!("{} ({})", style_id, style_type.as_str()),
+        }
+    }
+
+    for diagnostic in style_manager.diagnostics() {
+        println!("{}", diagnostic);
+    }
+    Ok(())
+}
+
+/// Prints the relationships of the package and those of the main document
+/// part, by their ID.
+pub fn print_relationships(path: &Path) -> Result<(), Error> {
+    let mut package = Package::open(path)?;
+    let package_relationships = load_relationships(&mut package.archive, "")?;
+
+    for (part_name, relationships) in [("Package", &package_relationships), (package.main_document_part.as_str(), &package.relationships)] {
+        println!("{}", part_name);
+
+        let mut relationships: Vec<_> = relationships.iter().map(|relationship| relationship.borrow()).collect();
+        relationships.sort_by(|a, b| a.id.cmp(&b.id));
+        for relationship in relationships {
+            println!("    {}  {:?}  {}", relationship.id, relationship.relation_type, relationship.target);
+        }
+    }
+    Ok(())
+}
+
+/// Copies the parts in the `media` directories of the package to the output
+/// directory.
+pub fn extract_media(path: &Path, output: &Path) -> Result<(), Error> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    let part_names: Vec<String> = archive.file_names()
+        .filter(|name| name.starts_with("media/") || name.contains("/media/"))
+        .map(String::from)
+        .collect();
+
+    std::fs::create_dir_all(output)?;
+    for part_name in part_names {
+        let file_name = part_name.rsplit('/').next().unwrap_or(&part_name);
+        let output_path = output.join(file_name);
+
+        let mut part = archive.by_name(&part_name)?;
+        std::io::copy(&mut part, &mut File::create(&output_path)?)?;
+        println!("{} => {}", part_name, output_path.display());
+    }
+    Ok(())
+}
+
+/// Prints the core properties (`docProps/core.xml`) of the document.
+pub fn print_properties(path: &Path) -> Result<(), Error> {
+    let mut package = Package::open(path)?;
+    println!("Main document part: {}", package.main_document_part);
+
+    let mut properties = DocumentProperties::new();
+    if let Some(text) = load_archive_file_to_string(&mut package.archive, "docProps/core.xml")? {
+        let document = xml::Document::parse(&text).map_err(|e| Error::from(e).in_part("docProps/core.xml"))?;
+        properties.import_core_file_properties_part(&document);
+    }
+
+    for name in ["Title", "Subject", "Author", "Keywords", "Comments", "Category", "LastSavedBy"] {
+        println!("{}: {}", name, properties.get_by_name(name).unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Prints the validation report of every document, and returns whether all
+/// of them can be loaded.
+pub fn validate_files(paths: &[std::path::PathBuf]) -> bool {
+    let mut loadable = true;
+    for path in paths {
+        let report = validate::validate_file(path);
+        println!("{}", path.display());
+        print!("{}", report);
+        println!();
+
+        loadable &= report.is_loadable();
+    }
+    loadable
+}
@@ -1,33 +1,78 @@
-// Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
+//
+// Inspects the structure of documents, using the same library as the
+// application does to load them.
 
-use notify::Watcher;
+use std::path::PathBuf;
 
-fn main() {
-    let mut watcher = notify::recommended_watcher(move |res| {
-        match res {
-            Ok(event) => {
-                println!("[Watcher] Event: {:?}", event);
-            }
-            Err(e) => println!("[Watcher] Failed to watch: {:?}", e),
-        }
-    }).expect("Failed to instantiate file watcher");
+use clap::{Parser, Subcommand};
+
+mod inspect;
 
-    let path = std::path::Path::new("C:\\");
+#[derive(Parser, Debug)]
+#[command(name = "uffice-analysis-tool", about = "Inspects the structure of documents")]
+struct Arguments {
+    #[command(subcommand)]
+    command: Command,
+}
 
-    println!("{:?} => {:?}", path, path.canonicalize());
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Lays out the document and prints the tree of nodes.
+    Tree {
+        file: PathBuf,
+    },
 
-    if let Ok(md) = path.metadata() {
-        println!("Meta: {:?}", md);
-    }
+    /// Lists the style definitions of the document.
+    Styles {
+        file: PathBuf,
+    },
 
-    match watcher.watch(path, notify::RecursiveMode::Recursive) {
-        Err(err) => {
-            println!("{:?}", err);
-        }
-        _ => {
-            #[allow(clippy::empty_loop)]
-            loop {}
+    /// Lists the relationships of the package and of the main document part.
+    Relationships {
+        file: PathBuf,
+    },
+
+    /// Extracts the media of the document, such as the pictures, to the
+    /// output directory.
+    Media {
+        file: PathBuf,
+        output: PathBuf,
+    },
+
+    /// Prints the core properties of the document, such as the title.
+    Properties {
+        file: PathBuf,
+    },
+
+    /// Checks the integrity of the packages, and exits with a nonzero code if
+    /// one of them can't be loaded.
+    Validate {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+fn main() {
+    let arguments = Arguments::parse();
+
+    let result = match arguments.command {
+        Command::Tree { file } => inspect::print_tree(&file),
+        Command::Styles { file } => inspect::print_styles(&file),
+        Command::Relationships { file } => inspect::print_relationships(&file),
+        Command::Media { file, output } => inspect::extract_media(&file, &output),
+        Command::Properties { file } => inspect::print_properties(&file),
+        Command::Validate { files } => {
+            if !inspect::validate_files(&files) {
+                std::process::exit(1);
+            }
+            Ok(())
         }
+    };
+
+    if let Err(error) = result {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
     }
 }
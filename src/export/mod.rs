@@ -0,0 +1,116 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Exporting documents without opening a window, by painting the document
+// view with the NullPainter and converting the recorded commands, or by
+// writing the text of the tree.
+
+pub mod pdf;
+pub mod text;
+
+use std::path::Path;
+
+use crate::{
+    gui::{
+        painter::null::{NullPainter, NullTextCalculator, PaintCommand},
+        view::{document_view::DocumentView, Event, PaintEvent, ViewImpl},
+        Brush,
+        Rect,
+    },
+    print::{self, PrintPage},
+    user_settings::UserSettings,
+    word_processing::text::TextFormat,
+    wp::revisions::RevisionView,
+};
+
+#[derive(Debug)]
+pub enum ExportError {
+    /// The document doesn't contain any pages.
+    NoPages,
+
+    /// The document couldn't be loaded, so there is no text to export.
+    NoDocument,
+
+    /// The document is too damaged to be loaded.
+    Load(crate::error::Error),
+
+    Io(std::io::Error),
+}
+
+impl From<crate::error::Error> for ExportError {
+    fn from(error: crate::error::Error) -> Self {
+        Self::Load(error)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Lays out the document and writes it to `output_path` as a PDF file.
+pub fn export_pdf(document_path: &str, output_path: &Path) -> Result<(), ExportError> {
+    let fallback_font = UserSettings::load().setting_fallback_font().to_owned();
+
+    let mut text_calculator = NullTextCalculator::new();
+    let mut view = DocumentView::new(document_path, &mut text_calculator, fallback_font, None, RevisionView::default(), &mut |_: f32| ())?;
+
+    // Paint every page at 100% zoom, so the coordinates of the commands are
+    // in points.
+    let mut painter = NullPainter::new();
+    view.handle_event(&mut Event::Paint(PaintEvent {
+        content_rect: Rect::from_positions(0.0, 0.0, 0.0, f32::MAX),
+        opaqueness: 1.0,
+        start_y: 0.0,
+        painter: &mut painter,
+        zoom: 1.0,
+        horizontal_offset: 0.0,
+    }));
+
+    let pages: Vec<_> = print::paginate(view.page_rects(), painter.commands()).iter()
+        .map(convert_to_pdf_page)
+        .collect();
+    if pages.is_empty() {
+        return Err(ExportError::NoPages);
+    }
+
+    let mut file = std::fs::File::create(output_path)?;
+    pdf::write_pdf(&pages, &mut file)?;
+    Ok(())
+}
+
+/// Loads the document and writes its text to `output_path` as a plain text or
+/// Markdown file.
+pub fn export_text(document_path: &str, output_path: &Path, format: TextFormat) -> Result<(), ExportError> {
+    let fallback_font = UserSettings::load().setting_fallback_font().to_owned();
+
+    let mut text_calculator = NullTextCalculator::new();
+    let view = DocumentView::new(document_path, &mut text_calculator, fallback_font, None, RevisionView::default(), &mut |_: f32| ())?;
+
+    let text = view.export_text(format).ok_or(ExportError::NoDocument)?;
+    std::fs::write(output_path, text)?;
+    Ok(())
+}
+
+/// Converts the page to a PDF page. Only solid colors are supported, and
+/// images are left out.
+pub fn convert_to_pdf_page(page: &PrintPage) -> pdf::PdfPage {
+    let mut pdf_page = pdf::PdfPage::new(page.size);
+
+    for command in &page.commands {
+        match command {
+            PaintCommand::PaintRect { brush: Brush::SolidColor(color), rect } => {
+                pdf_page.fill_rect(*color, *rect);
+            }
+            PaintCommand::PaintText { brush: Brush::SolidColor(color), position, text, size } => {
+                // The height of the text is the font size with the
+                // NullTextCalculator.
+                pdf_page.text(*color, *position, size.height(), text);
+            }
+            _ => (),
+        }
+    }
+
+    pdf_page
+}
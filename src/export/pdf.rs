@@ -0,0 +1,172 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// A minimal writer of PDF files (ISO 32000-1), supporting only what the
+// export needs: pages with filled rectangles and text in a standard font.
+
+use std::io::Write;
+
+use crate::gui::{
+    painter::null::ASCENT_FACTOR,
+    Color,
+    Position,
+    Rect,
+    Size,
+};
+
+/// The object numbers of the objects that are always present. The objects of
+/// the pages follow these.
+const CATALOG_OBJECT: usize = 1;
+const PAGES_OBJECT: usize = 2;
+const FONT_OBJECT: usize = 3;
+const FIRST_PAGE_OBJECT: usize = 4;
+
+/// A page of which the coordinates are in points, with the origin at the
+/// top-left corner like the rest of the layout. PDF puts the origin at the
+/// bottom-left corner, so the coordinates are flipped when writing.
+pub struct PdfPage {
+    size: Size<f32>,
+
+    /// The operators of the content stream of the page.
+    content: String,
+}
+
+impl PdfPage {
+    pub fn new(size: Size<f32>) -> Self {
+        Self {
+            size,
+            content: String::new(),
+        }
+    }
+
+    pub fn fill_rect(&mut self, color: Color, rect: Rect<f32>) {
+        if color.alpha() == 0 {
+            return;
+        }
+
+        self.content += &format!("{} rg {} {} {} {} re f\n", color_operands(color),
+            rect.left, self.size.height() - rect.bottom, rect.width(), rect.height());
+    }
+
+    /// Writes the text with the top-left corner at the given position.
+    pub fn text(&mut self, color: Color, position: Position<f32>, font_size: f32, text: &str) {
+        if color.alpha() == 0 || text.trim().is_empty() {
+            return;
+        }
+
+        let baseline = self.size.height() - position.y() - font_size * ASCENT_FACTOR;
+        self.content += &format!("BT {} rg /F1 {} Tf {} {} Td ({}) Tj ET\n", color_operands(color),
+            font_size, position.x(), baseline, escape_string(text));
+    }
+}
+
+fn color_operands(color: Color) -> String {
+    format!("{} {} {}", color.red() as f32 / 255.0, color.green() as f32 / 255.0, color.blue() as f32 / 255.0)
+}
+
+/// Escapes the text for a literal string. The standard fonts use the
+/// WinAnsiEncoding, which corresponds with Latin-1 for the characters we
+/// write, so other characters are replaced.
+fn escape_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            ' '..='~' => escaped.push(character),
+            '\t' => escaped.push(' '),
+            '\u{A0}'..='\u{FF}' => escaped += &format!("\\{:03o}", character as u32),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+/// Writes the pages as a PDF file.
+pub fn write_pdf(pages: &[PdfPage], writer: &mut dyn Write) -> std::io::Result<()> {
+    let mut output = Vec::new();
+    let mut offsets = Vec::new();
+
+    let mut write_object = |output: &mut Vec<u8>, number: usize, body: &[u8]| {
+        debug_assert_eq!(offsets.len() + 1, number, "objects must be written in order");
+        offsets.push(output.len());
+        output.extend_from_slice(format!("{} 0 obj\n", number).as_bytes());
+        output.extend_from_slice(body);
+        output.extend_from_slice(b"\nendobj\n");
+    };
+
+    output.extend_from_slice(b"%PDF-1.4\n");
+
+    write_object(&mut output, CATALOG_OBJECT, format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_OBJECT).as_bytes());
+
+    let kids = (0..pages.len())
+        .map(|index| format!("{} 0 R", FIRST_PAGE_OBJECT + index * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    write_object(&mut output, PAGES_OBJECT, format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).as_bytes());
+
+    write_object(&mut output, FONT_OBJECT, b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>");
+
+    for (index, page) in pages.iter().enumerate() {
+        let page_object = FIRST_PAGE_OBJECT + index * 2;
+        let content_object = page_object + 1;
+
+        write_object(&mut output, page_object, format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 {} 0 R >> >> /Contents {} 0 R >>",
+            PAGES_OBJECT, page.size.width(), page.size.height(), FONT_OBJECT, content_object
+        ).as_bytes());
+
+        let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+        stream.extend_from_slice(page.content.as_bytes());
+        stream.extend_from_slice(b"endstream");
+        write_object(&mut output, content_object, &stream);
+    }
+
+    let xref_offset = output.len();
+    output.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes());
+    for offset in &offsets {
+        output.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+
+    output.extend_from_slice(format!("trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+        offsets.len() + 1, CATALOG_OBJECT, xref_offset).as_bytes());
+
+    writer.write_all(&output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape_string("Hello (world)"), "Hello \\(world\\)");
+        assert_eq!(escape_string("a\\b"), "a\\\\b");
+        assert_eq!(escape_string("café"), "caf\\351");
+        assert_eq!(escape_string("日本"), "??");
+    }
+
+    #[test]
+    fn single_page() {
+        let mut page = PdfPage::new(Size::new(200.0, 400.0));
+        page.fill_rect(Color::BLACK, Rect::from_positions(10.0, 30.0, 20.0, 25.0));
+        page.text(Color::BLACK, Position::new(20.0, 20.0), 10.0, "Hello");
+
+        let mut output = Vec::new();
+        write_pdf(&[page], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("%PDF-1.4\n"));
+        assert!(output.contains("/Count 1"));
+        assert!(output.contains("0 0 0 rg 10 375 20 5 re f\n"));
+        assert!(output.contains("BT 0 0 0 rg /F1 10 Tf 20 372 Td (Hello) Tj ET\n"));
+
+        // The cross-reference table must point at the objects.
+        let xref_start = output.find("xref\n").unwrap();
+        let first_entry = output[xref_start..].lines().nth(3).unwrap();
+        let offset: usize = first_entry[..10].parse().unwrap();
+        assert!(output[offset..].starts_with("1 0 obj\n"));
+    }
+}
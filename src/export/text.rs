@@ -0,0 +1,422 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Exporting the text of documents as plain text or Markdown, by walking the
+// laid-out tree. Of Markdown, the headings, emphasis, lists, links and tables
+// are written; other formatting is left out.
+
+use crate::{
+    text_settings::TextSettings,
+    word_processing::text::TextFormat,
+    wp::{BreakType, Hyperlink, Node, NodeData},
+};
+
+/// The indentation of each level of the items of a Markdown list, which is
+/// enough for the text after the markers of the level above, like "10. ".
+const MARKDOWN_LIST_INDENTATION: &str = "    ";
+
+/// Text with the same formatting.
+#[derive(Debug, PartialEq)]
+struct Piece {
+    text: String,
+
+    /// Whether the text is bold or italic while the paragraph isn't, e.g.
+    /// because of the style of a heading.
+    bold: bool,
+    italic: bool,
+
+    /// The target of the link the text is part of.
+    link: Option<String>,
+}
+
+/// Writes the text of the tree in the given format.
+pub fn write_text(root: &Node, format: TextFormat) -> String {
+    let mut writer = TextWriter {
+        format,
+        output: String::new(),
+        after_list_item: false,
+    };
+    writer.write_blocks(root);
+    writer.output
+}
+
+struct TextWriter {
+    format: TextFormat,
+    output: String,
+
+    /// Whether the last block was a list item, after which the next item
+    /// follows without a blank line between them.
+    after_list_item: bool,
+}
+
+impl TextWriter {
+    fn write_blocks(&mut self, node: &Node) {
+        for child in &node.children {
+            match &child.data {
+                NodeData::Paragraph(..) => self.write_paragraph(child),
+                NodeData::Table { .. } => self.write_table(child),
+                NodeData::Revision(revision) if !revision.kind.is_insertion() => (),
+                _ => self.write_blocks(child),
+            }
+        }
+    }
+
+    fn write_paragraph(&mut self, paragraph: &Node) {
+        let text = write_inline_text(paragraph, self.format);
+        let number = paragraph.text_settings.numbering.as_ref()
+            .map(|numbering| (numbering.level.unwrap_or(0).max(0) as usize, numbering_text(paragraph)));
+
+        if self.format == TextFormat::PlainText {
+            if let Some((_, number)) = number.filter(|(_, number)| !number.is_empty()) {
+                self.output += &number;
+                self.output += " ";
+            }
+            self.output += &text;
+            self.output += "\n";
+            return;
+        }
+
+        let heading_level = paragraph.text_settings.heading_level();
+        let is_list_item = number.is_some() && heading_level.is_none();
+        let (prefix, text) = match (heading_level, number) {
+            // Headings can't span multiple lines.
+            (Some(level), _) => ("#".repeat((level as usize + 1).min(6)) + " ", text.replace("\\\n", " ")),
+            (None, Some((level, number))) => (MARKDOWN_LIST_INDENTATION.repeat(level) + &list_marker(&number) + " ", text),
+            (None, None) => (String::new(), escape_block_start(&text)),
+        };
+
+        if text.trim().is_empty() && !is_list_item {
+            return;
+        }
+
+        // The blocks are separated by blank lines, except for the items of a
+        // list, since that would make the list loose.
+        if !self.output.is_empty() && !(is_list_item && self.after_list_item) {
+            self.output += "\n";
+        }
+
+        self.output += &prefix;
+        self.output += &text;
+        self.output += "\n";
+        self.after_list_item = is_list_item;
+    }
+
+    fn write_table(&mut self, table: &Node) {
+        let rows: Vec<Vec<String>> = table.children.iter()
+            .filter(|row| matches!(row.data, NodeData::TableRow))
+            .map(|row| row.children.iter()
+                .filter(|cell| matches!(cell.data, NodeData::TableCell(..)))
+                .map(|cell| self.cell_text(cell))
+                .collect())
+            .collect();
+
+        if self.format == TextFormat::PlainText {
+            for row in rows {
+                self.output += &row.join("\t");
+                self.output += "\n";
+            }
+            return;
+        }
+
+        let column_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if column_count == 0 {
+            return;
+        }
+
+        if !self.output.is_empty() {
+            self.output += "\n";
+        }
+
+        // Markdown tables have a header, which is the first row.
+        for (index, row) in rows.iter().enumerate() {
+            let cells = (0..column_count).map(|column| row.get(column).map(String::as_str).unwrap_or_default());
+            self.output += &format!("| {} |\n", cells.collect::<Vec<_>>().join(" | "));
+
+            if index == 0 {
+                self.output += &format!("|{}\n", " --- |".repeat(column_count));
+            }
+        }
+
+        self.after_list_item = false;
+    }
+
+    /// The text of the paragraphs of the cell, on one line.
+    fn cell_text(&self, cell: &Node) -> String {
+        let separator = match self.format {
+            TextFormat::PlainText => " ",
+            TextFormat::Markdown => "<br>",
+        };
+
+        let paragraphs: Vec<_> = cell.children.iter()
+            .map(|child| write_inline_text(child, self.format))
+            .filter(|text| !text.trim().is_empty())
+            .collect();
+        paragraphs.join(separator).replace("\\\n", separator).replace('\n', separator)
+    }
+}
+
+/// The text of the numbering of the paragraph, such as "1." or a bullet.
+fn numbering_text(paragraph: &Node) -> String {
+    let mut text = String::new();
+    for numbering in paragraph.children.iter().filter(|child| matches!(child.data, NodeData::NumberingParent)) {
+        for part in numbering.find_all(|node| matches!(node.data, NodeData::TextPart(..))) {
+            if let NodeData::TextPart(part) = &part.data {
+                text += &part.text;
+            }
+        }
+    }
+    text
+}
+
+/// The Markdown marker of a list item, which keeps the numbers of numbered
+/// paragraphs and uses a hyphen for the others.
+fn list_marker(number: &str) -> String {
+    let digits: String = number.trim().chars().take_while(char::is_ascii_digit).collect();
+    if digits.is_empty() {
+        String::from("-")
+    } else {
+        format!("{}.", digits)
+    }
+}
+
+fn hyperlink_target(hyperlink: &Hyperlink) -> Option<String> {
+    match (&hyperlink.relationship, &hyperlink.anchor) {
+        (Some(relationship), Some(anchor)) => Some(format!("{}#{}", relationship.borrow().target, anchor)),
+        (Some(relationship), None) => Some(relationship.borrow().target.clone()),
+        (None, Some(anchor)) => Some(format!("#{}", anchor)),
+        (None, None) => None,
+    }
+}
+
+/// Collects the text of the node, leaving out the numbering and the text
+/// deleted by tracked changes. The emphasis is relative to the `base` text
+/// settings of the paragraph.
+fn collect_pieces(node: &Node, base: &TextSettings, link: Option<&str>, pieces: &mut Vec<Piece>) {
+    for child in &node.children {
+        match &child.data {
+            NodeData::NumberingParent => (),
+            NodeData::Revision(revision) if !revision.kind.is_insertion() => (),
+            NodeData::Hyperlink(hyperlink) => {
+                let target = hyperlink_target(hyperlink);
+                collect_pieces(child, base, target.as_deref().or(link), pieces);
+            }
+            NodeData::Break(BreakType::TextWrapping) => match pieces.last_mut() {
+                Some(piece) => piece.text.push('\n'),
+                None => pieces.push(Piece { text: String::from("\n"), bold: false, italic: false, link: link.map(String::from) }),
+            },
+            NodeData::TextPart(part) => {
+                let piece = Piece {
+                    text: part.text.clone(),
+                    bold: child.text_settings.bold.unwrap_or(false) && !base.bold.unwrap_or(false),
+                    italic: child.text_settings.italic.unwrap_or(false) && !base.italic.unwrap_or(false),
+                    link: link.map(String::from),
+                };
+
+                match pieces.last_mut() {
+                    Some(last) if (last.bold, last.italic, &last.link) == (piece.bold, piece.italic, &piece.link) => last.text += &piece.text,
+                    _ => pieces.push(piece),
+                }
+            }
+            _ => collect_pieces(child, base, link, pieces),
+        }
+    }
+}
+
+/// Writes the text inside the node, such as that of a paragraph.
+fn write_inline_text(node: &Node, format: TextFormat) -> String {
+    let mut pieces = Vec::new();
+    collect_pieces(node, &node.text_settings, None, &mut pieces);
+
+    if format == TextFormat::PlainText {
+        return pieces.into_iter().map(|piece| piece.text).collect();
+    }
+
+    let mut output = String::new();
+    let mut index = 0;
+    while index < pieces.len() {
+        let link = &pieces[index].link;
+        let end = index + pieces[index..].iter().take_while(|piece| &piece.link == link).count();
+        let text: String = pieces[index..end].iter().map(write_markdown_emphasis).collect();
+
+        match link {
+            // Targets with spaces or parentheses are enclosed in angle
+            // brackets (CommonMark 6.3).
+            Some(target) if target.contains([' ', '(', ')']) => output += &format!("[{}](<{}>)", text, target),
+            Some(target) => output += &format!("[{}]({})", text, target),
+            None => output += &text,
+        }
+
+        index = end;
+    }
+    output
+}
+
+/// Writes the text of the piece, of which the emphasis doesn't include the
+/// whitespace around it, since the emphasis wouldn't be recognized otherwise.
+fn write_markdown_emphasis(piece: &Piece) -> String {
+    let delimiter = match (piece.bold, piece.italic) {
+        (true, true) => "***",
+        (true, false) => "**",
+        (false, true) => "*",
+        (false, false) => "",
+    };
+
+    let text = piece.text.trim();
+    if delimiter.is_empty() || text.is_empty() {
+        return escape_markdown(&piece.text);
+    }
+
+    let leading = &piece.text[..piece.text.len() - piece.text.trim_start().len()];
+    let trailing = &piece.text[piece.text.trim_end().len()..];
+    format!("{}{}{}{}{}", escape_markdown(leading), delimiter, escape_markdown(text), delimiter, escape_markdown(trailing))
+}
+
+/// Escapes the characters that have a meaning in Markdown text, and writes
+/// line breaks as hard line breaks (CommonMark 6.7).
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '|' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            '\n' => escaped += "\\\n",
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Escapes the start of the text of a paragraph that would otherwise be read
+/// as the start of a heading, a list item or a block quote.
+fn escape_block_start(text: &str) -> String {
+    if text.starts_with(['#', '-', '+', '>']) {
+        return format!("\\{}", text);
+    }
+
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && text[digits..].starts_with(['.', ')']) {
+        return format!("{}\\{}", &text[..digits], &text[digits..]);
+    }
+
+    String::from(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        gui::painter::mock::MockTextCalculator,
+        relationships::{Relationship, RelationshipType},
+        text_settings::Numbering,
+        word_processing::text::process_text,
+        wp::{create_child, revisions::RevisionView, TextPart},
+    };
+
+    use super::*;
+
+    fn create_text_part(parent: &mut Node, text: &str) -> &mut Node {
+        let index = create_child(parent, NodeData::TextPart(TextPart { text: String::from(text), ascent: 0.0, character_spacing: 0.0, word_spacing: 0.0, grapheme_ends: Vec::new(), bidi_level: 0 }));
+        parent.nth_child_mut(index)
+    }
+
+    fn create_paragraph<'a>(parent: &'a mut Node, texts: &[&str]) -> &'a mut Node {
+        let index = create_child(parent, NodeData::Paragraph(Default::default()));
+        let paragraph = parent.nth_child_mut(index);
+        for text in texts {
+            create_text_part(paragraph, text);
+        }
+        paragraph
+    }
+
+    fn create_list_item(parent: &mut Node, level: i32, number: &str, text: &str) {
+        let paragraph = create_paragraph(parent, &[]);
+        paragraph.text_settings.numbering = Some(Numbering { definition: None, level: Some(level) });
+        let numbering = create_child(paragraph, NodeData::NumberingParent);
+        create_text_part(paragraph.nth_child_mut(numbering), number);
+        create_text_part(paragraph, text);
+    }
+
+    fn create_tree() -> Node {
+        let mut document = Node::new(NodeData::Document);
+
+        create_paragraph(&mut document, &["Title"]).text_settings.outline_level = Some(0);
+
+        let paragraph = create_paragraph(&mut document, &["Some "]);
+        create_text_part(paragraph, "bold ").text_settings.bold = Some(true);
+        create_text_part(paragraph, "text, see ");
+        let link = create_child(paragraph, NodeData::Hyperlink(Hyperlink {
+            relationship: Some(Rc::new(RefCell::new(Relationship {
+                id: Rc::from("rId1"),
+                relation_type: RelationshipType::Hyperlink,
+                target: String::from("https://example.com"),
+                data: Vec::new(),
+            }))),
+            anchor: None,
+        }));
+        create_text_part(paragraph.nth_child_mut(link), "here");
+        create_text_part(paragraph, " * 2");
+
+        create_list_item(&mut document, 0, "1.", "One");
+        create_list_item(&mut document, 1, "\u{2022}", "Nested");
+        create_list_item(&mut document, 0, "2.", "Two");
+
+        let table = create_child(&mut document, NodeData::Table { grid: Default::default(), properties: Default::default() });
+        for cells in [["Name", "Value"], ["a|b", "1"]] {
+            let row = create_child(document.nth_child_mut(table), NodeData::TableRow);
+            for text in cells {
+                let row = document.nth_child_mut(table).nth_child_mut(row);
+                let cell = create_child(row, NodeData::TableCell(Default::default()));
+                create_paragraph(row.nth_child_mut(cell), &[text]);
+            }
+        }
+
+        create_paragraph(&mut document, &["1. Not a list"]);
+        document
+    }
+
+    #[test]
+    fn markdown_contains_headings_emphasis_links_lists_and_tables() {
+        assert_eq!(write_text(&create_tree(), TextFormat::Markdown), concat!(
+            "# Title\n",
+            "\n",
+            "Some **bold** text, see [here](https://example.com) \\* 2\n",
+            "\n",
+            "1. One\n",
+            "    - Nested\n",
+            "2. Two\n",
+            "\n",
+            "| Name | Value |\n",
+            "| --- | --- |\n",
+            "| a\\|b | 1 |\n",
+            "\n",
+            "1\\. Not a list\n",
+        ));
+    }
+
+    #[test]
+    fn plain_text_contains_the_text_of_each_paragraph() {
+        assert_eq!(write_text(&create_tree(), TextFormat::PlainText), concat!(
+            "Title\n",
+            "Some bold text, see here * 2\n",
+            "1. One\n",
+            "\u{2022} Nested\n",
+            "2. Two\n",
+            "Name\tValue\n",
+            "a|b\t1\n",
+            "1. Not a list\n",
+        ));
+    }
+
+    #[test]
+    fn imported_markdown_is_exported_again() {
+        let markdown = "# Title\n\nSome **bold** and *italic* [text](https://example.com/)\n";
+
+        let mut text_calculator = MockTextCalculator::new(10.0, 2.0);
+        let result = process_text(markdown, TextFormat::Markdown, &mut text_calculator, "Times New Roman",
+            RevisionView::Markup, &mut |_: f32| ()).unwrap();
+        assert_eq!(write_text(&result.root_node, TextFormat::Markdown), markdown);
+    }
+}
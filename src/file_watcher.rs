@@ -0,0 +1,74 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, RecvTimeoutError},
+    time::Duration,
+};
+
+use notify::Watcher;
+
+/// How long the file must be left alone before we consider it to be finished
+/// changing. Word processors tend to write a file in multiple steps (write to
+/// a temporary file, truncate, rename, etc.), so we coalesce those events.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Watches a single file on disk and invokes the callback once the file has
+/// finished changing.
+pub struct FileWatcher {
+    /// The watcher must be kept alive for the duration of the watch.
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl FileWatcher {
+    pub fn new<F>(path: &Path, on_changed: F) -> notify::Result<Self>
+            where F: Fn() + Send + 'static {
+        let (event_sender, event_receiver) = channel();
+
+        let watched_path: PathBuf = path.to_owned();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            match result {
+                Ok(event) => {
+                    if !matches!(event.kind, notify::EventKind::Create(..) | notify::EventKind::Modify(..)) {
+                        return;
+                    }
+
+                    if event.paths.iter().any(|path| path == &watched_path) {
+                        _ = event_sender.send(());
+                    }
+                }
+                Err(e) => println!("[FileWatcher] Failed to watch: {:?}", e),
+            }
+        })?;
+
+        // Watch the parent directory instead of the file itself, since some
+        // applications save by replacing the file, which would invalidate the
+        // watch on the original file.
+        let directory = path.parent().unwrap_or(path);
+        watcher.watch(directory, notify::RecursiveMode::NonRecursive)?;
+
+        std::thread::Builder::new()
+            .name(format!("File Watcher {}", path.display()))
+            .spawn(move || {
+                // The loop ends when the notify watcher (and with that the
+                // sender) is dropped.
+                while event_receiver.recv().is_ok() {
+                    loop {
+                        match event_receiver.recv_timeout(DEBOUNCE_TIMEOUT) {
+                            Ok(()) => continue,
+                            Err(RecvTimeoutError::Timeout) => break,
+                            Err(RecvTimeoutError::Disconnected) => return,
+                        }
+                    }
+
+                    on_changed();
+                }
+            })
+            .expect("Failed to spawn file watcher thread");
+
+        Ok(Self {
+            _watcher: watcher,
+        })
+    }
+}
@@ -43,7 +43,7 @@ impl EasingFunction {
                     p2.clone() * 3.0 * t1 * x * x +
                     p3.clone() * 3.0 * x * x * x;
 
-                pt.x
+                pt.x()
             }
             EasingFunction::EaseOutQuadratic => 1.0 - (1.0 - x) * (1.0 - x),
         }
@@ -77,6 +77,45 @@ mod tests {
         assert_eq!(function.apply(0.75), 0.9375);
         assert_eq!(function.apply(1.0), 1.0);
     }
+
+    #[test]
+    fn test_zoomer_continuous_zoom() {
+        let mut zoomer = Zoomer::new();
+
+        assert!(zoomer.apply_delta(1.1));
+        assert!(zoomer.apply_delta(1.1));
+        assert!((zoomer.zoom_factor_unanimated() - 1.21).abs() < 0.0001);
+
+        // 1.21 is closer to 1.2 than to 1.33.
+        assert!(zoomer.snap_to_nearest_level());
+        assert_eq!(zoomer.zoom_factor_unanimated(), 1.2);
+        assert!(!zoomer.snap_to_nearest_level());
+
+        // Stepwise zooming continues from the snapped level.
+        assert!(zoomer.increase_zoom_level());
+        assert_eq!(zoomer.zoom_factor_unanimated(), 1.33);
+    }
+
+    #[test]
+    fn test_zoomer_continuous_zoom_is_clamped() {
+        let mut zoomer = Zoomer::new();
+
+        assert!(zoomer.apply_delta(1000.0));
+        assert_eq!(zoomer.zoom_factor_unanimated(), 20.0);
+        assert!(!zoomer.apply_delta(2.0));
+    }
+
+    #[test]
+    fn test_increase_clamped() {
+        let mut value = InterpolatedValue::new(0.75, 150.0, EasingFunction::Linear, 0.0..1.0);
+
+        assert!(value.increase_clamped(0.5));
+        assert_eq!(value.end_value, 1.0);
+        assert!(!value.increase_clamped(0.5));
+
+        assert!(value.increase_clamped(-2.0));
+        assert_eq!(value.end_value, 0.0);
+    }
 }
 
 #[derive(Debug)]
@@ -191,6 +230,15 @@ impl InterpolatedValue {
         }
     }
 
+    /// Increases the value, clamping it to the bounds instead of refusing to
+    /// change when the delta would go out of bounds. Returns whether or not it
+    /// has changed.
+    pub fn increase_clamped(&mut self, delta: f32) -> bool {
+        let previous_value = self.end_value;
+        self.change(self.end_value + delta);
+        self.end_value != previous_value
+    }
+
     pub fn get(&mut self) -> f32 {
         math::lerp_precise_f32(self.start_value, self.end_value, self.animator.update())
     }
@@ -233,6 +281,21 @@ const ZOOM_EASING_FUNCTION: EasingFunction = EasingFunction::EaseOutQuadratic;
 pub struct Zoomer {
     zoom_index: usize,
     zoom_level: InterpolatedValue,
+
+    /// The zoom factor while zooming continuously (e.g. using a touchpad),
+    /// which is in between the discrete `ZOOM_LEVELS`.
+    continuous_zoom: Option<f32>,
+}
+
+/// Finds the zoom level that is the closest to the given zoom factor,
+/// relatively speaking, so 0.14 is closer to 0.1 than to 0.2.
+fn nearest_zoom_level_index(zoom_factor: f32) -> usize {
+    ZOOM_LEVELS.iter()
+        .map(|level| (level / zoom_factor).ln().abs())
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+        .unwrap()
 }
 
 impl Zoomer {
@@ -240,12 +303,15 @@ impl Zoomer {
         Self {
             zoom_index: DEFAULT_ZOOM_LEVEL_INDEX,
             zoom_level: InterpolatedValue::new(ZOOM_LEVELS[DEFAULT_ZOOM_LEVEL_INDEX], ZOOM_ANIMATION_SPEED, ZOOM_EASING_FUNCTION, 0.0..f32::MAX),
+            continuous_zoom: None,
         }
     }
 
     /// Steps to the next zoom level, if any.
     /// For example, when the current zoom level is 1.5, it will move to 1.7.
     pub fn increase_zoom_level(&mut self) -> bool {
+        self.continuous_zoom = None;
+
         let next_zoom_index = self.zoom_index + 1;
         if next_zoom_index >= ZOOM_LEVELS.len() {
             return false;
@@ -260,6 +326,8 @@ impl Zoomer {
     /// Steps to the previous zoom level, if any.
     /// For example, when the current zoom level is 1.7, it will move to 1.5.
     pub fn decrease_zoom_level(&mut self) -> bool {
+        self.continuous_zoom = None;
+
         if self.zoom_index == 0 {
             return false;
         }
@@ -278,7 +346,37 @@ impl Zoomer {
     }
 
     pub fn zoom_factor_unanimated(&self) -> f32 {
-        ZOOM_LEVELS[self.zoom_index]
+        self.continuous_zoom.unwrap_or(ZOOM_LEVELS[self.zoom_index])
+    }
+
+    /// Multiplies the zoom factor by the given `factor` immediately, for
+    /// continuous zooming using gestures. Call [snap_to_nearest_level] when the
+    /// gesture has ended. Returns whether or not the zoom factor changed.
+    ///
+    /// [snap_to_nearest_level]: Self::snap_to_nearest_level
+    pub fn apply_delta(&mut self, factor: f32) -> bool {
+        let current = self.zoom_factor_unanimated();
+        let new_zoom = (current * factor).clamp(ZOOM_LEVELS[0], ZOOM_LEVELS[ZOOM_LEVELS.len() - 1]);
+        if new_zoom == current {
+            return false;
+        }
+
+        self.continuous_zoom = Some(new_zoom);
+        self.zoom_index = nearest_zoom_level_index(new_zoom);
+        self.zoom_level.change_immediately(new_zoom);
+        true
+    }
+
+    /// Ends continuous zooming, by animating to the nearest discrete zoom
+    /// level. Returns whether or not the zoom factor changed.
+    pub fn snap_to_nearest_level(&mut self) -> bool {
+        let Some(zoom) = self.continuous_zoom.take() else {
+            return false;
+        };
+
+        self.zoom_index = nearest_zoom_level_index(zoom);
+        self.zoom_level.change(ZOOM_LEVELS[self.zoom_index]);
+        true
     }
 }
 
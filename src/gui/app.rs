@@ -2,16 +2,13 @@
 // All Rights Reserved.
 
 use super::{
-    painter::Painter,
+    painter::{
+        create_painter,
+        Painter,
+    },
     AppEvent,
 };
 
-#[cfg(target_os = "macos")]
-use super::painter::macos::MacOSPainter;
-
-#[cfg(windows)]
-use super::painter::win32::Win32Painter;
-
 use winit::{
     event::{Event, WindowEvent},
     event_loop::{EventLoopProxy, EventLoopBuilder},
@@ -51,16 +48,6 @@ struct GuiAppData {
     _painter: Arc<RefCell<dyn Painter>>,
 }
 
-fn create_painter(window: &mut Window) -> Arc<RefCell<dyn Painter>> {
-    #[cfg(windows)]
-    let painter = Win32Painter::new(window).expect("Failed to create painter");
-
-    #[cfg(target_os = "macos")]
-    let painter = MacOSPainter::new(window).expect("Failed to create painter");
-
-    Arc::new(RefCell::new(painter))
-}
-
 impl GuiAppData {
     pub fn new(window: &mut Window) -> Self {
         Self {
@@ -10,329 +10,151 @@ pub mod scroll;
 pub mod view;
 pub mod widget;
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct Rect<T> {
-    pub left: T,
-    pub right: T,
-    pub top: T,
-    pub bottom: T,
-}
-
-impl<T> Rect<T> where T: Copy {
-    /// Creates a Rect with no size.
-    pub fn empty() -> Rect<T>
-            where T: Copy + Default {
-        Self {
-            left: Default::default(),
-            right: Default::default(),
-            top: Default::default(),
-            bottom: Default::default(),
-        }
-    }
-
-    pub fn from_positions(left: T, right: T, top: T, bottom: T) -> Self {
-        Self { left, right, top, bottom }
-    }
-
-    pub fn left(&self) -> T {
-        self.left
-    }
-
-    pub fn right(&self) -> T {
-        self.right
-    }
-
-    pub fn top(&self) -> T {
-        self.top
-    }
-
-    pub fn bottom(&self) -> T {
-        self.bottom
-    }
-
-    pub fn position(&self) -> Position<T> {
-        Position::new(self.left, self.top)
-    }
-}
-
-impl<T> Rect<T> where T: Copy + std::ops::Add<Output = T> + std::ops::Sub<Output = T> + std::cmp::PartialOrd {
-    pub fn from_position_and_size(position: Position<T>, size: Size<T>) -> Self {
-        Self {
-            left: position.x(),
-            right: position.x() + size.width(),
-            top: position.y(),
-            bottom: position.y() + size.height()
-        }
-    }
-
-    /// Get the width of the Rect.
-    pub fn width(&self) -> T {
-        self.right - self.left
-    }
-
-    /// Get the height of the Rect.
-    pub fn height(&self) -> T {
-        self.bottom - self.top
-    }
-
-    pub fn size(&self) -> Size<T> {
-        Size::new(self.width(), self.height())
-    }
-
-    pub fn is_inside_inclusive(&self, position: Position<T>) -> bool {
-        position.x() >= self.left && position.x() <= self.right
-            && position.y() >= self.top && position.y() <= self.bottom
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn empty_test() {
-        assert_eq!(Rect::<f32>::empty(), Rect::from_position_and_size(Position::new(0.0, 0.0), Size::new(0.0, 0.0)));
-
-        assert_eq!(Rect::<f32>::empty().bottom, 0.0);
-        assert_eq!(Rect::<f32>::empty().top, 0.0);
-        assert_eq!(Rect::<f32>::empty().right, 0.0);
-        assert_eq!(Rect::<f32>::empty().left, 0.0);
-
-        assert_eq!(Rect::<f32>::empty().width(), 0.0);
-        assert_eq!(Rect::<f32>::empty().height(), 0.0);
-
-        assert_eq!(Rect::<f32>::empty().position(), Position::new(0.0, 0.0));
-        assert_eq!(Rect::<f32>::empty().size(), Size::<f32>::empty());
-    }
-}
-
-/// Defines a size. Prefer this over using Vector2f for everything since it
-/// communicates the definition better.
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub struct Size<T> {
-    width: T,
-    height: T,
-}
-
-impl From<Position<f32>> for Size<f32> {
-    fn from(value: Position<f32>) -> Self {
-        Self {
-            width: value.x(),
-            height: value.y(),
-        }
-    }
-}
-
-impl From<Size<u32>> for Size<f32> {
-    fn from(value: Size<u32>) -> Self {
-        Self {
-            width: value.width as _,
-            height: value.height as _,
-        }
-    }
-}
-
-impl From<Size<f32>> for Size<u32> {
-    fn from(value: Size<f32>) -> Self {
-        Self {
-            width: value.width as _,
-            height: value.height as _,
-        }
-    }
-}
-
-impl<T> Size<T> where T: Copy {
-    /// Creates a size with no width or height.
-    pub fn empty() -> Size<T>
-            where T: Copy + Default {
-        Self { width: Default::default(), height: Default::default() }
-    }
-
-    pub fn new(width: T, height: T) -> Self {
-        Self { width, height }
-    }
-
-    pub fn width(&self) -> T {
-        self.width
-    }
-
-    pub fn height(&self) -> T {
-        self.height
-    }
-}
-
-impl std::ops::Mul<f32> for Size<f32> {
-    type Output = Self;
-    fn mul(self, rhs: f32) -> Self::Output {
-        Self {
-            width: self.width * rhs,
-            height: self.height * rhs,
-        }
-    }
-}
-
-impl<T> From<winit::dpi::LogicalSize<T>> for Size<T> {
-    fn from(value: winit::dpi::LogicalSize<T>) -> Self {
-        Self { width: value.width, height: value.height }
-    }
-}
-
-/// Defines a size. Prefer this over using Vector2f for everything since it
-/// communicates the definition better.
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub struct Position<T> {
-    x: T,
-    y: T,
-}
-
-impl<T> Position<T> where T: Copy {
-    pub fn new(x: T, y: T) -> Self {
-        Self { x, y }
-    }
-
-    /// Get the x value.
-    pub fn x(&self) -> T {
-        self.x
-    }
-
-    /// Get the y value.
-    pub fn y(&self) -> T {
-        self.y
-    }
-
-    /// Get a mutable reference to the x value.
-    pub fn x_mut(&mut self) -> &mut T {
-        &mut self.x
-    }
-
-    /// Get a mutable reference to the y value.
-    pub fn y_mut(&mut self) -> &mut T {
-        &mut self.y
-    }
-}
-
-impl<T: std::ops::Mul<T, Output = T> + Copy> std::ops::Mul<T> for Position<T> {
-    type Output = Position<T>;
-
-    fn mul(self, rhs: T) -> Self::Output {
-        Position::new(self.x * rhs, self.y * rhs)
-    }
-}
-
-impl<T: std::ops::Add<T, Output = T> + Copy> std::ops::Add<Position<T>> for Position<T> {
-    type Output = Position<T>;
+pub use uffice_lib::gui::{Brush, Color, Position, Rect, Size};
 
-    fn add(self, rhs: Position<T>) -> Self::Output {
-        Position::new(self.x + rhs.x, self.y + rhs.y)
-    }
-}
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    PainterRequest,
 
-impl<T: std::ops::Sub<T, Output = T> + Copy> std::ops::Sub<Position<T>> for Position<T> {
-    type Output = Position<T>;
+    /// A certain tab was loading and is now ready.
+    TabBecameReady(TabId),
 
-    fn sub(self, rhs: Position<T>) -> Self::Output {
-        Position::new(self.x - rhs.x, self.y - rhs.y)
-    }
-}
+    /// A certain tab was painted.
+    TabPainted {
+        tab_id: TabId,
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-/// An RGBA color.
-pub struct Color {
-    red: u8,
-    green: u8,
-    blue: u8,
-    alpha: u8,
-}
+        /// The total height of the content in pixels.
+        total_content_height: f32,
 
-impl Color {
+        /// How many pages were painted.
+        page_count: usize,
 
-    /// Black, or in hex notation: #000000
-    pub const BLACK: Color = Color::from_rgb(0, 0, 0);
+        /// The page that is the most visible, counting from 0.
+        current_page: usize,
 
-    /// White, or in hex notation: #FFFFFF
-    pub const WHITE: Color = Color::from_rgb(255, 255, 255);
+        /// The top of every page relative to the top of the content rect, in
+        /// pixels.
+        page_tops: Vec<f32>,
+    },
 
-    /// Red, or in hex notation: #FF0000
-    pub const RED: Color = Color::from_rgb(255, 0, 0);
+    /// A certain tab has progressed in loading.
+    TabProgressed {
+        tab_id: TabId,
 
-    /// Green, or in hex notation: #00FF00
-    pub const GREEN: Color = Color::from_rgb(0, 255, 0);
+        /// The progress of the tab, between 0 and 1.
+        progress: f32,
+    },
 
-    /// Blue, or in hex notation: #0000FF
-    pub const BLUE: Color = Color::from_rgb(0, 0, 255);
+    /// More pages of a tab that is still loading were laid out, which can be
+    /// shown already.
+    TabPagesLaidOut {
+        tab_id: TabId,
 
-    /// A fully transparent color, or in hex notation: #00000000
-    pub const TRANSPARENT: Color = Color::from_rgba(0, 0, 0, 0);
+        /// The number of pages that are laid out.
+        page_count: usize,
+    },
 
-    /// Creates a color from RGB color components, with full alpha opaqueness.
-    pub const fn from_rgb(red: u8, green: u8, blue: u8) -> Self  {
-        Self { red, green, blue, alpha: 255 }
-    }
+    /// The user changed the text selection of a certain tab, which needs to
+    /// be repainted.
+    TabSelectionChanged {
+        tab_id: TabId,
 
-    /// Creates a color from RGBA color components.
-    pub const fn from_rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self  {
-        Self { red, green, blue, alpha }
-    }
+        /// Whether the selection is collapsed, and shows a caret.
+        has_caret: bool,
+    },
 
-    /// Get the red component of the RGBA color.
-    pub fn red(&self) -> u8 {
-        self.red
-    }
+    /// The caret of a certain tab blinked, and needs to be repainted.
+    TabCaretBlinked {
+        tab_id: TabId,
+    },
 
-    /// Get the green component of the RGBA color.
-    pub fn green(&self) -> u8 {
-        self.green
-    }
+    /// The mouse moved over a certain tab, which wants the given cursor to
+    /// be shown, or the default cursor when None.
+    TabCursorChanged {
+        tab_id: TabId,
+        cursor: Option<winit::window::CursorIcon>,
+    },
 
-    /// Get the blue component of the RGBA color.
-    pub fn blue(&self) -> u8 {
-        self.blue
-    }
+    /// The mouse rested on a certain tab for long enough to show the tooltip
+    /// of what it hovers over.
+    TabMouseRested {
+        tab_id: TabId,
+    },
 
-    /// Get the alpha component of the RGBA color.
-    pub fn alpha(&self) -> u8 {
-        self.alpha
-    }
-}
+    /// The mouse moved over something of which a certain tab describes what
+    /// it is, or over nothing to describe when the text is None.
+    TabHoverTextChanged {
+        tab_id: TabId,
+        text: Option<String>,
+    },
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Brush {
-    Test,
+    TabCrashed {
+        tab_id: TabId,
+    },
 
-    /// A brush with a solid color.
-    SolidColor(Color),
-}
+    /// A link to another place in the document of a certain tab was clicked,
+    /// which is at the rect on the screen as of the last paint.
+    TabNavigated {
+        tab_id: TabId,
+        rect: Rect<f32>,
+    },
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum AppEvent {
-    PainterRequest,
+    /// The file of a certain tab was changed on disk, and has finished
+    /// changing.
+    TabFileChanged {
+        tab_id: TabId,
+    },
 
-    /// A certain tab was loading and is now ready.
-    TabBecameReady(TabId),
+    /// The matches of the search in a certain tab changed, or the user moved
+    /// to another match.
+    TabSearched {
+        tab_id: TabId,
+        results: view::SearchResults,
+    },
 
-    /// A certain tab was painted.
-    TabPainted {
+    /// The document of a certain tab was edited, or the edits were undone
+    /// back to the state in which it was loaded or last saved.
+    TabModified {
         tab_id: TabId,
+        is_modified: bool,
+    },
 
-        /// The total height of the content in pixels.
-        total_content_height: f32,
+    /// A certain tab saved its document to the given path.
+    TabSaved {
+        tab_id: TabId,
+        path: std::path::PathBuf,
+    },
 
-        /// How many pages were painted.
-        page_count: usize,
+    /// A certain tab failed to save its document to the given path.
+    TabSaveFailed {
+        tab_id: TabId,
+        path: std::path::PathBuf,
+        error: String,
     },
 
-    /// A certain tab has progressed in loading.
-    TabProgressed {
+    /// The document of a certain tab was laid out or edited, which changed
+    /// the number of words, characters and paragraphs.
+    TabStatistics {
         tab_id: TabId,
+        statistics: crate::wp::statistics::DocumentStatistics,
+    },
 
-        /// The progress of the tab, between 0 and 1.
-        progress: f32,
+    /// The comments on the document of a certain tab, once it is loaded.
+    TabComments {
+        tab_id: TabId,
+        comments: Vec<crate::wp::comments::Comment>,
     },
 
-    TabCrashed {
+    /// The problems encountered while loading the document of a certain tab.
+    TabDiagnostics {
         tab_id: TabId,
+        diagnostics: Vec<crate::wp::diagnostics::Diagnostic>,
     },
 
+    /// Another instance of uffice was started, and forwarded its files to be
+    /// opened in this instance.
+    OpenFiles(Vec<std::path::PathBuf>),
+
 }
 
 unsafe impl Send for AppEvent {}
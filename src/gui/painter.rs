@@ -1,100 +1,37 @@
 // Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
-use std::{cell::RefCell, rc::Rc};
-use bitflags::bitflags;
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+use winit::window::Window;
 use super::{Brush, Rect, Position, Size};
+use crate::fonts::DocumentFontSource;
 
-#[cfg(target_os = "macos")]
-pub mod macos;
+pub use uffice_lib::gui::painter::{
+    FontMetrics,
+    FontSelectionError,
+    FontSpecification,
+    FontWeight,
+    Image,
+    MeasureJob,
+    TextCalculator,
+    TextCalculatorFactory,
+};
 
-#[cfg(windows)]
-pub mod win32;
-
-#[derive(Debug)]
-pub enum FontSelectionError {
-    /// Failed to access the resource associated with the font.
-    CannotAccessResource,
-
-    /// Failed to find the font with the specified options.
-    NotFound,
-}
+#[cfg(test)]
+pub use uffice_lib::gui::painter::mock;
 
-/// https://learn.microsoft.com/en-us/typography/opentype/spec/os2#usweightclass
-#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
-pub enum FontWeight {
-    Custom(f32),
-
-    Thin,
-    ExtraLight,
-    Light,
-    SemiLight,
-    #[default] Regular,
-    Medium,
-    SemiBold,
-    Bold,
-    ExtraBold,
-    Black,
-}
+pub mod cached;
 
-impl From<FontWeight> for f32 {
-    /// Convert the FontWeight into the industry-standard numeric format.
-    fn from(value: FontWeight) -> Self {
-        match value {
-            FontWeight::Custom(value) => value,
-            FontWeight::Thin => 100.0,
-            FontWeight::ExtraLight => 200.0,
-            FontWeight::Light => 300.0,
-            FontWeight::SemiLight => 350.0,
-            FontWeight::Regular => 400.0,
-            FontWeight::Medium => 500.0,
-            FontWeight::SemiBold => 600.0,
-            FontWeight::Bold => 700.0,
-            FontWeight::ExtraBold => 800.0,
-            FontWeight::Black => 900.0,
-        }
-    }
-}
+pub mod null;
 
-bitflags! {
-    pub struct FontStyle: u32 {
-        const NORMAL = 0;
-        const BOLD = 1;
-        const ITALIC = 2;
-        const UNDERLINE = 4;
-        const STRIKEOUT = 8;
-    }
-}
-
-/// Specifies what font to use.
-#[derive(Debug, Clone, Copy)]
-pub struct FontSpecification<'a> {
-    family_name: &'a str,
-    size: f32,
-    weight: FontWeight,
-}
-
-impl<'a> FontSpecification<'a> {
-    pub fn new(family_name: &'a str, size: f32, weight: FontWeight) -> FontSpecification<'a> {
-        Self {
-            family_name,
-            size,
-            weight
-        }
-    }
-
-    pub fn family_name(&self) -> &str {
-        self.family_name
-    }
+#[cfg(windows)]
+pub mod print;
 
-    pub fn size(&self) -> f32 {
-        self.size
-    }
+#[cfg(not(windows))]
+pub mod software;
 
-    pub fn weight(&self) -> FontWeight {
-        self.weight
-    }
-}
+#[cfg(windows)]
+pub mod win32;
 
 /// Specifies the quality of the painter. For example, when a font is currently
 /// present, but it isn't generated with the correct size, the following
@@ -131,17 +68,6 @@ pub enum PainterCache {
     Document(usize),
 }
 
-/// Calculate properties about text in order to do layout without the need of
-/// claiming the Painter. This allows us to do layout in the background while
-/// the main UI thread can still render and run the main loop.
-pub trait TextCalculator {
-
-    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError>;
-
-    fn line_spacing(&mut self, font: FontSpecification) -> Result<f32, FontSelectionError>;
-
-}
-
 /// Paint on a window using specific functions. The underlying implementation
 /// might schedule paint tasks, so the commands might not get processed
 /// immediately.
@@ -168,6 +94,9 @@ pub trait Painter {
     /// Called when the window, client rect, etc resizes.
     fn handle_resize(&mut self, window: &mut winit::window::Window);
 
+    /// Paint the image, stretched to fill the rect.
+    fn paint_image(&mut self, image: &Image, rect: Rect<f32>);
+
     /// Paint a rect using the specified brush.
     fn paint_rect(&mut self, brush: Brush, rect: Rect<f32>);
 
@@ -175,6 +104,11 @@ pub trait Painter {
     /// in pixels.
     fn paint_text(&mut self, brush: Brush, position: Position<f32>, text: &str, size: Option<Size<f32>>) -> Size<f32>;
 
+    /// Uses the fonts of a document for the text painted in the cache of that
+    /// document, see [TextCalculator::set_document_fonts].
+    fn register_document_fonts(&mut self, _cache: PainterCache, _fonts: Arc<DocumentFontSource>) {
+    }
+
     /// Prepare for new paint commands.
     fn reset(&mut self);
 
@@ -189,4 +123,29 @@ pub trait Painter {
     /// Get the sharable text calculator.
     fn text_calculator(&mut self) -> Rc<RefCell<dyn TextCalculator>>;
 
+    /// Get a factory for text calculators that can be used on other threads,
+    /// or None when the text can only be measured on this thread.
+    fn text_calculator_factory(&self) -> Option<TextCalculatorFactory> {
+        None
+    }
+
+}
+
+/// Creates the painter for the current platform: the Win32 painter on Windows
+/// and the software painter elsewhere. When that isn't possible, e.g. because
+/// no GPU is available, the [NullPainter](null::NullPainter) is used instead.
+pub fn create_painter(window: &mut Window) -> Arc<RefCell<dyn Painter>> {
+    #[cfg(windows)]
+    match win32::Win32Painter::new(window) {
+        Ok(painter) => return Arc::new(RefCell::new(painter)),
+        Err(e) => println!("[Painter] Warning: failed to create the Win32 painter, falling back to the null painter: {:?}", e),
+    }
+
+    #[cfg(not(windows))]
+    match software::SoftwarePainter::new(window) {
+        Ok(painter) => return Arc::new(RefCell::new(painter)),
+        Err(e) => println!("[Painter] Warning: failed to create the software painter, falling back to the null painter: {:?}", e),
+    }
+
+    Arc::new(RefCell::new(null::NullPainter::new()))
 }
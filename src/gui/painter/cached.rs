@@ -0,0 +1,224 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// A text calculator that remembers the sizes it measured. The layout measures
+// the same text many times, e.g. a line while words are added to it, and the
+// measurement of the platform text calculators isn't cheap: the Win32 one
+// creates a text layout for every measurement. The text of the paragraphs
+// that are laid out next can be measured ahead on multiple threads.
+
+use std::{collections::HashMap, sync::Arc};
+
+use rayon::prelude::*;
+
+use crate::{fonts::DocumentFontSource, gui::Size};
+
+use super::{
+    FontMetrics,
+    FontSelectionError,
+    FontSpecification,
+    MeasureJob,
+    TextCalculator,
+    TextCalculatorFactory,
+};
+
+/// Identifies the font of a [FontSpecification], without the character
+/// spacing and horizontal scale, since those don't change the natural size.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct FontKey {
+    family_name: String,
+    size: u32,
+    weight: u32,
+    italic: bool,
+}
+
+impl From<FontSpecification<'_>> for FontKey {
+    fn from(font: FontSpecification<'_>) -> Self {
+        Self {
+            family_name: String::from(font.family_name()),
+            size: font.size().to_bits(),
+            weight: f32::from(font.weight()).to_bits(),
+            italic: font.is_italic(),
+        }
+    }
+}
+
+/// Wraps a [TextCalculator], and caches the sizes of the text it measured per
+/// font, so measuring the same text in the same font again is a lookup.
+pub struct CachedTextCalculator<T> {
+    inner: T,
+    text_sizes: HashMap<FontKey, HashMap<String, Result<Size<f32>, FontSelectionError>>>,
+
+    /// Creates the text calculators of the threads that measure the text
+    /// ahead, or None to measure it with the inner text calculator instead.
+    factory: Option<TextCalculatorFactory>,
+
+    /// The fonts of the document that is measured. The inner text calculator
+    /// can be shared with a painter, which uses the fonts of the document it
+    /// paints, so they are set again before measuring.
+    document_fonts: Option<Arc<DocumentFontSource>>,
+}
+
+impl<T: TextCalculator> CachedTextCalculator<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            text_sizes: HashMap::new(),
+            factory: None,
+            document_fonts: None,
+        }
+    }
+
+    /// Measures the text ahead on the threads of the rayon thread pool, with
+    /// the text calculators of the `factory`.
+    pub fn with_factory(self, factory: Option<TextCalculatorFactory>) -> Self {
+        Self {
+            factory,
+            ..self
+        }
+    }
+}
+
+impl<T: TextCalculator> TextCalculator for CachedTextCalculator<T> {
+    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+        let text_sizes = self.text_sizes.entry(font.into()).or_default();
+        if let Some(size) = text_sizes.get(text) {
+            return *size;
+        }
+
+        self.inner.set_document_fonts(self.document_fonts.clone());
+        let size = self.inner.calculate_text_size(font, text);
+        text_sizes.insert(String::from(text), size);
+        size
+    }
+
+    fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError> {
+        self.inner.set_document_fonts(self.document_fonts.clone());
+        self.inner.font_metrics(font)
+    }
+
+    fn measure_ahead(&mut self, jobs: Vec<MeasureJob>) {
+        // Without other text calculators, the text is measured when the
+        // layout needs it anyway.
+        let Some(factory) = &self.factory else {
+            return;
+        };
+
+        // Every thread runs its part of the jobs with a text calculator of its
+        // own, since they can't be shared, and caches what it measured.
+        let chunk_size = jobs.len().div_ceil(rayon::current_num_threads()).max(1);
+        let mut jobs = jobs.into_iter().peekable();
+        let mut chunks = Vec::new();
+        while jobs.peek().is_some() {
+            chunks.push(jobs.by_ref().take(chunk_size).collect::<Vec<_>>());
+        }
+
+        let document_fonts = &self.document_fonts;
+        let text_sizes: Vec<_> = chunks.into_par_iter()
+            .map(|chunk| {
+                let mut text_calculator = CachedTextCalculator::new(factory());
+                text_calculator.set_document_fonts(document_fonts.clone());
+                for job in chunk {
+                    job(&mut text_calculator);
+                }
+                text_calculator.text_sizes
+            })
+            .collect();
+
+        for text_sizes in text_sizes {
+            for (font, sizes) in text_sizes {
+                self.text_sizes.entry(font).or_default().extend(sizes);
+            }
+        }
+    }
+
+    fn set_document_fonts(&mut self, fonts: Option<Arc<DocumentFontSource>>) {
+        // The sizes measured with other fonts don't apply anymore.
+        let is_same = match (&self.document_fonts, &fonts) {
+            (Some(current), Some(fonts)) => current.id() == fonts.id(),
+            (current, fonts) => current.is_none() && fonts.is_none(),
+        };
+        if !is_same {
+            self.text_sizes.clear();
+        }
+
+        self.document_fonts = fonts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::gui::painter::{mock::MockTextCalculator, FontWeight};
+
+    /// Counts the text that is measured by the [MockTextCalculator].
+    struct CountingTextCalculator {
+        inner: MockTextCalculator,
+        measured: usize,
+    }
+
+    impl TextCalculator for CountingTextCalculator {
+        fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+            self.measured += 1;
+            self.inner.calculate_text_size(font, text)
+        }
+
+        fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError> {
+            self.inner.font_metrics(font)
+        }
+    }
+
+    fn calculator() -> CachedTextCalculator<CountingTextCalculator> {
+        CachedTextCalculator::new(CountingTextCalculator { inner: MockTextCalculator::new(5.0, 0.0), measured: 0 })
+    }
+
+    #[test]
+    fn text_is_measured_once_per_font() {
+        let mut calculator = calculator();
+        let font = FontSpecification::new("Calibri", 11.0, FontWeight::Regular);
+
+        assert_eq!(calculator.calculate_text_size(font, "Hello").unwrap(), Size::new(25.0, 11.0));
+        assert_eq!(calculator.calculate_text_size(font, "Hello").unwrap(), Size::new(25.0, 11.0));
+        assert_eq!(calculator.inner.measured, 1);
+
+        calculator.calculate_text_size(font, "Hello world").unwrap();
+        calculator.calculate_text_size(font.with_italic(true), "Hello").unwrap();
+        calculator.calculate_text_size(FontSpecification::new("Calibri", 12.0, FontWeight::Regular), "Hello").unwrap();
+        calculator.calculate_text_size(FontSpecification::new("Arial", 11.0, FontWeight::Regular), "Hello").unwrap();
+        assert_eq!(calculator.inner.measured, 5);
+    }
+
+    #[test]
+    fn spacing_and_scale_are_applied_to_the_cached_size() {
+        let mut calculator = calculator();
+        let font = FontSpecification::new("Calibri", 11.0, FontWeight::Regular);
+
+        calculator.measure_text(font, "Hello").unwrap();
+        let size = calculator.measure_text(font.with_character_spacing(1.0).with_horizontal_scale(2.0), "Hello").unwrap();
+
+        assert_eq!(size, Size::new(55.0, 11.0));
+        assert_eq!(calculator.inner.measured, 1);
+    }
+
+    #[test]
+    fn text_measured_ahead_is_cached() {
+        let factory: TextCalculatorFactory = Arc::new(|| Box::new(MockTextCalculator::new(5.0, 0.0)));
+        let mut calculator = calculator().with_factory(Some(factory));
+        let font = FontSpecification::new("Calibri", 11.0, FontWeight::Regular);
+
+        let jobs = ["Hello", "world"].into_iter()
+            .map(|text| -> MeasureJob {
+                Box::new(move |text_calculator: &mut dyn TextCalculator| {
+                    text_calculator.calculate_text_size(FontSpecification::new("Calibri", 11.0, FontWeight::Regular), text).unwrap();
+                })
+            })
+            .collect();
+        calculator.measure_ahead(jobs);
+
+        assert_eq!(calculator.calculate_text_size(font, "Hello").unwrap(), Size::new(25.0, 11.0));
+        assert_eq!(calculator.calculate_text_size(font, "world").unwrap(), Size::new(25.0, 11.0));
+        assert_eq!(calculator.inner.measured, 0);
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// This file contains a painter that doesn't paint anything, but records the
+// commands instead. It is used when no window or GPU is available, for
+// example in headless environments such as CI runners.
+
+use std::{rc::Rc, cell::RefCell, sync::Arc};
+
+use crate::gui::{
+    Brush,
+    Position,
+    Rect,
+    Size,
+};
+
+use super::{
+    FontSelectionError,
+    FontSpecification,
+    FontWeight,
+    Image,
+    Painter,
+    PainterCache,
+    PaintQuality,
+    TextCalculator,
+    TextCalculatorFactory,
+};
+
+pub use uffice_lib::gui::painter::null::{NullTextCalculator, ASCENT_FACTOR};
+
+/// A paint command that was recorded by the [NullPainter].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaintCommand {
+    BeginClipRegion(Rect<f32>),
+    EndClipRegion,
+    PaintImage {
+        image: Image,
+        rect: Rect<f32>,
+    },
+    PaintRect {
+        brush: Brush,
+        rect: Rect<f32>,
+    },
+    PaintText {
+        brush: Brush,
+        position: Position<f32>,
+        text: String,
+        size: Size<f32>,
+    },
+    SelectFont {
+        family_name: String,
+        size: f32,
+        weight: FontWeight,
+        italic: bool,
+    },
+}
+
+impl PaintCommand {
+    /// Paints the command with another painter, moved by the `offset`. When
+    /// the font can't be selected, the `fallback_font` is selected instead.
+    pub fn replay(&self, painter: &mut dyn Painter, offset: Position<f32>, fallback_font: Option<&str>) {
+        let translate = |rect: &Rect<f32>| Rect::from_position_and_size(rect.position() + offset, rect.size());
+        match self {
+            PaintCommand::BeginClipRegion(rect) => painter.begin_clip_region(translate(rect)),
+            PaintCommand::EndClipRegion => painter.end_clip_region(),
+            PaintCommand::PaintImage { image, rect } => painter.paint_image(image, translate(rect)),
+            PaintCommand::PaintRect { brush, rect } => painter.paint_rect(*brush, translate(rect)),
+            PaintCommand::PaintText { brush, position, text, size } => {
+                painter.paint_text(*brush, *position + offset, text, Some(*size));
+            }
+            PaintCommand::SelectFont { family_name, size, weight, italic } => {
+                let font = FontSpecification::new(family_name, *size, *weight).with_italic(*italic);
+                if let Err(e) = painter.select_font(font) {
+                    match fallback_font {
+                        Some(fallback_font) => {
+                            _ = painter.select_font(FontSpecification::new(fallback_font, *size, *weight).with_italic(*italic));
+                        }
+                        None => println!("[Painter] Warning: failed to select font \"{}\": {:?}", family_name, e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct NullPainter {
+    commands: Vec<PaintCommand>,
+
+    /// The family name and size of the selected font.
+    selected_font: Option<(String, f32)>,
+
+    text_calculator: Rc<RefCell<NullTextCalculator>>,
+}
+
+impl NullPainter {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            selected_font: None,
+            text_calculator: Rc::new(RefCell::new(NullTextCalculator::new())),
+        }
+    }
+
+    /// The commands that were recorded since the last
+    /// [reset](super::Painter::reset).
+    pub fn commands(&self) -> &[PaintCommand] {
+        &self.commands
+    }
+}
+
+impl Painter for NullPainter {
+    fn begin_clip_region(&mut self, rect: Rect<f32>) {
+        self.commands.push(PaintCommand::BeginClipRegion(rect));
+    }
+
+    fn clear_cache(&mut self, _cache: PainterCache) {
+    }
+
+    fn display(&mut self) {
+    }
+
+    fn end_clip_region(&mut self) {
+        self.commands.push(PaintCommand::EndClipRegion);
+    }
+
+    fn handle_resize(&mut self, _window: &mut winit::window::Window) {
+    }
+
+    fn paint_image(&mut self, image: &Image, rect: Rect<f32>) {
+        self.commands.push(PaintCommand::PaintImage { image: image.clone(), rect });
+    }
+
+    fn paint_rect(&mut self, brush: Brush, rect: Rect<f32>) {
+        self.commands.push(PaintCommand::PaintRect { brush, rect });
+    }
+
+    fn paint_text(&mut self, brush: Brush, position: Position<f32>, text: &str, size: Option<Size<f32>>) -> Size<f32> {
+        let size = match size {
+            Some(size) => size,
+
+            // The text can't be measured without a font, so the call is
+            // ignored.
+            None => {
+                let Some((family_name, font_size)) = self.selected_font.as_ref() else {
+                    return Size::empty();
+                };
+                let font = FontSpecification::new(family_name, *font_size, Default::default());
+                match self.text_calculator.borrow_mut().calculate_text_size(font, text) {
+                    Ok(size) => size,
+                    Err(..) => return Size::empty(),
+                }
+            }
+        };
+
+        self.commands.push(PaintCommand::PaintText {
+            brush,
+            position,
+            text: String::from(text),
+            size,
+        });
+
+        size
+    }
+
+    fn reset(&mut self) {
+        self.commands.clear();
+    }
+
+    fn select_font(&mut self, font: FontSpecification) -> Result<(), FontSelectionError> {
+        self.selected_font = Some((String::from(font.family_name()), font.size()));
+        self.commands.push(PaintCommand::SelectFont {
+            family_name: String::from(font.family_name()),
+            size: font.size(),
+            weight: font.weight(),
+            italic: font.is_italic(),
+        });
+        Ok(())
+    }
+
+    fn switch_cache(&mut self, _cache: PainterCache, _quality: PaintQuality) {
+    }
+
+    fn text_calculator(&mut self) -> Rc<RefCell<dyn TextCalculator>> {
+        self.text_calculator.clone()
+    }
+
+    fn text_calculator_factory(&self) -> Option<TextCalculatorFactory> {
+        Some(Arc::new(|| Box::new(NullTextCalculator::new())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::Color;
+
+    #[test]
+    fn replayed_commands_are_moved_by_the_offset() {
+        let commands = [
+            PaintCommand::BeginClipRegion(Rect::from_positions(0.0, 100.0, 0.0, 200.0)),
+            PaintCommand::PaintRect { brush: Brush::SolidColor(Color::RED), rect: Rect::from_positions(10.0, 20.0, 30.0, 40.0) },
+            PaintCommand::SelectFont {
+                family_name: String::from("Calibri"),
+                size: 11.0,
+                weight: FontWeight::Regular,
+                italic: true,
+            },
+            PaintCommand::PaintText {
+                brush: Brush::SolidColor(Color::BLACK),
+                position: Position::new(5.0, 5.0),
+                text: String::from("Text"),
+                size: Size::new(20.0, 10.0),
+            },
+            PaintCommand::EndClipRegion,
+        ];
+
+        let mut painter = NullPainter::new();
+        for command in &commands {
+            command.replay(&mut painter, Position::new(50.0, 300.0), None);
+        }
+
+        assert_eq!(painter.commands()[0], PaintCommand::BeginClipRegion(Rect::from_positions(50.0, 150.0, 300.0, 500.0)));
+        assert_eq!(painter.commands()[1], PaintCommand::PaintRect {
+            brush: Brush::SolidColor(Color::RED),
+            rect: Rect::from_positions(60.0, 70.0, 330.0, 340.0),
+        });
+        assert_eq!(painter.commands()[2], commands[2]);
+        assert_eq!(painter.commands()[3], PaintCommand::PaintText {
+            brush: Brush::SolidColor(Color::BLACK),
+            position: Position::new(55.0, 305.0),
+            text: String::from("Text"),
+            size: Size::new(20.0, 10.0),
+        });
+        assert_eq!(painter.commands()[4], PaintCommand::EndClipRegion);
+    }
+
+    #[test]
+    fn text_without_a_font_is_ignored() {
+        let mut painter = NullPainter::new();
+
+        let size = painter.paint_text(Brush::SolidColor(Color::BLACK), Position::new(5.0, 5.0), "Text", None);
+        assert_eq!(size, Size::empty());
+        assert!(painter.commands().is_empty());
+
+        // With a size, the text doesn't have to be measured.
+        painter.paint_text(Brush::SolidColor(Color::BLACK), Position::new(5.0, 5.0), "Text", Some(Size::new(20.0, 10.0)));
+        assert_eq!(painter.commands().len(), 1);
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// This file contains a painter targeting a printer, using the GDI of the
+// Windows printing API. The coordinates of the commands are in points, and
+// are scaled to the resolution of the printer.
+
+use std::{cell::RefCell, ffi::c_void, rc::Rc};
+
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::COLORREF,
+        Graphics::Gdi::{
+            BITMAPINFO,
+            BITMAPINFOHEADER,
+            BI_RGB,
+            CLIP_DEFAULT_PRECIS,
+            CreateFontW,
+            CreateSolidBrush,
+            DEFAULT_CHARSET,
+            DEFAULT_QUALITY,
+            DIB_RGB_COLORS,
+            DeleteDC,
+            DeleteObject,
+            FillRect,
+            GetDeviceCaps,
+            HDC,
+            HFONT,
+            HGDIOBJ,
+            IntersectClipRect,
+            LOGPIXELSX,
+            LOGPIXELSY,
+            OUT_DEFAULT_PRECIS,
+            RestoreDC,
+            SaveDC,
+            SelectObject,
+            SetBkMode,
+            SetTextColor,
+            SRCCOPY,
+            StretchDIBits,
+            TextOutW,
+            TRANSPARENT,
+        },
+        Storage::Xps::{
+            AbortDoc,
+            DOCINFOW,
+            EndDoc,
+            EndPage,
+            StartDocW,
+            StartPage,
+        },
+        System::Memory::GlobalFree,
+        UI::Controls::Dialogs::{
+            PD_NOPAGENUMS,
+            PD_NOSELECTION,
+            PD_RETURNDC,
+            PRINTDLGW,
+            PrintDlgW,
+        },
+    },
+};
+
+use crate::{
+    gui::{Brush, Color, Position, Rect, Size},
+    print::{PrintError, PrintPage},
+};
+
+use super::{
+    null::NullTextCalculator,
+    FontSelectionError,
+    FontSpecification,
+    Image,
+    PainterCache,
+    PaintQuality,
+    TextCalculator,
+};
+
+/// There are 72 points in an inch.
+const POINTS_PER_INCH: f32 = 72.0;
+
+pub struct PrintPainter {
+    dc: HDC,
+
+    /// The number of device pixels per point, horizontally and vertically.
+    scale: Size<f32>,
+
+    /// The font that is selected into the device context.
+    font: Option<HFONT>,
+
+    /// The commands contain the sizes of the text already, so this is only
+    /// needed to satisfy the [Painter](super::Painter) trait.
+    text_calculator: Rc<RefCell<NullTextCalculator>>,
+}
+
+impl PrintPainter {
+    /// Asks the user which printer to use, and prints the pages on it.
+    pub fn print(document_name: &str, pages: &[PrintPage]) -> Result<(), PrintError> {
+        let mut dialog = PRINTDLGW {
+            lStructSize: std::mem::size_of::<PRINTDLGW>() as u32,
+            Flags: PD_RETURNDC | PD_NOSELECTION | PD_NOPAGENUMS,
+            ..Default::default()
+        };
+
+        if !unsafe { PrintDlgW(&mut dialog) }.as_bool() {
+            return Err(PrintError::Cancelled);
+        }
+
+        // The device context is all we need, the dialog allocated the other
+        // handles for us.
+        unsafe {
+            _ = GlobalFree(dialog.hDevMode);
+            _ = GlobalFree(dialog.hDevNames);
+        }
+
+        let mut painter = Self::new(dialog.hDC);
+        let result = painter.print_document(document_name, pages);
+        if result.is_err() {
+            unsafe { AbortDoc(painter.dc) };
+        }
+
+        result
+    }
+
+    fn new(dc: HDC) -> Self {
+        let scale = unsafe {
+            Size::new(
+                GetDeviceCaps(dc, LOGPIXELSX) as f32 / POINTS_PER_INCH,
+                GetDeviceCaps(dc, LOGPIXELSY) as f32 / POINTS_PER_INCH,
+            )
+        };
+
+        unsafe {
+            SetBkMode(dc, TRANSPARENT);
+        }
+
+        Self {
+            dc,
+            scale,
+            font: None,
+            text_calculator: Rc::new(RefCell::new(NullTextCalculator::new())),
+        }
+    }
+
+    fn print_document(&mut self, document_name: &str, pages: &[PrintPage]) -> Result<(), PrintError> {
+        let document_name: Vec<u16> = document_name.encode_utf16().chain(std::iter::once(0)).collect();
+        let document_info = DOCINFOW {
+            cbSize: std::mem::size_of::<DOCINFOW>() as i32,
+            lpszDocName: PCWSTR(document_name.as_ptr()),
+            ..Default::default()
+        };
+
+        if unsafe { StartDocW(self.dc, &document_info) } <= 0 {
+            return Err(PrintError::Gdi("StartDocW"));
+        }
+
+        for page in pages {
+            if unsafe { StartPage(self.dc) } <= 0 {
+                return Err(PrintError::Gdi("StartPage"));
+            }
+
+            page.replay(self);
+
+            if unsafe { EndPage(self.dc) } <= 0 {
+                return Err(PrintError::Gdi("EndPage"));
+            }
+        }
+
+        if unsafe { EndDoc(self.dc) } <= 0 {
+            return Err(PrintError::Gdi("EndDoc"));
+        }
+
+        Ok(())
+    }
+
+    fn to_device_rect(&self, rect: Rect<f32>) -> windows::Win32::Foundation::RECT {
+        windows::Win32::Foundation::RECT {
+            left: (rect.left * self.scale.width()) as i32,
+            top: (rect.top * self.scale.height()) as i32,
+            right: (rect.right * self.scale.width()) as i32,
+            bottom: (rect.bottom * self.scale.height()) as i32,
+        }
+    }
+}
+
+fn to_color_ref(color: Color) -> COLORREF {
+    COLORREF(color.red() as u32 | (color.green() as u32) << 8 | (color.blue() as u32) << 16)
+}
+
+impl Drop for PrintPainter {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(font) = self.font.take() {
+                DeleteObject(HGDIOBJ(font.0));
+            }
+
+            DeleteDC(self.dc);
+        }
+    }
+}
+
+impl super::Painter for PrintPainter {
+    fn begin_clip_region(&mut self, rect: Rect<f32>) {
+        let rect = self.to_device_rect(rect);
+        unsafe {
+            SaveDC(self.dc);
+            IntersectClipRect(self.dc, rect.left, rect.top, rect.right, rect.bottom);
+        }
+    }
+
+    fn clear_cache(&mut self, _cache: PainterCache) {
+    }
+
+    fn display(&mut self) {
+    }
+
+    fn end_clip_region(&mut self) {
+        unsafe {
+            RestoreDC(self.dc, -1);
+        }
+    }
+
+    fn handle_resize(&mut self, _window: &mut winit::window::Window) {
+    }
+
+    fn paint_image(&mut self, image: &Image, rect: Rect<f32>) {
+        // The GDI expects the pixels as blue, green, red and a reserved byte.
+        let mut pixels = image.pixels().to_vec();
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+
+        let bitmap_info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: image.size().width() as i32,
+                // A negative height means the rows are stored top-down.
+                biHeight: -(image.size().height() as i32),
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let rect = self.to_device_rect(rect);
+        unsafe {
+            StretchDIBits(self.dc, rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top,
+                0, 0, image.size().width() as i32, image.size().height() as i32,
+                Some(pixels.as_ptr() as *const c_void), &bitmap_info, DIB_RGB_COLORS, SRCCOPY);
+        }
+    }
+
+    fn paint_rect(&mut self, brush: Brush, rect: Rect<f32>) {
+        let Brush::SolidColor(color) = brush else {
+            return;
+        };
+
+        if color.alpha() == 0 {
+            return;
+        }
+
+        let rect = self.to_device_rect(rect);
+        unsafe {
+            let brush = CreateSolidBrush(to_color_ref(color));
+            FillRect(self.dc, &rect, brush);
+            DeleteObject(HGDIOBJ(brush.0));
+        }
+    }
+
+    fn paint_text(&mut self, brush: Brush, position: Position<f32>, text: &str, size: Option<Size<f32>>) -> Size<f32> {
+        let size = size.unwrap_or_else(|| Size::new(0.0, 0.0));
+
+        let Brush::SolidColor(color) = brush else {
+            return size;
+        };
+
+        if color.alpha() == 0 {
+            return size;
+        }
+
+        let text: Vec<u16> = text.encode_utf16().collect();
+        unsafe {
+            SetTextColor(self.dc, to_color_ref(color));
+            TextOutW(self.dc, (position.x() * self.scale.width()) as i32, (position.y() * self.scale.height()) as i32, &text);
+        }
+
+        size
+    }
+
+    fn reset(&mut self) {
+    }
+
+    fn select_font(&mut self, font: FontSpecification) -> Result<(), FontSelectionError> {
+        let family_name: Vec<u16> = font.family_name().encode_utf16().chain(std::iter::once(0)).collect();
+
+        // A negative height selects the font by the size of the em square
+        // instead of the cell height, which is what the font size means.
+        let handle = unsafe {
+            CreateFontW(-(font.size() * self.scale.height()) as i32, 0, 0, 0, f32::from(font.weight()) as i32,
+                font.is_italic() as u32, 0, 0, DEFAULT_CHARSET.0 as u32, OUT_DEFAULT_PRECIS.0 as u32, CLIP_DEFAULT_PRECIS.0 as u32,
+                DEFAULT_QUALITY.0 as u32, 0, PCWSTR(family_name.as_ptr()))
+        };
+
+        if handle.is_invalid() {
+            return Err(FontSelectionError::NotFound);
+        }
+
+        unsafe {
+            SelectObject(self.dc, HGDIOBJ(handle.0));
+            if let Some(previous_font) = self.font.replace(handle) {
+                DeleteObject(HGDIOBJ(previous_font.0));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn switch_cache(&mut self, _cache: PainterCache, _quality: PaintQuality) {
+    }
+
+    fn text_calculator(&mut self) -> Rc<RefCell<dyn TextCalculator>> {
+        self.text_calculator.clone()
+    }
+}
@@ -0,0 +1,563 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// This file contains a software painter for the platforms without a painter
+// of their own, such as Linux and macOS. The commands are rasterized on the
+// CPU into a pixel buffer, which is presented on the window using softbuffer.
+// Fonts are loaded, measured and rasterized using font_kit.
+
+use std::{borrow::Cow, rc::Rc, cell::RefCell, collections::HashMap, sync::Arc};
+
+use font_kit::{
+    canvas::{Canvas, Format, RasterizationOptions},
+    family_name::FamilyName,
+    hinting::HintingOptions,
+    source::Source,
+    sources::multi::MultiSource,
+};
+
+use pathfinder_geometry::{
+    transform2d::Transform2F,
+    vector::Vector2F,
+};
+
+use unicode_bidi::BidiInfo;
+
+use winit::window::Window;
+
+use crate::{
+    fonts::DocumentFontSource,
+    gui::{
+        Brush,
+        Color,
+        Position,
+        Rect,
+        Size,
+    },
+};
+
+use super::{
+    FontMetrics,
+    FontSelectionError,
+    FontSpecification,
+    Image,
+    PainterCache,
+    PaintQuality,
+    TextCalculator,
+    TextCalculatorFactory,
+};
+
+/// The color of the window behind everything that is painted, the same as the
+/// Win32 painter.
+const BACKGROUND_COLOR: Color = Color::from_rgb(25, 25, 25);
+
+#[derive(Debug)]
+pub enum Error {
+    SoftBufferError(softbuffer::SoftBufferError),
+}
+
+impl From<softbuffer::SoftBufferError> for Error {
+    fn from(value: softbuffer::SoftBufferError) -> Self {
+        Self::SoftBufferError(value)
+    }
+}
+
+/// A font loaded by font_kit, with the metrics stored alongside it since
+/// querying them isn't free for every loader.
+pub struct LoadedFont {
+    font: font_kit::font::Font,
+    metrics: font_kit::metrics::Metrics,
+}
+
+impl LoadedFont {
+    fn glyph_for_char(&self, character: char) -> u32 {
+        // Glyph 0 is the .notdef glyph, which is usually drawn as a box.
+        self.font.glyph_for_char(character).unwrap_or(0)
+    }
+
+    /// The advance of the glyph in pixels, for the given font size.
+    fn glyph_advance(&self, glyph: u32, size: f32) -> f32 {
+        let advance = self.font.advance(glyph).map(|advance| advance.x()).unwrap_or(0.0);
+        advance * size / self.metrics.units_per_em as f32
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars()
+            .map(|character| self.glyph_advance(self.glyph_for_char(character), size))
+            .sum()
+    }
+
+    fn text_size(&self, text: &str, size: f32) -> Size<f32> {
+        Size::new(self.text_width(text, size), FontMetrics::from_font_kit(&self.metrics, size).line_height())
+    }
+}
+
+fn load_font(font_source: &MultiSource, document_fonts: Option<&DocumentFontSource>, font: FontSpecification) -> Result<LoadedFont, FontSelectionError> {
+    let family_name = font.family_name();
+    println!("[Painter(Software)] Loading new font \"{}\"", family_name);
+    let properties = font.font_kit_properties();
+
+    // The fonts embedded in the document are used instead of the installed
+    // ones, since they are what the author saw.
+    let bold = f32::from(font.weight()) >= 600.0;
+    if let Some(handle) = document_fonts.and_then(|fonts| fonts.select_embedded(family_name, bold, font.is_italic())) {
+        match handle.load() {
+            Ok(font) => {
+                let metrics = font.metrics();
+                return Ok(LoadedFont { font, metrics });
+            }
+            Err(e) => println!("[Painter(Software)] Warning: failed to load embedded font \"{}\": {:?}", family_name, e),
+        }
+    }
+
+    // Documents often use fonts that are only available on Windows, such as
+    // Calibri, so fall back to the alternatives of the font table, and then
+    // to a font that is always available.
+    let mut family_names = vec![FamilyName::Title(String::from(family_name))];
+    if let Some(document_fonts) = document_fonts {
+        family_names.extend_from_slice(document_fonts.fallback_family_names(family_name));
+    }
+    family_names.push(FamilyName::SansSerif);
+
+    use font_kit::error::SelectionError;
+    let handle = font_source.select_best_match(&family_names, &properties)
+        .map_err(|e| match e {
+            SelectionError::NotFound => FontSelectionError::NotFound,
+            SelectionError::CannotAccessSource => FontSelectionError::CannotAccessResource,
+        })?;
+
+    let font = handle.load().map_err(|e| {
+        println!("[Painter(Software)] Warning: failed to load font \"{}\": {:?}", family_name, e);
+        FontSelectionError::CannotAccessResource
+    })?;
+
+    let metrics = font.metrics();
+    Ok(LoadedFont { font, metrics })
+}
+
+/// The [TextCalculator] of the [SoftwarePainter], which is also the owner of
+/// the loaded fonts, since they're independent of the font size.
+pub struct SoftwareTextCalculator {
+    font_source: MultiSource,
+
+    /// The fonts of the document that is measured or painted.
+    document_fonts: Option<Arc<DocumentFontSource>>,
+
+    /// The fonts by the document that provides them (or 0 for the installed
+    /// fonts), family name, weight and whether they're italic. Fonts that
+    /// couldn't be loaded are stored as well, to avoid searching for them
+    /// again.
+    fonts: HashMap<(usize, String, u32, bool), Result<Rc<LoadedFont>, FontSelectionError>>,
+}
+
+impl SoftwareTextCalculator {
+    pub fn new() -> Self {
+        Self {
+            font_source: MultiSource::from_sources(crate::fonts::resolve_font_sources()),
+            document_fonts: None,
+            fonts: HashMap::new(),
+        }
+    }
+
+    fn load_font(&mut self, font: FontSpecification) -> Result<Rc<LoadedFont>, FontSelectionError> {
+        let document_fonts = self.document_fonts.as_deref()
+            .filter(|document_fonts| document_fonts.provides(font.family_name()));
+        let key = (
+            document_fonts.map_or(0, DocumentFontSource::id),
+            String::from(font.family_name()),
+            f32::from(font.weight()).to_bits(),
+            font.is_italic(),
+        );
+        self.fonts.entry(key)
+            .or_insert_with(|| load_font(&self.font_source, document_fonts, font).map(Rc::new))
+            .clone()
+    }
+}
+
+impl TextCalculator for SoftwareTextCalculator {
+    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+        Ok(self.load_font(font)?.text_size(text, font.size()))
+    }
+
+    fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError> {
+        Ok(FontMetrics::from_font_kit(&self.load_font(font)?.metrics, font.size()))
+    }
+
+    fn set_document_fonts(&mut self, fonts: Option<Arc<DocumentFontSource>>) {
+        self.document_fonts = fonts;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    /// The address of the [LoadedFont], which stays the same since fonts are
+    /// never unloaded.
+    font: usize,
+    glyph: u32,
+    size: u32,
+    scale_x: u32,
+    scale_y: u32,
+}
+
+/// The coverage of a glyph, relative to the pen position on the baseline.
+struct RasterizedGlyph {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    stride: usize,
+    coverage: Vec<u8>,
+}
+
+fn rasterize_glyph(font: &LoadedFont, glyph: u32, size: f32, scale: Vector2F) -> Option<RasterizedGlyph> {
+    let transform = Transform2F::from_scale(scale);
+    let hinting = HintingOptions::None;
+    let rasterization = RasterizationOptions::GrayscaleAa;
+
+    let bounds = font.font.raster_bounds(glyph, size, transform, hinting, rasterization).ok()?;
+    if bounds.width() <= 0 || bounds.height() <= 0 {
+        // E.g. the glyph of a space.
+        return None;
+    }
+
+    let mut canvas = Canvas::new(bounds.size(), Format::A8);
+    font.font.rasterize_glyph(&mut canvas, glyph, size, Transform2F::from_translation(-bounds.origin().to_f32()) * transform,
+        hinting, rasterization).ok()?;
+
+    Some(RasterizedGlyph {
+        left: bounds.origin_x(),
+        top: bounds.origin_y(),
+        width: bounds.width() as usize,
+        height: bounds.height() as usize,
+        stride: canvas.stride,
+        coverage: canvas.pixels,
+    })
+}
+
+/// The characters of the text in the order they are painted from left to
+/// right, which is reversed for right-to-left text. The Win32 painter doesn't
+/// need this, since DirectWrite reorders the text itself.
+fn visual_order(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+
+    let bidi_info = BidiInfo::new(text, None);
+    match bidi_info.paragraphs.as_slice() {
+        [paragraph] => bidi_info.reorder_line(paragraph, paragraph.range.clone()),
+        paragraphs => Cow::Owned(paragraphs.iter()
+            .map(|paragraph| bidi_info.reorder_line(paragraph, paragraph.range.clone()))
+            .collect()),
+    }
+}
+
+/// Blends the color with the 0RGB pixel, where the coverage is how much of
+/// the pixel is covered by the shape, e.g. for anti-aliased text.
+fn blend(pixel: &mut u32, color: Color, coverage: u8) {
+    let alpha = color.alpha() as u32 * coverage as u32 / 255;
+    if alpha == 0 {
+        return;
+    }
+
+    let destination = *pixel;
+    let mix = |shift: u32, component: u8| {
+        let destination = (destination >> shift) & 0xFF;
+        (component as u32 * alpha + destination * (255 - alpha)) / 255
+    };
+
+    *pixel = (mix(16, color.red()) << 16) | (mix(8, color.green()) << 8) | mix(0, color.blue());
+}
+
+fn mix_colors(a: Color, b: Color, t: f32) -> Color {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t.clamp(0.0, 1.0)).round() as u8;
+    Color::from_rgba(mix(a.red(), b.red()), mix(a.green(), b.green()), mix(a.blue(), b.blue()), mix(a.alpha(), b.alpha()))
+}
+
+/// Get the color of the brush at the given pixel of the rect it's painted in.
+fn brush_color(brush: Brush, rect: Rect<f32>, x: f32, y: f32) -> Color {
+    match brush {
+        Brush::Test => {
+            // An approximation of the radial gradient of the Win32 painter.
+            let color_a = Color::from_rgb(198, 152, 255);
+            let color_b = Color::from_rgb(93, 203, 255);
+
+            let dx = (x - rect.left) / rect.width().max(1.0);
+            let dy = (y - rect.top) / rect.height().max(1.0);
+            mix_colors(color_a, color_b, (dx * dx + dy * dy).sqrt() / std::f32::consts::SQRT_2)
+        }
+        Brush::SolidColor(color) => color,
+    }
+}
+
+pub struct SoftwarePainter {
+    context: softbuffer::GraphicsContext,
+
+    /// The pixels of the window in the 0RGB format softbuffer expects.
+    pixels: Vec<u32>,
+
+    window_size: winit::dpi::PhysicalSize<u32>,
+    window_scale_factor: f32,
+
+    /// The clip regions in pixels, each intersected with the one before it.
+    clip_regions: Vec<Rect<f32>>,
+
+    /// The selected font with its size.
+    selected_font: Option<(Rc<LoadedFont>, f32)>,
+
+    current_cache: PainterCache,
+    glyph_caches: HashMap<PainterCache, HashMap<GlyphKey, Option<Rc<RasterizedGlyph>>>>,
+
+    /// The fonts of the documents by the cache they are painted in.
+    document_fonts: HashMap<PainterCache, Arc<DocumentFontSource>>,
+
+    text_calculator: Rc<RefCell<SoftwareTextCalculator>>,
+}
+
+impl SoftwarePainter {
+    pub fn new(window: &mut Window) -> Result<Self, Error> {
+        // SAFETY: the window outlives the painter, since both live until the
+        //         event loop of the application exits.
+        let context = unsafe { softbuffer::GraphicsContext::new(&*window, &*window) }?;
+
+        let window_size = window.inner_size();
+        Ok(Self {
+            context,
+            pixels: vec![0; window_size.width as usize * window_size.height as usize],
+            window_size,
+            window_scale_factor: window.scale_factor() as _,
+            clip_regions: Vec::new(),
+            selected_font: None,
+            current_cache: PainterCache::UI,
+            glyph_caches: HashMap::new(),
+            document_fonts: HashMap::new(),
+            text_calculator: Rc::new(RefCell::new(SoftwareTextCalculator::new())),
+        })
+    }
+
+    /// Converts the rect from device-independent pixels to physical pixels.
+    fn to_physical(&self, rect: Rect<f32>) -> Rect<f32> {
+        let scale = self.window_scale_factor;
+        Rect::from_positions(rect.left * scale, rect.right * scale, rect.top * scale, rect.bottom * scale)
+    }
+
+    /// Get the pixels of the physical rect that are inside the window and the
+    /// current clip region.
+    fn pixel_bounds(&self, rect: Rect<f32>) -> Rect<usize> {
+        let width = self.window_size.width as usize;
+        let height = self.window_size.height as usize;
+
+        let clip = self.clip_regions.last().copied()
+            .unwrap_or_else(|| Rect::from_positions(0.0, width as f32, 0.0, height as f32));
+
+        let clamp = |value: f32, max: usize| value.round().clamp(0.0, max as f32) as usize;
+        Rect::from_positions(
+            clamp(rect.left.max(clip.left), width),
+            clamp(rect.right.min(clip.right), width),
+            clamp(rect.top.max(clip.top), height),
+            clamp(rect.bottom.min(clip.bottom), height),
+        )
+    }
+
+    fn glyph(&mut self, font: &Rc<LoadedFont>, glyph: u32, size: f32, scale: Vector2F) -> Option<Rc<RasterizedGlyph>> {
+        let key = GlyphKey {
+            font: Rc::as_ptr(font) as usize,
+            glyph,
+            size: size.to_bits(),
+            scale_x: scale.x().to_bits(),
+            scale_y: scale.y().to_bits(),
+        };
+
+        self.glyph_caches.entry(self.current_cache)
+            .or_default()
+            .entry(key)
+            .or_insert_with(|| rasterize_glyph(font, glyph, size, scale).map(Rc::new))
+            .clone()
+    }
+
+    /// Paints the glyph with the top-left corner of its coverage at the given
+    /// pixel.
+    fn paint_glyph(&mut self, brush: Brush, glyph: &RasterizedGlyph, left: i32, top: i32) {
+        let rect = Rect::from_positions(
+            left as f32, (left + glyph.width as i32) as f32,
+            top as f32, (top + glyph.height as i32) as f32
+        );
+        let bounds = self.pixel_bounds(rect);
+        let width = self.window_size.width as usize;
+
+        for y in bounds.top..bounds.bottom {
+            for x in bounds.left..bounds.right {
+                let glyph_x = (x as i32 - left) as usize;
+                let glyph_y = (y as i32 - top) as usize;
+                let coverage = glyph.coverage[glyph_y * glyph.stride + glyph_x];
+                let color = brush_color(brush, rect, x as f32, y as f32);
+                blend(&mut self.pixels[y * width + x], color, coverage);
+            }
+        }
+    }
+}
+
+impl super::Painter for SoftwarePainter {
+
+    fn begin_clip_region(&mut self, rect: Rect<f32>) {
+        let mut rect = self.to_physical(rect);
+        if let Some(previous) = self.clip_regions.last() {
+            rect = Rect::from_positions(
+                rect.left.max(previous.left),
+                rect.right.min(previous.right),
+                rect.top.max(previous.top),
+                rect.bottom.min(previous.bottom),
+            );
+        }
+        self.clip_regions.push(rect);
+    }
+
+    fn clear_cache(&mut self, cache: PainterCache) {
+        self.glyph_caches.remove(&cache);
+        self.document_fonts.remove(&cache);
+    }
+
+    fn display(&mut self) {
+        // The buffer is painted immediately, so it only has to be presented.
+        self.context.set_buffer(&self.pixels, self.window_size.width as u16, self.window_size.height as u16);
+    }
+
+    fn end_clip_region(&mut self) {
+        self.clip_regions.pop();
+    }
+
+    fn handle_resize(&mut self, window: &mut winit::window::Window) {
+        self.window_size = window.inner_size();
+        self.window_scale_factor = window.scale_factor() as _;
+        self.pixels = vec![0; self.window_size.width as usize * self.window_size.height as usize];
+    }
+
+    fn paint_image(&mut self, image: &Image, rect: Rect<f32>) {
+        let rect = self.to_physical(rect);
+        let bounds = self.pixel_bounds(rect);
+        let width = self.window_size.width as usize;
+        let image_size = image.size();
+
+        // Sample the nearest pixel of the image for every pixel of the rect.
+        let sample = |position: usize, start: f32, length: f32, image_length: u32| {
+            let relative = (position as f32 + 0.5 - start) / length.max(1.0);
+            ((relative * image_length as f32) as u32).min(image_length.saturating_sub(1))
+        };
+
+        for y in bounds.top..bounds.bottom {
+            let image_y = sample(y, rect.top, rect.height(), image_size.height());
+            for x in bounds.left..bounds.right {
+                let image_x = sample(x, rect.left, rect.width(), image_size.width());
+                blend(&mut self.pixels[y * width + x], image.pixel(image_x, image_y), 255);
+            }
+        }
+    }
+
+    fn paint_rect(&mut self, brush: Brush, rect: Rect<f32>) {
+        let rect = self.to_physical(rect);
+        let bounds = self.pixel_bounds(rect);
+        let width = self.window_size.width as usize;
+
+        for y in bounds.top..bounds.bottom {
+            for x in bounds.left..bounds.right {
+                let color = brush_color(brush, rect, x as f32, y as f32);
+                blend(&mut self.pixels[y * width + x], color, 255);
+            }
+        }
+    }
+
+    fn paint_text(&mut self, brush: Brush, position: Position<f32>, text: &str, size: Option<Size<f32>>) -> Size<f32> {
+        let (font, font_size) = self.selected_font.clone()
+            .expect("No font selected before painting text");
+        let natural_size = font.text_size(text, font_size);
+
+        // Like the Win32 painter, the text is stretched to the exact size.
+        let mut scale = Vector2F::new(1.0, 1.0);
+        if let Some(size) = size {
+            if natural_size.width() > 0.0 && natural_size.height() > 0.0 {
+                scale = Vector2F::new(size.width() / natural_size.width(), size.height() / natural_size.height());
+            }
+        }
+        let scale = scale * self.window_scale_factor;
+
+        let metrics = FontMetrics::from_font_kit(&font.metrics, font_size);
+        let mut pen_x = position.x() * self.window_scale_factor;
+        let baseline = (position.y() * self.window_scale_factor + metrics.ascent * scale.y()).round() as i32;
+
+        for character in visual_order(text).chars() {
+            let glyph = font.glyph_for_char(character);
+            if let Some(rasterized) = self.glyph(&font, glyph, font_size, scale) {
+                self.paint_glyph(brush, &rasterized, pen_x.round() as i32 + rasterized.left, baseline + rasterized.top);
+            }
+            pen_x += font.glyph_advance(glyph, font_size) * scale.x();
+        }
+
+        natural_size
+    }
+
+    fn register_document_fonts(&mut self, cache: PainterCache, fonts: Arc<DocumentFontSource>) {
+        if self.current_cache == cache {
+            self.text_calculator.borrow_mut().set_document_fonts(Some(fonts.clone()));
+        }
+        self.document_fonts.insert(cache, fonts);
+    }
+
+    fn reset(&mut self) {
+        let background = (BACKGROUND_COLOR.red() as u32) << 16
+            | (BACKGROUND_COLOR.green() as u32) << 8
+            | BACKGROUND_COLOR.blue() as u32;
+        self.pixels.fill(background);
+        self.clip_regions.clear();
+        self.current_cache = PainterCache::UI;
+        self.text_calculator.borrow_mut().set_document_fonts(None);
+    }
+
+    fn select_font(&mut self, font: FontSpecification) -> Result<(), FontSelectionError> {
+        let loaded_font = self.text_calculator.borrow_mut().load_font(font)?;
+        self.selected_font = Some((loaded_font, font.size()));
+        Ok(())
+    }
+
+    fn switch_cache(&mut self, cache: PainterCache, _quality: PaintQuality) {
+        self.current_cache = cache;
+        self.text_calculator.borrow_mut().set_document_fonts(self.document_fonts.get(&cache).cloned());
+    }
+
+    fn text_calculator(&mut self) -> Rc<RefCell<dyn TextCalculator>> {
+        self.text_calculator.clone()
+    }
+
+    fn text_calculator_factory(&self) -> Option<TextCalculatorFactory> {
+        // The fonts are loaded again by every text calculator, since font_kit
+        // fonts can't be shared between threads.
+        Some(Arc::new(|| Box::new(SoftwareTextCalculator::new())))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blending() {
+        let mut pixel = 0x000000;
+        blend(&mut pixel, Color::WHITE, 255);
+        assert_eq!(pixel, 0xFFFFFF);
+
+        blend(&mut pixel, Color::RED, 0);
+        assert_eq!(pixel, 0xFFFFFF);
+
+        blend(&mut pixel, Color::BLUE, 51);
+        assert_eq!(pixel, 0xCCCCFF);
+
+        blend(&mut pixel, Color::TRANSPARENT, 255);
+        assert_eq!(pixel, 0xCCCCFF);
+    }
+
+    #[test]
+    fn color_mixing() {
+        assert_eq!(mix_colors(Color::BLACK, Color::WHITE, 0.0), Color::BLACK);
+        assert_eq!(mix_colors(Color::BLACK, Color::WHITE, 0.5), Color::from_rgb(128, 128, 128));
+        assert_eq!(mix_colors(Color::BLACK, Color::WHITE, 2.0), Color::WHITE);
+    }
+}
@@ -6,16 +6,19 @@
 // Windows APIs relating to painting, but doesn't expose them since they're
 // not relevant for other systems.
 
-use std::{rc::Rc, cell::RefCell, collections::{HashMap, hash_map::Entry}, hash::Hash};
+use std::{rc::Rc, cell::RefCell, collections::{HashMap, hash_map::Entry}, hash::Hash, sync::Arc};
 
 use winit::window::Window;
 
 use raw_window_handle::HasRawWindowHandle;
 
-use crate::gui::{
-    Brush,
-    Rect,
-    Color, Position, Size
+use crate::{
+    fonts::DocumentFontSource,
+    gui::{
+        Brush,
+        Rect,
+        Color, Position, Size
+    },
 };
 
 use super::FontSelectionError;
@@ -58,83 +61,6 @@ enum PaintCommand {
     EndClipRegion,
 }
 
-impl From<Rect<f32>> for mltg::Rect<f32> {
-    fn from(value: Rect<f32>) -> Self {
-        Self::from_points(
-            (value.left, value.top),
-            (value.right, value.bottom)
-        )
-    }
-}
-
-impl From<Color> for mltg::Rgba<f32> {
-    fn from(value: Color) -> Self {
-        Self::new(
-            value.red() as f32 / 255.0,
-            value.green() as f32 / 255.0,
-            value.blue() as f32 / 255.0,
-            value.alpha() as f32 / 255.0
-        )
-    }
-}
-
-impl From<Position<f32>> for mltg::Point<f32> {
-    fn from(value: Position<f32>) -> Self {
-        Self::new(value.x(), value.y())
-    }
-}
-
-impl From<mltg::Size<f32>> for Size<f32> {
-    fn from(value: mltg::Size<f32>) -> Self {
-        Self::new(value.width, value.height)
-    }
-}
-
-impl From<Size<f32>> for mltg::Size<f32> {
-    fn from(value: Size<f32>) -> Self {
-        Self::new(value.width, value.height)
-    }
-}
-
-impl From<super::FontWeight> for font_kit::properties::Weight {
-    fn from(value: super::FontWeight) -> Self {
-        use font_kit::properties::Weight;
-        match value {
-            super::FontWeight::Custom(weight) => Weight(weight),
-
-            super::FontWeight::Thin => Weight::THIN,
-            super::FontWeight::ExtraLight => Weight::EXTRA_LIGHT,
-            super::FontWeight::Light => Weight(350.0),
-            super::FontWeight::SemiLight => Weight::LIGHT,
-            super::FontWeight::Regular => Weight::NORMAL,
-            super::FontWeight::Medium => Weight::MEDIUM,
-            super::FontWeight::SemiBold => Weight::SEMIBOLD,
-            super::FontWeight::Bold => Weight::BOLD,
-            super::FontWeight::ExtraBold => Weight::EXTRA_BOLD,
-            super::FontWeight::Black => Weight::BLACK,
-        }
-    }
-}
-
-impl From<super::FontWeight> for mltg::FontWeight {
-    fn from(value: super::FontWeight) -> Self {
-        match value {
-            super::FontWeight::Custom(_weight) => todo!("mltg is missing this API"),
-
-            super::FontWeight::Thin => mltg::FontWeight::Thin,
-            super::FontWeight::ExtraLight => mltg::FontWeight::UltraLight,
-            super::FontWeight::Light => mltg::FontWeight::Light,
-            super::FontWeight::SemiLight => mltg::FontWeight::SemiLight,
-            super::FontWeight::Regular => mltg::FontWeight::Regular,
-            super::FontWeight::Medium => mltg::FontWeight::Medium,
-            super::FontWeight::SemiBold => mltg::FontWeight::SemiBold,
-            super::FontWeight::Bold => mltg::FontWeight::Bold,
-            super::FontWeight::ExtraBold => mltg::FontWeight::UltraBold,
-            super::FontWeight::Black => mltg::FontWeight::UltraBlack,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
 struct FontVariantCacheKey {
     /// Some weird conversion in order to have the Hash trait.
@@ -142,13 +68,16 @@ struct FontVariantCacheKey {
 
     /// We only care about the 350.5 decimal, not the others.
     weight: u32,
+
+    italic: bool,
 }
 
 impl<'a> From<super::FontSpecification<'a>> for FontVariantCacheKey {
     fn from(value: super::FontSpecification<'a>) -> Self {
         Self {
-            size: (value.size * 10.0) as u64,
-            weight: (Into::<f32>::into(value.weight) * 10.0) as u32,
+            size: (value.size() * 10.0) as u64,
+            weight: (Into::<f32>::into(value.weight()) * 10.0) as u32,
+            italic: value.is_italic(),
         }
     }
 }
@@ -196,6 +125,7 @@ impl<T> SelectOption<T> {
 struct CachedFont {
     parent: Rc<RefCell<CachedFontFamily>>,
     format: mltg::TextFormat,
+    metrics: super::FontMetrics,
 
     text_layouts: HashMap<String, mltg::TextLayout>,
 }
@@ -209,46 +139,72 @@ struct Win32PainterCache {
     sources: Rc<RefCell<SharedCacheSources>>,
 
     font_families: HashMap<Rc<str>, Rc<RefCell<CachedFontFamily>>>,
+
+    /// The fonts of the document painted in this cache.
+    document_fonts: Option<Arc<DocumentFontSource>>,
 }
 
-fn load_font(sources: &Rc<RefCell<SharedCacheSources>>, factory: &mltg::Factory, font: super::FontSpecification) -> Result<(mltg::TextStyle, mltg::TextFormat), super::FontSelectionError> {
-    println!("[Painter(Win32)] Loading new font \"{}\" with size {}", font.family_name, Into::<FontVariantCacheKey>::into(font).size);
-    let properties = font_kit::properties::Properties {
-        weight: font.weight.into(),
-        ..Default::default()
-    };
+/// Scale the metrics of the font file, which are in font units, to the size
+/// of the font.
+fn load_font(sources: &Rc<RefCell<SharedCacheSources>>, factory: &mltg::Factory, document_fonts: Option<&DocumentFontSource>,
+        font: super::FontSpecification) -> Result<(mltg::TextStyle, mltg::TextFormat, super::FontMetrics), super::FontSelectionError> {
+    println!("[Painter(Win32)] Loading new font \"{}\" with size {}", font.family_name(), Into::<FontVariantCacheKey>::into(font).size);
+    let properties = font.font_kit_properties();
+
+    // The fonts embedded in the document are used instead of the installed
+    // ones, and the alternatives of the font table when it isn't installed.
+    let bold = Into::<f32>::into(font.weight()) >= 600.0;
+    let embedded_font = document_fonts.and_then(|fonts| fonts.select_embedded(font.family_name(), bold, font.is_italic()));
 
-    let family_names = [
-        font_kit::family_name::FamilyName::Title(String::from(font.family_name))
+    let mut family_names = vec![
+        font_kit::family_name::FamilyName::Title(String::from(font.family_name()))
     ];
+    if let Some(document_fonts) = document_fonts {
+        family_names.extend_from_slice(document_fonts.fallback_family_names(font.family_name()));
+    }
 
     use font_kit::handle::Handle;
     use font_kit::error::SelectionError;
 
-    match sources.as_ref().borrow().font_source.select_best_match(&family_names, &properties) {
+    let handle = match embedded_font {
+        Some(handle) => Ok(handle),
+        None => sources.as_ref().borrow().font_source.select_best_match(&family_names, &properties),
+    };
+
+    match handle {
         Ok(result) => {
+            let metrics = result.load().ok().map(|loaded_font| super::FontMetrics::from_font_kit(&loaded_font.metrics(), font.size()));
+
             let owning_bytes;
             let owning_path;
 
             let d2_font = match result {
                 Handle::Memory { bytes, font_index: _ } => {
                     owning_bytes = bytes;
-                    mltg::Font::Memory(&owning_bytes, font.family_name)
+                    mltg::Font::Memory(&owning_bytes, font.family_name())
                 }
                 Handle::Path { path, font_index: _ } => {
                     owning_path = path;
-                    mltg::Font::File(&owning_path, font.family_name)
+                    mltg::Font::File(&owning_path, font.family_name())
                 }
             };
 
             let style = mltg::TextStyle{
-                weight: font.weight.into(),
+                weight: font.weight().into(),
+                style: if font.is_italic() { mltg::FontStyle::Italic } else { mltg::FontStyle::Normal },
                 ..Default::default()
             };
 
-            let format = factory.create_text_format(d2_font, mltg::font_point(font.size), Some(&style), None).expect("Failed to create text format");
+            let format = factory.create_text_format(d2_font, mltg::font_point(font.size()), Some(&style), None).expect("Failed to create text format");
+
+            // When the font file couldn't be inspected, use the line spacing
+            // of DirectWrite as the line height.
+            let metrics = metrics.unwrap_or_else(|| super::FontMetrics {
+                ascent: format.line_spacing().unwrap().height,
+                ..Default::default()
+            });
 
-            Ok((style, format))
+            Ok((style, format, metrics))
         }
         Err(e) => Err(match e {
             SelectionError::CannotAccessSource => super::FontSelectionError::CannotAccessResource,
@@ -258,15 +214,23 @@ fn load_font(sources: &Rc<RefCell<SharedCacheSources>>, factory: &mltg::Factory,
 }
 
 impl Win32PainterCache {
-    pub fn insert_font(&mut self, font_spec: super::FontSpecification, font: (mltg::TextStyle, mltg::TextFormat)) -> Rc<RefCell<CachedFont>> {
-        let (_style, format) = font;
-        match self.font_families.entry(Rc::from(font_spec.family_name)) {
+    /// Whether the fonts of the document of this cache can differ from the
+    /// installed fonts of the family, in which case they aren't shared with
+    /// the other caches.
+    fn has_document_font(&self, family_name: &str) -> bool {
+        self.document_fonts.as_ref().is_some_and(|fonts| fonts.provides(family_name))
+    }
+
+    pub fn insert_font(&mut self, font_spec: super::FontSpecification, font: (mltg::TextStyle, mltg::TextFormat, super::FontMetrics)) -> Rc<RefCell<CachedFont>> {
+        let (_style, format, metrics) = font;
+        match self.font_families.entry(Rc::from(font_spec.family_name())) {
             Entry::Occupied(o) => {
                 let family = o.get().clone();
                 let mut family = family.borrow_mut();
                 let cached_font = Rc::new(RefCell::new(CachedFont {
                     parent: o.get().clone(),
                     format,
+                    metrics,
                     text_layouts: HashMap::new(),
                 }));
 
@@ -280,7 +244,7 @@ impl Win32PainterCache {
                     }
                 ));
 
-                let cached_font = Rc::new(RefCell::new(CachedFont { parent: family.clone(), format, text_layouts: HashMap::new() }));
+                let cached_font = Rc::new(RefCell::new(CachedFont { parent: family.clone(), format, metrics, text_layouts: HashMap::new() }));
                 let previous = family.borrow_mut().types.insert(font_spec.into(), cached_font.clone());
                 assert!(previous.is_none(), "Loaded a new font for nothing!");
 
@@ -292,7 +256,7 @@ impl Win32PainterCache {
     }
 
     pub fn find_cached_font(&self, font: super::FontSpecification) -> Option<Rc<RefCell<CachedFont>>> {
-        match self.font_families.get(font.family_name) {
+        match self.font_families.get(font.family_name()) {
             Some(family) => {
                 family.as_ref().borrow().types.get(&font.into()).cloned()
             }
@@ -301,7 +265,7 @@ impl Win32PainterCache {
     }
 
     pub fn find_cached_font_closest(&self, font: super::FontSpecification) -> Option<Rc<RefCell<CachedFont>>> {
-        match self.font_families.get(font.family_name) {
+        match self.font_families.get(font.family_name()) {
             Some(family) => {
                 let font = Into::<FontVariantCacheKey>::into(font);
 
@@ -356,7 +320,7 @@ impl Win32TextCalculator {
             return Ok(cached_font);
         }
 
-        let loaded_font = load_font(&self.cache.sources, &self.factory, font_spec)?;
+        let loaded_font = load_font(&self.cache.sources, &self.factory, self.cache.document_fonts.as_deref(), font_spec)?;
         Ok(self.cache.insert_font(font_spec, loaded_font))
     }
 }
@@ -368,8 +332,24 @@ impl super::TextCalculator for Win32TextCalculator {
         Ok(self.factory.create_text_layout(text, &font.format, mltg::TextAlignment::Leading, None).unwrap().size().into())
     }
 
-    fn line_spacing(&mut self, font: super::FontSpecification) -> Result<f32, FontSelectionError> {
-        Ok(self.get_font(font)?.as_ref().borrow().format.line_spacing().unwrap().height)
+    fn font_metrics(&mut self, font: super::FontSpecification) -> Result<super::FontMetrics, FontSelectionError> {
+        Ok(self.get_font(font)?.as_ref().borrow().metrics)
+    }
+
+    fn set_document_fonts(&mut self, fonts: Option<Arc<DocumentFontSource>>) {
+        let current_id = self.cache.document_fonts.as_ref().map(|fonts| fonts.id());
+        if current_id == fonts.as_ref().map(|fonts| fonts.id()) {
+            return;
+        }
+
+        // The families of the previous and the new document can be loaded
+        // from other fonts now.
+        let provided_by = |fonts: &Option<Arc<DocumentFontSource>>, family_name: &str| {
+            fonts.as_ref().is_some_and(|fonts| fonts.provides(family_name))
+        };
+        let previous_fonts = self.cache.document_fonts.take();
+        self.cache.font_families.retain(|family_name, _| !provided_by(&previous_fonts, family_name) && !provided_by(&fonts, family_name));
+        self.cache.document_fonts = fonts;
     }
 }
 
@@ -470,6 +450,7 @@ impl Win32Painter {
                 Win32PainterCache {
                     sources: self.shared_cache_sources.clone(),
                     font_families: HashMap::new(),
+                    document_fonts: None,
                 }
             )
         }
@@ -539,6 +520,12 @@ impl super::Painter for Win32Painter {
             .expect("Failed to resize render target");
     }
 
+    fn paint_image(&mut self, _image: &super::Image, rect: Rect<f32>) {
+        // TODO: mltg is missing an API for creating a bitmap from pixels in
+        //       memory, so only the area of the image is painted for now.
+        self.commands.push(PaintCommand::Rect { brush: Brush::SolidColor(Color::from_rgb(0xDD, 0xDD, 0xDD)), rect })
+    }
+
     fn paint_rect(&mut self, brush: Brush, rect: Rect<f32>) {
         self.commands.push(PaintCommand::Rect { brush, rect })
     }
@@ -565,6 +552,10 @@ impl super::Painter for Win32Painter {
         size.into()
     }
 
+    fn register_document_fonts(&mut self, cache: super::PainterCache, fonts: Arc<DocumentFontSource>) {
+        self.ensure_cache_created(cache).document_fonts = Some(fonts);
+    }
+
     fn reset(&mut self) {
         self.commands.clear();
         self.current_cache = crate::gui::painter::PainterCache::UI;
@@ -577,11 +568,13 @@ impl super::Painter for Win32Painter {
         }
 
         //
-        // Search in other caches to clone reference.
+        // Search in other caches to clone reference, unless the fonts of the
+        // documents can differ.
         //
 
+        let has_document_font = self.current_cache().has_document_font(font_spec.family_name());
         let mut found_font = None;
-        for cache in self.caches.values() {
+        for cache in self.caches.values().filter(|cache| !has_document_font && !cache.has_document_font(font_spec.family_name())) {
             if let Some(font) = cache.find_cached_font(font_spec) {
                 found_font = Some(font);
                 break;
@@ -590,7 +583,7 @@ impl super::Painter for Win32Painter {
 
         if let Some(font) = found_font {
             // Add a reference from the other cache into the current cache.
-            self.current_cache().font_families.insert(Rc::from(font_spec.family_name), font.borrow().parent.clone());
+            self.current_cache().font_families.insert(Rc::from(font_spec.family_name()), font.borrow().parent.clone());
 
             self.selected_font = SelectOption::Some(font);
             return Ok(());
@@ -607,7 +600,8 @@ impl super::Painter for Win32Painter {
         // Load the font, since no cache contains this font.
         //
 
-        match load_font(&self.shared_cache_sources, &self.factory, font_spec) {
+        let document_fonts = self.current_cache().document_fonts.clone();
+        match load_font(&self.shared_cache_sources, &self.factory, document_fonts.as_deref(), font_spec) {
             Ok(font) => {
                 self.selected_font = SelectOption::Some(self.current_cache().insert_font(font_spec, font));
                 Ok(())
@@ -633,6 +627,7 @@ impl super::Painter for Win32Painter {
                             Win32PainterCache {
                                 sources: self.shared_cache_sources.clone(),
                                 font_families: HashMap::new(),
+                                document_fonts: None,
                             }
                         )
                     )
@@ -643,4 +638,20 @@ impl super::Painter for Win32Painter {
             }
         }
     }
+
+    fn text_calculator_factory(&self) -> Option<super::TextCalculatorFactory> {
+        Some(Arc::new(|| {
+            // DirectWrite needs COM on the thread the text calculator is used
+            // on, which is a no-op when it is initialized already.
+            unsafe {
+                _ = windows::Win32::System::Com::CoInitialize(None);
+            }
+
+            Box::new(Win32TextCalculator::new(Win32PainterCache {
+                sources: Rc::new(RefCell::new(SharedCacheSources::new())),
+                font_families: HashMap::new(),
+                document_fonts: None,
+            }))
+        }))
+    }
 }
@@ -1,6 +1,8 @@
 // Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
+use std::time::{Duration, Instant};
+
 use winit::event::{MouseButton, ElementState};
 
 use crate::user_settings::{SettingChangeSubscriber, SettingChangeNotification, SettingName};
@@ -33,6 +35,10 @@ const SCROLL_BAR_THUMB_CLICK_COLOR: Color = Color::from_rgb(0x60, 0x2B, 0xBC);
 
 const LINE_SPEED: f32 = 100.0;
 
+/// The time without scroll input after which the scroll gesture is considered
+/// finished, and the scroller snaps to the nearest page.
+const SNAP_DELAY: Duration = Duration::from_millis(200);
+
 /// The scroller is responsible for processing the user input (mouse scrolling,
 /// thumb dragging), provides a way to calculate a thumb position and size.
 pub struct Scroller {
@@ -48,6 +54,21 @@ pub struct Scroller {
     pub thumb_rect: Rect<f32>,
 
     pub interaction_state: InteractionState,
+
+    /// The multiplier of the scroll distance, from the user settings.
+    speed: f32,
+
+    /// Whether or not the scroll direction is inverted, from the user
+    /// settings.
+    inverted: bool,
+
+    /// Whether or not to snap to the nearest page after scrolling, from the
+    /// user settings.
+    snap_to_page: bool,
+
+    /// The time of the last scroll input of a gesture that should be snapped
+    /// to the nearest page when it's finished.
+    pending_snap_since: Option<Instant>,
 }
 
 impl Scroller {
@@ -66,13 +87,93 @@ impl Scroller {
             thumb_rect: Rect::<f32>::empty(),
 
             interaction_state: InteractionState::Default,
+
+            speed: 1.0,
+            inverted: false,
+
+            snap_to_page: false,
+            pending_snap_since: None,
+        }
+    }
+
+    /// Called on scroll input by the user, which (re)starts the snap delay.
+    fn on_scroll_input(&mut self) {
+        if self.snap_to_page {
+            self.pending_snap_since = Some(Instant::now());
+        }
+    }
+
+    /// Returns true once when the scroll gesture is finished and should be
+    /// snapped to the nearest page using [snap_to_nearest](Self::snap_to_nearest).
+    pub fn take_pending_snap(&mut self) -> bool {
+        if self.interaction_state == InteractionState::Pressed {
+            return false;
+        }
+
+        match self.pending_snap_since {
+            Some(since) if since.elapsed() >= SNAP_DELAY => {
+                self.pending_snap_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Scroll (animated) to the nearest of the given positions, which are
+    /// between 0.0 and 1.0 like [scroll_to](Self::scroll_to). Further scroll
+    /// input overrides the animation.
+    pub fn snap_to_nearest(&mut self, positions: &[f32]) {
+        if let Some(position) = find_nearest_position(positions, self.value.get()) {
+            self.value.change(position);
+        }
+    }
+
+    /// The factor to apply to the scroll distance of the mouse wheel and
+    /// touchpad, according to the user settings.
+    fn user_scroll_factor(&self) -> f32 {
+        if self.inverted {
+            -self.speed
+        } else {
+            self.speed
         }
     }
 
     /// Scroll the amount of lines specified by the `value` parameter.
     /// Returns whether or not the scroller has scrolled.
     pub fn scroll_lines(&mut self, value: f32) -> bool {
-        self.value.increase(-value / self.content_height * LINE_SPEED)
+        self.on_scroll_input();
+        self.value.increase(-value * self.user_scroll_factor() / self.content_height * LINE_SPEED)
+    }
+
+    /// Scroll the amount of pixels reported by a precise scrolling device
+    /// (e.g. a touchpad), which is positive when scrolling up like
+    /// [scroll_lines](Self::scroll_lines).
+    /// Returns whether or not the scroller has scrolled.
+    pub fn scroll_pixels(&mut self, value: f32) -> bool {
+        self.on_scroll_input();
+        self.scroll_by_pixels(-value * self.user_scroll_factor())
+    }
+
+    /// Scroll down by the amount of pixels of the content, or up if negative.
+    /// The position is clamped to the start and end of the content.
+    /// Returns whether or not the scroller has scrolled.
+    pub fn scroll_by_pixels(&mut self, pixels: f32) -> bool {
+        if self.content_height <= 0.0 {
+            return false;
+        }
+
+        self.value.increase_clamped(pixels / self.content_height)
+    }
+
+    /// Scroll to the given position, between 0.0 (the start of the content)
+    /// and 1.0 (the end), which is clamped when out of range.
+    pub fn scroll_to(&mut self, position: f32) {
+        self.value.change(position);
+    }
+
+    /// The height of the area the content is shown in, as of the last paint.
+    pub fn view_height(&self) -> f32 {
+        self.view_height
     }
 
     /// Draws the scroll bar track with the thumb.
@@ -162,7 +263,13 @@ impl Scroller {
                     InteractionState::Default
                 }
             },
-            ElementState::Released => InteractionState::Default,
+            ElementState::Released => {
+                if self.interaction_state == InteractionState::Pressed {
+                    self.on_scroll_input();
+                }
+
+                InteractionState::Default
+            }
         };
     }
 
@@ -178,10 +285,18 @@ impl Scroller {
     }
 }
 
+/// Find the position nearest to the given value.
+fn find_nearest_position(positions: &[f32], value: f32) -> Option<f32> {
+    positions.iter()
+        .copied()
+        .min_by(|a, b| (a - value).abs().total_cmp(&(b - value).abs()))
+}
+
 impl super::animate::Animated for Scroller {
     fn has_running_animation(&self) -> bool {
         // TODO state changes like is_pressed and is_hovered
         self.value.has_running_animation() || self.thumb_height.has_running_animation()
+            || self.pending_snap_since.is_some()
     }
 }
 
@@ -194,13 +309,35 @@ impl SettingChangeSubscriber for Scroller {
                 EasingFunction::DisabledAnimations
             }
         );
+
+        self.speed = settings.setting_scroll_speed();
+        self.inverted = settings.setting_invert_scroll_direction();
+
+        self.snap_to_page = settings.setting_snap_to_page();
+        if !self.snap_to_page {
+            self.pending_snap_since = None;
+        }
     }
 
     fn setting_changed(&mut self, notification: &SettingChangeNotification) {
-        if notification.setting_name != SettingName::EnableAnimations {
-            return;
+        match notification.setting_name {
+            SettingName::EnableAnimations | SettingName::ScrollSpeed | SettingName::InvertScrollDirection | SettingName::SnapToPage => {
+                self.settings_loaded(notification.settings);
+            }
+            _ => (),
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        self.settings_loaded(notification.settings);
+    #[test]
+    fn nearest_position() {
+        assert_eq!(find_nearest_position(&[], 0.5), None);
+        assert_eq!(find_nearest_position(&[0.0, 0.3, 0.6, 0.9], 0.4), Some(0.3));
+        assert_eq!(find_nearest_position(&[0.0, 0.3, 0.6, 0.9], 0.5), Some(0.6));
+        assert_eq!(find_nearest_position(&[0.0, 0.3, 0.6, 0.9], 1.0), Some(0.9));
     }
 }
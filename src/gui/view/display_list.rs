@@ -0,0 +1,130 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The display list of a document, which separates painting from the layout:
+// the layout positions the nodes on their pages, after which the display list
+// tells which nodes to paint on every page, without walking the whole tree.
+
+use std::ops::Range;
+
+use crate::wp::{editing, Node};
+
+/// The nodes painted on every page in paint order, by their path from the
+/// root node. The positions of the nodes are in points relative to their page,
+/// so the list stays valid when the zoom level changes.
+#[derive(Debug, Default)]
+pub struct DisplayList {
+    pages: Vec<Vec<Box<[usize]>>>,
+}
+
+impl DisplayList {
+    /// Builds the display list of the first `page_count` pages.
+    pub fn build(root_node: &Node, page_count: usize) -> Self {
+        let mut list = Self { pages: vec![Vec::new(); page_count] };
+        list.collect(root_node, &mut Vec::new(), 0..page_count);
+        list
+    }
+
+    /// The nodes to paint on the page, in document order.
+    pub fn nodes_on_page<'a>(&'a self, root_node: &'a Node, page: usize) -> impl Iterator<Item = &'a Node> + 'a {
+        self.pages.get(page)
+            .into_iter()
+            .flatten()
+            .filter_map(move |path| editing::node_at_path(root_node, path))
+    }
+
+    /// Builds the lists of the `pages` again, after the nodes on them were
+    /// laid out again, e.g. because their text was edited. The other pages
+    /// are kept, except for those after the last page of the document.
+    pub fn invalidate_pages(&mut self, root_node: &Node, pages: Range<usize>) {
+        let page_count = root_node.page_last + 1;
+        self.pages.resize_with(page_count, Vec::new);
+
+        let pages = pages.start.min(page_count)..pages.end.min(page_count);
+        for page in pages.clone() {
+            self.pages[page].clear();
+        }
+
+        self.collect(root_node, &mut Vec::new(), pages);
+    }
+
+    fn collect(&mut self, node: &Node, path: &mut Vec<usize>, pages: Range<usize>) {
+        // The children of a node don't start on a page before it.
+        if node.page_first >= pages.end {
+            return;
+        }
+
+        if pages.contains(&node.page_first) {
+            self.pages[node.page_first].push(path.as_slice().into());
+        }
+
+        for (index, child) in node.children.iter().enumerate() {
+            path.push(index);
+            self.collect(child, path, pages.clone());
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wp::{NodeData, Paragraph};
+
+    fn paragraph(page_first: usize, page_last: usize, child_count: usize) -> Node {
+        let mut node = Node::new(NodeData::Paragraph(Paragraph::default()));
+        node.page_first = page_first;
+        node.page_last = page_last;
+        for _ in 0..child_count {
+            let mut child = Node::new(NodeData::Paragraph(Paragraph::default()));
+            child.page_first = page_last;
+            child.page_last = page_last;
+            node.children.push(child);
+        }
+        node
+    }
+
+    fn document(paragraphs: Vec<Node>) -> Node {
+        let mut root = Node::new(NodeData::Document);
+        root.page_last = paragraphs.iter().map(|paragraph| paragraph.page_last).max().unwrap_or(0);
+        root.children = paragraphs;
+        root
+    }
+
+    #[test]
+    fn nodes_are_listed_on_the_page_they_start_on() {
+        let root = document(vec![paragraph(0, 0, 2), paragraph(0, 1, 1)]);
+        let list = DisplayList::build(&root, 2);
+
+        assert_eq!(list.pages.len(), 2);
+
+        // The root, both paragraphs and the children of the first.
+        assert_eq!(list.nodes_on_page(&root, 0).count(), 5);
+        assert_eq!(list.nodes_on_page(&root, 1).count(), 1);
+        assert_eq!(list.nodes_on_page(&root, 2).count(), 0);
+    }
+
+    #[test]
+    fn building_fewer_pages_leaves_out_the_later_nodes() {
+        let root = document(vec![paragraph(0, 0, 1), paragraph(1, 1, 3)]);
+        let list = DisplayList::build(&root, 1);
+
+        assert_eq!(list.pages.len(), 1);
+        assert_eq!(list.nodes_on_page(&root, 0).count(), 3);
+    }
+
+    #[test]
+    fn invalidated_pages_are_collected_again() {
+        let mut root = document(vec![paragraph(0, 0, 1), paragraph(1, 1, 1)]);
+        let mut list = DisplayList::build(&root, 2);
+
+        root.children[1] = paragraph(1, 2, 4);
+        root.page_last = 2;
+        list.invalidate_pages(&root, 1..3);
+
+        assert_eq!(list.pages.len(), 3);
+        assert_eq!(list.nodes_on_page(&root, 0).count(), 3);
+        assert_eq!(list.nodes_on_page(&root, 1).count(), 1);
+        assert_eq!(list.nodes_on_page(&root, 2).count(), 4);
+    }
+}
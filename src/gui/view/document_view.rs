@@ -1,31 +1,49 @@
 // Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
-use roxmltree as xml;
+use std::{
+    ops::Range,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use uffice_lib::{profiling::Profiler, profile_expr};
-use winit::window::CursorIcon;
+use unicode_segmentation::UnicodeSegmentation;
+use winit::{event::VirtualKeyCode, window::CursorIcon};
 
 use crate::{
-    drawing_ml,
     wp::{
         self,
-        numbering::NumberingManager,
+        comments::{Comment, CommentRange},
+        diagnostics::Diagnostic,
+        editing::{self, ParagraphPosition},
+        history::{Command, CommandGroup, DeleteText, History, InsertText},
+        revisions::RevisionView,
+        save::SaveError,
+        search::TextIndex,
+        selection::{self, Selection, TextPosition},
+        statistics::DocumentStatistics,
         Document,
         Node,
     },
     word_processing::{
-        DocumentResult,
+        load::{self, PartCache},
+        LoadObserver,
         self,
     },
-    application::load_archive_file_to_string,
-    relationships::Relationships,
-    serialize::FromXmlStandalone,
-    style::StyleManager,
-    gui::{painter::{FontSpecification, TextCalculator}, Rect, Size, Position},
+    error::Error,
+    fonts::DocumentFontSource,
+    style::{BorderProperties, HexColor},
+    gui::{painter::{null::{NullPainter, PaintCommand}, Painter, TextCalculator}, Brush, Rect, Size, Position},
+    print::{self, PrintPage},
+    text_settings::UnderlineStyle,
 };
 
 use super::{
+    display_list::DisplayList,
+    EditInput,
+    SearchInput,
+    SearchResults,
     ViewData,
     ViewImpl,
 };
@@ -37,6 +55,71 @@ pub const VERTICAL_PAGE_MARGIN: f32 = 20.0;
 /// The gaps between the pages.
 pub const VERTICAL_PAGE_GAP: f32 = 30.0;
 
+/// The distance between a change bar and the left edge of the text, in points.
+const CHANGE_BAR_DISTANCE: f32 = 10.0;
+
+/// The width of a change bar, in points.
+const CHANGE_BAR_WIDTH: f32 = 1.0;
+
+/// The width of the line between the columns of a section, in points.
+const COLUMN_SEPARATOR_WIDTH: f32 = 0.5;
+
+/// The color of the box painted instead of a picture that couldn't be found
+/// in the package or couldn't be decoded.
+const MISSING_PICTURE_PLACEHOLDER_COLOR: crate::gui::Color = crate::gui::Color::from_rgb(0xDD, 0xDD, 0xDD);
+
+/// The width of the caret, in pixels.
+const CARET_WIDTH: f32 = 1.0;
+
+/// How long the caret is visible, and then invisible, when blinking.
+pub const CARET_BLINK_INTERVAL: Duration = Duration::from_millis(530);
+
+/// The distance between the baseline and the top of an underline, relative to
+/// the font size.
+const UNDERLINE_OFFSET: f32 = 0.1;
+
+/// The distance between the baseline and the top of the line striking
+/// through deleted text, relative to the font size.
+const STRIKETHROUGH_OFFSET: f32 = 0.3;
+
+/// The thickness of a single underline, relative to the font size. Heavy
+/// underlines are twice as thick.
+const UNDERLINE_THICKNESS: f32 = 0.05;
+
+/// The lengths of the dashes and the gaps between them of the dashed and
+/// dotted underlines, in thicknesses of the line.
+const DOTTED_PATTERN: [f32; 2] = [1.0, 1.0];
+const DASH_PATTERN: [f32; 2] = [4.0, 2.0];
+const DASH_LONG_PATTERN: [f32; 2] = [8.0, 3.0];
+const DOT_DASH_PATTERN: [f32; 4] = [4.0, 2.0, 1.0, 2.0];
+const DOT_DOT_DASH_PATTERN: [f32; 6] = [4.0, 2.0, 1.0, 2.0, 1.0, 2.0];
+
+/// The vertical offsets of the steps of a wavy underline, in thicknesses of
+/// the line. The steps are a thickness and a half wide.
+const WAVE_OFFSETS: [f32; 4] = [0.0, 0.5, 1.0, 0.5];
+
+/// The characters winit sends for the backspace and delete keys.
+const BACKSPACE: char = '\u{8}';
+const DELETE: char = '\u{7f}';
+
+/// The color painted behind the text selected by the user.
+const SELECTION_COLOR: crate::gui::Color = crate::gui::Color::from_rgb(0xB4, 0xD5, 0xFE);
+
+/// The color painted behind the text found by searching.
+const SEARCH_MATCH_COLOR: crate::gui::Color = crate::gui::Color::from_rgb(0xFF, 0xEE, 0x80);
+
+/// The color painted behind the match of the search the user is at.
+const CURRENT_SEARCH_MATCH_COLOR: crate::gui::Color = crate::gui::Color::from_rgb(0xFF, 0xA8, 0x40);
+
+/// The color painted behind the text that is commented on, and of the marker
+/// at the end of that text.
+const COMMENT_HIGHLIGHT_COLOR: crate::gui::Color = crate::gui::Color::from_rgb(0xFC, 0xE4, 0xEC);
+const COMMENT_MARKER_COLOR: crate::gui::Color = crate::gui::Color::from_rgb(0xE9, 0x1E, 0x63);
+
+/// The width and height of the marker at the end of a comment range, in
+/// points.
+const COMMENT_MARKER_SIZE: f32 = 4.0;
+
 #[derive(Debug)]
 pub struct DocumentView {
     #[allow(dead_code)]
@@ -45,175 +128,1107 @@ pub struct DocumentView {
     document: Option<Document>,
     root_node: Option<Node>,
 
+    /// The nodes to paint on every page, of which the pages are collected
+    /// again when their nodes are laid out again after an edit.
+    display_list: DisplayList,
+
+    /// The recorded commands of the pages that were painted, indexed by page
+    /// number, which are forgotten when the nodes on a page change.
+    page_caches: Vec<Option<PageCache>>,
+
     page_rects: Vec<Rect<f32>>,
+
+    /// The zoom factor of the last paint, which together with the
+    /// `page_rects` converts the positions of the nodes to the screen.
+    zoom: f32,
+
+    /// The rect the document was painted in the last time.
+    content_rect: Rect<f32>,
+
+    /// The font to use when a font of the document isn't available.
+    fallback_font: String,
+
+    diagnostics: Vec<Diagnostic>,
+
+    /// The text selected by the user. When nothing is selected, the focus of
+    /// the selection is the caret.
+    selection: Option<Selection>,
+
+    /// When the caret was last moved, since it blinks from then on.
+    caret_blink_start: Instant,
+
+    /// The edits that can be undone and redone.
+    history: History,
+
+    /// The text of the document for searching, which is indexed when the user
+    /// searches, and again when the text was edited since.
+    text_index: Option<TextIndex>,
+
+    /// The last search of the user, of which the matches are highlighted.
+    search: Option<Search>,
+
+    /// The text describing what the mouse hovers over, like the author of a
+    /// tracked change.
+    hover_text: Option<String>,
 }
 
-fn draw_document(archive_path: &str, text_calculator: &mut dyn TextCalculator, progress_sender: &dyn Fn(f32)) -> DocumentResult {
-    let mut profiler = Profiler::new(String::from("Document Rendering"));
+/// The text the user searched for, and where it was found.
+#[derive(Debug)]
+struct Search {
+    query: String,
 
-    let archive_file = profile_expr!(profiler, "Open Archive", std::fs::File::open(archive_path)
-            .expect("Failed to open specified file"));
+    /// The occurrences of the query in document order, as the text they
+    /// would select.
+    matches: Vec<Selection>,
 
-    let mut archive = profile_expr!(profiler, "Read Archive", zip::ZipArchive::new(archive_file)
-            .expect("Failed to read ZIP archive"));
+    /// The index of the match the user is at.
+    current_match: Option<usize>,
+}
 
-    let document_relationships;
-    {
-        let _frame = profiler.frame(String::from("Document Relationships"));
+/// Finds the page of which the largest part is visible inside the content
+/// rect, which is the page the user is currently reading.
+pub fn find_most_visible_page(page_rects: &[Rect<f32>], content_rect: Rect<f32>) -> Option<usize> {
+    page_rects.iter()
+        .map(|page_rect| {
+            let top = page_rect.top.max(content_rect.top);
+            let bottom = page_rect.bottom.min(content_rect.bottom);
+            (bottom - top).max(0.0)
+        })
+        .enumerate()
+        .filter(|(_, visible_height)| *visible_height > 0.0)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
 
-        let txt = load_archive_file_to_string(&mut archive, "word/_rels/document.xml.rels")
-                .expect("Document.xml.rels missing, assuming this is not a DOCX file.");
-        if let Ok(document) = xml::Document::parse(&txt) {
-            document_relationships = Relationships::load_xml(&document, &mut archive).unwrap();
-        } else {
-            println!("[Relationships] (word/_rels/document.xml.rels) Error!");
-            document_relationships = Relationships::empty();
+/// The pages of which a part is inside the content rect, given the page rects
+/// from the top to the bottom of the document.
+fn visible_pages(page_rects: &[Rect<f32>], content_rect: Rect<f32>) -> Range<usize> {
+    let start = page_rects.partition_point(|page_rect| page_rect.bottom <= content_rect.top);
+    let end = page_rects.partition_point(|page_rect| page_rect.top < content_rect.bottom);
+    start..end.max(start)
+}
+
+/// Maps a point in document space, which is in points relative to the
+/// top-left corner of a page, to the screen, where `page_origin` is the
+/// top-left corner of the painted page.
+fn page_point_to_screen(page_origin: Position<f32>, point: Position<f32>, zoom: f32) -> Position<f32> {
+    Position::new(
+        page_origin.x() + point.x() * zoom,
+        page_origin.y() + point.y() * zoom,
+    )
+}
+
+/// The rect of the node on the screen, where `page_origin` is the top-left
+/// corner of the page it is on.
+fn node_rect(node: &Node, page_origin: Position<f32>, zoom: f32) -> Rect<f32> {
+    Rect::from_position_and_size(page_point_to_screen(page_origin, node.position, zoom), node.size * zoom)
+}
+
+/// Paints the lines halfway between the columns of the section, from the top
+/// to the bottom margin of the page.
+fn paint_column_separators(painter: &mut dyn Painter, section: &wp::Section, page_origin: Position<f32>, zoom: f32) {
+    if !section.column_separator {
+        return;
+    }
+
+    let page_settings = &section.page_settings;
+    let top = page_settings.margins.top.get_pts();
+    let bottom = page_settings.size.height().get_pts() - page_settings.margins.bottom.get_pts();
+
+    for columns in section.columns.windows(2) {
+        let left = (columns[0].end + columns[1].start - COLUMN_SEPARATOR_WIDTH) / 2.0;
+        let position = page_point_to_screen(page_origin, Position::new(left, top), zoom);
+        painter.paint_rect(Brush::SolidColor(crate::gui::Color::BLACK),
+            Rect::from_position_and_size(position, Size::new(COLUMN_SEPARATOR_WIDTH, bottom - top) * zoom));
+    }
+}
+
+/// The inverse of [page_point_to_screen].
+fn screen_to_page_point(page_origin: Position<f32>, position: Position<f32>, zoom: f32) -> Position<f32> {
+    Position::new(
+        (position.x() - page_origin.x()) / zoom,
+        (position.y() - page_origin.y()) / zoom,
+    )
+}
+
+/// Finds the page containing the screen `position`, and the point on that
+/// page in document space.
+fn find_page_at_point(page_rects: &[Rect<f32>], zoom: f32, position: Position<f32>) -> Option<(usize, Position<f32>)> {
+    let index = page_rects.iter().position(|page_rect| page_rect.is_inside_inclusive(position))?;
+    Some((index, screen_to_page_point(page_rects[index].position(), position, zoom)))
+}
+
+/// Paints the text part character by character, at the positions of the
+/// layout, which include the character spacing of the run and the spacing of
+/// distributed and full justification.
+fn paint_spaced_text(painter: &mut dyn Painter, brush: Brush, position: Position<f32>, part: &wp::TextPart, height: f32, zoom: f32) {
+    for (index, grapheme) in part.text.graphemes(true).enumerate().take(part.grapheme_ends.len()) {
+        let left = part.grapheme_boundary(index);
+        let width = (part.grapheme_ends[index] - left - part.character_spacing).max(0.0);
+        painter.paint_text(brush, Position::new(position.x() + left * zoom, position.y()), grapheme,
+            Some(Size::new(width * zoom, height * zoom)));
+    }
+}
+
+/// Paints the backgrounds and borders of the cells of the table, before the
+/// contents of the cells are painted on top. The backgrounds are painted first,
+/// so they don't cover the borders of their neighbours.
+fn paint_table(painter: &mut dyn Painter, table: &Node, position: Position<f32>, zoom: f32) {
+    let cells = table.children.iter()
+        .flat_map(|row| row.children.iter())
+        .filter_map(|cell| match &cell.data {
+            wp::NodeData::TableCell(properties) => {
+                let cell_position = Position::new(
+                    position.x() + (cell.position.x() - table.position.x()) * zoom,
+                    position.y() + (cell.position.y() - table.position.y()) * zoom,
+                );
+                Some((properties, Rect::from_position_and_size(cell_position, cell.size * zoom)))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (properties, rect) in &cells {
+        if let Some(color) = properties.shading {
+            painter.paint_rect(Brush::SolidColor(color), *rect);
         }
     }
 
-    let numbering_manager = {
-        let _frame = profiler.frame(String::from("Numbering Definitions"));
+    for (properties, rect) in &cells {
+        let borders = &properties.borders;
 
-        if let Some(numbering_document_text) = load_archive_file_to_string(&mut archive, "word/numbering.xml") {
-            let numbering_document = xml::Document::parse(&numbering_document_text)
-                .expect("Failed to parse numbering document");
-            NumberingManager::from_xml(&numbering_document)
-        } else {
-            NumberingManager::new()
+        // The borders are centered on the edges of the cell.
+        if let Some(border) = visible_border(borders.top) {
+            let half = border_width(&border, zoom) / 2.0;
+            painter.paint_rect(border_brush(&border), Rect::from_positions(rect.left - half, rect.right + half, rect.top - half, rect.top + half));
+        }
+        if let Some(border) = visible_border(borders.bottom) {
+            let half = border_width(&border, zoom) / 2.0;
+            painter.paint_rect(border_brush(&border), Rect::from_positions(rect.left - half, rect.right + half, rect.bottom - half, rect.bottom + half));
+        }
+        if let Some(border) = visible_border(borders.left) {
+            let half = border_width(&border, zoom) / 2.0;
+            painter.paint_rect(border_brush(&border), Rect::from_positions(rect.left - half, rect.left + half, rect.top - half, rect.bottom + half));
+        }
+        if let Some(border) = visible_border(borders.right) {
+            let half = border_width(&border, zoom) / 2.0;
+            painter.paint_rect(border_brush(&border), Rect::from_positions(rect.right - half, rect.right + half, rect.top - half, rect.bottom + half));
         }
+    }
+}
+
+/// Paints the borders of the paragraph on the inside of its border rect, so
+/// that the spacing of the borders is kept between them and the text.
+fn paint_paragraph_borders(painter: &mut dyn Painter, paragraph: &wp::Paragraph, position: Position<f32>, zoom: f32) {
+    let borders = &paragraph.borders;
+    let rect = Rect::from_positions(
+        position.x() + paragraph.border_rect.left * zoom,
+        position.x() + paragraph.border_rect.right * zoom,
+        position.y() + paragraph.border_rect.top * zoom,
+        position.y() + paragraph.border_rect.bottom * zoom,
+    );
+
+    // A paragraph continuing on the next page isn't enclosed on this one.
+    if rect.bottom <= rect.top {
+        return;
+    }
+
+    if let Some(border) = visible_border(borders.top) {
+        let width = border_width(&border, zoom);
+        painter.paint_rect(border_brush(&border), Rect::from_positions(rect.left, rect.right, rect.top, rect.top + width));
+    }
+    if let Some(border) = visible_border(borders.bottom) {
+        let width = border_width(&border, zoom);
+        painter.paint_rect(border_brush(&border), Rect::from_positions(rect.left, rect.right, rect.bottom - width, rect.bottom));
+    }
+    if let Some(border) = visible_border(borders.left) {
+        let width = border_width(&border, zoom);
+        painter.paint_rect(border_brush(&border), Rect::from_positions(rect.left, rect.left + width, rect.top, rect.bottom));
+    }
+    if let Some(border) = visible_border(borders.right) {
+        let width = border_width(&border, zoom);
+        painter.paint_rect(border_brush(&border), Rect::from_positions(rect.right - width, rect.right, rect.top, rect.bottom));
+    }
+}
+
+fn visible_border(border: Option<BorderProperties>) -> Option<BorderProperties> {
+    border.filter(wp::table::is_border_visible)
+}
+
+/// The width of the border on the screen, which is at least a pixel so that
+/// hairlines don't disappear when zoomed out.
+fn border_width(border: &BorderProperties, zoom: f32) -> f32 {
+    (border.width.get_pts() * zoom).max(1.0)
+}
+
+fn border_brush(border: &BorderProperties) -> Brush {
+    match border.color {
+        HexColor::Auto => Brush::SolidColor(crate::gui::Color::BLACK),
+        HexColor::Color(color) => Brush::SolidColor(color),
+    }
+}
+
+/// Paints a single node at the given position on the screen, where
+/// `page_left` is the left edge of its page.
+fn paint_node(painter: &mut dyn Painter, node: &Node, position: Position<f32>, page_left: f32, margin_left: f32,
+        zoom: f32, fallback_font: &str) {
+    match &node.data {
+        wp::NodeData::Paragraph(paragraph) => {
+            if paragraph.has_revisions {
+                let change_bar_position = Position::new(
+                    page_left + (margin_left - CHANGE_BAR_DISTANCE) * zoom,
+                    position.y()
+                );
+                let change_bar_size = Size::new(CHANGE_BAR_WIDTH * zoom, node.size.height() * zoom);
+                painter.paint_rect(crate::gui::Brush::SolidColor(crate::gui::Color::BLACK),
+                    Rect::from_position_and_size(change_bar_position, change_bar_size));
+            }
+
+            paint_paragraph_borders(painter, paragraph, position, zoom);
+        }
+        wp::NodeData::Table { .. } => paint_table(painter, node, position, zoom),
+        wp::NodeData::Drawing(drawing) => {
+            let rect = Rect::from_position_and_size(position, node.size * zoom);
+            if let Some(image) = drawing.image() {
+                painter.paint_image(image, rect);
+            } else if drawing.is_picture() {
+                painter.paint_rect(crate::gui::Brush::SolidColor(MISSING_PICTURE_PLACEHOLDER_COLOR), rect);
+            }
+        }
+        wp::NodeData::TextPart(part) => {
+            // The layout stored the font it used in the text part.
+            let font_family_name: &str = match &node.text_settings.font {
+                Some(font) => font,
+                None => fallback_font,
+            };
+
+            if painter.select_font(node.text_settings.font_specification(font_family_name)).is_err() {
+                _ = painter.select_font(node.text_settings.font_specification(fallback_font));
+            }
+
+            if let Some(selected_graphemes) = &node.interaction_states.selected_graphemes {
+                let selection_rect = Rect::from_positions(
+                    position.x() + part.grapheme_boundary(selected_graphemes.start) * zoom,
+                    position.x() + part.grapheme_boundary(selected_graphemes.end) * zoom,
+                    position.y(),
+                    position.y() + node.size.height() * zoom,
+                );
+                painter.paint_rect(Brush::SolidColor(SELECTION_COLOR), selection_rect);
+            }
+
+            if part.character_spacing != 0.0 || part.word_spacing != 0.0 {
+                paint_spaced_text(painter, node.text_settings.brush(), position, part, node.size.height(), zoom);
+            } else {
+                //let size =
+                painter.paint_text(node.text_settings.brush(), position, &part.text, Some(node.size * zoom));
+            }
+
+            paint_underline(painter, node, part, position, zoom);
+            //println!("Text \"{}\" for size {} and dims {:?}", part.text, text_size, size);
+        }
+        _ => ()
+    }
+}
+
+/// Paints the underline of the text part, if its text is underlined.
+fn paint_underline(painter: &mut dyn Painter, node: &Node, part: &wp::TextPart, position: Position<f32>, zoom: f32) {
+    let Some(style) = node.text_settings.underline_style() else {
+        return;
     };
 
-    let theme_settings = {
-        let _frame = profiler.frame(String::from("DrawingML Style Settings"));
+    let font_size = node.text_settings.font_size_pts();
+    let mut thickness = font_size * UNDERLINE_THICKNESS * zoom;
+    if style.is_heavy() {
+        thickness *= 2.0;
+    }
 
-        if let Some(style_document_text) = load_archive_file_to_string(&mut archive, "word/theme/theme1.xml") {
-            let style_document = xml::Document::parse(&style_document_text)
-                .expect("Failed to parse DrawingML Style Settings XML document");
-            drawing_ml::style::StyleSettings::from_xml(&style_document.root()).unwrap()
-        } else {
-            Default::default()
+    let top = position.y() + (part.ascent + font_size * UNDERLINE_OFFSET) * zoom;
+    let spans = match style {
+        UnderlineStyle::Words => word_spans(part),
+        _ => vec![(0.0, node.size.width())],
+    };
+
+    let brush = node.text_settings.underline_brush();
+    for (start, end) in spans {
+        for rect in underline_rects(style, position.x() + start * zoom, position.x() + end * zoom, top, thickness) {
+            painter.paint_rect(brush, rect);
+        }
+    }
+}
+
+/// The horizontal spans of the words of the text part, relative to the start
+/// of the text part, which are underlined by [UnderlineStyle::Words].
+fn word_spans(part: &wp::TextPart) -> Vec<(f32, f32)> {
+    let mut spans: Vec<(f32, f32)> = Vec::new();
+    let mut previous_was_word = false;
+
+    for (index, grapheme) in part.text.graphemes(true).enumerate().take(part.grapheme_ends.len()) {
+        let is_word = !grapheme.trim().is_empty();
+        if is_word && previous_was_word {
+            spans.last_mut().unwrap().1 = part.grapheme_ends[index];
+        } else if is_word {
+            spans.push((part.grapheme_boundary(index), part.grapheme_ends[index]));
         }
+        previous_was_word = is_word;
+    }
+
+    spans
+}
+
+/// The rects to paint for an underline of the given style, from `left` to
+/// `right`, of which the line starts at `top`.
+fn underline_rects(style: UnderlineStyle, left: f32, right: f32, top: f32, thickness: f32) -> Vec<Rect<f32>> {
+    if thickness <= 0.0 || left >= right {
+        return Vec::new();
+    }
+
+    match style {
+        UnderlineStyle::None => Vec::new(),
+        UnderlineStyle::Single | UnderlineStyle::Words | UnderlineStyle::Thick =>
+            vec![Rect::from_positions(left, right, top, top + thickness)],
+        UnderlineStyle::Double => vec![
+            Rect::from_positions(left, right, top, top + thickness),
+            Rect::from_positions(left, right, top + thickness * 2.0, top + thickness * 3.0),
+        ],
+        UnderlineStyle::Dotted | UnderlineStyle::DottedHeavy => dashed_rects(&DOTTED_PATTERN, left, right, top, thickness),
+        UnderlineStyle::Dash | UnderlineStyle::DashedHeavy => dashed_rects(&DASH_PATTERN, left, right, top, thickness),
+        UnderlineStyle::DashLong | UnderlineStyle::DashLongHeavy => dashed_rects(&DASH_LONG_PATTERN, left, right, top, thickness),
+        UnderlineStyle::DotDash | UnderlineStyle::DashDotHeavy => dashed_rects(&DOT_DASH_PATTERN, left, right, top, thickness),
+        UnderlineStyle::DotDotDash | UnderlineStyle::DashDotDotHeavy => dashed_rects(&DOT_DOT_DASH_PATTERN, left, right, top, thickness),
+        UnderlineStyle::Wave | UnderlineStyle::WavyHeavy => wave_rects(left, right, top, thickness),
+        UnderlineStyle::WavyDouble => {
+            let mut rects = wave_rects(left, right, top, thickness);
+            rects.extend(wave_rects(left, right, top + thickness * 3.0, thickness));
+            rects
+        }
+    }
+}
+
+/// The dashes of a dashed line, where the `pattern` alternates between the
+/// lengths of the dashes and the gaps.
+fn dashed_rects(pattern: &[f32], left: f32, right: f32, top: f32, thickness: f32) -> Vec<Rect<f32>> {
+    let mut rects = Vec::new();
+    let mut x = left;
+    for (index, length) in pattern.iter().cycle().enumerate() {
+        if x >= right {
+            break;
+        }
+
+        let end = (x + length * thickness).min(right);
+        if index % 2 == 0 {
+            rects.push(Rect::from_positions(x, end, top, top + thickness));
+        }
+        x = end;
+    }
+    rects
+}
+
+/// A wavy line, approximated by steps going up and down.
+fn wave_rects(left: f32, right: f32, top: f32, thickness: f32) -> Vec<Rect<f32>> {
+    let mut rects = Vec::new();
+    let mut x = left;
+    for offset in WAVE_OFFSETS.iter().cycle() {
+        if x >= right {
+            break;
+        }
+
+        let end = (x + thickness * 1.5).min(right);
+        let step_top = top + offset * thickness;
+        rects.push(Rect::from_positions(x, end, step_top, step_top + thickness));
+        x = end;
+    }
+    rects
+}
+
+/// Hit tests the node and its descendants, and invokes the `callback` for the
+/// interactable nodes (text parts and the hyperlinks and revisions containing
+/// them) in the hit path, from innermost to outermost. Returns the position
+/// under the mouse in the text part that was hit, where `text_part` counts the
+/// text parts that were visited, in document order.
+fn check_interactable_node(node: &mut Node, mouse_position: Position<f32>, page_rects: &[Rect<f32>], zoom: f32,
+        text_part: &mut usize, callback: &mut dyn FnMut(&mut Node, Position<f32>)) -> Option<TextPosition> {
+    let hit = match &node.data {
+        wp::NodeData::TextPart(part) => {
+            let index = *text_part;
+            *text_part += 1;
+
+            node.bounding_box(page_rects, zoom)
+                .filter(|rect| rect.is_inside_inclusive(mouse_position))
+                .map(|rect| TextPosition {
+                    text_part: index,
+                    offset: part.nearest_grapheme_boundary((mouse_position.x() - rect.left) / zoom),
+                })
+        }
+        _ => node.children.iter_mut()
+                .find_map(|child| check_interactable_node(child, mouse_position, page_rects, zoom, text_part, callback)),
+    };
+
+    if hit.is_some() && matches!(node.data, wp::NodeData::TextPart(..) | wp::NodeData::Hyperlink(..) | wp::NodeData::Revision(..)) {
+        callback(node, mouse_position);
+    }
+
+    hit
+}
+
+/// The rects on the screen of the text covered by the `selection`, one for
+/// each text part it spans.
+fn selection_rects(text_parts: &[&Node], selection: &Selection, page_rects: &[Rect<f32>], zoom: f32) -> Vec<Rect<f32>> {
+    (selection.start().text_part..=selection.end().text_part)
+        .filter_map(|index| {
+            let node = text_parts.get(index)?;
+            let wp::NodeData::TextPart(part) = &node.data else {
+                return None;
+            };
+
+            let graphemes = selection.selected_graphemes(index, part.grapheme_ends.len())?;
+            let rect = node.bounding_box(page_rects, zoom)?;
+
+            // The selected graphemes of right-to-left text end at the left.
+            let start = part.visual_grapheme_boundary(graphemes.start);
+            let end = part.visual_grapheme_boundary(graphemes.end);
+            Some(Rect::from_positions(
+                rect.left + start.min(end) * zoom,
+                rect.left + start.max(end) * zoom,
+                rect.top,
+                rect.bottom,
+            ))
+        })
+        .collect()
+}
+
+/// Paints the backgrounds of the matches of the search, before the text is
+/// painted over them.
+fn paint_search_highlights(painter: &mut dyn Painter, root_node: &Node, search: &Search, page_rects: &[Rect<f32>], zoom: f32) {
+    let text_parts = root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)));
+    for (index, search_match) in search.matches.iter().enumerate() {
+        let color = if search.current_match == Some(index) { CURRENT_SEARCH_MATCH_COLOR } else { SEARCH_MATCH_COLOR };
+        for rect in selection_rects(&text_parts, search_match, page_rects, zoom) {
+            painter.paint_rect(Brush::SolidColor(color), rect);
+        }
+    }
+}
+
+/// Paints the backgrounds of the text on the `page` that the comments are
+/// about, with a marker at the end of every range, before the text is painted
+/// over them.
+fn paint_comment_highlights(painter: &mut dyn Painter, ranges: &[CommentRange], page: usize, page_origin: Position<f32>, zoom: f32) {
+    for range in ranges {
+        for node in range.text_parts.iter().filter(|node| node.page_first == page) {
+            painter.paint_rect(Brush::SolidColor(COMMENT_HIGHLIGHT_COLOR), node_rect(node, page_origin, zoom));
+        }
+
+        // An empty range is marked where it starts.
+        let end = range.text_parts.last().copied().unwrap_or(range.start);
+        if end.page_first != page {
+            continue;
+        }
+        let end = node_rect(end, page_origin, zoom);
+        let size = COMMENT_MARKER_SIZE * zoom;
+        painter.paint_rect(Brush::SolidColor(COMMENT_MARKER_COLOR),
+            Rect::from_positions(end.right - size / 2.0, end.right + size / 2.0, end.top - size / 2.0, end.top + size / 2.0));
+    }
+}
+
+/// Marks the text of the tracked changes on the `page` in the color of their
+/// author: inserted text is underlined and deleted text is struck through.
+fn paint_revision_marks(painter: &mut dyn Painter, revisions: &[&Node], page: usize, page_origin: Position<f32>, zoom: f32) {
+    // The colors are handed out in document order, so an author has the same
+    // color on every page.
+    let mut authors = Vec::new();
+    for node in revisions {
+        let wp::NodeData::Revision(revision) = &node.data else {
+            continue;
+        };
+
+        let brush = Brush::SolidColor(wp::revisions::author_color(&mut authors, &revision.author));
+        for text_part in node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..))) {
+            let wp::NodeData::TextPart(part) = &text_part.data else {
+                continue;
+            };
+            if text_part.page_first != page {
+                continue;
+            }
+
+            let rect = node_rect(text_part, page_origin, zoom);
+            let font_size = text_part.text_settings.font_size_pts();
+            let offset = if revision.kind.is_insertion() { UNDERLINE_OFFSET } else { -STRIKETHROUGH_OFFSET };
+            let top = rect.top + (part.ascent + font_size * offset) * zoom;
+            let thickness = font_size * UNDERLINE_THICKNESS * zoom;
+            painter.paint_rect(brush, Rect::from_positions(rect.left, rect.right, top, top + thickness));
+        }
+    }
+}
+
+/// The rect on the screen the comment is shown next to, which is the first
+/// text of its range, or where the range starts when it is empty.
+fn comment_anchor_rect(range: &CommentRange, page_rects: &[Rect<f32>], zoom: f32) -> Option<Rect<f32>> {
+    range.text_parts.iter()
+        .find_map(|node| node.bounding_box(page_rects, zoom))
+        .or_else(|| range.start.bounding_box(page_rects, zoom))
+}
+
+/// Finds the first match starting at or after the `position`, wrapping around
+/// to the first match of the document.
+fn find_match_from(matches: &[Selection], position: Option<TextPosition>) -> Option<usize> {
+    if matches.is_empty() {
+        return None;
+    }
+
+    let Some(position) = position else {
+        return Some(0);
     };
 
-    let style_manager = {
-        let _frame = profiler.frame(String::from("Style Definitions"));
+    Some(matches.iter().position(|search_match| search_match.start() >= position).unwrap_or(0))
+}
+
+/// Moves `steps` matches from the current match, wrapping around at the start
+/// and end of the document.
+fn step_match(current_match: Option<usize>, match_count: usize, steps: isize) -> Option<usize> {
+    if match_count == 0 {
+        return None;
+    }
+
+    match current_match {
+        Some(index) => Some((index as isize + steps).rem_euclid(match_count as isize) as usize),
+        None => Some(0),
+    }
+}
+
+/// Deletes the selected text, when the selection is inside a single
+/// paragraph.
+fn delete_selection(start: &ParagraphPosition, end: &ParagraphPosition) -> Option<Box<dyn Command>> {
+    if start.path != end.path {
+        println!("[DocumentView] Warning: deleting text across paragraphs isn't supported yet");
+        return None;
+    }
 
-        let styles_document_text = load_archive_file_to_string(&mut archive, "word/styles.xml")
-                .expect("Style definitions missing, assuming this is not a DOCX file.");
-        let styles_document = xml::Document::parse(&styles_document_text)
-                .expect("Failed to parse styles document");
-        StyleManager::from_document(&styles_document, &numbering_manager, &theme_settings).unwrap()
+    Some(Box::new(DeleteText::new(start.path.clone(), start.offset..end.offset)))
+}
+
+/// Creates the command for the character typed with the given selection, or
+/// returns None when the character doesn't change the text there.
+fn create_edit_command(root_node: &Node, selection: Option<Selection>, character: char) -> Option<Box<dyn Command>> {
+    let selection = selection?;
+    let start = editing::paragraph_position(root_node, selection.start())?;
+    let end = editing::paragraph_position(root_node, selection.end())?;
+
+    match character {
+        BACKSPACE => {
+            if !selection.is_collapsed() {
+                delete_selection(&start, &end)
+            } else if start.offset > 0 {
+                Some(Box::new(DeleteText::new(start.path, start.offset - 1..start.offset)))
+            } else {
+                None
+            }
+        }
+        DELETE => {
+            let length = editing::paragraph_length(root_node, &start.path).unwrap_or(0);
+            if !selection.is_collapsed() {
+                delete_selection(&start, &end)
+            } else if start.offset < length {
+                Some(Box::new(DeleteText::new(start.path, start.offset..start.offset + 1)))
+            } else {
+                None
+            }
+        }
+        // Other control characters, such as enter, aren't supported yet.
+        character if character.is_control() => None,
+        character => {
+            let insert: Box<dyn Command> = Box::new(InsertText::new(start.clone(), character.to_string()));
+            if selection.is_collapsed() {
+                Some(insert)
+            } else {
+                Some(Box::new(CommandGroup(vec![delete_selection(&start, &end)?, insert])))
+            }
+        }
+    }
+}
+
+/// Lays out the edited paragraph again, and moves the content after it on the
+/// same page by the change in its height. The pages aren't broken again.
+fn relayout_edited_paragraph(root_node: &mut Node, path: &[usize], document: &wp::Document,
+        text_calculator: &mut dyn TextCalculator, fallback_font: &str) {
+    let Some(paragraph) = editing::node_at_path_mut(root_node, path) else {
+        return;
+    };
+
+    let page = paragraph.page_last;
+    let page_settings = document.page_settings_for_page(paragraph.page_first);
+    let height_change = word_processing::relayout_paragraph(paragraph, &page_settings, text_calculator, fallback_font);
+    if height_change == 0.0 {
+        return;
+    }
+
+    // The content after the paragraph consists of the siblings after the
+    // paragraph and after each of its ancestors.
+    let mut move_down = |node: &mut Node, _depth: usize| {
+        if node.page_first == page {
+            *node.position.y_mut() += height_change;
+        }
     };
 
-    let mut document_properties = wp::document_properties::DocumentProperties::new();
-    if let Some(txt) = load_archive_file_to_string(&mut archive, "docProps/core.xml") {
-        if let Ok(document) = xml::Document::parse(&txt) {
-            document_properties.import_core_file_properties_part(&document);
+    let mut node = root_node;
+    for child_index in path {
+        let (before, after) = node.children.split_at_mut(child_index + 1);
+        for sibling in after {
+            sibling.apply_recursively_mut(&mut move_down, 0);
         }
+        node = &mut before[*child_index];
     }
+}
+
+/// Paints the white backgrounds of the `pages` below each other, centered
+/// horizontally, of which only the visible ones are painted, and returns
+/// their rects on the screen. The horizontal offset
+/// of the event is limited such that the widest page stays visible.
+fn paint_pages(event: &mut super::PaintEvent, document: &Document, pages: Range<usize>) -> Vec<Rect<f32>> {
+    // The pages of the sections can differ in size, and are centered
+    // horizontally. They can only be moved horizontally when the widest page
+    // doesn't fit.
+    let page_sizes = pages.map(|page| {
+        let page_settings = document.page_settings_for_page(page);
+        Size::new(page_settings.size.width().get_pts(), page_settings.size.height().get_pts()) * event.zoom
+    }).collect::<Vec<Size<f32>>>();
+    let max_page_width = page_sizes.iter().map(|size| size.width()).fold(0.0, f32::max);
+    let max_horizontal_offset = ((max_page_width - event.content_rect.width()) / 2.0).max(0.0);
+    event.horizontal_offset = event.horizontal_offset.clamp(-max_horizontal_offset, max_horizontal_offset);
 
-    let _frame = profiler.frame(String::from("Document"));
-    let document_text = load_archive_file_to_string(&mut archive, "word/document.xml")
-            .expect("Archive missing word/document.xml: this file is not a WordprocessingML document!");
-    let document = xml::Document::parse(&document_text)
-            .expect("Failed to parse document");
+    let mut page_rects = Vec::with_capacity(page_sizes.len());
+    let mut start_y = event.content_rect.top + event.start_y + VERTICAL_PAGE_MARGIN * event.zoom;
+    for page_size in &page_sizes {
+        let start_x = event.content_rect.left + (event.content_rect.width() - page_size.width()) / 2.0 + event.horizontal_offset;
+        let page_rect = Rect::from_position_and_size(Position::new(start_x, start_y), *page_size);
+
+        if page_rect.bottom > event.content_rect.top && page_rect.top < event.content_rect.bottom {
+            event.painter.paint_rect(crate::gui::Brush::SolidColor(crate::gui::Color::WHITE), page_rect);
+        }
 
-    word_processing::process_document(&document, &style_manager, &document_relationships, numbering_manager, document_properties, text_calculator, theme_settings, progress_sender)
+        page_rects.push(page_rect);
+        start_y += VERTICAL_PAGE_GAP + page_size.height();
+    }
+
+    page_rects
 }
 
-impl DocumentView {
-    pub fn new(archive_path: &str, text_calculator: &mut dyn TextCalculator, progress_sender: &dyn Fn(f32)) -> Self {
-        let result = draw_document(archive_path, text_calculator, progress_sender);
+/// The commands painting a page at a certain zoom level, relative to the
+/// top-left corner of the page. They are replayed when the page is painted
+/// again, e.g. after scrolling, instead of visiting its nodes again.
+#[derive(Debug)]
+struct PageCache {
+    zoom: f32,
+    commands: Vec<PaintCommand>,
+
+    /// Where the commands painting the contents of the page start, after the
+    /// highlights of the comments, which are below the search matches.
+    contents_start: usize,
+}
+
+impl PageCache {
+    fn highlights(&self) -> &[PaintCommand] {
+        &self.commands[..self.contents_start]
+    }
+
+    fn contents(&self) -> &[PaintCommand] {
+        &self.commands[self.contents_start..]
+    }
+}
+
+/// The document and what is found in its tree once, to paint the pages one
+/// by one.
+struct PageSource<'a> {
+    document: &'a mut Document,
+    root_node: &'a Node,
+    display_list: &'a DisplayList,
+
+    /// The number of pages, for the fields in the headers and footers.
+    page_count: usize,
+    fallback_font: &'a str,
+
+    /// The ranges of the comments, of which the text is highlighted.
+    comment_ranges: Vec<CommentRange<'a>>,
+
+    /// The tracked changes, which are marked when the markup is shown.
+    revisions: Vec<&'a Node>,
+}
+
+impl<'a> PageSource<'a> {
+    fn new(document: &'a mut Document, root_node: &'a Node, display_list: &'a DisplayList, page_count: usize,
+            fallback_font: &'a str) -> Self {
+        let revisions = match document.revision_view {
+            RevisionView::Markup => root_node.find_all(|node| matches!(node.data, wp::NodeData::Revision(..))),
+            _ => Vec::new(),
+        };
+
         Self {
+            document,
+            root_node,
+            display_list,
+            page_count,
+            fallback_font,
+            comment_ranges: Vec::new(),
+            revisions,
+        }
+    }
+
+    /// Highlights the text the comments are about as well, when recording.
+    fn with_comment_highlights(mut self) -> Self {
+        self.comment_ranges = wp::comments::find_comment_ranges(self.root_node);
+        self
+    }
+
+    /// Records the commands painting the `page` at the zoom level.
+    fn record_page(&mut self, page: usize, page_size: Size<f32>, zoom: f32) -> PageCache {
+        let mut painter = NullPainter::new();
+        paint_comment_highlights(&mut painter, &self.comment_ranges, page, Position::default(), zoom);
+        let contents_start = painter.commands().len();
+        self.paint_page(&mut painter, page, Rect::from_position_and_size(Position::default(), page_size), zoom);
+
+        PageCache {
+            zoom,
+            commands: painter.commands().to_vec(),
+            contents_start,
+        }
+    }
+
+    /// Paints the body, headers, footers and footnotes of the `page` at the
+    /// `page_rect`. Only the nodes the display list has for the page are
+    /// painted.
+    fn paint_page(&mut self, painter: &mut dyn Painter, page: usize, page_rect: Rect<f32>, zoom: f32) {
+        let page_origin = page_rect.position();
+        // The change bars are in the left margin, which is the right one on
+        // the even pages when the margins are mirrored.
+        let margin_left = self.document.page_settings_for_page(page).margins.left.get_pts()
+            + self.document.mirror_offset_for_page(page);
+        painter.begin_clip_region(page_rect);
+
+        for node in self.display_list.nodes_on_page(self.root_node, page) {
+            let position = page_point_to_screen(page_origin, node.position, zoom);
+            paint_node(painter, node, position, page_rect.left, margin_left, zoom, self.fallback_font);
+        }
+
+        paint_revision_marks(painter, &self.revisions, page, page_origin, zoom);
+
+        if let Some(section) = self.document.section_for_page_mut(page) {
+            section.update_page_fields(page, self.page_count);
+        }
+
+        // Headers, footers and footnotes are laid out as if they were on the
+        // first page. The footnotes are already moved to the mirrored margins,
+        // but the headers and footers are shared with the odd pages.
+        let document = &*self.document;
+        let mirror_offset = document.mirror_offset_for_page(page);
+        let mirrored_origin = Position::new(page_origin.x() + mirror_offset * zoom, page_origin.y());
+        let headers_footers = document.section_for_page(page)
+            .map(|section| [section.header_for_page(page), section.footer_for_page(page)])
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|tree| (tree, mirrored_origin));
+        let footnotes = document.footnote_areas.iter()
+            .filter(|area| area.page == page)
+            .map(|area| (&area.notes, page_origin));
+        for (tree, origin) in headers_footers.chain(footnotes) {
+            for node in tree.find_all(|_| true) {
+                let position = page_point_to_screen(origin, node.position, zoom);
+                paint_node(painter, node, position, page_rect.left, margin_left, zoom, self.fallback_font);
+            }
+        }
+
+        if let Some(section) = document.section_for_page(page) {
+            paint_column_separators(painter, section, mirrored_origin, zoom);
+        }
+
+        for separator in document.note_separators.iter().filter(|separator| separator.page == page) {
+            let position = page_point_to_screen(page_origin, separator.rect.position(), zoom);
+            painter.paint_rect(Brush::SolidColor(crate::gui::Color::BLACK),
+                Rect::from_position_and_size(position, separator.rect.size() * zoom));
+        }
+
+        painter.end_clip_region();
+    }
+}
+
+/// Paints the visible pages of the first `page_count` pages of a document
+/// that is still being loaded, see [word_processing::LoadObserver], and
+/// returns the rects of those pages on the screen. The search matches and the
+/// comments are only shown once the document is loaded.
+pub fn paint_partial_document(event: &mut super::PaintEvent, document: &mut Document, root_node: &Node,
+        page_count: usize, fallback_font: &str) -> Vec<Rect<f32>> {
+    let page_count = page_count.min(root_node.page_last + 1);
+    let page_rects = paint_pages(event, document, root_node.page_first..page_count);
+    let display_list = DisplayList::build(root_node, page_count);
+    let mut source = PageSource::new(document, root_node, &display_list, page_rects.len(), fallback_font);
+    for page in visible_pages(&page_rects, event.content_rect) {
+        source.paint_page(event.painter, page, page_rects[page], event.zoom);
+    }
+    page_rects
+}
+
+impl DocumentView {
+    /// Loads and lays out the document, showing its tracked changes as the
+    /// `revision_view` selects. Switching the view loads the document again.
+    /// Legacy Word Binary Files are converted first, and can't be saved. A
+    /// document that is too damaged to be shown is an error.
+    pub fn new(archive_path: &str, text_calculator: &mut dyn TextCalculator, fallback_font: String, part_cache: Option<&mut PartCache>,
+            revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<Self, Error> {
+        let result = load::load_document(archive_path, text_calculator, &fallback_font, part_cache, revision_view, observer)?;
+        let display_list = DisplayList::build(&result.root_node, result.root_node.page_last + 1);
+        Ok(Self {
             view_data: ViewData {  },
+            display_list,
+            page_caches: Vec::new(),
             page_rects: Vec::new(),
+            zoom: 1.0,
+            content_rect: Rect::empty(),
             document: Some(result.document),
             root_node: Some(result.root_node),
-        }
+            fallback_font,
+            diagnostics: result.diagnostics,
+            selection: None,
+            caret_blink_start: Instant::now(),
+            history: History::default(),
+            text_index: None,
+            search: None,
+            hover_text: None,
+        })
     }
 
-    /// In the future we should construct a layout tree from the DOM tree,
-    /// and based on the layout tree a paint tree. That way we can just iterate
-    /// the paint nodes and draw the document fast.
-    fn paint(&mut self, event: &mut super::PaintEvent) {
-        let max_y = event.content_rect.bottom;
+    /// The laid out tree of the document.
+    pub fn root_node(&self) -> Option<&Node> {
+        self.root_node.as_ref()
+    }
 
-        if let Some(document) = &mut self.document {
-            let root_node = self.root_node.as_mut().unwrap();
+    /// The fonts of the document, which the painter should use for the cache
+    /// the document is painted in.
+    pub fn document_fonts(&self) -> Option<&Arc<DocumentFontSource>> {
+        self.document.as_ref()?.fonts.as_ref()
+    }
 
-            let page_width = document.page_settings.size.width.get_pts() * event.zoom;
-            let page_height = document.page_settings.size.height.get_pts() * event.zoom;
-            let page_size = Size::new(page_width, page_height);
-            let start_x = event.content_rect.left + (event.content_rect.width() as f32 - page_width) / 2.0;
+    /// Converts a position on the screen to the index of the page under it,
+    /// and the point on that page in document space (in points, relative to
+    /// the top-left corner of the page), as of the last paint. Returns None
+    /// when the position isn't on a page.
+    pub fn page_at_point(&self, position: Position<f32>) -> Option<(usize, Position<f32>)> {
+        find_page_at_point(&self.page_rects, self.zoom, position)
+    }
 
-            self.page_rects.clear();
-            let start_y_pages = (root_node.page_first..(root_node.page_last + 1)).map(|index| {
-                let page_size_and_margin = VERTICAL_PAGE_GAP + document.page_settings.size.height().get_pts() * event.zoom;
-                let start_y = event.content_rect.top + event.start_y + VERTICAL_PAGE_MARGIN * event.zoom + index as f32 * page_size_and_margin;
+    /// Paints the pages inside the content rect. The commands of a page are
+    /// recorded once for the zoom level, and replayed when it is painted
+    /// again, until the nodes on the page or the selected text change.
+    fn paint(&mut self, event: &mut super::PaintEvent) {
+        if let (Some(document), Some(root_node)) = (&mut self.document, &self.root_node) {
+            self.zoom = event.zoom;
+            self.content_rect = event.content_rect;
+            self.page_rects = paint_pages(event, document, root_node.page_first..(root_node.page_last + 1));
+            self.page_caches.resize_with(self.page_rects.len(), || None);
 
-                if start_y < max_y {
-                    event.painter.paint_rect(crate::gui::Brush::SolidColor(crate::gui::Color::WHITE), crate::gui::Rect {
-                        left: start_x,
-                        right: start_x + page_width,
+            let visible_pages = visible_pages(&self.page_rects, event.content_rect);
+            let is_cached = |cache: &Option<PageCache>| cache.as_ref().is_some_and(|cache| cache.zoom == event.zoom);
+            if !self.page_caches[visible_pages.clone()].iter().all(is_cached) {
+                // Finding the comments and tracked changes visits the whole
+                // tree, so that is done once for all the pages to record.
+                let mut source = PageSource::new(document, root_node, &self.display_list, self.page_rects.len(), &self.fallback_font)
+                    .with_comment_highlights();
+                for page in visible_pages.clone() {
+                    if !is_cached(&self.page_caches[page]) {
+                        self.page_caches[page] = Some(source.record_page(page, self.page_rects[page].size(), event.zoom));
+                    }
+                }
+            }
 
-                        top: start_y,
-                        bottom: start_y + page_height
-                    });
+            let fallback_font = Some(self.fallback_font.as_str());
+            let pages = self.page_caches[visible_pages.clone()].iter()
+                .flatten()
+                .zip(&self.page_rects[visible_pages]);
+            for (cache, page_rect) in pages.clone() {
+                for command in cache.highlights() {
+                    command.replay(event.painter, page_rect.position(), fallback_font);
                 }
+            }
 
-                self.page_rects.push(Rect::from_position_and_size(Position::new(start_x, start_y), page_size));
+            if let Some(search) = &self.search {
+                paint_search_highlights(event.painter, root_node, search, &self.page_rects, event.zoom);
+            }
 
-                start_y
-            }).collect::<Vec<f32>>();
+            for (cache, page_rect) in pages {
+                for command in cache.contents() {
+                    command.replay(event.painter, page_rect.position(), fallback_font);
+                }
+            }
+        }
 
-            let mut previous_page = None;
+        if self.is_caret_visible() {
+            self.paint_caret(event.painter);
+        }
+    }
 
-            root_node.apply_recursively_mut(&mut |node, _depth| {
-                let start_y = start_y_pages[node.page_first];
+    /// Whether the caret is in the visible phase of blinking. The caret is
+    /// always visible right after it was moved.
+    fn is_caret_visible(&self) -> bool {
+        if !self.selection.is_some_and(|selection| selection.is_collapsed()) {
+            return false;
+        }
 
-                if start_y > max_y {
-                    // Outside the bounds of the window.
-                    return;
-                }
+        let intervals = self.caret_blink_start.elapsed().as_millis() / CARET_BLINK_INTERVAL.as_millis();
+        intervals % 2 == 0
+    }
 
-                let position = crate::gui::Position::new(
-                    start_x + node.position.x * event.zoom,
-                    start_y + node.position.y * event.zoom
-                );
+    fn paint_caret(&self, painter: &mut dyn Painter) {
+        let (Some(root_node), Some(selection)) = (self.root_node.as_ref(), self.selection) else {
+            return;
+        };
 
-                if Some(node.page_first) != previous_page {
-                    if previous_page.is_some() {
-                        event.painter.end_clip_region();
-                    }
+        let text_parts = root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)));
+        let Some(node) = text_parts.get(selection.focus.text_part) else {
+            return;
+        };
 
-                    previous_page = Some(node.page_first);
-                    event.painter.begin_clip_region(Rect::from_position_and_size(position, page_size));
-                }
+        let (wp::NodeData::TextPart(part), Some(rect)) = (&node.data, node.bounding_box(&self.page_rects, self.zoom)) else {
+            return;
+        };
 
-                match &node.data {
-                    wp::NodeData::TextPart(part) => {
-                        let text_size = node.text_settings.non_complex_text_size.unwrap().get_pts();
-                        let font_family_name = node.text_settings.font.clone().unwrap();
+        let x = rect.left + part.visual_grapheme_boundary(selection.focus.offset) * self.zoom;
+        painter.paint_rect(Brush::SolidColor(crate::gui::Color::BLACK),
+            Rect::from_positions(x, x + CARET_WIDTH, rect.top, rect.bottom));
+    }
 
-                        if event.painter.select_font(FontSpecification::new(&font_family_name, text_size, node.text_settings.font_weight())).is_err() {
-                            _ = event.painter.select_font(FontSpecification::new("Times New Roman", text_size, node.text_settings.font_weight()));
-                        }
+    /// Stores the selected text in the nodes, and forgets the recorded
+    /// commands of the pages on which the selected text changed.
+    fn apply_selection(&mut self) {
+        let Some(root_node) = &mut self.root_node else {
+            return;
+        };
 
-                        //let size =
-                        event.painter.paint_text(node.text_settings.brush(), position, &part.text, Some(node.size * event.zoom));
-                        //println!("Text \"{}\" for size {} and dims {:?}", part.text, text_size, size);
-                    }
-                    _ => ()
-                }
-            }, 0);
+        for page in selection::apply_selection(root_node, self.selection.as_ref()) {
+            if let Some(cache) = self.page_caches.get_mut(page) {
+                *cache = None;
+            }
+        }
+    }
+
+    /// Restarts the blinking of the caret, so it is visible right away.
+    fn restart_caret_blink(&mut self) {
+        self.caret_blink_start = Instant::now();
+    }
+
+    fn on_edit(&mut self, event: &mut super::EditEvent) {
+        match event.input {
+            EditInput::Key(VirtualKeyCode::Left) => return self.move_caret(-1),
+            EditInput::Key(VirtualKeyCode::Right) => return self.move_caret(1),
+            EditInput::Key(..) => return,
+            EditInput::Character(..) | EditInput::Undo | EditInput::Redo => (),
+        }
 
-            if previous_page.is_some() {
-                event.painter.end_clip_region();
+        let (Some(document), Some(root_node)) = (self.document.as_ref(), self.root_node.as_mut()) else {
+            return;
+        };
+
+        let edit = match event.input {
+            EditInput::Character(character) => {
+                let Some(command) = create_edit_command(root_node, self.selection, character) else {
+                    return;
+                };
+                self.history.apply(root_node, command)
+            }
+            EditInput::Undo => match self.history.undo(root_node) {
+                Some(edit) => edit,
+                None => return,
+            },
+            EditInput::Redo => match self.history.redo(root_node) {
+                Some(edit) => edit,
+                None => return,
+            },
+            EditInput::Key(..) => return,
+        };
+
+        let position = match edit {
+            Ok(position) => position,
+            Err(e) => {
+                println!("[DocumentView] Warning: can't edit the text at {:?}: {:?}", self.selection, e);
+                return;
             }
+        };
+
+        // Only the pages of the paragraph change, even when it moved to
+        // another line or page.
+        let page_range = |root_node: &Node| editing::node_at_path(root_node, &position.path)
+            .map(|paragraph| paragraph.page_first..(paragraph.page_last + 1));
+        let pages_before = page_range(&*root_node);
+        relayout_edited_paragraph(root_node, &position.path, document, event.text_calculator, &self.fallback_font);
+        if let (Some(before), Some(after)) = (pages_before, page_range(&*root_node)) {
+            let pages = before.start.min(after.start)..before.end.max(after.end);
+            self.display_list.invalidate_pages(root_node, pages.clone());
+            for cache in self.page_caches.iter_mut().take(pages.end).skip(pages.start) {
+                *cache = None;
+            }
+        }
+
+        self.selection = editing::text_position(root_node, &position).map(Selection::new_collapsed);
+        self.apply_selection();
+        self.restart_caret_blink();
+
+        // The text parts of the edited paragraph were replaced, so the
+        // matches are found again.
+        self.text_index = None;
+        if let Some(query) = self.search.as_ref().map(|search| search.query.clone()) {
+            self.search_for(query);
+        }
+    }
+
+    fn on_search(&mut self, input: &SearchInput) {
+        match input {
+            SearchInput::Query(query) => self.search_for(query.clone()),
+            SearchInput::Next => self.step_search_match(1),
+            SearchInput::Previous => self.step_search_match(-1),
+            SearchInput::Clear => self.search = None,
+        }
+    }
+
+    /// Finds the occurrences of the query. The search continues from the
+    /// current match while the user is typing, or else from the caret.
+    fn search_for(&mut self, query: String) {
+        let Some(root_node) = self.root_node.as_ref() else {
+            return;
+        };
+
+        let matches = self.text_index.get_or_insert_with(|| TextIndex::new(root_node)).find_all(&query);
+
+        let previous_match = self.search.as_ref()
+            .and_then(|search| search.current_match.map(|index| search.matches[index].start()));
+        let current_match = find_match_from(&matches, previous_match.or(self.selection.map(|selection| selection.start())));
+
+        self.search = Some(Search { query, matches, current_match });
+    }
+
+    fn step_search_match(&mut self, steps: isize) {
+        if let Some(search) = &mut self.search {
+            search.current_match = step_match(search.current_match, search.matches.len(), steps);
+        }
+    }
+
+    /// Moves the caret by the given number of graphemes, within its paragraph.
+    fn move_caret(&mut self, graphemes: isize) {
+        let (Some(root_node), Some(selection)) = (self.root_node.as_mut(), self.selection) else {
+            return;
+        };
+
+        let Some(mut position) = editing::paragraph_position(root_node, selection.focus) else {
+            return;
+        };
+
+        let length = editing::paragraph_length(root_node, &position.path).unwrap_or(0);
+        position.offset = position.offset.saturating_add_signed(graphemes).min(length);
+
+        if let Some(focus) = editing::text_position(root_node, &position) {
+            self.selection = Some(Selection::new_collapsed(focus));
+            self.apply_selection();
+            self.restart_caret_blink();
         }
     }
 
     fn on_mouse_moved(&mut self, mouse_position: Position<f32>, new_cursor: &mut Option<CursorIcon>) {
+        let mut hover_text = None;
         self.check_interactable_for_mouse(mouse_position, &mut |node, position| {
             node.interaction_states.hover = wp::HoverState::HoveringOver;
 
@@ -224,8 +1239,72 @@ impl DocumentView {
                 if let Some(cursor) = mouse_event.new_cursor {
                     *new_cursor = Some(cursor);
                 }
+                hover_text = hover_text.take().or(mouse_event.hover_text.clone());
             }
         });
+        self.hover_text = hover_text;
+    }
+
+    fn on_mouse_down(&mut self, mouse_position: Position<f32>) {
+        let Some(root_node) = self.root_node.as_mut() else {
+            return;
+        };
+
+        self.selection = root_node.nearest_text_position(mouse_position, &self.page_rects, self.zoom)
+            .map(Selection::new_collapsed);
+        self.apply_selection();
+        self.restart_caret_blink();
+    }
+
+    fn on_mouse_dragged(&mut self, mouse_position: Position<f32>) {
+        let (Some(root_node), Some(selection)) = (self.root_node.as_mut(), self.selection.as_mut()) else {
+            return;
+        };
+
+        // Keep the previous focus when the mouse is between the pages.
+        if let Some(focus) = root_node.nearest_text_position(mouse_position, &self.page_rects, self.zoom) {
+            selection.focus = focus;
+            self.apply_selection();
+        }
+    }
+
+    fn on_click(&mut self, mouse_position: Position<f32>, follows_links: bool, scroll_target: &mut Option<Rect<f32>>) {
+        let mut bookmark = None;
+        let mut url = None;
+        self.check_interactable_for_mouse(mouse_position, &mut |node, position| {
+            let mut mouse_event = wp::MouseEvent::new(position);
+            mouse_event.follows_links = follows_links;
+
+            let mut event = wp::Event::Click(mouse_event);
+            node.on_event(&mut event);
+
+            if let wp::Event::Click(mouse_event) = event {
+                bookmark = bookmark.take().or(mouse_event.bookmark);
+                url = url.take().or(mouse_event.url);
+            }
+        });
+
+        if let Some(bookmark) = bookmark {
+            *scroll_target = self.bookmark_rect(&bookmark);
+        }
+
+        if let Some(url) = url {
+            if let Err(e) = crate::platform::open_url(&url) {
+                println!("[DocumentView] Error: failed to open link \"{}\": {}", url, e);
+            }
+        }
+    }
+
+    /// The rect on the screen of the start of the bookmark with the given
+    /// name, as of the last paint. The reserved "_top" bookmark is the start
+    /// of the document (17.16.22 hyperlink). Links to unknown bookmarks are
+    /// reported when the document is loaded.
+    fn bookmark_rect(&self, name: &str) -> Option<Rect<f32>> {
+        if name == "_top" {
+            return self.page_rects.first().copied();
+        }
+
+        self.root_node.as_ref()?.find_bookmark(name)?.bounding_box(&self.page_rects, self.zoom)
     }
 }
 
@@ -239,38 +1318,60 @@ impl super::ViewImpl for DocumentView {
         }
     }
 
-    fn check_interactable_for_mouse(&mut self, mouse_position: Position<f32>, callback: &mut dyn FnMut(&mut crate::wp::Node, Position<f32>)) -> bool {
-        // TODO: check if the mouse is inside the bounds of a page.
+    fn check_interactable_for_mouse(&mut self, mouse_position: Position<f32>,
+            callback: &mut dyn FnMut(&mut crate::wp::Node, Position<f32>)) -> Option<TextPosition> {
+        let root_node = self.root_node.as_mut()?;
 
-        let mouse_position = Position::new(mouse_position.x, mouse_position.y);
+        if !self.page_rects.iter().any(|page_rect| page_rect.is_inside_inclusive(mouse_position)) {
+            return None;
+        }
 
-        // for (_, node) in self.document.as_mut().unwrap().node_arena.iter_mut() {
-        //     if let NodeData::TextPart(..) = &node.data {
-        //         let node_rect = Rect::from_position_and_size(node.position, node.size);
+        check_interactable_node(root_node, mouse_position, &self.page_rects, self.zoom, &mut 0, callback)
+    }
+
+    fn comments(&self) -> &[Comment] {
+        match &self.document {
+            Some(document) => &document.comments,
+            None => &[],
+        }
+    }
 
-        //         if node_rect.is_inside_inclusive(mouse_position) {
-        //             callback(node, mouse_position);
-        //             return true;
-        //         }
-        //     }
-        // }
+    fn comment_rects(&self) -> Vec<Option<Rect<f32>>> {
+        let ranges = match &self.root_node {
+            Some(root_node) => wp::comments::find_comment_ranges(root_node),
+            None => Vec::new(),
+        };
+
+        self.comments().iter()
+            .map(|comment| {
+                ranges.iter()
+                    .find(|range| range.id == comment.id)
+                    .and_then(|range| comment_anchor_rect(range, &self.page_rects, self.zoom))
+            })
+            .collect()
+    }
 
-        false
+    fn current_page(&self) -> Option<usize> {
+        find_most_visible_page(&self.page_rects, self.content_rect)
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
     }
 
     fn dump_dom_tree(&mut self) {
-        let Some(root_node) = self.root_node.as_mut() else {
+        let Some(root_node) = self.root_node.as_ref() else {
             println!("🌲: No tree");
             return;
         };
 
-        root_node.apply_recursively(&|node, depth| {
-            print!("🌲: {}{:?}", "    ".repeat(depth), node.data);
-            print!(" @ ({}, {})", node.position.x, node.position.y,);
-            print!(" sized ({}x{})", node.size.width(), node.size.height());
+        for line in root_node.to_debug_string().lines() {
+            println!("🌲: {}", line);
+        }
+    }
 
-            println!();
-        }, 0);
+    fn export_text(&self, format: crate::word_processing::text::TextFormat) -> Option<String> {
+        self.root_node.as_ref().map(|root_node| crate::export::text::write_text(root_node, format))
     }
 
     fn handle_event(&mut self, event: &mut super::Event) {
@@ -278,14 +1379,260 @@ impl super::ViewImpl for DocumentView {
             super::Event::Paint(event) => self.paint(event),
             super::Event::MouseMoved(mouse_position, new_cursor) =>
                 self.on_mouse_moved(*mouse_position, *new_cursor),
+            super::Event::Click(mouse_position, follows_links, scroll_target) => self.on_click(*mouse_position, *follows_links, scroll_target),
+            super::Event::MouseDown(mouse_position) => self.on_mouse_down(*mouse_position),
+            super::Event::MouseDragged(mouse_position) => self.on_mouse_dragged(*mouse_position),
+            super::Event::Edit(event) => self.on_edit(event),
+            super::Event::Search(input) => self.on_search(input),
         }
     }
 
+    fn hover_text(&self) -> Option<&str> {
+        self.hover_text.as_deref()
+    }
+
+    fn is_modified(&self) -> bool {
+        self.history.is_modified()
+    }
+
     fn page_count(&self) -> Option<usize> {
         if let Some(node) = &self.root_node {
-            Some(node.page_last)
+            // The page numbers start from 0.
+            Some(node.page_last + 1)
         } else {
             None
         }
     }
+
+    fn page_rects(&self) -> &[Rect<f32>] {
+        &self.page_rects
+    }
+
+    fn print_pages(&mut self) -> Vec<PrintPage> {
+        // The page rects are used to find the nodes under the mouse, and the
+        // selection and search matches shouldn't be printed, so they are
+        // restored afterwards.
+        let (page_rects, zoom, content_rect) = (self.page_rects.clone(), self.zoom, self.content_rect);
+        let selection = self.selection.take();
+        let search = self.search.take();
+        self.apply_selection();
+
+        // Paint every page at 100% zoom, so the coordinates of the commands
+        // are in points.
+        let mut painter = NullPainter::new();
+        self.paint(&mut super::PaintEvent {
+            content_rect: Rect::from_positions(0.0, 0.0, 0.0, f32::MAX),
+            opaqueness: 1.0,
+            start_y: 0.0,
+            painter: &mut painter,
+            zoom: 1.0,
+            horizontal_offset: 0.0,
+        });
+        let pages = print::paginate(&self.page_rects, painter.commands());
+
+        self.page_rects = page_rects;
+        self.zoom = zoom;
+        self.content_rect = content_rect;
+        self.selection = selection;
+        self.search = search;
+        self.apply_selection();
+
+        pages
+    }
+
+    fn save_to(&mut self, path: &Path) -> Result<(), SaveError> {
+        match (&self.document, &self.root_node) {
+            (Some(document), Some(root_node)) => document.save_to(root_node, path)?,
+            _ => return Err(SaveError::NoSourcePackage),
+        }
+
+        self.history.mark_saved();
+        Ok(())
+    }
+
+    fn search_results(&self) -> SearchResults {
+        let (Some(search), Some(root_node)) = (&self.search, &self.root_node) else {
+            return SearchResults::default();
+        };
+
+        let current_match_rect = search.current_match.and_then(|index| {
+            let text_parts = root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)));
+            selection_rects(&text_parts, &search.matches[index], &self.page_rects, self.zoom).into_iter().next()
+        });
+
+        SearchResults {
+            match_count: search.matches.len(),
+            current_match: search.current_match,
+            current_match_rect,
+        }
+    }
+
+    fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    fn statistics(&self) -> Option<DocumentStatistics> {
+        self.root_node.as_ref().map(DocumentStatistics::count)
+    }
+
+    fn time_until_caret_blink(&self) -> Option<Duration> {
+        if !self.selection.is_some_and(|selection| selection.is_collapsed()) {
+            return None;
+        }
+
+        let interval = CARET_BLINK_INTERVAL.as_millis();
+        let elapsed = self.caret_blink_start.elapsed().as_millis();
+        Some(Duration::from_millis((interval - elapsed % interval) as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three pages of 100 pixels high, with gaps of 10 pixels.
+    const PAGE_RECTS: [Rect<f32>; 3] = [
+        Rect { left: 0.0, right: 80.0, top: 0.0, bottom: 100.0 },
+        Rect { left: 0.0, right: 80.0, top: 110.0, bottom: 210.0 },
+        Rect { left: 0.0, right: 80.0, top: 220.0, bottom: 320.0 },
+    ];
+
+    fn content_rect(top: f32) -> Rect<f32> {
+        Rect::from_positions(0.0, 100.0, top, top + 100.0)
+    }
+
+    #[test]
+    fn most_visible_page() {
+        assert_eq!(find_most_visible_page(&PAGE_RECTS, content_rect(0.0)), Some(0));
+        assert_eq!(find_most_visible_page(&PAGE_RECTS, content_rect(40.0)), Some(0));
+        assert_eq!(find_most_visible_page(&PAGE_RECTS, content_rect(70.0)), Some(1));
+        assert_eq!(find_most_visible_page(&PAGE_RECTS, content_rect(250.0)), Some(2));
+        assert_eq!(find_most_visible_page(&PAGE_RECTS, content_rect(400.0)), None);
+        assert_eq!(find_most_visible_page(&[], content_rect(0.0)), None);
+    }
+
+    #[test]
+    fn only_the_visible_pages_are_painted() {
+        assert_eq!(visible_pages(&PAGE_RECTS, content_rect(0.0)), 0..1);
+        assert_eq!(visible_pages(&PAGE_RECTS, content_rect(50.0)), 0..2);
+        assert_eq!(visible_pages(&PAGE_RECTS, content_rect(100.0)), 1..2);
+        assert_eq!(visible_pages(&PAGE_RECTS, content_rect(200.0)), 1..3);
+        assert_eq!(visible_pages(&PAGE_RECTS, content_rect(400.0)), 3..3);
+        assert_eq!(visible_pages(&PAGE_RECTS, content_rect(-200.0)), 0..0);
+        assert_eq!(visible_pages(&[], content_rect(0.0)), 0..0);
+    }
+
+    #[test]
+    fn page_point_round_trip() {
+        let page_origin = Position::new(10.0, 110.0);
+        let point = Position::new(30.0, 40.0);
+
+        let screen_position = page_point_to_screen(page_origin, point, 2.0);
+        assert_eq!(screen_position, Position::new(70.0, 190.0));
+        assert_eq!(screen_to_page_point(page_origin, screen_position, 2.0), point);
+    }
+
+    #[test]
+    fn page_at_point() {
+        assert_eq!(find_page_at_point(&PAGE_RECTS, 2.0, Position::new(20.0, 150.0)), Some((1, Position::new(10.0, 20.0))));
+        assert_eq!(find_page_at_point(&PAGE_RECTS, 1.0, Position::new(0.0, 0.0)), Some((0, Position::new(0.0, 0.0))));
+
+        // Between the pages.
+        assert_eq!(find_page_at_point(&PAGE_RECTS, 1.0, Position::new(20.0, 105.0)), None);
+    }
+
+    fn text_part_node(text: &str, bidi_level: u8, x: f32) -> Node {
+        let mut node = Node::new(wp::NodeData::TextPart(wp::TextPart {
+            text: String::from(text),
+            ascent: 0.0,
+            character_spacing: 0.0,
+            word_spacing: 0.0,
+            grapheme_ends: vec![10.0, 20.0],
+            bidi_level,
+        }));
+        node.page_first = 1;
+        node.page_last = 1;
+        node.position = Position::new(x, 10.0);
+        node.size = Size::new(20.0, 10.0);
+        node
+    }
+
+    #[test]
+    fn hit_testing_finds_the_grapheme_under_the_mouse() {
+        let mut paragraph = Node::new(wp::NodeData::Paragraph(Default::default()));
+        paragraph.children.push(text_part_node("ab", 0, 10.0));
+        paragraph.children.push(text_part_node("\u{5D0}\u{5D1}", 1, 30.0));
+
+        let mut hit = |position| {
+            let mut hits = 0;
+            let text_position = check_interactable_node(&mut paragraph, position, &PAGE_RECTS, 1.0, &mut 0, &mut |_, _| hits += 1);
+            (text_position, hits)
+        };
+
+        assert_eq!(hit(Position::new(17.0, 125.0)), (Some(TextPosition { text_part: 0, offset: 1 }), 1));
+
+        // The right-to-left text starts at the right.
+        assert_eq!(hit(Position::new(33.0, 125.0)), (Some(TextPosition { text_part: 1, offset: 2 }), 1));
+        assert_eq!(hit(Position::new(48.0, 125.0)), (Some(TextPosition { text_part: 1, offset: 0 }), 1));
+
+        // Next to the text, and on the first page.
+        assert_eq!(hit(Position::new(70.0, 125.0)), (None, 0));
+        assert_eq!(hit(Position::new(17.0, 15.0)), (None, 0));
+    }
+
+    #[test]
+    fn search_continues_from_the_position() {
+        let position = |text_part, offset| TextPosition { text_part, offset };
+        let matches = [
+            Selection { anchor: position(0, 2), focus: position(0, 5) },
+            Selection { anchor: position(3, 0), focus: position(3, 3) },
+        ];
+
+        assert_eq!(find_match_from(&matches, None), Some(0));
+        assert_eq!(find_match_from(&matches, Some(position(0, 2))), Some(0));
+        assert_eq!(find_match_from(&matches, Some(position(1, 0))), Some(1));
+
+        // After the last match, the search wraps around.
+        assert_eq!(find_match_from(&matches, Some(position(4, 0))), Some(0));
+        assert_eq!(find_match_from(&[], Some(position(0, 0))), None);
+    }
+
+    #[test]
+    fn stepping_through_matches_wraps_around() {
+        assert_eq!(step_match(Some(0), 3, 1), Some(1));
+        assert_eq!(step_match(Some(2), 3, 1), Some(0));
+        assert_eq!(step_match(Some(0), 3, -1), Some(2));
+        assert_eq!(step_match(None, 3, -1), Some(0));
+        assert_eq!(step_match(None, 0, 1), None);
+    }
+
+    #[test]
+    fn underline_styles_produce_their_lines() {
+        assert_eq!(underline_rects(UnderlineStyle::Double, 0.0, 10.0, 20.0, 1.0), [
+            Rect::from_positions(0.0, 10.0, 20.0, 21.0),
+            Rect::from_positions(0.0, 10.0, 22.0, 23.0),
+        ]);
+        assert_eq!(underline_rects(UnderlineStyle::Dash, 0.0, 10.0, 20.0, 1.0), [
+            Rect::from_positions(0.0, 4.0, 20.0, 21.0),
+            Rect::from_positions(6.0, 10.0, 20.0, 21.0),
+        ]);
+        assert_eq!(underline_rects(UnderlineStyle::Wave, 0.0, 3.0, 20.0, 1.0), [
+            Rect::from_positions(0.0, 1.5, 20.0, 21.0),
+            Rect::from_positions(1.5, 3.0, 20.5, 21.5),
+        ]);
+        assert!(underline_rects(UnderlineStyle::None, 0.0, 10.0, 20.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn words_underline_skips_the_spaces() {
+        let part = wp::TextPart {
+            text: String::from("ab  c "),
+            ascent: 0.0,
+            character_spacing: 0.0,
+            word_spacing: 0.0,
+            grapheme_ends: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+            bidi_level: 0,
+        };
+        assert_eq!(word_spans(&part), [(0.0, 2.0), (4.0, 5.0)]);
+    }
 }
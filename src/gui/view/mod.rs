@@ -1,28 +1,41 @@
 // Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
-use std::ops::{
-    Deref,
-    DerefMut,
+use std::{
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::Duration,
 };
 
-use winit::window::CursorIcon;
+use winit::{event::VirtualKeyCode, window::CursorIcon};
 
 use super::{
-    painter::Painter,
+    painter::{Painter, TextCalculator},
     Position,
     Rect,
 };
 
+pub mod display_list;
 pub mod document_view;
+pub mod welcome_view;
 
 #[derive(Debug)]
 pub enum View {
     Document(document_view::DocumentView),
+
+    /// The welcome page, shown when no document is open.
+    Welcome(welcome_view::WelcomeView),
 }
 
 impl View {
-
+    pub fn as_welcome_mut(&mut self) -> Option<&mut welcome_view::WelcomeView> {
+        match self {
+            View::Welcome(view) => Some(view),
+            _ => None,
+        }
+    }
 }
 
 impl Deref for View {
@@ -30,7 +43,8 @@ impl Deref for View {
 
     fn deref(&self) -> &Self::Target {
         match self {
-            View::Document(view) => view
+            View::Document(view) => view,
+            View::Welcome(view) => view,
         }
     }
 }
@@ -38,7 +52,8 @@ impl Deref for View {
 impl DerefMut for View {
     fn deref_mut(&mut self) -> &mut Self::Target {
         match self {
-            View::Document(view) => view
+            View::Document(view) => view,
+            View::Welcome(view) => view,
         }
     }
 }
@@ -53,15 +68,64 @@ pub trait ViewImpl {
     /// scroll.
     fn calculate_content_height(&self) -> f32;
 
+    /// Hit tests the nodes under the mouse, invoking the `callback` for the
+    /// interactable ones. Returns the position in the text of the text part
+    /// under the mouse, i.e. the grapheme boundary nearest to the mouse.
     fn check_interactable_for_mouse(&mut self, mouse_position: Position<f32>,
-        callback: &mut dyn FnMut(&mut crate::wp::Node, Position<f32>)) -> bool;
+        callback: &mut dyn FnMut(&mut crate::wp::Node, Position<f32>)) -> Option<crate::wp::selection::TextPosition>;
+
+    /// The comments on the document, in the order of the comments part.
+    fn comments(&self) -> &[crate::wp::comments::Comment];
+
+    /// The rects on the screen of the text each of the [ViewImpl::comments]
+    /// is about, as of the last paint.
+    fn comment_rects(&self) -> Vec<Option<Rect<f32>>>;
+
+    /// The page that is currently the most visible, counting from 0.
+    fn current_page(&self) -> Option<usize>;
+
+    /// The problems encountered while loading the document.
+    fn diagnostics(&self) -> &[crate::wp::diagnostics::Diagnostic];
 
     /// Print the document tree to stdout.
     fn dump_dom_tree(&mut self);
 
+    /// The text of the document, as plain text or Markdown.
+    fn export_text(&self, format: crate::word_processing::text::TextFormat) -> Option<String>;
+
     fn handle_event(&mut self, event: &mut Event);
 
+    /// The text describing what the mouse hovered over the last time it
+    /// moved, like the author and date of a tracked change.
+    fn hover_text(&self) -> Option<&str>;
+
+    /// Whether the document was edited since it was loaded or last saved.
+    fn is_modified(&self) -> bool;
+
     fn page_count(&self) -> Option<usize>;
+
+    /// The rects of the pages as of the last paint.
+    fn page_rects(&self) -> &[Rect<f32>];
+
+    /// Paints the pages for printing, in the same layout as on the screen.
+    fn print_pages(&mut self) -> Vec<crate::print::PrintPage>;
+
+    /// Saves the document, including the edits, to the given path, after which
+    /// it is no longer modified.
+    fn save_to(&mut self, path: &std::path::Path) -> Result<(), crate::wp::save::SaveError>;
+
+    /// The matches of the last search, see [Event::Search].
+    fn search_results(&self) -> SearchResults;
+
+    /// The text selected by the user.
+    fn selection(&self) -> Option<crate::wp::selection::Selection>;
+
+    /// The number of words, characters and paragraphs in the document.
+    fn statistics(&self) -> Option<crate::wp::statistics::DocumentStatistics>;
+
+    /// The time until the caret should be shown or hidden, or None when there
+    /// is no caret.
+    fn time_until_caret_blink(&self) -> Option<Duration>;
 }
 
 #[derive(Debug)]
@@ -69,6 +133,89 @@ pub enum Event<'a> {
     Paint(PaintEvent<'a>),
 
     MouseMoved(Position<f32>, &'a mut Option<CursorIcon>),
+
+    /// The left mouse button was clicked (pressed and released) at the
+    /// position, while holding Ctrl when the boolean is true, which follows
+    /// links. When the click goes to another place in the document, e.g. by a
+    /// link to a bookmark, the view sets the rect on the screen to scroll to,
+    /// as of the last paint.
+    Click(Position<f32>, bool, &'a mut Option<Rect<f32>>),
+
+    /// The left mouse button was pressed at the position, which starts a new
+    /// selection.
+    MouseDown(Position<f32>),
+
+    /// The mouse moved to the position while the left mouse button is
+    /// pressed, which extends the selection.
+    MouseDragged(Position<f32>),
+
+    /// The user typed a character or pressed a key for editing the text at
+    /// the caret.
+    Edit(EditEvent<'a>),
+
+    /// The user searched for text, or navigated between the matches.
+    Search(SearchInput),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchInput {
+    /// Find the occurrences of the text, which changes while the user is
+    /// typing it. The first match after the caret becomes the current one.
+    Query(String),
+
+    /// Go to the next match, wrapping around at the end of the document.
+    Next,
+
+    /// Go to the previous match, wrapping around at the start of the
+    /// document.
+    Previous,
+
+    /// Stop searching, which removes the highlights.
+    Clear,
+}
+
+/// The outcome of searching the text of a view.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchResults {
+    pub match_count: usize,
+
+    /// The index of the match the user is at, counting from 0.
+    pub current_match: Option<usize>,
+
+    /// Where the current match is on the screen, as of the last paint.
+    pub current_match_rect: Option<Rect<f32>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditInput {
+    /// A character typed by the user, which includes the control characters
+    /// of e.g. the backspace key.
+    Character(char),
+
+    /// A key that doesn't type a character, e.g. the arrow keys.
+    Key(VirtualKeyCode),
+
+    /// Revert the last edit.
+    Undo,
+
+    /// Apply the last reverted edit again.
+    Redo,
+}
+
+pub struct EditEvent<'a> {
+    pub input: EditInput,
+
+    /// Measures the text of the edited paragraph, when it is laid out again.
+    pub text_calculator: &'a mut dyn TextCalculator,
+}
+
+impl<'a> core::fmt::Debug for EditEvent<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EditEvent")
+            .field("input", &self.input)
+            .field("text_calculator", &String::from("<impl>"))
+            .finish()
+    }
 }
 
 pub struct PaintEvent<'a> {
@@ -78,6 +225,11 @@ pub struct PaintEvent<'a> {
     pub start_y: f32,
     pub painter: &'a mut dyn Painter,
     pub zoom: f32,
+
+    /// The horizontal offset of the pages from the center of the content
+    /// rect. The view limits this to the range in which the pages are
+    /// visible.
+    pub horizontal_offset: f32,
 }
 
 impl<'a> core::fmt::Debug for PaintEvent<'a> {
@@ -88,6 +240,7 @@ impl<'a> core::fmt::Debug for PaintEvent<'a> {
             .field("start_y", &self.start_y)
             .field("painter", &String::from("<impl>"))
             .field("zoom", &self.zoom)
+            .field("horizontal_offset", &self.horizontal_offset)
             .finish()
     }
 }
@@ -0,0 +1,307 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The welcome page, which is shown when no document is open. It lists the
+// documents the user opened lately, and has a button to create a new
+// document.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use winit::window::CursorIcon;
+
+use crate::{
+    gui::{
+        painter::{FontSpecification, FontWeight, Painter},
+        Brush,
+        Color,
+        Position,
+        Rect,
+        Size,
+    },
+    print::PrintPage,
+    user_settings::RecentDocument,
+    wp::{
+        diagnostics::Diagnostic,
+        save::SaveError,
+        selection::Selection,
+        statistics::DocumentStatistics,
+    },
+};
+
+use super::{
+    PaintEvent,
+    SearchResults,
+    ViewImpl,
+};
+
+/// The distance between the edges of the content rect and the contents.
+const PAGE_PADDING: f32 = 48.0;
+
+/// The height of the "create new document" button and the recent documents.
+const ITEM_HEIGHT: f32 = 40.0;
+
+/// The width of the "create new document" button and the recent documents.
+const ITEM_WIDTH: f32 = 520.0;
+
+/// The distance between the text of an item and its edges.
+const ITEM_PADDING: f32 = 10.0;
+
+const BACKGROUND_COLOR: Color = Color::from_rgb(0xF3, 0xF3, 0xF3);
+const TEXT_COLOR: Color = Color::from_rgb(0x1F, 0x1F, 0x1F);
+const SECONDARY_TEXT_COLOR: Color = Color::from_rgb(0x70, 0x70, 0x70);
+const HOVER_COLOR: Color = Color::from_rgb(0xE0, 0xE0, 0xE0);
+const BUTTON_COLOR: Color = Color::from_rgb(0x2B, 0x57, 0x9A);
+const BUTTON_HOVER_COLOR: Color = Color::from_rgb(0x3C, 0x6C, 0xB4);
+
+/// What the user asked for by clicking on an item of the welcome page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WelcomeAction {
+    CreateNewDocument,
+    OpenDocument(PathBuf),
+}
+
+#[derive(Debug)]
+pub struct WelcomeView {
+    /// The documents the user opened lately, the most recent first.
+    recent_documents: Vec<RecentDocument>,
+
+    /// The clickable items as of the last paint.
+    items: Vec<(Rect<f32>, WelcomeAction)>,
+
+    /// The index of the item the mouse is over.
+    hovered_item: Option<usize>,
+
+    /// The action of the last clicked item, which the application takes to
+    /// open a tab.
+    clicked_action: Option<WelcomeAction>,
+}
+
+impl WelcomeView {
+    pub fn new(recent_documents: Vec<RecentDocument>) -> Self {
+        Self {
+            recent_documents,
+            items: Vec::new(),
+            hovered_item: None,
+            clicked_action: None,
+        }
+    }
+
+    /// The index of the item the mouse is over, which is highlighted.
+    pub fn hovered_item(&self) -> Option<usize> {
+        self.hovered_item
+    }
+
+    pub fn set_recent_documents(&mut self, recent_documents: Vec<RecentDocument>) {
+        self.recent_documents = recent_documents;
+    }
+
+    /// Takes the action of the item the user clicked on, if any.
+    pub fn take_clicked_action(&mut self) -> Option<WelcomeAction> {
+        self.clicked_action.take()
+    }
+
+    fn item_at(&self, position: Position<f32>) -> Option<usize> {
+        self.items.iter().position(|(rect, _)| rect.is_inside_inclusive(position))
+    }
+
+    fn on_click(&mut self, position: Position<f32>) {
+        if let Some(index) = self.item_at(position) {
+            self.clicked_action = Some(self.items[index].1.clone());
+        }
+    }
+
+    fn on_mouse_moved(&mut self, position: Position<f32>, new_cursor: &mut Option<CursorIcon>) {
+        self.hovered_item = self.item_at(position);
+        if self.hovered_item.is_some() {
+            *new_cursor = Some(CursorIcon::Hand);
+        }
+    }
+
+    fn paint(&mut self, event: &mut PaintEvent) {
+        let painter = &mut *event.painter;
+        let content_rect = event.content_rect;
+
+        painter.paint_rect(Brush::SolidColor(BACKGROUND_COLOR), content_rect);
+        self.items.clear();
+
+        let left = content_rect.left() + PAGE_PADDING;
+        let mut y = content_rect.top() + PAGE_PADDING;
+
+        painter.select_font(FontSpecification::new("Segoe UI", 24.0, FontWeight::Regular)).unwrap();
+        y += painter.paint_text(Brush::SolidColor(TEXT_COLOR), Position::new(left, y), "Welcome to Uffice", None).height();
+        y += ITEM_PADDING * 2.0;
+
+        let rect = Rect::from_position_and_size(Position::new(left, y), Size::new(ITEM_WIDTH / 2.0, ITEM_HEIGHT));
+        let color = if self.hovered_item == Some(self.items.len()) { BUTTON_HOVER_COLOR } else { BUTTON_COLOR };
+        painter.paint_rect(Brush::SolidColor(color), rect);
+        painter.select_font(FontSpecification::new("Segoe UI", 11.0, FontWeight::SemiBold)).unwrap();
+        paint_item_text(painter, rect, "Create new document", Color::WHITE);
+        self.items.push((rect, WelcomeAction::CreateNewDocument));
+        y += ITEM_HEIGHT + PAGE_PADDING / 2.0;
+
+        painter.select_font(FontSpecification::new("Segoe UI", 14.0, FontWeight::SemiBold)).unwrap();
+        y += painter.paint_text(Brush::SolidColor(TEXT_COLOR), Position::new(left, y), "Recent documents", None).height();
+        y += ITEM_PADDING;
+
+        if self.recent_documents.is_empty() {
+            painter.select_font(FontSpecification::new("Segoe UI", 10.0, FontWeight::Regular)).unwrap();
+            painter.paint_text(Brush::SolidColor(SECONDARY_TEXT_COLOR), Position::new(left, y),
+                "The documents you open will be listed here.", None);
+            return;
+        }
+
+        for document in &self.recent_documents {
+            if y + ITEM_HEIGHT > content_rect.bottom() {
+                break;
+            }
+
+            let rect = Rect::from_position_and_size(Position::new(left, y), Size::new(ITEM_WIDTH, ITEM_HEIGHT));
+            if self.hovered_item == Some(self.items.len()) {
+                painter.paint_rect(Brush::SolidColor(HOVER_COLOR), rect);
+            }
+
+            let (name, directory) = describe_path(&document.path);
+            painter.select_font(FontSpecification::new("Segoe UI", 10.0, FontWeight::SemiBold)).unwrap();
+            let name_size = paint_item_text(painter, rect, &name, TEXT_COLOR);
+
+            painter.select_font(FontSpecification::new("Segoe UI", 8.0, FontWeight::Regular)).unwrap();
+            painter.paint_text(Brush::SolidColor(SECONDARY_TEXT_COLOR),
+                Position::new(rect.left() + ITEM_PADDING * 2.0 + name_size.width(), rect.top() + ITEM_PADDING + 2.0),
+                &directory, None);
+
+            self.items.push((rect, WelcomeAction::OpenDocument(document.path.clone())));
+            y += ITEM_HEIGHT;
+        }
+    }
+}
+
+/// Paints the text vertically centered at the start of the item.
+fn paint_item_text(painter: &mut dyn Painter, rect: Rect<f32>, text: &str, color: Color) -> Size<f32> {
+    let size = painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), Position::new(0.0, 0.0), text, None);
+    let position = Position::new(rect.left() + ITEM_PADDING, rect.top() + (rect.height() - size.height()) / 2.0);
+    painter.paint_text(Brush::SolidColor(color), position, text, None)
+}
+
+/// Splits the path into the file name and the directory it is in.
+fn describe_path(path: &Path) -> (String, String) {
+    let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+    let directory = path.parent().map(|parent| parent.display().to_string()).unwrap_or_default();
+    (name, directory)
+}
+
+impl ViewImpl for WelcomeView {
+    fn calculate_content_height(&self) -> f32 {
+        0.0
+    }
+
+    fn check_interactable_for_mouse(&mut self, _mouse_position: Position<f32>,
+            _callback: &mut dyn FnMut(&mut crate::wp::Node, Position<f32>)) -> Option<crate::wp::selection::TextPosition> {
+        None
+    }
+
+    fn comments(&self) -> &[crate::wp::comments::Comment] {
+        &[]
+    }
+
+    fn comment_rects(&self) -> Vec<Option<Rect<f32>>> {
+        Vec::new()
+    }
+
+    fn current_page(&self) -> Option<usize> {
+        None
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic] {
+        &[]
+    }
+
+    fn dump_dom_tree(&mut self) {
+        println!("🌲: No tree");
+    }
+
+    fn export_text(&self, _format: crate::word_processing::text::TextFormat) -> Option<String> {
+        None
+    }
+
+    fn handle_event(&mut self, event: &mut super::Event) {
+        match event {
+            super::Event::Paint(event) => self.paint(event),
+            super::Event::MouseMoved(mouse_position, new_cursor) =>
+                self.on_mouse_moved(*mouse_position, *new_cursor),
+            super::Event::Click(mouse_position, _, _) => self.on_click(*mouse_position),
+            super::Event::MouseDown(..) | super::Event::MouseDragged(..) | super::Event::Edit(..)
+                | super::Event::Search(..) => (),
+        }
+    }
+
+    fn hover_text(&self) -> Option<&str> {
+        None
+    }
+
+    fn is_modified(&self) -> bool {
+        false
+    }
+
+    fn page_count(&self) -> Option<usize> {
+        None
+    }
+
+    fn page_rects(&self) -> &[Rect<f32>] {
+        &[]
+    }
+
+    fn print_pages(&mut self) -> Vec<PrintPage> {
+        Vec::new()
+    }
+
+    fn save_to(&mut self, _path: &Path) -> Result<(), SaveError> {
+        Err(SaveError::NoSourcePackage)
+    }
+
+    fn search_results(&self) -> SearchResults {
+        SearchResults::default()
+    }
+
+    fn selection(&self) -> Option<Selection> {
+        None
+    }
+
+    fn statistics(&self) -> Option<DocumentStatistics> {
+        None
+    }
+
+    fn time_until_caret_blink(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::view::Event;
+
+    #[test]
+    fn clicking_an_item_takes_its_action() {
+        let path = PathBuf::from("/home/user/Report.docx");
+        let mut view = WelcomeView::new(vec![RecentDocument { path: path.clone(), last_opened: std::time::SystemTime::now() }]);
+        view.items = vec![
+            (Rect::from_position_and_size(Position::new(0.0, 0.0), Size::new(100.0, 40.0)), WelcomeAction::CreateNewDocument),
+            (Rect::from_position_and_size(Position::new(0.0, 50.0), Size::new(100.0, 40.0)), WelcomeAction::OpenDocument(path.clone())),
+        ];
+
+        let mut cursor = None;
+        view.handle_event(&mut Event::MouseMoved(Position::new(10.0, 60.0), &mut cursor));
+        assert_eq!(view.hovered_item(), Some(1));
+        assert_eq!(cursor, Some(CursorIcon::Hand));
+
+        view.handle_event(&mut Event::Click(Position::new(10.0, 45.0), false, &mut None));
+        assert_eq!(view.take_clicked_action(), None);
+
+        view.handle_event(&mut Event::Click(Position::new(10.0, 60.0), false, &mut None));
+        assert_eq!(view.take_clicked_action(), Some(WelcomeAction::OpenDocument(path)));
+        assert_eq!(view.take_clicked_action(), None);
+    }
+}
@@ -5,8 +5,19 @@ use std::marker::PhantomData;
 
 use winit::event::{MouseButton, ElementState};
 
+use crate::{
+    user_settings::{
+        SettingChangeNotification,
+        SettingChangeSubscriber,
+        SettingName,
+        UserSettings,
+    },
+    wp::comments::Comment,
+};
+
 use super::{
     painter::{Painter, FontSpecification},
+    view::SearchResults,
     Brush,
     Color,
     MouseMoveEvent,
@@ -15,9 +26,108 @@ use super::{
     Size,
 };
 
-const TAB_MAX_WIDTH: f32 = 220.0;
 const TAB_PADDING: f32 = 6.0;
 
+/// Appended to titles that don't fit in the tab.
+const ELLIPSIS: &str = "…";
+
+/// The width and height of the search box.
+const SEARCH_WIDGET_WIDTH: f32 = 320.0;
+const SEARCH_WIDGET_HEIGHT: f32 = 30.0;
+
+/// The distance between the search box and the edges of the content, and
+/// between the text and the edges of the box.
+const SEARCH_WIDGET_MARGIN: f32 = 12.0;
+const SEARCH_WIDGET_PADDING: f32 = 8.0;
+
+/// Shown in the search box before the user typed anything.
+const SEARCH_PLACEHOLDER: &str = "Find in document";
+
+/// The character winit sends for the backspace key.
+const BACKSPACE: char = '\u{8}';
+
+/// The width of the comments panel, and of the strip it collapses to.
+const COMMENTS_PANEL_WIDTH: f32 = 260.0;
+const COMMENTS_PANEL_COLLAPSED_WIDTH: f32 = 28.0;
+
+/// The height of the header of the comments panel, which collapses or expands
+/// the panel when clicked.
+const COMMENTS_PANEL_HEADER_HEIGHT: f32 = 26.0;
+
+/// The space around the comments, and around the text inside of them.
+const COMMENTS_PANEL_PADDING: f32 = 8.0;
+
+/// The height of a line of text of a comment.
+const COMMENT_LINE_HEIGHT: f32 = 14.0;
+
+/// Fits the text in the `max_width` by removing characters from the end and
+/// appending an ellipsis, if needed. The `measure` function returns the width
+/// of the given text.
+fn fit_text_with_ellipsis(text: &str, max_width: f32, measure: &mut dyn FnMut(&str) -> f32) -> String {
+    if measure(text) <= max_width {
+        return String::from(text);
+    }
+
+    let boundaries: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+
+    // Find the longest prefix that still fits together with the ellipsis.
+    let mut low = 0;
+    let mut high = boundaries.len();
+    while low < high {
+        let middle = (low + high + 1) / 2;
+        let candidate = format!("{}{}", text[..boundaries[middle - 1]].trim_end(), ELLIPSIS);
+        if measure(&candidate) <= max_width {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    match low {
+        0 => String::from(ELLIPSIS),
+        _ => format!("{}{}", text[..boundaries[low - 1]].trim_end(), ELLIPSIS),
+    }
+}
+
+/// Breaks the text into lines that fit in the `max_width`, between the words
+/// and at the newlines. A word that is wider than the `max_width` gets a line
+/// of its own.
+fn wrap_text(text: &str, max_width: f32, measure: &mut dyn FnMut(&str) -> f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { String::from(word) } else { format!("{} {}", line, word) };
+            if line.is_empty() || measure(&candidate) <= max_width {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, String::from(word)));
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Positions the comments of the given heights from top to bottom, each next
+/// to the top of its anchor, but below the comment before it. The comments
+/// without an anchor are placed after the others.
+fn place_comments(anchor_tops: &[Option<f32>], heights: &[f32], top: f32) -> Vec<f32> {
+    let mut order: Vec<usize> = (0..anchor_tops.len()).collect();
+    order.sort_by(|a, b| match (anchor_tops[*a], anchor_tops[*b]) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+
+    let mut tops = vec![0.0; anchor_tops.len()];
+    let mut next_top = top;
+    for index in order {
+        tops[index] = anchor_tops[index].map_or(next_top, |anchor_top| anchor_top.max(next_top));
+        next_top = tops[index] + heights[index] + COMMENTS_PANEL_PADDING;
+    }
+    tops
+}
+
 pub trait Widget {
     fn rect(&self) -> Rect<f32>;
     fn on_mouse_enter(&mut self, event: &mut MouseMoveEvent);
@@ -45,6 +155,9 @@ pub struct TabWidget<TabItem>
         where TabItem: TabWidgetItem {
     _marker: PhantomData<TabItem>,
     bar_rect: Rect<f32>,
+
+    /// The maximum width of a tab, see [UserSettings::setting_tab_max_width].
+    tab_max_width: f32,
 }
 
 impl<'a, TabItem> TabWidget<TabItem>
@@ -54,6 +167,7 @@ impl<'a, TabItem> TabWidget<TabItem>
         Self {
             _marker: Default::default(),
             bar_rect: Default::default(),
+            tab_max_width: UserSettings::default().setting_tab_max_width(),
         }
     }
 
@@ -64,7 +178,8 @@ impl<'a, TabItem> TabWidget<TabItem>
         let tab_brush_normal = Brush::SolidColor(Color::from_rgb(0x45, 0x45, 0x45));
         let tab_brush_selected = Brush::SolidColor(Color::from_rgb(0x1F, 0x1F, 0x1F));
         let mut position = self.bar_rect.position();
-        let size = Size::new(TAB_MAX_WIDTH, self.bar_rect.height() - TAB_PADDING * 2.0);
+        let tab_height = self.bar_rect.height() - TAB_PADDING * 2.0;
+        let max_title_width = self.tab_max_width - TAB_PADDING * 2.0;
 
         let tab_font = FontSpecification::new("Segoe UI", 12.0, super::painter::FontWeight::SemiBold);
         painter.select_font(tab_font).unwrap();
@@ -86,18 +201,15 @@ impl<'a, TabItem> TabWidget<TabItem>
                 }
             };
 
-            let title = item.title();
-            let title_text_size = painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), position, &title, None);
-
-            let size = if title_text_size.width + TAB_PADDING * 2.0 < size.width {
-                title_text_size
-            } else {
-                size
-            };
+            let title = fit_text_with_ellipsis(&item.title(), max_title_width, &mut |text| {
+                painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), position, text, None).width()
+            });
+            let title_width = painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), position, &title, None).width();
+            let size = Size::new(title_width.min(max_title_width) + TAB_PADDING * 2.0, tab_height);
 
-            position.x += TAB_PADDING;
+            *position.x_mut() += TAB_PADDING;
             let mut rect = Rect::from_position_and_size(
-                Position::new(position.x, position.y + TAB_PADDING),
+                Position::new(position.x(), position.y() + TAB_PADDING),
                 size
             );
             painter.paint_rect(tab_brush, rect);
@@ -109,12 +221,27 @@ impl<'a, TabItem> TabWidget<TabItem>
             painter.paint_text(Brush::SolidColor(Color::WHITE), rect.position(), &title, None);
             painter.end_clip_region();
 
-            position.x += size.width + TAB_PADDING;
+            *position.x_mut() += size.width() + TAB_PADDING;
         }
     }
 
 }
 
+impl<TabItem> SettingChangeSubscriber for TabWidget<TabItem>
+        where TabItem: TabWidgetItem {
+    fn settings_loaded(&mut self, settings: &UserSettings) {
+        self.tab_max_width = settings.setting_tab_max_width();
+    }
+
+    fn setting_changed(&mut self, notification: &SettingChangeNotification) {
+        if notification.setting_name != SettingName::TabMaxWidth {
+            return;
+        }
+
+        self.settings_loaded(notification.settings);
+    }
+}
+
 impl<'a, TabItem> Widget for TabWidget<TabItem>
         where TabItem: TabWidgetItem + 'a {
     fn rect(&self) -> Rect<f32> {
@@ -145,9 +272,379 @@ impl<'a, TabItem> Widget for TabWidget<TabItem>
         self.bar_rect = Rect::from_position_and_size(
             Position::new(0.0, 0.0),
             Size::new(
-                window_size.width as _,
+                window_size.width() as _,
                 33.0
             )
         );
     }
 }
+
+/// The box for finding text in the document, shown in the top-right corner
+/// of the document after pressing Ctrl+F. The query is searched for while
+/// the user types it.
+#[derive(Debug, Default)]
+pub struct SearchWidget {
+    rect: Rect<f32>,
+    is_open: bool,
+    query: String,
+
+    /// The matches of the query, as reported by the view.
+    results: SearchResults,
+}
+
+impl SearchWidget {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Shows the search box, with the query of the last time it was open.
+    pub fn open(&mut self) {
+        self.is_open = true;
+    }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.results = SearchResults::default();
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Types the character in the query. Returns whether the query changed.
+    pub fn on_character(&mut self, character: char) -> bool {
+        match character {
+            BACKSPACE => self.query.pop().is_some(),
+            character if character.is_control() => false,
+            character => {
+                self.query.push(character);
+                true
+            }
+        }
+    }
+
+    pub fn set_results(&mut self, results: SearchResults) {
+        self.results = results;
+    }
+
+    /// Describes where the user is in the matches, e.g. "2 of 5".
+    fn status_text(&self) -> String {
+        if self.query.is_empty() {
+            return String::new();
+        }
+
+        match (self.results.current_match, self.results.match_count) {
+            (_, 0) => String::from("No results"),
+            (Some(index), count) => format!("{} of {}", index + 1, count),
+            (None, count) => format!("{} results", count),
+        }
+    }
+
+    /// Paints the search box in the top-right corner of the content rect.
+    pub fn paint(&mut self, painter: &mut dyn Painter, content_rect: Rect<f32>) {
+        self.rect = Rect::from_position_and_size(
+            Position::new(content_rect.right - SEARCH_WIDGET_MARGIN - SEARCH_WIDGET_WIDTH, content_rect.top + SEARCH_WIDGET_MARGIN),
+            Size::new(SEARCH_WIDGET_WIDTH, SEARCH_WIDGET_HEIGHT),
+        );
+        painter.paint_rect(Brush::SolidColor(Color::from_rgb(0x22, 0x22, 0x22)), self.rect);
+
+        painter.select_font(FontSpecification::new("Segoe UI", 10.0, super::painter::FontWeight::Regular)).unwrap();
+        // The status is empty before the user typed anything, so the height
+        // of the text is measured using the placeholder.
+        let line_height = painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), Position::new(0.0, 0.0), SEARCH_PLACEHOLDER, None).height();
+        let text_top = self.rect.top + (SEARCH_WIDGET_HEIGHT - line_height) / 2.0;
+
+        let status = self.status_text();
+        let status_width = painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), Position::new(0.0, 0.0), &status, None).width();
+        let status_left = self.rect.right - SEARCH_WIDGET_PADDING - status_width;
+        painter.paint_text(Brush::SolidColor(Color::from_rgb(0xAA, 0xAA, 0xAA)), Position::new(status_left, text_top), &status, None);
+
+        let query_rect = Rect::from_positions(self.rect.left + SEARCH_WIDGET_PADDING, status_left - SEARCH_WIDGET_PADDING,
+            self.rect.top, self.rect.bottom);
+        painter.begin_clip_region(query_rect);
+        let query_width = if self.query.is_empty() {
+            painter.paint_text(Brush::SolidColor(Color::from_rgb(0x80, 0x80, 0x80)), Position::new(query_rect.left, text_top),
+                SEARCH_PLACEHOLDER, None);
+            0.0
+        } else {
+            painter.paint_text(Brush::SolidColor(Color::WHITE), Position::new(query_rect.left, text_top), &self.query, None).width()
+        };
+
+        // The caret is always at the end of the query.
+        painter.paint_rect(Brush::SolidColor(Color::WHITE), Rect::from_positions(query_rect.left + query_width,
+            query_rect.left + query_width + 1.0, text_top, text_top + line_height));
+        painter.end_clip_region();
+    }
+}
+
+impl Widget for SearchWidget {
+    fn rect(&self) -> Rect<f32> {
+        self.rect
+    }
+
+    fn on_mouse_enter(&mut self, _event: &mut MouseMoveEvent) {
+
+    }
+
+    fn on_mouse_input(&mut self, _mouse_position: Position<f32>, _button: MouseButton, _state: ElementState) {
+
+    }
+
+    fn on_mouse_leave(&mut self, _event: &mut MouseMoveEvent) {
+
+    }
+
+    fn on_mouse_move(&mut self, _event: &mut MouseMoveEvent) {
+
+    }
+
+    fn on_window_focus_lost(&mut self) {
+
+    }
+
+    /// The search box is positioned relative to the content rect when it is
+    /// painted.
+    fn on_window_resize(&mut self, _window_size: Size<u32>) {
+
+    }
+}
+
+/// The panel on the right of the document, which lists the comments on the
+/// document. Every comment is shown next to the text it is about, so the
+/// comments scroll along with the document. Clicking the header collapses
+/// the panel to a strip, and expands it again.
+#[derive(Debug, Default)]
+pub struct CommentsPanel {
+    rect: Rect<f32>,
+    header_rect: Rect<f32>,
+    is_collapsed: bool,
+
+    comments: Vec<Comment>,
+
+    /// The rects on the screen of the text the comments are about, as of the
+    /// last paint of the document.
+    anchor_rects: Vec<Option<Rect<f32>>>,
+}
+
+impl CommentsPanel {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The panel is only shown for documents with comments.
+    pub fn is_visible(&self) -> bool {
+        !self.comments.is_empty()
+    }
+
+    /// The width the panel takes up next to the document.
+    pub fn width(&self) -> f32 {
+        match (self.is_visible(), self.is_collapsed) {
+            (false, _) => 0.0,
+            (true, true) => COMMENTS_PANEL_COLLAPSED_WIDTH,
+            (true, false) => COMMENTS_PANEL_WIDTH,
+        }
+    }
+
+    pub fn set_comments(&mut self, comments: Vec<Comment>) {
+        self.comments = comments;
+    }
+
+    pub fn set_anchor_rects(&mut self, anchor_rects: Vec<Option<Rect<f32>>>) {
+        self.anchor_rects = anchor_rects;
+    }
+
+    /// Paints the panel in the given rect, which is right of the document.
+    pub fn paint(&mut self, painter: &mut dyn Painter, rect: Rect<f32>) {
+        self.rect = rect;
+        self.header_rect = Rect::from_positions(rect.left, rect.right, rect.top, rect.top + COMMENTS_PANEL_HEADER_HEIGHT);
+
+        painter.paint_rect(Brush::SolidColor(Color::from_rgb(0xF3, 0xF3, 0xF3)), rect);
+        painter.paint_rect(Brush::SolidColor(Color::from_rgb(0xD0, 0xD0, 0xD0)),
+            Rect::from_positions(rect.left, rect.left + 1.0, rect.top, rect.bottom));
+
+        painter.select_font(FontSpecification::new("Segoe UI", 10.0, super::painter::FontWeight::SemiBold)).unwrap();
+        let header = if self.is_collapsed {
+            self.comments.len().to_string()
+        } else {
+            format!("Comments ({})", self.comments.len())
+        };
+        painter.paint_text(Brush::SolidColor(Color::from_rgb(0x33, 0x33, 0x33)),
+            Position::new(rect.left + COMMENTS_PANEL_PADDING, rect.top + 5.0), &header, None);
+
+        if self.is_collapsed {
+            return;
+        }
+
+        let card_width = rect.width() - COMMENTS_PANEL_PADDING * 2.0;
+        let text_width = card_width - COMMENTS_PANEL_PADDING * 2.0;
+
+        painter.select_font(FontSpecification::new("Segoe UI", 9.0, super::painter::FontWeight::Regular)).unwrap();
+        let mut measure = |text: &str| painter.paint_text(Brush::SolidColor(Color::TRANSPARENT), Position::new(0.0, 0.0), text, None).width();
+        let lines: Vec<Vec<String>> = self.comments.iter()
+            .map(|comment| wrap_text(&comment.text, text_width, &mut measure))
+            .collect();
+
+        // The author and date are on the first line.
+        let heights: Vec<f32> = lines.iter()
+            .map(|lines| (lines.len() + 1) as f32 * COMMENT_LINE_HEIGHT + COMMENTS_PANEL_PADDING * 2.0)
+            .collect();
+        let anchor_tops: Vec<Option<f32>> = (0..self.comments.len())
+            .map(|index| self.anchor_rects.get(index).copied().flatten().map(|rect| rect.top))
+            .collect();
+        let tops = place_comments(&anchor_tops, &heights, self.header_rect.bottom);
+
+        painter.begin_clip_region(Rect::from_positions(rect.left, rect.right, self.header_rect.bottom, rect.bottom));
+        for (index, comment) in self.comments.iter().enumerate() {
+            let card = Rect::from_position_and_size(Position::new(rect.left + COMMENTS_PANEL_PADDING, tops[index]),
+                Size::new(card_width, heights[index]));
+            if card.bottom < self.header_rect.bottom || card.top > rect.bottom {
+                continue;
+            }
+
+            painter.paint_rect(Brush::SolidColor(Color::WHITE), card);
+            painter.paint_rect(Brush::SolidColor(Color::from_rgb(0xE9, 0x1E, 0x63)),
+                Rect::from_positions(card.left, card.left + 3.0, card.top, card.bottom));
+
+            let left = card.left + COMMENTS_PANEL_PADDING;
+            let mut y = card.top + COMMENTS_PANEL_PADDING;
+
+            painter.select_font(FontSpecification::new("Segoe UI", 9.0, super::painter::FontWeight::SemiBold)).unwrap();
+            let author_width = painter.paint_text(Brush::SolidColor(Color::from_rgb(0x22, 0x22, 0x22)),
+                Position::new(left, y), &comment.author, None).width();
+
+            painter.select_font(FontSpecification::new("Segoe UI", 9.0, super::painter::FontWeight::Regular)).unwrap();
+            painter.paint_text(Brush::SolidColor(Color::from_rgb(0x80, 0x80, 0x80)),
+                Position::new(left + author_width + COMMENTS_PANEL_PADDING, y), &comment.date_text(), None);
+
+            for line in &lines[index] {
+                y += COMMENT_LINE_HEIGHT;
+                painter.paint_text(Brush::SolidColor(Color::from_rgb(0x33, 0x33, 0x33)), Position::new(left, y), line, None);
+            }
+        }
+        painter.end_clip_region();
+    }
+}
+
+impl Widget for CommentsPanel {
+    fn rect(&self) -> Rect<f32> {
+        self.rect
+    }
+
+    fn on_mouse_enter(&mut self, _event: &mut MouseMoveEvent) {
+
+    }
+
+    /// Collapses or expands the panel when the header is clicked.
+    fn on_mouse_input(&mut self, mouse_position: Position<f32>, button: MouseButton, state: ElementState) {
+        if button == MouseButton::Left && state == ElementState::Released && self.header_rect.is_inside_inclusive(mouse_position) {
+            self.is_collapsed = !self.is_collapsed;
+        }
+    }
+
+    fn on_mouse_leave(&mut self, _event: &mut MouseMoveEvent) {
+
+    }
+
+    fn on_mouse_move(&mut self, _event: &mut MouseMoveEvent) {
+
+    }
+
+    fn on_window_focus_lost(&mut self) {
+
+    }
+
+    /// The panel is positioned next to the document when it is painted.
+    fn on_window_resize(&mut self, _window_size: Size<u32>) {
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character is 10 pixels wide.
+    fn measure(text: &str) -> f32 {
+        text.chars().count() as f32 * 10.0
+    }
+
+    #[test]
+    fn text_that_fits_is_unchanged() {
+        assert_eq!(fit_text_with_ellipsis("Report.docx", 110.0, &mut measure), "Report.docx");
+    }
+
+    #[test]
+    fn text_is_truncated_with_ellipsis() {
+        assert_eq!(fit_text_with_ellipsis("Annual Report.docx", 100.0, &mut measure), "Annual Re…");
+        assert_eq!(fit_text_with_ellipsis("Annual Report.docx", 80.0, &mut measure), "Annual…");
+        assert_eq!(fit_text_with_ellipsis("Ünïcödé", 40.0, &mut measure), "Ünï…");
+    }
+
+    #[test]
+    fn ellipsis_only_when_nothing_fits() {
+        assert_eq!(fit_text_with_ellipsis("Report.docx", 5.0, &mut measure), "…");
+    }
+
+    #[test]
+    fn typing_in_the_search_widget() {
+        let mut widget = SearchWidget::new();
+        assert!(widget.on_character('f'));
+        assert!(widget.on_character('ö'));
+        assert!(!widget.on_character('\r'));
+        assert_eq!(widget.query(), "fö");
+
+        assert!(widget.on_character(BACKSPACE));
+        assert_eq!(widget.query(), "f");
+        assert!(widget.on_character(BACKSPACE));
+        assert!(!widget.on_character(BACKSPACE));
+    }
+
+    #[test]
+    fn search_widget_status() {
+        let mut widget = SearchWidget::new();
+        assert_eq!(widget.status_text(), "");
+
+        widget.on_character('x');
+        assert_eq!(widget.status_text(), "No results");
+
+        widget.set_results(SearchResults { match_count: 5, current_match: Some(1), current_match_rect: None });
+        assert_eq!(widget.status_text(), "2 of 5");
+    }
+
+    #[test]
+    fn text_is_wrapped_between_words() {
+        assert_eq!(wrap_text("Please check this number", 120.0, &mut measure), ["Please check", "this number"]);
+        assert_eq!(wrap_text("First\nSecond line", 120.0, &mut measure), ["First", "Second line"]);
+        assert_eq!(wrap_text("Incomprehensible", 50.0, &mut measure), ["Incomprehensible"]);
+        assert_eq!(wrap_text("", 50.0, &mut measure), [""]);
+    }
+
+    #[test]
+    fn comments_are_placed_next_to_their_anchors() {
+        let tops = place_comments(&[Some(100.0), Some(40.0), None, Some(60.0)], &[30.0, 30.0, 30.0, 30.0], 20.0);
+
+        // The second comment is first, and pushes the fourth down.
+        assert_eq!(tops, [116.0, 40.0, 154.0, 78.0]);
+    }
+
+    #[test]
+    fn comments_panel_collapses_when_the_header_is_clicked() {
+        let mut panel = CommentsPanel::new();
+        assert!(!panel.is_visible());
+        assert_eq!(panel.width(), 0.0);
+
+        panel.set_comments(vec![Comment::default()]);
+        assert_eq!(panel.width(), COMMENTS_PANEL_WIDTH);
+
+        panel.header_rect = Rect::from_positions(0.0, COMMENTS_PANEL_WIDTH, 0.0, COMMENTS_PANEL_HEADER_HEIGHT);
+        panel.on_mouse_input(Position::new(10.0, 100.0), MouseButton::Left, ElementState::Released);
+        assert_eq!(panel.width(), COMMENTS_PANEL_WIDTH);
+
+        panel.on_mouse_input(Position::new(10.0, 10.0), MouseButton::Left, ElementState::Released);
+        assert_eq!(panel.width(), COMMENTS_PANEL_COLLAPSED_WIDTH);
+    }
+}
@@ -4,27 +4,43 @@
 use clap::Parser;
 
 mod application;
-mod color_parser;
-mod drawing_ml;
-mod error;
-mod fonts;
+mod arguments;
+mod export;
+mod file_watcher;
 mod gui;
 mod platform;
-mod relationships;
-mod serialize;
-mod style;
-mod text_settings;
-mod word_processing;
-mod unicode;
-mod user_settings;
-mod wp;
-
-pub const WORD_PROCESSING_XML_NAMESPACE: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
-
-#[derive(Parser, Debug, Default, Clone)]
-pub struct CommandLineArguments {
-    /// The files to open.
-    files: Vec<String>,
+mod print;
+
+// The document model is in the library, which the analysis tool uses as well.
+use uffice_lib::{
+    error,
+    fonts,
+    package,
+    relationships,
+    style,
+    text_settings,
+    word_processing,
+    user_settings,
+    wp,
+};
+
+pub use arguments::CommandLineArguments;
+
+#[cfg(windows)]
+fn register_file_types() {
+    match platform::windows::file_association::register_file_types() {
+        Ok(()) => println!("[FileAssociation] Registered uffice as a handler of the document file types"),
+        Err(error) => {
+            println!("[FileAssociation] Error: failed to register the file types: {:?}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn register_file_types() {
+    println!("[FileAssociation] Error: registering the file types is only supported on Windows");
+    std::process::exit(2);
 }
 
 fn main() {
@@ -41,6 +57,42 @@ fn main() {
         }
     }
 
+    if args.register_file_types {
+        register_file_types();
+        return;
+    }
+
+    let export_path = args.export_pdf.as_ref()
+        .or(args.export_text.as_ref())
+        .or(args.export_markdown.as_ref());
+    if let Some(output_path) = export_path {
+        let [file] = args.files.as_slice() else {
+            println!("[Export] Error: exactly one file must be given to export, but got {}", args.files.len());
+            std::process::exit(2);
+        };
+
+        let result = if args.export_pdf.is_some() {
+            export::export_pdf(file, output_path)
+        } else if args.export_text.is_some() {
+            export::export_text(file, output_path, word_processing::text::TextFormat::PlainText)
+        } else {
+            export::export_text(file, output_path, word_processing::text::TextFormat::Markdown)
+        };
+
+        if let Err(error) = result {
+            println!("[Export] Error: failed to export \"{}\": {:?}", file, error);
+            std::process::exit(1);
+        }
+
+        println!("[Export] Exported \"{}\" to {}", file, output_path.display());
+        return;
+    }
+
+    if platform::ipc::forward_to_running_instance(&args.files) {
+        println!("[IPC] Opened the files in the running instance");
+        return;
+    }
+
     gui::app::run(|window, event_loop_proxy| {
         Box::new(application::App::new(window, event_loop_proxy, args.files))
     });
@@ -0,0 +1,59 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The implementation for the platforms other than Windows and macOS, e.g.
+// Linux and the BSDs. The dialogs are shown using zenity, which is available
+// on most desktops, and are printed to the terminal when it isn't.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub fn choose_save_path(suggested_path: &Path) -> Option<PathBuf> {
+    let output = Command::new("zenity")
+        .args(["--file-selection", "--save", "--confirm-overwrite"])
+        .arg(format!("--filename={}", suggested_path.display()))
+        .output();
+
+    match output {
+        // zenity exits with 1 when the user cancelled.
+        Ok(output) if output.status.success() => {
+            let path = String::from_utf8_lossy(&output.stdout);
+            Some(PathBuf::from(path.trim_end_matches('\n')))
+        }
+        Ok(..) => None,
+        Err(e) => {
+            println!("[Platform] Error: can't ask where to save the document, because zenity failed to start: {}", e);
+            None
+        }
+    }
+}
+
+pub fn open_file_user(path: &str) {
+    println!("[Platform] Warning: opening \"{}\" in another application isn't supported on this platform", path);
+}
+
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    Command::new("xdg-open").arg(url).spawn().map(|_| ())
+}
+
+pub fn set_current_thread_name(name: &str) {
+    _ = name;
+}
+
+pub fn save_restore_arguments(arguments: crate::CommandLineArguments) {
+    _ = arguments;
+}
+
+pub fn show_message_box_blocking(title: &str, message: &str) {
+    let status = Command::new("zenity")
+        .args(["--error", "--no-markup"])
+        .arg(format!("--title={}", title))
+        .arg(format!("--text={}", message))
+        .status();
+
+    if status.is_err() {
+        eprintln!("{}: {}", title, message);
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Keeps all documents in a single instance of uffice. When uffice is started
+// while another instance is running, the files are forwarded to that instance
+// over a named pipe (Windows) or a Unix domain socket, and the new process
+// exits. The files are sent as their absolute paths, one per line.
+
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+/// Sends the files to the instance that is already running, which opens them
+/// in new tabs. Returns false when there is no running instance.
+pub fn forward_to_running_instance(files: &[String]) -> bool {
+    let Some(mut connection) = connect() else {
+        return false;
+    };
+
+    // The running instance has a different working directory.
+    let paths: Vec<PathBuf> = files.iter()
+        .map(|file| {
+            let path = PathBuf::from(file);
+            path.canonicalize().unwrap_or(path)
+        })
+        .collect();
+
+    if let Err(e) = connection.write_all(encode_paths(&paths).as_bytes()) {
+        println!("[IPC] Warning: failed to forward the files to the running instance: {}", e);
+        return false;
+    }
+
+    true
+}
+
+/// Listens for other instances forwarding their files, on a separate thread.
+/// The callback is also invoked when no files were forwarded, since the user
+/// still expects the window to be shown.
+pub fn listen<F>(on_files_received: F)
+        where F: Fn(Vec<PathBuf>) + Send + 'static {
+    std::thread::Builder::new()
+        .name(String::from("Single Instance Listener"))
+        .spawn(move || accept_connections(on_files_received))
+        .expect("Failed to spawn single instance listener thread");
+}
+
+fn encode_paths(paths: &[PathBuf]) -> String {
+    paths.iter()
+        .map(|path| format!("{}\n", path.display()))
+        .collect()
+}
+
+fn decode_paths(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn handle_connection<F>(mut connection: impl Read, on_files_received: &F)
+        where F: Fn(Vec<PathBuf>) {
+    let mut text = String::new();
+    match connection.read_to_string(&mut text) {
+        Ok(..) => on_files_received(decode_paths(&text)),
+        Err(e) => println!("[IPC] Warning: failed to receive the files of another instance: {}", e),
+    }
+}
+
+#[cfg(unix)]
+fn socket_path() -> PathBuf {
+    let directory = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    directory.join(format!("uffice-{}.sock", std::env::var("USER").unwrap_or_default()))
+}
+
+#[cfg(unix)]
+fn connect() -> Option<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(socket_path()).ok()
+}
+
+#[cfg(unix)]
+fn accept_connections<F>(on_files_received: F)
+        where F: Fn(Vec<PathBuf>) {
+    let path = socket_path();
+
+    // A socket left behind by an instance that crashed prevents binding, and
+    // nobody is listening on it, since we weren't able to connect to it.
+    _ = std::fs::remove_file(&path);
+
+    let listener = match std::os::unix::net::UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("[IPC] Warning: failed to listen on {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    for connection in listener.incoming().flatten() {
+        handle_connection(connection, &on_files_received);
+    }
+}
+
+#[cfg(windows)]
+fn pipe_name() -> String {
+    format!(r"\\.\pipe\uffice-{}", std::env::var("USERNAME").unwrap_or_default())
+}
+
+#[cfg(windows)]
+fn connect() -> Option<std::fs::File> {
+    std::fs::OpenOptions::new().write(true).open(pipe_name()).ok()
+}
+
+#[cfg(windows)]
+fn accept_connections<F>(on_files_received: F)
+        where F: Fn(Vec<PathBuf>) {
+    use std::os::windows::io::FromRawHandle;
+
+    use windows::{
+        core::PCWSTR,
+        Win32::{
+            Storage::FileSystem::PIPE_ACCESS_INBOUND,
+            System::Pipes::{
+                ConnectNamedPipe,
+                CreateNamedPipeW,
+                PIPE_READMODE_BYTE,
+                PIPE_TYPE_BYTE,
+                PIPE_UNLIMITED_INSTANCES,
+                PIPE_WAIT,
+            },
+        },
+    };
+
+    let name: Vec<u16> = pipe_name().encode_utf16().chain(std::iter::once(0)).collect();
+    loop {
+        let handle = unsafe {
+            CreateNamedPipeW(PCWSTR(name.as_ptr()), PIPE_ACCESS_INBOUND, PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+                PIPE_UNLIMITED_INSTANCES, 0, 4096, 0, None)
+        };
+
+        if handle.is_invalid() {
+            println!("[IPC] Warning: failed to create the named pipe {}", pipe_name());
+            return;
+        }
+
+        // Blocks until another instance connects. When it connected before
+        // this call, the function fails with ERROR_PIPE_CONNECTED, but the
+        // pipe can be read from anyway.
+        unsafe {
+            _ = ConnectNamedPipe(handle, None);
+        }
+
+        // The file takes ownership of the handle, and closes it when dropped.
+        let pipe = unsafe { std::fs::File::from_raw_handle(handle.0 as _) };
+        handle_connection(pipe, &on_files_received);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_survive_encoding() {
+        let paths = vec![PathBuf::from("/home/user/Report.docx"), PathBuf::from("/tmp/With Spaces.docx")];
+        assert_eq!(decode_paths(&encode_paths(&paths)), paths);
+        assert!(decode_paths(&encode_paths(&[])).is_empty());
+    }
+
+    #[test]
+    fn connection_delivers_the_paths() {
+        let paths = vec![PathBuf::from("/tmp/Letter.docx")];
+        let received = std::cell::RefCell::new(None);
+        handle_connection(encode_paths(&paths).as_bytes(), &|files| *received.borrow_mut() = Some(files));
+        assert_eq!(received.into_inner(), Some(paths));
+    }
+}
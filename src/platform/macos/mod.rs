@@ -1,9 +1,47 @@
 // Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Quotes the text as an AppleScript string literal.
+fn quote_apple_script(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Shows the save panel using AppleScript, which avoids binding to AppKit for
+/// a single dialog.
+pub fn choose_save_path(suggested_path: &Path) -> Option<PathBuf> {
+    let mut script = format!("POSIX path of (choose file name with prompt \"Save As\" default name {}",
+        quote_apple_script(&suggested_path.file_name().unwrap_or_default().to_string_lossy()));
+    if let Some(directory) = suggested_path.parent().filter(|directory| directory.is_dir()) {
+        script += &format!(" default location POSIX file {}", quote_apple_script(&directory.to_string_lossy()));
+    }
+    script += ")";
+
+    match Command::new("osascript").arg("-e").arg(script).output() {
+        // osascript fails when the user cancelled.
+        Ok(output) if output.status.success() => {
+            let path = String::from_utf8_lossy(&output.stdout);
+            Some(PathBuf::from(path.trim_end_matches('\n')))
+        }
+        Ok(..) => None,
+        Err(e) => {
+            show_message_box_blocking("Cannot Save Document", &format!("The save panel couldn't be shown: {}", e));
+            None
+        }
+    }
+}
+
 pub fn open_file_user(path: &str) {
 }
 
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    std::process::Command::new("open").arg(url).spawn().map(|_| ())
+}
+
 pub fn set_current_thread_name(name: &str) {
 }
 
@@ -15,5 +53,10 @@ pub fn save_restore_arguments(arguments: crate::CommandLineArguments) {
 }
 
 pub fn show_message_box_blocking(title: &str, message: &str) {
-    unimplemented!()
+    let script = format!("display alert {} message {} as critical",
+        quote_apple_script(title), quote_apple_script(message));
+
+    if Command::new("osascript").arg("-e").arg(script).status().is_err() {
+        eprintln!("{}: {}", title, message);
+    }
 }
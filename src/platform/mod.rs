@@ -1,26 +1,64 @@
 // Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
+pub mod ipc;
+
 #[cfg(target_os = "macos")]
 pub mod macos;
 
 #[cfg(windows)]
 pub mod windows;
 
+#[cfg(not(any(windows, target_os = "macos")))]
+pub mod fallback;
+
 #[cfg(target_os = "macos")]
 pub use self::macos as implementation;
 
 #[cfg(windows)]
 pub use self::windows as implementation;
 
+#[cfg(not(any(windows, target_os = "macos")))]
+pub use self::fallback as implementation;
+
 pub fn show_message_box_blocking(title: &str, message: &str) {
     implementation::show_message_box_blocking(title, message);
 }
 
+/// Asks the user where to save a document, suggesting the given path. Returns
+/// None when the user cancelled.
+pub fn choose_save_path(suggested_path: &std::path::Path) -> Option<std::path::PathBuf> {
+    implementation::choose_save_path(suggested_path)
+}
+
 pub fn open_file_user(path: &str) {
     implementation::open_file_user(path);
 }
 
+/// The schemes of the links that are opened. The URL of a link comes from the
+/// document, and other schemes (e.g. `file:` or `ms-msdt:`) can start any
+/// application the system associates with them.
+const OPENABLE_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Parses the URL, and checks that it has one of the [OPENABLE_URL_SCHEMES].
+fn openable_url(url: &str) -> std::io::Result<url::Url> {
+    let url = url::Url::parse(url)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+    if !OPENABLE_URL_SCHEMES.contains(&url.scheme()) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+            format!("links with the scheme \"{}\" aren't opened", url.scheme())));
+    }
+
+    Ok(url)
+}
+
+/// Opens the URL in the default browser of the user, or the application
+/// associated with its scheme (e.g. `mailto:`).
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    implementation::open_url(openable_url(url)?.as_str())
+}
+
 pub fn set_current_thread_name(name: &str) {
     implementation::set_current_thread_name(name);
 }
@@ -30,3 +68,19 @@ pub fn set_current_thread_name(name: &str) {
 pub fn save_restore_arguments(arguments: crate::CommandLineArguments) {
     implementation::save_restore_arguments(arguments)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_and_mail_links_are_opened() {
+        assert!(openable_url("https://example.com/?a=1&b=2").is_ok());
+        assert!(openable_url("http://example.com").is_ok());
+        assert!(openable_url("mailto:someone@example.com").is_ok());
+
+        assert!(openable_url("file:///C:/Windows/System32/calc.exe").is_err());
+        assert!(openable_url("ms-msdt:/id PCWDiagnostic").is_err());
+        assert!(openable_url("calc.exe & notepad.exe").is_err());
+    }
+}
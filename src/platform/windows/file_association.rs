@@ -0,0 +1,84 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Registers uffice as a handler of the WordprocessingML file types for the
+// current user, so documents can be opened with uffice from the Explorer and
+// uffice can be chosen in the Default Apps settings.
+//
+// [Microsoft Learn](https://learn.microsoft.com/en-us/windows/win32/shell/default-programs)
+
+use windows::Win32::UI::Shell::{
+    SHChangeNotify,
+    SHCNE_ASSOCCHANGED,
+    SHCNF_IDLIST,
+};
+
+use super::registry::{
+    PredefinedRegistryKey,
+    RegistryError,
+    RegistryKey,
+};
+
+/// The programmatic identifier of the documents opened by uffice.
+const PROG_ID: &str = "Uffice.Document";
+
+/// The key under HKEY_CURRENT_USER describing the capabilities of uffice,
+/// which is listed under RegisteredApplications.
+const CAPABILITIES_PATH: &str = "Software\\Uffice\\Capabilities";
+
+#[derive(Debug)]
+pub enum FileAssociationError {
+    /// The path of the executable couldn't be determined.
+    CurrentExe(std::io::Error),
+
+    Registry(RegistryError),
+}
+
+impl From<RegistryError> for FileAssociationError {
+    fn from(error: RegistryError) -> Self {
+        Self::Registry(error)
+    }
+}
+
+/// Registers uffice as a handler for the supported document types, for the
+/// current user only, so no administrator privileges are needed.
+pub fn register_file_types() -> Result<(), FileAssociationError> {
+    let executable = std::env::current_exe().map_err(FileAssociationError::CurrentExe)?;
+    let executable = executable.display();
+
+    let classes = RegistryKey::create(PredefinedRegistryKey::CurrentUser, "Software\\Classes")?;
+
+    let prog_id = classes.create_subkey(PROG_ID)?;
+    prog_id.set_string_value("", "Word Document")?;
+    prog_id.create_subkey("DefaultIcon")?
+        .set_string_value("", &format!("\"{}\",0", executable))?;
+    prog_id.create_subkey("shell\\open\\command")?
+        .set_string_value("", &format!("\"{}\" \"%1\"", executable))?;
+
+    let capabilities = RegistryKey::create(PredefinedRegistryKey::CurrentUser, CAPABILITIES_PATH)?;
+    capabilities.set_string_value("ApplicationName", "Uffice")?;
+    capabilities.set_string_value("ApplicationDescription", "An alpha-stage word processor for WordprocessingML documents.")?;
+    let capability_associations = capabilities.create_subkey("FileAssociations")?;
+
+    for extension in crate::application::SUPPORTED_DOCUMENT_EXTENSIONS {
+        let extension = format!(".{}", extension);
+
+        // The Open With list of the extension, which doesn't take over the
+        // default handler chosen by the user.
+        classes.create_subkey(&format!("{}\\OpenWithProgids", extension))?
+            .set_string_value(PROG_ID, "")?;
+
+        capability_associations.set_string_value(&extension, PROG_ID)?;
+    }
+
+    RegistryKey::create(PredefinedRegistryKey::CurrentUser, "Software\\RegisteredApplications")?
+        .set_string_value("Uffice", CAPABILITIES_PATH)?;
+
+    // Let the Explorer know the associations changed, so the icons and the
+    // Open With menu are updated.
+    unsafe {
+        SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, None, None);
+    }
+
+    Ok(())
+}
@@ -1,10 +1,17 @@
 // Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
+use std::{
+    ffi::{c_void, OsString},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
 use windows::{
     core::{
         PCSTR,
         PCWSTR,
+        PWSTR,
         HRESULT,
     },
     w,
@@ -15,7 +22,17 @@ use windows::{
             HWND,
         },
         UI::{
-            Shell::ShellExecuteW,
+            Controls::Dialogs::{
+                GetSaveFileNameW,
+                OFN_OVERWRITEPROMPT,
+                OFN_PATHMUSTEXIST,
+                OPENFILENAMEW,
+            },
+            Shell::{
+                SHAddToRecentDocs,
+                SHARD_PATHW,
+                ShellExecuteW,
+            },
             WindowsAndMessaging::{
                 MB_ICONERROR,
                 MB_OK,
@@ -34,10 +51,53 @@ use windows::{
     },
 };
 
+pub mod file_association;
 pub mod registry;
 
 const OPEN_VERB: PCWSTR = w!("open");
 
+/// The longest path the save dialog can return, in UTF-16 code units.
+const MAX_SAVE_PATH_LENGTH: usize = 1024;
+
+/// Encodes the string as a null-terminated wide string.
+fn to_wide_string(text: &std::ffi::OsStr) -> Vec<u16> {
+    text.encode_wide().chain(std::iter::once(0)).collect()
+}
+
+pub fn choose_save_path(suggested_path: &Path) -> Option<PathBuf> {
+    let mut file_name = to_wide_string(suggested_path.file_name().unwrap_or_default());
+    file_name.resize(MAX_SAVE_PATH_LENGTH, 0);
+
+    let directory = to_wide_string(suggested_path.parent().unwrap_or(Path::new("")).as_os_str());
+    let extension = to_wide_string(suggested_path.extension().unwrap_or_default());
+
+    // Pairs of descriptions and patterns, ended by an empty string.
+    let filter = match suggested_path.extension().and_then(|extension| extension.to_str()) {
+        Some("txt") => "Plain Text\0*.txt\0\0",
+        Some("md") => "Markdown\0*.md\0\0",
+        _ => "Word Documents\0*.docx;*.docm\0Word Templates\0*.dotx;*.dotm\0\0",
+    };
+    let filter: Vec<u16> = filter.encode_utf16().collect();
+
+    let mut dialog = OPENFILENAMEW {
+        lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+        lpstrFilter: PCWSTR(filter.as_ptr()),
+        lpstrFile: PWSTR(file_name.as_mut_ptr()),
+        nMaxFile: file_name.len() as u32,
+        lpstrInitialDir: PCWSTR(directory.as_ptr()),
+        lpstrDefExt: PCWSTR(extension.as_ptr()),
+        Flags: OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST,
+        ..Default::default()
+    };
+
+    if !unsafe { GetSaveFileNameW(&mut dialog) }.as_bool() {
+        return None;
+    }
+
+    let length = file_name.iter().position(|character| *character == 0).unwrap_or(file_name.len());
+    Some(PathBuf::from(OsString::from_wide(&file_name[..length])))
+}
+
 pub fn open_file_user(path: &str) {
     println!("Path: {}", path);
     let path: Vec<u16> = path.encode_utf16().collect();
@@ -70,6 +130,22 @@ pub fn open_file_user(path: &str) {
     // })()
 }
 
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    // The URL is given to the application of its scheme as is, without going
+    // through a shell that would interpret characters like `&`.
+    let url = to_wide_string(std::ffi::OsStr::new(url));
+    let result = unsafe {
+        ShellExecuteW(HWND::default(), OPEN_VERB, PCWSTR(url.as_ptr()), None, None, SW_SHOWNORMAL)
+    }.0;
+
+    // ShellExecuteW returns a value greater than 32 when it succeeded.
+    if result <= 32 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
 pub unsafe fn load_symbol(library_name: &str, symbol_name: &str) -> Option<unsafe extern "system" fn() -> isize> {
     let Ok(kernel) = LoadLibraryA(PCSTR(library_name.as_ptr())) else {
         return None;
@@ -112,6 +188,23 @@ pub fn save_restore_arguments(arguments: crate::CommandLineArguments) {
     }
 }
 
+/// Adds the document to the recent documents of the application, which are
+/// listed in the Jump List of the taskbar button, so the user can reopen it
+/// from the taskbar.
+///
+/// Note: The shell only lists the document when the application is registered
+/// as a handler of its file type.
+pub fn add_to_jump_list(path: &Path) {
+    let path: Vec<u16> = path.as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    unsafe {
+        SHAddToRecentDocs(SHARD_PATHW.0 as u32, Some(path.as_ptr() as *const c_void));
+    }
+}
+
 pub fn show_message_box_blocking(title: &str, message: &str) {
     unsafe {
         MessageBoxA(None, windows::core::PCSTR(message.as_ptr()), windows::core::PCSTR(title.as_ptr()), MB_ICONERROR | MB_OK);
@@ -18,10 +18,14 @@ use windows::{
             HKEY_PERFORMANCE_TEXT,
             HKEY_USERS,
             KEY_READ,
+            KEY_WRITE,
+            REG_OPTION_NON_VOLATILE,
             RegCloseKey,
+            RegCreateKeyExA,
             RegEnumValueA,
             RegOpenKeyExA,
             RegQueryInfoKeyA,
+            RegSetValueExA,
             REG_BINARY,
             REG_DWORD,
             REG_DWORD_BIG_ENDIAN,
@@ -160,6 +164,48 @@ impl RegistryKey {
         }
     }
 
+    /// Opens the subkey of the predefined key for reading and writing, and
+    /// creates it (and its parents) when it doesn't exist.
+    pub fn create(key: PredefinedRegistryKey, path: &str) -> Result<Self, RegistryError> {
+        Self::create_key(key.into(), path)
+    }
+
+    /// Opens the subkey for reading and writing, and creates it when it
+    /// doesn't exist. The key must be opened for writing, e.g. using
+    /// [RegistryKey::create].
+    pub fn create_subkey(&self, name: &str) -> Result<RegistryKey, RegistryError> {
+        Self::create_key(self.handle, name)
+    }
+
+    fn create_key(parent: HKEY, path: &str) -> Result<Self, RegistryError> {
+        let path = null_terminated(path);
+        let mut handle: HKEY = Default::default();
+        let result = unsafe {
+            RegCreateKeyExA(parent, PCSTR(path.as_ptr()), 0, PCSTR::null(), REG_OPTION_NON_VOLATILE,
+                KEY_READ | KEY_WRITE, None, &mut handle, None)
+        };
+
+        match result {
+            ERROR_SUCCESS => Ok(Self{ handle }),
+            _ => Err(RegistryError::Unknown(result.0))
+        }
+    }
+
+    /// Sets the value to a string. An empty name sets the default value of
+    /// the key.
+    pub fn set_string_value(&self, name: &str, value: &str) -> Result<(), RegistryError> {
+        let name = null_terminated(name);
+        let value = null_terminated(value);
+        let result = unsafe {
+            RegSetValueExA(self.handle, PCSTR(name.as_ptr()), 0, REG_SZ, Some(&value))
+        };
+
+        match result {
+            ERROR_SUCCESS => Ok(()),
+            _ => Err(RegistryError::Unknown(result.0))
+        }
+    }
+
     pub fn info(&self) -> RegistryKeyInformation {
         let mut info: RegistryKeyInformation = Default::default();
         unsafe {
@@ -257,6 +303,14 @@ impl RegistryKey {
 
 }
 
+/// The ANSI functions expect strings terminated by a NUL character.
+fn null_terminated(string: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(string.len() + 1);
+    bytes.extend_from_slice(string.as_bytes());
+    bytes.push(0);
+    bytes
+}
+
 impl Drop for RegistryKey {
     fn drop(&mut self) {
         if self.handle.is_invalid() {
@@ -0,0 +1,192 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Printing documents. The view paints the document with the NullPainter, in
+// the layout shown on the screen, and the recorded commands are divided over
+// the pages using the page rects of the view. The pages are then replayed on
+// the printer using the GDI on Windows, and written to a PDF file elsewhere.
+
+use crate::gui::{
+    painter::{
+        null::PaintCommand,
+        Painter,
+    },
+    Position,
+    Rect,
+    Size,
+};
+
+#[derive(Debug)]
+pub enum PrintError {
+    /// The document doesn't contain any pages.
+    NoPages,
+
+    /// The user closed the print dialog without printing.
+    Cancelled,
+
+    /// The GDI function with the given name failed.
+    #[cfg(windows)]
+    Gdi(&'static str),
+
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for PrintError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A page to print, with the commands relative to the top-left corner of the
+/// page, in points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintPage {
+    pub size: Size<f32>,
+    pub commands: Vec<PaintCommand>,
+}
+
+impl PrintPage {
+    /// Paints the commands of the page with the painter.
+    pub fn replay(&self, painter: &mut dyn Painter) {
+        for command in &self.commands {
+            command.replay(painter, Position::default(), None);
+        }
+    }
+}
+
+/// Puts the painted commands on the page they were painted on, relative to
+/// the top-left corner of that page. Commands outside of the pages (and clip
+/// regions, since the pages clip the content already) are dropped.
+pub fn paginate(page_rects: &[Rect<f32>], commands: &[PaintCommand]) -> Vec<PrintPage> {
+    let mut pages: Vec<PrintPage> = page_rects.iter()
+        .map(|page_rect| PrintPage { size: page_rect.size(), commands: Vec::new() })
+        .collect();
+
+    let find_page = |position: Position<f32>| {
+        page_rects.iter()
+            .position(|page_rect| page_rect.is_inside_inclusive(position))
+            .map(|index| (index, Position::new(position.x() - page_rects[index].left, position.y() - page_rects[index].top)))
+    };
+
+    // The font is selected again on every page it is used on, so the pages
+    // can be printed on their own.
+    let mut selected_font = None;
+    let mut page_fonts: Vec<Option<&PaintCommand>> = vec![None; pages.len()];
+
+    for command in commands {
+        match command {
+            PaintCommand::SelectFont { .. } => selected_font = Some(command),
+            PaintCommand::PaintRect { brush, rect } => {
+                if let Some((index, position)) = find_page(rect.position()) {
+                    pages[index].commands.push(PaintCommand::PaintRect {
+                        brush: *brush,
+                        rect: Rect::from_position_and_size(position, rect.size()),
+                    });
+                }
+            }
+            PaintCommand::PaintImage { image, rect } => {
+                if let Some((index, position)) = find_page(rect.position()) {
+                    pages[index].commands.push(PaintCommand::PaintImage {
+                        image: image.clone(),
+                        rect: Rect::from_position_and_size(position, rect.size()),
+                    });
+                }
+            }
+            PaintCommand::PaintText { brush, position, text, size } => {
+                let Some((index, position)) = find_page(*position) else {
+                    continue;
+                };
+
+                if let Some(font) = selected_font {
+                    if !page_fonts[index].is_some_and(|page_font| std::ptr::eq(page_font, font)) {
+                        pages[index].commands.push(font.clone());
+                        page_fonts[index] = Some(font);
+                    }
+                }
+
+                pages[index].commands.push(PaintCommand::PaintText {
+                    brush: *brush,
+                    position,
+                    text: text.clone(),
+                    size: *size,
+                });
+            }
+            PaintCommand::BeginClipRegion(..) | PaintCommand::EndClipRegion => (),
+        }
+    }
+
+    pages
+}
+
+/// Asks the user which printer to use, and prints the pages on it.
+#[cfg(windows)]
+pub fn print_pages(document_name: &str, pages: &[PrintPage]) -> Result<(), PrintError> {
+    if pages.is_empty() {
+        return Err(PrintError::NoPages);
+    }
+
+    crate::gui::painter::print::PrintPainter::print(document_name, pages)
+}
+
+/// Printing isn't supported on this platform yet, so the pages are written to
+/// a PDF file in the temporary directory instead, which can be printed using
+/// another application.
+#[cfg(not(windows))]
+pub fn print_pages(document_name: &str, pages: &[PrintPage]) -> Result<(), PrintError> {
+    if pages.is_empty() {
+        return Err(PrintError::NoPages);
+    }
+
+    let path = std::env::temp_dir().join(format!("{}.pdf", document_name));
+    let pdf_pages: Vec<_> = pages.iter().map(crate::export::convert_to_pdf_page).collect();
+    crate::export::pdf::write_pdf(&pdf_pages, &mut std::fs::File::create(&path)?)?;
+
+    println!("[Print] Printing isn't supported on this platform, the pages were written to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::{painter::FontWeight, Brush, Color};
+
+    fn text(position: Position<f32>) -> PaintCommand {
+        PaintCommand::PaintText {
+            brush: Brush::SolidColor(Color::BLACK),
+            position,
+            text: String::from("Text"),
+            size: Size::new(20.0, 10.0),
+        }
+    }
+
+    #[test]
+    fn commands_are_put_on_their_page() {
+        let page_rects = [
+            Rect::from_position_and_size(Position::new(-50.0, 20.0), Size::new(100.0, 200.0)),
+            Rect::from_position_and_size(Position::new(-50.0, 250.0), Size::new(100.0, 200.0)),
+        ];
+        let font = PaintCommand::SelectFont {
+            family_name: String::from("Calibri"),
+            size: 11.0,
+            weight: FontWeight::Regular,
+            italic: false,
+        };
+        let commands = [
+            PaintCommand::BeginClipRegion(page_rects[0]),
+            font.clone(),
+            text(Position::new(-40.0, 30.0)),
+            text(Position::new(-40.0, 260.0)),
+            text(Position::new(-40.0, 270.0)),
+            PaintCommand::EndClipRegion,
+
+            // Between the pages.
+            text(Position::new(-40.0, 230.0)),
+        ];
+
+        let pages = paginate(&page_rects, &commands);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].size, Size::new(100.0, 200.0));
+        assert_eq!(pages[0].commands, [font.clone(), text(Position::new(10.0, 10.0))]);
+        assert_eq!(pages[1].commands, [font, text(Position::new(10.0, 10.0)), text(Position::new(10.0, 20.0))]);
+    }
+}
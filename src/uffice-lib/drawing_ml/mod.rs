@@ -0,0 +1,420 @@
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+pub mod style;
+
+use crate::{relationships::{Relationship, Relationships}, gui::{painter::Image, Size}};
+use roxmltree as xml;
+use std::{cell::RefCell, rc::Rc};
+use crate::Emu;
+
+#[derive(Debug)]
+pub struct DrawingObject {
+    extent: Option<Extent>,
+    graphic: GraphicObject,
+
+    /// The positioning of floating objects, which is None for inline objects.
+    anchor: Option<Anchor>,
+
+    /// The markup of the `<wp:inline>` or `<wp:anchor>` element, which is
+    /// written back unchanged when the document is saved.
+    markup: Rc<str>,
+}
+
+impl DrawingObject {
+    pub fn parse_inline_object(node: &xml::Node, relationships: &Relationships) -> Self {
+        let mut object = DrawingObject {
+            extent: None,
+            graphic: GraphicObject::Empty,
+            anchor: None,
+            markup: Rc::from(&node.document().input_text()[node.range()]),
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "extent" => object.extent = Extent::parse_xml(&child),
+                "graphic" => object.graphic = GraphicObject::parse_xml(&child, relationships),
+
+                _ => ()
+            }
+        }
+
+        object
+    }
+
+    /// 20.4.2.3 anchor (Anchor for Floating DrawingML Object)
+    pub fn parse_anchor_object(node: &xml::Node, relationships: &Relationships) -> Self {
+        let mut object = Self::parse_inline_object(node, relationships);
+        object.anchor = Some(Anchor::parse_xml(node));
+        object
+    }
+
+    pub fn anchor(&self) -> Option<&Anchor> {
+        self.anchor.as_ref()
+    }
+
+    pub fn markup(&self) -> &str {
+        &self.markup
+    }
+
+    /// The relationship id of the embedded picture, when the relationship
+    /// doesn't exist in the package, e.g. when the media was stripped. A
+    /// placeholder is painted instead.
+    pub fn missing_relationship_id(&self) -> Option<&str> {
+        match &self.graphic {
+            GraphicObject::Empty => None,
+            GraphicObject::Picture(..) => self.blip()?.missing_relationship_id.as_deref(),
+        }
+    }
+
+    pub fn is_picture(&self) -> bool {
+        matches!(self.graphic, GraphicObject::Picture(..))
+    }
+
+    /// The decoded image of the picture, or None when this isn't a picture or
+    /// the image couldn't be loaded.
+    pub fn image(&self) -> Option<&Image> {
+        self.blip()?.image.as_ref()
+    }
+
+    /// The reason the embedded image of the picture couldn't be decoded.
+    pub fn image_decode_error(&self) -> Option<&str> {
+        self.blip()?.decode_error.as_deref()
+    }
+
+    fn blip(&self) -> Option<&Blip> {
+        match &self.graphic {
+            GraphicObject::Empty => None,
+            GraphicObject::Picture(picture) => picture.fill.as_ref()?.blip.as_ref(),
+        }
+    }
+
+    pub fn size(&self) -> Size<f32> {
+        match self.extent {
+            // The size is in points, like the rest of the layout. It is
+            // converted to pixels using the zoom when painting.
+            Some(extent) => Size::new(
+                Emu(extent.width).get_pts(),
+                Emu(extent.height).get_pts(),
+            ),
+            None => Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// 20.4.2.7 extent (Drawing Object Size)
+pub struct Extent {
+    width: u32,
+    height: u32,
+}
+
+impl Extent {
+    /// Parses the size, which is None when an attribute is missing or
+    /// malformed, making the object empty.
+    pub fn parse_xml(node: &xml::Node) -> Option<Self> {
+        Some(Self {
+            width: node.attribute("cx")?.parse().ok()?,
+            height: node.attribute("cy")?.parse().ok()?,
+        })
+    }
+}
+
+/// How text flows around a floating object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WrapMode {
+    /// 20.4.2.15 wrapNone: the object is in front of or behind the text.
+    None,
+
+    /// 20.4.2.20 wrapTopAndBottom: text is only placed above and below the
+    /// object.
+    TopAndBottom,
+
+    /// wrapSquare, wrapThrough and wrapTight, where the text flows around the
+    /// sides of the object, which isn't supported yet.
+    Sides,
+}
+
+/// 20.4.2.10 positionH (Horizontal Positioning)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HorizontalPosition {
+    Left,
+    Center,
+    Right,
+
+    /// The offset from the start of the text margin.
+    Offset(Emu<i64>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    pub wrap: WrapMode,
+    pub horizontal_position: HorizontalPosition,
+
+    /// The minimum distance between the object and the text above it.
+    pub distance_top: Emu<u32>,
+
+    /// The minimum distance between the object and the text below it.
+    pub distance_bottom: Emu<u32>,
+}
+
+impl Anchor {
+    pub fn parse_xml(node: &xml::Node) -> Self {
+        let distance = |name| Emu(node.attribute(name).and_then(|value| value.parse().ok()).unwrap_or(0));
+
+        let mut anchor = Anchor {
+            wrap: WrapMode::None,
+            horizontal_position: HorizontalPosition::Left,
+            distance_top: distance("distT"),
+            distance_bottom: distance("distB"),
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "wrapNone" => anchor.wrap = WrapMode::None,
+                "wrapTopAndBottom" => anchor.wrap = WrapMode::TopAndBottom,
+                "wrapSquare" | "wrapThrough" | "wrapTight" => anchor.wrap = WrapMode::Sides,
+                "positionH" => {
+                    if let Some(position) = parse_horizontal_position(&child) {
+                        anchor.horizontal_position = position;
+                    }
+                }
+                _ => ()
+            }
+        }
+
+        anchor
+    }
+}
+
+fn parse_horizontal_position(node: &xml::Node) -> Option<HorizontalPosition> {
+    for child in node.children() {
+        match (child.tag_name().name(), child.text()) {
+            // The inside and outside of the page are the left and right
+            // margins of odd pages, and since we don't mirror margins, we
+            // treat every page that way.
+            ("align", Some("left" | "inside")) => return Some(HorizontalPosition::Left),
+            ("align", Some("center")) => return Some(HorizontalPosition::Center),
+            ("align", Some("right" | "outside")) => return Some(HorizontalPosition::Right),
+            ("posOffset", Some(offset)) => return offset.trim().parse().ok().map(|offset| HorizontalPosition::Offset(Emu(offset))),
+            _ => ()
+        }
+    }
+
+    None
+}
+
+#[derive(Debug)]
+pub enum GraphicObject {
+    Empty,
+
+    Picture(Picture)
+}
+
+impl GraphicObject {
+    /// Parses the <a:graphic> element. Only pictures are supported, so other
+    /// graphics, like charts, are left empty.
+    pub fn parse_xml(node: &xml::Node, relationships: &Relationships) -> Self {
+        for child in node.children() {
+            if child.tag_name().name() == "graphicData" {
+                for child in child.children() {
+                    if child.tag_name().name() == "pic" {
+                        return GraphicObject::Picture(Picture::parse_xml(&child, relationships));
+                    }
+                }
+            }
+        }
+
+        GraphicObject::Empty
+    }
+}
+
+#[derive(Debug)]
+pub struct Picture {
+    fill: Option<PictureFill>,
+}
+
+impl Picture {
+    pub fn parse_xml(node: &xml::Node, relationships: &Relationships) -> Self {
+        let mut picture = Picture {
+            fill: None
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "blipFill" => picture.fill = Some(PictureFill::parse_xml(&child, relationships)),
+
+                _ => (),
+            }
+        }
+
+        picture
+    }
+}
+
+#[derive(Debug)]
+pub struct PictureFill {
+    blip: Option<Blip>
+}
+
+impl PictureFill {
+    pub fn parse_xml(node: &xml::Node, relationships: &Relationships) -> Self {
+        let mut fill = PictureFill {
+            blip: None
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "blip" => fill.blip = Some(Blip::parse_xml(&child, relationships)),
+
+                _ => (),
+            }
+        }
+
+        fill
+    }
+}
+
+pub struct Blip {
+    embedded: Option<Rc<RefCell<Relationship>>>,
+
+    /// The r:embed that wasn't found in the relationships.
+    missing_relationship_id: Option<String>,
+
+    image: Option<Image>,
+
+    /// Why the embedded image couldn't be decoded, e.g. an unsupported format
+    /// such as EMF.
+    decode_error: Option<String>,
+}
+
+impl Blip {
+    pub fn parse_xml(node: &xml::Node, relationships: &Relationships) -> Self {
+        let mut blip = Blip {
+            embedded: None,
+            missing_relationship_id: None,
+            image: None,
+            decode_error: None,
+        };
+
+        for attribute in node.attributes() {
+            match attribute.name() {
+                "embed" => {
+                    let Some(relationship) = relationships.find(attribute.value()) else {
+                        blip.missing_relationship_id = Some(String::from(attribute.value()));
+                        continue;
+                    };
+                    let relationship = relationship.clone();
+
+                    let rela = relationship.as_ref().borrow();
+                    assert_eq!(rela.relation_type, crate::relationships::RelationshipType::Image);
+
+                    match Image::decode(&rela.data) {
+                        Ok(image) => blip.image = Some(image),
+                        Err(e) => blip.decode_error = Some(e.to_string()),
+                    }
+                    drop(rela);
+
+                    blip.embedded = Some(relationship);
+                }
+                _ => ()
+            }
+        }
+
+        blip
+    }
+}
+
+impl core::fmt::Debug for Blip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Blip")
+            .field("embedded", &self.embedded)
+            .field("missing_relationship_id", &self.missing_relationship_id)
+            .field("image", &self.image)
+            .field("decode_error", &self.decode_error)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_of_one_inch_extent() {
+        let object = DrawingObject {
+            extent: Some(Extent { width: 914400, height: 457200 }),
+            graphic: GraphicObject::Empty,
+            anchor: None,
+            markup: Rc::from(""),
+        };
+
+        assert_eq!(object.size(), Size::new(72.0, 36.0));
+        // An inch is 96 pixels on a display without scaling.
+        assert_eq!(object.size().width() * 96.0 / 72.0, 96.0);
+    }
+
+    #[test]
+    fn size_of_large_extent() {
+        let object = DrawingObject {
+            extent: Some(Extent { width: u32::MAX, height: 12700 }),
+            graphic: GraphicObject::Empty,
+            anchor: None,
+            markup: Rc::from(""),
+        };
+
+        assert!((object.size().width() - u32::MAX as f32 / 12700.0).abs() < 0.1);
+        assert_eq!(object.size().height(), 1.0);
+    }
+
+    #[test]
+    fn missing_embedded_picture() {
+        let document = xml::Document::parse(concat!(
+            r#"<wp:inline xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" "#,
+            r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" "#,
+            r#"xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture" "#,
+            r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+            r#"<wp:extent cx="914400" cy="914400"/>"#,
+            r#"<a:graphic><a:graphicData><pic:pic><pic:blipFill><a:blip r:embed="rId42"/></pic:blipFill></pic:pic></a:graphicData></a:graphic>"#,
+            r#"</wp:inline>"#,
+        )).unwrap();
+
+        let object = DrawingObject::parse_inline_object(&document.root_element(), &Relationships::empty());
+        assert_eq!(object.missing_relationship_id(), Some("rId42"));
+        assert_eq!(object.size(), Size::new(72.0, 72.0));
+    }
+
+    #[test]
+    fn charts_and_malformed_extents_are_left_empty() {
+        let document = xml::Document::parse(concat!(
+            r#"<wp:inline xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" "#,
+            r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" "#,
+            r#"xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart">"#,
+            r#"<wp:extent cx="one inch"/>"#,
+            r#"<a:graphic><a:graphicData><c:chart/></a:graphicData></a:graphic>"#,
+            r#"</wp:inline>"#,
+        )).unwrap();
+
+        let object = DrawingObject::parse_inline_object(&document.root_element(), &Relationships::empty());
+        assert!(!object.is_picture());
+        assert_eq!(object.size(), Size::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn anchor_with_top_and_bottom_wrapping() {
+        let document = xml::Document::parse(concat!(
+            r#"<wp:anchor xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" distT="12700" distB="25400">"#,
+            r#"<wp:positionH relativeFrom="margin"><wp:align>center</wp:align></wp:positionH>"#,
+            r#"<wp:positionV relativeFrom="paragraph"><wp:posOffset>0</wp:posOffset></wp:positionV>"#,
+            r#"<wp:wrapTopAndBottom/>"#,
+            r#"</wp:anchor>"#,
+        )).unwrap();
+
+        assert_eq!(Anchor::parse_xml(&document.root_element()), Anchor {
+            wrap: WrapMode::TopAndBottom,
+            horizontal_position: HorizontalPosition::Center,
+            distance_top: Emu(12700),
+            distance_bottom: Emu(25400),
+        });
+    }
+}
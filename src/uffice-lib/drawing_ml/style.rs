@@ -0,0 +1,446 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::rc::Rc;
+
+use crate::{
+    color_parser,
+    gui::Color,
+    serialize::FromXmlStandalone,
+    wp::diagnostics::Diagnostic,
+};
+
+use roxmltree as xml;
+
+/// 20.1.6.2 clrScheme (Color Scheme)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorScheme {
+    pub dark1: Color,
+    pub light1: Color,
+    pub dark2: Color,
+    pub light2: Color,
+    pub accent1: Color,
+    pub accent2: Color,
+    pub accent3: Color,
+    pub accent4: Color,
+    pub accent5: Color,
+    pub accent6: Color,
+    pub hyperlink: Color,
+    pub followed_hyperlink: Color,
+}
+
+impl ColorScheme {
+    /// Looks up the color of a slot of the scheme, by the name DrawingML uses
+    /// (ST_SchemeColorVal, e.g. `tx1` or `accent1`) or the name of the
+    /// WordprocessingML `w:themeColor` attribute (ST_ThemeColor, e.g.
+    /// `text1`). The text and background colors are mapped to the dark and
+    /// light colors, like the default color mapping does.
+    pub fn color(&self, name: &str) -> Option<Color> {
+        Some(match name {
+            "dk1" | "dark1" | "tx1" | "text1" => self.dark1,
+            "lt1" | "light1" | "bg1" | "background1" => self.light1,
+            "dk2" | "dark2" | "tx2" | "text2" => self.dark2,
+            "lt2" | "light2" | "bg2" | "background2" => self.light2,
+            "accent1" => self.accent1,
+            "accent2" => self.accent2,
+            "accent3" => self.accent3,
+            "accent4" => self.accent4,
+            "accent5" => self.accent5,
+            "accent6" => self.accent6,
+            "hlink" | "hyperlink" => self.hyperlink,
+            "folHlink" | "followedHyperlink" => self.followed_hyperlink,
+            _ => return None,
+        })
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        Some(match name {
+            "dk1" => &mut self.dark1,
+            "lt1" => &mut self.light1,
+            "dk2" => &mut self.dark2,
+            "lt2" => &mut self.light2,
+            "accent1" => &mut self.accent1,
+            "accent2" => &mut self.accent2,
+            "accent3" => &mut self.accent3,
+            "accent4" => &mut self.accent4,
+            "accent5" => &mut self.accent5,
+            "accent6" => &mut self.accent6,
+            "hlink" => &mut self.hyperlink,
+            "folHlink" => &mut self.followed_hyperlink,
+            _ => return None,
+        })
+    }
+}
+
+impl Default for ColorScheme {
+    /// The colors of the default Office theme.
+    fn default() -> Self {
+        Self {
+            dark1: Color::BLACK,
+            light1: Color::WHITE,
+            dark2: Color::from_rgb(0x44, 0x54, 0x6A),
+            light2: Color::from_rgb(0xE7, 0xE6, 0xE6),
+            accent1: Color::from_rgb(0x44, 0x72, 0xC4),
+            accent2: Color::from_rgb(0xED, 0x7D, 0x31),
+            accent3: Color::from_rgb(0xA5, 0xA5, 0xA5),
+            accent4: Color::from_rgb(0xFF, 0xC0, 0x00),
+            accent5: Color::from_rgb(0x5B, 0x9B, 0xD5),
+            accent6: Color::from_rgb(0x70, 0xAD, 0x47),
+            hyperlink: Color::from_rgb(0x05, 0x63, 0xC1),
+            followed_hyperlink: Color::from_rgb(0x95, 0x4F, 0x72),
+        }
+    }
+}
+
+impl ColorScheme {
+    /// Parses the scheme, where the colors that are missing or invalid keep
+    /// the color of the default theme.
+    pub fn from_xml(node: &xml::Node, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let mut result = Self::default();
+
+        for child in node.children().filter(|child| child.is_element()) {
+            let color = child.children()
+                .filter(|element| element.is_element())
+                .find_map(|element| parse_color_element(&element, &result));
+
+            match (result.color_mut(child.tag_name().name()), color) {
+                (Some(slot), Some(color)) => *slot = color,
+                (Some(..), None) => diagnostics.push(Diagnostic::warning("a:clrScheme",
+                    format!("invalid color for <a:{}>", child.tag_name().name()))),
+                (None, _) => (),
+            }
+        }
+
+        result
+    }
+}
+
+/// Parses an element of the 20.1.2.3 color choice group, e.g. `<a:srgbClr>`
+/// or `<a:schemeClr>`, and applies the color transforms it contains.
+pub fn parse_color_element(node: &xml::Node, color_scheme: &ColorScheme) -> Option<Color> {
+    let mut color = match node.tag_name().name() {
+        "srgbClr" => color_parser::parse_color(node.attribute("val")?).ok()?,
+        // The system color is taken from the computer that saved the document.
+        "sysClr" => color_parser::parse_color(node.attribute("lastClr")?).ok()?,
+        "schemeClr" => color_scheme.color(node.attribute("val")?)?,
+        _ => return None,
+    };
+
+    for transform in node.children().filter(|child| child.is_element()) {
+        // The values are in thousandths of a percent.
+        let Some(value) = transform.attribute("val").and_then(|value| value.parse::<f32>().ok()) else {
+            continue;
+        };
+        let value = value / 100_000.0;
+
+        color = match transform.tag_name().name() {
+            "tint" => tint_color(color, value),
+            "shade" => shade_color(color, value),
+            "lumMod" => modify_luminance(color, |luminance| luminance * value),
+            "lumOff" => modify_luminance(color, |luminance| luminance + value),
+            _ => color,
+        };
+    }
+
+    Some(color)
+}
+
+/// Lightens the color, where a tint of 0.0 gives white and a tint of 1.0
+/// leaves the color as is.
+pub fn tint_color(color: Color, tint: f32) -> Color {
+    modify_luminance(color, |luminance| luminance * tint + (1.0 - tint))
+}
+
+/// Darkens the color, where a shade of 0.0 gives black and a shade of 1.0
+/// leaves the color as is.
+pub fn shade_color(color: Color, shade: f32) -> Color {
+    modify_luminance(color, |luminance| luminance * shade)
+}
+
+/// Changes the luminance of the color in the HSL color space.
+fn modify_luminance(color: Color, modify: impl FnOnce(f32) -> f32) -> Color {
+    let [red, green, blue] = [color.red(), color.green(), color.blue()].map(|component| component as f32 / 255.0);
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let luminance = (max + min) / 2.0;
+
+    let (hue, saturation) = if max == min {
+        (0.0, 0.0)
+    } else {
+        let delta = max - min;
+        let saturation = if luminance > 0.5 { delta / (2.0 - max - min) } else { delta / (max + min) };
+        let hue = if max == red {
+            (green - blue) / delta + if green < blue { 6.0 } else { 0.0 }
+        } else if max == green {
+            (blue - red) / delta + 2.0
+        } else {
+            (red - green) / delta + 4.0
+        };
+        (hue / 6.0, saturation)
+    };
+
+    let luminance = modify(luminance).clamp(0.0, 1.0);
+
+    let [red, green, blue] = if saturation == 0.0 {
+        [luminance; 3]
+    } else {
+        let q = if luminance < 0.5 { luminance * (1.0 + saturation) } else { luminance + saturation - luminance * saturation };
+        let p = 2.0 * luminance - q;
+        [hue + 1.0 / 3.0, hue, hue - 1.0 / 3.0].map(|t| hue_to_rgb(p, q, t))
+    };
+
+    let [red, green, blue] = [red, green, blue].map(|component| (component * 255.0).round() as u8);
+    Color::from_rgba(red, green, blue, color.alpha())
+}
+
+fn hue_to_rgb(p: f32, q: f32, t: f32) -> f32 {
+    let t = t.rem_euclid(1.0);
+    if t < 1.0 / 6.0 {
+        p + (q - p) * 6.0 * t
+    } else if t < 1.0 / 2.0 {
+        q
+    } else if t < 2.0 / 3.0 {
+        p + (q - p) * (2.0 / 3.0 - t) * 6.0
+    } else {
+        p
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FontCollection {
+    pub latin: TextFont,
+    pub east_asian: TextFont,
+    pub complex_script: TextFont,
+}
+
+impl Default for FontCollection {
+    fn default() -> Self {
+        let font = Rc::from(String::new());
+        Self {
+            latin: TextFont { typeface: Rc::clone(&font) },
+            east_asian: TextFont { typeface: Rc::clone(&font) },
+            complex_script: TextFont { typeface: font },
+        }
+    }
+}
+
+impl FromXmlStandalone for FontCollection {
+    type ParseError = ParseError;
+
+    fn from_xml(node: &xml::Node) -> Result<Self, Self::ParseError>
+            where Self: Sized {
+        let mut result = Self::default();
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "latin" => {
+                    result.latin = TextFont::from_xml(&child)?;
+                }
+                "ea" => {
+                    result.east_asian = TextFont::from_xml(&child)?;
+                }
+                "cs" => {
+                    result.complex_script = TextFont::from_xml(&child)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FontScheme {
+    pub major_font: FontCollection,
+    pub minor_font: FontCollection,
+}
+
+impl FromXmlStandalone for FontScheme {
+    type ParseError = ParseError;
+
+    fn from_xml(node: &xml::Node) -> Result<Self, Self::ParseError> {
+        let mut major_font = FontCollection::default();
+        let mut minor_font = FontCollection::default();
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "majorFont" => {
+                    major_font = FontCollection::from_xml(&child)?;
+                }
+                "minorFont" => {
+                    minor_font = FontCollection::from_xml(&child)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            major_font,
+            minor_font,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    InvalidXmlStructureRoot,
+}
+
+/// 20.1.4 Styles
+#[derive(Clone, Debug, Default)]
+pub struct StyleSettings {
+    pub theme_elements: ThemeElements,
+}
+
+impl StyleSettings {
+    /// Parses the theme from either the `<a:theme>` element, or the root of
+    /// the theme part.
+    pub fn from_xml(node: &xml::Node, diagnostics: &mut Vec<Diagnostic>) -> Result<Self, ParseError> {
+        let mut theme_elements = Default::default();
+
+        // The root of the document can also contain comments and processing
+        // instructions, so only look at the elements.
+        let node = if node.is_element() {
+            *node
+        } else {
+            let mut elements = node.children().filter(|child| child.is_element());
+            match (elements.next(), elements.next()) {
+                (Some(element), None) => element,
+                _ => return Err(ParseError::InvalidXmlStructureRoot),
+            }
+        };
+
+        if node.tag_name().name() != "theme" {
+            return Err(ParseError::InvalidXmlStructureRoot);
+        }
+
+        for child in node.children() {
+            if child.tag_name().name() == "themeElements" {
+                theme_elements = ThemeElements::from_xml(&child, diagnostics)?;
+            }
+        }
+
+        Ok(Self {
+            theme_elements,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TextFont {
+    /// The typeface, or the empty string if no default is specified.
+    pub typeface: Rc<str>,
+}
+
+impl FromXmlStandalone for TextFont {
+    type ParseError = ParseError;
+
+    fn from_xml(node: &xml::Node) -> Result<Self, Self::ParseError> {
+        Ok(Self {
+            typeface: Rc::from(node.attribute("typeface").unwrap_or_default()),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ThemeElements {
+    pub color_scheme: ColorScheme,
+    pub font_scheme: FontScheme,
+}
+
+impl ThemeElements {
+    pub fn from_xml(node: &xml::Node, diagnostics: &mut Vec<Diagnostic>) -> Result<Self, ParseError> {
+        let mut color_scheme = ColorScheme::default();
+        let mut font_scheme = FontScheme::default();
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "clrScheme" => {
+                    color_scheme = ColorScheme::from_xml(&child, diagnostics);
+                }
+                "fontScheme" => {
+                    font_scheme = FontScheme::from_xml(&child)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            color_scheme,
+            font_scheme,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_font_scheme() {
+        let document = xml::Document::parse(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<!-- Theme -->
+<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" name="Office Theme">
+    <a:themeElements>
+        <a:fontScheme name="Office">
+            <a:majorFont><a:latin typeface="Calibri Light"/></a:majorFont>
+            <a:minorFont><a:latin typeface="Calibri"/></a:minorFont>
+        </a:fontScheme>
+    </a:themeElements>
+</a:theme>"#).unwrap();
+
+        for node in [document.root(), document.root_element()] {
+            let settings = StyleSettings::from_xml(&node, &mut Vec::new()).unwrap();
+            assert_eq!(&*settings.theme_elements.font_scheme.major_font.latin.typeface, "Calibri Light");
+            assert_eq!(&*settings.theme_elements.font_scheme.minor_font.latin.typeface, "Calibri");
+        }
+    }
+
+    #[test]
+    fn theme_color_scheme() {
+        let document = xml::Document::parse(r#"<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main">
+    <a:themeElements>
+        <a:clrScheme name="Custom">
+            <a:dk1><a:sysClr val="windowText" lastClr="111111"/></a:dk1>
+            <a:lt1><a:sysClr val="window" lastClr="FFFFFF"/></a:lt1>
+            <a:accent1><a:srgbClr val="FF0000"/></a:accent1>
+            <a:accent2><a:srgbClr val="red"/></a:accent2>
+        </a:clrScheme>
+    </a:themeElements>
+</a:theme>"#).unwrap();
+
+        let mut diagnostics = Vec::new();
+        let settings = StyleSettings::from_xml(&document.root_element(), &mut diagnostics).unwrap();
+        let color_scheme = &settings.theme_elements.color_scheme;
+        assert_eq!(color_scheme.color("text1"), Some(Color::from_rgb(0x11, 0x11, 0x11)));
+        assert_eq!(color_scheme.color("bg1"), Some(Color::WHITE));
+        assert_eq!(color_scheme.color("accent1"), Some(Color::RED));
+        assert_eq!(color_scheme.color("accent2"), Some(ColorScheme::default().accent2));
+        assert_eq!(color_scheme.color("phClr"), None);
+
+        // The invalid color keeps the color of the default theme.
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].source, "a:clrScheme");
+    }
+
+    #[test]
+    fn scheme_colors_with_transforms() {
+        let document = xml::Document::parse(r#"<a:solidFill xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main">
+    <a:schemeClr val="accent1"><a:lumMod val="50000"/></a:schemeClr>
+    <a:schemeClr val="accent1"><a:tint val="50000"/></a:schemeClr>
+    <a:srgbClr val="808080"><a:shade val="0"/></a:srgbClr>
+</a:solidFill>"#).unwrap();
+
+        let color_scheme = ColorScheme { accent1: Color::RED, ..Default::default() };
+        let colors: Vec<_> = document.root_element().children()
+            .filter(|child| child.is_element())
+            .map(|child| parse_color_element(&child, &color_scheme))
+            .collect();
+        assert_eq!(colors, [
+            Some(Color::from_rgb(0x80, 0, 0)),
+            Some(Color::from_rgb(0xFF, 0x80, 0x80)),
+            Some(Color::BLACK),
+        ]);
+    }
+}
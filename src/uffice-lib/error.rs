@@ -0,0 +1,110 @@
+// Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::fmt;
+
+use roxmltree as xml;
+
+use crate::word_processing::{doc::DocError, odf::OdfError};
+
+/// Describes why a document couldn't be loaded. The errors of a part are
+/// wrapped in [Error::Part], so the user can see which part failed.
+#[derive(Debug)]
+pub enum Error {
+    RoXmlTree(xml::Error),
+    StdNumParseInt(std::num::ParseIntError),
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+
+    /// The Word Binary File couldn't be read.
+    WordBinary(DocError),
+
+    /// The OpenDocument Text file couldn't be read.
+    OpenDocument(OdfError),
+
+    /// A part without which the document can't be shown is missing from the
+    /// package.
+    MissingPart(String),
+
+    /// An element without which the part can't be used is missing.
+    MissingElement(&'static str),
+
+    /// A required attribute of the element is missing.
+    MissingAttribute {
+        element: &'static str,
+        attribute: &'static str,
+    },
+
+    /// The element has an attribute value that isn't allowed.
+    InvalidValue {
+        element: &'static str,
+        value: String,
+    },
+
+    /// Something in the part with the given name failed.
+    Part {
+        name: String,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Attributes the error to the part with the given name.
+    pub fn in_part(self, name: &str) -> Self {
+        Self::Part { name: String::from(name), error: Box::new(self) }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RoXmlTree(error) => write!(f, "the XML is malformed: {}", error),
+            Self::StdNumParseInt(error) => write!(f, "a number is invalid: {}", error),
+            Self::Io(error) => write!(f, "the file couldn't be read: {}", error),
+            Self::Zip(error) => write!(f, "the package is damaged: {}", error),
+            Self::WordBinary(error) => write!(f, "the Word document couldn't be read: {:?}", error),
+            Self::OpenDocument(error) => write!(f, "the OpenDocument file couldn't be read: {:?}", error),
+            Self::MissingPart(name) => write!(f, "the part {} is missing", name),
+            Self::MissingElement(element) => write!(f, "the <{}> element is missing", element),
+            Self::MissingAttribute { element, attribute } => write!(f, "the <{}> element has no {} attribute", element, attribute),
+            Self::InvalidValue { element, value } => write!(f, "the <{}> element has an invalid value: \"{}\"", element, value),
+            Self::Part { name, error } => write!(f, "{}: {}", name, error),
+        }
+    }
+}
+
+impl From<xml::Error> for Error {
+    fn from(error: xml::Error) -> Self {
+        Self::RoXmlTree(error)
+    }
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(error: std::num::ParseIntError) -> Self {
+        Self::StdNumParseInt(error)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(error: zip::result::ZipError) -> Self {
+        Self::Zip(error)
+    }
+}
+
+impl From<DocError> for Error {
+    fn from(error: DocError) -> Self {
+        Self::WordBinary(error)
+    }
+}
+
+impl From<OdfError> for Error {
+    fn from(error: OdfError) -> Self {
+        Self::OpenDocument(error)
+    }
+}
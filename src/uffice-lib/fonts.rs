@@ -0,0 +1,275 @@
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{atomic::{AtomicUsize, Ordering}, Arc},
+};
+
+use font_kit::{family_name::FamilyName, handle::Handle};
+
+use crate::wp::font_table::{EmbeddedFontStyle, FontDefinition, GenericFontFamily};
+
+/// Looks for fonts in the given directories.
+pub struct DirectoryFontSource {
+    directories: Vec<PathBuf>,
+}
+
+impl DirectoryFontSource {
+    pub fn new(directories: Vec<PathBuf>) -> Self {
+        Self {
+            directories
+        }
+    }
+}
+
+type SelectionResult = Result<font_kit::family_handle::FamilyHandle, font_kit::error::SelectionError>;
+
+struct FontSearcher<'a> {
+    family_name: &'a str
+}
+
+impl<'a> FontSearcher<'a> {
+    fn search(&self, dir: &PathBuf) -> Option<font_kit::family_handle::FamilyHandle> {
+        if !dir.is_dir() {
+            return None;
+        }
+
+        if let Ok(iter) = std::fs::read_dir(dir) {
+            for entry in iter {
+                if entry.is_err() {
+                    continue;
+                }
+
+                let entry = entry.unwrap();
+                if !entry.path().is_dir() {
+                    continue;
+                }
+
+                if entry.file_name().eq_ignore_ascii_case(self.family_name) {
+                    if let Some(result) = self.search_family_name_dir(&entry.path()) {
+                        return Some(result);
+                    }
+                }
+
+                if let Some(result) = self.search(&entry.path()) {
+                    return Some(result);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn search_family_name_dir(&self, dir: &PathBuf) -> Option<font_kit::family_handle::FamilyHandle> {
+        let iter = std::fs::read_dir(dir);
+        if iter.is_err() {
+            return None;
+        }
+
+        let mut handle = font_kit::family_handle::FamilyHandle::new();
+
+        let mut index = 0;
+
+        let iter = iter.unwrap();
+        for entry in iter {
+            if entry.is_err() {
+                continue;
+            }
+
+            let entry = entry.unwrap();
+            if entry.path().is_dir() {
+                continue;
+            }
+
+            if let Some(extension) = entry.path().extension() {
+                if !extension.to_string_lossy().eq_ignore_ascii_case("ttf") {
+                    continue;
+                }
+
+                handle.push(font_kit::handle::Handle::from_path(entry.path(), index));
+                index += 1;
+            }
+        }
+
+        if handle.is_empty() {
+            return None;
+        }
+
+        Some(handle)
+    }
+
+}
+
+impl font_kit::source::Source for DirectoryFontSource {
+    fn all_fonts(&self) -> Result<Vec<font_kit::handle::Handle>, font_kit::error::SelectionError> {
+        todo!()
+    }
+
+    fn all_families(&self) -> Result<Vec<String>, font_kit::error::SelectionError> {
+        todo!()
+    }
+
+    fn select_family_by_name(&self, family_name: &str) -> SelectionResult {
+        for directory in &self.directories {
+            if !directory.is_dir() {
+                continue;
+            }
+
+            let searcher = FontSearcher{family_name};
+            if let Some(result) = searcher.search(directory) {
+                return Ok(result);
+            }
+        }
+
+        Err(font_kit::error::SelectionError::NotFound)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        todo!()
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+        todo!()
+    }
+}
+
+/// A font embedded in the package of a document (17.8.1), which is already
+/// deobfuscated.
+struct EmbeddedFont {
+    family_name: String,
+    style: EmbeddedFontStyle,
+    data: Arc<Vec<u8>>,
+}
+
+/// The fonts of a document: the fonts embedded in its package, and the
+/// alternative names and generic families of the fonts of its font table,
+/// which are used when a font isn't installed. The painters load the fonts of
+/// a document in the cache of that document.
+pub struct DocumentFontSource {
+    /// Identifies the fonts of this document in the caches of the text
+    /// calculators, which is never reused, unlike the address.
+    id: usize,
+
+    embedded_fonts: Vec<EmbeddedFont>,
+
+    /// The names to look for when a font isn't installed, by the lowercase
+    /// name of the font.
+    fallbacks: HashMap<String, Vec<FamilyName>>,
+}
+
+static NEXT_DOCUMENT_FONT_SOURCE_ID: AtomicUsize = AtomicUsize::new(1);
+
+impl DocumentFontSource {
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_DOCUMENT_FONT_SOURCE_ID.fetch_add(1, Ordering::Relaxed),
+            embedded_fonts: Vec::new(),
+            fallbacks: HashMap::new(),
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn add_embedded_font(&mut self, family_name: &str, style: EmbeddedFontStyle, data: Vec<u8>) {
+        self.embedded_fonts.push(EmbeddedFont {
+            family_name: String::from(family_name),
+            style,
+            data: Arc::new(data),
+        });
+    }
+
+    /// Remembers the alternative name and the generic family of the font of
+    /// the font table.
+    pub fn add_font_definition(&mut self, font: &FontDefinition) {
+        let mut family_names = Vec::new();
+        if let Some(alt_name) = &font.alt_name {
+            family_names.push(FamilyName::Title(alt_name.clone()));
+        }
+
+        family_names.extend(font.generic_family().map(|family| match family {
+            GenericFontFamily::Serif => FamilyName::Serif,
+            GenericFontFamily::SansSerif => FamilyName::SansSerif,
+            GenericFontFamily::Monospace => FamilyName::Monospace,
+            GenericFontFamily::Cursive => FamilyName::Cursive,
+            GenericFontFamily::Fantasy => FamilyName::Fantasy,
+        }));
+
+        if !family_names.is_empty() {
+            self.fallbacks.insert(font.name.to_lowercase(), family_names);
+        }
+    }
+
+    /// Whether the document has an embedded font or fallbacks for the family,
+    /// i.e. whether the font loaded for it can differ from the installed one.
+    pub fn provides(&self, family_name: &str) -> bool {
+        self.embedded_fonts.iter().any(|font| font.family_name.eq_ignore_ascii_case(family_name))
+            || self.fallbacks.contains_key(&family_name.to_lowercase())
+    }
+
+    /// Finds the embedded font of the family with the style, or another
+    /// style of the family when that one isn't embedded.
+    pub fn select_embedded(&self, family_name: &str, bold: bool, italic: bool) -> Option<Handle> {
+        let fonts: Vec<&EmbeddedFont> = self.embedded_fonts.iter()
+            .filter(|font| font.family_name.eq_ignore_ascii_case(family_name))
+            .collect();
+
+        let font = fonts.iter()
+            .find(|font| font.style.is_bold() == bold && font.style.is_italic() == italic)
+            .or_else(|| fonts.iter().find(|font| font.style == EmbeddedFontStyle::Regular))
+            .or_else(|| fonts.first())?;
+        Some(Handle::from_memory(font.data.clone(), 0))
+    }
+
+    /// The names to look for when the font isn't installed, before falling
+    /// back to the fallback font.
+    pub fn fallback_family_names(&self, family_name: &str) -> &[FamilyName] {
+        self.fallbacks.get(&family_name.to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Debug for DocumentFontSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocumentFontSource")
+            .field("id", &self.id)
+            .field("embedded_fonts", &self.embedded_fonts.iter().map(|font| (&font.family_name, font.style)).collect::<Vec<_>>())
+            .field("fallbacks", &self.fallbacks.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Generates font sources based on the platform.
+pub fn resolve_font_sources() -> Vec<Box<(dyn font_kit::source::Source + 'static)>> {
+    #[cfg(target_os = "windows")]
+    let mut sources = vec![];
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::path::Path;
+        let str = format!("{}\\Microsoft\\FontCache\\4\\CloudFonts", env!("LOCALAPPDATA"));
+
+        match Path::new(&str).canonicalize() {
+            Ok(path) => {
+                sources.push(path);
+            }
+            Err(e) => {
+                println!("[ResolveFontSources] Failed to locate Windows FontCache \"{}\": {:?}", str, e);
+                std::process::exit(0)
+            }
+        }
+    }
+
+    vec![
+        Box::new(font_kit::source::SystemSource::new()),
+
+        #[cfg(target_os = "windows")]
+        Box::new(DirectoryFontSource::new(
+            sources
+        ))
+    ]
+}
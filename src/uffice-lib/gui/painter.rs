@@ -0,0 +1,373 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+use bitflags::bitflags;
+use unicode_segmentation::UnicodeSegmentation;
+use super::{Color, Size};
+use crate::fonts::DocumentFontSource;
+
+// The tests of the application use the mock as well, so it is compiled
+// outside of the tests of this crate too.
+pub mod mock;
+
+pub mod null;
+
+#[derive(Clone, Copy, Debug)]
+pub enum FontSelectionError {
+    /// Failed to access the resource associated with the font.
+    CannotAccessResource,
+
+    /// Failed to find the font with the specified options.
+    NotFound,
+}
+
+/// https://learn.microsoft.com/en-us/typography/opentype/spec/os2#usweightclass
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub enum FontWeight {
+    Custom(f32),
+
+    Thin,
+    ExtraLight,
+    Light,
+    SemiLight,
+    #[default] Regular,
+    Medium,
+    SemiBold,
+    Bold,
+    ExtraBold,
+    Black,
+}
+
+impl From<FontWeight> for font_kit::properties::Weight {
+    fn from(value: FontWeight) -> Self {
+        use font_kit::properties::Weight;
+        match value {
+            FontWeight::Custom(weight) => Weight(weight),
+
+            FontWeight::Thin => Weight::THIN,
+            FontWeight::ExtraLight => Weight::EXTRA_LIGHT,
+            FontWeight::Light => Weight(350.0),
+            FontWeight::SemiLight => Weight::LIGHT,
+            FontWeight::Regular => Weight::NORMAL,
+            FontWeight::Medium => Weight::MEDIUM,
+            FontWeight::SemiBold => Weight::SEMIBOLD,
+            FontWeight::Bold => Weight::BOLD,
+            FontWeight::ExtraBold => Weight::EXTRA_BOLD,
+            FontWeight::Black => Weight::BLACK,
+        }
+    }
+}
+
+impl From<FontWeight> for f32 {
+    /// Convert the FontWeight into the industry-standard numeric format.
+    fn from(value: FontWeight) -> Self {
+        match value {
+            FontWeight::Custom(value) => value,
+            FontWeight::Thin => 100.0,
+            FontWeight::ExtraLight => 200.0,
+            FontWeight::Light => 300.0,
+            FontWeight::SemiLight => 350.0,
+            FontWeight::Regular => 400.0,
+            FontWeight::Medium => 500.0,
+            FontWeight::SemiBold => 600.0,
+            FontWeight::Bold => 700.0,
+            FontWeight::ExtraBold => 800.0,
+            FontWeight::Black => 900.0,
+        }
+    }
+}
+
+bitflags! {
+    pub struct FontStyle: u32 {
+        const NORMAL = 0;
+        const BOLD = 1;
+        const ITALIC = 2;
+        const UNDERLINE = 4;
+        const STRIKEOUT = 8;
+    }
+}
+
+/// Specifies what font to use.
+#[derive(Debug, Clone, Copy)]
+pub struct FontSpecification<'a> {
+    family_name: &'a str,
+    size: f32,
+    weight: FontWeight,
+    italic: bool,
+
+    /// The space added after every character, in points, which is negative
+    /// for condensed text.
+    character_spacing: f32,
+
+    /// The factor the text is stretched (or compressed) horizontally by.
+    horizontal_scale: f32,
+}
+
+impl<'a> FontSpecification<'a> {
+    pub fn new(family_name: &'a str, size: f32, weight: FontWeight) -> FontSpecification<'a> {
+        Self {
+            family_name,
+            size,
+            weight,
+            italic: false,
+            character_spacing: 0.0,
+            horizontal_scale: 1.0,
+        }
+    }
+
+    /// Selects the italic style of the font instead of the upright one.
+    pub fn with_italic(self, italic: bool) -> FontSpecification<'a> {
+        Self {
+            italic,
+            ..self
+        }
+    }
+
+    /// Adds the `character_spacing` after every character of the measured
+    /// text.
+    pub fn with_character_spacing(self, character_spacing: f32) -> FontSpecification<'a> {
+        Self {
+            character_spacing,
+            ..self
+        }
+    }
+
+    /// Stretches the measured text horizontally by the `horizontal_scale`.
+    pub fn with_horizontal_scale(self, horizontal_scale: f32) -> FontSpecification<'a> {
+        Self {
+            horizontal_scale,
+            ..self
+        }
+    }
+
+    pub fn family_name(&self) -> &'a str {
+        self.family_name
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    pub fn weight(&self) -> FontWeight {
+        self.weight
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.italic
+    }
+
+    pub fn character_spacing(&self) -> f32 {
+        self.character_spacing
+    }
+
+    pub fn horizontal_scale(&self) -> f32 {
+        self.horizontal_scale
+    }
+
+    /// The properties for selecting the font file from a font_kit source.
+    pub fn font_kit_properties(&self) -> font_kit::properties::Properties {
+        font_kit::properties::Properties {
+            weight: self.weight.into(),
+            style: if self.italic { font_kit::properties::Style::Italic } else { font_kit::properties::Style::Normal },
+            ..Default::default()
+        }
+    }
+}
+
+/// The vertical metrics of a font, in the same unit as the size of the font.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FontMetrics {
+    /// The distance from the baseline to the top of the tallest glyphs.
+    pub ascent: f32,
+
+    /// The distance from the baseline to the bottom of the lowest glyphs, as
+    /// a positive value.
+    pub descent: f32,
+
+    /// The extra space the font designer recommends between two lines.
+    pub line_gap: f32,
+}
+
+impl FontMetrics {
+    /// The distance between the baselines of two consecutive lines.
+    pub fn line_height(&self) -> f32 {
+        self.ascent + self.descent + self.line_gap
+    }
+
+    /// Converts the metrics of a font loaded by font_kit, which are in font
+    /// units, to the given font size.
+    pub fn from_font_kit(metrics: &font_kit::metrics::Metrics, size: f32) -> Self {
+        let scale = size / metrics.units_per_em as f32;
+        Self {
+            ascent: metrics.ascent * scale,
+
+            // The descent is negative in font_kit, since it's below the baseline.
+            descent: -metrics.descent * scale,
+            line_gap: metrics.line_gap * scale,
+        }
+    }
+}
+
+/// A decoded image with 8-bit RGBA pixels, which are shared between the
+/// clones, so painters can cache their resources by the address of the
+/// pixels.
+#[derive(Clone, PartialEq)]
+pub struct Image {
+    size: Size<u32>,
+    pixels: Rc<[u8]>,
+}
+
+impl Image {
+    /// Decodes an image file, such as a PNG or JPEG file.
+    pub fn decode(data: &[u8]) -> Result<Self, image::ImageError> {
+        let image = image::load_from_memory(data)?.into_rgba8();
+        Ok(Self {
+            size: Size::new(image.width(), image.height()),
+            pixels: Rc::from(image.into_raw()),
+        })
+    }
+
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    /// The pixels row by row, with four bytes per pixel: red, green, blue and
+    /// alpha.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get the color of the pixel at the given coordinates.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let index = (y as usize * self.size.width() as usize + x as usize) * 4;
+        Color::from_rgba(self.pixels[index], self.pixels[index + 1], self.pixels[index + 2], self.pixels[index + 3])
+    }
+}
+
+impl std::fmt::Debug for Image {
+    /// Custom formatter to avoid dumping the pixels.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// Calculate properties about text in order to do layout without the need of
+/// claiming the Painter. This allows us to do layout in the background while
+/// the main UI thread can still render and run the main loop.
+pub trait TextCalculator {
+
+    /// Calculates the natural size of the text in the font, without the
+    /// character spacing and horizontal scale of the specification.
+    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError>;
+
+    fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError>;
+
+    /// Calculates the size the text takes up in a document, which is the
+    /// natural size, stretched by the horizontal scale, plus the character
+    /// spacing after every grapheme.
+    fn measure_text(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+        let size = self.calculate_text_size(font, text)?;
+        if font.character_spacing == 0.0 && font.horizontal_scale == 1.0 {
+            return Ok(size);
+        }
+
+        let grapheme_count = text.graphemes(true).count() as f32;
+        let width = size.width() * font.horizontal_scale + font.character_spacing * grapheme_count;
+        Ok(Size::new(width.max(0.0), size.height()))
+    }
+
+    /// Runs the jobs that measure text before it is needed, e.g. on multiple
+    /// threads. The sizes aren't returned, so this only helps text
+    /// calculators that remember what they measured, like the cached text
+    /// calculator of the application, and the others don't run the jobs at
+    /// all.
+    fn measure_ahead(&mut self, _jobs: Vec<MeasureJob>) {
+    }
+
+    /// Uses the fonts of the document before the installed fonts, or only the
+    /// installed fonts when None. Text calculators that can't load fonts from
+    /// memory ignore this.
+    fn set_document_fonts(&mut self, _fonts: Option<Arc<DocumentFontSource>>) {
+    }
+
+}
+
+/// Measures text with the given text calculator, which is a text calculator
+/// of the thread the job runs on.
+pub type MeasureJob = Box<dyn FnOnce(&mut dyn TextCalculator) + Send>;
+
+/// Creates a text calculator that measures the same as the text calculator
+/// of a painter, which is used to measure text on another thread.
+pub type TextCalculatorFactory = Arc<dyn Fn() -> Box<dyn TextCalculator> + Send + Sync>;
+
+impl TextCalculator for Box<dyn TextCalculator> {
+    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+        self.as_mut().calculate_text_size(font, text)
+    }
+
+    fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError> {
+        self.as_mut().font_metrics(font)
+    }
+
+    fn measure_ahead(&mut self, jobs: Vec<MeasureJob>) {
+        self.as_mut().measure_ahead(jobs)
+    }
+
+    fn set_document_fonts(&mut self, fonts: Option<Arc<DocumentFontSource>>) {
+        self.as_mut().set_document_fonts(fonts)
+    }
+}
+
+/// Borrows the shared text calculator for every measurement only, so the
+/// painter it belongs to can paint in between, e.g. the pages of a document
+/// that is still being laid out.
+impl TextCalculator for Rc<RefCell<dyn TextCalculator>> {
+    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+        self.borrow_mut().calculate_text_size(font, text)
+    }
+
+    fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError> {
+        self.borrow_mut().font_metrics(font)
+    }
+
+    fn measure_ahead(&mut self, jobs: Vec<MeasureJob>) {
+        self.borrow_mut().measure_ahead(jobs)
+    }
+
+    fn set_document_fonts(&mut self, fonts: Option<Arc<DocumentFontSource>>) {
+        self.borrow_mut().set_document_fonts(fonts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_image() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_raw(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]).unwrap()
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+
+        let image = Image::decode(png.get_ref()).unwrap();
+        assert_eq!(image.size(), Size::new(2, 1));
+        assert_eq!(image.pixel(0, 0), Color::RED);
+        assert_eq!(image.pixel(1, 0), Color::from_rgba(0, 0, 255, 128));
+
+        assert!(Image::decode(b"not an image").is_err());
+    }
+
+    #[test]
+    fn measured_text_includes_character_spacing_and_scale() {
+        let mut text_calculator = mock::MockTextCalculator::new(10.0, 2.0);
+        let font = FontSpecification::new("Calibri", 11.0, FontWeight::Regular);
+        assert_eq!(text_calculator.measure_text(font, "abc").unwrap(), Size::new(30.0, 11.0));
+
+        let font = font.with_horizontal_scale(0.5).with_character_spacing(2.0);
+        assert_eq!(text_calculator.measure_text(font, "abc").unwrap(), Size::new(21.0, 11.0));
+    }
+}
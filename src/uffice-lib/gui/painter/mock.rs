@@ -0,0 +1,45 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use crate::gui::Size;
+
+use super::{
+    FontMetrics,
+    FontSelectionError,
+    FontSpecification,
+    TextCalculator,
+};
+
+/// A [TextCalculator] that doesn't use any platform fonts, but returns
+/// deterministic metrics instead. Every character has the same width (like a
+/// monospace font) and the height of the text is the font size, so layout
+/// results can be asserted on in tests. The ascent of the font is the font
+/// size, so the line height is the font size plus the line gap.
+#[derive(Clone, Copy, Debug)]
+pub struct MockTextCalculator {
+    pub char_width: f32,
+    pub line_gap: f32,
+}
+
+impl MockTextCalculator {
+    pub fn new(char_width: f32, line_gap: f32) -> Self {
+        Self {
+            char_width,
+            line_gap,
+        }
+    }
+}
+
+impl TextCalculator for MockTextCalculator {
+    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+        Ok(Size::new(text.chars().count() as f32 * self.char_width, font.size()))
+    }
+
+    fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError> {
+        Ok(FontMetrics {
+            ascent: font.size(),
+            descent: 0.0,
+            line_gap: self.line_gap,
+        })
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// This file contains a text calculator that doesn't need any fonts, which is
+// used when no window or GPU is available, for example in headless
+// environments such as CI runners.
+
+use crate::gui::Size;
+
+use super::{
+    FontMetrics,
+    FontSelectionError,
+    FontSpecification,
+    TextCalculator,
+};
+
+/// The width of a character relative to the font size. Since there are no
+/// fonts available, every character is treated as if it has the same width.
+const CHARACTER_WIDTH_FACTOR: f32 = 0.5;
+
+/// The ascent, descent and line gap relative to the font size, which add up
+/// to a line height of 1.2 times the font size.
+pub const ASCENT_FACTOR: f32 = 0.8;
+const DESCENT_FACTOR: f32 = 0.2;
+const LINE_GAP_FACTOR: f32 = 0.2;
+
+/// A [TextCalculator] returning deterministic metrics, without needing any
+/// fonts installed on the system.
+#[derive(Debug, Default)]
+pub struct NullTextCalculator;
+
+impl NullTextCalculator {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TextCalculator for NullTextCalculator {
+    fn calculate_text_size(&mut self, font: FontSpecification, text: &str) -> Result<Size<f32>, FontSelectionError> {
+        let width = text.chars().count() as f32 * font.size() * CHARACTER_WIDTH_FACTOR;
+        Ok(Size::new(width, font.size()))
+    }
+
+    fn font_metrics(&mut self, font: FontSpecification) -> Result<FontMetrics, FontSelectionError> {
+        Ok(FontMetrics {
+            ascent: font.size() * ASCENT_FACTOR,
+            descent: font.size() * DESCENT_FACTOR,
+            line_gap: font.size() * LINE_GAP_FACTOR,
+        })
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The conversions to the types of mltg, used by the Win32 painter of the
+// application. They are implemented next to the types, since the application
+// can't implement From between the types of two other crates.
+
+use super::{painter::FontWeight, Color, Position, Rect, Size};
+
+impl From<Rect<f32>> for mltg::Rect<f32> {
+    fn from(value: Rect<f32>) -> Self {
+        Self::from_points(
+            (value.left, value.top),
+            (value.right, value.bottom)
+        )
+    }
+}
+
+impl From<Color> for mltg::Rgba<f32> {
+    fn from(value: Color) -> Self {
+        Self::new(
+            value.red() as f32 / 255.0,
+            value.green() as f32 / 255.0,
+            value.blue() as f32 / 255.0,
+            value.alpha() as f32 / 255.0
+        )
+    }
+}
+
+impl From<Position<f32>> for mltg::Point<f32> {
+    fn from(value: Position<f32>) -> Self {
+        Self::new(value.x(), value.y())
+    }
+}
+
+impl From<mltg::Size<f32>> for Size<f32> {
+    fn from(value: mltg::Size<f32>) -> Self {
+        Self::new(value.width, value.height)
+    }
+}
+
+impl From<Size<f32>> for mltg::Size<f32> {
+    fn from(value: Size<f32>) -> Self {
+        Self::new(value.width, value.height)
+    }
+}
+
+impl From<FontWeight> for mltg::FontWeight {
+    fn from(value: FontWeight) -> Self {
+        match value {
+            FontWeight::Custom(_weight) => todo!("mltg is missing this API"),
+
+            FontWeight::Thin => mltg::FontWeight::Thin,
+            FontWeight::ExtraLight => mltg::FontWeight::UltraLight,
+            FontWeight::Light => mltg::FontWeight::Light,
+            FontWeight::SemiLight => mltg::FontWeight::SemiLight,
+            FontWeight::Regular => mltg::FontWeight::Regular,
+            FontWeight::Medium => mltg::FontWeight::Medium,
+            FontWeight::SemiBold => mltg::FontWeight::SemiBold,
+            FontWeight::Bold => mltg::FontWeight::Bold,
+            FontWeight::ExtraBold => mltg::FontWeight::UltraBold,
+            FontWeight::Black => mltg::FontWeight::UltraBlack,
+        }
+    }
+}
@@ -5,10 +5,26 @@ use std::collections::HashMap;
 
 use winit::event::VirtualKeyCode;
 
+pub mod color_parser;
 pub mod constants;
+pub mod drawing_ml;
+pub mod error;
+pub mod fonts;
+pub mod gui;
 pub mod namespaces;
+pub mod package;
 pub mod math;
 pub mod profiling;
+pub mod relationships;
+pub mod serialize;
+pub mod style;
+pub mod text_settings;
+pub mod word_processing;
+pub mod unicode;
+pub mod user_settings;
+pub mod wp;
+
+pub use namespaces::XMLNS_WORD_PROCESSING as WORD_PROCESSING_XML_NAMESPACE;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub enum KeyState {
@@ -50,6 +66,11 @@ impl Keyboard {
         self.is_down(VirtualKeyCode::LControl) || self.is_down(VirtualKeyCode::RControl)
     }
 
+    /// Checks if either of the shift keys are down.
+    pub fn is_shift_key_down(&self) -> bool {
+        self.is_down(VirtualKeyCode::LShift) || self.is_down(VirtualKeyCode::RShift)
+    }
+
     pub fn is_down(&self, key: VirtualKeyCode) -> bool {
         self.get_state(key) != KeyState::Released
     }
@@ -92,6 +113,26 @@ impl TwelfteenthPoint<i32> {
     }
 }
 
+/// English Metric Units (DrawingML 20.1.2.1), of which there are 914400 in
+/// an inch, and 12700 in a point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Emu<T>(pub T);
+
+impl Emu<u32> {
+    /// Get the value in points.
+    pub fn get_pts(&self) -> f32 {
+        // Large extents lose precision as f32 before the division.
+        (self.0 as f64 / 12700.0) as f32
+    }
+}
+
+impl Emu<i64> {
+    /// Get the value in points.
+    pub fn get_pts(&self) -> f32 {
+        (self.0 as f64 / 12700.0) as f32
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct EighteenthPoint<T>(pub T);
 
@@ -1,5 +1,6 @@
 // Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
 // All Rights Reserved.
 
+pub const XMLNS_WORD_PROCESSING: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
 pub const XMLNS_RELATIONSHIPS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
 pub const XMLNS_WORD_PROCESSING_DRAWING: &str = "http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing";
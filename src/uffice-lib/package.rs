@@ -0,0 +1,278 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Helpers for the Open Packaging Conventions (ECMA-376 Part 2), the ZIP-based
+// container format of WordprocessingML documents.
+
+use std::{path::Path, rc::Rc};
+
+use roxmltree as xml;
+
+use crate::error::Error;
+
+/// The relationship type of the main part of the package, which is the
+/// document part for WordprocessingML.
+pub const RELATIONSHIP_TYPE_OFFICE_DOCUMENT: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument";
+
+/// The name of the part with the relationships of the package itself.
+pub const PACKAGE_RELATIONSHIPS_PART: &str = "_rels/.rels";
+
+/// The name of the part with the content types of the other parts.
+pub const CONTENT_TYPES_PART: &str = "[Content_Types].xml";
+
+/// Part names are absolute (`/word/document.xml`), but the names in the ZIP
+/// archive aren't.
+pub fn normalize_part_name(part_name: &str) -> String {
+    String::from(part_name.trim_start_matches('/'))
+}
+
+/// Get the name of the relationships part belonging to the given part, e.g.
+/// `word/_rels/document.xml.rels` for `word/document.xml`.
+pub fn relationships_part_name(part_name: &str) -> String {
+    match part_name.rsplit_once('/') {
+        Some((directory, file_name)) => format!("{}/_rels/{}.rels", directory, file_name),
+        None => format!("_rels/{}.rels", part_name),
+    }
+}
+
+/// Get the name of the part the relationships part belongs to, which is the
+/// inverse of [relationships_part_name]. The package relationships
+/// (`_rels/.rels`) belong to the package itself, which has an empty name.
+pub fn source_part_name(relationships_part_name: &str) -> Option<String> {
+    let file_name = relationships_part_name.strip_suffix(".rels")?;
+    let (directory, file_name) = match file_name.rsplit_once('/') {
+        Some((directory, file_name)) => (directory, file_name),
+        None => return None,
+    };
+
+    match directory.rsplit_once('/') {
+        Some((parent, "_rels")) => Some(format!("{}/{}", parent, file_name)),
+        None if directory == "_rels" => Some(String::from(file_name)),
+        _ => None,
+    }
+}
+
+/// Resolves the target of a relationship to a part name. Targets are relative
+/// to the directory of the source part (e.g. `media/image1.png` for
+/// `word/document.xml`), unless they start with a slash.
+pub fn resolve_relationship_target(source_part_name: &str, target: &str) -> String {
+    if target.starts_with('/') {
+        return normalize_part_name(target);
+    }
+
+    let mut segments: Vec<&str> = match source_part_name.rsplit_once('/') {
+        Some((directory, _)) => directory.split('/').collect(),
+        None => Vec::new(),
+    };
+
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => _ = segments.pop(),
+            segment => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+/// Reads the part with the given name from the archive, or returns None when
+/// the archive doesn't contain it. A part that can't be read, because its
+/// entry is corrupt or it isn't UTF-8, is an error.
+pub fn load_archive_file_to_string(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Result<Option<Rc<String>>, Error> {
+    let zip_document = match archive.by_name(name) {
+        Ok(zip_document) => zip_document,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(Error::from(e).in_part(name)),
+    };
+
+    std::io::read_to_string(zip_document)
+        .map(|text| Some(Rc::new(text)))
+        .map_err(|e| Error::from(e).in_part(name))
+}
+
+/// The content types of the main document part of documents (`.docx`),
+/// templates (`.dotx`) and their macro-enabled variants (`.docm`/`.dotm`).
+const MAIN_DOCUMENT_CONTENT_TYPES: &[&str] = &[
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.template.main+xml",
+    "application/vnd.ms-word.document.macroEnabled.main+xml",
+    "application/vnd.ms-word.template.macroEnabledTemplate.main+xml",
+];
+
+/// The content types of the main document part of templates, and those of
+/// the documents created from them.
+const TEMPLATE_CONTENT_TYPES: &[(&str, &str)] = &[
+    ("application/vnd.openxmlformats-officedocument.wordprocessingml.template.main+xml",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"),
+    ("application/vnd.ms-word.template.macroEnabledTemplate.main+xml",
+        "application/vnd.ms-word.document.macroEnabled.main+xml"),
+];
+
+/// The extensions of templates, from which new documents are created.
+const TEMPLATE_EXTENSIONS: &[&str] = &["dotx", "dotm"];
+
+/// The part name used by Microsoft Word, used when the package doesn't tell
+/// us where the main document part is.
+const DEFAULT_MAIN_DOCUMENT_PART: &str = "word/document.xml";
+
+/// Finds the name of the main document part in the archive, by following the
+/// package relationships (`_rels/.rels`), or otherwise by looking at the
+/// content types (`[Content_Types].xml`).
+pub fn find_main_document_part(archive: &mut zip::ZipArchive<std::fs::File>) -> String {
+    if let Ok(Some(text)) = load_archive_file_to_string(archive, PACKAGE_RELATIONSHIPS_PART) {
+        if let Ok(document) = xml::Document::parse(&text) {
+            if let Some(part) = find_office_document_target(&document) {
+                return part;
+            }
+        }
+    }
+
+    if let Ok(Some(text)) = load_archive_file_to_string(archive, CONTENT_TYPES_PART) {
+        if let Ok(document) = xml::Document::parse(&text) {
+            if let Some(part) = find_main_document_part_in_content_types(&document) {
+                return part;
+            }
+        }
+    }
+
+    println!("[Package] Warning: main document part not found, assuming \"{}\"", DEFAULT_MAIN_DOCUMENT_PART);
+    String::from(DEFAULT_MAIN_DOCUMENT_PART)
+}
+
+/// Finds the target of the officeDocument relationship in the package
+/// relationships part.
+fn find_office_document_target(document: &xml::Document) -> Option<String> {
+    document.root_element()
+        .children()
+        .filter(|child| child.tag_name().name() == "Relationship")
+        .find(|child| child.attribute("Type") == Some(RELATIONSHIP_TYPE_OFFICE_DOCUMENT))
+        .and_then(|child| child.attribute("Target"))
+        .map(normalize_part_name)
+}
+
+/// Finds the part name of the main document part in the `[Content_Types].xml`
+/// part.
+fn find_main_document_part_in_content_types(document: &xml::Document) -> Option<String> {
+    document.root_element()
+        .children()
+        .filter(|child| child.tag_name().name() == "Override")
+        .find(|child| child.attribute("ContentType").is_some_and(|content_type| MAIN_DOCUMENT_CONTENT_TYPES.contains(&content_type)))
+        .and_then(|child| child.attribute("PartName"))
+        .map(normalize_part_name)
+}
+
+/// Checks if the path has the extension of a template (`.dotx`/`.dotm`).
+pub fn is_template_path(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        TEMPLATE_EXTENSIONS.iter().any(|template| extension.to_string_lossy().eq_ignore_ascii_case(template))
+    })
+}
+
+/// Changes the content type of the main document part in the text of the
+/// `[Content_Types].xml` part to that of a template or of a document, which
+/// has to match the extension of the package.
+pub fn convert_main_document_content_type(content_types: &str, to_template: bool) -> String {
+    let mut text = String::from(content_types);
+    for (template, document) in TEMPLATE_CONTENT_TYPES {
+        text = if to_template {
+            text.replace(document, template)
+        } else {
+            text.replace(template, document)
+        };
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relationship_targets() {
+        assert_eq!(resolve_relationship_target("word/document.xml", "styles.xml"), "word/styles.xml");
+        assert_eq!(resolve_relationship_target("word/document.xml", "theme/theme1.xml"), "word/theme/theme1.xml");
+        assert_eq!(resolve_relationship_target("word/document.xml", "../customXml/item1.xml"), "customXml/item1.xml");
+        assert_eq!(resolve_relationship_target("word/document.xml", "/word/numbering.xml"), "word/numbering.xml");
+        assert_eq!(resolve_relationship_target("document.xml", "styles.xml"), "styles.xml");
+        assert_eq!(resolve_relationship_target("", "word/document.xml"), "word/document.xml");
+    }
+
+    #[test]
+    fn relationships_part_names() {
+        assert_eq!(relationships_part_name("word/document.xml"), "word/_rels/document.xml.rels");
+        assert_eq!(relationships_part_name("document.xml"), "_rels/document.xml.rels");
+    }
+
+    #[test]
+    fn source_part_names() {
+        assert_eq!(source_part_name("word/_rels/document.xml.rels").as_deref(), Some("word/document.xml"));
+        assert_eq!(source_part_name("_rels/document.xml.rels").as_deref(), Some("document.xml"));
+        assert_eq!(source_part_name(PACKAGE_RELATIONSHIPS_PART).as_deref(), Some(""));
+        assert_eq!(source_part_name("word/document.xml"), None);
+        assert_eq!(source_part_name("word/document.xml.rels"), None);
+    }
+
+    #[test]
+    fn office_document_target_from_relationships() {
+        let document = xml::Document::parse(r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+            <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+            <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="/word/document2.xml"/>
+        </Relationships>"#).unwrap();
+
+        assert_eq!(find_office_document_target(&document), Some(String::from("word/document2.xml")));
+    }
+
+    #[test]
+    fn main_document_part_from_content_types() {
+        let document = xml::Document::parse(r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+            <Default Extension="xml" ContentType="application/xml"/>
+            <Override PartName="/word/vbaData.xml" ContentType="application/vnd.ms-word.vbaData+xml"/>
+            <Override PartName="/word/document.xml" ContentType="application/vnd.ms-word.document.macroEnabled.main+xml"/>
+        </Types>"#).unwrap();
+
+        assert_eq!(find_main_document_part_in_content_types(&document), Some(String::from("word/document.xml")));
+    }
+
+    #[test]
+    fn template_content_types_are_converted() {
+        let template = r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+            <Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.template.main+xml"/>
+            <Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+        </Types>"#;
+
+        let document = convert_main_document_content_type(template, false);
+        assert!(document.contains(r#"ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml""#));
+        assert!(document.contains("wordprocessingml.styles+xml"));
+        assert_eq!(convert_main_document_content_type(&document, true), template);
+
+        let macro_enabled = convert_main_document_content_type("application/vnd.ms-word.template.macroEnabledTemplate.main+xml", false);
+        assert_eq!(macro_enabled, "application/vnd.ms-word.document.macroEnabled.main+xml");
+    }
+
+    #[test]
+    fn template_paths() {
+        assert!(is_template_path(Path::new("Letter.dotx")));
+        assert!(is_template_path(Path::new("/tmp/Macros.DOTM")));
+        assert!(!is_template_path(Path::new("Letter.docx")));
+        assert!(!is_template_path(Path::new("dotx")));
+    }
+
+    #[test]
+    fn parts_that_are_not_utf8_are_errors() {
+        use std::io::Write;
+
+        let directory = std::env::temp_dir().join("uffice-unreadable-part-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("binary.docx");
+
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        writer.start_file("word/document.xml", zip::write::FileOptions::default()).unwrap();
+        writer.write_all(&[0xFF, 0xFE, 0x00]).unwrap();
+        writer.finish().unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert!(load_archive_file_to_string(&mut archive, "word/document.xml").is_err());
+        assert!(matches!(load_archive_file_to_string(&mut archive, "word/styles.xml"), Ok(None)));
+    }
+}
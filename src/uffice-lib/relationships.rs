@@ -19,6 +19,7 @@ pub enum RelationshipType {
     CommentsIds,
     CustomXml,
     Endnotes,
+    Font,
     FontTable,
     Footer,
     Footnotes,
@@ -27,10 +28,12 @@ pub enum RelationshipType {
     Hyperlink,
     Image,
     Numbering,
+    OfficeDocument,
     People,
     Settings,
     Styles,
     Theme,
+    VbaProject,
     WebSettings
 }
 
@@ -43,6 +46,7 @@ impl RelationshipType {
             "http://schemas.microsoft.com/office/2016/09/relationships/commentsIds" => Some(Self::CommentsIds),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/customXml" => Some(Self::CustomXml),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/endnotes" => Some(Self::Endnotes),
+            "http://schemas.openxmlformats.org/officeDocument/2006/relationships/font" => Some(Self::Font),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/fontTable" => Some(Self::FontTable),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/footer" => Some(Self::Footer),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/footnotes" => Some(Self::Footnotes),
@@ -51,10 +55,14 @@ impl RelationshipType {
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" => Some(Self::Hyperlink),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" => Some(Self::Image),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" => Some(Self::Numbering),
+            crate::package::RELATIONSHIP_TYPE_OFFICE_DOCUMENT => Some(Self::OfficeDocument),
             "http://schemas.microsoft.com/office/2011/relationships/people" => Some(Self::People),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" => Some(Self::Styles),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings" => Some(Self::Settings),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" => Some(Self::Theme),
+
+            // The macros of .docm/.dotm files, which we ignore.
+            "http://schemas.microsoft.com/office/2006/relationships/vbaProject" => Some(Self::VbaProject),
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships/webSettings" => Some(Self::WebSettings),
             _ => {
                 #[cfg(debug_assertions)]
@@ -97,8 +105,11 @@ impl Relationships {
         }
     }
 
-    pub fn load_xml(document: &xml::Document, zip_archive: &mut ZipArchive<File>) -> Result<Self, Error> {
-        assert_eq!(document.root_element().tag_name().name(), "Relationships");
+    /// Loads the relationships of the part named `source_part_name`.
+    pub fn load_xml(document: &xml::Document, zip_archive: &mut ZipArchive<File>, source_part_name: &str) -> Result<Self, Error> {
+        if document.root_element().tag_name().name() != "Relationships" {
+            return Err(Error::MissingElement("Relationships"));
+        }
 
         let mut relationships = HashMap::new();
 
@@ -115,25 +126,29 @@ impl Relationships {
                 }
             }
 
-            let relation_type = relationship_xml.attribute("Type");
-            let relation_type = RelationshipType::convert(relation_type.unwrap());
+            let relation_type = relationship_xml.attribute("Type")
+                .ok_or(Error::MissingAttribute { element: "Relationship", attribute: "Type" })?;
+            let relation_type = RelationshipType::convert(relation_type)
+                .ok_or_else(|| Error::InvalidValue { element: "Relationship", value: String::from(relation_type) })?;
 
-            let id: Rc<str> = relationship_xml.attribute("Id").unwrap().into();
-            let target = relationship_xml.attribute("Target").unwrap();
+            let id: Rc<str> = relationship_xml.attribute("Id")
+                .ok_or(Error::MissingAttribute { element: "Relationship", attribute: "Id" })?.into();
+            let target = relationship_xml.attribute("Target")
+                .ok_or(Error::MissingAttribute { element: "Relationship", attribute: "Target" })?;
 
             let mut data = Vec::new();
-            if relation_type.unwrap() == RelationshipType::Image {
-                match &mut zip_archive.by_name(&format!("word/{}", target)) {
-                    Ok(file) => {
-                        std::io::copy(file, &mut data).expect("Failed to read Image");
-                    }
-                    Err(e) => panic!("Failed to load target \"{}\": {}", target, e)
+            if matches!(relation_type, RelationshipType::Image | RelationshipType::Font) {
+                // A missing picture or font is reported when it is used,
+                // since the rest of the document can be shown without it.
+                let part_name = crate::package::resolve_relationship_target(source_part_name, target);
+                if let Ok(mut file) = zip_archive.by_name(&part_name) {
+                    std::io::copy(&mut file, &mut data).map_err(|e| Error::from(e).in_part(&part_name))?;
                 }
             }
 
             relationships.insert(id.clone(), Rc::new(RefCell::new(Relationship{
                 id: id.clone(),
-                relation_type: relation_type.unwrap(),
+                relation_type,
                 target: String::from(target),
                 data
             })));
@@ -144,11 +159,35 @@ impl Relationships {
         })
     }
 
+    /// Adds a relationship that isn't loaded from a package, such as the
+    /// target of a link in a document converted from another file format.
+    pub fn insert(&mut self, relationship: Relationship) {
+        self.relationships.insert(relationship.id.clone(), Rc::new(RefCell::new(relationship)));
+    }
+
     pub fn len(&self) -> usize {
         self.relationships.len()
     }
 
+    /// All relationships, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Rc<RefCell<Relationship>>> {
+        self.relationships.values()
+    }
+
     pub fn find(&self, name: &str) -> Option<&Rc<RefCell<Relationship>>> {
         self.relationships.get(name)
     }
+
+    /// Finds the first relationship of the given type, which is useful for
+    /// parts that occur only once, such as the styles part.
+    pub fn find_by_type(&self, relation_type: RelationshipType) -> Option<&Rc<RefCell<Relationship>>> {
+        self.relationships.values()
+            .find(|relationship| relationship.borrow().relation_type == relation_type)
+    }
+
+    /// Finds all relationships of the given type, such as the headers.
+    pub fn find_all_by_type(&self, relation_type: RelationshipType) -> impl Iterator<Item = &Rc<RefCell<Relationship>>> {
+        self.relationships.values()
+            .filter(move |relationship| relationship.borrow().relation_type == relation_type)
+    }
 }
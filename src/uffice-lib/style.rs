@@ -0,0 +1,1340 @@
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use roxmltree as xml;
+
+use crate::{
+    EighteenthPoint,
+    TwelfteenthPoint,
+    WholePoint,
+};
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    str::FromStr,
+    num::ParseIntError,
+    rc::Rc,
+};
+
+use crate::{
+    error::Error,
+    WORD_PROCESSING_XML_NAMESPACE,
+    text_settings::TextSettings, serialize::FromXmlStandalone,
+    wp::{
+        diagnostics::Diagnostic,
+        table::{
+            TableCellProperties,
+            TableConditionalFormatting,
+            TableProperties,
+            TableStyleOverrideType,
+        },
+    },
+};
+
+pub type ThemeSettings = crate::drawing_ml::style::StyleSettings;
+
+/// ST_Border
+#[derive(Copy, Clone, Debug, Default)]
+pub enum BorderType {
+    Nil,
+    None,
+
+    #[default]
+    Single,
+    Thick,
+    Double,
+    Dotted,
+    Dashed,
+    DotDash,
+    DotDotDash,
+    Triple,
+    ThinThickSmallGap,
+    ThickThinSmallGap,
+    ThinThickThinSmallGap,
+    ThinThickMediumGap,
+    ThickThinMediumGap,
+    ThinThickThinMediumGap,
+    ThinThickLargeGap,
+    ThickThinLargeGap,
+    ThinThickThinLargeGap,
+    Wave,
+    DoubleWave,
+    DashSmallGap,
+    DashDotStroked,
+    ThreeDEmboss,
+    ThreeDEngrave,
+    Outset,
+    Inset,
+    Apples,
+    ArchedScallops,
+    BabyPacifier,
+    BabyRattle,
+    Balloons3Colors,
+    BalloonsHotAir,
+    BasicBlackDashes,
+    BasicBlackDots,
+    BasicBlackSquares,
+    BasicThinLines,
+    BasicWhiteDashes,
+    BasicWhiteDots,
+    BasicWhiteSquares,
+    BasicWideInline,
+    BasicWideMidline,
+    BasicWideOutline,
+    Bats,
+    Birds,
+    BirdsFlight,
+    Cabins,
+    CakeSlice,
+    CandyCorn,
+    CelticKnotwork,
+    CertificateBanner,
+    ChainLink,
+    ChampagneBottle,
+    CheckedBarBlack,
+    CheckedBarColor,
+    Checkered,
+    ChristmasTree,
+    CirclesLines,
+    CirclesRectangles,
+    ClassicalWave,
+    Clocks,
+    Compass,
+    Confetti,
+    ConfettiGrays,
+    ConfettiOutline,
+    ConfettiStreamers,
+    ConfettiWhite,
+    CornerTriangles,
+    CouponCutoutDashes,
+    CouponCutoutDots,
+    CrazyMaze,
+    CreaturesButterfly,
+    CreaturesFish,
+    CreaturesInsects,
+    CreaturesLadyBug,
+    CrossStitch,
+    Cup,
+    DecoArch,
+    DecoArchColor,
+    DecoBlocks,
+    DiamondsGray,
+    DoubleD,
+    DoubleDiamonds,
+    Earth1,
+    Earth2,
+    EasterEggBasket,
+    EclipsingSquares1,
+    EclipsingSquares2,
+    Eggplant,
+    Fans,
+    Film,
+    Firecrackers,
+    FlowersBlockPrint,
+    FlowersDaisies,
+    FlowersModern1,
+    FlowersModern2,
+    FlowersPansy,
+    FlowersRedRose,
+    FlowersRoses,
+    FlowersTeacup,
+    FlowersTiny,
+    Gems,
+    GingerbreadMan,
+    Gradient,
+    Handmade1,
+    Handmade2,
+    HeartBalloon,
+    HeartGray,
+    Hearts,
+    HeebieJeebies,
+    Holly,
+    HouseFunky,
+    Hypnotic,
+    IceCreamCones,
+    LightBulb,
+    Lightning1,
+    Lightning2,
+    MapPins,
+    MapleLeaf,
+    MapleMuffins,
+    Marquee,
+    MarqueeToothed,
+    Moons,
+    Mosaic,
+    MusicNotes,
+    Northwest,
+    Ovals,
+    Packages,
+    PalmsBlack,
+    PalmsColor,
+    PaperClips,
+    PartyFavor,
+    PartyGlass,
+    Pencils,
+    People,
+    PeopleHats,
+    PeopleWaving,
+    Poinsettias,
+    PostageStamp,
+    Pumpkin1,
+    PushPinNote1,
+    PushPinNote2,
+    Pyramids,
+    PyramidsAbove,
+    Quadrants,
+    Rings,
+    Safari,
+    Sawtooth,
+    SawtoothGray,
+    ScaredCat,
+    Seattle,
+    ShadowedSquares,
+    SharksTeeth,
+    ShorebirdTracks,
+    Skyrocket,
+    SnowflakeFancy,
+    Snowflakes,
+    Sombrero,
+    Southwest,
+    Stars,
+    Stars3d,
+    StarsBlack,
+    StarsShadowed,
+    StarsTop,
+    Sun,
+    Swirligig,
+    TornPaper,
+    TornPaperBlack,
+    Trees,
+    TriangleParty,
+    Triangles,
+    Tribal1,
+    Tribal2,
+    Tribal3,
+    Tribal4,
+    Tribal5,
+    Tribal6,
+    TwistedLines1,
+    TwistedLines2,
+    Vine,
+    Waveline,
+    WeavingAngles,
+    WeavingBraid,
+    WeavingRibbon,
+    WeavingStrips,
+    WhiteFlowers,
+    Woodwork,
+    XIllusions,
+    ZanyTriangles,
+    ZigZag,
+    ZigZagStitch,
+    Custom,
+}
+
+#[derive(Clone, Debug)]
+pub enum BorderTypeParseError {
+    UnknownBorderType(String),
+}
+
+impl FromStr for BorderType {
+    type Err = BorderTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nil" => Ok(Self::Nil),
+            "none" => Ok(Self::None),
+            "single" => Ok(Self::Single),
+            "thick" => Ok(Self::Thick),
+            "double" => Ok(Self::Double),
+            "dotted" => Ok(Self::Dotted),
+            "dashed" => Ok(Self::Dashed),
+            "dotDash" => Ok(Self::DotDash),
+            "dotDotDash" => Ok(Self::DotDotDash),
+            "triple" => Ok(Self::Triple),
+            "thinThickSmallGap" => Ok(Self::ThinThickSmallGap),
+            "thickThinSmallGap" => Ok(Self::ThickThinSmallGap),
+            "thinThickThinSmallGap" => Ok(Self::ThinThickThinSmallGap),
+            "thinThickMediumGap" => Ok(Self::ThinThickMediumGap),
+            "thickThinMediumGap" => Ok(Self::ThickThinMediumGap),
+            "thinThickThinMediumGap" => Ok(Self::ThinThickThinMediumGap),
+            "thinThickLargeGap" => Ok(Self::ThinThickLargeGap),
+            "thickThinLargeGap" => Ok(Self::ThickThinLargeGap),
+            "thinThickThinLargeGap" => Ok(Self::ThinThickThinLargeGap),
+            "wave" => Ok(Self::Wave),
+            "doubleWave" => Ok(Self::DoubleWave),
+            "dashSmallGap" => Ok(Self::DashSmallGap),
+            "dashDotStroked" => Ok(Self::DashDotStroked),
+            "threeDEmboss" => Ok(Self::ThreeDEmboss),
+            "threeDEngrave" => Ok(Self::ThreeDEngrave),
+            "outset" => Ok(Self::Outset),
+            "inset" => Ok(Self::Inset),
+            "apples" => Ok(Self::Apples),
+            "archedScallops" => Ok(Self::ArchedScallops),
+            "babyPacifier" => Ok(Self::BabyPacifier),
+            "babyRattle" => Ok(Self::BabyRattle),
+            "balloons3Colors" => Ok(Self::Balloons3Colors),
+            "balloonsHotAir" => Ok(Self::BalloonsHotAir),
+            "basicBlackDashes" => Ok(Self::BasicBlackDashes),
+            "basicBlackDots" => Ok(Self::BasicBlackDots),
+            "basicBlackSquares" => Ok(Self::BasicBlackSquares),
+            "basicThinLines" => Ok(Self::BasicThinLines),
+            "basicWhiteDashes" => Ok(Self::BasicWhiteDashes),
+            "basicWhiteDots" => Ok(Self::BasicWhiteDots),
+            "basicWhiteSquares" => Ok(Self::BasicWhiteSquares),
+            "basicWideInline" => Ok(Self::BasicWideInline),
+            "basicWideMidline" => Ok(Self::BasicWideMidline),
+            "basicWideOutline" => Ok(Self::BasicWideOutline),
+            "bats" => Ok(Self::Bats),
+            "birds" => Ok(Self::Birds),
+            "birdsFlight" => Ok(Self::BirdsFlight),
+            "cabins" => Ok(Self::Cabins),
+            "cakeSlice" => Ok(Self::CakeSlice),
+            "candyCorn" => Ok(Self::CandyCorn),
+            "celticKnotwork" => Ok(Self::CelticKnotwork),
+            "certificateBanner" => Ok(Self::CertificateBanner),
+            "chainLink" => Ok(Self::ChainLink),
+            "champagneBottle" => Ok(Self::ChampagneBottle),
+            "checkedBarBlack" => Ok(Self::CheckedBarBlack),
+            "checkedBarColor" => Ok(Self::CheckedBarColor),
+            "checkered" => Ok(Self::Checkered),
+            "christmasTree" => Ok(Self::ChristmasTree),
+            "circlesLines" => Ok(Self::CirclesLines),
+            "circlesRectangles" => Ok(Self::CirclesRectangles),
+            "classicalWave" => Ok(Self::ClassicalWave),
+            "clocks" => Ok(Self::Clocks),
+            "compass" => Ok(Self::Compass),
+            "confetti" => Ok(Self::Confetti),
+            "confettiGrays" => Ok(Self::ConfettiGrays),
+            "confettiOutline" => Ok(Self::ConfettiOutline),
+            "confettiStreamers" => Ok(Self::ConfettiStreamers),
+            "confettiWhite" => Ok(Self::ConfettiWhite),
+            "cornerTriangles" => Ok(Self::CornerTriangles),
+            "couponCutoutDashes" => Ok(Self::CouponCutoutDashes),
+            "couponCutoutDots" => Ok(Self::CouponCutoutDots),
+            "crazyMaze" => Ok(Self::CrazyMaze),
+            "creaturesButterfly" => Ok(Self::CreaturesButterfly),
+            "creaturesFish" => Ok(Self::CreaturesFish),
+            "creaturesInsects" => Ok(Self::CreaturesInsects),
+            "creaturesLadyBug" => Ok(Self::CreaturesLadyBug),
+            "crossStitch" => Ok(Self::CrossStitch),
+            "cup" => Ok(Self::Cup),
+            "decoArch" => Ok(Self::DecoArch),
+            "decoArchColor" => Ok(Self::DecoArchColor),
+            "decoBlocks" => Ok(Self::DecoBlocks),
+            "diamondsGray" => Ok(Self::DiamondsGray),
+            "doubleD" => Ok(Self::DoubleD),
+            "doubleDiamonds" => Ok(Self::DoubleDiamonds),
+            "earth1" => Ok(Self::Earth1),
+            "earth2" => Ok(Self::Earth2),
+            "easterEggBasket" => Ok(Self::EasterEggBasket),
+            "eclipsingSquares1" => Ok(Self::EclipsingSquares1),
+            "eclipsingSquares2" => Ok(Self::EclipsingSquares2),
+            "eggplant" => Ok(Self::Eggplant),
+            "fans" => Ok(Self::Fans),
+            "film" => Ok(Self::Film),
+            "firecrackers" => Ok(Self::Firecrackers),
+            "flowersBlockPrint" => Ok(Self::FlowersBlockPrint),
+            "flowersDaisies" => Ok(Self::FlowersDaisies),
+            "flowersModern1" => Ok(Self::FlowersModern1),
+            "flowersModern2" => Ok(Self::FlowersModern2),
+            "flowersPansy" => Ok(Self::FlowersPansy),
+            "flowersRedRose" => Ok(Self::FlowersRedRose),
+            "flowersRoses" => Ok(Self::FlowersRoses),
+            "flowersTeacup" => Ok(Self::FlowersTeacup),
+            "flowersTiny" => Ok(Self::FlowersTiny),
+            "gems" => Ok(Self::Gems),
+            "gingerbreadMan" => Ok(Self::GingerbreadMan),
+            "gradient" => Ok(Self::Gradient),
+            "handmade1" => Ok(Self::Handmade1),
+            "handmade2" => Ok(Self::Handmade2),
+            "heartBalloon" => Ok(Self::HeartBalloon),
+            "heartGray" => Ok(Self::HeartGray),
+            "hearts" => Ok(Self::Hearts),
+            "heebieJeebies" => Ok(Self::HeebieJeebies),
+            "holly" => Ok(Self::Holly),
+            "houseFunky" => Ok(Self::HouseFunky),
+            "hypnotic" => Ok(Self::Hypnotic),
+            "iceCreamCones" => Ok(Self::IceCreamCones),
+            "lightBulb" => Ok(Self::LightBulb),
+            "lightning1" => Ok(Self::Lightning1),
+            "lightning2" => Ok(Self::Lightning2),
+            "mapPins" => Ok(Self::MapPins),
+            "mapleLeaf" => Ok(Self::MapleLeaf),
+            "mapleMuffins" => Ok(Self::MapleMuffins),
+            "marquee" => Ok(Self::Marquee),
+            "marqueeToothed" => Ok(Self::MarqueeToothed),
+            "moons" => Ok(Self::Moons),
+            "mosaic" => Ok(Self::Mosaic),
+            "musicNotes" => Ok(Self::MusicNotes),
+            "northwest" => Ok(Self::Northwest),
+            "ovals" => Ok(Self::Ovals),
+            "packages" => Ok(Self::Packages),
+            "palmsBlack" => Ok(Self::PalmsBlack),
+            "palmsColor" => Ok(Self::PalmsColor),
+            "paperClips" => Ok(Self::PaperClips),
+            "partyFavor" => Ok(Self::PartyFavor),
+            "partyGlass" => Ok(Self::PartyGlass),
+            "pencils" => Ok(Self::Pencils),
+            "people" => Ok(Self::People),
+            "peopleHats" => Ok(Self::PeopleHats),
+            "peopleWaving" => Ok(Self::PeopleWaving),
+            "poinsettias" => Ok(Self::Poinsettias),
+            "postageStamp" => Ok(Self::PostageStamp),
+            "pumpkin1" => Ok(Self::Pumpkin1),
+            "pushPinNote1" => Ok(Self::PushPinNote1),
+            "pushPinNote2" => Ok(Self::PushPinNote2),
+            "pyramids" => Ok(Self::Pyramids),
+            "pyramidsAbove" => Ok(Self::PyramidsAbove),
+            "quadrants" => Ok(Self::Quadrants),
+            "rings" => Ok(Self::Rings),
+            "safari" => Ok(Self::Safari),
+            "sawtooth" => Ok(Self::Sawtooth),
+            "sawtoothGray" => Ok(Self::SawtoothGray),
+            "scaredCat" => Ok(Self::ScaredCat),
+            "seattle" => Ok(Self::Seattle),
+            "shadowedSquares" => Ok(Self::ShadowedSquares),
+            "sharksTeeth" => Ok(Self::SharksTeeth),
+            "shorebirdTracks" => Ok(Self::ShorebirdTracks),
+            "skyrocket" => Ok(Self::Skyrocket),
+            "snowflakeFancy" => Ok(Self::SnowflakeFancy),
+            "snowflakes" => Ok(Self::Snowflakes),
+            "sombrero" => Ok(Self::Sombrero),
+            "southwest" => Ok(Self::Southwest),
+            "stars" => Ok(Self::Stars),
+            "stars3d" => Ok(Self::Stars3d),
+            "starsBlack" => Ok(Self::StarsBlack),
+            "starsShadowed" => Ok(Self::StarsShadowed),
+            "starsTop" => Ok(Self::StarsTop),
+            "sun" => Ok(Self::Sun),
+            "swirligig" => Ok(Self::Swirligig),
+            "tornPaper" => Ok(Self::TornPaper),
+            "tornPaperBlack" => Ok(Self::TornPaperBlack),
+            "trees" => Ok(Self::Trees),
+            "triangleParty" => Ok(Self::TriangleParty),
+            "triangles" => Ok(Self::Triangles),
+            "tribal1" => Ok(Self::Tribal1),
+            "tribal2" => Ok(Self::Tribal2),
+            "tribal3" => Ok(Self::Tribal3),
+            "tribal4" => Ok(Self::Tribal4),
+            "tribal5" => Ok(Self::Tribal5),
+            "tribal6" => Ok(Self::Tribal6),
+            "twistedLines1" => Ok(Self::TwistedLines1),
+            "twistedLines2" => Ok(Self::TwistedLines2),
+            "vine" => Ok(Self::Vine),
+            "waveline" => Ok(Self::Waveline),
+            "weavingAngles" => Ok(Self::WeavingAngles),
+            "weavingBraid" => Ok(Self::WeavingBraid),
+            "weavingRibbon" => Ok(Self::WeavingRibbon),
+            "weavingStrips" => Ok(Self::WeavingStrips),
+            "whiteFlowers" => Ok(Self::WhiteFlowers),
+            "woodwork" => Ok(Self::Woodwork),
+            "xIllusions" => Ok(Self::XIllusions),
+            "zanyTriangles" => Ok(Self::ZanyTriangles),
+            "zigZag" => Ok(Self::ZigZag),
+            "zigZagStitch" => Ok(Self::ZigZagStitch),
+            "custom" => Ok(Self::Custom),
+            _ => Err(BorderTypeParseError::UnknownBorderType(s.to_string()))
+        }
+    }
+}
+
+impl BorderType {
+    /// The ST_Border value of the border type, as used in the `w:val` attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Nil => "nil",
+            Self::None => "none",
+            Self::Single => "single",
+            Self::Thick => "thick",
+            Self::Double => "double",
+            Self::Dotted => "dotted",
+            Self::Dashed => "dashed",
+            Self::DotDash => "dotDash",
+            Self::DotDotDash => "dotDotDash",
+            Self::Triple => "triple",
+            Self::ThinThickSmallGap => "thinThickSmallGap",
+            Self::ThickThinSmallGap => "thickThinSmallGap",
+            Self::ThinThickThinSmallGap => "thinThickThinSmallGap",
+            Self::ThinThickMediumGap => "thinThickMediumGap",
+            Self::ThickThinMediumGap => "thickThinMediumGap",
+            Self::ThinThickThinMediumGap => "thinThickThinMediumGap",
+            Self::ThinThickLargeGap => "thinThickLargeGap",
+            Self::ThickThinLargeGap => "thickThinLargeGap",
+            Self::ThinThickThinLargeGap => "thinThickThinLargeGap",
+            Self::Wave => "wave",
+            Self::DoubleWave => "doubleWave",
+            Self::DashSmallGap => "dashSmallGap",
+            Self::DashDotStroked => "dashDotStroked",
+            Self::ThreeDEmboss => "threeDEmboss",
+            Self::ThreeDEngrave => "threeDEngrave",
+            Self::Outset => "outset",
+            Self::Inset => "inset",
+            Self::Apples => "apples",
+            Self::ArchedScallops => "archedScallops",
+            Self::BabyPacifier => "babyPacifier",
+            Self::BabyRattle => "babyRattle",
+            Self::Balloons3Colors => "balloons3Colors",
+            Self::BalloonsHotAir => "balloonsHotAir",
+            Self::BasicBlackDashes => "basicBlackDashes",
+            Self::BasicBlackDots => "basicBlackDots",
+            Self::BasicBlackSquares => "basicBlackSquares",
+            Self::BasicThinLines => "basicThinLines",
+            Self::BasicWhiteDashes => "basicWhiteDashes",
+            Self::BasicWhiteDots => "basicWhiteDots",
+            Self::BasicWhiteSquares => "basicWhiteSquares",
+            Self::BasicWideInline => "basicWideInline",
+            Self::BasicWideMidline => "basicWideMidline",
+            Self::BasicWideOutline => "basicWideOutline",
+            Self::Bats => "bats",
+            Self::Birds => "birds",
+            Self::BirdsFlight => "birdsFlight",
+            Self::Cabins => "cabins",
+            Self::CakeSlice => "cakeSlice",
+            Self::CandyCorn => "candyCorn",
+            Self::CelticKnotwork => "celticKnotwork",
+            Self::CertificateBanner => "certificateBanner",
+            Self::ChainLink => "chainLink",
+            Self::ChampagneBottle => "champagneBottle",
+            Self::CheckedBarBlack => "checkedBarBlack",
+            Self::CheckedBarColor => "checkedBarColor",
+            Self::Checkered => "checkered",
+            Self::ChristmasTree => "christmasTree",
+            Self::CirclesLines => "circlesLines",
+            Self::CirclesRectangles => "circlesRectangles",
+            Self::ClassicalWave => "classicalWave",
+            Self::Clocks => "clocks",
+            Self::Compass => "compass",
+            Self::Confetti => "confetti",
+            Self::ConfettiGrays => "confettiGrays",
+            Self::ConfettiOutline => "confettiOutline",
+            Self::ConfettiStreamers => "confettiStreamers",
+            Self::ConfettiWhite => "confettiWhite",
+            Self::CornerTriangles => "cornerTriangles",
+            Self::CouponCutoutDashes => "couponCutoutDashes",
+            Self::CouponCutoutDots => "couponCutoutDots",
+            Self::CrazyMaze => "crazyMaze",
+            Self::CreaturesButterfly => "creaturesButterfly",
+            Self::CreaturesFish => "creaturesFish",
+            Self::CreaturesInsects => "creaturesInsects",
+            Self::CreaturesLadyBug => "creaturesLadyBug",
+            Self::CrossStitch => "crossStitch",
+            Self::Cup => "cup",
+            Self::DecoArch => "decoArch",
+            Self::DecoArchColor => "decoArchColor",
+            Self::DecoBlocks => "decoBlocks",
+            Self::DiamondsGray => "diamondsGray",
+            Self::DoubleD => "doubleD",
+            Self::DoubleDiamonds => "doubleDiamonds",
+            Self::Earth1 => "earth1",
+            Self::Earth2 => "earth2",
+            Self::EasterEggBasket => "easterEggBasket",
+            Self::EclipsingSquares1 => "eclipsingSquares1",
+            Self::EclipsingSquares2 => "eclipsingSquares2",
+            Self::Eggplant => "eggplant",
+            Self::Fans => "fans",
+            Self::Film => "film",
+            Self::Firecrackers => "firecrackers",
+            Self::FlowersBlockPrint => "flowersBlockPrint",
+            Self::FlowersDaisies => "flowersDaisies",
+            Self::FlowersModern1 => "flowersModern1",
+            Self::FlowersModern2 => "flowersModern2",
+            Self::FlowersPansy => "flowersPansy",
+            Self::FlowersRedRose => "flowersRedRose",
+            Self::FlowersRoses => "flowersRoses",
+            Self::FlowersTeacup => "flowersTeacup",
+            Self::FlowersTiny => "flowersTiny",
+            Self::Gems => "gems",
+            Self::GingerbreadMan => "gingerbreadMan",
+            Self::Gradient => "gradient",
+            Self::Handmade1 => "handmade1",
+            Self::Handmade2 => "handmade2",
+            Self::HeartBalloon => "heartBalloon",
+            Self::HeartGray => "heartGray",
+            Self::Hearts => "hearts",
+            Self::HeebieJeebies => "heebieJeebies",
+            Self::Holly => "holly",
+            Self::HouseFunky => "houseFunky",
+            Self::Hypnotic => "hypnotic",
+            Self::IceCreamCones => "iceCreamCones",
+            Self::LightBulb => "lightBulb",
+            Self::Lightning1 => "lightning1",
+            Self::Lightning2 => "lightning2",
+            Self::MapPins => "mapPins",
+            Self::MapleLeaf => "mapleLeaf",
+            Self::MapleMuffins => "mapleMuffins",
+            Self::Marquee => "marquee",
+            Self::MarqueeToothed => "marqueeToothed",
+            Self::Moons => "moons",
+            Self::Mosaic => "mosaic",
+            Self::MusicNotes => "musicNotes",
+            Self::Northwest => "northwest",
+            Self::Ovals => "ovals",
+            Self::Packages => "packages",
+            Self::PalmsBlack => "palmsBlack",
+            Self::PalmsColor => "palmsColor",
+            Self::PaperClips => "paperClips",
+            Self::PartyFavor => "partyFavor",
+            Self::PartyGlass => "partyGlass",
+            Self::Pencils => "pencils",
+            Self::People => "people",
+            Self::PeopleHats => "peopleHats",
+            Self::PeopleWaving => "peopleWaving",
+            Self::Poinsettias => "poinsettias",
+            Self::PostageStamp => "postageStamp",
+            Self::Pumpkin1 => "pumpkin1",
+            Self::PushPinNote1 => "pushPinNote1",
+            Self::PushPinNote2 => "pushPinNote2",
+            Self::Pyramids => "pyramids",
+            Self::PyramidsAbove => "pyramidsAbove",
+            Self::Quadrants => "quadrants",
+            Self::Rings => "rings",
+            Self::Safari => "safari",
+            Self::Sawtooth => "sawtooth",
+            Self::SawtoothGray => "sawtoothGray",
+            Self::ScaredCat => "scaredCat",
+            Self::Seattle => "seattle",
+            Self::ShadowedSquares => "shadowedSquares",
+            Self::SharksTeeth => "sharksTeeth",
+            Self::ShorebirdTracks => "shorebirdTracks",
+            Self::Skyrocket => "skyrocket",
+            Self::SnowflakeFancy => "snowflakeFancy",
+            Self::Snowflakes => "snowflakes",
+            Self::Sombrero => "sombrero",
+            Self::Southwest => "southwest",
+            Self::Stars => "stars",
+            Self::Stars3d => "stars3d",
+            Self::StarsBlack => "starsBlack",
+            Self::StarsShadowed => "starsShadowed",
+            Self::StarsTop => "starsTop",
+            Self::Sun => "sun",
+            Self::Swirligig => "swirligig",
+            Self::TornPaper => "tornPaper",
+            Self::TornPaperBlack => "tornPaperBlack",
+            Self::Trees => "trees",
+            Self::TriangleParty => "triangleParty",
+            Self::Triangles => "triangles",
+            Self::Tribal1 => "tribal1",
+            Self::Tribal2 => "tribal2",
+            Self::Tribal3 => "tribal3",
+            Self::Tribal4 => "tribal4",
+            Self::Tribal5 => "tribal5",
+            Self::Tribal6 => "tribal6",
+            Self::TwistedLines1 => "twistedLines1",
+            Self::TwistedLines2 => "twistedLines2",
+            Self::Vine => "vine",
+            Self::Waveline => "waveline",
+            Self::WeavingAngles => "weavingAngles",
+            Self::WeavingBraid => "weavingBraid",
+            Self::WeavingRibbon => "weavingRibbon",
+            Self::WeavingStrips => "weavingStrips",
+            Self::WhiteFlowers => "whiteFlowers",
+            Self::Woodwork => "woodwork",
+            Self::XIllusions => "xIllusions",
+            Self::ZanyTriangles => "zanyTriangles",
+            Self::ZigZag => "zigZag",
+            Self::ZigZagStitch => "zigZagStitch",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+/// The properties of a border.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BorderProperties {
+    pub border_type: BorderType,
+    pub width: EighteenthPoint<u32>,
+    pub spacing: WholePoint<u32>,
+    pub color: HexColor,
+}
+
+#[derive(Debug)]
+pub enum BorderPropertiesParseError {
+    BorderTypeParseError(BorderTypeParseError),
+    HexColorParseError(HexColorParseError),
+    ParseIntError(ParseIntError),
+
+    /// The `w:val` attribute is required by CT_Border.
+    ValAttributeMissing,
+}
+
+impl From<BorderTypeParseError> for BorderPropertiesParseError {
+    fn from(error: BorderTypeParseError) -> Self {
+        BorderPropertiesParseError::BorderTypeParseError(error)
+    }
+}
+
+impl From<HexColorParseError> for BorderPropertiesParseError {
+    fn from(error: HexColorParseError) -> Self {
+        BorderPropertiesParseError::HexColorParseError(error)
+    }
+}
+
+impl From<ParseIntError> for BorderPropertiesParseError {
+    fn from(error: ParseIntError) -> Self {
+        BorderPropertiesParseError::ParseIntError(error)
+    }
+}
+
+impl FromXmlStandalone for BorderProperties {
+    type ParseError = BorderPropertiesParseError;
+
+    fn from_xml(node: &xml::Node) -> Result<Self, BorderPropertiesParseError> {
+        let mut result = Self::default();
+
+        match node.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+            Some(val) => result.border_type = val.parse()?,
+            None => return Err(BorderPropertiesParseError::ValAttributeMissing)
+        }
+
+        if let Some(size) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "sz")) {
+            result.width = EighteenthPoint(size.parse()?);
+        }
+
+        if let Some(space) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "space")) {
+            result.spacing = WholePoint(space.parse()?);
+        }
+
+        if let Some(color) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "color")) {
+            result.color = color.parse()?;
+        }
+
+        Ok(result)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub enum HexColor {
+    #[default]
+    Auto,
+    Color(crate::gui::Color),
+}
+
+#[derive(Debug)]
+pub enum HexColorParseError {
+    NotSixHexadecimalDigits,
+    DigitOutOfRange,
+}
+
+impl FromStr for HexColor {
+    type Err = HexColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, HexColorParseError> {
+        if s == "auto" {
+            return Ok(Self::Auto)
+        }
+
+        if s.len() != 6 {
+            return Err(HexColorParseError::NotSixHexadecimalDigits);
+        }
+
+        let mut result = [0u8; 6];
+        for i in 0..6 {
+            result[i] = match s.as_bytes()[i] {
+                b'0'..=b'9' => s.as_bytes()[i] - b'0',
+                b'a'..=b'f' => s.as_bytes()[i] - b'a' + 10,
+                b'A'..=b'F' => s.as_bytes()[i] - b'A' + 10,
+                _ => return Err(HexColorParseError::DigitOutOfRange)
+            };
+        }
+
+        Ok(Self::Color(crate::gui::Color::from_rgb(
+            result[0] * 16 + result[1],
+            result[2] * 16 + result[3],
+            result[4] * 16 + result[5]
+        )))
+    }
+}
+
+/// ST_StyleType
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StyleType {
+    #[default]
+    Paragraph,
+    Character,
+    Table,
+    Numbering,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StyleTypeParseError {
+    UnknownStyleType(String),
+}
+
+impl FromStr for StyleType {
+    type Err = StyleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "paragraph" => Ok(Self::Paragraph),
+            "character" => Ok(Self::Character),
+            "table" => Ok(Self::Table),
+            "numbering" => Ok(Self::Numbering),
+            _ => Err(StyleTypeParseError::UnknownStyleType(s.to_string()))
+        }
+    }
+}
+
+impl StyleType {
+    /// The ST_StyleType value of the style type, as used in the `w:type`
+    /// attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Paragraph => "paragraph",
+            Self::Character => "character",
+            Self::Table => "table",
+            Self::Numbering => "numbering",
+        }
+    }
+}
+
+/// A `<w:style>` definition, with only the properties the style specifies
+/// itself. The properties of the styles it is based on are added when the
+/// style is resolved.
+struct Style {
+    style_type: StyleType,
+
+    /// The style this style inherits its properties from (17.7.4.3 basedOn).
+    based_on: Option<String>,
+
+    /// The paragraph style of a character style, or the character style of a
+    /// paragraph style (17.7.4.6 link).
+    link: Option<String>,
+
+    text_settings: TextSettings,
+    table_properties: Option<TableProperties>,
+
+    /// The formatting of the parts of a table, for table styles.
+    conditional_formats: Vec<(TableStyleOverrideType, TableConditionalFormatting)>,
+}
+
+/// The properties of a style, including the properties it inherits from the
+/// styles it is based on.
+#[derive(Clone, Debug)]
+pub struct ResolvedStyle {
+    pub text_settings: TextSettings,
+
+    /// The table properties of the nearest style in the chain specifying them.
+    pub table_properties: Option<TableProperties>,
+
+    pub conditional_formats: Vec<(TableStyleOverrideType, TableConditionalFormatting)>,
+}
+
+impl ResolvedStyle {
+    /// Combines the conditional formats of the given types, where the later
+    /// types override the earlier ones.
+    pub fn conditional_formatting(&self, types: &[TableStyleOverrideType]) -> TableConditionalFormatting {
+        let mut result = TableConditionalFormatting::default();
+        for format_type in types {
+            if let Some((_, formatting)) = self.conditional_formats.iter().find(|(candidate, _)| candidate == format_type) {
+                result.apply(formatting);
+            }
+        }
+        result
+    }
+}
+
+/// Adds the conditional format, or overrides the properties of the format of
+/// the same type if there is one.
+fn add_conditional_format(formats: &mut Vec<(TableStyleOverrideType, TableConditionalFormatting)>,
+        format_type: TableStyleOverrideType, formatting: &TableConditionalFormatting) {
+    match formats.iter_mut().find(|(candidate, _)| *candidate == format_type) {
+        Some((_, existing)) => existing.apply(formatting),
+        None => formats.push((format_type, formatting.clone())),
+    }
+}
+
+/// Reads the borders and the shading of a `<w:tcPr>` of a table style.
+fn apply_conditional_cell_properties(manager: &mut StyleManager, formatting: &mut TableConditionalFormatting, element: &xml::Node) {
+    match TableCellProperties::from_xml(element) {
+        Ok(properties) => {
+            formatting.cell_borders = properties.borders.or(&formatting.cell_borders);
+            formatting.cell_shading = properties.shading.or(formatting.cell_shading);
+        }
+        Err(e) => manager.diagnostics.push(Diagnostic::warning("w:tcPr", format!("invalid cell properties: {:?}", e))),
+    }
+}
+
+fn is_correct_namespace(element: &xml::Node) -> bool {
+    if element.tag_name().namespace().is_none() {
+        return false;
+    }
+
+    element.tag_name().namespace().unwrap() == WORD_PROCESSING_XML_NAMESPACE
+}
+
+impl Style {
+
+    pub fn from_xml(manager: &mut StyleManager, theme_settings: &ThemeSettings,
+            numbering_manager: &crate::wp::numbering::NumberingManager, element: &xml::Node) -> Self {
+        assert!(element.tag_name().namespace().is_some());
+        assert_eq!(element.tag_name().namespace().unwrap(), WORD_PROCESSING_XML_NAMESPACE);
+
+        // 17.7.4.17 style: a missing type means a paragraph style.
+        let style_type = match element.attribute((WORD_PROCESSING_XML_NAMESPACE, "type")).map(StyleType::from_str) {
+            Some(Ok(style_type)) => style_type,
+            Some(Err(e)) => {
+                manager.diagnostics.push(Diagnostic::warning("w:style", format!("invalid w:type: {:?}", e)));
+                StyleType::Paragraph
+            }
+            None => StyleType::Paragraph,
+        };
+
+        let mut style = Style{
+            style_type,
+            based_on: None,
+            link: None,
+            text_settings: TextSettings::new(),
+            table_properties: None,
+            conditional_formats: Vec::new(),
+        };
+
+        for child in element.children() {
+            #[cfg(feature = "debug-styles")]
+            println!("Style>> {}", child.tag_name().name());
+
+            if child.tag_name().namespace().is_none() || child.tag_name().namespace().unwrap() != WORD_PROCESSING_XML_NAMESPACE {
+                println!("Incorrect namespace: {:?}", child.tag_name().namespace());
+                continue;
+            }
+
+            match child.tag_name().name() {
+                "basedOn" => {
+                    style.based_on = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")).map(String::from);
+                }
+                "link" => {
+                    style.link = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")).map(String::from);
+                }
+                "rPr" => {
+                    let mut diagnostics = Vec::new();
+                    style.text_settings.apply_run_properties_element(manager, theme_settings, &child, &mut diagnostics);
+                    manager.diagnostics.extend(diagnostics);
+                }
+                "pPr" => {
+                    let mut diagnostics = Vec::new();
+                    crate::word_processing::process_paragraph_properties_element(numbering_manager, manager,
+                        &mut style.text_settings, &child, &mut diagnostics);
+                    manager.diagnostics.extend(diagnostics);
+                }
+                "tblPr" => {
+                    match TableProperties::from_xml(&child) {
+                        Ok(properties) => style.table_properties = Some(properties),
+                        Err(e) => manager.diagnostics.push(Diagnostic::warning("w:tblPr", format!("invalid table properties: {:?}", e))),
+                    }
+                }
+                // The cell properties of the style apply to the whole table.
+                "tcPr" => {
+                    let mut formatting = TableConditionalFormatting::default();
+                    apply_conditional_cell_properties(manager, &mut formatting, &child);
+                    add_conditional_format(&mut style.conditional_formats, TableStyleOverrideType::WholeTable, &formatting);
+                }
+                // 17.7.6.6 tblStylePr (Style Conditional Table Formatting Properties)
+                "tblStylePr" => {
+                    let format_type = match child.attribute((WORD_PROCESSING_XML_NAMESPACE, "type")).map(TableStyleOverrideType::from_str) {
+                        Some(Ok(format_type)) => format_type,
+                        value => {
+                            manager.diagnostics.push(Diagnostic::warning("w:tblStylePr", format!("invalid w:type: {:?}", value)));
+                            continue;
+                        }
+                    };
+
+                    let mut formatting = TableConditionalFormatting::default();
+                    for property in child.children() {
+                        match property.tag_name().name() {
+                            "rPr" => {
+                                let mut diagnostics = Vec::new();
+                                formatting.text_settings.apply_run_properties_element(manager, theme_settings, &property, &mut diagnostics);
+                                manager.diagnostics.extend(diagnostics);
+                            }
+                            "pPr" => {
+                                let mut diagnostics = Vec::new();
+                                crate::word_processing::process_paragraph_properties_element(numbering_manager, manager,
+                                    &mut formatting.text_settings, &property, &mut diagnostics);
+                                manager.diagnostics.extend(diagnostics);
+                            }
+                            "tcPr" => apply_conditional_cell_properties(manager, &mut formatting, &property),
+                            _ => (),
+                        }
+                    }
+
+                    add_conditional_format(&mut style.conditional_formats, format_type, &formatting);
+                }
+                _ => {
+                    #[cfg(feature = "debug-styles")]
+                    println!("  Unknown");
+                }
+            }
+        }
+
+        style
+    }
+
+}
+
+pub struct StyleManager {
+    styles: HashMap<String, Style>,
+
+    /// The styles that were resolved already, by their style ID.
+    resolved_styles: RefCell<HashMap<String, Rc<ResolvedStyle>>>,
+
+    default_text_settings: TextSettings,
+
+    /// The problems encountered while loading the styles.
+    diagnostics: Vec<Diagnostic>,
+}
+
+fn process_xml_doc_defaults(element: &xml::Node, manager: &mut StyleManager, theme_settings: &ThemeSettings) {
+    for child in element.children() {
+        #[cfg(feature = "debug-styles")]
+        println!("Style⟫ │  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "rPrDefault" => {
+                process_xml_rpr_default(&child, theme_settings, manager);
+            }
+            "pPrDefault" => {
+                process_xml_ppr_default(&child, manager);
+            }
+            _ => ()
+        }
+    }
+}
+
+fn process_xml_rpr_default(element: &xml::Node, theme_settings: &ThemeSettings, manager: &mut StyleManager) {
+    for child in element.children() {
+        #[cfg(feature = "debug-styles")]
+        println!("Style⟫ │  │  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "rPr" => {
+                let mut settings = manager.default_text_settings.clone();
+
+                let mut diagnostics = Vec::new();
+                settings.apply_run_properties_element(manager, theme_settings, &child, &mut diagnostics);
+                manager.diagnostics.extend(diagnostics);
+
+                manager.default_text_settings = settings;
+            }
+            _ => ()
+        }
+    }
+}
+
+fn process_xml_ppr_default(element: &xml::Node, manager: &mut StyleManager) {
+    for child in element.children() {
+        #[cfg(feature = "debug-styles")]
+        println!("Style⟫ │  │  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "pPr" => {
+
+                // TODO implement this better
+                for property in child.children() {
+                    match property.tag_name().name() {
+                        "spacing" => {
+                            if let Some(val) = property.attribute((WORD_PROCESSING_XML_NAMESPACE, "after")) {
+                                match val.parse() {
+                                    Ok(value) => manager.default_text_settings.spacing_below_paragraph = Some(TwelfteenthPoint(value)),
+                                    Err(..) => manager.diagnostics.push(Diagnostic::error("w:spacing", format!("invalid 'after' value: \"{}\"", val))),
+                                }
+                            }
+                        }
+                        _ => ()
+                    }
+                }
+            }
+            _ => ()
+        }
+    }
+}
+
+impl StyleManager {
+    pub fn from_document(document: &xml::Document, numbering_manager: &crate::wp::numbering::NumberingManager,
+            theme_settings: &ThemeSettings) -> Result<Self, Error> {
+        let mut manager = StyleManager{
+            styles: HashMap::new(),
+            resolved_styles: RefCell::new(HashMap::new()),
+            default_text_settings: TextSettings::new(),
+            diagnostics: Vec::new(),
+        };
+
+        assert_eq!(document.root_element().tag_name().name(), "styles");
+        assert!(is_correct_namespace(&document.root_element()));
+
+        #[cfg(feature = "debug-styles")]
+        println!("Style⟫ {}", document.root_element().tag_name().name());
+
+        for element in document.root_element().children() {
+            #[cfg(feature = "debug-styles")]
+            println!("Style⟫ ├─ {}", element.tag_name().name());
+
+            if !is_correct_namespace(&element) {
+                continue;
+            }
+
+            match element.tag_name().name() {
+                "docDefaults" => process_xml_doc_defaults(&element, &mut manager, theme_settings),
+                "style" =>
+                    match element.attribute((WORD_PROCESSING_XML_NAMESPACE, "styleId")) {
+                        Some(id) => {
+                            #[cfg(feature = "debug-styles")]
+                            println!("Style> {}", id);
+                            let style = Style::from_xml(&mut manager, theme_settings, numbering_manager, &element);
+                            manager.styles.insert(String::from(id), style);
+                        }
+                        None => {
+                            manager.diagnostics.push(Diagnostic::warning("w:style", String::from("missing w:styleId attribute")));
+                        }
+                    }
+                _ => ()
+            }
+        }
+
+        manager.check_inheritance_chains();
+
+        // Styles resolved while loading might have used the links that were
+        // removed.
+        manager.resolved_styles.borrow_mut().clear();
+
+        // Resolve the text size once, so documents that never specify a
+        // size still have one.
+        if manager.default_text_settings.non_complex_text_size.is_none() {
+            manager.default_text_settings.non_complex_text_size = Some(crate::text_settings::DEFAULT_NON_COMPLEX_TEXT_SIZE);
+        }
+
+        Ok(manager)
+    }
+
+    /// Follows the `w:basedOn` chain of every style, and removes the links
+    /// to unknown styles, to styles of another type and the links closing a
+    /// cycle, so the styles can be resolved without checking them again.
+    fn check_inheritance_chains(&mut self) {
+        // Sorted so the same link of a cycle is removed every time.
+        let mut style_ids: Vec<String> = self.styles.keys().cloned().collect();
+        style_ids.sort();
+
+        for style_id in style_ids {
+            let mut chain = vec![style_id];
+            let problem = loop {
+                let style = &self.styles[chain.last().unwrap()];
+                let Some(based_on) = &style.based_on else {
+                    break None;
+                };
+
+                match self.styles.get(based_on) {
+                    None => break Some(format!("the style is based on the unknown style \"{}\"", based_on)),
+                    Some(base) if base.style_type != style.style_type => {
+                        break Some(format!("the {} style is based on the {} style \"{}\"",
+                            style.style_type.as_str(), base.style_type.as_str(), based_on));
+                    }
+                    Some(..) if chain.contains(based_on) => {
+                        break Some(format!("the styles are based on each other: {} → {}", chain.join(" → "), based_on));
+                    }
+                    Some(..) => chain.push(based_on.clone()),
+                }
+            };
+
+            if let Some(problem) = problem {
+                let style_id = chain.last().unwrap();
+                self.diagnostics.push(Diagnostic::warning("w:basedOn", format!("{}: {}", style_id, problem)));
+                self.styles.get_mut(style_id).unwrap().based_on = None;
+            }
+        }
+    }
+
+    /// Resolves the properties of the style and the styles it is based on,
+    /// or returns [None] if the style doesn't exist. The results are cached.
+    pub fn resolve_style(&self, style_id: &str) -> Option<Rc<ResolvedStyle>> {
+        if let Some(resolved) = self.resolved_styles.borrow().get(style_id) {
+            return Some(Rc::clone(resolved));
+        }
+
+        self.styles.get(style_id)?;
+
+        // Walk up the chain until a resolved style is found. The chains are
+        // checked after loading, but character styles can be referenced
+        // while the styles are still being loaded, so a cycle stops the
+        // chain here as well.
+        let mut chain = vec![style_id];
+        let mut base = None;
+        while let Some(based_on) = self.styles[*chain.last().unwrap()].based_on.as_deref() {
+            if let Some(resolved) = self.resolved_styles.borrow().get(based_on) {
+                base = Some(Rc::clone(resolved));
+                break;
+            }
+
+            if !self.styles.contains_key(based_on) || chain.contains(&based_on) {
+                break;
+            }
+
+            chain.push(based_on);
+        }
+
+        let mut resolved = match base {
+            Some(base) => (*base).clone(),
+            None => ResolvedStyle {
+                text_settings: TextSettings::new(),
+                table_properties: None,
+                conditional_formats: Vec::new(),
+            },
+        };
+
+        for style_id in chain.iter().rev() {
+            let style = &self.styles[*style_id];
+            resolved.text_settings.inherit_from(&style.text_settings);
+            if style.table_properties.is_some() {
+                resolved.table_properties = style.table_properties;
+            }
+
+            for (format_type, formatting) in &style.conditional_formats {
+                add_conditional_format(&mut resolved.conditional_formats, *format_type, formatting);
+            }
+        }
+
+        let resolved = Rc::new(resolved);
+        self.resolved_styles.borrow_mut().insert(String::from(style_id), Rc::clone(&resolved));
+        Some(resolved)
+    }
+
+    /// The type of the style, or [None] if the style doesn't exist.
+    pub fn style_type(&self, style_id: &str) -> Option<StyleType> {
+        self.styles.get(style_id).map(|style| style.style_type)
+    }
+
+    pub fn apply_paragraph_style(&self, style_id: &str, paragraph_text_settings: &mut TextSettings, diagnostics: &mut Vec<Diagnostic>) {
+        match self.resolve_style(style_id) {
+            Some(style) => paragraph_text_settings.inherit_from(&style.text_settings),
+            None => diagnostics.push(Diagnostic::warning("w:pStyle", format!("paragraph style not found: \"{}\"", style_id))),
+        }
+    }
+
+    /// Applies the character style to the text settings of a run. When the
+    /// run refers to a paragraph style instead, the character style linked
+    /// to it is used, like Word does.
+    pub fn apply_character_style(&self, style_id: &str, text_settings: &mut TextSettings) {
+        let style_id = match self.styles.get(style_id) {
+            Some(Style { style_type: StyleType::Paragraph, link: Some(link), .. })
+                    if self.style_type(link) == Some(StyleType::Character) => link.as_str(),
+            _ => style_id,
+        };
+
+        if let Some(style) = self.resolve_style(style_id) {
+            text_settings.inherit_from(&style.text_settings);
+        }
+    }
+
+    /// The IDs of all styles, in no particular order.
+    pub fn style_ids(&self) -> impl Iterator<Item = &str> {
+        self.styles.keys().map(String::as_str)
+    }
+
+    /// The style the style inherits its properties from, if any.
+    pub fn based_on(&self, style_id: &str) -> Option<&str> {
+        self.styles.get(style_id)?.based_on.as_deref()
+    }
+
+    pub fn default_text_settings(&self) -> TextSettings {
+        self.default_text_settings.clone()
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HalfPoint;
+    use crate::{gui::Color, wp::numbering::NumberingManager};
+
+    const STYLES: &str = concat!(
+        r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+        r#"<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:pPr><w:spacing w:after="160"/></w:pPr><w:rPr><w:sz w:val="22"/></w:rPr></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Heading1"><w:basedOn w:val="Normal"/><w:link w:val="Heading1Char"/><w:rPr><w:b/><w:sz w:val="32"/></w:rPr></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Heading2"><w:rPr><w:sz w:val="26"/></w:rPr><w:basedOn w:val="Heading1"/></w:style>"#,
+        r#"<w:style w:type="character" w:styleId="Heading1Char"><w:basedOn w:val="DefaultParagraphFont"/><w:rPr><w:color w:val="FF0000"/></w:rPr></w:style>"#,
+        r#"<w:style w:type="character" w:default="1" w:styleId="DefaultParagraphFont"/>"#,
+        r#"<w:style w:type="table" w:default="1" w:styleId="TableNormal"><w:tblPr><w:tblCellMar><w:left w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>"#,
+        r#"<w:style w:type="table" w:styleId="TableGrid"><w:basedOn w:val="TableNormal"/><w:rPr><w:i/></w:rPr></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Loop1"><w:basedOn w:val="Loop2"/></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Loop2"><w:basedOn w:val="Loop1"/><w:rPr><w:sz w:val="40"/></w:rPr></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Mixed"><w:basedOn w:val="Heading1Char"/></w:style>"#,
+        r#"</w:styles>"#,
+    );
+
+    fn create_style_manager() -> StyleManager {
+        let document = xml::Document::parse(STYLES).unwrap();
+        StyleManager::from_document(&document, &NumberingManager::new(), &ThemeSettings::default()).unwrap()
+    }
+
+    #[test]
+    fn styles_inherit_through_the_whole_chain() {
+        let manager = create_style_manager();
+        let style = manager.resolve_style("Heading2").unwrap();
+
+        // The own properties aren't overwritten by the style it is based on,
+        // even though the w:basedOn comes after them.
+        assert_eq!(style.text_settings.non_complex_text_size, Some(HalfPoint(26)));
+        assert_eq!(style.text_settings.bold, Some(true));
+        assert_eq!(style.text_settings.spacing_below_paragraph, Some(TwelfteenthPoint(160)));
+
+        assert_eq!(manager.resolve_style("Heading1").unwrap().text_settings.non_complex_text_size, Some(HalfPoint(32)));
+        assert!(manager.resolve_style("Heading3").is_none());
+    }
+
+    #[test]
+    fn table_styles_keep_their_table_properties() {
+        let manager = create_style_manager();
+        let style = manager.resolve_style("TableGrid").unwrap();
+        assert_eq!(style.table_properties.unwrap().cell_margins.left, TwelfteenthPoint(108));
+        assert_eq!(style.text_settings.italic, Some(true));
+        assert!(manager.resolve_style("Normal").unwrap().table_properties.is_none());
+    }
+
+    #[test]
+    fn run_styles_referring_to_paragraph_styles_use_the_linked_character_style() {
+        let manager = create_style_manager();
+
+        let mut text_settings = TextSettings::new();
+        manager.apply_character_style("Heading1", &mut text_settings);
+        assert_eq!(text_settings.color, Some(Color::RED));
+        assert_eq!(text_settings.bold, None);
+
+        let mut text_settings = TextSettings::new();
+        manager.apply_character_style("Heading1Char", &mut text_settings);
+        assert_eq!(text_settings.color, Some(Color::RED));
+    }
+
+    #[test]
+    fn invalid_inheritance_is_reported_and_ignored() {
+        let manager = create_style_manager();
+        assert_eq!(manager.diagnostics().len(), 2);
+
+        // The link of Loop2 closes the cycle, since Loop1 is checked first.
+        assert_eq!(manager.resolve_style("Loop1").unwrap().text_settings.non_complex_text_size, Some(HalfPoint(40)));
+        assert_eq!(manager.resolve_style("Loop2").unwrap().text_settings.non_complex_text_size, Some(HalfPoint(40)));
+
+        // A paragraph style can't be based on a character style.
+        assert_eq!(manager.resolve_style("Mixed").unwrap().text_settings.color, None);
+    }
+
+    #[test]
+    fn malformed_default_properties_are_reported() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:docDefaults>"#,
+            r#"<w:rPrDefault><w:rPr><w:sz w:val="large"/></w:rPr></w:rPrDefault>"#,
+            r#"<w:pPrDefault><w:pPr><w:spacing w:after="some"/></w:pPr></w:pPrDefault>"#,
+            r#"</w:docDefaults></w:styles>"#,
+        )).unwrap();
+        let manager = StyleManager::from_document(&document, &NumberingManager::new(), &ThemeSettings::default()).unwrap();
+
+        let sources: Vec<&str> = manager.diagnostics().iter().map(|diagnostic| diagnostic.source.as_str()).collect();
+        assert_eq!(sources, vec!["w:sz", "w:spacing"]);
+
+        let text_settings = manager.default_text_settings();
+        assert_eq!(text_settings.non_complex_text_size, Some(crate::text_settings::DEFAULT_NON_COMPLEX_TEXT_SIZE));
+        assert_eq!(text_settings.spacing_below_paragraph, None);
+    }
+}
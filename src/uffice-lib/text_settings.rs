@@ -0,0 +1,954 @@
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{rc::Rc, cell::RefCell, str::FromStr};
+
+use roxmltree as xml;
+use crate::{TwelfteenthPoint, HalfPoint};
+
+use crate::{
+    color_parser,
+    WORD_PROCESSING_XML_NAMESPACE,
+    serialize::FromXmlStandalone,
+    style::{BorderProperties, BorderPropertiesParseError, StyleManager},
+    wp::{
+        diagnostics::Diagnostic,
+        layout::LineLayout,
+        table::is_border_visible,
+        Node,
+    },
+    drawing_ml,
+    gui::Color,
+    gui::{
+        painter::{
+            FontSpecification,
+            FontWeight,
+            TextCalculator, FontStyle,
+        },
+        Rect,
+        Size,
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PageSettings {
+    pub size: Size<TwelfteenthPoint<u32>>,
+    pub margins: Rect<TwelfteenthPoint<u32>>,
+    pub offset_header: TwelfteenthPoint<u32>,
+    pub offset_footer: TwelfteenthPoint<u32>,
+}
+
+impl PageSettings {
+    pub fn new(size: Size<TwelfteenthPoint<u32>>, margins: Rect<TwelfteenthPoint<u32>>,
+               offset_header: TwelfteenthPoint<u32>, offset_footer: TwelfteenthPoint<u32>) -> Self {
+        Self { size, margins, offset_header, offset_footer }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextJustification {
+    Start,
+    Center,
+    End,
+
+    /// Every line, except the last, is stretched to fill the line by adding
+    /// space between the words.
+    Both,
+
+    /// Every line, including the last, is stretched to fill the line by
+    /// adding space between the characters.
+    Distribute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TabStopAlignment {
+    Start,
+    Center,
+    End,
+}
+
+/// A custom tab stop of a paragraph (17.3.1.37 tab).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabStop {
+    pub alignment: TabStopAlignment,
+
+    /// The position relative to the start of the text margin.
+    pub position: TwelfteenthPoint<i32>,
+}
+
+/// The borders around a paragraph (17.3.1.24 pBdr).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParagraphBorders {
+    pub top: Option<BorderProperties>,
+    pub left: Option<BorderProperties>,
+    pub bottom: Option<BorderProperties>,
+    pub right: Option<BorderProperties>,
+}
+
+impl ParagraphBorders {
+    /// The distance between the text and the outer edge of the top border,
+    /// which is zero when there is no visible border.
+    pub fn top_extent(&self) -> f32 {
+        border_extent(self.top)
+    }
+
+    pub fn left_extent(&self) -> f32 {
+        border_extent(self.left)
+    }
+
+    pub fn bottom_extent(&self) -> f32 {
+        border_extent(self.bottom)
+    }
+
+    pub fn right_extent(&self) -> f32 {
+        border_extent(self.right)
+    }
+}
+
+/// The width of the border plus the spacing between it and the text.
+fn border_extent(border: Option<BorderProperties>) -> f32 {
+    match border {
+        Some(border) if is_border_visible(&border) => border.width.get_pts() + border.spacing.get_pts(),
+        _ => 0.0,
+    }
+}
+
+impl FromXmlStandalone for ParagraphBorders {
+    type ParseError = BorderPropertiesParseError;
+
+    fn from_xml(node: &xml::Node) -> Result<Self, BorderPropertiesParseError> {
+        let mut borders = Self::default();
+
+        for border in node.children() {
+            match border.tag_name().name() {
+                "top" => borders.top = Some(BorderProperties::from_xml(&border)?),
+                "left" | "start" => borders.left = Some(BorderProperties::from_xml(&border)?),
+                "bottom" => borders.bottom = Some(BorderProperties::from_xml(&border)?),
+                "right" | "end" => borders.right = Some(BorderProperties::from_xml(&border)?),
+                _ => ()
+            }
+        }
+
+        Ok(borders)
+    }
+}
+
+/// The spacing between the lines of a paragraph (17.3.1.33 spacing, the
+/// `w:line` and `w:lineRule` attributes).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineSpacing {
+    /// The line height of the font multiplied by the given factor, e.g. 2.0
+    /// for double spacing.
+    Auto(f32),
+
+    /// Exactly the given height, regardless of the font.
+    Exact(TwelfteenthPoint<u32>),
+
+    /// The line height of the font, but at least the given height.
+    AtLeast(TwelfteenthPoint<u32>),
+}
+
+/// ST_VerticalAlignRun, the position of the text of a run relative to the
+/// baseline (17.3.2.42 vertAlign).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAlignment {
+    Baseline,
+    Superscript,
+    Subscript,
+}
+
+impl VerticalAlignment {
+    /// The ST_VerticalAlignRun value, as used in the `w:val` attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Superscript => "superscript",
+            Self::Subscript => "subscript",
+        }
+    }
+}
+
+/// ST_Underline, the line drawn under the text of a run (17.3.2.40 u).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnderlineStyle {
+    Single,
+
+    /// A single line under the words, but not under the spaces between them.
+    Words,
+    Double,
+    Thick,
+    Dotted,
+    DottedHeavy,
+    Dash,
+    DashedHeavy,
+    DashLong,
+    DashLongHeavy,
+    DotDash,
+    DashDotHeavy,
+    DotDotDash,
+    DashDotDotHeavy,
+    Wave,
+    WavyHeavy,
+    WavyDouble,
+    None,
+}
+
+#[derive(Clone, Debug)]
+pub enum UnderlineStyleParseError {
+    UnknownUnderlineStyle(String),
+}
+
+impl FromStr for UnderlineStyle {
+    type Err = UnderlineStyleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(Self::Single),
+            "words" => Ok(Self::Words),
+            "double" => Ok(Self::Double),
+            "thick" => Ok(Self::Thick),
+            "dotted" => Ok(Self::Dotted),
+            "dottedHeavy" => Ok(Self::DottedHeavy),
+            "dash" => Ok(Self::Dash),
+            "dashedHeavy" => Ok(Self::DashedHeavy),
+            "dashLong" => Ok(Self::DashLong),
+            "dashLongHeavy" => Ok(Self::DashLongHeavy),
+            "dotDash" => Ok(Self::DotDash),
+            "dashDotHeavy" => Ok(Self::DashDotHeavy),
+            "dotDotDash" => Ok(Self::DotDotDash),
+            "dashDotDotHeavy" => Ok(Self::DashDotDotHeavy),
+            "wave" => Ok(Self::Wave),
+            "wavyHeavy" => Ok(Self::WavyHeavy),
+            "wavyDouble" => Ok(Self::WavyDouble),
+            "none" => Ok(Self::None),
+            _ => Err(UnderlineStyleParseError::UnknownUnderlineStyle(s.to_owned())),
+        }
+    }
+}
+
+impl UnderlineStyle {
+    /// The ST_Underline value of the style, as used in the `w:val` attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Single => "single",
+            Self::Words => "words",
+            Self::Double => "double",
+            Self::Thick => "thick",
+            Self::Dotted => "dotted",
+            Self::DottedHeavy => "dottedHeavy",
+            Self::Dash => "dash",
+            Self::DashedHeavy => "dashedHeavy",
+            Self::DashLong => "dashLong",
+            Self::DashLongHeavy => "dashLongHeavy",
+            Self::DotDash => "dotDash",
+            Self::DashDotHeavy => "dashDotHeavy",
+            Self::DotDotDash => "dotDotDash",
+            Self::DashDotDotHeavy => "dashDotDotHeavy",
+            Self::Wave => "wave",
+            Self::WavyHeavy => "wavyHeavy",
+            Self::WavyDouble => "wavyDouble",
+            Self::None => "none",
+        }
+    }
+
+    /// Whether the line is twice as thick as a single line.
+    pub fn is_heavy(&self) -> bool {
+        matches!(self, Self::Thick | Self::DottedHeavy | Self::DashedHeavy | Self::DashLongHeavy
+            | Self::DashDotHeavy | Self::DashDotDotHeavy | Self::WavyHeavy)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Numbering {
+    pub definition: Option<Rc<RefCell<crate::wp::numbering::NumberingDefinitionInstance>>>,
+    pub level: Option<i32>,
+}
+impl Numbering {
+    pub fn create_node(&self, paragraph: &mut Node, line_layout: &mut LineLayout,
+                       text_calculator: &mut dyn TextCalculator,
+                       theme: &crate::drawing_ml::style::StyleSettings, fallback_font: &str,
+                       diagnostics: &mut Vec<Diagnostic>) -> Option<(usize, usize)> {
+        let Some(numbering_definition_instance) = &self.definition else {
+            diagnostics.push(Diagnostic::warning("w:numPr", String::from("numbering definition instance is missing")));
+            return None;
+        };
+
+        let level_idx = self.level.unwrap_or(0);
+        let language = paragraph.text_settings.numbering_language();
+        let Some(mut displayed_text) = numbering_definition_instance.borrow_mut().next_text(level_idx, language) else {
+            diagnostics.push(Diagnostic::warning("w:ilvl", format!("numbering level {} is not defined", level_idx)));
+            return None;
+        };
+
+        let numbering_definition_instance = numbering_definition_instance.borrow();
+        let abstract_definition = numbering_definition_instance
+                .abstract_numbering_definition
+                .as_ref()
+                .unwrap()
+                .borrow();
+        let level = abstract_definition.levels.get(&level_idx).unwrap().borrow();
+
+        // See the documentation of NodeData::NumberingParent for why we need
+        // this parent and not just inherit from the parent Paragraph.
+        let numbering_parent = crate::wp::create_child(paragraph, crate::wp::NodeData::NumberingParent);
+        let text_settings = self.combine_text_settings(paragraph, &level);
+
+        // Bullets are usually written in a symbol font, which has its own
+        // glyphs for ordinary characters, so without that font, the text is
+        // replaced by the Unicode characters that look like those glyphs.
+        let family_name = text_settings.font_family_name(theme);
+        if text_calculator.font_metrics(text_settings.font_specification(&family_name)).is_err() {
+            if let Some(text) = crate::unicode::symbol_fonts::to_unicode(&family_name, &displayed_text) {
+                displayed_text = text;
+            }
+        }
+
+        let num_parent = paragraph.nth_child_mut(numbering_parent);
+        num_parent.text_settings = text_settings;
+
+        crate::word_processing::append_text_element(&displayed_text, num_parent, line_layout, text_calculator, theme, fallback_font);
+        Some((numbering_parent, 0))
+    }
+
+    fn combine_text_settings(&self, paragraph: &crate::wp::Node, level: &crate::wp::numbering::NumberingLevelDefinition) -> TextSettings {
+        let mut settings = paragraph.text_settings.clone();
+        settings.inherit_from(&level.text_settings);
+        settings
+    }
+}
+
+/// The text size used when no size is specified at any level, which is the
+/// default of Microsoft Word (11pt).
+pub const DEFAULT_NON_COMPLEX_TEXT_SIZE: HalfPoint<u32> = HalfPoint(22);
+
+/// The outline level of paragraphs that aren't part of the outline of the
+/// document (17.3.1.20 outlineLvl).
+pub const OUTLINE_LEVEL_BODY_TEXT: u8 = 9;
+
+/// The size of superscript and subscript text, relative to the size of the
+/// text of the run.
+const VERTICAL_ALIGNMENT_SIZE_FACTOR: f32 = 0.65;
+
+/// How far superscript text is raised, and subscript text is lowered,
+/// relative to the size of the text of the run.
+const SUPERSCRIPT_RAISE_FACTOR: f32 = 0.33;
+const SUBSCRIPT_LOWER_FACTOR: f32 = 0.14;
+
+#[derive(Clone, Debug)]
+pub struct TextSettings {
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<UnderlineStyle>,
+
+    /// The color of the underline, or the color of the text when [None].
+    pub underline_color: Option<Color>,
+
+    /// The space added after every character (<w:spacing>), which is
+    /// negative for condensed text.
+    pub character_spacing: Option<TwelfteenthPoint<i32>>,
+
+    /// The text is stretched horizontally by this percentage (<w:w>).
+    pub horizontal_scale: Option<u32>,
+
+    /// The smallest font size at which the font is kerned (<w:kern>).
+    pub kerning: Option<HalfPoint<u32>>,
+
+    /// Whether the text is superscript or subscript (<w:vertAlign>).
+    pub vertical_alignment: Option<VerticalAlignment>,
+    pub font: Option<Rc<str>>,
+    pub color: Option<Color>,
+
+    /// The language of the text (<w:lang w:val>), e.g. `nb-NO`.
+    pub language: Option<Rc<str>>,
+
+    /// The language of the complex script text, such as Arabic
+    /// (<w:lang w:bidi>).
+    pub complex_script_language: Option<Rc<str>>,
+
+    /// Whether the run is right-to-left text (<w:rtl>), which makes the
+    /// spaces and numbers in it flow right-to-left too.
+    pub right_to_left: Option<bool>,
+
+    /// Whether the paragraph is a right-to-left paragraph (<w:bidi>), which
+    /// starts at the right and mirrors its alignment and indentation.
+    pub bidi: Option<bool>,
+
+    pub spacing_above_paragraph: Option<TwelfteenthPoint<u32>>,
+    pub spacing_below_paragraph: Option<TwelfteenthPoint<u32>>,
+
+    /// When true, the spacing above the paragraph is determined
+    /// automatically, ignoring `spacing_above_paragraph`.
+    pub spacing_above_paragraph_automatic: Option<bool>,
+
+    /// When true, the spacing below the paragraph is determined
+    /// automatically, ignoring `spacing_below_paragraph`.
+    pub spacing_below_paragraph_automatic: Option<bool>,
+
+    /// The spacing between the lines of the paragraph.
+    pub line_spacing: Option<LineSpacing>,
+
+    /// When true, the spacing above and below the paragraph is ignored
+    /// between paragraphs of the same style.
+    pub contextual_spacing: Option<bool>,
+
+    /// The styleId of the paragraph style (<w:pStyle>) of the paragraph.
+    pub paragraph_style_id: Option<Rc<str>>,
+
+    /// The outline level (<w:outlineLvl>) of the paragraph, where 0 is the
+    /// top level and 9 means body text.
+    pub outline_level: Option<u8>,
+
+    pub non_complex_text_size: Option<HalfPoint<u32>>,
+    pub justify: Option<TextJustification>,
+
+    pub highlight_color: Option<Color>,
+    pub numbering: Option<Numbering>,
+
+    /// The borders around the paragraph (<w:pBdr>).
+    pub paragraph_borders: Option<ParagraphBorders>,
+
+    /// The custom tab stops (<w:tabs>) of the paragraph, ordered by position.
+    pub tab_stops: Option<Vec<TabStop>>,
+
+    /// Specifies the indentation which shall be removed from the first line of
+    /// the parent paragraph, by moving the indentation on the first line back
+    /// towards the beginning of the direction of text flow.
+    pub indentation_hanging: Option<TwelfteenthPoint<i32>>,
+
+    /// The distance between the start of the text margin and the start of
+    /// the lines of the paragraph.
+    pub indentation_left: Option<TwelfteenthPoint<i32>>,
+
+    /// The distance between the end of the lines of the paragraph and the
+    /// end of the text margin.
+    pub indentation_right: Option<TwelfteenthPoint<i32>>,
+
+    /// Specifies the indentation which shall be added to the first line of
+    /// the paragraph. It excludes the hanging indentation.
+    pub indentation_first_line: Option<TwelfteenthPoint<i32>>,
+}
+
+/// Resolves the `w:themeColor` attribute of the element using the color
+/// scheme of the theme, darkened or lightened by the `w:themeShade` and
+/// `w:themeTint` attributes, which are hexadecimal bytes.
+fn parse_theme_color(element: &xml::Node, theme_settings: &drawing_ml::style::StyleSettings,
+                     diagnostics: &mut Vec<Diagnostic>) -> Option<Color> {
+    let theme_color = element.attribute((WORD_PROCESSING_XML_NAMESPACE, "themeColor"))?;
+    if theme_color == "none" {
+        return None;
+    }
+
+    let Some(mut color) = theme_settings.theme_elements.color_scheme.color(theme_color) else {
+        let source = format!("w:{}", element.tag_name().name());
+        diagnostics.push(Diagnostic::warning(&source, format!("unknown theme color: \"{}\"", theme_color)));
+        return None;
+    };
+
+    let parse_byte = |name: &str| element.attribute((WORD_PROCESSING_XML_NAMESPACE, name))
+        .and_then(|value| u8::from_str_radix(value, 16).ok())
+        .map(|value| value as f32 / 255.0);
+
+    if let Some(tint) = parse_byte("themeTint") {
+        color = drawing_ml::style::tint_color(color, tint);
+    }
+
+    if let Some(shade) = parse_byte("themeShade") {
+        color = drawing_ml::style::shade_color(color, shade);
+    }
+
+    Some(color)
+}
+
+fn inherit_or_original<T: Clone + std::fmt::Debug>(inherit: &Option<T>, original: &mut Option<T>) {
+    if let Some(value) = inherit {
+        *original = Some((*value).clone());
+    }
+}
+
+impl TextSettings {
+    pub fn new() -> Self {
+        Self{
+            bold: None,
+            italic: None,
+            underline: None,
+            underline_color: None,
+            character_spacing: None,
+            horizontal_scale: None,
+            kerning: None,
+            vertical_alignment: None,
+            font: None,
+            color: None,
+            language: None,
+            complex_script_language: None,
+            right_to_left: None,
+            bidi: None,
+            spacing_above_paragraph: None,
+            spacing_below_paragraph: None,
+            spacing_above_paragraph_automatic: None,
+            spacing_below_paragraph_automatic: None,
+            line_spacing: None,
+            contextual_spacing: None,
+            paragraph_style_id: None,
+            outline_level: None,
+            non_complex_text_size: None,
+            justify: None,
+            highlight_color: None,
+            numbering: None,
+            paragraph_borders: None,
+            tab_stops: None,
+            indentation_hanging: None,
+            indentation_left: None,
+            indentation_right: None,
+            indentation_first_line: None,
+        }
+    }
+
+    pub fn inherit_from(&mut self, other: &TextSettings) {
+        inherit_or_original(&other.bold, &mut self.bold);
+        inherit_or_original(&other.italic, &mut self.italic);
+        inherit_or_original(&other.underline, &mut self.underline);
+        inherit_or_original(&other.underline_color, &mut self.underline_color);
+        inherit_or_original(&other.character_spacing, &mut self.character_spacing);
+        inherit_or_original(&other.horizontal_scale, &mut self.horizontal_scale);
+        inherit_or_original(&other.kerning, &mut self.kerning);
+        inherit_or_original(&other.vertical_alignment, &mut self.vertical_alignment);
+        inherit_or_original(&other.font, &mut self.font);
+        inherit_or_original(&other.color, &mut self.color);
+        inherit_or_original(&other.language, &mut self.language);
+        inherit_or_original(&other.complex_script_language, &mut self.complex_script_language);
+        inherit_or_original(&other.right_to_left, &mut self.right_to_left);
+        inherit_or_original(&other.bidi, &mut self.bidi);
+        inherit_or_original(&other.spacing_above_paragraph, &mut self.spacing_above_paragraph);
+        inherit_or_original(&other.spacing_below_paragraph, &mut self.spacing_below_paragraph);
+        inherit_or_original(&other.spacing_above_paragraph_automatic, &mut self.spacing_above_paragraph_automatic);
+        inherit_or_original(&other.spacing_below_paragraph_automatic, &mut self.spacing_below_paragraph_automatic);
+        inherit_or_original(&other.line_spacing, &mut self.line_spacing);
+        inherit_or_original(&other.contextual_spacing, &mut self.contextual_spacing);
+        inherit_or_original(&other.paragraph_style_id, &mut self.paragraph_style_id);
+        inherit_or_original(&other.outline_level, &mut self.outline_level);
+        inherit_or_original(&other.non_complex_text_size, &mut self.non_complex_text_size);
+        inherit_or_original(&other.justify, &mut self.justify);
+        inherit_or_original(&other.highlight_color, &mut self.highlight_color);
+        inherit_or_original(&other.numbering, &mut self.numbering);
+        inherit_or_original(&other.paragraph_borders, &mut self.paragraph_borders);
+        inherit_or_original(&other.tab_stops, &mut self.tab_stops);
+
+        inherit_or_original(&other.indentation_hanging, &mut self.indentation_hanging);
+        inherit_or_original(&other.indentation_left, &mut self.indentation_left);
+        inherit_or_original(&other.indentation_right, &mut self.indentation_right);
+        inherit_or_original(&other.indentation_first_line, &mut self.indentation_first_line);
+    }
+
+    /// Get the distance between the baselines of two lines of the paragraph,
+    /// given the line height of the font.
+    pub fn line_advance(&self, font_line_height: f32) -> f32 {
+        match self.line_spacing {
+            None => font_line_height,
+            Some(LineSpacing::Auto(factor)) => font_line_height * factor,
+            Some(LineSpacing::Exact(height)) => height.get_pts(),
+            Some(LineSpacing::AtLeast(height)) => font_line_height.max(height.get_pts()),
+        }
+    }
+
+    /// The language the numbers of the paragraph are written in. Right-to-left
+    /// paragraphs use the language of the complex script text, e.g. so that
+    /// Arabic lists use the Arabic digits.
+    pub fn numbering_language(&self) -> Option<&str> {
+        match (&self.complex_script_language, self.bidi) {
+            (Some(language), Some(true)) => Some(language),
+            _ => self.language.as_deref(),
+        }
+    }
+
+    /// Get the heading level of the paragraph, where 0 is the top level. The
+    /// outline level is authoritative, but when it isn't specified, the
+    /// styleId is used (e.g. `Heading1`), since not every producer writes
+    /// the <w:outlineLvl> element.
+    pub fn heading_level(&self) -> Option<u8> {
+        if let Some(outline_level) = self.outline_level {
+            return if outline_level < OUTLINE_LEVEL_BODY_TEXT { Some(outline_level) } else { None };
+        }
+
+        let style_id = self.paragraph_style_id.as_deref()?;
+        let number = style_id.strip_prefix("Heading")
+            .or_else(|| style_id.strip_prefix("heading"))?
+            .trim_start();
+
+        match number.parse::<u8>() {
+            Ok(number @ 1..=OUTLINE_LEVEL_BODY_TEXT) => Some(number - 1),
+            _ => None,
+        }
+    }
+
+    pub fn create_style(&self) -> FontStyle {
+        let mut style = FontStyle::NORMAL;
+
+        if self.bold.unwrap_or(false) {
+            style |= FontStyle::BOLD;
+        }
+
+        if self.italic.unwrap_or(false) {
+            style |= FontStyle::ITALIC;
+        }
+
+        if self.underline_style().is_some() {
+            style |= FontStyle::UNDERLINE;
+        }
+
+        style
+    }
+
+    pub fn apply_run_properties_element(&mut self, style_manager: &StyleManager, theme_settings: &drawing_ml::style::StyleSettings,
+                                        element: &xml::Node, diagnostics: &mut Vec<Diagnostic>) {
+        assert_eq!(element.tag_name().name(), "rPr");
+
+        for run_property in element.children() {
+            match run_property.tag_name().name() {
+                "b" => {
+                    self.bold = match self.bold {
+                        None => Some(true),
+                        Some(bold) => Some(!bold)
+                    };
+                }
+                // 17.3.2.16 i (Italics), which toggles like <w:b>.
+                "i" => {
+                    self.italic = match self.italic {
+                        None => Some(true),
+                        Some(italic) => Some(!italic)
+                    };
+                }
+                // 17.3.2.6 color (Run Content Color), where the theme color
+                // supersedes the value.
+                "color" => {
+                    if let Some(color) = parse_theme_color(&run_property, theme_settings, diagnostics) {
+                        self.color = Some(color);
+                    } else {
+                        for attr in run_property.attributes() {
+                            if attr.name() == "val" && attr.value() != "auto" {
+                                match color_parser::parse_color(attr.value()) {
+                                    Ok(color) => self.color = Some(color),
+                                    Err(..) => diagnostics.push(Diagnostic::error("w:color", format!("invalid color: \"{}\"", attr.value()))),
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // 17.3.2.19 kern (Font Kerning)
+                "kern" => {
+                    if let Some(value) = crate::word_processing::parse_val_attribute(&run_property, "w:kern", diagnostics) {
+                        self.kerning = Some(HalfPoint(value));
+                    }
+                }
+
+                // 17.3.2.35 spacing (Character Spacing Adjustment)
+                "spacing" => {
+                    if let Some(value) = crate::word_processing::parse_val_attribute(&run_property, "w:spacing", diagnostics) {
+                        self.character_spacing = Some(TwelfteenthPoint(value));
+                    }
+                }
+
+                // 17.3.2.43 w (Expanded/Compressed Text), a percentage, which
+                // the strict schema writes with a percent sign.
+                "w" => {
+                    let Some(val) = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) else {
+                        diagnostics.push(Diagnostic::error("w:w", String::from("missing w:val attribute")));
+                        continue;
+                    };
+                    match val.trim_end_matches('%').parse::<u32>() {
+                        Ok(value) if value > 0 => self.horizontal_scale = Some(value),
+                        _ => diagnostics.push(Diagnostic::error("w:w", format!("invalid value: \"{}\"", val))),
+                    }
+                }
+
+                // 17.3.2.15 highlight (Text Highlighting)
+                "highlight" => {
+                    let Some(val) = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) else {
+                        diagnostics.push(Diagnostic::error("w:highlight", String::from("missing w:val attribute")));
+                        continue;
+                    };
+                    self.highlight_color = Some(color_parser::parse_highlight_color(val));
+                }
+
+                // 17.3.2.20 lang (Languages for Run Content)
+                "lang" => {
+                    if let Some(value) = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                        self.language = Some(Rc::from(value));
+                    }
+                    if let Some(value) = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "bidi")) {
+                        self.complex_script_language = Some(Rc::from(value));
+                    }
+                }
+
+                // 17.3.2.30 rtl (Right To Left Text)
+                "rtl" => {
+                    let right_to_left = match run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                        Some(val) => crate::word_processing::parse_on_off(val, "w:rtl", diagnostics),
+                        None => Some(true),
+                    };
+                    if right_to_left.is_some() {
+                        self.right_to_left = right_to_left;
+                    }
+                }
+
+                "rFonts" => {
+                    if let Some(value) = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "asciiTheme")) {
+                        match value {
+                            "majorAnsi" => {
+                                self.font = Some(theme_settings.theme_elements.font_scheme.major_font.latin.typeface.clone());
+                            }
+                            "minorAnsi" => {
+                                self.font = Some(theme_settings.theme_elements.font_scheme.minor_font.latin.typeface.clone());
+                            }
+                            "majorHAnsi" => {
+                                self.font = Some(theme_settings.theme_elements.font_scheme.major_font.latin.typeface.clone());
+                            }
+                            "minorHAnsi" => {
+                                self.font = Some(theme_settings.theme_elements.font_scheme.minor_font.latin.typeface.clone());
+                            }
+                            _ => {
+                                println!("[WARNING] Unknown w:asciiTheme value: {}", value);
+                            }
+                        }
+                    } else if let Some(value) = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "ascii")) {
+                        self.font = Some(Rc::from(value));
+                    }
+                }
+
+                "rStyle" => {
+                    let Some(val) = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) else {
+                        diagnostics.push(Diagnostic::error("w:rStyle", String::from("missing w:val attribute")));
+                        continue;
+                    };
+                    style_manager.apply_character_style(val, self);
+                }
+
+                "sz" => {
+                    for attr in run_property.attributes() {
+                        //println!("│  │  │  │  ├─ Size Attribute: {} => {}", attr.name(), attr.value());
+                        if attr.name() == "val" {
+                            match attr.value().parse() {
+                                Ok(new_value) => self.non_complex_text_size = Some(HalfPoint(new_value)),
+                                Err(..) => diagnostics.push(Diagnostic::error("w:sz", format!("invalid size: \"{}\"", attr.value()))),
+                            }
+                        }
+                    }
+                }
+
+                // 17.3.2.40 u (Underline)
+                "u" => {
+                    let style = run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")).unwrap_or("single");
+                    match UnderlineStyle::from_str(style) {
+                        Ok(style) => self.underline = Some(style),
+                        Err(..) => diagnostics.push(Diagnostic::warning("w:u", format!("unknown underline style: \"{}\"", style))),
+                    }
+
+                    match run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "color")) {
+                        _ if run_property.has_attribute((WORD_PROCESSING_XML_NAMESPACE, "themeColor")) => {
+                            self.underline_color = parse_theme_color(&run_property, theme_settings, diagnostics);
+                        }
+                        Some("auto") => self.underline_color = None,
+                        Some(color) => self.underline_color = color_parser::parse_color(color).ok(),
+                        None => (),
+                    }
+                }
+
+                // 17.3.2.42 vertAlign (Subscript/Superscript Text)
+                "vertAlign" => {
+                    match run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                        Some("baseline") => self.vertical_alignment = Some(VerticalAlignment::Baseline),
+                        Some("superscript") => self.vertical_alignment = Some(VerticalAlignment::Superscript),
+                        Some("subscript") => self.vertical_alignment = Some(VerticalAlignment::Subscript),
+                        Some(value) => diagnostics.push(Diagnostic::warning("w:vertAlign", format!("unknown vertical alignment: \"{}\"", value))),
+                        None => diagnostics.push(Diagnostic::error("w:vertAlign", String::from("missing w:val attribute"))),
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
+
+    pub(crate) fn indent_one(&self, x: f32, _is_first_line: bool) -> f32 {
+        //println!("indent_one");
+        //println!("  In X: {}", x);
+
+        if let Some(indentation) = self.indentation_left {
+            //println!("  IndentationLeft: {}", indentation);
+
+            let indentation = indentation.get_pts();
+            //println!("  Step: {}", indentation);
+
+            let x = ((x / indentation) as u32 + 1) as f32 * indentation;
+            //println!("  X: {}", x);
+
+            // return if let Some(hanging) = self.indentation_hanging {
+            //     x - hanging as f32 * TWELFTEENTH_POINT
+            // } else {
+            //     x
+            // }
+            return x;
+        }
+
+        x
+    }
+
+    /// Parse the <w:ind> element (17.3.1.12 ind).
+    pub fn parse_element_ind(&mut self, node: &xml::Node) {
+        let value = |names: &[&str]| names.iter()
+            .find_map(|name| node.attribute((WORD_PROCESSING_XML_NAMESPACE, *name)))
+            .and_then(|value| value.parse().ok())
+            .map(TwelfteenthPoint);
+
+        // The w:left and w:right are the names of w:start and w:end in the
+        // transitional schema, which Microsoft Word writes.
+        if let Some(value) = value(&["start", "left"]) {
+            self.indentation_left = Some(value);
+        }
+
+        if let Some(value) = value(&["end", "right"]) {
+            self.indentation_right = Some(value);
+        }
+
+        // The first line and hanging indentations exclude each other, and
+        // the hanging indentation wins when both are specified.
+        if let Some(value) = value(&["firstLine"]) {
+            self.indentation_first_line = Some(value);
+            self.indentation_hanging = None;
+        }
+
+        if let Some(value) = value(&["hanging"]) {
+            self.indentation_hanging = Some(value);
+            self.indentation_first_line = None;
+        }
+    }
+
+    /// How much further the first line starts than the other lines, in
+    /// points, which is negative for a hanging indentation.
+    pub fn first_line_offset_pts(&self) -> f32 {
+        match (self.indentation_hanging, self.indentation_first_line) {
+            (Some(hanging), _) => -hanging.get_pts(),
+            (None, Some(first_line)) => first_line.get_pts(),
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Parse the <w:tabs> element, which adds tab stops to the ones of the
+    /// style, or clears them when `w:val` is `clear`.
+    pub fn parse_element_tabs(&mut self, node: &xml::Node) {
+        let mut tab_stops = self.tab_stops.take().unwrap_or_default();
+
+        for child in node.children() {
+            if child.tag_name().name() != "tab" {
+                continue;
+            }
+
+            let Some(position) = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "pos")).and_then(|pos| pos.parse().ok()) else {
+                continue;
+            };
+            let position = TwelfteenthPoint(position);
+
+            tab_stops.retain(|tab_stop| tab_stop.position != position);
+
+            let alignment = match child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                Some("clear") => continue,
+                Some("center") => TabStopAlignment::Center,
+                Some("end" | "right") => TabStopAlignment::End,
+
+                // TODO decimal and bar tab stops
+                _ => TabStopAlignment::Start,
+            };
+
+            tab_stops.push(TabStop { alignment, position });
+        }
+
+        tab_stops.sort_by_key(|tab_stop| tab_stop.position);
+        self.tab_stops = Some(tab_stops);
+    }
+
+    /// The font family of the text, which is the major font of the theme
+    /// when no font was specified.
+    pub fn font_family_name(&self, theme: &drawing_ml::style::StyleSettings) -> Rc<str> {
+        match &self.font {
+            Some(font) => font.clone(),
+            None => theme.theme_elements.font_scheme.major_font.latin.typeface.clone(),
+        }
+    }
+
+    /// The size of non-complex script text in points, falling back to
+    /// [DEFAULT_NON_COMPLEX_TEXT_SIZE] when none is specified.
+    pub fn non_complex_text_size_pts(&self) -> f32 {
+        self.non_complex_text_size.unwrap_or(DEFAULT_NON_COMPLEX_TEXT_SIZE).get_pts()
+    }
+
+    /// The size the text is laid out and painted with, which is smaller than
+    /// the size of the run for superscript and subscript text.
+    pub fn font_size_pts(&self) -> f32 {
+        match self.vertical_alignment {
+            Some(VerticalAlignment::Superscript | VerticalAlignment::Subscript) =>
+                self.non_complex_text_size_pts() * VERTICAL_ALIGNMENT_SIZE_FACTOR,
+            _ => self.non_complex_text_size_pts(),
+        }
+    }
+
+    /// How far the baseline of the text is raised above the baseline of the
+    /// line, which is negative for subscript text.
+    pub fn baseline_shift_pts(&self) -> f32 {
+        match self.vertical_alignment {
+            Some(VerticalAlignment::Superscript) => self.non_complex_text_size_pts() * SUPERSCRIPT_RAISE_FACTOR,
+            Some(VerticalAlignment::Subscript) => -self.non_complex_text_size_pts() * SUBSCRIPT_LOWER_FACTOR,
+            _ => 0.0,
+        }
+    }
+
+    pub fn font_weight(&self) -> FontWeight {
+        if self.bold == Some(true) {
+            FontWeight::Bold
+        } else {
+            FontWeight::Regular
+        }
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.italic == Some(true)
+    }
+
+    /// The space added after every character, in points.
+    pub fn character_spacing_pts(&self) -> f32 {
+        self.character_spacing.map(|spacing| spacing.get_pts()).unwrap_or(0.0)
+    }
+
+    /// The factor the text is stretched horizontally by, where 1.0 is the
+    /// natural width.
+    pub fn horizontal_scale_factor(&self) -> f32 {
+        self.horizontal_scale.map(|percentage| percentage as f32 / 100.0).unwrap_or(1.0)
+    }
+
+    /// The font of the text, with the given family name, which is either the
+    /// font of the text or the fallback font when that isn't available.
+    pub fn font_specification<'a>(&self, family_name: &'a str) -> FontSpecification<'a> {
+        FontSpecification::new(family_name, self.font_size_pts(), self.font_weight())
+            .with_italic(self.is_italic())
+            .with_character_spacing(self.character_spacing_pts())
+            .with_horizontal_scale(self.horizontal_scale_factor())
+    }
+
+    /// The style of the underline, if the text is underlined.
+    pub fn underline_style(&self) -> Option<UnderlineStyle> {
+        self.underline.filter(|style| *style != UnderlineStyle::None)
+    }
+
+    /// The brush of the underline, which is the brush of the text unless the
+    /// underline has a color of its own.
+    pub fn underline_brush(&self) -> crate::gui::Brush {
+        match self.underline_color {
+            Some(color) => crate::gui::Brush::SolidColor(color),
+            None => self.brush(),
+        }
+    }
+
+    pub fn brush(&self) -> crate::gui::Brush {
+        let color = self.color.unwrap_or(Color::BLACK);
+        crate::gui::Brush::SolidColor(color)
+    }
+
+}
@@ -0,0 +1,227 @@
+// Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use super::primary_language_subtag;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LetterCase {
+    Lower,
+    Upper,
+}
+
+pub trait Alphabet {
+    /// The letters of the alphabet in lower case, in alphabetical order.
+    fn letters(&self) -> &'static [char];
+
+    fn len(&self) -> usize {
+        self.letters().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.letters().is_empty()
+    }
+
+    fn nth(&self, index: usize) -> char {
+        assert!(index < self.len(), "Invalid value");
+        self.letters()[index]
+    }
+
+    /// Get the nth letter of the alphabet in the given case.
+    fn nth_in_case(&self, index: usize, case: LetterCase) -> char {
+        let letter = self.nth(index);
+        match case {
+            LetterCase::Lower => letter,
+            LetterCase::Upper => {
+                let mut uppercase = letter.to_uppercase();
+                match (uppercase.next(), uppercase.next()) {
+                    (Some(upper), None) => upper,
+
+                    // Some letters (e.g. ß) don't have a single-character
+                    // uppercase variant, so keep them as they are.
+                    _ => letter,
+                }
+            }
+        }
+    }
+}
+
+pub struct Latin;
+impl Alphabet for Latin {
+    fn letters(&self) -> &'static [char] {
+        &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z']
+    }
+}
+
+/// The Latin alphabet extended with Æ, Ø and Å, used by Danish and Norwegian.
+pub struct DanoNorwegian;
+impl Alphabet for DanoNorwegian {
+    fn letters(&self) -> &'static [char] {
+        &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'æ', 'ø', 'å']
+    }
+}
+
+/// The Latin alphabet extended with Å, Ä and Ö, used by Swedish and Finnish.
+pub struct Swedish;
+impl Alphabet for Swedish {
+    fn letters(&self) -> &'static [char] {
+        &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'å', 'ä', 'ö']
+    }
+}
+
+/// The Cyrillic letters used by the russianLower and russianUpper numbering
+/// formats. Like Microsoft Word, this omits ё, й, ъ, ы and ь, since they
+/// aren't used for enumerating.
+pub struct Russian;
+impl Alphabet for Russian {
+    fn letters(&self) -> &'static [char] {
+        &['а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'к', 'л', 'м', 'н', 'о', 'п', 'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'э', 'ю', 'я']
+    }
+}
+
+/// The Cyrillic letters used for enumerating in Ukrainian, which omits ї, й
+/// and ь.
+pub struct Ukrainian;
+impl Alphabet for Ukrainian {
+    fn letters(&self) -> &'static [char] {
+        &['а', 'б', 'в', 'г', 'ґ', 'д', 'е', 'є', 'ж', 'з', 'и', 'і', 'к', 'л', 'м', 'н', 'о', 'п', 'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ю', 'я']
+    }
+}
+
+/// The Greek alphabet, without the final form of sigma.
+pub struct Greek;
+impl Alphabet for Greek {
+    fn letters(&self) -> &'static [char] {
+        &['α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'λ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ', 'σ', 'τ', 'υ', 'φ', 'χ', 'ψ', 'ω']
+    }
+}
+
+/// The Hebrew alphabet, without the final forms, used by the hebrew2
+/// numbering format.
+pub struct Hebrew;
+impl Alphabet for Hebrew {
+    fn letters(&self) -> &'static [char] {
+        &['א', 'ב', 'ג', 'ד', 'ה', 'ו', 'ז', 'ח', 'ט', 'י', 'כ', 'ל', 'מ', 'נ', 'ס', 'ע', 'פ', 'צ', 'ק', 'ר', 'ש', 'ת']
+    }
+}
+
+/// The Arabic alphabet in the usual (hijā'ī) order, used by the arabicAlpha
+/// numbering format.
+pub struct ArabicAlpha;
+impl Alphabet for ArabicAlpha {
+    fn letters(&self) -> &'static [char] {
+        &['ا', 'ب', 'ت', 'ث', 'ج', 'ح', 'خ', 'د', 'ذ', 'ر', 'ز', 'س', 'ش', 'ص', 'ض', 'ط', 'ظ', 'ع', 'غ', 'ف', 'ق', 'ك', 'ل', 'م', 'ن', 'ه', 'و', 'ي']
+    }
+}
+
+/// The Arabic alphabet in the abjad order, used by the arabicAbjad numbering
+/// format.
+pub struct ArabicAbjad;
+impl Alphabet for ArabicAbjad {
+    fn letters(&self) -> &'static [char] {
+        &['ا', 'ب', 'ج', 'د', 'ه', 'و', 'ز', 'ح', 'ط', 'ي', 'ك', 'ل', 'م', 'ن', 'س', 'ع', 'ف', 'ص', 'ق', 'ر', 'ش', 'ت', 'ث', 'خ', 'ذ', 'ض', 'ظ', 'غ']
+    }
+}
+
+/// The Thai consonants used by the thaiLetters numbering format, which omits
+/// the obsolete ฃ and ฅ.
+pub struct Thai;
+impl Alphabet for Thai {
+    fn letters(&self) -> &'static [char] {
+        &['ก', 'ข', 'ค', 'ฆ', 'ง', 'จ', 'ฉ', 'ช', 'ซ', 'ฌ', 'ญ', 'ฎ', 'ฏ', 'ฐ', 'ฑ', 'ฒ', 'ณ', 'ด', 'ต', 'ถ', 'ท', 'ธ', 'น', 'บ', 'ป', 'ผ',
+          'ฝ', 'พ', 'ฟ', 'ภ', 'ม', 'ย', 'ร', 'ล', 'ว', 'ศ', 'ษ', 'ส', 'ห', 'ฬ', 'อ', 'ฮ']
+    }
+}
+
+/// Get the Latin-derived alphabet of the given language tag (e.g. `nb-NO`),
+/// as specified by the `<w:lang>` element. Languages that don't use an
+/// extended Latin alphabet, or aren't written in Latin script at all, use
+/// the basic Latin alphabet.
+pub fn latin_alphabet_for_language(language: Option<&str>) -> &'static dyn Alphabet {
+    match primary_language_subtag(language).as_deref() {
+        Some("da" | "nb" | "nn" | "no") => &DanoNorwegian,
+        Some("sv" | "fi") => &Swedish,
+        _ => &Latin,
+    }
+}
+
+/// Get the Cyrillic alphabet of the given language tag, which is the Russian
+/// one for languages that aren't written in Cyrillic script.
+pub fn cyrillic_alphabet_for_language(language: Option<&str>) -> &'static dyn Alphabet {
+    match primary_language_subtag(language).as_deref() {
+        Some("uk") => &Ukrainian,
+        _ => &Russian,
+    }
+}
+
+/// Find the alphabet that starts with the given letter (in either case), e.g.
+/// for the sample `α, β, γ, ...` of a custom numbering format. The language
+/// decides between the variants of the Latin and Cyrillic alphabets.
+pub fn alphabet_starting_with(letter: char, language: Option<&str>) -> Option<&'static dyn Alphabet> {
+    let letter = letter.to_lowercase().next()?;
+    let alphabets: [&'static dyn Alphabet; 6] = [
+        latin_alphabet_for_language(language), cyrillic_alphabet_for_language(language),
+        &Greek, &Hebrew, &ArabicAlpha, &Thai,
+    ];
+
+    alphabets.into_iter().find(|alphabet| alphabet.letters().first() == Some(&letter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin() {
+        assert_eq!(Latin.len(), 26);
+        assert_eq!(Latin.nth(0), 'a');
+        assert_eq!(Latin.nth(25), 'z');
+    }
+
+    #[test]
+    fn uppercase() {
+        assert_eq!(Latin.nth_in_case(0, LetterCase::Upper), 'A');
+        assert_eq!(Latin.nth_in_case(25, LetterCase::Lower), 'z');
+        assert_eq!(DanoNorwegian.nth_in_case(27, LetterCase::Upper), 'Ø');
+        assert_eq!(Russian.nth_in_case(27, LetterCase::Upper), 'Я');
+    }
+
+    #[test]
+    fn russian() {
+        assert_eq!(Russian.len(), 28);
+        assert_eq!(Russian.nth(0), 'а');
+        assert_eq!(Russian.nth(9), 'к');
+        assert_eq!(Russian.nth(27), 'я');
+    }
+
+    #[test]
+    fn alphabet_selection_by_language() {
+        assert_eq!(latin_alphabet_for_language(Some("nn-NO")).nth(26), 'æ');
+        assert_eq!(latin_alphabet_for_language(Some("da-DK")).nth(28), 'å');
+        assert_eq!(latin_alphabet_for_language(Some("sv-SE")).nth(28), 'ö');
+        assert_eq!(latin_alphabet_for_language(Some("en-US")).len(), 26);
+        assert_eq!(latin_alphabet_for_language(Some("ru-RU")).len(), 26);
+        assert_eq!(latin_alphabet_for_language(None).len(), 26);
+
+        assert_eq!(cyrillic_alphabet_for_language(Some("uk-UA")).nth(4), 'ґ');
+        assert_eq!(cyrillic_alphabet_for_language(Some("ru-RU")).nth(4), 'д');
+    }
+
+    #[test]
+    fn non_latin_alphabets() {
+        assert_eq!(Greek.len(), 24);
+        assert_eq!(Greek.nth_in_case(17, LetterCase::Upper), 'Σ');
+        assert_eq!(Hebrew.len(), 22);
+        assert_eq!(Hebrew.nth_in_case(21, LetterCase::Upper), 'ת');
+        assert_eq!(ArabicAlpha.len(), 28);
+        assert_eq!(ArabicAbjad.len(), 28);
+        assert_eq!(Thai.len(), 42);
+    }
+
+    #[test]
+    fn alphabet_selection_by_first_letter() {
+        assert_eq!(alphabet_starting_with('Α', None).map(|alphabet| alphabet.nth(1)), Some('β'));
+        assert_eq!(alphabet_starting_with('а', Some("uk-UA")).map(|alphabet| alphabet.len()), Some(30));
+        assert_eq!(alphabet_starting_with('a', Some("sv-SE")).map(|alphabet| alphabet.len()), Some(29));
+        assert!(alphabet_starting_with('b', None).is_none());
+    }
+}
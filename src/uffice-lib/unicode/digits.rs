@@ -0,0 +1,80 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use super::primary_language_subtag;
+
+/// The characters the digits 0 to 9 are written with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigitForm {
+    /// U+0030–U+0039.
+    Western,
+
+    /// U+0660–U+0669, used by Arabic.
+    ArabicIndic,
+
+    /// U+06F0–U+06F9, used by Persian and Urdu.
+    ExtendedArabicIndic,
+
+    /// U+0966–U+096F.
+    Devanagari,
+
+    /// U+0E50–U+0E59.
+    Thai,
+
+    /// U+FF10–U+FF19, which are as wide as the ideographs.
+    FullWidth,
+}
+
+impl DigitForm {
+    /// Get the digits the numbers in the given language tag (e.g. `ar-SA`)
+    /// are written with, like the context numerals of Microsoft Word. Only the
+    /// languages written in the Arabic script replace the Western digits.
+    pub fn for_language(language: Option<&str>) -> Self {
+        match primary_language_subtag(language).as_deref() {
+            Some("ar") => Self::ArabicIndic,
+            Some("fa" | "ur" | "ps") => Self::ExtendedArabicIndic,
+            _ => Self::Western,
+        }
+    }
+
+    fn zero(&self) -> char {
+        match self {
+            Self::Western => '0',
+            Self::ArabicIndic => '\u{660}',
+            Self::ExtendedArabicIndic => '\u{6F0}',
+            Self::Devanagari => '\u{966}',
+            Self::Thai => '\u{E50}',
+            Self::FullWidth => '\u{FF10}',
+        }
+    }
+
+    /// Replaces the Western digits of the text with the digits of this form.
+    pub fn apply(&self, text: &str) -> String {
+        let zero = self.zero() as u32;
+        text.chars()
+            .map(|character| match character.to_digit(10) {
+                Some(digit) => char::from_u32(zero + digit).unwrap_or(character),
+                None => character,
+            })
+            .collect()
+    }
+
+    /// Format the value as a decimal number in this form.
+    pub fn format(&self, value: i32) -> String {
+        self.apply(&value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits_of_language() {
+        assert_eq!(DigitForm::for_language(Some("ar-SA")).format(1024), "١٠٢٤");
+        assert_eq!(DigitForm::for_language(Some("fa-IR")).format(15), "۱۵");
+        assert_eq!(DigitForm::for_language(Some("en-US")).format(-3), "-3");
+        assert_eq!(DigitForm::for_language(None).format(42), "42");
+        assert_eq!(DigitForm::Thai.apply("1."), "๑.");
+    }
+}
@@ -0,0 +1,14 @@
+// Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+pub mod alphabet;
+pub mod digits;
+pub mod symbol_fonts;
+
+/// Get the primary language subtag of the language tag (e.g. `nb` for
+/// `nb-NO`), in lower case.
+pub fn primary_language_subtag(language: Option<&str>) -> Option<String> {
+    language
+        .and_then(|language| language.split(['-', '_']).next())
+        .map(|subtag| subtag.to_ascii_lowercase())
+}
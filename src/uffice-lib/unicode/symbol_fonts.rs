@@ -0,0 +1,156 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Symbol fonts, like Symbol and Wingdings, draw their own glyphs for the
+// codes of the Latin-1 characters, which documents write either as those
+// characters or as U+F020–U+F0FF in the Private Use Area. Without the font,
+// the text is shown as the wrong characters, so it is replaced by the Unicode
+// characters that look like the glyphs.
+
+/// The symbol fonts of which the glyphs are mapped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolFont {
+    Symbol,
+    Wingdings,
+}
+
+impl SymbolFont {
+    /// Find the symbol font with the given family name.
+    pub fn from_family_name(family_name: &str) -> Option<Self> {
+        if family_name.eq_ignore_ascii_case("Symbol") {
+            Some(Self::Symbol)
+        } else if family_name.eq_ignore_ascii_case("Wingdings") {
+            Some(Self::Wingdings)
+        } else {
+            None
+        }
+    }
+
+    /// Get the Unicode character that looks like the glyph of the font for
+    /// the given code (0x20–0xFF).
+    fn character_of_code(&self, code: u8) -> Option<char> {
+        match self {
+            Self::Symbol => symbol_character(code),
+            Self::Wingdings => wingdings_character(code),
+        }
+    }
+
+    /// Replaces the characters of text written in this font by the Unicode
+    /// characters that look like their glyphs. Characters without an
+    /// equivalent are kept.
+    pub fn to_unicode(&self, text: &str) -> String {
+        text.chars()
+            .map(|character| {
+                let code = match character as u32 {
+                    code @ 0xF020..=0xF0FF => code - 0xF000,
+                    code @ 0x20..=0xFF => code,
+                    _ => return character,
+                };
+                self.character_of_code(code as u8).unwrap_or(character)
+            })
+            .collect()
+    }
+}
+
+/// Replaces the text written in the font with the given family name by the
+/// Unicode characters that look like its glyphs. Returns [None] when the font
+/// isn't a symbol font.
+pub fn to_unicode(family_name: &str, text: &str) -> Option<String> {
+    SymbolFont::from_family_name(family_name).map(|font| font.to_unicode(text))
+}
+
+/// The Symbol font, which has the Greek alphabet at the places of the Latin
+/// alphabet, and mathematical symbols and arrows above 0xA0.
+fn symbol_character(code: u8) -> Option<char> {
+    const GREEK_UPPER: &str = "ΑΒΧΔΕΦΓΗΙϑΚΛΜΝΟΠΘΡΣΤΥςΩΞΨΖ";
+    const GREEK_LOWER: &str = "αβχδεφγηιϕκλμνοπθρστυϖωξψζ";
+
+    match code {
+        b'A'..=b'Z' => GREEK_UPPER.chars().nth((code - b'A') as usize),
+        b'a'..=b'z' => GREEK_LOWER.chars().nth((code - b'a') as usize),
+        0x22 => Some('∀'),
+        0x24 => Some('∃'),
+        0x27 => Some('∋'),
+        0x2A => Some('∗'),
+        0x2D => Some('−'),
+        0x40 => Some('≅'),
+        0x5C => Some('∴'),
+        0x5E => Some('⊥'),
+        0x7E => Some('∼'),
+        0xA3 => Some('≤'),
+        0xA5 => Some('∞'),
+        0xA7 => Some('♣'),
+        0xA8 => Some('♦'),
+        0xA9 => Some('♥'),
+        0xAA => Some('♠'),
+        0xAB => Some('↔'),
+        0xAC => Some('←'),
+        0xAD => Some('↑'),
+        0xAE => Some('→'),
+        0xAF => Some('↓'),
+        0xB0 => Some('°'),
+        0xB1 => Some('±'),
+        0xB3 => Some('≥'),
+        0xB4 => Some('×'),
+        0xB7 => Some('•'),
+        0xB8 => Some('÷'),
+        0xB9 => Some('≠'),
+        0xBA => Some('≡'),
+        0xBB => Some('≈'),
+        0xBC => Some('…'),
+        0xC4 => Some('⊗'),
+        0xC5 => Some('⊕'),
+        0xD6 => Some('√'),
+        0xD7 => Some('⋅'),
+        0xDB => Some('⇔'),
+        0xDE => Some('⇒'),
+        0xE0 => Some('◊'),
+        0xE5 => Some('∑'),
+        0x20..=0x7F => Some(code as char),
+        _ => None,
+    }
+}
+
+/// The glyphs of Wingdings that are used as bullets of lists.
+fn wingdings_character(code: u8) -> Option<char> {
+    match code {
+        b'F' => Some('☞'),
+        b'J' => Some('☺'),
+        b'L' => Some('☹'),
+        b'l' => Some('●'),
+        b'n' => Some('■'),
+        b'o' => Some('□'),
+        b'q' => Some('❑'),
+        b'u' => Some('◆'),
+        b'v' => Some('❖'),
+        0xA7 => Some('▪'),
+        0xA8 => Some('◻'),
+        0xD8 => Some('➢'),
+        0xE8 => Some('➔'),
+        0xFC => Some('✔'),
+        0xFD => Some('☒'),
+        0xFE => Some('☑'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bullets_of_symbol_fonts() {
+        assert_eq!(to_unicode("Symbol", "\u{F0B7}").as_deref(), Some("•"));
+        assert_eq!(to_unicode("Symbol", "\u{B7}").as_deref(), Some("•"));
+        assert_eq!(to_unicode("Wingdings", "\u{F0A7}").as_deref(), Some("▪"));
+        assert_eq!(to_unicode("wingdings", "\u{F0D8}").as_deref(), Some("➢"));
+        assert_eq!(to_unicode("Wingdings", "\u{F0FC}").as_deref(), Some("✔"));
+        assert_eq!(to_unicode("Courier New", "o"), None);
+    }
+
+    #[test]
+    fn symbol_letters_are_greek() {
+        assert_eq!(to_unicode("Symbol", "abgD").as_deref(), Some("αβγΔ"));
+        assert_eq!(to_unicode("Symbol", "x + 1").as_deref(), Some("ξ + 1"));
+    }
+}
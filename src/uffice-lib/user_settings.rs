@@ -0,0 +1,364 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug)]
+pub enum SettingState<T> {
+    /// Automatic and follows the system setting wherever possible.
+    Default(T),
+
+    /// The user changed the option.
+    #[allow(dead_code)] // TODO: implement settings menu
+    Manual(T),
+}
+
+impl<T> SettingState<T> {
+    fn get(&self) -> &T {
+        match self {
+            Self::Default(value) => value,
+            Self::Manual(value) => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SettingName {
+    /// Whether or not to enable animations. These may be disabled as a measure
+    /// for accessibility.
+    EnableAnimations,
+
+    /// Whether or not to reload a document automatically when the file was
+    /// changed on disk.
+    AutoReloadOnChange,
+
+    /// The font to use when a font of the document isn't available on the
+    /// system.
+    FallbackFont,
+
+    /// The maximum width of a tab in the tab bar, in pixels.
+    TabMaxWidth,
+
+    /// The multiplier of the scroll distance of the mouse wheel and touchpad.
+    ScrollSpeed,
+
+    /// Whether or not to scroll in the opposite direction of the mouse wheel
+    /// and touchpad.
+    InvertScrollDirection,
+
+    /// Whether or not to align the nearest page to the top of the view after
+    /// scrolling.
+    SnapToPage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[allow(dead_code)]
+pub enum SettingChangeOrigin {
+    /// The user changed this setting in the application.
+    User,
+
+    /// A system parameter or policy was changed. This can also be a (direct)
+    /// result of the action of the user, but is received as an event by the
+    /// operating system.
+    System,
+}
+
+#[derive(Debug)]
+/// Information about a setting that was changed.
+pub struct SettingChangeNotification<'a> {
+    /// Indicating where the change originated from.
+    pub origin: SettingChangeOrigin,
+
+    /// The name of the setting that was changed.
+    pub setting_name: SettingName,
+
+    /// A reference to the settings which can be used to update some part of
+    /// the object that was notified.
+    pub settings: &'a UserSettings,
+}
+
+/// A trait indicating that the class be notified about changes in settings.
+pub trait SettingChangeSubscriber {
+    /// Called when the settings are loaded at the beginning of the application
+    /// execution, or the beginning of the component initialization.
+    fn settings_loaded(&mut self, settings: &UserSettings);
+
+    /// Called when a setting was changed.
+    fn setting_changed(&mut self, notification: &SettingChangeNotification);
+}
+
+impl<T: Default> Default for SettingState<T> {
+    fn default() -> Self {
+        Self::Default(Default::default())
+    }
+}
+
+/// The font used when the font of the document isn't available, and the user
+/// didn't configure another font.
+const DEFAULT_FALLBACK_FONT: &str = "Times New Roman";
+
+/// The maximum width of a tab, when the user didn't configure another width.
+const DEFAULT_TAB_MAX_WIDTH: f32 = 220.0;
+
+/// The scroll speed multiplier, when the user didn't configure another speed.
+const DEFAULT_SCROLL_SPEED: f32 = 1.0;
+
+/// The maximum number of documents in the recent documents list.
+const MAX_RECENT_DOCUMENTS: usize = 10;
+
+/// The file in the settings directory the recent documents are stored in,
+/// one document per line: the time it was last opened in seconds since the
+/// UNIX epoch, a tab and the path.
+const RECENT_DOCUMENTS_FILE_NAME: &str = "recent-documents.txt";
+
+#[derive(Debug)]
+pub struct UserSettings {
+    /// Whether or not to enable animations. These may be disabled as a measure
+    /// for accessibility.
+    enable_animations: SettingState<bool>,
+
+    /// Whether or not to reload a document automatically when the file was
+    /// changed on disk. This is opt-in, since reloading discards the scroll
+    /// state of the layout.
+    auto_reload_on_change: SettingState<bool>,
+
+    /// The font to use when a font of the document isn't available on the
+    /// system.
+    fallback_font: SettingState<String>,
+
+    /// The maximum width of a tab in the tab bar, in pixels. Titles that
+    /// don't fit are truncated with an ellipsis.
+    tab_max_width: SettingState<f32>,
+
+    /// The multiplier of the scroll distance of the mouse wheel and touchpad,
+    /// since some mice scroll a lot further per notch than others.
+    scroll_speed: SettingState<f32>,
+
+    /// Whether or not to scroll in the opposite direction of the mouse wheel
+    /// and touchpad, also known as "natural" scrolling.
+    invert_scroll_direction: SettingState<bool>,
+
+    /// Whether or not to align the nearest page to the top of the view after
+    /// scrolling, which is useful when reading page by page.
+    snap_to_page: SettingState<bool>,
+
+    /// The documents the user opened lately, the most recent first. These
+    /// are shown on the welcome page.
+    recent_documents: Vec<RecentDocument>,
+}
+
+/// A document the user opened lately.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecentDocument {
+    pub path: PathBuf,
+
+    /// When the document was opened the last time.
+    pub last_opened: SystemTime,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            enable_animations: Default::default(),
+            auto_reload_on_change: Default::default(),
+            fallback_font: SettingState::Default(String::from(DEFAULT_FALLBACK_FONT)),
+            tab_max_width: SettingState::Default(DEFAULT_TAB_MAX_WIDTH),
+            scroll_speed: SettingState::Default(DEFAULT_SCROLL_SPEED),
+            invert_scroll_direction: Default::default(),
+            snap_to_page: Default::default(),
+            recent_documents: Vec::new(),
+        }
+    }
+}
+
+impl UserSettings {
+
+    pub fn load() -> Self {
+        let mut settings: Self = Default::default();
+        settings.reload_system_settings();
+        settings.load_recent_documents();
+        settings
+    }
+
+    #[cfg(windows)]
+    /// Loads the `Default` settings from the system.
+    pub fn reload_system_settings(&mut self) {
+        use std::ffi::c_void;
+
+        use windows::Win32::{UI::WindowsAndMessaging::{
+            SystemParametersInfoA,
+            SPI_GETCLIENTAREAANIMATION
+        }, Foundation::BOOL};
+
+        let mut value: BOOL = true.into();
+        unsafe {
+            let ptr = &mut value as *mut BOOL as *mut c_void;
+            SystemParametersInfoA(SPI_GETCLIENTAREAANIMATION, 0, Some(ptr), Default::default());
+        }
+        self.enable_animations = SettingState::Default(value.into());
+    }
+
+    #[cfg(not(windows))]
+    /// Loads the `Default` settings from the system.
+    pub fn reload_system_settings(&mut self) {
+        println!("[UserSettings] TODO: reload_system_settings()");
+    }
+
+    /// Whether or not to enable animations. These may be disabled as a measure
+    /// for accessibility.
+    pub fn setting_enable_animations(&self) -> bool {
+        *self.enable_animations.get()
+    }
+
+    /// Whether or not to reload a document automatically when the file was
+    /// changed on disk.
+    pub fn setting_auto_reload_on_change(&self) -> bool {
+        *self.auto_reload_on_change.get()
+    }
+
+    /// The font to use when a font of the document isn't available on the
+    /// system.
+    pub fn setting_fallback_font(&self) -> &str {
+        self.fallback_font.get()
+    }
+
+    /// The maximum width of a tab in the tab bar, in pixels.
+    pub fn setting_tab_max_width(&self) -> f32 {
+        *self.tab_max_width.get()
+    }
+
+    /// The multiplier of the scroll distance of the mouse wheel and touchpad.
+    pub fn setting_scroll_speed(&self) -> f32 {
+        *self.scroll_speed.get()
+    }
+
+    /// Whether or not to scroll in the opposite direction of the mouse wheel
+    /// and touchpad.
+    pub fn setting_invert_scroll_direction(&self) -> bool {
+        *self.invert_scroll_direction.get()
+    }
+
+    /// Whether or not to align the nearest page to the top of the view after
+    /// scrolling.
+    pub fn setting_snap_to_page(&self) -> bool {
+        *self.snap_to_page.get()
+    }
+
+    /// The documents the user opened lately, the most recently opened first.
+    pub fn recent_documents(&self) -> &[RecentDocument] {
+        &self.recent_documents
+    }
+
+    /// Records that the document was opened just now, moving it to the front
+    /// of the recent documents, and stores the list so it is available the
+    /// next time the application starts.
+    pub fn add_recent_document(&mut self, path: &Path) {
+        self.recent_documents.retain(|recent| recent.path != path);
+        self.recent_documents.insert(0, RecentDocument {
+            path: path.to_owned(),
+            last_opened: SystemTime::now(),
+        });
+        self.recent_documents.truncate(MAX_RECENT_DOCUMENTS);
+
+        let Some(directory) = settings_directory() else {
+            return;
+        };
+
+        let result = std::fs::create_dir_all(&directory)
+            .and_then(|()| std::fs::write(directory.join(RECENT_DOCUMENTS_FILE_NAME), serialize_recent_documents(&self.recent_documents)));
+        if let Err(e) = result {
+            println!("[UserSettings] Warning: failed to store the recent documents: {}", e);
+        }
+    }
+
+    fn load_recent_documents(&mut self) {
+        let Some(directory) = settings_directory() else {
+            return;
+        };
+
+        // The file doesn't exist until a document was opened.
+        if let Ok(text) = std::fs::read_to_string(directory.join(RECENT_DOCUMENTS_FILE_NAME)) {
+            self.recent_documents = parse_recent_documents(&text);
+        }
+    }
+
+}
+
+/// The directory the settings of the user are stored in.
+#[cfg(windows)]
+fn settings_directory() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|app_data| PathBuf::from(app_data).join("Uffice"))
+}
+
+/// The directory the settings of the user are stored in, following the XDG
+/// Base Directory Specification.
+#[cfg(not(windows))]
+fn settings_directory() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|config| config.join("uffice"))
+}
+
+fn parse_recent_documents(text: &str) -> Vec<RecentDocument> {
+    let mut recent_documents: Vec<RecentDocument> = text.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            // Lines without a time are from before the time was stored, and
+            // are treated as the least recent documents.
+            let (seconds, path) = line.split_once('\t')
+                .and_then(|(seconds, path)| Some((seconds.parse().ok()?, path)))
+                .unwrap_or((0, line));
+
+            RecentDocument {
+                path: PathBuf::from(path),
+                last_opened: SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+            }
+        })
+        .collect();
+
+    recent_documents.sort_by(|a, b| b.last_opened.cmp(&a.last_opened));
+    recent_documents.truncate(MAX_RECENT_DOCUMENTS);
+    recent_documents
+}
+
+fn serialize_recent_documents(recent_documents: &[RecentDocument]) -> String {
+    recent_documents.iter()
+        .map(|document| {
+            let seconds = document.last_opened.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+            format!("{}\t{}\n", seconds, document.path.display())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_documents_survive_serializing() {
+        let recent_documents = vec![
+            RecentDocument {
+                path: PathBuf::from("/home/user/Report.docx"),
+                last_opened: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            },
+            RecentDocument {
+                path: PathBuf::from("/tmp/Letter\twith tab.docx"),
+                last_opened: SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000),
+            },
+        ];
+        assert_eq!(parse_recent_documents(&serialize_recent_documents(&recent_documents)), recent_documents);
+        assert!(parse_recent_documents("\n\n").is_empty());
+    }
+
+    #[test]
+    fn recent_documents_are_sorted_by_time() {
+        let recent_documents = parse_recent_documents("/home/user/Old.docx\n100\t/tmp/Older.docx\n200\t/tmp/Newest.docx\n");
+        let paths: Vec<_> = recent_documents.iter().map(|document| document.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["/tmp/Newest.docx", "/tmp/Older.docx", "/home/user/Old.docx"]);
+    }
+}
+
@@ -0,0 +1,4508 @@
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+pub mod doc;
+pub mod load;
+pub mod odf;
+pub mod text;
+pub mod validate;
+
+use std::{collections::HashMap, rc::Rc};
+
+use roxmltree as xml;
+use crate::{namespaces::XMLNS_RELATIONSHIPS, TwelfteenthPoint};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    *,
+    text_settings::{
+        PageSettings,
+        Numbering,
+        TabStopAlignment,
+        TextJustification,
+        TextSettings,
+    },
+    error::Error,
+    gui::{
+        Position,
+        Rect,
+        Size,
+    },
+    relationships::Relationships,
+    wp::{
+        diagnostics::Diagnostic,
+        Document,
+        layout::{Column, LineLayout},
+        Node,
+        numbering, instructions, StructuredDocumentTagLevel, StructuredDocumentTag,
+        table::{
+            TableCellLocation,
+            TableCellMargins,
+            TableCellProperties,
+            TableConditionalFormatting,
+            TableLook,
+            TableProperties,
+            TableRowProperties,
+            TableGrid,
+        },
+    },
+    gui::painter::{
+        TextCalculator,
+        FontMetrics,
+        FontSpecification,
+        FontWeight,
+        MeasureJob,
+    },
+    style::{ResolvedStyle, StyleManager}, serialize::FromXmlStandalone,
+};
+
+pub const TWELFTEENTH_POINT: f32 = 1f32 / 12.0;
+pub const HALF_POINT: f32 = 0.5;
+
+/// The value of the `w:line` attribute of <w:spacing> that means single line
+/// spacing, when the line rule is `auto` (17.3.1.33).
+const AUTO_LINE_SPACING_SINGLE: f32 = 240.0;
+
+type ThemeSettings = drawing_ml::style::StyleSettings;
+
+struct Context<'a> {
+    document: &'a mut Document,
+
+    text_calculator: &'a mut dyn gui::painter::TextCalculator,
+
+    /// Follows the layout of the body, which is None for the content that
+    /// isn't part of the body, such as headers.
+    observer: Option<&'a mut dyn LoadObserver>,
+
+    document_relationships: &'a Relationships,
+    style_manager: &'a StyleManager,
+
+    /// The page settings of the section that is being laid out.
+    page_settings: PageSettings,
+
+    /// The sections of the body in document order, of which the last is the
+    /// section of the body section properties.
+    section_layouts: Vec<SectionLayout>,
+
+    /// The index in `section_layouts` of the section that is being laid out.
+    current_section: usize,
+
+    /// The columns the content is laid out in, which is empty for content
+    /// that isn't part of the body, such as headers.
+    columns: Vec<Column>,
+
+    /// The index in `columns` of the column the content is laid out in.
+    current_column: usize,
+
+    drawing_ml_style_settings: drawing_ml::style::StyleSettings,
+
+    /// The font to use when a font isn't available.
+    fallback_font: &'a str,
+
+    numbering_manager: wp::numbering::NumberingManager,
+
+    /// The problems encountered while processing the document.
+    diagnostics: Vec<Diagnostic>,
+
+    /// The footnotes and endnotes referenced in the body, in document order.
+    footnote_references: Vec<NoteReference>,
+    endnote_references: Vec<NoteReference>,
+
+    /// The number of the footnote or endnote that is being laid out, which is
+    /// shown by the <w:footnoteRef> and <w:endnoteRef> elements.
+    current_note_number: Option<usize>,
+
+    /// The text of the bookmarks by their name, for the REF fields.
+    bookmarks: HashMap<String, String>,
+
+    /// The fields of which the end hasn't been reached yet, from the
+    /// outermost to the innermost.
+    fields: Vec<FieldState>,
+}
+
+/// A reference to a footnote or endnote (17.11.14 footnoteReference).
+struct NoteReference {
+    id: String,
+
+    /// The number shown as the reference mark, or None when the run contains
+    /// a custom mark instead.
+    number: Option<usize>,
+
+    /// The page the reference mark is on.
+    page: usize,
+}
+
+/// A complex field (17.16.18 fldChar) or simple field (17.16.19 fldSimple)
+/// that is being laid out.
+#[derive(Default)]
+struct FieldState {
+    /// The instruction, collected from the <w:instrText> elements and the
+    /// results of nested fields.
+    instruction: String,
+
+    /// The field, which is parsed when the separate character is reached.
+    /// The runs after it contain the result stored in the document.
+    field: Option<instructions::Field>,
+
+    /// The value of the field, which replaces the stored result, or None when
+    /// the field isn't supported and the stored result is shown instead.
+    value: Option<String>,
+    value_shown: bool,
+
+    /// Whether the document stores a result for the field.
+    has_result: bool,
+}
+
+/// How a section of the body is laid out, as far as it is known before the
+/// layout of the body.
+#[derive(Debug)]
+struct SectionLayout {
+    page_settings: PageSettings,
+    section_type: wp::SectionType,
+
+    columns: Vec<Column>,
+
+    /// Whether a line is drawn between the columns.
+    column_separator: bool,
+
+    /// The page the section starts on, which is known when the section is
+    /// reached by the layout.
+    page_first: usize,
+}
+
+/// Reads the page settings of the last section. The section properties of
+/// the body are optional, and without them the pages are A4 with the margins
+/// of Microsoft Word.
+fn load_page_settings(body: &xml::Node) -> Result<PageSettings, Error> {
+    let Some(section_properties) = find_body_section_properties(body) else {
+        return Ok(PageSettings::new(
+            Size::new(TwelfteenthPoint(11906), TwelfteenthPoint(16838)),
+            Rect::from_positions(TwelfteenthPoint(1440), TwelfteenthPoint(1440), TwelfteenthPoint(1440), TwelfteenthPoint(1440)),
+            TwelfteenthPoint(708),
+            TwelfteenthPoint(708),
+        ));
+    };
+
+    parse_page_settings(&section_properties)
+}
+
+/// Reads the page size and margins of the section properties (17.6.17 sectPr).
+/// The size is used as is, unless the page is in landscape orientation while
+/// its size is given in portrait, in which case the width and height are
+/// swapped.
+fn parse_page_settings(section_properties: &xml::Node) -> Result<PageSettings, Error> {
+    let mut page_size = Size::empty();
+    let mut margins = Rect::<TwelfteenthPoint<u32>>::empty();
+
+    let mut offset_header = TwelfteenthPoint(0);
+    let mut offset_footer = TwelfteenthPoint(0);
+
+    for child in section_properties.children() {
+        match child.tag_name().name() {
+            "pgSz" => {
+                page_size = Size::new(
+                    TwelfteenthPoint(str::parse(child.attribute((WORD_PROCESSING_XML_NAMESPACE, "w"))
+                        .ok_or(Error::MissingAttribute { element: "w:pgSz", attribute: "w:w" })?)?),
+                    TwelfteenthPoint(str::parse(child.attribute((WORD_PROCESSING_XML_NAMESPACE, "h"))
+                        .ok_or(Error::MissingAttribute { element: "w:pgSz", attribute: "w:h" })?)?)
+                );
+
+                // 17.18.65 ST_PageOrientation
+                let is_landscape = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "orient")) == Some("landscape");
+                if is_landscape && page_size.width() < page_size.height() {
+                    page_size = Size::new(page_size.height(), page_size.width());
+                }
+            }
+            "pgMar" => {
+                for attribute in child.attributes() {
+                    match attribute.name() {
+                        "left" => margins.left.0 = str::parse(attribute.value())?,
+                        "right" => margins.right.0 = str::parse(attribute.value())?,
+                        "top" => margins.top.0 = str::parse(attribute.value())?,
+                        "bottom" => margins.bottom.0 = str::parse(attribute.value())?,
+                        "header" => offset_header.0 = str::parse(attribute.value())?,
+                        "footer" => offset_footer.0 = str::parse(attribute.value())?,
+                        _ => ()
+                    }
+                }
+            }
+            _ => ()
+        }
+    }
+
+    Ok(PageSettings::new(page_size, margins, offset_header, offset_footer))
+}
+
+/// Finds the section properties of the sections of the body, in document
+/// order. Every section but the last is ended by a paragraph containing its
+/// section properties (17.6.18 sectPr), and the last by the section
+/// properties of the body.
+fn find_section_properties<'a, 'input>(body: &xml::Node<'a, 'input>) -> Vec<xml::Node<'a, 'input>> {
+    let mut section_properties: Vec<_> = body.children()
+        .filter(|child| child.tag_name().name() == "p")
+        .filter_map(find_paragraph_section_properties)
+        .collect();
+    section_properties.extend(find_body_section_properties(body));
+    section_properties
+}
+
+/// Finds the body of the main document part (17.2.2 body).
+fn find_body<'a, 'input>(document: &'a xml::Document<'input>) -> Result<xml::Node<'a, 'input>, Error> {
+    document.root_element().children()
+        .find(|child| child.tag_name().name() == "body")
+        .ok_or(Error::MissingElement("w:body"))
+}
+
+/// Finds the section properties of the section the paragraph ends, if any.
+fn find_paragraph_section_properties<'a, 'input>(paragraph: xml::Node<'a, 'input>) -> Option<xml::Node<'a, 'input>> {
+    paragraph.children()
+        .find(|child| child.tag_name().name() == "pPr")?
+        .children()
+        .find(|child| child.tag_name().name() == "sectPr")
+}
+
+/// The page settings and type of the section properties.
+fn load_section_layout(diagnostics: &mut Vec<Diagnostic>, section_properties: &xml::Node, fallback: PageSettings) -> SectionLayout {
+    let page_settings = match parse_page_settings(section_properties) {
+        Ok(page_settings) => page_settings,
+        Err(e) => {
+            diagnostics.push(Diagnostic::error("w:sectPr", format!("invalid page size or margins: {:?}", e)));
+            fallback
+        }
+    };
+
+    // 17.6.22 type (Section Type)
+    let section_type = section_properties.children()
+        .find(|child| child.tag_name().name() == "type")
+        .and_then(|child| child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")));
+
+    let (columns, column_separator) = parse_columns(diagnostics, section_properties, &page_settings);
+
+    SectionLayout {
+        page_settings,
+        section_type: wp::SectionType::from_string(section_type, diagnostics),
+        columns,
+        column_separator,
+        page_first: 0,
+    }
+}
+
+/// Reads the columns of the section (17.6.4 cols), as the horizontal bounds of
+/// their text on the page, and whether a line is drawn between them.
+fn parse_columns(diagnostics: &mut Vec<Diagnostic>, section_properties: &xml::Node, page_settings: &PageSettings) -> (Vec<Column>, bool) {
+    let left = page_settings.margins.left.get_pts();
+    let right = page_settings.size.width().get_pts() - page_settings.margins.right.get_pts();
+    let single_column = vec![Column { start: left, end: right }];
+
+    let Some(cols) = section_properties.children().find(|child| child.tag_name().name() == "cols") else {
+        return (single_column, false);
+    };
+
+    let on_off = |name: &str, default: bool, diagnostics: &mut Vec<Diagnostic>| match cols.attribute((WORD_PROCESSING_XML_NAMESPACE, name)) {
+        Some(value) => parse_on_off(value, "w:cols", diagnostics).unwrap_or(default),
+        None => default,
+    };
+    fn twips(node: &xml::Node, name: &str) -> Option<f32> {
+        node.attribute((WORD_PROCESSING_XML_NAMESPACE, name))
+            .and_then(|value| value.parse().ok())
+            .map(|value: u32| TwelfteenthPoint(value).get_pts())
+    }
+
+    let separator = on_off("sep", false, diagnostics);
+    let column_elements: Vec<_> = cols.children().filter(|child| child.tag_name().name() == "col").collect();
+
+    // Columns of different widths are specified one by one (17.6.3 col).
+    if !on_off("equalWidth", true, diagnostics) && !column_elements.is_empty() {
+        let mut start = left;
+        let columns = column_elements.iter().map(|element| {
+            let column = Column { start, end: start + twips(element, "w").unwrap_or(0.0) };
+            start = column.end + twips(element, "space").unwrap_or(0.0);
+            column
+        }).collect();
+        return (columns, separator);
+    }
+
+    let count = cols.attribute((WORD_PROCESSING_XML_NAMESPACE, "num"))
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let space = twips(&cols, "space").unwrap_or(TwelfteenthPoint(720u32).get_pts());
+    let width = (right - left - space * (count - 1) as f32) / count as f32;
+    if width <= 0.0 {
+        diagnostics.push(Diagnostic::error("w:cols", format!("{} columns with {}pt between them don't fit on the page", count, space)));
+        return (single_column, separator);
+    }
+
+    let columns = (0..count).map(|index| {
+        let start = left + index as f32 * (width + space);
+        Column { start, end: start + width }
+    }).collect();
+    (columns, separator)
+}
+
+/// The XML of the parts related to the main document part, which are laid out
+/// together with it.
+#[derive(Debug, Default)]
+pub struct RelatedParts {
+    /// The header and footer parts by the id of their relationship.
+    pub headers_footers: HashMap<Rc<str>, String>,
+
+    pub footnotes: Option<String>,
+    pub endnotes: Option<String>,
+    pub comments: Option<String>,
+
+    /// The document settings part (17.15.1.78 settings).
+    pub settings: Option<String>,
+}
+
+/// Finds the section properties of the last section, which are a direct child
+/// of the body (17.6.17 sectPr).
+fn find_body_section_properties<'a, 'input>(body: &xml::Node<'a, 'input>) -> Option<xml::Node<'a, 'input>> {
+    body.children()
+        .find(|root_child| root_child.tag_name().name() == "sectPr")
+}
+
+pub struct DocumentResult {
+    pub document: Document,
+    pub root_node: Node,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Follows the loading of a document, of which the body is laid out one child
+/// at a time, so the pages can be shown before the whole document is loaded.
+pub trait LoadObserver {
+    /// Called after a child of the body was laid out, with how much of the
+    /// body is laid out, between 0.0 and 1.0, and the document so far. The
+    /// pages before `root_node.page_last` won't change anymore, but the
+    /// headers, footers and notes are only laid out after the body.
+    fn on_progress(&mut self, progress: f32, document: &mut Document, root_node: &mut Node);
+
+    /// Whether the loading should stop, e.g. because the tab was closed. The
+    /// document is then returned as far as it was laid out.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(f32)> LoadObserver for F {
+    fn on_progress(&mut self, progress: f32, _document: &mut Document, _root_node: &mut Node) {
+        self(progress)
+    }
+}
+
+pub fn process_document(xml_document: &xml::Document, style_manager: &StyleManager,
+                        document_relationships: &Relationships,
+                        related_parts: &RelatedParts,
+                        numbering_manager: wp::numbering::NumberingManager,
+                        document_properties: wp::document_properties::DocumentProperties,
+                        text_calculator: &mut dyn gui::painter::TextCalculator,
+                        drawing_ml_style_settings: drawing_ml::style::StyleSettings,
+                        fallback_font: &str,
+                        revision_view: wp::revisions::RevisionView,
+                        observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let text_settings = style_manager.default_text_settings();
+    let body = find_body(xml_document)?;
+    let body_page_settings = load_page_settings(&body)?;
+
+    let mut diagnostics = numbering_manager.diagnostics().to_vec();
+    diagnostics.extend_from_slice(style_manager.diagnostics());
+    let settings = match related_parts.settings.as_deref().map(xml::Document::parse) {
+        Some(Ok(settings)) => wp::settings::DocumentSettings::from_xml(&settings, &mut diagnostics),
+        Some(Err(e)) => {
+            diagnostics.push(Diagnostic::error("w:settings", format!("failed to parse the part: {}", e)));
+            Default::default()
+        }
+        None => Default::default(),
+    };
+
+    let section_properties = find_section_properties(&body);
+    let section_layouts: Vec<SectionLayout> = section_properties.iter()
+        .map(|properties| load_section_layout(&mut diagnostics, properties, body_page_settings))
+        .collect();
+
+    // The content is laid out with the page settings and in the columns of
+    // the section it is in, starting with the first.
+    let page_settings = section_layouts.first().map(|layout| layout.page_settings).unwrap_or(body_page_settings);
+    let columns = section_layouts.first().map(|layout| layout.columns.clone()).unwrap_or_default();
+
+    let mut position = Position::new(
+        page_settings.margins.left.get_pts(),
+        page_settings.margins.top.get_pts()
+    );
+
+    let mut document = Document {
+        page_settings: body_page_settings,
+        document_properties,
+        sections: Vec::new(),
+        footnote_areas: Vec::new(),
+        comments: Vec::new(),
+        revision_view,
+        note_separators: Vec::new(),
+        source: None,
+        namespaces: xml_document.root_element().namespaces()
+            .map(|namespace| (namespace.name().map(Rc::from), Rc::from(namespace.uri())))
+            .collect(),
+        body_section_properties: find_body_section_properties(&body)
+            .map(|node| Rc::from(&xml_document.input_text()[node.range()])),
+        settings,
+        fonts: None,
+    };
+
+    let mut root_node = Document::new(text_settings);
+
+    let mut context = Context{
+        document: &mut document,
+        text_calculator,
+        observer: Some(observer),
+
+        document_relationships,
+        style_manager,
+        page_settings,
+        section_layouts,
+        current_section: 0,
+        columns,
+        current_column: 0,
+
+        drawing_ml_style_settings,
+        fallback_font,
+
+        numbering_manager,
+
+        diagnostics,
+
+        footnote_references: Vec::new(),
+        endnote_references: Vec::new(),
+        current_note_number: None,
+
+        bookmarks: collect_bookmark_texts(xml_document),
+        fields: Vec::new(),
+    };
+
+    for child in xml_document.root_element().children() {
+        if child.tag_name().name() == "body" {
+            position = process_body_element(&mut context, &mut root_node, &child, position);
+        }
+    }
+
+    // The rest of the document isn't needed anymore.
+    if context.observer.as_ref().is_some_and(|observer| observer.is_cancelled()) {
+        let diagnostics = context.diagnostics;
+        return Ok(DocumentResult { document, root_node, diagnostics });
+    }
+
+    if let Some(footnotes) = parse_notes_part(&mut context, related_parts.footnotes.as_deref(), true) {
+        process_footnotes(&mut context, &footnotes);
+    }
+
+    if let Some(endnotes) = parse_notes_part(&mut context, related_parts.endnotes.as_deref(), false) {
+        process_endnotes(&mut context, &mut root_node, &endnotes, position);
+    }
+
+    if let Some(comments) = &related_parts.comments {
+        match xml::Document::parse(comments) {
+            Ok(comments) => context.document.comments = wp::comments::parse_comments_part(&comments),
+            Err(e) => context.diagnostics.push(Diagnostic::error("w:comments", format!("failed to parse the part: {}", e))),
+        }
+    }
+
+    root_node.update_page_last();
+
+    // The page numbers are only known after pagination, so the fields that
+    // depend on them are evaluated again.
+    let page_count = root_node.page_last + 1;
+    root_node.update_page_fields(None, page_count,
+        &mut |text_part| remeasure_text_part(context.text_calculator, text_part, context.fallback_font));
+
+    for index in 0..section_properties.len() {
+        context.page_settings = context.section_layouts[index].page_settings;
+        let mut section = process_section_headers_footers(&mut context, &section_properties[..=index], &related_parts.headers_footers);
+        section.page_first = context.section_layouts[index].page_first;
+        section.page_settings = context.page_settings;
+        section.columns = context.section_layouts[index].columns.clone();
+        section.column_separator = context.section_layouts[index].column_separator;
+        section.even_and_odd_headers = context.document.settings.even_and_odd_headers;
+
+        // The headers and footers are shown on multiple pages, and are
+        // updated for every page when they are painted.
+        let headers_footers = [&mut section.headers, &mut section.footers].into_iter()
+            .flat_map(|set| [set.default.as_mut(), set.first.as_mut(), set.even.as_mut()])
+            .flatten();
+        for node in headers_footers {
+            node.update_page_fields(Some(section.page_first), page_count,
+                &mut |text_part| remeasure_text_part(context.text_calculator, text_part, context.fallback_font));
+        }
+
+        context.document.sections.push(section);
+    }
+
+    if context.document.settings.mirror_margins {
+        apply_mirror_margins(context.document, &mut root_node);
+    }
+
+    let diagnostics = context.diagnostics;
+    Ok(DocumentResult { document, root_node, diagnostics })
+}
+
+/// Moves the content of the even pages over, since their left and right
+/// margins are swapped when the margins are mirrored (17.15.1.57
+/// mirrorMargins). The headers and footers are shown on multiple pages, so
+/// they are moved when they are painted.
+fn apply_mirror_margins(document: &mut Document, root_node: &mut Node) {
+    let offsets: Vec<f32> = (0..=root_node.page_last)
+        .map(|page| document.mirror_offset_for_page(page))
+        .collect();
+    let offset_for_page = |page: usize| offsets.get(page).copied().unwrap_or_default();
+
+    root_node.apply_recursively_mut(&mut |node, _depth| {
+        let offset = offset_for_page(node.page_first);
+        *node.position.x_mut() += offset;
+
+        // The paragraph is laid out again within these bounds after editing.
+        if let wp::NodeData::Paragraph(data) = &mut node.data {
+            *data.text_start.x_mut() += offset;
+            data.line_bounds.left += offset;
+            data.line_bounds.right += offset;
+        }
+    }, 0);
+
+    for area in &mut document.footnote_areas {
+        let offset = offset_for_page(area.page);
+        area.notes.apply_recursively_mut(&mut |node, _depth| *node.position.x_mut() += offset, 0);
+    }
+
+    for separator in &mut document.note_separators {
+        let offset = offset_for_page(separator.page);
+        separator.rect.left += offset;
+        separator.rect.right += offset;
+    }
+}
+
+/// The start of a main document part converted from another file format.
+const CONVERTED_DOCUMENT_START: &str = concat!(
+    r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" "#,
+    r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><w:body>"#,
+);
+
+/// The end of a main document part converted from another file format. The
+/// section properties aren't converted, so the pages are A4 with the margins
+/// of Microsoft Word.
+const CONVERTED_DOCUMENT_END: &str = concat!(
+    r#"<w:sectPr><w:pgSz w:w="11906" w:h="16838"/>"#,
+    r#"<w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr>"#,
+    r#"</w:body></w:document>"#,
+);
+
+/// Lays out a main document part converted from another file format, such as
+/// a Word Binary File. The document has no source package, so it can't be
+/// saved.
+fn process_converted_document(document_text: &str, styles: &str, numbering: Option<&str>, relationships: &Relationships,
+        text_calculator: &mut dyn TextCalculator, fallback_font: &str, revision_view: wp::revisions::RevisionView,
+        observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let document = xml::Document::parse(document_text)
+        .map_err(|e| Error::from(e).in_part("word/document.xml"))?;
+
+    let numbering_manager = match numbering {
+        Some(numbering) => xml::Document::parse(numbering).map_err(Error::from)
+            .and_then(|numbering| wp::numbering::NumberingManager::from_xml(&numbering))
+            .map_err(|e| e.in_part("word/numbering.xml"))?,
+        None => wp::numbering::NumberingManager::new(),
+    };
+
+    let theme_settings = ThemeSettings::default();
+    let style_manager = xml::Document::parse(styles).map_err(Error::from)
+        .and_then(|styles| StyleManager::from_document(&styles, &numbering_manager, &theme_settings))
+        .map_err(|e| e.in_part("word/styles.xml"))?;
+
+    process_document(&document, &style_manager, relationships, &RelatedParts::default(), numbering_manager,
+        wp::document_properties::DocumentProperties::new(), text_calculator, theme_settings, fallback_font,
+        revision_view, observer)
+}
+
+/// Collects the text of the bookmarks (17.13.6.2 bookmarkStart) by their
+/// name, before the layout, since REF fields can refer to bookmarks further on
+/// in the document.
+fn collect_bookmark_texts(document: &xml::Document) -> HashMap<String, String> {
+    let mut bookmarks = HashMap::new();
+
+    // The id, name and text of the bookmarks of which the end hasn't been
+    // reached yet, since bookmarks can overlap.
+    let mut open_bookmarks: Vec<(&str, &str, String)> = Vec::new();
+
+    for node in document.root_element().descendants() {
+        if node.tag_name().namespace() != Some(WORD_PROCESSING_XML_NAMESPACE) {
+            continue;
+        }
+
+        let id = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "id"));
+        match node.tag_name().name() {
+            "bookmarkStart" => {
+                if let (Some(id), Some(name)) = (id, node.attribute((WORD_PROCESSING_XML_NAMESPACE, "name"))) {
+                    open_bookmarks.push((id, name, String::new()));
+                }
+            }
+
+            "bookmarkEnd" => {
+                if let Some(index) = open_bookmarks.iter().position(|(open_id, _, _)| Some(*open_id) == id) {
+                    let (_, name, text) = open_bookmarks.remove(index);
+                    bookmarks.insert(String::from(name), text);
+                }
+            }
+
+            "t" => {
+                for (_, _, text) in &mut open_bookmarks {
+                    text.push_str(node.text().unwrap_or_default());
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    bookmarks
+}
+
+/// Lays out the headers and footers referenced by the section properties of
+/// the last section of `sections`, where `header_footer_parts` contains the
+/// XML of the header and footer parts by the id of their relationship. The
+/// headers and footers a section doesn't reference are inherited from the
+/// sections before it (17.10.5).
+fn process_section_headers_footers(context: &mut Context, sections: &[xml::Node],
+                                   header_footer_parts: &HashMap<Rc<str>, String>) -> wp::Section {
+    let mut section = wp::Section::default();
+    let Some((section_properties, previous_sections)) = sections.split_last() else {
+        return section;
+    };
+
+    fn reference_type<'a>(child: &xml::Node<'a, '_>) -> (bool, &'a str) {
+        (child.tag_name().name() == "headerReference",
+            child.attribute((WORD_PROCESSING_XML_NAMESPACE, "type")).unwrap_or("default"))
+    }
+
+    let is_reference = |child: &xml::Node| matches!(child.tag_name().name(), "headerReference" | "footerReference");
+
+    let own_references: Vec<_> = section_properties.children().filter(is_reference).map(|child| reference_type(&child)).collect();
+    let mut inherited_references: Vec<xml::Node> = Vec::new();
+    for child in previous_sections.iter().rev().flat_map(|properties| properties.children()).filter(is_reference) {
+        let kind = reference_type(&child);
+        if !own_references.contains(&kind) && !inherited_references.iter().any(|inherited| reference_type(inherited) == kind) {
+            inherited_references.push(child);
+        }
+    }
+
+    for child in inherited_references.into_iter().chain(section_properties.children()) {
+        let is_header = match child.tag_name().name() {
+            // 17.10.5 headerReference
+            "headerReference" => true,
+
+            // 17.10.2 footerReference
+            "footerReference" => false,
+
+            // 17.10.6 titlePg (Different First Page Headers and Footers)
+            "titlePg" => {
+                section.title_page = match child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                    Some(val) => parse_on_off(val, "w:titlePg", &mut context.diagnostics).unwrap_or(false),
+                    None => true,
+                };
+                continue;
+            }
+
+            _ => continue,
+        };
+
+        let source = if is_header { "w:headerReference" } else { "w:footerReference" };
+        let Some(relationship_id) = child.attribute((XMLNS_RELATIONSHIPS, "id")) else {
+            context.diagnostics.push(Diagnostic::error(source, String::from("missing relationship id")));
+            continue;
+        };
+
+        let Some(part_text) = header_footer_parts.get(relationship_id) else {
+            context.diagnostics.push(Diagnostic::warning(source, format!("part not found: no relationship with id \"{}\"", relationship_id)));
+            continue;
+        };
+
+        let part = match xml::Document::parse(part_text) {
+            Ok(part) => part,
+            Err(e) => {
+                context.diagnostics.push(Diagnostic::error(source, format!("failed to parse the part: {}", e)));
+                continue;
+            }
+        };
+
+        let set = if is_header { &mut section.headers } else { &mut section.footers };
+        match child.attribute((WORD_PROCESSING_XML_NAMESPACE, "type")).unwrap_or("default") {
+            "default" => set.default = Some(process_header_footer_part(context, &part.root_element(), is_header)),
+            "first" => set.first = Some(process_header_footer_part(context, &part.root_element(), is_header)),
+
+            // The even headers and footers are only used when the settings
+            // say so (17.10.1 evenAndOddHeaders).
+            "even" if context.document.settings.even_and_odd_headers => {
+                set.even = Some(process_header_footer_part(context, &part.root_element(), is_header));
+            }
+            "even" => (),
+
+            header_type => {
+                context.diagnostics.push(Diagnostic::warning(source, format!("{} headers and footers aren't supported yet", header_type)));
+            }
+        }
+    }
+
+    section
+}
+
+/// Lays out the content of a header (`w:hdr`) or footer (`w:ftr`) part on the
+/// first page. The top of a header is at the header distance from the top of
+/// the page, and the bottom of a footer at the footer distance from the
+/// bottom of the page (17.6.11 pgMar).
+fn process_header_footer_part(context: &mut Context, element: &xml::Node, is_header: bool) -> Node {
+    let page_settings = context.page_settings;
+    let mut node = Document::new(context.style_manager.default_text_settings());
+
+    let top = if is_header { page_settings.offset_header.get_pts() } else { 0.0 };
+    let position = Position::new(page_settings.margins.left.get_pts(), top);
+    let end = process_detached_content(context, &mut node, element, position);
+
+    if !is_header {
+        let bottom = lines_bottom(context, &node, end.y());
+        move_down(&mut node, page_settings.size.height().get_pts() - page_settings.offset_footer.get_pts() - bottom);
+    }
+
+    node
+}
+
+/// Lays out block-level content that isn't part of the body, such as headers
+/// and footnotes, without reporting progress.
+fn process_detached_content(context: &mut Context, parent: &mut Node, element: &xml::Node, position: Position<f32>) -> Position<f32> {
+    // The progress is about the body of the document.
+    let observer = context.observer.take();
+    let columns = std::mem::take(&mut context.columns);
+    let end = process_body_element(context, parent, element, position);
+    context.observer = observer;
+    context.columns = columns;
+    end
+}
+
+/// The bottom of the lowest line of the content on the last page of the node,
+/// or `end` when that is further down. The layout continues below the last
+/// line of a paragraph at the height of its text, without the line gap of
+/// the font, but content placed against an edge has to fit its lines
+/// completely.
+fn lines_bottom(context: &mut Context, node: &Node, end: f32) -> f32 {
+    node.find_all(|child| matches!(child.data, wp::NodeData::TextPart(..)) && child.page_first == node.page_last)
+        .into_iter()
+        .map(|part| {
+            let (_, font_metrics) = resolve_font(&part.text_settings, context.text_calculator, &context.drawing_ml_style_settings, context.fallback_font);
+            part.position.y() + font_metrics.line_height()
+        })
+        .fold(end, f32::max)
+}
+
+fn move_down(node: &mut Node, offset: f32) {
+    node.apply_recursively_mut(&mut |node, _depth| *node.position.y_mut() += offset, 0);
+}
+
+/// The width of the line separating the footnotes and endnotes from the text
+/// above them, in points.
+const NOTE_SEPARATOR_WIDTH: f32 = 144.0;
+
+/// The thickness of the separator line, in points.
+const NOTE_SEPARATOR_THICKNESS: f32 = 0.5;
+
+/// The space between the separator line and the notes below it, in points.
+const NOTE_SEPARATOR_SPACING: f32 = 6.0;
+
+/// Parses the footnotes part (17.11.15 footnotes) or the endnotes part
+/// (17.11.8 endnotes), when the body references any of its notes.
+fn parse_notes_part<'input>(context: &mut Context, part: Option<&'input str>, footnotes: bool) -> Option<xml::Document<'input>> {
+    let (references, source) = if footnotes {
+        (&context.footnote_references, "w:footnotes")
+    } else {
+        (&context.endnote_references, "w:endnotes")
+    };
+
+    if references.is_empty() {
+        return None;
+    }
+
+    let Some(part) = part else {
+        context.diagnostics.push(Diagnostic::error(source, String::from("notes are referenced, but the document has no part containing them")));
+        return None;
+    };
+
+    match xml::Document::parse(part) {
+        Ok(document) => Some(document),
+        Err(e) => {
+            context.diagnostics.push(Diagnostic::error(source, format!("failed to parse the part: {}", e)));
+            None
+        }
+    }
+}
+
+/// Finds the footnote or endnote with the given id (17.11.10 footnote).
+fn find_note<'a, 'input>(notes: &'a xml::Document<'input>, id: &str) -> Option<xml::Node<'a, 'input>> {
+    notes.root_element().children()
+        .find(|note| note.is_element() && note.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")) == Some(id))
+}
+
+/// Lays out the footnotes at the bottom of the page they're referenced on,
+/// below a separator line.
+///
+/// TODO: the text of the body doesn't make room for the footnotes yet, so
+///       they overlap text at the bottom of a full page.
+fn process_footnotes(context: &mut Context, footnotes: &xml::Document) {
+    let page_settings = context.page_settings;
+    let left = page_settings.margins.left.get_pts();
+    let bottom = page_settings.size.height().get_pts() - page_settings.margins.bottom.get_pts();
+
+    let references = std::mem::take(&mut context.footnote_references);
+    let mut index = 0;
+    while index < references.len() {
+        let page = references[index].page;
+        let mut notes = Document::new(context.style_manager.default_text_settings());
+        let mut position = Position::new(left, 0.0);
+
+        while let Some(reference) = references.get(index).filter(|reference| reference.page == page) {
+            match find_note(footnotes, &reference.id) {
+                Some(footnote) => {
+                    context.current_note_number = reference.number;
+                    position = process_detached_content(context, &mut notes, &footnote, position);
+                }
+                None => context.diagnostics.push(Diagnostic::warning("w:footnoteReference",
+                    format!("footnote not found: no footnote with id \"{}\"", reference.id))),
+            }
+            index += 1;
+        }
+
+        let top = bottom - lines_bottom(context, &notes, position.y());
+        move_down(&mut notes, top);
+        context.document.note_separators.push(wp::NoteSeparator {
+            page,
+            rect: Rect::from_position_and_size(
+                Position::new(left, top - NOTE_SEPARATOR_SPACING),
+                Size::new(NOTE_SEPARATOR_WIDTH, NOTE_SEPARATOR_THICKNESS)
+            ),
+        });
+        context.document.footnote_areas.push(wp::FootnoteArea { page, notes });
+    }
+
+    context.current_note_number = None;
+    context.footnote_references = references;
+}
+
+/// Lays out the endnotes after the body, below a separator line.
+fn process_endnotes(context: &mut Context, root_node: &mut Node, endnotes: &xml::Document, position: Position<f32>) {
+    let body_bottom = lines_bottom(context, root_node, position.y());
+    let separator_position = Position::new(position.x(), body_bottom + NOTE_SEPARATOR_SPACING);
+    context.document.note_separators.push(wp::NoteSeparator {
+        page: root_node.page_last,
+        rect: Rect::from_position_and_size(separator_position, Size::new(NOTE_SEPARATOR_WIDTH, NOTE_SEPARATOR_THICKNESS)),
+    });
+
+    let mut position = Position::new(position.x(), separator_position.y() + NOTE_SEPARATOR_SPACING);
+    let references = std::mem::take(&mut context.endnote_references);
+    for reference in &references {
+        match find_note(endnotes, &reference.id) {
+            Some(endnote) => {
+                context.current_note_number = reference.number;
+                position = process_detached_content(context, root_node, &endnote, position);
+            }
+            None => context.diagnostics.push(Diagnostic::warning("w:endnoteReference",
+                format!("endnote not found: no endnote with id \"{}\"", reference.id))),
+        }
+    }
+
+    context.current_note_number = None;
+    context.endnote_references = references;
+}
+
+/// Appends the mark of a reference to a footnote or endnote, and records the
+/// reference so the note is laid out later.
+fn process_note_reference_element(context: &mut Context, text_run: &mut Node, line_layout: &mut LineLayout,
+                                  node: &xml::Node, position: Position<f32>) -> Position<f32> {
+    let is_footnote = node.tag_name().name() == "footnoteReference";
+    let source = if is_footnote { "w:footnoteReference" } else { "w:endnoteReference" };
+
+    let Some(id) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")) else {
+        context.diagnostics.push(Diagnostic::error(source, String::from("missing id")));
+        return position;
+    };
+
+    // The run contains a custom mark instead of the number.
+    let has_custom_mark = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "customMarkFollows"))
+        .and_then(|value| parse_on_off(value, source, &mut context.diagnostics))
+        .unwrap_or(false);
+
+    let references = if is_footnote { &context.footnote_references } else { &context.endnote_references };
+    let number = if has_custom_mark {
+        None
+    } else {
+        Some(references.iter().filter(|reference| reference.number.is_some()).count() + 1)
+    };
+
+    let mut position = position;
+    if let Some(number) = number {
+        position = append_text_element(&number.to_string(), text_run, line_layout, context.text_calculator,
+            &context.drawing_ml_style_settings, context.fallback_font);
+    }
+
+    let page = text_run.children.last().map(|child| child.page_last).unwrap_or(text_run.page_last);
+    let reference = NoteReference { id: String::from(id), number, page };
+    if is_footnote {
+        context.footnote_references.push(reference);
+    } else {
+        context.endnote_references.push(reference);
+    }
+
+    position
+}
+
+fn process_drawing_element(context: &mut Context, parent: &mut Node, line_layout: &mut LineLayout,
+                           node: &xml::Node, mut position: Position<f32>) -> Position<f32> {
+    for child in node.children() {
+        match child.tag_name().name() {
+            "anchor" => {
+                let drawing_object = drawing_ml::DrawingObject::parse_anchor_object(&child, context.document_relationships);
+                check_missing_picture(context, &drawing_object);
+
+                let anchor = *drawing_object.anchor().unwrap();
+                match anchor.wrap {
+                    drawing_ml::WrapMode::TopAndBottom => {
+                        position = place_top_and_bottom_drawing(parent, line_layout, drawing_object, &anchor, position);
+                    }
+                    wrap => {
+                        context.diagnostics.push(Diagnostic::warning("wp:anchor",
+                            format!("floating objects with wrap mode {:?} aren't supported yet", wrap)));
+                    }
+                }
+            }
+
+            "inline" => {
+                let drawing_object = drawing_ml::DrawingObject::parse_inline_object(&child, context.document_relationships);
+                let size = drawing_object.size();
+                check_missing_picture(context, &drawing_object);
+
+                let inline_drawing = wp::create_child(parent, wp::NodeData::Drawing(drawing_object));
+                parent.nth_child_mut(inline_drawing).size = size;
+
+                assert_eq!(parent.size, Size::empty());
+                parent.size = size;
+            }
+
+            _ => ()
+        }
+    }
+
+    position
+}
+
+fn check_missing_picture(context: &mut Context, drawing_object: &drawing_ml::DrawingObject) {
+    if !drawing_object.is_picture() {
+        context.diagnostics.push(Diagnostic::warning("a:graphicData",
+            String::from("graphics other than pictures aren't supported yet")));
+    }
+
+    if let Some(relationship_id) = drawing_object.missing_relationship_id() {
+        context.diagnostics.push(Diagnostic::warning("a:blip",
+            format!("embedded picture not found: no relationship with id \"{}\"", relationship_id)));
+    }
+
+    if let Some(error) = drawing_object.image_decode_error() {
+        context.diagnostics.push(Diagnostic::warning("a:blip",
+            format!("embedded picture couldn't be decoded: {}", error)));
+    }
+}
+
+/// Places a floating object with top-and-bottom wrapping below the current
+/// line, and returns the position below the object, where the text continues.
+fn place_top_and_bottom_drawing(parent: &mut Node, line_layout: &mut LineLayout, drawing_object: drawing_ml::DrawingObject,
+                                anchor: &drawing_ml::Anchor, position: Position<f32>) -> Position<f32> {
+    let size = drawing_object.size();
+    let distance_top = anchor.distance_top.get_pts();
+    let distance_bottom = anchor.distance_bottom.get_pts();
+
+    let mut top = position.y();
+    if position.x() > line_layout.line_start() {
+        top += line_layout.line_height();
+    }
+
+    // Move the object to the next column or page when it doesn't fit, unless
+    // it doesn't fit on any page.
+    if top + distance_top + size.height() > line_layout.page_vertical_end && top > line_layout.page_vertical_start {
+        if line_layout.break_column() {
+            parent.page_last += 1;
+        }
+        top = line_layout.page_vertical_start;
+    }
+
+    let available_width = line_layout.page_horizontal_end - line_layout.page_horizontal_start;
+    let left = line_layout.page_horizontal_start + match anchor.horizontal_position {
+        drawing_ml::HorizontalPosition::Left => 0.0,
+        drawing_ml::HorizontalPosition::Center => (available_width - size.width()) / 2.0,
+        drawing_ml::HorizontalPosition::Right => available_width - size.width(),
+        drawing_ml::HorizontalPosition::Offset(offset) => offset.get_pts(),
+    };
+
+    let drawing = wp::create_child(parent, wp::NodeData::Drawing(drawing_object));
+    let drawing = parent.nth_child_mut(drawing);
+    drawing.position = Position::new(left, top + distance_top);
+    drawing.size = size;
+
+    let bottom = top + distance_top + size.height() + distance_bottom;
+    line_layout.new_line_at(bottom);
+    Position::new(line_layout.line_start(), bottom)
+}
+
+fn process_body_element(context: &mut Context,
+                        parent: &mut Node,
+                        node: &xml::Node,
+                        position: Position<f32>) -> Position<f32> {
+    let mut position = position;
+
+    let child_count = node.children().count();
+    let mut child_idx = 0;
+
+    let mut previous_paragraph = None;
+
+    for child in node.children() {
+        if context.observer.as_ref().is_some_and(|observer| observer.is_cancelled()) {
+            break;
+        }
+
+        if child_idx % MEASURE_AHEAD_CHILD_COUNT == 0 {
+            measure_paragraphs_ahead(context, parent, node.children().skip(child_idx).take(MEASURE_AHEAD_CHILD_COUNT));
+        }
+
+        match child.tag_name().name() {
+            "p" => {
+                position = process_paragraph_element(context, parent, &child, position, None, &mut previous_paragraph);
+                if find_paragraph_section_properties(child).is_some() {
+                    parent.check_last_page_number_from_new_child();
+                    position = start_next_section(context, parent, position);
+                    previous_paragraph = None;
+                }
+            }
+            "sdt" => {
+                position = process_structured_document_tag_block_level(context, parent, &child, position);
+                previous_paragraph = None;
+            }
+            "tbl" => {
+                position = process_table_element(context, parent, &child, position);
+                previous_paragraph = None;
+            }
+            "bookmarkStart" | "bookmarkEnd" => process_bookmark_element(context, parent, &child, position),
+            "commentRangeStart" | "commentRangeEnd" => process_comment_range_element(context, parent, &child, position),
+            _ => ()
+        }
+
+        parent.check_last_page_number_from_new_child();
+
+        child_idx += 1;
+        if let Some(observer) = context.observer.as_deref_mut() {
+            observer.on_progress(child_idx as f32 / child_count as f32, context.document, parent);
+        }
+    }
+
+    position
+}
+
+/// The number of children of the body of which the paragraphs are measured
+/// ahead at once, which is small enough to show the first pages quickly.
+const MEASURE_AHEAD_CHILD_COUNT: usize = 64;
+
+/// A paragraph of which the text is measured ahead, with the horizontal
+/// bounds of its lines.
+struct ParagraphAhead {
+    first_line_start: f32,
+    line_start: f32,
+    line_end: f32,
+    runs: Vec<RunAhead>,
+}
+
+/// The text of a run that is measured ahead, with an owned copy of its font,
+/// so it can be measured on another thread.
+struct RunAhead {
+    family_name: String,
+    font_size: f32,
+    font_weight: FontWeight,
+    italic: bool,
+    character_spacing: f32,
+    horizontal_scale: f32,
+    texts: Vec<String>,
+}
+
+impl RunAhead {
+    fn new(text_settings: &TextSettings, theme: &ThemeSettings) -> Self {
+        let family_name = text_settings.font_family_name(theme);
+        let font_spec = text_settings.font_specification(&family_name);
+        Self {
+            family_name: String::from(font_spec.family_name()),
+            font_size: font_spec.size(),
+            font_weight: font_spec.weight(),
+            italic: font_spec.is_italic(),
+            character_spacing: font_spec.character_spacing(),
+            horizontal_scale: font_spec.horizontal_scale(),
+            texts: Vec::new(),
+        }
+    }
+
+    fn font_specification<'a>(&self, family_name: &'a str) -> FontSpecification<'a> {
+        FontSpecification::new(family_name, self.font_size, self.font_weight)
+            .with_italic(self.italic)
+            .with_character_spacing(self.character_spacing)
+            .with_horizontal_scale(self.horizontal_scale)
+    }
+}
+
+impl ParagraphAhead {
+    /// Breaks the text of the runs into lines, as if the paragraph started
+    /// at the top of a page.
+    fn measure(&self, text_calculator: &mut dyn TextCalculator, fallback_font: &str) {
+        let mut x = self.first_line_start;
+
+        for run in &self.runs {
+            let family_name = match text_calculator.font_metrics(run.font_specification(&run.family_name)) {
+                Ok(..) => run.family_name.as_str(),
+                Err(..) => fallback_font,
+            };
+
+            for text in &run.texts {
+                x = break_text_ahead(text_calculator, run.font_specification(family_name), text, x, self.line_start, self.line_end);
+            }
+        }
+    }
+}
+
+/// Measures the paragraphs among the `children` of the body before they are
+/// laid out, on the threads of the text calculator. Every paragraph is broken
+/// into lines independently, since the position it starts at isn't known
+/// yet, so the text of most lines is measured already when the layout places
+/// them.
+fn measure_paragraphs_ahead<'a, 'input: 'a>(context: &mut Context, parent: &Node, children: impl Iterator<Item = xml::Node<'a, 'input>>) {
+    let mut section = context.current_section;
+    let mut jobs: Vec<MeasureJob> = Vec::new();
+
+    for child in children {
+        if child.tag_name().name() != "p" {
+            continue;
+        }
+
+        let (page_settings, columns) = match context.section_layouts.get(section) {
+            Some(layout) => (layout.page_settings, layout.columns.clone()),
+            None => (context.page_settings, context.columns.clone()),
+        };
+
+        // The problems with the properties are reported when the paragraph
+        // is laid out.
+        let mut diagnostics = Vec::new();
+        let mut text_settings = parent.text_settings.clone();
+        if let Some(properties) = child.first_child().filter(|node| node.tag_name().name() == "pPr") {
+            process_paragraph_properties_element(&context.numbering_manager, context.style_manager, &mut text_settings, &properties, &mut diagnostics);
+        }
+
+        let mut line_layout = LineLayout::new(&page_settings, 0.0);
+        line_layout.set_columns(columns, 0);
+        line_layout.indentation_start = text_settings.indentation_left.map(|indentation| indentation.get_pts()).unwrap_or(0.0);
+        line_layout.indentation_end = text_settings.indentation_right.map(|indentation| indentation.get_pts()).unwrap_or(0.0);
+        line_layout.first_line_offset = text_settings.first_line_offset_pts();
+
+        let runs = child.children()
+            .filter(|node| node.tag_name().name() == "r")
+            .map(|run| {
+                let mut run_text_settings = text_settings.clone();
+                let mut texts = Vec::new();
+                for property in run.children() {
+                    match property.tag_name().name() {
+                        "rPr" => run_text_settings.apply_run_properties_element(context.style_manager, &context.drawing_ml_style_settings,
+                            &property, &mut diagnostics),
+                        "t" => texts.extend(property.children()
+                            .filter(|node| node.node_type() == xml::NodeType::Text)
+                            .map(|node| String::from(node.text().unwrap_or_default()))),
+                        _ => (),
+                    }
+                }
+
+                RunAhead {
+                    texts,
+                    ..RunAhead::new(&run_text_settings, &context.drawing_ml_style_settings)
+                }
+            })
+            .collect();
+
+        let paragraph = ParagraphAhead {
+            first_line_start: line_layout.first_line_start(),
+            line_start: line_layout.line_start(),
+            line_end: line_layout.line_end(),
+            runs,
+        };
+        let fallback_font = String::from(context.fallback_font);
+        jobs.push(Box::new(move |text_calculator: &mut dyn TextCalculator| paragraph.measure(text_calculator, &fallback_font)));
+
+        if find_paragraph_section_properties(child).is_some() {
+            section += 1;
+        }
+    }
+
+    if !jobs.is_empty() {
+        context.text_calculator.measure_ahead(jobs);
+    }
+}
+
+/// Breaks the text into lines like [process_text_element_text] does, which
+/// measures the same text, without laying it out. Returns the horizontal
+/// position after the text.
+fn break_text_ahead(text_calculator: &mut dyn TextCalculator, font_spec: FontSpecification, text_string: &str,
+        x: f32, line_start: f32, line_end: f32) -> f32 {
+    let mut x = x;
+
+    let mut start_index = None;
+    let mut previous_word_pair = None;
+    let mut has_stopped = false;
+
+    let mut iter = UnicodeSegmentation::split_word_bound_indices(text_string).peekable();
+    while let Some((index, word)) = iter.next() {
+        let start = *start_index.get_or_insert(index);
+
+        let mut line = &text_string[start..(index + word.len())];
+        let Ok(text_size) = text_calculator.measure_text(font_spec, line) else {
+            return x;
+        };
+        let mut width = text_size.width();
+
+        let max_width_fitting_on_line = line_end - x;
+
+        let is_hanging_whitespace = !has_stopped && start == index && word.trim().is_empty();
+        if !is_hanging_whitespace && (max_width_fitting_on_line < 0.0 || has_stopped) {
+            x = line_start;
+
+            if iter.peek().is_some() {
+                has_stopped = false;
+                previous_word_pair = Some((index, word));
+                continue;
+            }
+        }
+
+        if let Some((next_index, next_word)) = iter.peek() {
+            let line_with_next = &text_string[start..(next_index + next_word.len())];
+            let width_with_next = text_calculator.measure_text(font_spec, line_with_next).map(|size| size.width()).unwrap_or(0.0);
+
+            if width < max_width_fitting_on_line && (iter.clone().nth(1).is_some() || width_with_next < max_width_fitting_on_line) {
+                previous_word_pair = Some((index, word));
+                continue;
+            }
+
+            start_index = None;
+
+            if let Some((previous_word_index, previous_word)) = previous_word_pair {
+                if !word.trim().is_empty() {
+                    line = &text_string[start..(previous_word_index + previous_word.len())];
+                    width = text_calculator.measure_text(font_spec, line).map(|size| size.width()).unwrap_or(0.0);
+
+                    start_index = Some(index);
+                }
+            }
+        }
+
+        previous_word_pair = None;
+
+        measure_grapheme_ends(text_calculator, font_spec, line, width);
+        x += width;
+        has_stopped = true;
+    }
+
+    x
+}
+
+/// Continues the layout with the section after the one that was just ended,
+/// on the page where the section type says it starts.
+fn start_next_section(context: &mut Context, parent: &mut Node, position: Position<f32>) -> Position<f32> {
+    let Some(section) = context.section_layouts.get(context.current_section + 1) else {
+        return position;
+    };
+
+    let page_settings = section.page_settings;
+    let next_page = match section.section_type {
+        // A page can't change its size halfway, so such a section starts on
+        // the next page anyway.
+        wp::SectionType::Continuous if page_settings.size != context.page_settings.size => Some(parent.page_last + 1),
+        wp::SectionType::Continuous => None,
+        wp::SectionType::NextColumn | wp::SectionType::NextPage => Some(parent.page_last + 1),
+
+        // The page numbers start at one, so the even pages have odd indices.
+        wp::SectionType::EvenPage => Some(parent.page_last + 1 + (parent.page_last % 2)),
+        wp::SectionType::OddPage => Some(parent.page_last + 2 - (parent.page_last % 2)),
+    };
+
+    context.current_section += 1;
+    context.page_settings = page_settings;
+    context.columns = section.columns.clone();
+    context.current_column = 0;
+
+    let Some(next_page) = next_page else {
+        context.section_layouts[context.current_section].page_first = parent.page_last;
+        return position;
+    };
+
+    context.section_layouts[context.current_section].page_first = next_page;
+
+    // The content after the break is put on the next page by its parent, so
+    // no break is left behind to be saved as a page break.
+    parent.propose_last_page_number(next_page);
+    Position::new(page_settings.margins.left.get_pts(), page_settings.margins.top.get_pts())
+}
+
+fn process_break_element(parent: &mut Node, line_layout: &mut LineLayout, node: &xml::Node, diagnostics: &mut Vec<Diagnostic>) {
+    let break_type = wp::BreakType::from_string(node.attribute((WORD_PROCESSING_XML_NAMESPACE, "type")));
+    match break_type {
+        wp::BreakType::Column | wp::BreakType::Page => {
+            // Without columns, a column break moves to the next page too.
+            let is_next_page = match break_type {
+                wp::BreakType::Column => line_layout.break_column(),
+                _ => {
+                    line_layout.break_page();
+                    true
+                }
+            };
+
+            let next_page = parent.page_last + is_next_page as usize;
+
+            let child = wp::create_child(parent, wp::NodeData::Break(break_type));
+            let mut child = &mut parent.nth_child_mut(child);
+            child.page_first = next_page;
+            child.position = line_layout.position_on_line;
+            child.page_last = next_page;
+        }
+        _ => {
+            diagnostics.push(Diagnostic::warning("w:br", format!("unsupported break type: {:?}", break_type)));
+        }
+    }
+}
+
+fn process_hyperlink_element(context: &mut Context,
+                             parent: &mut Node,
+                             line_layout: &mut wp::layout::LineLayout,
+                             node: &xml::Node,
+                             mut position: Position<f32>) -> Position<f32> {
+    let hyperlink_idx = wp::append_child(parent, wp::Node::new(wp::NodeData::Hyperlink(Default::default())));
+    let hyperlink = &mut parent.nth_child_mut(hyperlink_idx);
+
+    for child in node.children() {
+        match child.tag_name().name() {
+            "bookmarkStart" | "bookmarkEnd" => process_bookmark_element(context, hyperlink, &child, position),
+            "commentRangeStart" | "commentRangeEnd" => process_comment_range_element(context, hyperlink, &child, position),
+
+            "ins" | "del" | "moveFrom" | "moveTo" => {
+                position = process_revision_element(context, hyperlink, line_layout, &child, position);
+            }
+
+            // Text Run
+            "r" => position = process_text_run_element(context, hyperlink, line_layout, &child, position),
+
+            _ => (),
+        }
+
+        hyperlink.update_page_last();
+    }
+
+    let anchor = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "anchor"));
+    if let wp::NodeData::Hyperlink(hyperlink) = &mut hyperlink.data {
+        hyperlink.anchor = anchor.map(String::from);
+    }
+
+    // The anchor of a link to outside the document is the fragment of the
+    // URL, and "_top" is the start of the document.
+    if let Some(anchor) = anchor.filter(|anchor| *anchor != "_top" && !node.has_attribute((XMLNS_RELATIONSHIPS, "id"))) {
+        if !context.bookmarks.contains_key(anchor) {
+            context.diagnostics.push(Diagnostic::warning("w:hyperlink", format!("link to unknown bookmark: \"{}\"", anchor)));
+        }
+    }
+
+    if let Some(relationship_id) = node.attribute((XMLNS_RELATIONSHIPS, "id")) {
+        if let Some(relationship) = context.document_relationships.find(relationship_id) {
+            if let wp::NodeData::Hyperlink(hyperlink) = &mut hyperlink.data {
+                hyperlink.relationship = Some(relationship.clone());
+            }
+        } else {
+            context.diagnostics.push(Diagnostic::warning("w:hyperlink",
+                format!("relationship not found: \"{}\" (out of {} relationship(s))", relationship_id, context.document_relationships.len())));
+        }
+    } else {
+        // println!("[WARNING] <w:hyperlink> doesn't have an r:id attribute!");
+    }
+
+    position
+}
+
+/// 17.13.5.18 ins, 17.13.5.14 del, 17.13.5.22 moveFrom, 17.13.5.25 moveTo
+/// Lays out the runs of a tracked change, when the revision view of the
+/// document shows them. Otherwise, the markup is kept for saving.
+fn process_revision_element(context: &mut Context,
+                            parent: &mut Node,
+                            line_layout: &mut wp::layout::LineLayout,
+                            node: &xml::Node,
+                            mut position: Position<f32>) -> Position<f32> {
+    let Some(mut revision) = wp::revisions::Revision::from_element(node) else {
+        return position;
+    };
+
+    if !context.document.revision_view.shows(revision.kind) {
+        revision.hidden_markup = Some(Rc::from(&node.document().input_text()[node.range()]));
+        wp::create_child(parent, wp::NodeData::Revision(revision));
+        return position;
+    }
+
+    let revision_idx = wp::create_child(parent, wp::NodeData::Revision(revision));
+    let revision = parent.nth_child_mut(revision_idx);
+
+    for child in node.children() {
+        match child.tag_name().name() {
+            "bookmarkStart" | "bookmarkEnd" => process_bookmark_element(context, revision, &child, position),
+            "commentRangeStart" | "commentRangeEnd" => process_comment_range_element(context, revision, &child, position),
+
+            // Text Run
+            "r" => position = process_text_run_element(context, revision, line_layout, &child, position),
+
+            _ => (),
+        }
+
+        revision.update_page_last();
+    }
+
+    position
+}
+
+/// 17.13.6.2 bookmarkStart, 17.13.6.1 bookmarkEnd
+/// Marks the start or end of a bookmark at the position, so links to the
+/// bookmark can scroll to it.
+fn process_bookmark_element(context: &mut Context, parent: &mut Node, node: &xml::Node, position: Position<f32>) {
+    let is_start = node.tag_name().name() == "bookmarkStart";
+    let Some(id) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")) else {
+        let source = if is_start { "w:bookmarkStart" } else { "w:bookmarkEnd" };
+        context.diagnostics.push(Diagnostic::warning(source, String::from("missing id")));
+        return;
+    };
+
+    let data = if is_start {
+        wp::NodeData::BookmarkStart(wp::Bookmark {
+            id: String::from(id),
+            name: String::from(node.attribute((WORD_PROCESSING_XML_NAMESPACE, "name")).unwrap_or_default()),
+        })
+    } else {
+        wp::NodeData::BookmarkEnd(String::from(id))
+    };
+
+    let bookmark = wp::create_child(parent, data);
+    parent.nth_child_mut(bookmark).position = position;
+}
+
+/// 17.13.4.4 commentRangeStart, 17.13.4.3 commentRangeEnd
+/// Marks the start or end of the text a comment is about, which is
+/// highlighted when the document is painted.
+fn process_comment_range_element(context: &mut Context, parent: &mut Node, node: &xml::Node, position: Position<f32>) {
+    let is_start = node.tag_name().name() == "commentRangeStart";
+    let Some(id) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")) else {
+        let source = if is_start { "w:commentRangeStart" } else { "w:commentRangeEnd" };
+        context.diagnostics.push(Diagnostic::warning(source, String::from("missing id")));
+        return;
+    };
+
+    let data = if is_start {
+        wp::NodeData::CommentRangeStart(String::from(id))
+    } else {
+        wp::NodeData::CommentRangeEnd(String::from(id))
+    };
+
+    let range = wp::create_child(parent, data);
+    parent.nth_child_mut(range).position = position;
+}
+
+/// Information about the preceding paragraph, used for contextual spacing.
+struct PreviousParagraph {
+    style_id: Option<Rc<str>>,
+    contextual_spacing: bool,
+
+    /// The spacing that was added below the paragraph.
+    spacing_below: f32,
+}
+
+fn process_paragraph_element(context: &mut Context,
+                             parent: &mut Node,
+                             node: &xml::Node,
+                             mut original_position: Position<f32>,
+                             bounding_box: Option<Rect<f32>>,
+                             previous_paragraph: &mut Option<PreviousParagraph>) -> Position<f32> {
+    let paragraph_idx = wp::append_child(parent, wp::Node::new(wp::NodeData::Paragraph(wp::Paragraph {
+        has_revisions: has_revision_markers(node),
+        section_properties: find_paragraph_section_properties(*node)
+            .map(|properties| Rc::from(&properties.document().input_text()[properties.range()])),
+        ..Default::default()
+    })));
+    let paragraph = &mut parent.nth_child_mut(paragraph_idx);
+
+    //position.x = context.page_settings.margins.left as f32 * TWELFTEENTH_POINT;
+    let mut line_layout = wp::layout::LineLayout::new(&context.page_settings, original_position.y());
+
+    if let Some(bounding_box) = bounding_box {
+        line_layout.page_horizontal_start = bounding_box.left();
+        line_layout.page_horizontal_end = bounding_box.right();
+        line_layout.page_vertical_start = bounding_box.top();
+        line_layout.page_vertical_end = bounding_box.bottom();
+
+        line_layout.position_on_line = bounding_box.position();
+    } else {
+        line_layout.set_columns(context.columns.clone(), context.current_column);
+    }
+
+    paragraph.position = line_layout.position_on_line;
+    let mut position = line_layout.position_on_line;
+    //paragraph.borrow_mut().position = position;
+
+    if let Some(first_child) = node.first_child() {
+        // Paragraph Properties section 17.3.1.26
+        if first_child.tag_name().name() == "pPr" {
+            // println!("│  ├─ {}", first_child.tag_name().name());
+            process_paragraph_properties_element_for_paragraph(context, paragraph, &first_child);
+        }
+    }
+
+    line_layout.indentation_start = paragraph.text_settings.indentation_left.map(|indentation| indentation.get_pts()).unwrap_or(0.0);
+    line_layout.indentation_end = paragraph.text_settings.indentation_right.map(|indentation| indentation.get_pts()).unwrap_or(0.0);
+    line_layout.first_line_offset = paragraph.text_settings.first_line_offset_pts();
+    *line_layout.position_on_line.x_mut() = line_layout.first_line_start();
+    position = line_layout.position_on_line;
+
+    let family_name = paragraph.text_settings.font_family_name(&context.drawing_ml_style_settings);
+    let font_spec = paragraph.text_settings.font_specification(&family_name);
+
+    let contextual_spacing = paragraph.text_settings.contextual_spacing.unwrap_or(false);
+    let same_style_as_previous = match previous_paragraph {
+        Some(previous) => previous.style_id == paragraph.text_settings.paragraph_style_id,
+        None => false,
+    };
+
+    // The spacing below the previous paragraph was already added, so take it
+    // back when the previous paragraph ignores it.
+    if let Some(previous) = previous_paragraph {
+        if same_style_as_previous && previous.contextual_spacing && previous.spacing_below > 0.0 {
+            *original_position.y_mut() -= previous.spacing_below;
+            *line_layout.position_on_line.y_mut() -= previous.spacing_below;
+            paragraph.position = line_layout.position_on_line;
+            position = line_layout.position_on_line;
+        }
+    }
+
+    let spacing_above_paragraph = if same_style_as_previous && contextual_spacing {
+        0.0
+    } else {
+        calculate_paragraph_spacing(context, font_spec,
+            paragraph.text_settings.spacing_above_paragraph, paragraph.text_settings.spacing_above_paragraph_automatic)
+    };
+    if spacing_above_paragraph > 0.0 {
+        *line_layout.position_on_line.y_mut() += spacing_above_paragraph;
+        position = line_layout.position_on_line;
+    }
+
+    // The top border is above the text, separated by the spacing of the
+    // border, while the left and right borders are outside of the margins.
+    let borders = paragraph.text_settings.paragraph_borders.unwrap_or_default();
+    let border_top = line_layout.position_on_line.y();
+    if borders.top_extent() > 0.0 {
+        *line_layout.position_on_line.y_mut() += borders.top_extent();
+        position = line_layout.position_on_line;
+    }
+
+    {
+        if let Some(numbering) = paragraph.text_settings.numbering.clone() {
+            if let Some(node) = numbering.create_node(paragraph, &mut line_layout, context.text_calculator, &context.drawing_ml_style_settings, context.fallback_font, &mut context.diagnostics) {
+                *position.x_mut() += paragraph.nth_child_mut(node.0).nth_child_mut(node.1).size.width();
+
+                pub const NUMBERING_INDENTATION: f32 = 700.0 * TWELFTEENTH_POINT;
+
+                // With a hanging indentation, the text starts at the
+                // indentation of the other lines, when the number fits before
+                // it.
+                if paragraph.text_settings.indentation_left.is_some() && position.x() < line_layout.line_start() {
+                    *position.x_mut() = line_layout.line_start();
+                } else if paragraph.text_settings.indentation_left.is_some() {
+                    *position.x_mut() = paragraph.text_settings.indent_one(position.x(), true);
+                } else {
+                    *position.x_mut() = (position.x() / NUMBERING_INDENTATION + 1.0).floor() * NUMBERING_INDENTATION;
+                }
+            }
+        }
+    }
+
+    if let wp::NodeData::Paragraph(data) = &mut paragraph.data {
+        data.text_start = position;
+        data.line_bounds = Rect::from_positions(line_layout.line_start(), line_layout.line_end(),
+            line_layout.page_vertical_start, line_layout.page_vertical_end);
+    }
+
+    for child in node.children() {
+        // println!("│  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "bookmarkStart" | "bookmarkEnd" => process_bookmark_element(context, paragraph, &child, position),
+            "commentRangeStart" | "commentRangeEnd" => process_comment_range_element(context, paragraph, &child, position),
+
+            // 17.16.22 hyperlink (Hyperlink)
+            "hyperlink" => {
+                position = process_hyperlink_element(context, paragraph, &mut line_layout, &child, position);
+            }
+
+            // 17.16.19 fldSimple (Simple Field)
+            "fldSimple" => {
+                position = process_simple_field_element(context, paragraph, &mut line_layout, &child, position);
+            }
+
+            "ins" | "del" | "moveFrom" | "moveTo" => {
+                position = process_revision_element(context, paragraph, &mut line_layout, &child, position);
+            }
+
+            // Text Run
+            "r" => {
+                position = process_text_run_element(context, paragraph, &mut line_layout, &child, position);
+            }
+
+            // Structured Document Tag
+            "sdt" => {
+                position = process_structured_document_tag_non_block_level(context, paragraph, &child, original_position, StructuredDocumentTagLevel::Inline, &mut line_layout);
+            }
+
+            _ => ()
+        }
+
+        paragraph.update_page_last();
+    }
+
+    finish_paragraph_lines(paragraph, &line_layout);
+
+    // The cursor is probably somewhere in the middle of the line.
+    // We should put it at the next line.
+    //
+    // NOTE: This isn't line/paragraph spacing; see below.
+    if position != original_position {
+        //position.y += text.global_bounds().height;
+        // TODO?
+    }
+
+    let line_height;
+    if line_layout.line_height() > 0.0 {
+        line_height = line_layout.line_height();
+    } else {
+        line_height = calculate_font_line_height(context.text_calculator, font_spec, context.fallback_font);
+    }
+
+    // The last line is advanced by the line spacing of the paragraph too, so
+    // e.g. double spaced paragraphs are twice as far apart.
+    let line_spacing = paragraph.text_settings.line_advance(line_height);
+
+    let paragraph_spacing = calculate_paragraph_spacing(context, font_spec,
+        paragraph.text_settings.spacing_below_paragraph, paragraph.text_settings.spacing_below_paragraph_automatic);
+
+    assert!(line_spacing >= 0.0);
+    assert!(paragraph_spacing >= 0.0);
+
+    // println!("│  ├─ Advancing {}  +  {}", line_spacing, paragraph_spacing);
+    *position.y_mut() += line_spacing + borders.bottom_extent();
+
+    let border_rect = Rect::from_positions(
+        line_layout.line_start().min(line_layout.first_line_start()) - borders.left_extent() - paragraph.position.x(),
+        line_layout.line_end() + borders.right_extent() - paragraph.position.x(),
+        border_top - paragraph.position.y(),
+        position.y() - paragraph.position.y(),
+    );
+    if let wp::NodeData::Paragraph(data) = &mut paragraph.data {
+        data.borders = borders;
+        data.border_rect = border_rect;
+    }
+
+    *position.y_mut() += paragraph_spacing;
+
+    let diff = position - original_position;
+    paragraph.size = diff.into();
+
+    *previous_paragraph = Some(PreviousParagraph {
+        style_id: paragraph.text_settings.paragraph_style_id.clone(),
+        contextual_spacing,
+        spacing_below: paragraph_spacing,
+    });
+
+    if bounding_box.is_none() {
+        context.current_column = line_layout.column();
+    }
+
+    Position::new(original_position.x(), position.y())
+}
+
+/// Aligns the lines of the paragraph after its text was laid out. The lines
+/// are justified and reordered before their baselines are aligned, since that
+/// moves the text parts of a line to different vertical positions.
+fn finish_paragraph_lines(paragraph: &mut Node, line_layout: &LineLayout) {
+    match paragraph.text_settings.justify {
+        Some(TextJustification::Both) => justify_lines(paragraph, line_layout),
+        Some(TextJustification::Distribute) => distribute_lines(paragraph, line_layout),
+        Some(justification @ (TextJustification::Center | TextJustification::End)) => align_lines(paragraph, line_layout, justification),
+        Some(TextJustification::Start) | None => (),
+    }
+
+    reorder_bidi_lines(paragraph, line_layout);
+    align_baselines(paragraph, line_layout);
+}
+
+/// Lays out the text of an edited paragraph again, in the area it was laid
+/// out in originally. The text parts of every run are merged and broken into
+/// lines again. The content after the paragraph isn't moved, so the change in
+/// the height of the text is returned, which is negative when the paragraph
+/// became shorter.
+///
+/// Only paragraphs for which [wp::editing::is_editable] holds can be laid out
+/// again, since e.g. tabs don't leave a node behind.
+pub fn relayout_paragraph(paragraph: &mut Node, page_settings: &PageSettings, text_calculator: &mut dyn TextCalculator,
+                          fallback_font: &str) -> f32 {
+    let wp::NodeData::Paragraph(data) = &paragraph.data else {
+        return 0.0;
+    };
+    let (text_start, line_bounds) = (data.text_start, data.line_bounds);
+
+    let text_bottom = |paragraph: &Node| paragraph.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)))
+        .iter()
+        .map(|node| node.position.y() + node.size.height())
+        .fold(text_start.y(), f32::max);
+    let previous_bottom = text_bottom(paragraph);
+
+    let mut line_layout = LineLayout::new(page_settings, text_start.y());
+    line_layout.page_horizontal_start = line_bounds.left;
+    line_layout.page_horizontal_end = line_bounds.right;
+    line_layout.page_vertical_start = line_bounds.top;
+    line_layout.page_vertical_end = line_bounds.bottom;
+    line_layout.position_on_line = text_start;
+
+    // The fonts of the runs were resolved by the first layout, so the theme
+    // isn't needed anymore.
+    let theme = drawing_ml::style::StyleSettings::default();
+
+    let mut page = paragraph.page_first;
+    let mut position = text_start;
+    relayout_text_runs(paragraph, &mut line_layout, text_calculator, &theme, fallback_font, &mut page, &mut position);
+    paragraph.page_last = page;
+
+    finish_paragraph_lines(paragraph, &line_layout);
+
+    let height_change = text_bottom(paragraph) - previous_bottom;
+    paragraph.size = Size::new(paragraph.size.width(), paragraph.size.height() + height_change);
+    if let wp::NodeData::Paragraph(data) = &mut paragraph.data {
+        data.border_rect.bottom += height_change;
+    }
+    height_change
+}
+
+fn relayout_text_runs(node: &mut Node, line_layout: &mut LineLayout, text_calculator: &mut dyn TextCalculator,
+                      theme: &drawing_ml::style::StyleSettings, fallback_font: &str, page: &mut usize, position: &mut Position<f32>) {
+    for child in &mut node.children {
+        match child.data {
+            wp::NodeData::TextRun(..) => {
+                let text = wp::editing::run_text(child);
+
+                if child.text_settings.font.is_none() {
+                    child.text_settings.font = child.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)))
+                        .into_iter()
+                        .find_map(|part| part.text_settings.font.clone());
+                }
+
+                child.children.clear();
+                child.page_first = *page;
+                child.page_last = *page;
+
+                if text.is_empty() {
+                    append_empty_text_part(child, line_layout, text_calculator, theme, fallback_font, *position);
+                } else {
+                    *position = process_text_element_text(child, line_layout, text_calculator, &text, theme, fallback_font, *position);
+                    *page = child.page_last;
+                }
+            }
+            wp::NodeData::Hyperlink(..) => {
+                relayout_text_runs(child, line_layout, text_calculator, theme, fallback_font, page, position);
+                child.page_last = *page;
+            }
+            _ => ()
+        }
+    }
+}
+
+/// Moves the text parts of the paragraph down so that the text on each line
+/// shares the baseline of the text with the largest ascent on that line, e.g.
+/// when a line mixes font sizes.
+fn align_baselines(paragraph: &mut Node, line_layout: &LineLayout) {
+    let mut line_ascents = HashMap::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        if let wp::NodeData::TextPart(part) = &node.data {
+            let line_ascent = line_ascents.entry(line_key(node, line_layout)).or_insert(0.0f32);
+            *line_ascent = line_ascent.max(part.ascent);
+        }
+    });
+
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        if let wp::NodeData::TextPart(part) = &node.data {
+            *node.position.y_mut() += line_ascents[&line_key(node, line_layout)] - part.ascent;
+        }
+    });
+}
+
+/// Moves the lines of the paragraph to the center or to the end of the line
+/// (jc=center and jc=end), since their text is laid out from the start.
+fn align_lines(paragraph: &mut Node, line_layout: &LineLayout, justification: TextJustification) {
+    let mut line_ends: HashMap<LineKey, f32> = HashMap::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        let line_end = line_ends.entry(line_key(node, line_layout)).or_insert(f32::MIN);
+        *line_end = line_end.max(node.position.x() + node.size.width());
+    });
+
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        let key = line_key(node, line_layout);
+        let remaining = (f32::from_bits(key.2) - line_ends[&key]).max(0.0);
+        *node.position.x_mut() += match justification {
+            TextJustification::Center => remaining / 2.0,
+            _ => remaining,
+        };
+    });
+}
+
+/// Reorders the text parts of every line of the paragraph to their visual
+/// order, when the paragraph is right-to-left or contains right-to-left text.
+fn reorder_bidi_lines(paragraph: &mut Node, line_layout: &LineLayout) {
+    let paragraph_level = wp::bidi::paragraph_level(&paragraph.text_settings);
+
+    // The text parts are visited in document order, which is their logical
+    // order on the line.
+    let mut lines: HashMap<LineKey, Vec<wp::bidi::LinePart>> = HashMap::new();
+    let mut keys = Vec::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        if let wp::NodeData::TextPart(part) = &node.data {
+            let key = line_key(node, line_layout);
+            lines.entry(key).or_default().push(wp::bidi::LinePart {
+                level: part.bidi_level,
+                left: node.position.x(),
+                width: node.size.width(),
+                is_whitespace: part.text.trim().is_empty(),
+            });
+            keys.push(key);
+        }
+    });
+
+    if paragraph_level == 0 && lines.values().flatten().all(|part| part.level == 0) {
+        return;
+    }
+
+    for parts in lines.values_mut() {
+        let (line_start, line_end) = line_layout.column_bounds_at(parts[0].left);
+        wp::bidi::reorder_line(parts, paragraph_level, line_start, line_end);
+    }
+
+    let mut keys = keys.into_iter();
+    let mut next_indices: HashMap<LineKey, usize> = HashMap::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        let Some(key) = keys.next() else {
+            return;
+        };
+
+        let index = next_indices.entry(key).or_insert(0);
+        *node.position.x_mut() = lines[&key][*index].left;
+        *index += 1;
+    });
+}
+
+/// Stretches every line of the paragraph to the end of the line by spacing
+/// out the characters of the text parts on that line.
+fn distribute_lines(paragraph: &mut Node, line_layout: &LineLayout) {
+    struct Line {
+        right: f32,
+        character_count: usize,
+    }
+
+    let mut lines: HashMap<LineKey, Line> = HashMap::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        if let wp::NodeData::TextPart(part) = &node.data {
+            let line = lines.entry(line_key(node, line_layout)).or_insert(Line { right: 0.0, character_count: 0 });
+            line.right = line.right.max(node.position.x() + node.size.width());
+            line.character_count += part.text.graphemes(true).count();
+        }
+    });
+
+    // The text parts are visited in document order, so the offset of a text
+    // part is the spacing added to the text parts before it on its line.
+    let mut offsets: HashMap<LineKey, f32> = HashMap::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        let key = line_key(node, line_layout);
+        let line = &lines[&key];
+        let line_end = f32::from_bits(key.2);
+        if line.character_count < 2 || line.right >= line_end {
+            return;
+        }
+
+        let spacing = (line_end - line.right) / (line.character_count - 1) as f32;
+        if let wp::NodeData::TextPart(part) = &mut node.data {
+            part.character_spacing += spacing;
+            for (index, grapheme_end) in part.grapheme_ends.iter_mut().enumerate() {
+                *grapheme_end += spacing * (index + 1) as f32;
+            }
+
+            let offset = offsets.entry(key).or_insert(0.0);
+            let added_width = spacing * part.text.graphemes(true).count() as f32;
+            *node.position.x_mut() += *offset;
+            node.size = Size::new(node.size.width() + added_width, node.size.height());
+            *offset += added_width;
+        }
+    });
+}
+
+/// Stretches the lines of the paragraph, except the last, to the end of the
+/// line by widening the spaces between the words (jc=both). The spaces before the
+/// first word and after the last word of a line aren't widened.
+fn justify_lines(paragraph: &mut Node, line_layout: &LineLayout) {
+    #[derive(Default)]
+    struct Line {
+        /// The end of the last word on the line.
+        right: f32,
+        gap_count: usize,
+
+        /// The spaces seen since the last word, which are only gaps when
+        /// another word follows.
+        pending_spaces: usize,
+        has_words: bool,
+    }
+
+    let mut lines: HashMap<LineKey, Line> = HashMap::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        if let wp::NodeData::TextPart(part) = &node.data {
+            let line = lines.entry(line_key(node, line_layout)).or_default();
+            for (grapheme, grapheme_end) in part.text.graphemes(true).zip(&part.grapheme_ends) {
+                if grapheme.trim().is_empty() {
+                    line.pending_spaces += line.has_words as usize;
+                    continue;
+                }
+
+                line.gap_count += line.pending_spaces;
+                line.pending_spaces = 0;
+                line.has_words = true;
+                line.right = line.right.max(node.position.x() + grapheme_end);
+            }
+        }
+    });
+
+    // The last line is at the bottom of the last column of the last page.
+    let last_line = lines.keys()
+        .max_by(|a, b| a.0.cmp(&b.0)
+            .then(f32::from_bits(a.2).total_cmp(&f32::from_bits(b.2)))
+            .then(f32::from_bits(a.1).total_cmp(&f32::from_bits(b.1))))
+        .copied();
+
+    // The text parts are visited in document order, so the offset of a text
+    // part is the spacing added to the text parts before it on its line.
+    #[derive(Default)]
+    struct Progress {
+        offset: f32,
+        widened_gaps: usize,
+        has_words: bool,
+    }
+
+    let mut progress: HashMap<LineKey, Progress> = HashMap::new();
+    for_each_text_part_of_paragraph(paragraph, &mut |node| {
+        let key = line_key(node, line_layout);
+        let line = &lines[&key];
+        let line_end = f32::from_bits(key.2);
+        if Some(key) == last_line || line.gap_count == 0 || line.right >= line_end {
+            return;
+        }
+
+        let spacing = (line_end - line.right) / line.gap_count as f32;
+        if let wp::NodeData::TextPart(part) = &mut node.data {
+            let progress = progress.entry(key).or_default();
+            let mut added_width = 0.0;
+            for (grapheme, grapheme_end) in part.text.graphemes(true).zip(part.grapheme_ends.iter_mut()) {
+                if !grapheme.trim().is_empty() {
+                    progress.has_words = true;
+                } else if progress.has_words && progress.widened_gaps < line.gap_count {
+                    added_width += spacing;
+                    progress.widened_gaps += 1;
+                }
+                *grapheme_end += added_width;
+            }
+
+            if added_width > 0.0 {
+                part.word_spacing = spacing;
+            }
+            *node.position.x_mut() += progress.offset;
+            node.size = Size::new(node.size.width() + added_width, node.size.height());
+            progress.offset += added_width;
+        }
+    });
+}
+
+/// The page, top position and end of a line, as bits so it can be hashed.
+type LineKey = (usize, u32, u32);
+
+/// Text parts on the same line have the same page and top position, since
+/// they're only moved down when the line wraps, and are in the same column.
+fn line_key(node: &Node, line_layout: &LineLayout) -> LineKey {
+    (node.page_first, node.position.y().to_bits(), line_layout.line_end_at(node.position.x()).to_bits())
+}
+
+/// Calls the `callback` for the text parts of the paragraph, excluding those
+/// of nested paragraphs (e.g. in text boxes), which are aligned on their own.
+fn for_each_text_part_of_paragraph(node: &mut Node, callback: &mut dyn FnMut(&mut Node)) {
+    for child in &mut node.children {
+        match child.data {
+            wp::NodeData::Paragraph(..) => (),
+            wp::NodeData::TextPart(..) => callback(child),
+            _ => for_each_text_part_of_paragraph(child, callback),
+        }
+    }
+}
+
+/// The elements marking tracked changes (17.13.5), which make Word draw a
+/// change bar next to the paragraph.
+const REVISION_ELEMENTS: &[&str] = &["ins", "del", "moveFrom", "moveTo", "pPrChange", "rPrChange"];
+
+/// Checks if the paragraph (or its paragraph mark) contains revisions.
+fn has_revision_markers(paragraph: &xml::Node) -> bool {
+    paragraph.descendants().any(|descendant| {
+        descendant.tag_name().namespace() == Some(WORD_PROCESSING_XML_NAMESPACE)
+            && REVISION_ELEMENTS.contains(&descendant.tag_name().name())
+    })
+}
+
+/// The height of an empty line using the given font, or the fallback font
+/// when the font isn't available.
+fn calculate_font_line_height(text_calculator: &mut dyn TextCalculator, font_spec: FontSpecification, fallback_font: &str) -> f32 {
+    match text_calculator.font_metrics(font_spec) {
+        Ok(metrics) => metrics.line_height(),
+        Err(..) => {
+            let font_spec = FontSpecification::new(fallback_font, font_spec.size(), font_spec.weight()).with_italic(font_spec.is_italic());
+            text_calculator.font_metrics(font_spec).unwrap().line_height()
+        }
+    }
+}
+
+/// Calculates the spacing above or below a paragraph. When automatic spacing
+/// is requested (e.g. `beforeAutospacing`), the explicit value is ignored and
+/// the spacing is a line of the paragraph's font instead, or a fixed amount
+/// when the compatibility settings ask for it.
+fn calculate_paragraph_spacing(context: &mut Context, font_spec: FontSpecification,
+                               spacing: Option<TwelfteenthPoint<u32>>, automatic: Option<bool>) -> f32 {
+    if automatic == Some(true) {
+        if context.document.settings.compatibility.fixed_automatic_spacing {
+            return wp::settings::FIXED_AUTOMATIC_SPACING;
+        }
+        return calculate_font_line_height(context.text_calculator, font_spec, context.fallback_font);
+    }
+
+    spacing.unwrap_or(TwelfteenthPoint(0)).get_pts()
+}
+
+/// Parses a value of the ST_OnOff simple type (17.17.4) of the `source`
+/// element.
+pub fn parse_on_off(value: &str, source: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<bool> {
+    match value {
+        "1" | "on" | "true" => Some(true),
+        "0" | "off" | "false" => Some(false),
+        _ => {
+            diagnostics.push(Diagnostic::error(source, format!("invalid ST_OnOff value: \"{}\"", value)));
+            None
+        }
+    }
+}
+
+/// Parses the `w:val` attribute of the `source` element, reporting it when
+/// it's missing or malformed.
+pub(crate) fn parse_val_attribute<T: std::str::FromStr>(node: &xml::Node, source: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<T> {
+    let Some(val) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) else {
+        diagnostics.push(Diagnostic::error(source, String::from("missing w:val attribute")));
+        return None;
+    };
+
+    let value = val.parse().ok();
+    if value.is_none() {
+        diagnostics.push(Diagnostic::error(source, format!("invalid value: \"{}\"", val)));
+    }
+    value
+}
+
+// pPr
+pub fn process_paragraph_properties_element(numbering_manager: &numbering::NumberingManager, style_manager: &StyleManager,
+                                            paragraph_text_settings: &mut text_settings::TextSettings, node: &xml::Node,
+                                            diagnostics: &mut Vec<Diagnostic>) {
+    for property in node.children() {
+        match property.tag_name().name() {
+            // 17.3.1.6 bidi (Right to Left Paragraph Layout)
+            "bidi" => {
+                paragraph_text_settings.bidi = match property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                    Some(val) => parse_on_off(val, "w:bidi", diagnostics),
+                    None => Some(true),
+                };
+            }
+
+            // 17.3.1.9 contextualSpacing (Ignore Spacing Above and Below When
+            // Using Identical Styles)
+            "contextualSpacing" => {
+                paragraph_text_settings.contextual_spacing = match property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                    Some(val) => parse_on_off(val, "w:contextualSpacing", diagnostics),
+                    None => Some(true),
+                };
+            }
+
+            "ind" => paragraph_text_settings.parse_element_ind(&property),
+
+            // 17.3.1.24 pBdr (Paragraph Borders)
+            "pBdr" => {
+                match text_settings::ParagraphBorders::from_xml(&property) {
+                    Ok(borders) => paragraph_text_settings.paragraph_borders = Some(borders),
+                    Err(e) => diagnostics.push(Diagnostic::error("w:pBdr", format!("invalid borders: {:?}", e))),
+                }
+            }
+
+            // 17.3.1.38 tabs (Set of Custom Tab Stops)
+            "tabs" => paragraph_text_settings.parse_element_tabs(&property),
+
+            // 17.3.1.13 jc (Paragraph Alignment)
+            "jc" => {
+                let Some(val) = property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) else {
+                    diagnostics.push(Diagnostic::error("w:jc", String::from("missing w:val attribute")));
+                    continue;
+                };
+                match val {
+                    "start" => paragraph_text_settings.justify = Some(TextJustification::Start),
+
+                    "center" => paragraph_text_settings.justify = Some(TextJustification::Center),
+
+                    // TODO I can't find the "right" value to be valid in the
+                    // ECMA Specification, but Microsoft Word seams to be using
+                    // this property anyway, so I inserted the quirk below.
+                    "end" | "right" => paragraph_text_settings.justify = Some(TextJustification::End),
+
+                    "both" => paragraph_text_settings.justify = Some(TextJustification::Both),
+
+                    // The Thai variant distributes using Thai-specific rules,
+                    // but distributing between the characters is close enough.
+                    "distribute" | "thaiDistribute" => paragraph_text_settings.justify = Some(TextJustification::Distribute),
+                    _ => {
+                        // println!("│  │  │  ├─ E: Unknown Attribute Value");
+                    }
+                }
+            }
+
+            // 17.3.1.20 outlineLvl (Associated Outline Level)
+            "outlineLvl" => {
+                let Some(val) = property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) else {
+                    diagnostics.push(Diagnostic::error("w:outlineLvl", String::from("missing w:val attribute")));
+                    continue;
+                };
+                match val.parse::<u8>() {
+                    Ok(level) if level <= text_settings::OUTLINE_LEVEL_BODY_TEXT => paragraph_text_settings.outline_level = Some(level),
+                    _ => diagnostics.push(Diagnostic::error("w:outlineLvl", format!("invalid outline level: \"{}\"", val))),
+                }
+            }
+
+            "numPr" => process_numbering_definition_instance_reference_property(numbering_manager, &property, paragraph_text_settings, diagnostics),
+
+            // Paragraph Style
+            "pStyle" => {
+                let Some(style_id) = property.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) else {
+                    diagnostics.push(Diagnostic::error("w:pStyle", String::from("missing w:val attribute")));
+                    continue;
+                };
+                style_manager.apply_paragraph_style(style_id, paragraph_text_settings, diagnostics);
+                paragraph_text_settings.paragraph_style_id = Some(Rc::from(style_id));
+            }
+
+            // Run Properties section 17.3.2.28
+            "rPr" => {
+                //apply_run_properties_for_paragraph_mark(&property, paragraph_text_settings);
+            }
+
+            "spacing" => {
+                for attribute in property.attributes() {
+                    // println!("│  │  │  ├─ Spacing Attribute: {} = {}", attribute.name(), attribute.value());
+                    match attribute.name() {
+                        "after" => match attribute.value().parse() {
+                            Ok(value) => paragraph_text_settings.spacing_below_paragraph = Some(TwelfteenthPoint(value)),
+                            Err(..) => diagnostics.push(Diagnostic::error("w:spacing", format!("invalid 'after' value: \"{}\"", attribute.value()))),
+                        }
+                        "afterAutospacing" => {
+                            paragraph_text_settings.spacing_below_paragraph_automatic = parse_on_off(attribute.value(), "w:spacing", diagnostics);
+                        }
+                        "before" => match attribute.value().parse() {
+                            Ok(value) => paragraph_text_settings.spacing_above_paragraph = Some(TwelfteenthPoint(value)),
+                            Err(..) => diagnostics.push(Diagnostic::error("w:spacing", format!("invalid 'before' value: \"{}\"", attribute.value()))),
+                        }
+                        "beforeAutospacing" => {
+                            paragraph_text_settings.spacing_above_paragraph_automatic = parse_on_off(attribute.value(), "w:spacing", diagnostics);
+                        }
+                        _ => ()
+                    }
+                }
+
+                if let Some(line_spacing) = parse_line_spacing(&property, diagnostics) {
+                    paragraph_text_settings.line_spacing = Some(line_spacing);
+                }
+            }
+            _ => ()
+        }
+    }
+}
+
+/// Parses the `w:line` and `w:lineRule` attributes of the <w:spacing>
+/// element. When the rule is `auto` (or absent), the line is in 240ths of a
+/// line, otherwise it's in twentieths of a point.
+fn parse_line_spacing(node: &xml::Node, diagnostics: &mut Vec<Diagnostic>) -> Option<text_settings::LineSpacing> {
+    let line = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "line"))?;
+    let Ok(line) = line.parse::<u32>() else {
+        diagnostics.push(Diagnostic::error("w:spacing", format!("invalid 'line' value: \"{}\"", line)));
+        return None;
+    };
+
+    match node.attribute((WORD_PROCESSING_XML_NAMESPACE, "lineRule")).unwrap_or("auto") {
+        "auto" => Some(text_settings::LineSpacing::Auto(line as f32 / AUTO_LINE_SPACING_SINGLE)),
+        "exact" => Some(text_settings::LineSpacing::Exact(TwelfteenthPoint(line))),
+        "atLeast" => Some(text_settings::LineSpacing::AtLeast(TwelfteenthPoint(line))),
+        rule => {
+            diagnostics.push(Diagnostic::error("w:spacing", format!("invalid 'lineRule' value: \"{}\"", rule)));
+            None
+        }
+    }
+}
+
+fn process_paragraph_properties_element_for_paragraph(context: &mut Context, paragraph: &mut Node, node: &xml::Node) {
+    process_paragraph_properties_element(&context.numbering_manager, context.style_manager, &mut paragraph.text_settings, node, &mut context.diagnostics);
+}
+
+// 17.3.1.19 numPr (Numbering Definition Instance Reference)
+fn process_numbering_definition_instance_reference_property(numbering_manager: &wp::numbering::NumberingManager, node: &xml::Node,
+        text_settings: &mut text_settings::TextSettings, diagnostics: &mut Vec<Diagnostic>) {
+    let mut numbering = Numbering{
+        definition: None,
+        level: None,
+    };
+
+    for child in node.children().filter(xml::Node::is_element) {
+        match child.tag_name().name() {
+            // 17.9.3 ilvl (Numbering Level Reference)
+            "ilvl" => numbering.level = parse_val_attribute(&child, "w:ilvl", diagnostics),
+
+            // 17.9.18 numId (Numbering Definition Instance Reference)
+            "numId" => {
+                let Some(instance_id) = parse_val_attribute(&child, "w:numId", diagnostics) else {
+                    continue;
+                };
+
+                numbering.definition = numbering_manager.find_definition_instance(instance_id);
+                if numbering.definition.is_none() {
+                    diagnostics.push(Diagnostic::warning("w:numId", format!("no numbering definition found for instance id {}", instance_id)));
+                }
+            }
+
+            _ => diagnostics.push(Diagnostic::warning("w:numPr", format!("unknown element: {}", child.tag_name().name()))),
+        }
+    }
+
+    //assert_eq!(numbering.definition.is_some(), numbering.level.is_some(), "Both should be None or both be Some");
+
+    text_settings.numbering = Some(numbering);
+}
+
+/// Process the <w:docPartObj> element
+/// This element in a child of the <w:sdtPr> elemennt
+fn process_sdt_built_in_doc_part(context: &mut Context, parent: &mut Node, node: &xml::Node) {
+
+    for child in node.children() {
+        // println!("│  │  │  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "docPartGallery" => process_sdt_document_part_gallery_filter(context, parent, &child),
+            _ => ()
+        }
+    }
+}
+
+/// Process the w:docPartGallery
+fn process_sdt_document_part_gallery_filter(context: &mut Context, _parent: &mut Node, node: &xml::Node) {
+    for attr in node.attributes() {
+        context.diagnostics.push(Diagnostic::info("w:docPartGallery",
+            format!("ignored attribute \"{}\" => \"{}\" in namespace \"{}\"", attr.name(), attr.value(), attr.namespace().unwrap_or(""))));
+    }
+}
+
+/// Process the <w:sdtPr> element
+fn process_std_properties(context: &mut Context, parent: &mut Node, node: &xml::Node) {
+    for child in node.children() {
+        // println!("│  │  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "docPartObj" => process_sdt_built_in_doc_part(context, parent, &child),
+            _ => ()
+        }
+    }
+}
+
+/// Process the <w:sdtEndPr> element
+fn process_sdt_end_character_properties(_context: &mut Context, _parent: &mut Node, node: &xml::Node) {
+    for _child in node.children() {
+        // println!("│  │  ├─ {}", child.tag_name().name());
+    }
+}
+
+/// Process the <w:sdtContent> element
+fn process_sdt_content_block_level(context: &mut Context, parent: &mut Node, node: &xml::Node, original_position: Position<f32>) -> Position<f32> {
+    let mut position = original_position;
+
+    for child in node.children() {
+        // println!("│  │  ├─ {}", child.tag_name().name());
+        match child.tag_name().name() {
+            "p" => position = process_paragraph_element(context, parent, &child, position, None, &mut None),
+            _ => ()
+        }
+    }
+
+    position
+}
+
+/// Process the <w:sdtContent> element
+fn process_sdt_content_non_block_level(context: &mut Context, parent: &mut Node, node: &xml::Node, original_position: Position<f32>,
+        line_layout: &mut LineLayout) -> Position<f32> {
+    let mut position = original_position;
+
+    for child in node.children() {
+        match child.tag_name().name() {
+            "r" => position = process_text_run_element(context, parent, line_layout, &child, position),
+            "p" => position = process_paragraph_element(context, parent, &child, position, None, &mut None),
+            _ => context.diagnostics.push(Diagnostic::warning("w:sdtContent", format!("unknown element (non-block): {}", child.tag_name().name()))),
+        }
+    }
+
+    position
+}
+
+/// Process the <w:sdt> element
+/// 17.5.2 Structured Document Tags
+fn process_structured_document_tag_block_level(context: &mut Context, parent: &mut Node, node: &xml::Node, original_position: Position<f32>) -> Position<f32> {
+    let mut position = original_position;
+
+    let sdt = wp::append_child(parent,
+        wp::Node::new(
+            wp::NodeData::StructuredDocumentTag(
+                StructuredDocumentTag{
+                    level: StructuredDocumentTagLevel::Block,
+                }
+            )
+        )
+    );
+    let sdt = parent.nth_child_mut(sdt);
+
+    for child in node.children().filter(xml::Node::is_element) {
+        // println!("│  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "sdtContent" => position = process_sdt_content_block_level(context, sdt, &child, original_position),
+            "sdtEndPr" => process_sdt_end_character_properties(context, sdt, &child),
+            "sdtPr" => process_std_properties(context, sdt, &child),
+            _ => context.diagnostics.push(Diagnostic::warning("w:sdt", format!("unknown element: {}", child.tag_name().name()))),
+        }
+
+        sdt.check_last_page_number_from_new_child();
+    }
+
+    position
+}
+
+/// Process the <w:sdt> element
+/// 17.5.2 Structured Document Tags
+fn process_structured_document_tag_non_block_level(context: &mut Context,
+        parent: &mut Node, node: &xml::Node, original_position: Position<f32>,
+        level: StructuredDocumentTagLevel, line_layout: &mut LineLayout) -> Position<f32> {
+    let mut position = original_position;
+
+    let sdt = wp::append_child(parent,
+        wp::Node::new(
+            wp::NodeData::StructuredDocumentTag(
+                StructuredDocumentTag{
+                    level
+                }
+            )
+        )
+    );
+    let sdt = parent.nth_child_mut(sdt);
+
+    for child in node.children().filter(xml::Node::is_element) {
+        // println!("│  ├─ {}", child.tag_name().name());
+
+        match child.tag_name().name() {
+            "sdtContent" => position = process_sdt_content_non_block_level(context, sdt, &child, original_position, line_layout),
+            "sdtEndPr" => process_sdt_end_character_properties(context, sdt, &child),
+            "sdtPr" => process_std_properties(context, sdt, &child),
+            _ => context.diagnostics.push(Diagnostic::warning("w:sdt", format!("unknown element: {}", child.tag_name().name()))),
+        }
+
+        sdt.check_last_page_number_from_new_child();
+    }
+
+    position
+}
+
+/// The properties of a table that are needed to lay out its rows.
+struct TableLayout {
+    grid: TableGrid,
+    properties: TableProperties,
+
+    /// The table style, and which of its conditional formats the table uses.
+    style: Option<Rc<ResolvedStyle>>,
+    look: TableLook,
+
+    row_count: usize,
+}
+
+impl TableLayout {
+    /// The formatting the table style gives to the cell, if the table has a
+    /// style.
+    fn cell_formatting(&self, location: TableCellLocation) -> Option<TableConditionalFormatting> {
+        let style = self.style.as_ref()?;
+        Some(style.conditional_formatting(&self.look.conditional_formats(location, &self.properties)))
+    }
+}
+
+/// Processt the `<w:tbl>` element.
+fn process_table_element(context: &mut Context, parent: &mut Node, node: &xml::Node, original_position: Position<f32>) -> Position<f32> {
+    let mut position = original_position;
+
+    let grid = match node.children().find(|child| child.tag_name().name() == "tblGrid") {
+        Some(child) => TableGrid::from_xml(&child).unwrap_or_else(|e| {
+            context.diagnostics.push(Diagnostic::error("w:tblGrid", format!("invalid column width: {}", e)));
+            Default::default()
+        }),
+        None => Default::default(),
+    };
+
+    let properties_element = node.children().find(|child| child.tag_name().name() == "tblPr");
+
+    // 17.4.63 tblStyle (Referenced Table Style)
+    let style = properties_element
+        .and_then(|properties| properties.children().find(|child| child.tag_name().name() == "tblStyle"))
+        .and_then(|style| style.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")))
+        .and_then(|style_id| {
+            let style = context.style_manager.resolve_style(style_id);
+            if style.is_none() {
+                context.diagnostics.push(Diagnostic::warning("w:tblStyle", format!("unknown table style: {}", style_id)));
+            }
+            style
+        });
+
+    let look = match properties_element.and_then(|properties| properties.children().find(|child| child.tag_name().name() == "tblLook")) {
+        Some(child) => match TableLook::from_xml(&child) {
+            Ok(look) => look,
+            Err(e) => {
+                context.diagnostics.push(Diagnostic::warning("w:tblLook", format!("invalid value: {:?}", e)));
+                Default::default()
+            }
+        },
+        None => Default::default(),
+    };
+
+    // The properties of the table override the properties of its style.
+    let mut properties = style.as_ref().and_then(|style| style.table_properties).unwrap_or_default();
+    if let Some(child) = properties_element {
+        if let Err(e) = properties.apply_xml(&child) {
+            context.diagnostics.push(Diagnostic::warning("w:tblPr", format!("invalid table properties: {:?}", e)));
+        }
+    }
+
+    let table = wp::append_child(parent, wp::Node::new(wp::NodeData::Table{
+        grid: grid.clone(),
+        properties
+    }));
+
+    let table = parent.nth_child_mut(table);
+    table.position = original_position;
+    if let Some(style) = &style {
+        table.text_settings.inherit_from(&style.text_settings);
+    }
+
+    let layout = TableLayout {
+        grid,
+        properties,
+        style,
+        look,
+        row_count: node.children().filter(|child| child.tag_name().name() == "tr").count(),
+    };
+
+    let mut row_index = 0;
+    for child in node.children() {
+        match child.tag_name().name() {
+            "tblPr" => (),
+            "tblGrid" => (),
+            "tr" => {
+                position = process_table_row_element(context, table, &layout, row_index, &child, position);
+                row_index += 1;
+            }
+            _ => context.diagnostics.push(Diagnostic::warning("w:tbl", format!("unknown element: {}", child.tag_name().name()))),
+        }
+    }
+
+    resolve_table_cell_borders(table, &properties);
+
+    let width = table.children.iter().map(|row| row.size.width()).fold(0.0, f32::max);
+    table.size = Size::new(width, position.y() - original_position.y());
+
+    position
+}
+
+/// Fills in the borders the cells inherit from the table, now that it is
+/// known which cells are on the edges of the table.
+fn resolve_table_cell_borders(table: &mut Node, properties: &TableProperties) {
+    let row_count = table.children.len();
+    for (row_index, row) in table.children.iter_mut().enumerate() {
+        let cell_count = row.children.len();
+        for (cell_index, cell) in row.children.iter_mut().enumerate() {
+            if let wp::NodeData::TableCell(cell_properties) = &mut cell.data {
+                cell_properties.borders = properties.borders.resolve_for_cell(&cell_properties.borders,
+                    row_index == 0, row_index + 1 == row_count, cell_index == 0, cell_index + 1 == cell_count);
+            }
+        }
+    }
+}
+
+/// Process the `<w:tr>` element.
+fn process_table_row_element(context: &mut Context, parent: &mut Node, table: &TableLayout, row_index: usize,
+                             node: &xml::Node, original_position: Position<f32>) -> Position<f32> {
+    let grid = &table.grid;
+    let mut position = original_position;
+
+    let table_row = wp::append_child(parent, wp::Node::new(wp::NodeData::TableRow));
+    let table_row = parent.nth_child_mut(table_row);
+    table_row.position = original_position;
+
+    let row_properties = match node.children().find(|child| child.tag_name().name() == "trPr") {
+        Some(child) => TableRowProperties::from_xml(&child).unwrap_or_default(),
+        None => Default::default(),
+    };
+
+    let cells = node.children().filter(|child| child.tag_name().name() == "tc").collect::<Vec<_>>();
+
+    // Cells without a grid column or preferred width share the width of the
+    // page area that is left.
+    let available_width = {
+        let line_layout = LineLayout::new(&context.page_settings, original_position.y());
+        line_layout.page_horizontal_end - original_position.x()
+    };
+
+    let mut column_index = 0;
+    let mut row_height = 0.0f32;
+
+    for (cell_index, child) in cells.iter().enumerate() {
+        let mut cell_properties = match child.children().find(|child| child.tag_name().name() == "tcPr") {
+            Some(properties) => match TableCellProperties::from_xml(&properties) {
+                Ok(properties) => properties,
+                Err(e) => {
+                    context.diagnostics.push(Diagnostic::warning("w:tcPr", format!("invalid cell properties: {:?}", e)));
+                    Default::default()
+                }
+            },
+            None => Default::default(),
+        };
+
+        let columns = grid.0.get(column_index..column_index + cell_properties.grid_span);
+        let width = match (columns, cell_properties.width) {
+            (Some(columns), _) => columns.iter().map(|column| column.width.get_pts()).sum(),
+            (None, Some(width)) => width.get_pts(),
+            (None, None) => {
+                let used = position.x() - original_position.x();
+                (available_width - used).max(0.0) / (cells.len() - cell_index) as f32
+            }
+        };
+        let formatting = table.cell_formatting(TableCellLocation {
+            row: row_index,
+            row_count: table.row_count,
+            column_start: column_index,
+            column_end: column_index + cell_properties.grid_span,
+            column_count: if grid.0.is_empty() { cells.len() } else { grid.0.len() },
+        });
+        column_index += cell_properties.grid_span;
+
+        // The properties of the cell override the formatting of the table
+        // style.
+        if let Some(formatting) = &formatting {
+            cell_properties.borders = cell_properties.borders.or(&formatting.cell_borders);
+            cell_properties.shading = cell_properties.shading.or(formatting.cell_shading);
+        }
+
+        let table_cell = wp::append_child(table_row, wp::Node::new(wp::NodeData::TableCell(cell_properties)));
+        let table_cell = table_row.nth_child_mut(table_cell);
+        if let Some(formatting) = &formatting {
+            table_cell.text_settings.inherit_from(&formatting.text_settings);
+        }
+
+        let height = process_table_cell_element(context, table_cell, child, &table.properties.cell_margins, position, width);
+        row_height = row_height.max(height);
+
+        *position.x_mut() += width;
+    }
+
+    if let Some(height) = row_properties.height {
+        if row_properties.exact_height {
+            row_height = height.get_pts();
+        } else {
+            row_height = row_height.max(height.get_pts());
+        }
+    }
+
+    for cell in &mut table_row.children {
+        cell.size = Size::new(cell.size.width(), row_height);
+    }
+
+    table_row.size = Size::new(position.x() - original_position.x(), row_height);
+
+    Position::new(original_position.x(), original_position.y() + row_height)
+}
+
+/// Process the `<w:tc>` element of the cell node, and returns the height the
+/// cell needs for its contents.
+fn process_table_cell_element(context: &mut Context, table_cell: &mut Node, node: &xml::Node, margins: &TableCellMargins,
+                              original_position: Position<f32>, width: f32) -> f32 {
+    table_cell.position = original_position;
+    table_cell.size = Size::new(width, 0.0);
+
+    let content_top = original_position.y() + margins.top.get_pts();
+    let content_left = original_position.x() + margins.left.get_pts();
+    let content_width = (width - margins.left.get_pts() - margins.right.get_pts()).max(0.0);
+
+    let mut position = Position::new(content_left, content_top);
+    let mut previous_paragraph = None;
+
+    for child in node.children() {
+        match child.tag_name().name() {
+            "tcPr" => (),
+            "p" => {
+                let bounding_box = Rect::from_position_and_size(position, Size::new(content_width, f32::MAX));
+                position = process_paragraph_element(context, table_cell, &child, position, Some(bounding_box), &mut previous_paragraph);
+            }
+            "sdt" => {
+                let mut line_layout = wp::layout::LineLayout::new(&context.page_settings, position.y());
+                line_layout.page_horizontal_start = content_left;
+                line_layout.page_horizontal_end = content_left + content_width;
+                line_layout.position_on_line = position;
+                position = process_structured_document_tag_non_block_level(context, table_cell, &child, position, StructuredDocumentTagLevel::Cell, &mut line_layout);
+            }
+            _ => context.diagnostics.push(Diagnostic::warning("w:tc", format!("unknown element: {}", child.tag_name().name()))),
+        }
+
+        table_cell.check_last_page_number_from_new_child();
+    }
+
+    // The row has to fit the last line of the cell completely, and the
+    // margins of the cell.
+    lines_bottom(context, table_cell, position.y()) - original_position.y() + margins.bottom.get_pts()
+}
+
+/// Process the w:t element.
+fn process_text_element(context: &mut Context,
+                        parent_text_run: &mut Node,
+                        line_layout: &mut wp::layout::LineLayout,
+                        node: &xml::Node,
+                        position: Position<f32>) -> Position<f32> {
+    let mut position = position;
+
+    let text_node = wp::append_child(parent_text_run, wp::Node::new(wp::NodeData::Text));
+    let text_node = parent_text_run.nth_child_mut(text_node);
+
+    for child in node.children() {
+        if child.node_type() == xml::NodeType::Text {
+            let text_string = child.text().unwrap();
+            // println!("│  │  │  ├─ Text: \"{}\"", text_string);
+            position = process_text_element_text(text_node, line_layout, context.text_calculator, text_string, &context.drawing_ml_style_settings, context.fallback_font, position);
+        }
+    }
+
+    let last_page = text_node.page_last;
+    drop(text_node);
+    parent_text_run.propose_last_page_number(last_page);
+
+    position
+}
+
+/// 17.16.18 fldChar (Complex Field Character)
+fn process_field_character_element(context: &mut Context, text_run: &mut Node, line_layout: &mut LineLayout,
+        node: &xml::Node, position: Position<f32>) -> Position<f32> {
+    match node.attribute((WORD_PROCESSING_XML_NAMESPACE, "fldCharType")) {
+        Some("begin") => context.fields.push(FieldState::default()),
+        Some("separate") => separate_field(context, text_run.page_last),
+        Some("end") => return end_field(context, text_run, line_layout, position),
+        field_char_type => {
+            context.diagnostics.push(Diagnostic::warning("w:fldChar", format!("invalid fldCharType: {:?}", field_char_type)));
+        }
+    }
+
+    position
+}
+
+/// 17.16.19 fldSimple (Simple Field)
+fn process_simple_field_element(context: &mut Context, parent: &mut Node, line_layout: &mut LineLayout,
+        node: &xml::Node, mut position: Position<f32>) -> Position<f32> {
+    context.fields.push(FieldState {
+        instruction: node.attribute((WORD_PROCESSING_XML_NAMESPACE, "instr")).unwrap_or_default().to_string(),
+        ..Default::default()
+    });
+    separate_field(context, parent.page_last);
+
+    for child in node.children() {
+        match child.tag_name().name() {
+            "fldSimple" => position = process_simple_field_element(context, parent, line_layout, &child, position),
+            "r" => position = process_text_run_element(context, parent, line_layout, &child, position),
+            _ => (),
+        }
+
+        parent.update_page_last();
+    }
+
+    // When there weren't any runs with a result, the value is shown in a run
+    // of its own.
+    let run = wp::append_child(parent, wp::Node::new(wp::NodeData::TextRun(Default::default())));
+    position = end_field(context, parent.nth_child_mut(run), line_layout, position);
+    if parent.children[run].children.is_empty() {
+        parent.children.pop();
+    }
+
+    position
+}
+
+/// Parses and evaluates the innermost field, of which the stored result
+/// starts.
+fn separate_field(context: &mut Context, page: usize) {
+    let Some(state) = context.fields.last_mut() else {
+        context.diagnostics.push(Diagnostic::warning("w:fldChar", String::from("separate character outside of a field")));
+        return;
+    };
+
+    if state.field.is_some() {
+        return;
+    }
+
+    let field = instructions::Field::parse(&state.instruction);
+
+    // The page-dependent fields are evaluated again after pagination.
+    state.value = field.evaluate(&instructions::FieldContext {
+        page,
+        page_count: page + 1,
+        document_properties: &context.document.document_properties,
+        bookmarks: &context.bookmarks,
+        now: chrono::Local::now().naive_local(),
+    });
+    state.field = Some(field);
+}
+
+/// Ends the innermost field. A field that isn't supported and doesn't have a
+/// stored result shows its instruction instead, e.g. "{ SEQ Table }", unless
+/// it only marks content for other fields.
+fn end_field(context: &mut Context, text_run: &mut Node, line_layout: &mut LineLayout, position: Position<f32>) -> Position<f32> {
+    if context.fields.is_empty() {
+        context.diagnostics.push(Diagnostic::warning("w:fldChar", String::from("end character outside of a field")));
+        return position;
+    }
+
+    separate_field(context, text_run.page_last);
+    let position = show_field_value(context, text_run, line_layout, position);
+
+    let Some(FieldState { field: Some(field), value: None, has_result: false, .. }) = context.fields.pop() else {
+        return position;
+    };
+
+    let placeholder = format!("{{ {} }}", field.instruction().trim());
+    if field.is_hidden() || !pass_text_through_fields(&mut context.fields, &placeholder) {
+        return position;
+    }
+
+    append_field_result(context, text_run, line_layout, field, &placeholder)
+}
+
+/// Shows the value of the innermost field in the run, when it has a value
+/// that hasn't been shown yet. The value replaces the stored result, and
+/// takes the formatting of its first run.
+fn show_field_value(context: &mut Context, text_run: &mut Node, line_layout: &mut LineLayout, position: Position<f32>) -> Position<f32> {
+    let Some((state, outer_fields)) = context.fields.split_last_mut() else {
+        return position;
+    };
+
+    let (Some(field), Some(value), false) = (&state.field, &state.value, state.value_shown) else {
+        return position;
+    };
+
+    state.value_shown = true;
+    if !pass_text_through_fields(outer_fields, value) {
+        return position;
+    }
+
+    let (field, value) = (field.clone(), value.clone());
+    append_field_result(context, text_run, line_layout, field, &value)
+}
+
+/// Passes text through the fields it is nested in, from the innermost
+/// outwards, and returns whether it should be shown. Before the separate
+/// character, the text is part of the instruction of the field. After it,
+/// the text is replaced by the value of a supported field.
+fn pass_text_through_fields(fields: &mut [FieldState], text: &str) -> bool {
+    let Some((state, outer_fields)) = fields.split_last_mut() else {
+        return true;
+    };
+
+    if state.field.is_none() {
+        state.instruction.push_str(text);
+        return false;
+    }
+
+    state.has_result = true;
+    state.value.is_none() && pass_text_through_fields(outer_fields, text)
+}
+
+/// Appends the result of a field to the run, which keeps the field so the
+/// page-dependent fields can be updated, and the field is saved.
+fn append_field_result(context: &mut Context, text_run: &mut Node, line_layout: &mut LineLayout,
+        field: instructions::Field, text: &str) -> Position<f32> {
+    if let wp::NodeData::TextRun(run) = &mut text_run.data {
+        run.instruction = Some(field);
+    }
+
+    let default_tab_stop = context.document.settings.default_tab_stop.get_pts();
+    append_text_element_with_tabs(text, text_run, line_layout, context.text_calculator, &context.drawing_ml_style_settings,
+        context.fallback_font, default_tab_stop)
+}
+
+/// Append the text, where tab characters advance to the next tab stop, like
+/// the <w:tab> element does. This is used for generated text, such as the
+/// results of fields.
+fn append_text_element_with_tabs(text_string: &str, parent: &mut Node, line_layout: &mut wp::layout::LineLayout, text_calculator: &mut dyn TextCalculator,
+        theme: &ThemeSettings, fallback_font: &str, default_tab_stop: f32) -> Position<f32> {
+    let mut position = line_layout.position_on_line;
+
+    for (index, segment) in text_string.split('\t').enumerate() {
+        if index != 0 {
+            let following_width = measure_text_width(text_calculator, &parent.text_settings, theme, fallback_font, segment);
+            position = advance_to_next_tab_stop(&parent.text_settings, line_layout, position, following_width, default_tab_stop);
+        }
+
+        if !segment.is_empty() {
+            position = append_text_element(segment, parent, line_layout, text_calculator, theme, fallback_font);
+        }
+    }
+
+    position
+}
+
+fn measure_text_width(text_calculator: &mut dyn TextCalculator, text_settings: &TextSettings, theme: &ThemeSettings,
+        fallback_font: &str, text: &str) -> f32 {
+    let family_name = text_settings.font_family_name(theme);
+
+    text_calculator.measure_text(text_settings.font_specification(&family_name), text)
+        .or_else(|_| text_calculator.measure_text(text_settings.font_specification(fallback_font), text))
+        .map(|size| size.width())
+        .unwrap_or(0.0)
+}
+
+/// Advance the position to the next custom or default tab stop, where the
+/// default tab stops are `default_tab_stop` points apart (17.15.1.25
+/// defaultTabStop). The `following_width` is the width of the text up to the
+/// next tab, which is needed for centered and end-aligned tab stops.
+fn advance_to_next_tab_stop(text_settings: &TextSettings, line_layout: &mut LineLayout, position: Position<f32>,
+        following_width: f32, default_tab_stop: f32) -> Position<f32> {
+    let x = position.x() - line_layout.page_horizontal_start;
+
+    let tab_stop = text_settings.tab_stops.iter()
+        .flatten()
+        .find(|tab_stop| tab_stop.position.get_pts() > x);
+
+    let new_x = match tab_stop {
+        Some(tab_stop) => match tab_stop.alignment {
+            TabStopAlignment::Start => tab_stop.position.get_pts(),
+            TabStopAlignment::Center => (tab_stop.position.get_pts() - following_width / 2.0).max(x),
+            TabStopAlignment::End => (tab_stop.position.get_pts() - following_width).max(x),
+        },
+        None => ((x / default_tab_stop).floor() + 1.0) * default_tab_stop,
+    };
+
+    move_on_line(line_layout, position, line_layout.page_horizontal_start + new_x)
+}
+
+/// 17.3.3.23 ptab (Absolute Position Tab Character)
+/// Advance the position to the start, center or end of the text margins.
+fn advance_to_absolute_position_tab(line_layout: &mut LineLayout, node: &xml::Node, position: Position<f32>,
+        following_width: f32) -> Position<f32> {
+    let start = line_layout.page_horizontal_start;
+    let end = line_layout.page_horizontal_end;
+
+    let new_x = match node.attribute((WORD_PROCESSING_XML_NAMESPACE, "alignment")) {
+        Some("center") => (start + end - following_width) / 2.0,
+        Some("right") => end - following_width,
+        _ => start,
+    };
+
+    move_on_line(line_layout, position, new_x.max(position.x()))
+}
+
+fn move_on_line(line_layout: &mut LineLayout, position: Position<f32>, x: f32) -> Position<f32> {
+    *line_layout.position_on_line.x_mut() = x;
+    Position::new(x, position.y())
+}
+
+/// Get the text following the given element in the run, up to the next tab
+/// or break, which is the text that is aligned by a tab.
+fn following_text_in_run(node: &xml::Node) -> String {
+    let mut text = String::new();
+
+    let mut sibling = node.next_sibling_element();
+    while let Some(element) = sibling {
+        match element.tag_name().name() {
+            "t" => text.push_str(element.text().unwrap_or_default()),
+            "br" | "cr" | "ptab" | "tab" => break,
+            _ => (),
+        }
+
+        sibling = element.next_sibling_element();
+    }
+
+    text
+}
+
+pub fn append_text_element(text_string: &str, parent: &mut Node, line_layout: &mut wp::layout::LineLayout, text_calculator: &mut dyn TextCalculator,
+        theme: &ThemeSettings, fallback_font: &str) -> Position<f32> {
+    let position = line_layout.position_on_line;
+    process_text_element_text(parent, line_layout, text_calculator, text_string, theme, fallback_font, position)
+}
+
+pub fn process_text_element_text(parent: &mut Node, line_layout: &mut wp::layout::LineLayout, text_calculator: &mut dyn TextCalculator, text_string: &str,
+        theme: &drawing_ml::style::StyleSettings, fallback_font: &str, original_position: Position<f32>) -> Position<f32> {
+    // The text is laid out in logical order, and is split where its direction
+    // changes, so the text parts can be reordered when the paragraph is
+    // finished.
+    let mut position = original_position;
+    for (range, bidi_level) in wp::bidi::level_runs(text_string, wp::bidi::run_level(&parent.text_settings)) {
+        position = process_text_of_level(parent, line_layout, text_calculator, &text_string[range], bidi_level, theme, fallback_font, position);
+    }
+    position
+}
+
+/// Lays out text of a single embedding level, breaking it into lines at the
+/// word boundaries.
+#[allow(clippy::too_many_arguments)]
+fn process_text_of_level(parent: &mut Node, line_layout: &mut wp::layout::LineLayout, text_calculator: &mut dyn TextCalculator, text_string: &str,
+        bidi_level: u8, theme: &drawing_ml::style::StyleSettings, fallback_font: &str, original_position: Position<f32>) -> Position<f32> {
+    #[derive(Debug)]
+    enum LineStopReason {
+        /// The end of the text was reached. This could also very well mean the
+        /// whole string fitted on the text.
+        EndReached,
+
+        /// The line isn't the end of the text run, but this was all that could
+        /// fit on the line.
+        RestWasCutOff,
+    }
+
+    let mut position = original_position;
+
+    let mut start_index = None;
+    let mut previous_word_pair = None;
+
+    let mut previous_stop_reason = None;
+
+    let mut page_number = parent.page_last;
+    let text_settings = parent.text_settings.clone();
+
+    let (family_name, font_metrics) = resolve_font(&text_settings, text_calculator, theme, fallback_font);
+    let line_advance = text_settings.line_advance(font_metrics.line_height());
+
+    let font_spec = text_settings.font_specification(&family_name);
+
+    let mut iter = UnicodeSegmentation::split_word_bound_indices(text_string).peekable();
+    while let Some((index, word)) = iter.next() {
+        let start;
+        match start_index {
+            Some(start_index) => start = start_index,
+            None => {
+                start_index = Some(index);
+                start = index;
+            }
+        }
+
+        let mut line = &text_string[start..(index + word.bytes().count())];
+        let text_size = text_calculator.measure_text(font_spec, line).unwrap();
+        let mut width = text_size.width();
+
+        let max_width_fitting_on_page = line_layout.line_end() - position.x();
+
+        #[cfg(feature = "debug-text-layout")]
+        println!("path \"{}\" x={} w={} max_on_page={} previous_stop={:?}", line, position.x(), width, max_width_fitting_on_page, previous_stop_reason);
+
+        // Whitespace that doesn't fit on the line anymore (e.g. a run with
+        // only a space after a run that filled the line) hangs at the end of
+        // the line, instead of being moved to the start of the next line,
+        // where it would indent the text after it.
+        let is_hanging_whitespace = previous_stop_reason.is_none() && start == index && word.trim().is_empty();
+
+        if !is_hanging_whitespace && (max_width_fitting_on_page < 0.0 || previous_stop_reason.is_some()) {
+            *position.y_mut() += line_advance;
+
+            if position.y() > line_layout.page_vertical_end {
+                if line_layout.break_column() {
+                    page_number += 1;
+                    parent.page_last = page_number;
+                }
+                *position.y_mut() = line_layout.page_vertical_start;
+            }
+
+            *position.x_mut() = line_layout.line_start();
+
+            // The word starts the new line, so the line can be broken after
+            // it when the next word doesn't fit anymore.
+            if iter.peek().is_some() {
+                previous_stop_reason = None;
+                previous_word_pair = Some((index, word));
+                continue;
+            }
+        }
+
+        let stop_reason;
+
+        #[cfg(feature = "debug-text-layout")]
+        println!("width({}) < max_width_fitting_on_page({}) \"{}\"", width, max_width_fitting_on_page, line);
+
+        if let Some((next_index, next_word)) = iter.peek() {
+            let line_with_next = &text_string[start..(next_index + next_word.bytes().count())];
+
+            let width_with_next = text_calculator.measure_text(font_spec, line_with_next).unwrap().width();
+            //text.set_string(line);
+
+            if width < max_width_fitting_on_page && (iter.clone().skip(1).next().is_some() || width_with_next < max_width_fitting_on_page) {
+                previous_word_pair = Some((index, word));
+                continue;
+            }
+
+            stop_reason = LineStopReason::RestWasCutOff;
+            start_index = None;
+
+            if let Some((previous_word_index, previous_word)) = previous_word_pair {
+                if !word.trim().is_empty() {
+                    line = &text_string[start..(previous_word_index + previous_word.len())];
+                    width = text_calculator.measure_text(font_spec, line).unwrap().width();
+
+                    start_index = Some(index);
+                }
+            }
+
+            #[cfg(feature = "debug-text-layout")]
+            println!("   stop_reason={:?} start_index={:?}", stop_reason, start_index);
+        } else {
+            stop_reason = LineStopReason::EndReached;
+        }
+
+        previous_word_pair = None;
+
+        #[cfg(feature = "debug-text-layout")]
+        {
+            println!("│  │  │  │  ├─ Line: \"{}\", stop_reason={:?}", line, stop_reason);
+            println!("│  │  │  │  ├─ Calculation: x={} w={} m={}", position.x, width, max_width_fitting_on_page);
+        }
+
+        // Superscript and subscript text is raised and lowered by aligning
+        // its shifted baseline with those of the other text on the line.
+        let text_part_idx = wp::append_child(parent, wp::Node::new(wp::NodeData::TextPart(wp::TextPart{
+            text: String::from(line),
+            ascent: font_metrics.ascent + text_settings.baseline_shift_pts(),
+            character_spacing: text_settings.character_spacing_pts(),
+            word_spacing: 0.0,
+            grapheme_ends: measure_grapheme_ends(text_calculator, font_spec, line, width),
+            bidi_level,
+        })));
+        let mut text_part = parent.nth_child_mut(text_part_idx);
+        text_part.page_first = page_number;
+        text_part.page_last = page_number;
+        text_part.size = text_calculator.measure_text(font_spec, line).unwrap();
+
+        // Store the font that was used for the layout, so the text is painted
+        // with the same font.
+        text_part.text_settings.font = Some(family_name.clone());
+
+        // The lines are aligned when the paragraph is finished, since a line
+        // can consist of multiple text parts.
+        text_part.position = position;
+
+        line_layout.add_line_height_candidate(text_part.size.height());
+        *line_layout.position_on_line.x_mut() += width;
+
+        *position.x_mut() += width;
+
+        previous_stop_reason = Some(stop_reason);
+    }
+
+    assert!(previous_word_pair.is_none());
+    position
+}
+
+/// The family name of the font the text is laid out in, with its metrics,
+/// which is the fallback font when the font of the text isn't available.
+fn resolve_font(text_settings: &TextSettings, text_calculator: &mut dyn TextCalculator,
+        theme: &drawing_ml::style::StyleSettings, fallback_font: &str) -> (Rc<str>, FontMetrics) {
+    let family_name = text_settings.font_family_name(theme);
+    match text_calculator.font_metrics(text_settings.font_specification(&family_name)) {
+        Ok(font_metrics) => (family_name, font_metrics),
+        Err(..) => {
+            let family_name: Rc<str> = Rc::from(fallback_font);
+            let font_metrics = text_calculator.font_metrics(text_settings.font_specification(&family_name)).unwrap();
+            (family_name, font_metrics)
+        }
+    }
+}
+
+/// Adds an empty text part to the run, which keeps the place of the run in
+/// the paragraph when all of its text was deleted, so the caret can still be
+/// put there and the text can be typed again. It is as high as the text of
+/// the run would be.
+fn append_empty_text_part(parent: &mut Node, line_layout: &mut wp::layout::LineLayout, text_calculator: &mut dyn TextCalculator,
+        theme: &drawing_ml::style::StyleSettings, fallback_font: &str, position: Position<f32>) {
+    let text_settings = parent.text_settings.clone();
+    let (family_name, font_metrics) = resolve_font(&text_settings, text_calculator, theme, fallback_font);
+    let page_number = parent.page_last;
+
+    let text_part_idx = wp::append_child(parent, wp::Node::new(wp::NodeData::TextPart(wp::TextPart{
+        text: String::new(),
+        ascent: font_metrics.ascent + text_settings.baseline_shift_pts(),
+        character_spacing: text_settings.character_spacing_pts(),
+        word_spacing: 0.0,
+        grapheme_ends: Vec::new(),
+        bidi_level: wp::bidi::run_level(&text_settings),
+    })));
+    let text_part = parent.nth_child_mut(text_part_idx);
+    text_part.page_first = page_number;
+    text_part.page_last = page_number;
+    text_part.size = Size::new(0.0, font_metrics.ascent + font_metrics.descent);
+    text_part.text_settings.font = Some(family_name);
+    text_part.position = position;
+
+    line_layout.add_line_height_candidate(text_part.size.height());
+}
+
+/// Measures the text part again after its text changed, e.g. when a field was
+/// updated.
+fn remeasure_text_part(text_calculator: &mut dyn TextCalculator, node: &mut Node, fallback_font: &str) {
+    let wp::NodeData::TextPart(part) = &mut node.data else {
+        return;
+    };
+
+    let font_spec = node.text_settings.font_specification(node.text_settings.font.as_deref().unwrap_or(fallback_font));
+    let Ok(size) = text_calculator.measure_text(font_spec, &part.text) else {
+        return;
+    };
+
+    part.grapheme_ends = measure_grapheme_ends(text_calculator, font_spec, &part.text, size.width());
+    node.size = size;
+}
+
+/// Measures where every grapheme of the text ends, for hit testing and
+/// selection. The graphemes are measured separately, since their widths are
+/// mostly cached, and are then scaled to the width of the whole text, which
+/// includes kerning.
+fn measure_grapheme_ends(text_calculator: &mut dyn TextCalculator, font_spec: FontSpecification, text: &str, width: f32) -> Vec<f32> {
+    let mut end = 0.0;
+    let mut grapheme_ends: Vec<f32> = text.graphemes(true)
+        .map(|grapheme| {
+            end += text_calculator.measure_text(font_spec, grapheme).map(|size| size.width()).unwrap_or(0.0);
+            end
+        })
+        .collect();
+
+    if end > 0.0 {
+        for grapheme_end in &mut grapheme_ends {
+            *grapheme_end *= width / end;
+        }
+    }
+
+    grapheme_ends
+}
+
+/// 17.3.2.25 r (Text Run)
+/// This element specifies a run of content in the parent field, hyperlink,
+/// custom XML element, structured document tag, smart tag, or paragraph.
+fn process_text_run_element(context: &mut Context,
+                            parent: &mut Node,
+                            line_layout: &mut wp::layout::LineLayout,
+                            node: &xml::Node,
+                            position: Position<f32>) -> Position<f32> {
+    let mut position = position;
+
+    let text_run = wp::append_child(parent, wp::Node::new(wp::NodeData::TextRun(Default::default())));
+    let text_run = parent.nth_child_mut(text_run);
+
+    for text_run_property in node.children() {
+        match text_run_property.tag_name().name() {
+            // 17.3.3.1 br (Break)
+            "br" => {
+                mark_layout_content(text_run);
+                process_break_element(text_run, line_layout, &text_run_property, &mut context.diagnostics);
+
+                // TODO: phase out the following code when the `position`
+                //       parameters are fully replaced by the LineLayout
+                //       system.
+                if let Some(child) = text_run.children.last() {
+                    position = child.position;
+                }
+                // End TODO
+            }
+
+            "drawing" => {
+                mark_layout_content(text_run);
+                position = process_drawing_element(context, text_run, line_layout, &text_run_property, position);
+            }
+
+            // 17.13.4.5 commentReference
+            "commentReference" => {
+                if let wp::NodeData::TextRun(run) = &mut text_run.data {
+                    run.comment_reference = text_run_property.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")).map(String::from);
+                }
+            }
+
+            // 17.11.13 footnoteRef, 17.11.6 endnoteRef
+            "footnoteRef" | "endnoteRef" => {
+                mark_layout_content(text_run);
+                if let Some(number) = context.current_note_number {
+                    position = append_text_element(&number.to_string(), text_run, line_layout, context.text_calculator,
+                        &context.drawing_ml_style_settings, context.fallback_font);
+                }
+            }
+
+            // 17.11.14 footnoteReference, 17.11.7 endnoteReference
+            "footnoteReference" | "endnoteReference" => {
+                mark_layout_content(text_run);
+                position = process_note_reference_element(context, text_run, line_layout, &text_run_property, position);
+            }
+
+            // 17.16.18 fldChar (Complex Field Character)
+            "fldChar" => {
+                position = process_field_character_element(context, text_run, line_layout, &text_run_property, position);
+            }
+
+            // 17.16.23 instrText (Field Code)
+            "instrText" => {
+                if let Some(state) = context.fields.last_mut().filter(|state| state.field.is_none()) {
+                    state.instruction.push_str(text_run_property.text().unwrap_or_default());
+                }
+            }
+
+            // 17.3.3.23 ptab (Absolute Position Tab Character)
+            "ptab" => {
+                mark_layout_content(text_run);
+                let following_width = measure_text_width(context.text_calculator, &text_run.text_settings, &context.drawing_ml_style_settings,
+                    context.fallback_font, &following_text_in_run(&text_run_property));
+                position = advance_to_absolute_position_tab(line_layout, &text_run_property, position, following_width);
+            }
+
+            "rPr" =>  {
+                text_run.text_settings.apply_run_properties_element(context.style_manager, &context.drawing_ml_style_settings,
+                    &text_run_property, &mut context.diagnostics);
+            }
+
+            // 17.3.3.7 delText (Deleted Text)
+            "t" | "delText" => {
+                position = show_field_value(context, text_run, line_layout, position);
+                if pass_text_through_fields(&mut context.fields, text_run_property.text().unwrap_or_default()) {
+                    position = process_text_element(context, text_run, line_layout, &text_run_property, position);
+                }
+            }
+
+            // 17.3.3.32 tab (Tab Character)
+            "tab" => {
+                mark_layout_content(text_run);
+                let following_width = measure_text_width(context.text_calculator, &text_run.text_settings, &context.drawing_ml_style_settings,
+                    context.fallback_font, &following_text_in_run(&text_run_property));
+                let default_tab_stop = context.document.settings.default_tab_stop.get_pts();
+                position = advance_to_next_tab_stop(&text_run.text_settings, line_layout, position, following_width, default_tab_stop);
+            }
+
+            _ => ()
+        }
+
+        text_run.check_last_page_number_from_new_child();
+    }
+
+    let last_page = text_run.page_last;
+    drop(text_run);
+    parent.propose_last_page_number(last_page);
+
+    position
+}
+
+/// Marks the run as containing content that can't be laid out again from the
+/// tree, see [wp::TextRun::has_layout_content].
+fn mark_layout_content(text_run: &mut Node) {
+    if let wp::NodeData::TextRun(run) = &mut text_run.data {
+        run.has_layout_content = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::painter::mock::MockTextCalculator;
+    use crate::wp::revisions::{RevisionKind, RevisionView};
+
+    /// Page of 200x400pt with 20pt margins, so text is laid out from x=20 to
+    /// x=180.
+    const SECTION_PROPERTIES: &str = r#"<w:sectPr><w:pgSz w:w="2400" w:h="4800"/><w:pgMar w:left="240" w:right="240" w:top="240" w:bottom="240" w:header="0" w:footer="0"/></w:sectPr>"#;
+
+    /// Font size of 10pt, and a list style with contextual spacing.
+    const STYLES: &str = concat!(
+        r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+        r#"<w:docDefaults><w:rPrDefault><w:rPr><w:sz w:val="20"/></w:rPr></w:rPrDefault></w:docDefaults>"#,
+        r#"<w:style w:type="paragraph" w:styleId="ListParagraph"><w:pPr><w:spacing w:after="240"/><w:contextualSpacing/></w:pPr></w:style>"#,
+        r#"</w:styles>"#,
+    );
+
+    const NUMBERING: &str = r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/></w:lvl></w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num></w:numbering>"#;
+
+    /// Lays out the given body with a [MockTextCalculator] where every
+    /// character is 10pt wide, and returns the text parts with their
+    /// positions in document order.
+    fn layout_text_parts(body: &str, numbering: Option<&str>) -> Vec<(String, Position<f32>)> {
+        layout_text_parts_with_styles(body, STYLES, numbering)
+    }
+
+    fn layout_text_parts_with_styles(body: &str, styles: &str, numbering: Option<&str>) -> Vec<(String, Position<f32>)> {
+        let mut result = process_test_document(body, styles, numbering);
+
+        let mut text_parts = Vec::new();
+        result.root_node.apply_recursively_mut(&mut |node, _depth| {
+            if let wp::NodeData::TextPart(part) = &node.data {
+                text_parts.push((part.text.clone(), node.position));
+            }
+        }, 0);
+        text_parts
+    }
+
+    fn process_test_document(body: &str, styles: &str, numbering: Option<&str>) -> DocumentResult {
+        process_test_document_with_parts(body, SECTION_PROPERTIES, styles, numbering, &RelatedParts::default())
+    }
+
+    fn process_test_document_with_parts(body: &str, section_properties: &str, styles: &str, numbering: Option<&str>,
+                                        related_parts: &RelatedParts) -> DocumentResult {
+        process_test_document_with_revision_view(body, section_properties, styles, numbering, related_parts, RevisionView::Markup)
+    }
+
+    fn process_test_document_with_revision_view(body: &str, section_properties: &str, styles: &str, numbering: Option<&str>,
+                                                related_parts: &RelatedParts, revision_view: RevisionView) -> DocumentResult {
+        process_test_document_with_observer(body, section_properties, styles, numbering, related_parts, revision_view, &mut |_: f32| ())
+    }
+
+    fn process_test_document_with_observer(body: &str, section_properties: &str, styles: &str, numbering: Option<&str>,
+                                           related_parts: &RelatedParts, revision_view: RevisionView,
+                                           observer: &mut dyn LoadObserver) -> DocumentResult {
+        let document_text = format!(
+            r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><w:body>{}{}</w:body></w:document>"#,
+            body, section_properties
+        );
+        let document = xml::Document::parse(&document_text).unwrap();
+
+        let numbering_manager = match numbering {
+            Some(numbering) => wp::numbering::NumberingManager::from_xml(&xml::Document::parse(numbering).unwrap()).unwrap(),
+            None => wp::numbering::NumberingManager::new(),
+        };
+
+        let theme_settings = ThemeSettings::default();
+        let styles = xml::Document::parse(styles).unwrap();
+        let style_manager = StyleManager::from_document(&styles, &numbering_manager, &theme_settings).unwrap();
+
+        let mut text_calculator = MockTextCalculator::new(10.0, 2.0);
+        process_document(&document, &style_manager, &Relationships::empty(), related_parts, numbering_manager,
+            wp::document_properties::DocumentProperties::new(), &mut text_calculator, theme_settings, "Times New Roman",
+            revision_view, observer).unwrap()
+    }
+
+    /// Cancels the loading after a number of children of the body, and
+    /// remembers the progress with the number of paragraphs laid out so far.
+    struct CancellingObserver {
+        children_left: usize,
+        progress: Vec<(f32, usize)>,
+    }
+
+    impl LoadObserver for CancellingObserver {
+        fn on_progress(&mut self, progress: f32, _document: &mut Document, root_node: &mut Node) {
+            self.progress.push((progress, root_node.children.len()));
+            self.children_left = self.children_left.saturating_sub(1);
+        }
+
+        fn is_cancelled(&self) -> bool {
+            self.children_left == 0
+        }
+    }
+
+    #[test]
+    fn observer_receives_every_child_of_the_body() {
+        let body = "<w:p><w:r><w:t>a</w:t></w:r></w:p><w:p><w:r><w:t>b</w:t></w:r></w:p>";
+        let mut observer = CancellingObserver { children_left: usize::MAX, progress: Vec::new() };
+        let result = process_test_document_with_observer(body, SECTION_PROPERTIES, STYLES, None, &RelatedParts::default(),
+            RevisionView::Markup, &mut observer);
+
+        // The section properties are a child of the body too.
+        assert_eq!(observer.progress, vec![(1.0 / 3.0, 1), (2.0 / 3.0, 2), (1.0, 2)]);
+        assert_eq!(result.root_node.children.len(), 2);
+    }
+
+    #[test]
+    fn cancelled_loading_stops_after_the_current_child() {
+        let body = "<w:p><w:r><w:t>a</w:t></w:r></w:p><w:p><w:r><w:t>b</w:t></w:r></w:p><w:p><w:r><w:t>c</w:t></w:r></w:p>";
+        let mut observer = CancellingObserver { children_left: 1, progress: Vec::new() };
+        let result = process_test_document_with_observer(body, SECTION_PROPERTIES, STYLES, None, &RelatedParts::default(),
+            RevisionView::Markup, &mut observer);
+
+        assert_eq!(observer.progress.len(), 1);
+        assert_eq!(result.root_node.children.len(), 1);
+    }
+
+    #[test]
+    fn layout_places_headers_and_footers_at_their_distances() {
+        let section_properties = concat!(
+            r#"<w:sectPr><w:headerReference w:type="default" r:id="rId1"/><w:footerReference w:type="default" r:id="rId2"/>"#,
+            r#"<w:pgSz w:w="2400" w:h="4800"/><w:pgMar w:left="240" w:right="240" w:top="240" w:bottom="240" w:header="120" w:footer="120"/></w:sectPr>"#,
+        );
+
+        let mut parts = RelatedParts::default();
+        parts.headers_footers.insert(Rc::from("rId1"), String::from(
+            r#"<w:hdr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:p><w:r><w:t>Head</w:t></w:r></w:p></w:hdr>"#));
+        parts.headers_footers.insert(Rc::from("rId2"), String::from(
+            r#"<w:ftr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:p><w:r><w:t>Foot</w:t></w:r></w:p></w:ftr>"#));
+
+        let result = process_test_document_with_parts(r#"<w:p><w:r><w:t>Body</w:t></w:r></w:p>"#, section_properties, STYLES, None, &parts);
+        let section = result.document.section_for_page(0).unwrap();
+        let text_part_position = |node: &Node| node.find_first(|node| matches!(node.data, wp::NodeData::TextPart(..))).unwrap().position;
+
+        // The header starts 10pt below the top of the page, and the footer
+        // (a single line of 12pt) ends 10pt above the bottom of the page.
+        assert_eq!(text_part_position(section.header_for_page(0).unwrap()), Position::new(20.0, 10.0));
+        assert_eq!(text_part_position(section.footer_for_page(0).unwrap()), Position::new(20.0, 378.0));
+
+        // Without a title page, every page uses the default header.
+        assert!(section.header_for_page(3).is_some());
+    }
+
+    #[test]
+    fn layout_evaluates_fields() {
+        let body = concat!(
+            r#"<w:p><w:r><w:t xml:space="preserve">Page </w:t></w:r>"#,
+            r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText xml:space="preserve"> PAGE </w:instrText></w:r>"#,
+            r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>9</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+            r#"<w:r><w:t xml:space="preserve"> of </w:t></w:r><w:fldSimple w:instr="NUMPAGES"><w:r><w:t>9</w:t></w:r></w:fldSimple></w:p>"#,
+            r#"<w:p><w:r><w:fldChar w:fldCharType="begin"/><w:instrText>SEQ Table</w:instrText><w:fldChar w:fldCharType="end"/></w:r>"#,
+            r#"<w:r><w:fldChar w:fldCharType="begin"/><w:instrText>XE Entry</w:instrText><w:fldChar w:fldCharType="end"/></w:r>"#,
+            r#"<w:fldSimple w:instr="TOC \o"><w:r><w:t>Cached</w:t></w:r></w:fldSimple>"#,
+            r#"<w:r><w:fldChar w:fldCharType="begin"/><w:instrText>REF Later</w:instrText><w:fldChar w:fldCharType="separate"/>"#,
+            r#"<w:t>Old</w:t><w:fldChar w:fldCharType="end"/></w:r></w:p>"#,
+            r#"<w:p><w:r><w:br w:type="page"/></w:r><w:bookmarkStart w:id="0" w:name="Later"/><w:r><w:t>Target</w:t></w:r><w:bookmarkEnd w:id="0"/>"#,
+            r#"<w:fldSimple w:instr="PAGE"/></w:p>"#,
+        );
+
+        let mut result = process_test_document(body, STYLES, None);
+        let texts: Vec<String> = text_parts_of(&result.root_node).into_iter().map(|(text, _)| text).collect();
+
+        // The number of pages is only known after pagination.
+        assert_eq!(texts, ["Page ", "1", " of ", "2", "{ SEQ Table }", "Cached", "Target", "Target", "2"]);
+
+        let number_of_pages = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)))[3];
+        assert_eq!(number_of_pages.size.width(), 10.0);
+
+        // The fields in headers are updated for every page they are shown on.
+        let mut header = Document::new(TextSettings::new());
+        let mut run = wp::Node::new(wp::NodeData::TextRun(wp::TextRun {
+            instruction: Some(instructions::Field::parse(r"PAGE \* roman")),
+            has_layout_content: false,
+            comment_reference: None,
+        }));
+        append_text_element("i", &mut run, &mut LineLayout::new(&result.document.page_settings, 20.0),
+            &mut MockTextCalculator::new(10.0, 2.0), &ThemeSettings::default(), "Times New Roman");
+        wp::append_child(&mut header, run);
+
+        let section = result.document.section_for_page_mut(0).unwrap();
+        section.headers.default = Some(header);
+        section.update_page_fields(3, 4);
+        assert_eq!(text_parts_of(section.header_for_page(3).unwrap())[0].0, "iv");
+    }
+
+    #[test]
+    fn links_to_bookmarks_go_to_their_start() {
+        let body = concat!(
+            r#"<w:p><w:hyperlink w:anchor="Target"><w:r><w:t>Go</w:t></w:r></w:hyperlink></w:p>"#,
+            r#"<w:p><w:r><w:br w:type="page"/></w:r><w:bookmarkStart w:id="0" w:name="Target"/><w:r><w:t>Here</w:t></w:r><w:bookmarkEnd w:id="0"/></w:p>"#,
+        );
+
+        let result = process_test_document(body, STYLES, None);
+        assert_eq!(result.root_node.find_bookmark("Target").unwrap().page_first, 1);
+        assert!(result.root_node.find_bookmark("Other").is_none());
+
+        let link = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::Hyperlink(..))).unwrap();
+        let wp::NodeData::Hyperlink(hyperlink) = &link.data else {
+            unreachable!();
+        };
+
+        let mut mouse_event = wp::MouseEvent::new(Position::new(0.0, 0.0));
+        mouse_event.follows_links = true;
+
+        let mut event = wp::Event::Click(mouse_event);
+        hyperlink.on_event(&mut event);
+        let wp::Event::Click(mouse_event) = event else {
+            unreachable!();
+        };
+        assert_eq!(mouse_event.bookmark.as_deref(), Some("Target"));
+    }
+
+    #[test]
+    fn revision_views_show_their_text() {
+        let body = concat!(
+            r#"<w:p><w:r><w:t>Kept</w:t></w:r>"#,
+            r#"<w:ins w:id="1" w:author="Jane"><w:r><w:t>new</w:t></w:r></w:ins>"#,
+            r#"<w:del w:id="2" w:author="John"><w:r><w:delText>old</w:delText></w:r></w:del></w:p>"#,
+        );
+
+        let texts = |revision_view| {
+            let result = process_test_document_with_revision_view(body, SECTION_PROPERTIES, STYLES, None, &RelatedParts::default(), revision_view);
+            text_parts_of(&result.root_node).into_iter().map(|(text, _)| text).collect::<Vec<_>>()
+        };
+        assert_eq!(texts(RevisionView::Markup), ["Kept", "new", "old"]);
+        assert_eq!(texts(RevisionView::Final), ["Kept", "new"]);
+        assert_eq!(texts(RevisionView::Original), ["Kept", "old"]);
+
+        // The hidden deletion is kept for saving.
+        let result = process_test_document_with_revision_view(body, SECTION_PROPERTIES, STYLES, None, &RelatedParts::default(), RevisionView::Final);
+        let deletion = result.root_node.find_first(|node| matches!(&node.data,
+            wp::NodeData::Revision(revision) if revision.kind == RevisionKind::Deletion)).unwrap();
+        let wp::NodeData::Revision(revision) = &deletion.data else {
+            unreachable!();
+        };
+        assert_eq!(revision.author, "John");
+        assert!(revision.hidden_markup.as_deref().is_some_and(|markup| markup.contains("<w:delText>old</w:delText>")));
+    }
+
+    #[test]
+    fn comments_are_anchored_to_their_ranges() {
+        let body = concat!(
+            r#"<w:p><w:r><w:t xml:space="preserve">Not </w:t></w:r><w:commentRangeStart w:id="5"/><w:r><w:t>this</w:t></w:r><w:commentRangeEnd w:id="5"/>"#,
+            r#"<w:r><w:rPr><w:rStyle w:val="CommentReference"/></w:rPr><w:commentReference w:id="5"/></w:r></w:p>"#,
+        );
+
+        let mut parts = RelatedParts::default();
+        parts.comments = Some(String::from(concat!(
+            r#"<w:comments xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:comment w:id="5" w:author="Reviewer"><w:p><w:r><w:t>Why?</w:t></w:r></w:p></w:comment>"#,
+            r#"</w:comments>"#,
+        )));
+
+        let result = process_test_document_with_parts(body, SECTION_PROPERTIES, STYLES, None, &parts);
+        assert_eq!(result.document.comments.len(), 1);
+        assert_eq!(result.document.comments[0].author, "Reviewer");
+        assert_eq!(result.document.comments[0].text, "Why?");
+
+        let ranges = wp::comments::find_comment_ranges(&result.root_node);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].id, "5");
+        assert_eq!(ranges[0].text_parts.len(), 1);
+        assert_eq!(ranges[0].text_parts[0].position, Position::new(60.0, 20.0));
+
+        let reference = result.root_node.find_first(|node| matches!(&node.data,
+            wp::NodeData::TextRun(run) if run.comment_reference.as_deref() == Some("5")));
+        assert!(reference.is_some());
+    }
+
+    fn text_parts_of(node: &Node) -> Vec<(String, Position<f32>)> {
+        node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)))
+            .into_iter()
+            .map(|node| match &node.data {
+                wp::NodeData::TextPart(part) => (part.text.clone(), node.position),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layout_places_footnotes_at_the_bottom_of_the_page() {
+        let parts = RelatedParts {
+            footnotes: Some(String::from(concat!(
+                r#"<w:footnotes xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+                r#"<w:footnote w:type="separator" w:id="-1"><w:p><w:r><w:separator/></w:r></w:p></w:footnote>"#,
+                r#"<w:footnote w:id="1"><w:p><w:r><w:footnoteRef/></w:r><w:r><w:t>Note</w:t></w:r></w:p></w:footnote>"#,
+                r#"</w:footnotes>"#,
+            ))),
+            ..Default::default()
+        };
+
+        let body = r#"<w:p><w:r><w:t>Text</w:t></w:r><w:r><w:footnoteReference w:id="1"/></w:r></w:p>"#;
+        let result = process_test_document_with_parts(body, SECTION_PROPERTIES, STYLES, None, &parts);
+
+        assert_eq!(text_parts_of(&result.root_node), vec![
+            (String::from("Text"), Position::new(20.0, 20.0)),
+            (String::from("1"), Position::new(60.0, 20.0)),
+        ]);
+
+        // The single line of 12pt ends at the bottom margin of the page.
+        let area = &result.document.footnote_areas[0];
+        assert_eq!(area.page, 0);
+        assert_eq!(text_parts_of(&area.notes), vec![
+            (String::from("1"), Position::new(20.0, 368.0)),
+            (String::from("Note"), Position::new(30.0, 368.0)),
+        ]);
+
+        assert_eq!(result.document.note_separators, vec![wp::NoteSeparator {
+            page: 0,
+            rect: Rect::from_positions(20.0, 20.0 + NOTE_SEPARATOR_WIDTH, 362.0, 362.0 + NOTE_SEPARATOR_THICKNESS),
+        }]);
+    }
+
+    #[test]
+    fn mirrored_margins_move_the_even_pages() {
+        let parts = RelatedParts {
+            settings: Some(format!(r#"<w:settings xmlns:w="{}"><w:mirrorMargins/></w:settings>"#, WORD_PROCESSING_XML_NAMESPACE)),
+            ..Default::default()
+        };
+
+        // The left margin is 20pt, and the right margin 40pt.
+        let section_properties = r#"<w:sectPr><w:pgSz w:w="2400" w:h="4800"/><w:pgMar w:left="240" w:right="480" w:top="240" w:bottom="240" w:header="0" w:footer="0"/></w:sectPr>"#;
+        let body = r#"<w:p><w:r><w:t>Odd</w:t></w:r></w:p><w:p><w:r><w:br w:type="page"/></w:r><w:r><w:t>Even</w:t></w:r></w:p>"#;
+        let result = process_test_document_with_parts(body, section_properties, STYLES, None, &parts);
+
+        let text_parts = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)));
+        let pages_and_lefts: Vec<_> = text_parts.iter().map(|node| (node.page_first, node.position.x())).collect();
+        assert_eq!(pages_and_lefts, vec![(0, 20.0), (1, 40.0)]);
+    }
+
+    #[test]
+    fn layout_places_endnotes_after_the_body() {
+        let parts = RelatedParts {
+            endnotes: Some(String::from(concat!(
+                r#"<w:endnotes xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+                r#"<w:endnote w:id="4"><w:p><w:r><w:endnoteRef/></w:r><w:r><w:t>End</w:t></w:r></w:p></w:endnote>"#,
+                r#"</w:endnotes>"#,
+            ))),
+            ..Default::default()
+        };
+
+        let body = r#"<w:p><w:r><w:t>Text</w:t></w:r><w:r><w:endnoteReference w:id="4"/></w:r></w:p>"#;
+        let result = process_test_document_with_parts(body, SECTION_PROPERTIES, STYLES, None, &parts);
+
+        // The body ends at 32pt, followed by the separator and the spacing
+        // around it.
+        assert_eq!(text_parts_of(&result.root_node), vec![
+            (String::from("Text"), Position::new(20.0, 20.0)),
+            (String::from("1"), Position::new(60.0, 20.0)),
+            (String::from("1"), Position::new(20.0, 44.0)),
+            (String::from("End"), Position::new(30.0, 44.0)),
+        ]);
+        assert_eq!(result.document.note_separators[0].rect.top, 38.0);
+    }
+
+    #[test]
+    fn layout_places_table_cells_on_the_grid() {
+        let body = concat!(
+            r#"<w:tbl><w:tblGrid><w:gridCol w:w="900"/><w:gridCol w:w="900"/></w:tblGrid>"#,
+            r#"<w:tr><w:tc><w:p><w:r><w:t>A</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>B</w:t></w:r></w:p></w:tc></w:tr>"#,
+            r#"<w:tr><w:trPr><w:trHeight w:val="360"/></w:trPr><w:tc><w:tcPr><w:gridSpan w:val="2"/></w:tcPr><w:p><w:r><w:t>C</w:t></w:r></w:p></w:tc></w:tr>"#,
+            r#"</w:tbl>"#,
+        );
+        let result = process_test_document(body, STYLES, None);
+
+        // The text is inset by the default cell margin of 9pt.
+        assert_eq!(text_parts_of(&result.root_node), vec![
+            (String::from("A"), Position::new(29.0, 20.0)),
+            (String::from("B"), Position::new(104.0, 20.0)),
+            (String::from("C"), Position::new(29.0, 32.0)),
+        ]);
+
+        let cells = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TableCell(..)));
+        let cell_rects = cells.iter().map(|cell| Rect::from_position_and_size(cell.position, cell.size)).collect::<Vec<_>>();
+        assert_eq!(cell_rects, vec![
+            Rect::from_positions(20.0, 95.0, 20.0, 32.0),
+            Rect::from_positions(95.0, 170.0, 20.0, 32.0),
+            // The row is at least 30pt high.
+            Rect::from_positions(20.0, 170.0, 32.0, 62.0),
+        ]);
+
+        let table = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::Table { .. })).unwrap();
+        assert_eq!(table.size, Size::new(150.0, 42.0));
+    }
+
+    #[test]
+    fn layout_applies_the_conditional_formatting_of_table_styles() {
+        let styles = concat!(
+            r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:docDefaults><w:rPrDefault><w:rPr><w:sz w:val="20"/></w:rPr></w:rPrDefault></w:docDefaults>"#,
+            r#"<w:style w:type="table" w:styleId="Banded"><w:tblPr><w:tblCellMar><w:left w:w="0" w:type="dxa"/></w:tblCellMar></w:tblPr>"#,
+            r#"<w:tblStylePr w:type="firstRow"><w:rPr><w:b/></w:rPr><w:tcPr><w:shd w:val="clear" w:color="auto" w:fill="FF0000"/></w:tcPr></w:tblStylePr>"#,
+            r#"<w:tblStylePr w:type="band2Horz"><w:tcPr><w:shd w:val="clear" w:color="auto" w:fill="00FF00"/></w:tcPr></w:tblStylePr>"#,
+            r#"</w:style></w:styles>"#,
+        );
+        let row = r#"<w:tr><w:tc><w:p><w:r><w:t>a</w:t></w:r></w:p></w:tc></w:tr>"#;
+        let body = format!(
+            r#"<w:tbl><w:tblPr><w:tblStyle w:val="Banded"/><w:tblLook w:val="04A0" w:noVBand="1"/></w:tblPr>{}{}{}{}{}</w:tbl>"#,
+            row, row, row, row,
+            r#"<w:tr><w:tc><w:tcPr><w:shd w:val="clear" w:color="auto" w:fill="0000FF"/></w:tcPr><w:p><w:r><w:t>a</w:t></w:r></w:p></w:tc></w:tr>"#,
+        );
+        let result = process_test_document(&body, styles, None);
+
+        let cells = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TableCell(..)));
+        let shadings = cells.iter().map(|cell| match &cell.data {
+            wp::NodeData::TableCell(properties) => properties.shading,
+            _ => unreachable!(),
+        }).collect::<Vec<_>>();
+
+        // The header row isn't part of the banding, and the shading of the
+        // cell itself overrides the shading of the band.
+        assert_eq!(shadings, vec![
+            Some(gui::Color::from_rgb(0xFF, 0, 0)),
+            None,
+            Some(gui::Color::from_rgb(0, 0xFF, 0)),
+            None,
+            Some(gui::Color::from_rgb(0, 0, 0xFF)),
+        ]);
+
+        let bold = cells.iter().map(|cell| cell.text_settings.bold).collect::<Vec<_>>();
+        assert_eq!(bold, vec![Some(true), None, None, None, None]);
+
+        // The cell margins come from the table style.
+        assert_eq!(text_parts_of(&result.root_node)[0].1, Position::new(20.0, 20.0));
+    }
+
+    #[test]
+    fn layout_wraps_text_at_the_page_margin() {
+        let text_parts = layout_text_parts(r#"<w:p><w:r><w:t>aaaa bbbb cccc dddd</w:t></w:r></w:p>"#, None);
+
+        // The second line is advanced by the line height of the font, which
+        // is the ascent (10) plus the line gap (2).
+        assert_eq!(text_parts, vec![
+            (String::from("aaaa bbbb cccc "), Position::new(20.0, 20.0)),
+            (String::from("dddd"), Position::new(20.0, 32.0)),
+        ]);
+    }
+
+    #[test]
+    fn relayout_wraps_edited_text() {
+        let mut result = process_test_document(r#"<w:p><w:r><w:t>aaaa bbbb cccc</w:t></w:r></w:p>"#, STYLES, None);
+
+        let position = wp::editing::ParagraphPosition { path: vec![0], offset: 14 };
+        wp::editing::insert_text(&mut result.root_node, &position, " dddd").unwrap();
+
+        let mut text_calculator = MockTextCalculator::new(10.0, 2.0);
+        let height_change = relayout_paragraph(result.root_node.nth_child_mut(0), &result.document.page_settings,
+            &mut text_calculator, "Times New Roman");
+
+        assert_eq!(height_change, 12.0);
+        assert_eq!(text_parts_of(&result.root_node), vec![
+            (String::from("aaaa bbbb cccc "), Position::new(20.0, 20.0)),
+            (String::from("dddd"), Position::new(20.0, 32.0)),
+        ]);
+    }
+
+    #[test]
+    fn relayout_keeps_an_empty_text_part_to_type_in() {
+        let mut result = process_test_document(r#"<w:p><w:r><w:t>a</w:t></w:r></w:p>"#, STYLES, None);
+        let mut text_calculator = MockTextCalculator::new(10.0, 2.0);
+
+        wp::editing::delete_text(&mut result.root_node, &[0], 0..1).unwrap();
+        let height_change = relayout_paragraph(result.root_node.nth_child_mut(0), &result.document.page_settings,
+            &mut text_calculator, "Times New Roman");
+
+        // The empty line is as high as the text was.
+        assert_eq!(height_change, 0.0);
+        assert_eq!(text_parts_of(&result.root_node), vec![(String::new(), Position::new(20.0, 20.0))]);
+
+        let position = wp::editing::ParagraphPosition { path: vec![0], offset: 0 };
+        assert!(wp::editing::text_position(&result.root_node, &position).is_some());
+
+        wp::editing::insert_text(&mut result.root_node, &position, "b").unwrap();
+        relayout_paragraph(result.root_node.nth_child_mut(0), &result.document.page_settings,
+            &mut text_calculator, "Times New Roman");
+        assert_eq!(text_parts_of(&result.root_node), vec![(String::from("b"), Position::new(20.0, 20.0))]);
+    }
+
+    #[test]
+    fn layout_makes_room_for_paragraph_borders() {
+        let result = process_test_document(concat!(
+            r#"<w:p><w:pPr><w:pBdr><w:top w:val="single" w:sz="16" w:space="4"/><w:bottom w:val="single" w:sz="16" w:space="4"/>"#,
+            r#"<w:left w:val="none"/></w:pBdr></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>b</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        // The borders are 2pt wide, and 4pt away from the 10pt high text.
+        assert_eq!(text_parts_of(&result.root_node), vec![
+            (String::from("a"), Position::new(20.0, 26.0)),
+            (String::from("b"), Position::new(20.0, 42.0)),
+        ]);
+
+        let wp::NodeData::Paragraph(paragraph) = &result.root_node.children[0].data else { unreachable!() };
+        assert_eq!(paragraph.border_rect.top, 0.0);
+        assert_eq!(paragraph.border_rect.bottom, 22.0);
+        assert_eq!(paragraph.border_rect.left, 0.0);
+        assert_eq!(paragraph.borders.left_extent(), 0.0);
+    }
+
+    #[test]
+    fn layout_applies_line_spacing_of_the_paragraph() {
+        let double_spacing = layout_text_parts(
+            r#"<w:p><w:pPr><w:spacing w:line="480" w:lineRule="auto"/></w:pPr><w:r><w:t>aaaa bbbb cccc dddd</w:t></w:r></w:p>"#, None);
+        assert_eq!(double_spacing[1], (String::from("dddd"), Position::new(20.0, 44.0)));
+
+        let exact_spacing = layout_text_parts(
+            r#"<w:p><w:pPr><w:spacing w:line="360" w:lineRule="exact"/></w:pPr><w:r><w:t>aaaa bbbb cccc dddd</w:t></w:r></w:p>"#, None);
+        assert_eq!(exact_spacing[1], (String::from("dddd"), Position::new(20.0, 50.0)));
+
+        let at_least_spacing = layout_text_parts(
+            r#"<w:p><w:pPr><w:spacing w:line="60" w:lineRule="atLeast"/></w:pPr><w:r><w:t>aaaa bbbb cccc dddd</w:t></w:r></w:p>"#, None);
+        assert_eq!(at_least_spacing[1], (String::from("dddd"), Position::new(20.0, 32.0)));
+    }
+
+    #[test]
+    fn layout_applies_line_spacing_to_the_last_line() {
+        let next_paragraph = r#"<w:p><w:r><w:t>b</w:t></w:r></w:p>"#;
+
+        let double_spacing = layout_text_parts(&format!("{}{}",
+            r#"<w:p><w:pPr><w:spacing w:line="480" w:lineRule="auto"/></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#, next_paragraph), None);
+        assert_eq!(double_spacing[1], (String::from("b"), Position::new(20.0, 40.0)));
+
+        let exact_spacing = layout_text_parts(&format!("{}{}",
+            r#"<w:p><w:pPr><w:spacing w:line="360" w:lineRule="exact"/></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#, next_paragraph), None);
+        assert_eq!(exact_spacing[1], (String::from("b"), Position::new(20.0, 50.0)));
+
+        // The spacing before the paragraph comes on top of the line spacing.
+        let spacing_before = layout_text_parts(&format!("{}{}", next_paragraph,
+            r#"<w:p><w:pPr><w:spacing w:before="120" w:line="480" w:lineRule="auto"/></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#), None);
+        assert_eq!(spacing_before[1], (String::from("a"), Position::new(20.0, 40.0)));
+    }
+
+    #[test]
+    fn layout_applies_first_line_and_hanging_indentation() {
+        let first_line = layout_text_parts(
+            r#"<w:p><w:pPr><w:ind w:firstLine="120"/></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#, None);
+        assert_eq!(first_line, vec![(String::from("a"), Position::new(30.0, 20.0))]);
+
+        // The first line starts 10pt before the other lines, which end 20pt
+        // before the margin.
+        let hanging = layout_text_parts(concat!(
+            r#"<w:p><w:pPr><w:ind w:left="240" w:right="240" w:hanging="120"/></w:pPr>"#,
+            r#"<w:r><w:t>aaaa bbbb cccc dddd</w:t></w:r></w:p>"#,
+        ), None);
+        assert_eq!(hanging, vec![
+            (String::from("aaaa bbbb "), Position::new(30.0, 20.0)),
+            (String::from("cccc dddd"), Position::new(40.0, 32.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_aligns_baselines_of_mixed_size_runs() {
+        let text_parts = layout_text_parts(concat!(
+            r#"<w:p><w:r><w:t>ab</w:t></w:r><w:r><w:rPr><w:sz w:val="40"/></w:rPr><w:t>CD</w:t></w:r></w:p>"#,
+        ), None);
+
+        // The ascent of the 10pt text is 10 smaller than that of the 20pt
+        // text, so it is moved down by that difference.
+        assert_eq!(text_parts, vec![
+            (String::from("ab"), Position::new(20.0, 30.0)),
+            (String::from("CD"), Position::new(40.0, 20.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_raises_superscript_and_lowers_subscript() {
+        let text_parts = layout_text_parts(concat!(
+            r#"<w:p><w:r><w:rPr><w:sz w:val="60"/></w:rPr><w:t>H</w:t></w:r>"#,
+            r#"<w:r><w:rPr><w:sz w:val="60"/><w:vertAlign w:val="subscript"/></w:rPr><w:t>2</w:t></w:r>"#,
+            r#"<w:r><w:rPr><w:sz w:val="60"/><w:vertAlign w:val="superscript"/></w:rPr><w:t>3</w:t></w:r></w:p>"#,
+        ), None);
+
+        // The 30pt text has an ascent of 30, and the 19.5pt subscript and
+        // superscript text are shifted by 4.2 and 9.9 from that baseline.
+        let expected = [("H", 20.0, 20.0), ("2", 30.0, 34.7), ("3", 40.0, 20.6)];
+        assert_eq!(text_parts.len(), expected.len());
+        for ((text, position), (expected_text, x, y)) in text_parts.iter().zip(expected) {
+            assert_eq!(text, expected_text);
+            assert_eq!(position.x(), x);
+            assert!((position.y() - y).abs() < 0.001, "{} is at {}, expected {}", text, position.y(), y);
+        }
+    }
+
+    #[test]
+    fn layout_applies_character_spacing_and_scaling() {
+        let result = process_test_document(concat!(
+            r#"<w:p><w:r><w:rPr><w:spacing w:val="24"/><w:w w:val="50"/></w:rPr><w:t>ab</w:t></w:r>"#,
+            r#"<w:r><w:t>c</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        // The characters are 10 wide, which is halved, plus the spacing of 2
+        // after each character.
+        let text_parts = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)));
+        let wp::NodeData::TextPart(part) = &text_parts[0].data else { unreachable!() };
+        assert_eq!(text_parts[0].size.width(), 14.0);
+        assert_eq!(part.character_spacing, 2.0);
+        assert_eq!(part.grapheme_ends, vec![7.0, 14.0]);
+        assert_eq!(text_parts[1].position, Position::new(34.0, 20.0));
+    }
+
+    #[test]
+    fn layout_distributes_every_line() {
+        let mut result = process_test_document(concat!(
+            r#"<w:p><w:pPr><w:jc w:val="distribute"/></w:pPr><w:r><w:t>ab</w:t></w:r><w:r><w:t>cd</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        let mut text_parts = Vec::new();
+        result.root_node.apply_recursively_mut(&mut |node, _depth| {
+            if let wp::NodeData::TextPart(part) = &node.data {
+                text_parts.push((part.text.clone(), node.position, part.character_spacing));
+            }
+        }, 0);
+
+        // The last (and only) line is 40 wide, so the remaining 120 of the
+        // line is divided over the 3 gaps between the characters.
+        assert_eq!(text_parts, vec![
+            (String::from("ab"), Position::new(20.0, 20.0), 40.0),
+            (String::from("cd"), Position::new(120.0, 20.0), 40.0),
+        ]);
+    }
+
+    #[test]
+    fn sections_have_their_own_page_settings() {
+        let result = process_test_document(concat!(
+            r#"<w:p><w:pPr><w:sectPr><w:pgSz w:w="4800" w:h="2400" w:orient="landscape"/>"#,
+            r#"<w:pgMar w:left="480" w:right="480" w:top="480" w:bottom="480" w:header="0" w:footer="0"/></w:sectPr></w:pPr>"#,
+            r#"<w:r><w:t>a</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:sectPr><w:type w:val="continuous"/><w:pgSz w:w="4800" w:h="2400" w:orient="landscape"/>"#,
+            r#"<w:pgMar w:left="360" w:right="360" w:top="480" w:bottom="480" w:header="0" w:footer="0"/></w:sectPr></w:pPr>"#,
+            r#"<w:r><w:t>b</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>c</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        let text_parts = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)));
+        let pages_and_positions: Vec<_> = text_parts.iter().map(|node| (node.page_first, node.position.x())).collect();
+
+        // The continuous section stays on the first page, but the last
+        // section starts on the next page, with the margins of the body.
+        assert_eq!(pages_and_positions, vec![(0, 40.0), (0, 30.0), (1, 20.0)]);
+        assert_eq!(text_parts[0].position.y(), 40.0);
+        assert_eq!(text_parts[2].position.y(), 20.0);
+
+        let document = &result.document;
+        assert_eq!(document.sections.iter().map(|section| section.page_first).collect::<Vec<_>>(), vec![0, 0, 1]);
+        assert_eq!(document.page_settings_for_page(0).size.width(), TwelfteenthPoint(4800));
+        assert_eq!(document.page_settings_for_page(1).size.width(), TwelfteenthPoint(2400));
+    }
+
+    #[test]
+    fn layout_flows_text_through_columns() {
+        // The text area is 160 wide and 20 high, which leaves room for two
+        // lines in each of the 70 wide columns.
+        let section_properties = concat!(
+            r#"<w:sectPr><w:pgSz w:w="2400" w:h="720"/><w:pgMar w:left="240" w:right="240" w:top="240" w:bottom="240" w:header="0" w:footer="0"/>"#,
+            r#"<w:cols w:num="2" w:space="240" w:sep="1"/></w:sectPr>"#,
+        );
+        let body = concat!(
+            r#"<w:p><w:r><w:t>aaaa bbbb cccc d</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:br w:type="column"/><w:t>e</w:t></w:r></w:p>"#,
+        );
+        let result = process_test_document_with_parts(body, section_properties, STYLES, None, &RelatedParts::default());
+
+        let text_parts: Vec<_> = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..)))
+            .into_iter()
+            .map(|node| match &node.data {
+                wp::NodeData::TextPart(part) => (part.text.clone(), node.page_first, node.position),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        // The column break in the last column continues on the next page.
+        assert_eq!(text_parts, vec![
+            (String::from("aaaa "), 0, Position::new(20.0, 20.0)),
+            (String::from("bbbb "), 0, Position::new(20.0, 32.0)),
+            (String::from("cccc d"), 0, Position::new(110.0, 20.0)),
+            (String::from("e"), 1, Position::new(20.0, 20.0)),
+        ]);
+
+        let section = &result.document.sections[0];
+        assert_eq!(section.columns, vec![Column { start: 20.0, end: 90.0 }, Column { start: 110.0, end: 180.0 }]);
+        assert!(section.column_separator);
+    }
+
+    #[test]
+    fn layout_justifies_every_line_but_the_last() {
+        let mut result = process_test_document(concat!(
+            r#"<w:p><w:pPr><w:jc w:val="both"/></w:pPr><w:r><w:t xml:space="preserve">aaaa </w:t></w:r>"#,
+            r#"<w:r><w:t>bbbb cccc dddd eeee</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        let mut text_parts = Vec::new();
+        result.root_node.apply_recursively_mut(&mut |node, _depth| {
+            if let wp::NodeData::TextPart(part) = &node.data {
+                text_parts.push((part.text.clone(), node.position, part.word_spacing));
+            }
+        }, 0);
+
+        // The words of the first line end at 160, so the remaining 20 of the
+        // line is divided over the 2 spaces between the words, but not over
+        // the space at the end of the line.
+        assert_eq!(text_parts, vec![
+            (String::from("aaaa "), Position::new(20.0, 20.0), 10.0),
+            (String::from("bbbb cccc "), Position::new(80.0, 20.0), 10.0),
+            (String::from("dddd eeee"), Position::new(20.0, 32.0), 0.0),
+        ]);
+    }
+
+    #[test]
+    fn layout_places_text_below_top_and_bottom_drawing() {
+        let mut result = process_test_document(concat!(
+            r#"<w:p><w:r><w:t>abc</w:t></w:r><w:r><w:drawing>"#,
+            r#"<wp:anchor xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" "#,
+            r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" "#,
+            r#"xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture" "#,
+            r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" distT="127000" distB="127000">"#,
+            r#"<wp:positionH relativeFrom="margin"><wp:align>center</wp:align></wp:positionH>"#,
+            r#"<wp:extent cx="914400" cy="457200"/><wp:wrapTopAndBottom/>"#,
+            r#"<a:graphic><a:graphicData><pic:pic><pic:blipFill><a:blip r:embed="rId42"/></pic:blipFill></pic:pic></a:graphicData></a:graphic>"#,
+            r#"</wp:anchor></w:drawing></w:r><w:r><w:t>def</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        // The drawing of 72x36 is placed below the first line (10) with a
+        // distance of 10 above and below it.
+        let drawing = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::Drawing(..))).unwrap();
+        assert_eq!(drawing.position, Position::new(64.0, 40.0));
+        assert_eq!(drawing.size, Size::new(72.0, 36.0));
+
+        let mut text_parts = Vec::new();
+        result.root_node.apply_recursively_mut(&mut |node, _depth| {
+            if let wp::NodeData::TextPart(part) = &node.data {
+                text_parts.push((part.text.clone(), node.position));
+            }
+        }, 0);
+        assert_eq!(text_parts, vec![
+            (String::from("abc"), Position::new(20.0, 20.0)),
+            (String::from("def"), Position::new(20.0, 86.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_aligns_text() {
+        let text_parts = layout_text_parts(concat!(
+            r#"<w:p><w:r><w:t>abc</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:jc w:val="center"/></w:pPr><w:r><w:t>abc</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:jc w:val="end"/></w:pPr><w:r><w:t>abc</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:jc w:val="center"/></w:pPr><w:r><w:t>ab</w:t></w:r><w:r><w:t>cd</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:jc w:val="both"/></w:pPr><w:r><w:t>abc</w:t></w:r></w:p>"#,
+        ), None);
+
+        // The runs of a line are aligned together. The last line of a
+        // justified paragraph is aligned to the start, see
+        // layout_justifies_every_line_but_the_last.
+        assert_eq!(text_parts, vec![
+            (String::from("abc"), Position::new(20.0, 20.0)),
+            (String::from("abc"), Position::new(85.0, 30.0)),
+            (String::from("abc"), Position::new(150.0, 40.0)),
+            (String::from("ab"), Position::new(80.0, 50.0)),
+            (String::from("cd"), Position::new(100.0, 50.0)),
+            (String::from("abc"), Position::new(20.0, 60.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_reorders_right_to_left_text() {
+        let text_parts = layout_text_parts(concat!(
+            r#"<w:p><w:r><w:t xml:space="preserve">ab </w:t></w:r><w:r><w:rPr><w:rtl/></w:rPr><w:t xml:space="preserve">אב </w:t></w:r>"#,
+            r#"<w:r><w:rPr><w:rtl/></w:rPr><w:t>גד</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:bidi/></w:pPr><w:r><w:rPr><w:rtl/></w:rPr><w:t xml:space="preserve">אב </w:t></w:r><w:r><w:t>cd</w:t></w:r></w:p>"#,
+        ), None);
+
+        // The right-to-left runs after the left-to-right text are reversed,
+        // and the right-to-left paragraph starts at the right margin.
+        assert_eq!(text_parts, vec![
+            (String::from("ab "), Position::new(20.0, 20.0)),
+            (String::from("אב "), Position::new(70.0, 20.0)),
+            (String::from("גד"), Position::new(50.0, 20.0)),
+            (String::from("אב "), Position::new(150.0, 30.0)),
+            (String::from("cd"), Position::new(130.0, 30.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_automatic_spacing_overrides_explicit_spacing() {
+        let text_parts = layout_text_parts(concat!(
+            r#"<w:p><w:pPr><w:spacing w:before="240" w:beforeAutospacing="1" w:after="240" w:afterAutospacing="1"/></w:pPr><w:r><w:t>abc</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>abc</w:t></w:r></w:p>"#,
+        ), None);
+
+        // The automatic spacing is the line height of the font, which is the
+        // ascent (10) plus the line gap (2).
+        assert_eq!(text_parts, vec![
+            (String::from("abc"), Position::new(20.0, 32.0)),
+            (String::from("abc"), Position::new(20.0, 54.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_ignores_spacing_between_paragraphs_of_the_same_style() {
+        let list_paragraph = r#"<w:p><w:pPr><w:pStyle w:val="ListParagraph"/></w:pPr><w:r><w:t>abc</w:t></w:r></w:p>"#;
+        let text_parts = layout_text_parts(&format!("{}{}<w:p><w:r><w:t>abc</w:t></w:r></w:p>", list_paragraph, list_paragraph), None);
+
+        // Only the last paragraph of the list has spacing (20) below it.
+        assert_eq!(text_parts, vec![
+            (String::from("abc"), Position::new(20.0, 20.0)),
+            (String::from("abc"), Position::new(20.0, 30.0)),
+            (String::from("abc"), Position::new(20.0, 60.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_uses_default_text_size_when_none_is_specified() {
+        let text_parts = layout_text_parts_with_styles(
+            r#"<w:p><w:r><w:t>abc</w:t></w:r></w:p><w:p><w:r><w:t>abc</w:t></w:r></w:p>"#,
+            r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"></w:styles>"#,
+            None
+        );
+
+        // The line height is the default text size of 11pt.
+        assert_eq!(text_parts, vec![
+            (String::from("abc"), Position::new(20.0, 20.0)),
+            (String::from("abc"), Position::new(20.0, 31.0)),
+        ]);
+    }
+
+    #[test]
+    fn layout_indents_text_after_numbering() {
+        let paragraph = r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Item</w:t></w:r></w:p>"#;
+        let text_parts = layout_text_parts(&paragraph.repeat(2), Some(NUMBERING));
+
+        let indentation = 700.0 * TWELFTEENTH_POINT;
+        assert_eq!(text_parts, vec![
+            (String::from("1."), Position::new(20.0, 20.0)),
+            (String::from("Item"), Position::new(indentation, 20.0)),
+            (String::from("2."), Position::new(20.0, 30.0)),
+            (String::from("Item"), Position::new(indentation, 30.0)),
+        ]);
+    }
+
+    #[test]
+    fn invalid_on_off_values_are_reported() {
+        let mut diagnostics = Vec::new();
+
+        assert_eq!(parse_on_off("on", "w:spacing", &mut diagnostics), Some(true));
+        assert_eq!(parse_on_off("0", "w:spacing", &mut diagnostics), Some(false));
+        assert!(diagnostics.is_empty());
+
+        assert_eq!(parse_on_off("maybe", "w:spacing", &mut diagnostics), None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, wp::diagnostics::Severity::Error);
+        assert_eq!(diagnostics[0].source, "w:spacing");
+    }
+
+    /// The sources of the diagnostics with the given severity, in the order
+    /// they were reported.
+    fn diagnostic_sources(result: &DocumentResult, severity: wp::diagnostics::Severity) -> Vec<&str> {
+        result.diagnostics.iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .map(|diagnostic| diagnostic.source.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn malformed_paragraph_properties_are_reported() {
+        let body = r#"<w:p><w:pPr><w:jc/><w:pStyle/><w:spacing w:after="much" w:before="-"/></w:pPr><w:r><w:t>Text</w:t></w:r></w:p>"#;
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:jc", "w:pStyle", "w:spacing", "w:spacing"]);
+        assert_eq!(layout_text_parts(body, None), vec![(String::from("Text"), Position::new(20.0, 20.0))]);
+    }
+
+    #[test]
+    fn malformed_run_properties_are_reported() {
+        let body = r#"<w:p><w:r><w:rPr><w:highlight/><w:rStyle/><w:sz w:val="big"/><w:color w:val="red"/></w:rPr><w:t>Text</w:t></w:r></w:p>"#;
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:highlight", "w:rStyle", "w:sz", "w:color"]);
+        assert_eq!(layout_text_parts(body, None), vec![(String::from("Text"), Position::new(20.0, 20.0))]);
+    }
+
+    #[test]
+    fn malformed_table_grids_are_reported() {
+        let body = r#"<w:tbl><w:tblGrid><w:gridCol w:w="wide"/></w:tblGrid><w:tr><w:tc><w:p><w:r><w:t>Text</w:t></w:r></w:p></w:tc></w:tr></w:tbl>"#;
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:tblGrid"]);
+    }
+
+    #[test]
+    fn document_without_a_body_is_an_error() {
+        let document = xml::Document::parse(r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"/>"#).unwrap();
+        let numbering_manager = wp::numbering::NumberingManager::new();
+        let theme_settings = ThemeSettings::default();
+        let styles = xml::Document::parse(STYLES).unwrap();
+        let style_manager = StyleManager::from_document(&styles, &numbering_manager, &theme_settings).unwrap();
+
+        let result = process_document(&document, &style_manager, &Relationships::empty(), &RelatedParts::default(), numbering_manager,
+            wp::document_properties::DocumentProperties::new(), &mut MockTextCalculator::new(10.0, 2.0), theme_settings, "Times New Roman",
+            RevisionView::Markup, &mut |_: f32| ());
+        assert!(matches!(result, Err(Error::MissingElement("w:body"))));
+    }
+
+    #[test]
+    fn body_without_section_properties_has_a4_pages() {
+        let result = process_test_document_with_parts(r#"<w:p><w:r><w:t>Text</w:t></w:r></w:p>"#, "", STYLES, None, &RelatedParts::default());
+
+        let page_settings = &result.document.page_settings;
+        assert_eq!(page_settings.size, Size::new(TwelfteenthPoint(11906), TwelfteenthPoint(16838)));
+        let text_part = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::TextPart(..))).unwrap();
+        assert_eq!(text_part.position.x(), 120.0);
+    }
+
+    #[test]
+    fn invalid_spacing_scale_and_kerning_are_reported() {
+        let body = concat!(
+            r#"<w:p><w:r><w:rPr><w:kern w:val="auto"/><w:spacing w:val="1.5"/><w:w w:val="0%"/></w:rPr><w:t>a</w:t></w:r>"#,
+            r#"<w:r><w:rPr><w:kern/><w:spacing w:val="-4"/><w:w w:val="150%"/></w:rPr><w:t>b</w:t></w:r></w:p>"#,
+        );
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:kern", "w:spacing", "w:w", "w:kern"]);
+        let runs = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextRun(..)));
+        assert_eq!(runs[0].text_settings.horizontal_scale, None);
+        assert_eq!(runs[1].text_settings.character_spacing, Some(TwelfteenthPoint(-4)));
+        assert_eq!(runs[1].text_settings.horizontal_scale, Some(150));
+    }
+
+    #[test]
+    fn invalid_right_to_left_values_are_reported() {
+        let body = r#"<w:p><w:r><w:rPr><w:rtl/><w:rtl w:val="yes"/></w:rPr><w:t>a</w:t></w:r><w:r><w:rPr><w:rtl w:val="off"/></w:rPr><w:t>b</w:t></w:r></w:p>"#;
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:rtl"]);
+        let runs = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextRun(..)));
+        assert_eq!(runs[0].text_settings.right_to_left, Some(true));
+        assert_eq!(runs[1].text_settings.right_to_left, Some(false));
+    }
+
+    #[test]
+    fn unknown_underline_styles_are_reported() {
+        let body = r#"<w:p><w:r><w:rPr><w:u w:val="squiggly"/></w:rPr><w:t>a</w:t></w:r><w:r><w:rPr><w:u w:val="wave"/></w:rPr><w:t>b</w:t></w:r></w:p>"#;
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["w:u"]);
+        let runs = result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextRun(..)));
+        assert_eq!(runs[0].text_settings.underline_style(), None);
+        assert_eq!(runs[1].text_settings.underline_style(), Some(text_settings::UnderlineStyle::Wave));
+    }
+
+    #[test]
+    fn unknown_vertical_alignments_are_reported() {
+        let body = r#"<w:p><w:r><w:rPr><w:vertAlign w:val="middle"/></w:rPr><w:t>a</w:t></w:r><w:r><w:rPr><w:vertAlign/></w:rPr><w:t>b</w:t></w:r></w:p>"#;
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["w:vertAlign"]);
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:vertAlign"]);
+        assert!(result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextRun(..)))
+            .iter()
+            .all(|run| run.text_settings.vertical_alignment.is_none()));
+    }
+
+    #[test]
+    fn unknown_theme_colors_are_reported() {
+        let body = r#"<w:p><w:r><w:rPr><w:color w:val="FF0000" w:themeColor="accent9"/><w:u w:val="single" w:themeColor="text1"/></w:rPr><w:t>Text</w:t></w:r></w:p>"#;
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["w:color"]);
+        let run = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::TextRun(..))).unwrap();
+        assert_eq!(run.text_settings.color, Some(crate::gui::Color::RED));
+    }
+
+    #[test]
+    fn malformed_numbering_references_are_reported() {
+        // The whitespace between the elements isn't an unknown element.
+        let body = concat!(
+            "<w:p><w:pPr><w:numPr>\n  <w:ilvl w:val=\"first\"/>\n  <w:numId w:val=\"1\"/>\n</w:numPr></w:pPr><w:r><w:t>Item</w:t></w:r></w:p>",
+            "<w:p><w:pPr><w:numPr><w:ilvl w:val=\"0\"/><w:numId/></w:numPr></w:pPr><w:r><w:t>Text</w:t></w:r></w:p>",
+        );
+        let result = process_test_document(body, STYLES, Some(NUMBERING));
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:ilvl", "w:numId"]);
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["w:numPr"]);
+
+        // The invalid level falls back to the first level.
+        let text_parts = layout_text_parts(body, Some(NUMBERING));
+        assert_eq!(text_parts[0].0, "1.");
+        assert_eq!(text_parts.last().unwrap().0, "Text");
+    }
+
+    #[test]
+    fn unknown_structured_document_tag_children_are_reported() {
+        let body = concat!(
+            "<w:sdt>\n  <w:sdtPr/>\n  <w:sdtContent><w:p><w:r><w:t>Text</w:t></w:r></w:p></w:sdtContent>\n  <w:sdtUnknown/>\n</w:sdt>",
+            "<w:p><w:sdt>\n  <w:sdtContent><w:r><w:t>Run</w:t></w:r></w:sdtContent>\n  <w:sdtUnknown/>\n</w:sdt></w:p>",
+        );
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["w:sdt", "w:sdt"]);
+        let texts: Vec<String> = layout_text_parts(body, None).into_iter().map(|(text, _)| text).collect();
+        assert_eq!(texts, vec!["Text", "Run"]);
+    }
+
+    #[test]
+    fn graphics_other_than_pictures_are_reported() {
+        let body = concat!(
+            r#"<w:p><w:r><w:drawing><wp:inline xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" "#,
+            r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart">"#,
+            r#"<wp:extent cx="914400" cy="914400"/><a:graphic><a:graphicData><c:chart/></a:graphicData></a:graphic>"#,
+            r#"</wp:inline></w:drawing></w:r></w:p>"#,
+        );
+        let result = process_test_document(body, STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["a:graphicData"]);
+    }
+
+    #[test]
+    fn paragraphs_with_revisions_are_detected() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:body xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:p><w:r><w:t>Unchanged</w:t></w:r></w:p>"#,
+            r#"<w:p><w:ins w:id="1" w:author="A"><w:r><w:t>Inserted</w:t></w:r></w:ins></w:p>"#,
+            r#"<w:p><w:pPr><w:pPrChange w:id="2" w:author="A"><w:pPr/></w:pPrChange></w:pPr></w:p>"#,
+            r#"</w:body>"#,
+        )).unwrap();
+
+        let paragraphs: Vec<bool> = document.root_element().children()
+            .map(|paragraph| has_revision_markers(&paragraph))
+            .collect();
+        assert_eq!(paragraphs, vec![false, true, true]);
+    }
+
+    #[test]
+    fn heading_level_prefers_outline_level() {
+        let styles = concat!(
+            r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:style w:type="paragraph" w:styleId="Kop1"><w:pPr><w:outlineLvl w:val="0"/></w:pPr></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Heading2"/>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Heading3"><w:pPr><w:outlineLvl w:val="9"/></w:pPr></w:style>"#,
+            r#"</w:styles>"#,
+        );
+
+        let result = process_test_document(concat!(
+            r#"<w:p><w:pPr><w:pStyle w:val="Kop1"/></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>b</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading3"/></w:pPr><w:r><w:t>c</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:outlineLvl w:val="4"/></w:pPr><w:r><w:t>d</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>e</w:t></w:r></w:p>"#,
+        ), styles, None);
+
+        let levels: Vec<Option<u8>> = result.root_node
+            .find_all(|node| matches!(node.data, wp::NodeData::Paragraph(..)))
+            .iter()
+            .map(|paragraph| paragraph.text_settings.heading_level())
+            .collect();
+        assert_eq!(levels, vec![Some(0), Some(1), None, Some(4), None]);
+    }
+
+    #[test]
+    fn links_to_unknown_bookmarks_are_reported() {
+        let result = process_test_document(concat!(
+            r#"<w:p><w:bookmarkStart w:id="0" w:name="Target"/><w:r><w:t>a</w:t></w:r><w:bookmarkEnd w:id="0"/></w:p>"#,
+            r#"<w:p><w:hyperlink w:anchor="Target"><w:r><w:t>b</w:t></w:r></w:hyperlink></w:p>"#,
+            r#"<w:p><w:hyperlink w:anchor="_top"><w:r><w:t>c</w:t></w:r></w:hyperlink></w:p>"#,
+            r#"<w:p><w:hyperlink w:anchor="Missing"><w:r><w:t>d</w:t></w:r></w:hyperlink></w:p>"#,
+        ), STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["w:hyperlink"]);
+        assert!(result.diagnostics.iter().any(|diagnostic| diagnostic.message.contains("\"Missing\"")));
+    }
+
+    #[test]
+    fn missing_paragraph_styles_are_reported() {
+        let result = process_test_document(concat!(
+            r#"<w:p><w:pPr><w:pStyle w:val="ListParagraph"/></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>b</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Warning), vec!["w:pStyle"]);
+        assert!(result.diagnostics.iter().any(|diagnostic| diagnostic.message.contains("Title")));
+    }
+
+    #[test]
+    fn missing_and_invalid_outline_levels_are_reported() {
+        let result = process_test_document(concat!(
+            r#"<w:p><w:pPr><w:outlineLvl/></w:pPr><w:r><w:t>a</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:outlineLvl w:val="10"/></w:pPr><w:r><w:t>b</w:t></w:r></w:p>"#,
+        ), STYLES, None);
+
+        assert_eq!(diagnostic_sources(&result, wp::diagnostics::Severity::Error), vec!["w:outlineLvl", "w:outlineLvl"]);
+        assert!(result.root_node
+            .find_all(|node| matches!(node.data, wp::NodeData::Paragraph(..)))
+            .iter()
+            .all(|paragraph| paragraph.text_settings.heading_level().is_none()));
+    }
+
+    #[test]
+    fn layout_keeps_whitespace_only_runs() {
+        let text_parts = layout_text_parts(concat!(
+            r#"<w:p><w:r><w:t>word1</w:t></w:r><w:r><w:t xml:space="preserve"> </w:t></w:r><w:r><w:t>word2</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>aaaaaaaaaaaaaaaaa</w:t></w:r><w:r><w:t xml:space="preserve"> </w:t></w:r><w:r><w:t>word2</w:t></w:r></w:p>"#,
+        ), None);
+
+        // The space after the line that overflowed the page margin hangs at
+        // the end of that line, so the next line doesn't start with it.
+        assert_eq!(text_parts, vec![
+            (String::from("word1"), Position::new(20.0, 20.0)),
+            (String::from(" "), Position::new(70.0, 20.0)),
+            (String::from("word2"), Position::new(80.0, 20.0)),
+            (String::from("aaaaaaaaaaaaaaaaa"), Position::new(20.0, 30.0)),
+            (String::from(" "), Position::new(190.0, 30.0)),
+            (String::from("word2"), Position::new(20.0, 42.0)),
+        ]);
+    }
+
+    /// A paragraph with a right-aligned tab stop at the end of the text
+    /// margin, like the entries of a table of contents.
+    const TAB_STOP_PROPERTIES: &str = r#"<w:pPr><w:tabs><w:tab w:val="right" w:pos="1920"/></w:tabs></w:pPr>"#;
+
+    #[test]
+    fn layout_aligns_text_after_tab_to_tab_stop() {
+        let text_parts = layout_text_parts(&format!(
+            r#"<w:p>{}<w:r><w:t>Intro</w:t></w:r><w:r><w:tab/><w:t>12</w:t></w:r></w:p><w:p><w:r><w:t>a</w:t><w:tab/><w:t>b</w:t></w:r></w:p>"#,
+            TAB_STOP_PROPERTIES
+        ), None);
+
+        // The default tab stops are every 60pt.
+        assert_eq!(text_parts, vec![
+            (String::from("Intro"), Position::new(20.0, 20.0)),
+            (String::from("12"), Position::new(160.0, 20.0)),
+            (String::from("a"), Position::new(20.0, 30.0)),
+            (String::from("b"), Position::new(80.0, 30.0)),
+        ]);
+    }
+
+    #[test]
+    fn field_results_align_to_tab_stops() {
+        let page_settings = PageSettings::new(
+            Size::new(TwelfteenthPoint(2400), TwelfteenthPoint(4800)),
+            Rect::from_positions(TwelfteenthPoint(240), TwelfteenthPoint(240), TwelfteenthPoint(240), TwelfteenthPoint(240)),
+            TwelfteenthPoint(0),
+            TwelfteenthPoint(0),
+        );
+        let mut line_layout = LineLayout::new(&page_settings, 20.0);
+
+        let mut paragraph = Node::new(wp::NodeData::Paragraph(Default::default()));
+        let properties_text = format!(r#"<w:p xmlns:w="{}">{}</w:p>"#, WORD_PROCESSING_XML_NAMESPACE, TAB_STOP_PROPERTIES);
+        let properties = xml::Document::parse(&properties_text).unwrap();
+        paragraph.text_settings.parse_element_tabs(&properties.root_element().first_element_child().unwrap().first_element_child().unwrap());
+        paragraph.text_settings.non_complex_text_size = Some(crate::HalfPoint(20));
+
+        let mut text_calculator = MockTextCalculator::new(10.0, 2.0);
+        append_text_element_with_tabs("Intro\t12", &mut paragraph, &mut line_layout, &mut text_calculator, &ThemeSettings::default(), "Times New Roman",
+            wp::settings::DEFAULT_TAB_STOP.get_pts());
+
+        let text_parts: Vec<(String, Position<f32>)> = paragraph.children.iter()
+            .filter_map(|child| match &child.data {
+                wp::NodeData::TextPart(part) => Some((part.text.clone(), child.position)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text_parts, vec![
+            (String::from("Intro"), Position::new(20.0, 20.0)),
+            (String::from("12"), Position::new(160.0, 20.0)),
+        ]);
+    }
+}
@@ -0,0 +1,841 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Reading the legacy Word Binary File Format (.doc) [MS-DOC], which is stored
+// in a Compound File Binary container. The text of the main document and its
+// direct formatting are converted to a WordprocessingML main document part,
+// which is then laid out like any other document.
+//
+// Only the most common character and paragraph properties are read. Styles,
+// fonts, tables, headers, footers, notes, pictures and the section properties
+// aren't, so the documents use the default page settings and are opened
+// read-only.
+
+use std::{io::Read, path::Path};
+
+use crate::{
+    error::Error,
+    gui::painter::TextCalculator,
+    relationships::Relationships,
+    wp::{
+        diagnostics::Diagnostic,
+        revisions::RevisionView,
+        save::escape,
+    },
+};
+
+use super::{
+    CONVERTED_DOCUMENT_END,
+    CONVERTED_DOCUMENT_START,
+    DocumentResult,
+    LoadObserver,
+    process_converted_document,
+};
+
+/// The wIdent of the FibBase, which identifies the WordDocument stream.
+const FIB_IDENTIFIER: u16 = 0xA5EC;
+
+/// The flags of the FibBase.
+const FIB_FLAG_ENCRYPTED: u16 = 0x0100;
+const FIB_FLAG_WHICH_TABLE_STREAM: u16 = 0x0200;
+
+/// The size of the FibBase, which is followed by the csw of the FIB.
+const FIB_BASE_SIZE: usize = 32;
+
+/// The offset of ccpText in the FibRgLw97.
+const FIB_RG_LW_CCP_TEXT: usize = 12;
+
+/// The indices of the pairs of the FibRgFcLcb97 that are read.
+const FC_LCB_PLCF_BTE_CHPX: usize = 12;
+const FC_LCB_PLCF_BTE_PAPX: usize = 13;
+const FC_LCB_CLX: usize = 33;
+
+/// The size of a formatted disk page (FKP) in the WordDocument stream.
+const FKP_SIZE: usize = 512;
+
+/// The size of a BxPap in a PapxFkp.
+const BX_PAP_SIZE: usize = 13;
+
+/// The size of a Pcd in a PlcPcd.
+const PCD_SIZE: usize = 8;
+
+/// Set in the FcCompressed of a piece when its text is stored as 8-bit
+/// characters.
+const FC_COMPRESSED_FLAG: u32 = 0x4000_0000;
+
+/// The special characters of the text (2.8.26 PlcPcd, 2.8.25 PlcfFld).
+const CELL_MARK: u16 = 0x07;
+const TAB: u16 = 0x09;
+const LINE_BREAK: u16 = 0x0B;
+const PAGE_BREAK: u16 = 0x0C;
+const PARAGRAPH_MARK: u16 = 0x0D;
+const FIELD_BEGIN: u16 = 0x13;
+const FIELD_SEPARATOR: u16 = 0x14;
+const FIELD_END: u16 = 0x15;
+const NON_BREAKING_HYPHEN: u16 = 0x1E;
+
+/// The sprms (2.6 Single Property Modifiers) that are read.
+const SPRM_C_F_BOLD: u16 = 0x0835;
+const SPRM_C_F_ITALIC: u16 = 0x0836;
+const SPRM_C_F_STRIKE: u16 = 0x0837;
+const SPRM_C_KUL: u16 = 0x2A3E;
+const SPRM_C_ICO: u16 = 0x2A42;
+const SPRM_C_HPS: u16 = 0x4A43;
+const SPRM_C_CV: u16 = 0x6870;
+const SPRM_P_JC_80: u16 = 0x2403;
+const SPRM_P_JC: u16 = 0x2461;
+const SPRM_P_DXA_LEFT_80: u16 = 0x840F;
+const SPRM_P_DXA_LEFT: u16 = 0x845E;
+const SPRM_P_DYA_BEFORE: u16 = 0xA413;
+const SPRM_P_DYA_AFTER: u16 = 0xA414;
+
+/// The only sprm with a variable operand that doesn't start with the size of
+/// the operand in a single byte.
+const SPRM_T_DEF_TABLE: u16 = 0xD608;
+
+/// The characters of the bytes 0x80 to 0x9F in compressed text, which are
+/// those of Windows-1252 as far as the specification maps them (2.4.1
+/// Retrieving Text). The other bytes are the Unicode code point itself.
+const COMPRESSED_CHARACTERS: [u16; 32] = [
+    0x80, 0x81, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021,
+    0x02C6, 0x2030, 0x0160, 0x2039, 0x0152, 0x8D, 0x8E, 0x8F,
+    0x90, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x9D, 0x9E, 0x0178,
+];
+
+/// The colors of the Ico values of sprmCIco, starting at 1 (black). 0 is
+/// the automatic color.
+const ICO_COLORS: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00], [0x00, 0x00, 0xFF], [0x00, 0xFF, 0xFF], [0x00, 0xFF, 0x00],
+    [0xFF, 0x00, 0xFF], [0xFF, 0x00, 0x00], [0xFF, 0xFF, 0x00], [0xFF, 0xFF, 0xFF],
+    [0x00, 0x00, 0x80], [0x00, 0x80, 0x80], [0x00, 0x80, 0x00], [0x80, 0x00, 0x80],
+    [0x80, 0x00, 0x00], [0x80, 0x80, 0x00], [0x80, 0x80, 0x80], [0xC0, 0xC0, 0xC0],
+];
+
+/// The style sheet isn't read, so the text uses the Normal style of Microsoft
+/// Word 97-2003.
+const STYLES_PART: &str = concat!(
+    r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+    r#"<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Times New Roman" w:hAnsi="Times New Roman" w:cs="Times New Roman"/>"#,
+    r#"<w:sz w:val="24"/><w:szCs w:val="24"/></w:rPr></w:rPrDefault></w:docDefaults>"#,
+    r#"</w:styles>"#,
+);
+
+#[derive(Debug)]
+pub enum DocError {
+    Io(std::io::Error),
+
+    /// The WordDocument stream doesn't start with a FIB.
+    NotAWordDocument,
+
+    /// The document is password-protected.
+    Encrypted,
+
+    /// A structure extends past the end of its stream.
+    Truncated,
+
+    /// The Clx doesn't contain a piece table.
+    MissingPieceTable,
+}
+
+impl From<std::io::Error> for DocError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// The parts of the File Information Block (2.5.1 Fib) that are read.
+#[derive(Debug)]
+struct Fib {
+    encrypted: bool,
+
+    /// The name of the stream containing the tables, "0Table" or "1Table".
+    table_stream_name: &'static str,
+
+    /// The number of characters of the main document.
+    ccp_text: u32,
+
+    /// The offsets and sizes in the table stream of the PlcBteChpx, the
+    /// PlcBtePapx and the Clx.
+    character_properties: (u32, u32),
+    paragraph_properties: (u32, u32),
+    clx: (u32, u32),
+}
+
+impl Fib {
+    fn parse(data: &[u8]) -> Result<Self, DocError> {
+        if read_u16(data, 0)? != FIB_IDENTIFIER {
+            return Err(DocError::NotAWordDocument);
+        }
+
+        let flags = read_u16(data, 0x0A)?;
+
+        // The FibRgW97, FibRgLw97 and FibRgFcLcb are preceded by their
+        // counts, which differ between the versions of Word.
+        let csw = read_u16(data, FIB_BASE_SIZE)? as usize;
+        let rg_lw = FIB_BASE_SIZE + 2 + csw * 2 + 2;
+        let cslw = read_u16(data, rg_lw - 2)? as usize;
+        let rg_fc_lcb = rg_lw + cslw * 4 + 2;
+        let cb_rg_fc_lcb = read_u16(data, rg_fc_lcb - 2)? as usize;
+
+        let fc_lcb = |index: usize| -> Result<(u32, u32), DocError> {
+            if index >= cb_rg_fc_lcb {
+                return Ok((0, 0));
+            }
+            Ok((read_u32(data, rg_fc_lcb + index * 8)?, read_u32(data, rg_fc_lcb + index * 8 + 4)?))
+        };
+
+        Ok(Self {
+            encrypted: flags & FIB_FLAG_ENCRYPTED != 0,
+            table_stream_name: if flags & FIB_FLAG_WHICH_TABLE_STREAM != 0 { "1Table" } else { "0Table" },
+            ccp_text: read_u32(data, rg_lw + FIB_RG_LW_CCP_TEXT)?,
+            character_properties: fc_lcb(FC_LCB_PLCF_BTE_CHPX)?,
+            paragraph_properties: fc_lcb(FC_LCB_PLCF_BTE_PAPX)?,
+            clx: fc_lcb(FC_LCB_CLX)?,
+        })
+    }
+}
+
+/// A piece of the text (2.9.177 Pcd), stored at `fc` in the WordDocument
+/// stream.
+#[derive(Debug)]
+struct Piece {
+    cp_start: u32,
+    cp_end: u32,
+    fc: u32,
+    compressed: bool,
+}
+
+/// The direct formatting of a run of text, from the sprms of its Chpx.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct CharacterProperties {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+
+    /// The ST_Underline of the underline.
+    underline: Option<&'static str>,
+
+    /// In half-points.
+    font_size: Option<u16>,
+    color: Option<[u8; 3]>,
+}
+
+impl CharacterProperties {
+    fn apply_sprm(&mut self, sprm: u16, operand: &[u8]) {
+        match sprm {
+            SPRM_C_F_BOLD => self.bold = is_toggled_on(operand[0]),
+            SPRM_C_F_ITALIC => self.italic = is_toggled_on(operand[0]),
+            SPRM_C_F_STRIKE => self.strike = is_toggled_on(operand[0]),
+            SPRM_C_KUL => self.underline = underline_style(operand[0]),
+            SPRM_C_HPS => self.font_size = Some(u16::from_le_bytes([operand[0], operand[1]])),
+            SPRM_C_ICO => self.color = operand[0].checked_sub(1).and_then(|index| ICO_COLORS.get(index as usize)).copied(),
+
+            // A COLORREF, of which the last byte is 0xFF for the automatic
+            // color.
+            SPRM_C_CV => self.color = (operand[3] != 0xFF).then_some([operand[0], operand[1], operand[2]]),
+            _ => (),
+        }
+    }
+
+    fn write_xml(&self, output: &mut String) {
+        let mut properties = String::new();
+        if self.bold {
+            properties += "<w:b/>";
+        }
+        if self.italic {
+            properties += "<w:i/>";
+        }
+        if self.strike {
+            properties += "<w:strike/>";
+        }
+        if let Some([red, green, blue]) = self.color {
+            properties += &format!(r#"<w:color w:val="{:02X}{:02X}{:02X}"/>"#, red, green, blue);
+        }
+        if let Some(font_size) = self.font_size {
+            properties += &format!(r#"<w:sz w:val="{}"/>"#, font_size);
+        }
+        if let Some(underline) = self.underline {
+            properties += &format!(r#"<w:u w:val="{}"/>"#, underline);
+        }
+
+        if !properties.is_empty() {
+            *output += &format!("<w:rPr>{}</w:rPr>", properties);
+        }
+    }
+}
+
+/// The direct formatting of a paragraph, from the sprms of its Papx.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ParagraphProperties {
+    /// The ST_Jc of the justification.
+    justification: Option<&'static str>,
+
+    /// In twentieths of a point.
+    spacing_before: Option<u16>,
+    spacing_after: Option<u16>,
+    indentation_left: Option<i16>,
+}
+
+impl ParagraphProperties {
+    fn apply_sprm(&mut self, sprm: u16, operand: &[u8]) {
+        match sprm {
+            SPRM_P_JC_80 | SPRM_P_JC => {
+                self.justification = match operand[0] {
+                    0 => Some("left"),
+                    1 => Some("center"),
+                    2 => Some("right"),
+                    3 => Some("both"),
+                    _ => None,
+                };
+            }
+            SPRM_P_DXA_LEFT_80 | SPRM_P_DXA_LEFT => self.indentation_left = Some(i16::from_le_bytes([operand[0], operand[1]])),
+            SPRM_P_DYA_BEFORE => self.spacing_before = Some(u16::from_le_bytes([operand[0], operand[1]])),
+            SPRM_P_DYA_AFTER => self.spacing_after = Some(u16::from_le_bytes([operand[0], operand[1]])),
+            _ => (),
+        }
+    }
+
+    fn write_xml(&self, output: &mut String) {
+        let mut properties = String::new();
+        if self.spacing_before.is_some() || self.spacing_after.is_some() {
+            properties += "<w:spacing";
+            if let Some(before) = self.spacing_before {
+                properties += &format!(r#" w:before="{}""#, before);
+            }
+            if let Some(after) = self.spacing_after {
+                properties += &format!(r#" w:after="{}""#, after);
+            }
+            properties += "/>";
+        }
+        if let Some(left) = self.indentation_left {
+            properties += &format!(r#"<w:ind w:left="{}"/>"#, left);
+        }
+        if let Some(justification) = self.justification {
+            properties += &format!(r#"<w:jc w:val="{}"/>"#, justification);
+        }
+
+        if !properties.is_empty() {
+            *output += &format!("<w:pPr>{}</w:pPr>", properties);
+        }
+    }
+}
+
+/// Text with the same formatting, of which the tabs and breaks are kept as
+/// their special characters.
+#[derive(Debug, PartialEq)]
+struct Run {
+    properties: CharacterProperties,
+    text: Vec<u16>,
+}
+
+impl Run {
+    fn write_xml(&self, output: &mut String) {
+        *output += "<w:r>";
+        self.properties.write_xml(output);
+
+        let mut text = Vec::new();
+        for code in &self.text {
+            let element = match *code {
+                TAB => "<w:tab/>",
+                LINE_BREAK => "<w:br/>",
+                PAGE_BREAK => r#"<w:br w:type="page"/>"#,
+                _ => {
+                    text.push(*code);
+                    continue;
+                }
+            };
+
+            flush_text(output, &mut text);
+            *output += element;
+        }
+
+        flush_text(output, &mut text);
+        *output += "</w:r>";
+    }
+}
+
+fn flush_text(output: &mut String, text: &mut Vec<u16>) {
+    if !text.is_empty() {
+        *output += &format!(r#"<w:t xml:space="preserve">{}</w:t>"#, escape(&String::from_utf16_lossy(text)));
+        text.clear();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Paragraph {
+    properties: ParagraphProperties,
+    runs: Vec<Run>,
+}
+
+/// The main document of a Word Binary File.
+#[derive(Debug, Default)]
+pub struct BinaryDocument {
+    paragraphs: Vec<Paragraph>,
+}
+
+impl BinaryDocument {
+    /// Converts the document to a WordprocessingML main document part.
+    pub fn to_document_xml(&self) -> String {
+        let mut output = String::from(CONVERTED_DOCUMENT_START);
+        for paragraph in &self.paragraphs {
+            output += "<w:p>";
+            paragraph.properties.write_xml(&mut output);
+            for run in &paragraph.runs {
+                run.write_xml(&mut output);
+            }
+            output += "</w:p>";
+        }
+        output += CONVERTED_DOCUMENT_END;
+        output
+    }
+}
+
+/// The properties of the text stored between two offsets in the WordDocument
+/// stream.
+#[derive(Debug)]
+struct PropertyRange<T> {
+    start: u32,
+    end: u32,
+    properties: T,
+}
+
+fn find_properties<T>(ranges: &[PropertyRange<T>], fc: u32) -> Option<&T> {
+    let index = ranges.partition_point(|range| range.start <= fc);
+    let range = ranges.get(index.checked_sub(1)?)?;
+    (fc < range.end).then_some(&range.properties)
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, DocError> {
+    data.get(offset).copied().ok_or(DocError::Truncated)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, DocError> {
+    let bytes = slice(data, offset, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, DocError> {
+    let bytes = slice(data, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn slice(data: &[u8], offset: usize, length: usize) -> Result<&[u8], DocError> {
+    data.get(offset..offset.checked_add(length).ok_or(DocError::Truncated)?).ok_or(DocError::Truncated)
+}
+
+/// The toggle properties (2.9.341 ToggleOperand) are relative to the style,
+/// which isn't read, so they are taken as relative to "off".
+fn is_toggled_on(operand: u8) -> bool {
+    matches!(operand, 0x01 | 0x81)
+}
+
+/// Converts the Kul of sprmCKul to the ST_Underline of WordprocessingML.
+fn underline_style(kul: u8) -> Option<&'static str> {
+    Some(match kul {
+        0 => return None,
+        2 => "words",
+        3 => "double",
+        4 => "dotted",
+        6 => "thick",
+        7 => "dash",
+        9 => "dotDash",
+        10 => "dotDotDash",
+        11 => "wave",
+        39 => "dashLong",
+        _ => "single",
+    })
+}
+
+/// Calls the `callback` for the sprms of the grpprl, with their operands.
+fn for_each_sprm(grpprl: &[u8], mut callback: impl FnMut(u16, &[u8])) {
+    let mut offset = 0;
+    while let Ok(sprm) = read_u16(grpprl, offset) {
+        offset += 2;
+
+        // The spra of the sprm tells the size of the operand.
+        let (size_length, size) = match sprm >> 13 {
+            0 | 1 => (0, 1),
+            2 | 4 | 5 => (0, 2),
+            3 => (0, 4),
+            7 => (0, 3),
+            _ if sprm == SPRM_T_DEF_TABLE => match read_u16(grpprl, offset) {
+                Ok(size) => (2, (size as usize).saturating_sub(1)),
+                Err(..) => return,
+            },
+            _ => match read_u8(grpprl, offset) {
+                Ok(size) => (1, size as usize),
+                Err(..) => return,
+            },
+        };
+
+        let Ok(operand) = slice(grpprl, offset + size_length, size) else {
+            return;
+        };
+        if !operand.is_empty() {
+            callback(sprm, operand);
+        }
+        offset += size_length + size;
+    }
+}
+
+/// Reads the pieces of the piece table (2.9.178 PlcPcd) in the Clx.
+fn parse_pieces(clx: &[u8]) -> Result<Vec<Piece>, DocError> {
+    // The piece table is preceded by the Prcs, containing the grpprls the
+    // pieces can refer to.
+    let mut offset = 0;
+    while read_u8(clx, offset)? == 0x01 {
+        offset += 3 + read_u16(clx, offset + 1)? as usize;
+    }
+
+    if read_u8(clx, offset)? != 0x02 {
+        return Err(DocError::MissingPieceTable);
+    }
+
+    let size = read_u32(clx, offset + 1)? as usize;
+    let plc = slice(clx, offset + 5, size)?;
+    let count = size.saturating_sub(4) / (4 + PCD_SIZE);
+
+    (0..count)
+        .map(|index| {
+            let pcd = 4 * (count + 1) + PCD_SIZE * index;
+            let fc_compressed = read_u32(plc, pcd + 2)?;
+            let compressed = fc_compressed & FC_COMPRESSED_FLAG != 0;
+            let fc = fc_compressed & 0x3FFF_FFFF;
+
+            Ok(Piece {
+                cp_start: read_u32(plc, 4 * index)?,
+                cp_end: read_u32(plc, 4 * (index + 1))?,
+                fc: if compressed { fc / 2 } else { fc },
+                compressed,
+            })
+        })
+        .collect()
+}
+
+/// Reads the characters of the main document, with the offsets they're
+/// stored at in the WordDocument stream.
+fn read_characters(word_document: &[u8], pieces: &[Piece], ccp_text: u32) -> Result<Vec<(u16, u32)>, DocError> {
+    let mut characters = Vec::new();
+    for piece in pieces {
+        for index in 0..piece.cp_end.min(ccp_text).saturating_sub(piece.cp_start) {
+            characters.push(if piece.compressed {
+                let fc = piece.fc + index;
+                let byte = read_u8(word_document, fc as usize)?;
+                let code = match byte {
+                    0x80..=0x9F => COMPRESSED_CHARACTERS[byte as usize - 0x80],
+                    _ => byte as u16,
+                };
+                (code, fc)
+            } else {
+                let fc = piece.fc + index * 2;
+                (read_u16(word_document, fc as usize)?, fc)
+            });
+        }
+    }
+    Ok(characters)
+}
+
+/// Reads the ranges of the FKPs listed by a PlcBteChpx or a PlcBtePapx,
+/// using `read_properties` to read the properties of an entry of a page.
+fn read_property_ranges<T>(word_document: &[u8], plc_bte: &[u8],
+        read_properties: impl Fn(&[u8], usize, usize) -> Result<T, DocError>) -> Result<Vec<PropertyRange<T>>, DocError> {
+    let mut ranges = Vec::new();
+    let count = plc_bte.len().saturating_sub(4) / 8;
+
+    for index in 0..count {
+        let page_number = read_u32(plc_bte, 4 * (count + 1) + 4 * index)? & 0x003F_FFFF;
+        let page = slice(word_document, page_number as usize * FKP_SIZE, FKP_SIZE)?;
+
+        // The number of entries is stored in the last byte of the page.
+        let entries = page[FKP_SIZE - 1] as usize;
+        for entry in 0..entries {
+            ranges.push(PropertyRange {
+                start: read_u32(page, 4 * entry)?,
+                end: read_u32(page, 4 * (entry + 1))?,
+                properties: read_properties(page, entries, entry)?,
+            });
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// Reads the Chpx of an entry of a ChpxFkp (2.9.33).
+fn read_character_properties(page: &[u8], entries: usize, entry: usize) -> Result<CharacterProperties, DocError> {
+    let mut properties = CharacterProperties::default();
+
+    let offset = read_u8(page, 4 * (entries + 1) + entry)? as usize * 2;
+    if offset != 0 {
+        let size = read_u8(page, offset)? as usize;
+        for_each_sprm(slice(page, offset + 1, size)?, |sprm, operand| properties.apply_sprm(sprm, operand));
+    }
+
+    Ok(properties)
+}
+
+/// Reads the PapxInFkp of an entry of a PapxFkp (2.9.175).
+fn read_paragraph_properties(page: &[u8], entries: usize, entry: usize) -> Result<ParagraphProperties, DocError> {
+    let mut properties = ParagraphProperties::default();
+
+    let offset = read_u8(page, 4 * (entries + 1) + BX_PAP_SIZE * entry)? as usize * 2;
+    if offset != 0 {
+        let (start, size) = match read_u8(page, offset)? {
+            0 => (offset + 2, read_u8(page, offset + 1)? as usize * 2),
+            cb => (offset + 1, cb as usize * 2 - 1),
+        };
+
+        // The sprms follow the istd of the paragraph style.
+        let grpprl = slice(page, start, size)?.get(2..).unwrap_or_default();
+        for_each_sprm(grpprl, |sprm, operand| properties.apply_sprm(sprm, operand));
+    }
+
+    Ok(properties)
+}
+
+fn parse_document(fib: &Fib, word_document: &[u8], table: &[u8]) -> Result<BinaryDocument, DocError> {
+    let table_part = |(offset, size): (u32, u32)| slice(table, offset as usize, size as usize);
+
+    let pieces = parse_pieces(table_part(fib.clx)?)?;
+    let characters = read_characters(word_document, &pieces, fib.ccp_text)?;
+    let character_ranges = read_property_ranges(word_document, table_part(fib.character_properties)?, read_character_properties)?;
+    let paragraph_ranges = read_property_ranges(word_document, table_part(fib.paragraph_properties)?, read_paragraph_properties)?;
+
+    let mut document = BinaryDocument::default();
+    let mut runs: Vec<Run> = Vec::new();
+
+    // Whether the separator of the fields that are open was reached, after
+    // which their result is shown instead of their instruction.
+    let mut fields = Vec::new();
+
+    for (code, fc) in characters {
+        match code {
+            FIELD_BEGIN => fields.push(false),
+            FIELD_SEPARATOR => if let Some(separated) = fields.last_mut() {
+                *separated = true;
+            },
+            FIELD_END => {
+                fields.pop();
+            }
+            _ => (),
+        }
+
+        if matches!(code, FIELD_BEGIN | FIELD_SEPARATOR | FIELD_END) || fields.contains(&false) {
+            continue;
+        }
+
+        let code = match code {
+            // The cells of tables are shown as paragraphs.
+            PARAGRAPH_MARK | CELL_MARK => {
+                document.paragraphs.push(Paragraph {
+                    properties: find_properties(&paragraph_ranges, fc).cloned().unwrap_or_default(),
+                    runs: std::mem::take(&mut runs),
+                });
+                continue;
+            }
+            TAB | LINE_BREAK | PAGE_BREAK => code,
+            NON_BREAKING_HYPHEN => 0x2011,
+
+            // The anchors of pictures, notes and comments, and optional
+            // hyphens.
+            0..=0x1F => continue,
+            _ => code,
+        };
+
+        let properties = find_properties(&character_ranges, fc).cloned().unwrap_or_default();
+        match runs.last_mut() {
+            Some(run) if run.properties == properties => run.text.push(code),
+            _ => runs.push(Run { properties, text: vec![code] }),
+        }
+    }
+
+    // The text should end with a paragraph mark.
+    if !runs.is_empty() {
+        document.paragraphs.push(Paragraph { properties: Default::default(), runs });
+    }
+
+    Ok(document)
+}
+
+fn read_stream(file: &mut cfb::CompoundFile<std::fs::File>, name: &str) -> Result<Vec<u8>, DocError> {
+    let mut data = Vec::new();
+    file.open_stream(name)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Checks if the file is a Word Binary File that isn't password-protected,
+/// which can be read by [read_doc_file].
+pub fn is_readable_word_binary_file(path: &Path) -> bool {
+    let Ok(mut file) = cfb::open(path) else {
+        return false;
+    };
+
+    read_stream(&mut file, "WordDocument")
+        .and_then(|word_document| Fib::parse(&word_document))
+        .is_ok_and(|fib| !fib.encrypted)
+}
+
+/// Reads the main document of the Word Binary File at `path`.
+pub fn read_doc_file(path: &Path) -> Result<BinaryDocument, DocError> {
+    let mut file = cfb::open(path)?;
+    let word_document = read_stream(&mut file, "WordDocument")?;
+
+    let fib = Fib::parse(&word_document)?;
+    if fib.encrypted {
+        return Err(DocError::Encrypted);
+    }
+
+    let table = read_stream(&mut file, fib.table_stream_name)?;
+    parse_document(&fib, &word_document, &table)
+}
+
+/// Reads the Word Binary File and lays it out. The document has no source
+/// package, so it can't be saved.
+pub fn process_doc_file(path: &Path, text_calculator: &mut dyn TextCalculator, fallback_font: &str,
+        revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let document_text = read_doc_file(path)?.to_document_xml();
+
+    let mut result = process_converted_document(&document_text, STYLES_PART, None, &Relationships::empty(),
+        text_calculator, fallback_font, revision_view, observer)?;
+    result.diagnostics.insert(0, Diagnostic::info("WordDocument",
+        String::from("legacy Word document, opened read-only without its styles, tables, pictures, headers and footers")));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use roxmltree as xml;
+
+    use super::*;
+
+    /// Where the text is stored in the WordDocument streams of the tests.
+    const TEXT_OFFSET: usize = 1024;
+
+    fn write_u16(data: &mut [u8], offset: usize, value: u16) {
+        data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Creates a WordDocument stream of `size` bytes starting with a FIB of
+    /// Word 97, with the given pairs of the FibRgFcLcb97.
+    fn create_word_document(size: usize, ccp_text: u32, fc_lcbs: &[(usize, u32, u32)]) -> Vec<u8> {
+        let mut data = vec![0; size];
+        write_u16(&mut data, 0, FIB_IDENTIFIER);
+        write_u16(&mut data, 0x0A, FIB_FLAG_WHICH_TABLE_STREAM);
+
+        // 14 shorts, 22 longs and 93 pairs.
+        write_u16(&mut data, 32, 14);
+        write_u16(&mut data, 62, 22);
+        write_u32(&mut data, 64 + FIB_RG_LW_CCP_TEXT, ccp_text);
+        write_u16(&mut data, 152, 93);
+        for (index, fc, lcb) in fc_lcbs {
+            write_u32(&mut data, 154 + index * 8, *fc);
+            write_u32(&mut data, 154 + index * 8 + 4, *lcb);
+        }
+        data
+    }
+
+    /// Creates a Clx with a single piece of `length` characters.
+    fn create_clx(length: u32, fc_compressed: u32) -> Vec<u8> {
+        let mut clx = vec![0; 21];
+        clx[0] = 0x02;
+        write_u32(&mut clx, 1, 16);
+        write_u32(&mut clx, 9, length);
+        write_u32(&mut clx, 15, fc_compressed);
+        clx
+    }
+
+    fn text_of(run: &Run) -> String {
+        String::from_utf16_lossy(&run.text)
+    }
+
+    #[test]
+    fn text_is_read_with_its_formatting() {
+        let text: Vec<u16> = "Hello World\rSecond\r".encode_utf16().collect();
+        let text_end = (TEXT_OFFSET + text.len() * 2) as u32;
+
+        let mut table = create_clx(text.len() as u32, TEXT_OFFSET as u32);
+        table.resize(64, 0);
+        // The PlcBteChpx and PlcBtePapx, of a page each.
+        for (offset, page_number) in [(32, 3), (48, 4)] {
+            write_u32(&mut table, offset, TEXT_OFFSET as u32);
+            write_u32(&mut table, offset + 4, text_end);
+            write_u32(&mut table, offset + 8, page_number);
+        }
+
+        let mut word_document = create_word_document(5 * FKP_SIZE, text.len() as u32, &[
+            (FC_LCB_PLCF_BTE_CHPX, 32, 12),
+            (FC_LCB_PLCF_BTE_PAPX, 48, 12),
+            (FC_LCB_CLX, 0, 21),
+        ]);
+        for (index, code) in text.iter().enumerate() {
+            write_u16(&mut word_document, TEXT_OFFSET + index * 2, *code);
+        }
+
+        // "Hello" is bold.
+        let chpx_page = 3 * FKP_SIZE;
+        for (index, fc) in [TEXT_OFFSET as u32, TEXT_OFFSET as u32 + 10, text_end].into_iter().enumerate() {
+            write_u32(&mut word_document, chpx_page + index * 4, fc);
+        }
+        word_document[chpx_page + 12] = 250;
+        word_document[chpx_page + 500..chpx_page + 504].copy_from_slice(&[3, 0x35, 0x08, 0x01]);
+        word_document[chpx_page + FKP_SIZE - 1] = 2;
+
+        // The first paragraph is centered.
+        let papx_page = 4 * FKP_SIZE;
+        for (index, fc) in [TEXT_OFFSET as u32, TEXT_OFFSET as u32 + 24, text_end].into_iter().enumerate() {
+            write_u32(&mut word_document, papx_page + index * 4, fc);
+        }
+        word_document[papx_page + 12] = 240;
+        word_document[papx_page + 480..papx_page + 486].copy_from_slice(&[3, 0, 0, 0x03, 0x24, 0x01]);
+        word_document[papx_page + FKP_SIZE - 1] = 2;
+
+        let fib = Fib::parse(&word_document).unwrap();
+        assert_eq!(fib.table_stream_name, "1Table");
+        let document = parse_document(&fib, &word_document, &table).unwrap();
+
+        assert_eq!(document.paragraphs.len(), 2);
+        let first = &document.paragraphs[0];
+        assert_eq!(first.properties.justification, Some("center"));
+        assert_eq!(first.runs.iter().map(text_of).collect::<Vec<_>>(), ["Hello", " World"]);
+        assert!(first.runs[0].properties.bold);
+        assert!(!first.runs[1].properties.bold);
+
+        let second = &document.paragraphs[1];
+        assert_eq!(second.properties, ParagraphProperties::default());
+        assert_eq!(second.runs.iter().map(text_of).collect::<Vec<_>>(), ["Second"]);
+
+        let document_text = document.to_document_xml();
+        let xml_document = xml::Document::parse(&document_text).unwrap();
+        let names: Vec<_> = xml_document.descendants()
+            .filter(|node| node.is_element())
+            .map(|node| node.tag_name().name())
+            .collect();
+        assert!(names.starts_with(&["document", "body", "p", "pPr", "jc", "r", "rPr", "b", "t", "r", "t", "p", "r", "t", "sectPr"]));
+    }
+
+    #[test]
+    fn compressed_text_shows_the_results_of_fields() {
+        let text = b"A\x13 PAGE \x141\x15\x93B\x94\tC\r";
+
+        let table = create_clx(text.len() as u32, (TEXT_OFFSET as u32 * 2) | FC_COMPRESSED_FLAG);
+        let mut word_document = create_word_document(3 * FKP_SIZE, text.len() as u32, &[(FC_LCB_CLX, 0, 21)]);
+        word_document[TEXT_OFFSET..TEXT_OFFSET + text.len()].copy_from_slice(text);
+
+        let fib = Fib::parse(&word_document).unwrap();
+        let document = parse_document(&fib, &word_document, &table).unwrap();
+
+        assert_eq!(document.paragraphs.len(), 1);
+        assert_eq!(document.paragraphs[0].runs.iter().map(text_of).collect::<Vec<_>>(), ["A1\u{201C}B\u{201D}\tC"]);
+        assert!(document.to_document_xml().contains(r#"<w:t xml:space="preserve">A1“B”</w:t><w:tab/><w:t xml:space="preserve">C</w:t>"#));
+    }
+
+    #[test]
+    fn streams_without_a_fib_are_rejected() {
+        assert!(matches!(Fib::parse(&[0; 1024]), Err(DocError::NotAWordDocument)));
+        assert!(matches!(Fib::parse(&[0xEC, 0xA5, 0, 0]), Err(DocError::Truncated)));
+    }
+}
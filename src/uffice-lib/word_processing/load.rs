@@ -0,0 +1,439 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Loads a document from a file: a WordprocessingML package, following the
+// relationships of its main document part to the other parts, or one of the
+// other formats that are converted when they are loaded.
+
+use std::{path::PathBuf, rc::Rc, sync::Arc};
+
+use roxmltree as xml;
+
+use crate::{
+    drawing_ml,
+    error::Error,
+    fonts::DocumentFontSource,
+    gui::painter::TextCalculator,
+    package::{self, load_archive_file_to_string},
+    profile_expr,
+    profiling::Profiler,
+    relationships::{
+        Relationships,
+        RelationshipType,
+    },
+    style::StyleManager,
+    wp::{
+        self,
+        diagnostics::Diagnostic,
+        numbering::NumberingManager,
+        revisions::RevisionView,
+    },
+};
+
+use super::{DocumentResult, LoadObserver};
+
+/// The style definitions used when the document doesn't have them, which only
+/// have the defaults of [StyleManager].
+const DEFAULT_STYLES_PART: &str = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"/>"#;
+
+/// Identifies the contents of a part in the archive, using the metadata of
+/// the ZIP archive, so a part doesn't have to be read to know it changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PartKey {
+    size: u64,
+    crc32: u32,
+}
+
+impl PartKey {
+    fn from_archive(archive: &mut zip::ZipArchive<std::fs::File>, part_name: Option<&str>) -> Option<Self> {
+        let file = archive.by_name(part_name?).ok()?;
+        Some(Self {
+            size: file.size(),
+            crc32: file.crc32(),
+        })
+    }
+}
+
+struct CachedPart<K, T> {
+    key: K,
+    value: T,
+}
+
+/// Get the cached value when the key is the same, or otherwise create and
+/// cache it.
+fn get_or_create_cached<K: PartialEq, T: Clone>(slot: &mut Option<CachedPart<K, T>>, key: K, create: impl FnOnce() -> T) -> T {
+    if let Some(cached) = slot {
+        if cached.key == key {
+            return cached.value.clone();
+        }
+    }
+
+    let value = create();
+    *slot = Some(CachedPart { key, value: value.clone() });
+    value
+}
+
+/// Like [get_or_create_cached], but a value that failed to be created isn't
+/// cached.
+fn try_get_or_create_cached<K: PartialEq, T: Clone>(slot: &mut Option<CachedPart<K, T>>, key: K,
+        create: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    if let Some(cached) = slot {
+        if cached.key == key {
+            return Ok(cached.value.clone());
+        }
+    }
+
+    let value = create()?;
+    *slot = Some(CachedPart { key, value: value.clone() });
+    Ok(value)
+}
+
+/// The parsed styles, numbering definitions and theme of a document, which
+/// are reused when the document is loaded again (e.g. when it is reloaded),
+/// as long as those parts didn't change.
+#[derive(Default)]
+pub struct PartCache {
+    numbering: Option<CachedPart<Option<PartKey>, NumberingManager>>,
+    theme: Option<CachedPart<Option<PartKey>, (drawing_ml::style::StyleSettings, Vec<Diagnostic>)>>,
+
+    /// The styles depend on the numbering definitions and the theme, so
+    /// those are part of the key as well.
+    styles: Option<CachedPart<[Option<PartKey>; 3], Rc<StyleManager>>>,
+}
+
+/// Finds the name of the part that is related to the source part with the
+/// given relationship type, e.g. the styles part of the document part.
+fn find_related_part(relationships: &Relationships, source_part_name: &str, relation_type: RelationshipType) -> Option<String> {
+    let relationship = relationships.find_by_type(relation_type)?.borrow();
+    Some(package::resolve_relationship_target(source_part_name, &relationship.target))
+}
+
+/// Loads the text of the part that is related to the source part with the
+/// given relationship type, if there is one.
+fn load_related_part(archive: &mut zip::ZipArchive<std::fs::File>, relationships: &Relationships, source_part_name: &str,
+        relation_type: RelationshipType) -> Result<Option<String>, Error> {
+    let Some(part) = find_related_part(relationships, source_part_name, relation_type) else {
+        return Ok(None);
+    };
+    Ok(load_archive_file_to_string(archive, &part)?.map(Rc::unwrap_or_clone))
+}
+
+/// Loads the font table part (17.8.3.10 fonts), and the fonts embedded in the
+/// package, which are relationships of the font table part.
+fn load_document_fonts(archive: &mut zip::ZipArchive<std::fs::File>, part_name: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<Arc<DocumentFontSource>> {
+    let text = match load_archive_file_to_string(archive, part_name) {
+        Ok(text) => text?,
+        Err(e) => {
+            diagnostics.push(Diagnostic::error("w:fonts", format!("failed to read the font table part: {}", e)));
+            return None;
+        }
+    };
+    let font_table = match xml::Document::parse(&text) {
+        Ok(document) => wp::font_table::FontTable::from_xml(&document, diagnostics),
+        Err(e) => {
+            diagnostics.push(Diagnostic::error("w:fonts", format!("failed to parse the font table part: {}", e)));
+            return None;
+        }
+    };
+
+    let relationships_part = package::relationships_part_name(part_name);
+    let relationships = match load_archive_file_to_string(archive, &relationships_part) {
+        Ok(Some(text)) => xml::Document::parse(&text).map_err(Error::from)
+            .and_then(|document| Relationships::load_xml(&document, archive, part_name))
+            .unwrap_or_else(|e| {
+                diagnostics.push(Diagnostic::error("w:fonts", format!("failed to load the relationships of the font table: {}", e)));
+                Relationships::empty()
+            }),
+        Ok(None) => Relationships::empty(),
+        Err(e) => {
+            diagnostics.push(Diagnostic::error("w:fonts", format!("failed to load the relationships of the font table: {}", e)));
+            Relationships::empty()
+        }
+    };
+
+    let mut fonts = DocumentFontSource::new();
+    for font in font_table.fonts() {
+        fonts.add_font_definition(font);
+
+        for reference in &font.embedded {
+            let Some(relationship) = relationships.find(&reference.relationship_id) else {
+                diagnostics.push(Diagnostic::warning("w:fonts", format!("embedded font \"{}\" not found: no relationship with id \"{}\"",
+                    font.name, reference.relationship_id)));
+                continue;
+            };
+
+            let mut data = relationship.borrow().data.clone();
+            if data.is_empty() {
+                diagnostics.push(Diagnostic::warning("w:fonts", format!("embedded font \"{}\" missing: {}", font.name, relationship.borrow().target)));
+                continue;
+            }
+
+            if let Some(font_key) = &reference.font_key {
+                wp::font_table::deobfuscate_font(&mut data, font_key);
+            }
+            fonts.add_embedded_font(&font.name, reference.style, data);
+        }
+    }
+
+    Some(Arc::new(fonts))
+}
+
+fn draw_document(archive_path: &str, text_calculator: &mut dyn TextCalculator, fallback_font: &str, part_cache: Option<&mut PartCache>,
+        revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let path = std::path::Path::new(archive_path);
+    if let Some(format) = super::text::TextFormat::from_path(path) {
+        return super::text::process_text_file(path, format, text_calculator, fallback_font, revision_view, observer);
+    }
+
+    let mut profiler = Profiler::new(String::from("Document Rendering"));
+
+    let archive_file = profile_expr!(profiler, "Open Archive", std::fs::File::open(archive_path)?);
+
+    let mut archive = profile_expr!(profiler, "Read Archive", zip::ZipArchive::new(archive_file)?);
+
+    let mut diagnostics = Vec::new();
+
+    let main_document_part = profile_expr!(profiler, "Find Main Document Part", package::find_main_document_part(&mut archive));
+
+    let document_relationships;
+    {
+        let _frame = profiler.frame(String::from("Document Relationships"));
+
+        let relationships_part = package::relationships_part_name(&main_document_part);
+        let txt = load_archive_file_to_string(&mut archive, &relationships_part)?
+                .ok_or_else(|| Error::MissingPart(relationships_part.clone()))?;
+        if let Ok(document) = xml::Document::parse(&txt) {
+            document_relationships = Relationships::load_xml(&document, &mut archive, &main_document_part)
+                .map_err(|e| e.in_part(&relationships_part))?;
+        } else {
+            println!("[Relationships] ({}) Error!", relationships_part);
+            document_relationships = Relationships::empty();
+        }
+    }
+
+    let mut uncached_parts = PartCache::default();
+    let part_cache = part_cache.unwrap_or(&mut uncached_parts);
+
+    let numbering_part = find_related_part(&document_relationships, &main_document_part, RelationshipType::Numbering);
+    let numbering_key = PartKey::from_archive(&mut archive, numbering_part.as_deref());
+    let mut numbering_manager = try_get_or_create_cached(&mut part_cache.numbering, numbering_key, || {
+        let _frame = profiler.frame(String::from("Numbering Definitions"));
+
+        let Some(part) = numbering_part else {
+            return Ok(NumberingManager::new());
+        };
+        match load_archive_file_to_string(&mut archive, &part)? {
+            Some(numbering_document_text) => xml::Document::parse(&numbering_document_text).map_err(Error::from)
+                .and_then(|numbering_document| NumberingManager::from_xml(&numbering_document))
+                .map_err(|e| e.in_part(&part)),
+            None => Ok(NumberingManager::new()),
+        }
+    })?;
+
+    // The definitions are shared with the cache, so the numbering of the
+    // previous load has to be restarted.
+    numbering_manager.reset_values();
+
+    let theme_part = find_related_part(&document_relationships, &main_document_part, RelationshipType::Theme);
+    let theme_key = PartKey::from_archive(&mut archive, theme_part.as_deref());
+    let (theme_settings, mut theme_diagnostics) = get_or_create_cached(&mut part_cache.theme, theme_key, || {
+        let _frame = profiler.frame(String::from("DrawingML Style Settings"));
+
+        let style_document_text = match theme_part.map(|part| load_archive_file_to_string(&mut archive, &part)) {
+            Some(Ok(text)) => text,
+            Some(Err(e)) => return (Default::default(), vec![Diagnostic::error("a:theme", format!("failed to read the theme part: {}", e))]),
+            None => None,
+        };
+
+        if let Some(style_document_text) = style_document_text {
+            let style_document = match xml::Document::parse(&style_document_text) {
+                Ok(style_document) => style_document,
+                Err(e) => return (Default::default(), vec![Diagnostic::error("a:theme", format!("failed to parse the theme part: {}", e))]),
+            };
+            let mut diagnostics = Vec::new();
+            match drawing_ml::style::StyleSettings::from_xml(&style_document.root_element(), &mut diagnostics) {
+                Ok(theme_settings) => (theme_settings, diagnostics),
+                Err(e) => {
+                    diagnostics.push(Diagnostic::error("a:theme", format!("failed to parse the theme part: {:?}", e)));
+                    (Default::default(), diagnostics)
+                }
+            }
+        } else {
+            (Default::default(), vec![Diagnostic::warning("a:theme", String::from("document has no theme, theme fonts will be unavailable"))])
+        }
+    });
+    diagnostics.append(&mut theme_diagnostics);
+
+    let styles_part = find_related_part(&document_relationships, &main_document_part, RelationshipType::Styles);
+    let styles_key = [PartKey::from_archive(&mut archive, styles_part.as_deref()), numbering_key, theme_key];
+    let style_manager = try_get_or_create_cached(&mut part_cache.styles, styles_key, || {
+        let _frame = profiler.frame(String::from("Style Definitions"));
+
+        let styles_document_text = match &styles_part {
+            Some(part) => load_archive_file_to_string(&mut archive, part)?,
+            None => None,
+        };
+        let Some(styles_document_text) = styles_document_text else {
+            let styles_document = xml::Document::parse(DEFAULT_STYLES_PART).unwrap();
+            return StyleManager::from_document(&styles_document, &numbering_manager, &theme_settings).map(Rc::new);
+        };
+
+        let part = styles_part.as_deref().unwrap_or_default();
+        xml::Document::parse(&styles_document_text).map_err(Error::from)
+            .and_then(|styles_document| StyleManager::from_document(&styles_document, &numbering_manager, &theme_settings))
+            .map(Rc::new)
+            .map_err(|e| e.in_part(part))
+    })?;
+
+    if styles_key[0].is_none() {
+        let message = match &styles_part {
+            Some(part) => format!("the style definitions part {} is missing, the default styles are used", part),
+            None => String::from("document has no style definitions, the default styles are used"),
+        };
+        diagnostics.push(Diagnostic::warning("w:styles", message));
+    }
+
+    let mut related_parts = super::RelatedParts::default();
+    {
+        let _frame = profiler.frame(String::from("Headers and Footers"));
+
+        let relationships = document_relationships.find_all_by_type(RelationshipType::Header)
+            .chain(document_relationships.find_all_by_type(RelationshipType::Footer));
+        for relationship in relationships {
+            let relationship = relationship.borrow();
+            let part_name = package::resolve_relationship_target(&main_document_part, &relationship.target);
+            match load_archive_file_to_string(&mut archive, &part_name)? {
+                Some(text) => {
+                    related_parts.headers_footers.insert(relationship.id.clone(), Rc::unwrap_or_clone(text));
+                }
+                None => diagnostics.push(Diagnostic::warning("w:hdr", format!("header or footer part missing: {}", part_name))),
+            }
+        }
+    }
+
+    related_parts.footnotes = load_related_part(&mut archive, &document_relationships, &main_document_part, RelationshipType::Footnotes)?;
+    related_parts.endnotes = load_related_part(&mut archive, &document_relationships, &main_document_part, RelationshipType::Endnotes)?;
+    related_parts.comments = load_related_part(&mut archive, &document_relationships, &main_document_part, RelationshipType::Comments)?;
+    related_parts.settings = load_related_part(&mut archive, &document_relationships, &main_document_part, RelationshipType::Settings)?;
+
+    let mut document_properties = wp::document_properties::DocumentProperties::new();
+    if let Some(txt) = load_archive_file_to_string(&mut archive, "docProps/core.xml")? {
+        if let Ok(document) = xml::Document::parse(&txt) {
+            document_properties.import_core_file_properties_part(&document);
+        }
+    }
+
+    let document_fonts = find_related_part(&document_relationships, &main_document_part, RelationshipType::FontTable)
+        .and_then(|part| {
+            let _frame = profiler.frame(String::from("Font Table"));
+            load_document_fonts(&mut archive, &part, &mut diagnostics)
+        });
+    text_calculator.set_document_fonts(document_fonts.clone());
+
+    let _frame = profiler.frame(String::from("Document"));
+    let document_text = load_archive_file_to_string(&mut archive, &main_document_part)?
+            .ok_or_else(|| Error::MissingPart(main_document_part.clone()))?;
+    let document = xml::Document::parse(&document_text)
+            .map_err(|e| Error::from(e).in_part(&main_document_part))?;
+
+    let mut result = super::process_document(&document, &style_manager, &document_relationships, &related_parts, numbering_manager, document_properties, text_calculator, theme_settings, fallback_font, revision_view, observer)
+            .map_err(|e| e.in_part(&main_document_part))?;
+    result.document.source = Some(wp::SourcePackage {
+        path: PathBuf::from(archive_path),
+        main_document_part,
+    });
+    result.document.fonts = document_fonts;
+
+    diagnostics.append(&mut result.diagnostics);
+    result.diagnostics = diagnostics;
+    Ok(result)
+}
+
+/// Loads and lays out the document, showing its tracked changes as the
+/// `revision_view` selects. Legacy Word Binary Files, OpenDocument Text and
+/// plain text documents are converted first. A document that is too damaged
+/// to be shown is an error.
+pub fn load_document(archive_path: &str, text_calculator: &mut dyn TextCalculator, fallback_font: &str, part_cache: Option<&mut PartCache>,
+        revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let path = std::path::Path::new(archive_path);
+    if super::doc::is_readable_word_binary_file(path) {
+        super::doc::process_doc_file(path, text_calculator, fallback_font, revision_view, observer)
+    } else if super::odf::is_open_document_text_path(path) {
+        super::odf::process_odt_file(path, text_calculator, fallback_font, revision_view, observer)
+    } else {
+        draw_document(archive_path, text_calculator, fallback_font, part_cache, revision_view, observer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::gui::painter::mock::MockTextCalculator;
+
+    #[test]
+    fn cached_parts_are_invalidated_when_the_key_changes() {
+        let mut slot = None;
+        let key = PartKey { size: 10, crc32: 0xCAFE };
+
+        assert_eq!(get_or_create_cached(&mut slot, key, || 1), 1);
+        assert_eq!(get_or_create_cached(&mut slot, key, || 2), 1);
+        assert_eq!(get_or_create_cached(&mut slot, PartKey { crc32: 0xBEEF, ..key }, || 3), 3);
+    }
+
+    /// Writes the parts to a package in a directory in the temporary directory.
+    fn write_package(directory: &str, parts: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("renamed.docx");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        for (name, text) in parts {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(text.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn parts_are_found_through_the_relationships_of_the_main_document_part() {
+        let parts = [
+            ("_rels/.rels", r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="content/main.xml"/></Relationships>"#),
+            ("content/_rels/main.xml.rels", concat!(
+                r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+                r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="definitions/styles1.xml"/>"#,
+                r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings" Target="/preferences.xml"/>"#,
+                r#"</Relationships>"#,
+            )),
+            ("content/main.xml", r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p/></w:body></w:document>"#),
+            ("content/definitions/styles1.xml", r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"/>"#),
+            ("preferences.xml", r#"<w:settings xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:mirrorMargins/><w:evenAndOddHeaders/></w:settings>"#),
+        ];
+
+        let path = write_package("uffice-related-parts-test", &parts);
+        let result = load_document(path.to_str().unwrap(), &mut MockTextCalculator::new(10.0, 0.0), "Calibri", None,
+            RevisionView::Markup, &mut |_: f32| ()).unwrap();
+        let source = result.document.source.as_ref().unwrap();
+        assert_eq!(source.main_document_part, "content/main.xml");
+        assert!(result.document.settings.mirror_margins);
+        assert!(result.document.settings.even_and_odd_headers);
+    }
+
+    #[test]
+    fn documents_without_style_definitions_use_the_default_styles() {
+        let parts = [
+            ("_rels/.rels", r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#),
+            ("word/_rels/document.xml.rels", r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#),
+            ("word/document.xml", r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p><w:r><w:t>a</w:t></w:r></w:p></w:body></w:document>"#),
+        ];
+
+        let path = write_package("uffice-missing-styles-test", &parts);
+        let result = load_document(path.to_str().unwrap(), &mut MockTextCalculator::new(10.0, 0.0), "Calibri", None,
+            RevisionView::Markup, &mut |_: f32| ()).unwrap();
+
+        let warning = result.diagnostics.iter().find(|diagnostic| diagnostic.source == "w:styles").unwrap();
+        assert_eq!(warning.message, "the style definitions part word/styles.xml is missing, the default styles are used");
+        assert_eq!(result.root_node.find_all(|node| matches!(node.data, wp::NodeData::TextPart(..))).len(), 1);
+    }
+}
@@ -0,0 +1,1664 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Opening OpenDocument Text (.odt) files, as written by LibreOffice. The
+// content.xml and styles.xml parts of the package are converted to
+// WordprocessingML parts, which are then laid out like any other document.
+//
+// The paragraphs, headings, spans, links, lists and tables are converted with
+// the formatting of their styles, as are the size and margins of the pages.
+// Frames, pictures, notes, headers and footers are left out, and the document
+// is opened read-only.
+
+use std::{collections::HashMap, io::Read, path::Path, rc::Rc};
+
+use roxmltree as xml;
+
+use crate::{
+    error::Error,
+    gui::painter::TextCalculator,
+    relationships::{
+        Relationship,
+        RelationshipType,
+        Relationships,
+    },
+    wp::{
+        diagnostics::Diagnostic,
+        revisions::RevisionView,
+        save::escape,
+    },
+};
+
+use super::{
+    CONVERTED_DOCUMENT_END,
+    CONVERTED_DOCUMENT_START,
+    DocumentResult,
+    LoadObserver,
+    process_converted_document,
+    text::{hyperlink_relationship_id, write_text},
+};
+
+const XMLNS_ODF_FO: &str = "urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0";
+const XMLNS_ODF_OFFICE: &str = "urn:oasis:names:tc:opendocument:xmlns:office:1.0";
+const XMLNS_ODF_STYLE: &str = "urn:oasis:names:tc:opendocument:xmlns:style:1.0";
+const XMLNS_ODF_SVG: &str = "urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0";
+const XMLNS_ODF_TABLE: &str = "urn:oasis:names:tc:opendocument:xmlns:table:1.0";
+const XMLNS_ODF_TEXT: &str = "urn:oasis:names:tc:opendocument:xmlns:text:1.0";
+const XMLNS_XLINK: &str = "http://www.w3.org/1999/xlink";
+const XMLNS_XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The start of the media type of text documents, which is followed by
+/// "-template" for templates.
+const ODT_MEDIA_TYPE: &str = "application/vnd.oasis.opendocument.text";
+
+/// The font size of text without one, in points (20.183 fo:font-size).
+const DEFAULT_FONT_SIZE: f32 = 12.0;
+
+/// The width between the margins of the pages of [CONVERTED_DOCUMENT_END], in
+/// points, which is shared by the columns of tables without a width.
+const DEFAULT_TEXT_WIDTH: f32 = 451.3;
+
+/// The color of links without a text style, which is the one of the
+/// "Internet Link" style of LibreOffice.
+const DEFAULT_LINK_COLOR: &str = "000080";
+
+/// The number of levels of a list, which is the number of levels a numbering
+/// definition can have (17.9.3 ilvl), while ODF has ten.
+const LIST_LEVELS: usize = 9;
+
+/// The indentation of each level of a list without a list style, and the part
+/// of it the bullet hangs into, in points.
+const LIST_INDENTATION: f32 = 36.0;
+const LIST_HANGING_INDENTATION: f32 = 18.0;
+
+/// The bullets of the levels of lists without a list style, starting again
+/// after the last one.
+const LIST_BULLETS: [&str; 3] = ["\u{2022}", "\u{25E6}", "\u{25AA}"];
+
+/// The number of times a column, row or cell is repeated at most, since
+/// spreadsheets repeat the empty ones up to the maximum size of a table.
+const MAX_REPEAT_COUNT: usize = 256;
+
+/// The number of styles of which the properties are inherited at most, since
+/// the parents of the styles could form a cycle.
+const MAX_STYLE_DEPTH: usize = 32;
+
+/// The elements that contain blocks, such as the body of a table of contents
+/// (8.3 text:table-of-content) of which the entries are paragraphs.
+const CONTAINER_ELEMENTS: &[&str] = &[
+    "alphabetical-index",
+    "bibliography",
+    "illustration-index",
+    "index-body",
+    "index-title",
+    "object-index",
+    "section",
+    "table-index",
+    "table-of-content",
+    "user-index",
+];
+
+#[derive(Debug)]
+pub enum OdfError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    Xml(xml::Error),
+
+    /// The package doesn't contain the content.xml part.
+    MissingContent,
+
+    /// The package is another kind of OpenDocument file, such as a
+    /// spreadsheet.
+    NotATextDocument,
+}
+
+impl From<std::io::Error> for OdfError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<zip::result::ZipError> for OdfError {
+    fn from(error: zip::result::ZipError) -> Self {
+        Self::Zip(error)
+    }
+}
+
+impl From<xml::Error> for OdfError {
+    fn from(error: xml::Error) -> Self {
+        Self::Xml(error)
+    }
+}
+
+/// The families of the styles that are converted (19.480 style:family).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum StyleFamily {
+    Paragraph,
+    Text,
+    TableColumn,
+    TableCell,
+}
+
+impl StyleFamily {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "paragraph" => Some(Self::Paragraph),
+            "text" => Some(Self::Text),
+            "table-column" => Some(Self::TableColumn),
+            "table-cell" => Some(Self::TableCell),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum VerticalPosition {
+    Baseline,
+    Superscript,
+    Subscript,
+}
+
+/// The properties of style:text-properties (17.10), of which the ones that
+/// aren't set are inherited.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct TextProperties {
+    font: Option<String>,
+
+    /// The font size in points.
+    size: Option<f32>,
+
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underline: Option<bool>,
+
+    /// The color as six hexadecimal digits.
+    color: Option<String>,
+
+    position: Option<VerticalPosition>,
+}
+
+impl TextProperties {
+    fn parse(properties: &xml::Node, font_faces: &HashMap<String, String>) -> Self {
+        let attribute = |namespace: &str, name: &str| properties.attribute((namespace, name));
+
+        Self {
+            // The font name refers to a font face declaration (16.23
+            // style:font-face), which has the name of the family.
+            font: attribute(XMLNS_ODF_STYLE, "font-name")
+                .map(|name| font_faces.get(name).cloned().unwrap_or_else(|| String::from(name)))
+                .or_else(|| attribute(XMLNS_ODF_FO, "font-family").map(parse_font_family)),
+            size: attribute(XMLNS_ODF_FO, "font-size").and_then(parse_length),
+            bold: attribute(XMLNS_ODF_FO, "font-weight")
+                .map(|weight| weight == "bold" || weight.parse::<u32>().is_ok_and(|weight| weight >= 600)),
+            italic: attribute(XMLNS_ODF_FO, "font-style").map(|style| style != "normal"),
+            underline: attribute(XMLNS_ODF_STYLE, "text-underline-style").map(|style| style != "none"),
+            color: attribute(XMLNS_ODF_FO, "color").and_then(parse_color),
+            position: attribute(XMLNS_ODF_STYLE, "text-position").map(parse_text_position),
+        }
+    }
+
+    fn inherit_from(&mut self, parent: &Self) {
+        self.font = self.font.take().or_else(|| parent.font.clone());
+        self.size = self.size.or(parent.size);
+        self.bold = self.bold.or(parent.bold);
+        self.italic = self.italic.or(parent.italic);
+        self.underline = self.underline.or(parent.underline);
+        self.color = self.color.take().or_else(|| parent.color.clone());
+        self.position = self.position.or(parent.position);
+    }
+
+    /// Writes the properties as the contents of a <w:rPr> element. Since
+    /// <w:b> and <w:i> toggle the properties of the styles, the properties
+    /// are always written in full, and the styles don't have any.
+    fn write(&self, output: &mut String) {
+        if let Some(font) = &self.font {
+            *output += &format!(r#"<w:rFonts w:ascii="{0}" w:hAnsi="{0}" w:cs="{0}"/>"#, escape(font));
+        }
+        if self.bold == Some(true) {
+            *output += "<w:b/>";
+        }
+        if self.italic == Some(true) {
+            *output += "<w:i/>";
+        }
+        if let Some(color) = &self.color {
+            *output += &format!(r#"<w:color w:val="{}"/>"#, color);
+        }
+        if let Some(size) = self.size {
+            let half_points = (size * 2.0).round().max(1.0) as u32;
+            *output += &format!(r#"<w:sz w:val="{0}"/><w:szCs w:val="{0}"/>"#, half_points);
+        }
+        if self.underline == Some(true) {
+            *output += r#"<w:u w:val="single"/>"#;
+        }
+        match self.position {
+            Some(VerticalPosition::Superscript) => *output += r#"<w:vertAlign w:val="superscript"/>"#,
+            Some(VerticalPosition::Subscript) => *output += r#"<w:vertAlign w:val="subscript"/>"#,
+            _ => (),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LineHeight {
+    /// A multiple of the height of a single line.
+    Proportional(f32),
+
+    /// The height in points.
+    Exact(f32),
+    AtLeast(f32),
+}
+
+/// The properties of style:paragraph-properties (17.6), of which the ones
+/// that aren't set are inherited. The lengths are in points.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ParagraphProperties {
+    /// The value of <w:jc>.
+    alignment: Option<&'static str>,
+
+    margin_top: Option<f32>,
+    margin_bottom: Option<f32>,
+    margin_left: Option<f32>,
+    margin_right: Option<f32>,
+
+    /// The indentation of the first line, which is negative when it hangs.
+    text_indent: Option<f32>,
+
+    line_height: Option<LineHeight>,
+
+    /// Whether the paragraph starts on a new page.
+    break_before: Option<bool>,
+}
+
+impl ParagraphProperties {
+    fn parse(properties: &xml::Node) -> Self {
+        let attribute = |namespace: &str, name: &str| properties.attribute((namespace, name));
+        let length = |name: &str| attribute(XMLNS_ODF_FO, name).and_then(parse_length);
+
+        let line_height = match attribute(XMLNS_ODF_FO, "line-height") {
+            Some("normal") => Some(LineHeight::Proportional(1.0)),
+            Some(value) => match value.strip_suffix('%') {
+                Some(percentage) => percentage.parse::<f32>().ok().map(|percentage| LineHeight::Proportional(percentage / 100.0)),
+                None => parse_length(value).map(LineHeight::Exact),
+            },
+            None => attribute(XMLNS_ODF_STYLE, "line-height-at-least").and_then(parse_length).map(LineHeight::AtLeast),
+        };
+
+        Self {
+            alignment: attribute(XMLNS_ODF_FO, "text-align").and_then(|alignment| match alignment {
+                "start" | "left" => Some("start"),
+                "center" => Some("center"),
+                "end" | "right" => Some("end"),
+                "justify" => Some("both"),
+                _ => None,
+            }),
+            margin_top: length("margin-top"),
+            margin_bottom: length("margin-bottom"),
+            margin_left: length("margin-left"),
+            margin_right: length("margin-right"),
+            text_indent: length("text-indent"),
+            line_height,
+            break_before: attribute(XMLNS_ODF_FO, "break-before").map(|value| value == "page"),
+        }
+    }
+
+    fn inherit_from(&mut self, parent: &Self) {
+        self.alignment = self.alignment.or(parent.alignment);
+        self.margin_top = self.margin_top.or(parent.margin_top);
+        self.margin_bottom = self.margin_bottom.or(parent.margin_bottom);
+        self.margin_left = self.margin_left.or(parent.margin_left);
+        self.margin_right = self.margin_right.or(parent.margin_right);
+        self.text_indent = self.text_indent.or(parent.text_indent);
+        self.line_height = self.line_height.or(parent.line_height);
+        self.break_before = self.break_before.or(parent.break_before);
+    }
+
+    /// Writes the spacing, indentation and alignment as elements of a
+    /// <w:pPr> element.
+    fn write(&self, output: &mut String) {
+        let mut spacing = String::new();
+        if let Some(top) = self.margin_top {
+            spacing += &format!(r#" w:before="{}""#, twips(top).max(0));
+        }
+        if let Some(bottom) = self.margin_bottom {
+            spacing += &format!(r#" w:after="{}""#, twips(bottom).max(0));
+        }
+        match self.line_height {
+            Some(LineHeight::Proportional(lines)) => spacing += &format!(r#" w:line="{}" w:lineRule="auto""#, (lines * 240.0).round().max(0.0) as u32),
+            Some(LineHeight::Exact(height)) => spacing += &format!(r#" w:line="{}" w:lineRule="exact""#, twips(height).max(0)),
+            Some(LineHeight::AtLeast(height)) => spacing += &format!(r#" w:line="{}" w:lineRule="atLeast""#, twips(height).max(0)),
+            None => (),
+        }
+        if !spacing.is_empty() {
+            *output += &format!("<w:spacing{}/>", spacing);
+        }
+
+        let mut indentation = String::new();
+        if let Some(left) = self.margin_left {
+            indentation += &format!(r#" w:left="{}""#, twips(left).max(0));
+        }
+        if let Some(right) = self.margin_right {
+            indentation += &format!(r#" w:right="{}""#, twips(right).max(0));
+        }
+        match self.text_indent {
+            Some(indent) if indent < 0.0 => indentation += &format!(r#" w:hanging="{}""#, twips(-indent)),
+            Some(indent) => indentation += &format!(r#" w:firstLine="{}""#, twips(indent)),
+            None => (),
+        }
+        if !indentation.is_empty() {
+            *output += &format!("<w:ind{}/>", indentation);
+        }
+
+        if let Some(alignment) = self.alignment {
+            *output += &format!(r#"<w:jc w:val="{}"/>"#, alignment);
+        }
+    }
+}
+
+/// The properties of style:table-cell-properties (17.18) that are converted.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct CellProperties {
+    /// The borders at the top, left, bottom and right, as the values of the
+    /// fo:border attributes (20.176), such as "0.5pt solid #000000".
+    borders: [Option<String>; 4],
+
+    /// The background color as six hexadecimal digits.
+    background: Option<String>,
+}
+
+impl CellProperties {
+    fn parse(properties: &xml::Node) -> Self {
+        let attribute = |name: &str| properties.attribute((XMLNS_ODF_FO, name)).map(String::from);
+
+        let border = attribute("border");
+        let borders = ["border-top", "border-left", "border-bottom", "border-right"]
+            .map(|name| attribute(name).or_else(|| border.clone()));
+
+        Self {
+            borders,
+            background: properties.attribute((XMLNS_ODF_FO, "background-color")).and_then(parse_color),
+        }
+    }
+
+    fn inherit_from(&mut self, parent: &Self) {
+        for (border, parent_border) in self.borders.iter_mut().zip(&parent.borders) {
+            *border = border.take().or_else(|| parent_border.clone());
+        }
+        self.background = self.background.take().or_else(|| parent.background.clone());
+    }
+
+    /// Writes the borders and shading as elements of a <w:tcPr> element.
+    fn write(&self, output: &mut String) {
+        if self.borders.iter().any(Option::is_some) {
+            *output += "<w:tcBorders>";
+            for (border, name) in self.borders.iter().zip(["top", "left", "bottom", "right"]) {
+                if let Some(border) = border {
+                    *output += &format!("<w:{} {}/>", name, convert_border(border));
+                }
+            }
+            *output += "</w:tcBorders>";
+        }
+
+        if let Some(background) = &self.background {
+            *output += &format!(r#"<w:shd w:val="clear" w:color="auto" w:fill="{}"/>"#, background);
+        }
+    }
+}
+
+/// A style (16.2 style:style), or the default style of a family (16.4
+/// style:default-style).
+#[derive(Clone, Debug, Default)]
+struct Style {
+    parent: Option<String>,
+    display_name: Option<String>,
+
+    /// Whether the style is an automatic style, which is part of the
+    /// formatting of the content, instead of a style the user chose.
+    automatic: bool,
+
+    /// The outline level of the headings using the style, starting at 1.
+    outline_level: Option<u8>,
+
+    paragraph: ParagraphProperties,
+    text: TextProperties,
+    cell: CellProperties,
+
+    /// The width of the columns using the style, in points.
+    column_width: Option<f32>,
+}
+
+impl Style {
+    fn inherit_from(&mut self, parent: &Self) {
+        self.outline_level = self.outline_level.or(parent.outline_level);
+        self.paragraph.inherit_from(&parent.paragraph);
+        self.text.inherit_from(&parent.text);
+        self.cell.inherit_from(&parent.cell);
+        self.column_width = self.column_width.or(parent.column_width);
+    }
+}
+
+/// A level of a list style (16.30 text:list-style), converted to a level of a
+/// numbering definition.
+#[derive(Clone, Debug, PartialEq)]
+struct ListLevel {
+    /// The values of <w:numFmt> and <w:lvlText>.
+    format: &'static str,
+    text: String,
+
+    start: u32,
+
+    /// The indentation of the paragraphs, and the part of it the number or
+    /// bullet hangs into, in points.
+    indentation: f32,
+    hanging: f32,
+}
+
+impl ListLevel {
+    fn bullet(level: usize) -> Self {
+        Self {
+            format: "bullet",
+            text: String::from(LIST_BULLETS[level % LIST_BULLETS.len()]),
+            start: 1,
+            indentation: LIST_INDENTATION * (level as f32 + 1.0),
+            hanging: LIST_HANGING_INDENTATION,
+        }
+    }
+
+    fn parse(element: &xml::Node, level: usize) -> Self {
+        let mut list_level = Self::bullet(level);
+
+        let prefix = element.attribute((XMLNS_ODF_STYLE, "num-prefix")).unwrap_or_default();
+        let suffix = element.attribute((XMLNS_ODF_STYLE, "num-suffix")).unwrap_or_default();
+
+        match element.tag_name().name() {
+            "list-level-style-number" => {
+                list_level.format = match element.attribute((XMLNS_ODF_STYLE, "num-format")).unwrap_or("1") {
+                    "a" => "lowerLetter",
+                    "A" => "upperLetter",
+                    "i" => "lowerRoman",
+                    "I" => "upperRoman",
+                    "" => "none",
+                    _ => "decimal",
+                };
+
+                // The numbers of the levels above can be shown as well, such
+                // as "1.2.".
+                let display_levels = element.attribute((XMLNS_ODF_TEXT, "display-levels"))
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, level + 1);
+                let numbers: Vec<_> = (level + 2 - display_levels..=level + 1)
+                    .map(|number| format!("%{}", number))
+                    .collect();
+
+                list_level.text = match list_level.format {
+                    "none" => format!("{}{}", prefix, suffix),
+                    _ => format!("{}{}{}", prefix, numbers.join("."), suffix),
+                };
+                list_level.start = element.attribute((XMLNS_ODF_TEXT, "start-value"))
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(1);
+            }
+            "list-level-style-bullet" => {
+                let bullet = element.attribute((XMLNS_ODF_TEXT, "bullet-char")).unwrap_or(LIST_BULLETS[0]);
+                list_level.text = format!("{}{}{}", prefix, bullet, suffix);
+            }
+
+            // Pictures are shown as the bullet of the level.
+            _ => (),
+        }
+
+        let Some(properties) = element.children().find(|child| child.has_tag_name((XMLNS_ODF_STYLE, "list-level-properties"))) else {
+            return list_level;
+        };
+
+        match properties.children().find(|child| child.has_tag_name((XMLNS_ODF_STYLE, "list-level-label-alignment"))) {
+            Some(alignment) => {
+                if let Some(margin) = length_attribute(&alignment, XMLNS_ODF_FO, "margin-left") {
+                    list_level.indentation = margin;
+                }
+                if let Some(indent) = length_attribute(&alignment, XMLNS_ODF_FO, "text-indent") {
+                    list_level.hanging = -indent;
+                }
+            }
+
+            // Documents of older versions position the labels from the space
+            // before them (19.880 text:space-before).
+            None => {
+                let space_before = length_attribute(&properties, XMLNS_ODF_TEXT, "space-before").unwrap_or(0.0);
+                if let Some(label_width) = length_attribute(&properties, XMLNS_ODF_TEXT, "min-label-width") {
+                    list_level.indentation = space_before + label_width;
+                    list_level.hanging = label_width;
+                }
+            }
+        }
+
+        list_level
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PageLayout {
+    /// The size and margins of the pages, in points.
+    width: f32,
+    height: f32,
+    margin_top: f32,
+    margin_right: f32,
+    margin_bottom: f32,
+    margin_left: f32,
+}
+
+impl PageLayout {
+    fn parse(element: &xml::Node) -> Option<Self> {
+        let properties = element.children().find(|child| child.has_tag_name((XMLNS_ODF_STYLE, "page-layout-properties")))?;
+        let length = |name: &str| length_attribute(&properties, XMLNS_ODF_FO, name);
+
+        Some(Self {
+            width: length("page-width")?,
+            height: length("page-height")?,
+            margin_top: length("margin-top").unwrap_or(0.0),
+            margin_right: length("margin-right").unwrap_or(0.0),
+            margin_bottom: length("margin-bottom").unwrap_or(0.0),
+            margin_left: length("margin-left").unwrap_or(0.0),
+        })
+    }
+
+    fn section_properties(&self) -> String {
+        format!(
+            concat!(
+                r#"<w:sectPr><w:pgSz w:w="{}" w:h="{}"/>"#,
+                r#"<w:pgMar w:top="{}" w:right="{}" w:bottom="{}" w:left="{}" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr>"#,
+            ),
+            twips(self.width).max(0), twips(self.height).max(0),
+            twips(self.margin_top).max(0), twips(self.margin_right).max(0),
+            twips(self.margin_bottom).max(0), twips(self.margin_left).max(0)
+        )
+    }
+}
+
+/// The paragraph of a list item, or of a list header, which isn't numbered.
+#[derive(Clone, Copy, Debug)]
+struct ListItem {
+    /// The index of the list in [Converter::lists].
+    list: usize,
+    level: usize,
+
+    /// Whether the paragraph is numbered, which only the first paragraph of
+    /// an item is.
+    numbered: bool,
+}
+
+/// Text with the same formatting, of which the tabs and line breaks are kept
+/// as characters.
+#[derive(Debug, PartialEq)]
+struct Run {
+    /// The contents of the <w:rPr> element.
+    properties: String,
+    text: String,
+
+    /// The index of the target of the link in [Converter::hyperlinks].
+    link: Option<usize>,
+}
+
+/// Converts the parts of the package to the parts of a WordprocessingML
+/// document.
+#[derive(Debug, Default)]
+struct Converter {
+    /// The font families by the names of the font face declarations.
+    font_faces: HashMap<String, String>,
+
+    /// The styles by their family and name, of which the default styles have
+    /// an empty name.
+    styles: HashMap<(StyleFamily, String), Style>,
+
+    list_styles: HashMap<String, Vec<ListLevel>>,
+    page_layouts: HashMap<String, PageLayout>,
+
+    /// The name of the page layout of the standard master page (16.9
+    /// style:master-page), which all pages use.
+    page_layout_name: Option<String>,
+
+    body: String,
+
+    /// The targets of the links, of which the relationship id is "rId"
+    /// followed by the index plus one.
+    hyperlinks: Vec<String>,
+
+    /// The list styles of the lists, each of which is numbered by the
+    /// numbering definition with the index plus one as its id.
+    lists: Vec<Option<String>>,
+
+    /// The lists by their xml:id, for the lists continuing them (19.791
+    /// text:continue-list).
+    list_ids: HashMap<String, usize>,
+
+    /// The last list of each list style, for the lists continuing its
+    /// numbering (19.792 text:continue-numbering).
+    last_lists: HashMap<Option<String>, usize>,
+
+    in_table_cell: bool,
+}
+
+impl Converter {
+    /// Converts the content.xml part, with the styles of the styles.xml part
+    /// if the package has one.
+    fn convert(content: &str, styles: Option<&str>) -> Result<Self, OdfError> {
+        let mut converter = Self::default();
+
+        if let Some(styles) = styles {
+            converter.read_styles(xml::Document::parse(styles)?.root_element());
+        }
+
+        let content = xml::Document::parse(content)?;
+        converter.read_styles(content.root_element());
+
+        let text = content.root_element().children()
+            .find(|child| child.has_tag_name((XMLNS_ODF_OFFICE, "body")))
+            .and_then(|body| body.children().find(|child| child.has_tag_name((XMLNS_ODF_OFFICE, "text"))))
+            .ok_or(OdfError::NotATextDocument)?;
+        converter.convert_blocks(text);
+
+        Ok(converter)
+    }
+
+    /// Reads the font faces, styles and master pages of the root element of
+    /// the styles.xml or content.xml part.
+    fn read_styles(&mut self, root: xml::Node) {
+        for section in root.children().filter(|child| child.tag_name().namespace() == Some(XMLNS_ODF_OFFICE)) {
+            match section.tag_name().name() {
+                "font-face-decls" => {
+                    for font_face in section.children().filter(|child| child.has_tag_name((XMLNS_ODF_STYLE, "font-face"))) {
+                        if let (Some(name), Some(family)) = (font_face.attribute((XMLNS_ODF_STYLE, "name")), font_face.attribute((XMLNS_ODF_SVG, "font-family"))) {
+                            self.font_faces.insert(String::from(name), parse_font_family(family));
+                        }
+                    }
+                }
+                "styles" => self.read_style_elements(section, false),
+                "automatic-styles" => self.read_style_elements(section, true),
+                "master-styles" => {
+                    let master_pages: Vec<_> = section.children()
+                        .filter(|child| child.has_tag_name((XMLNS_ODF_STYLE, "master-page")))
+                        .collect();
+                    let master_page = master_pages.iter()
+                        .find(|master_page| master_page.attribute((XMLNS_ODF_STYLE, "name")) == Some("Standard"))
+                        .or(master_pages.first());
+
+                    if let Some(name) = master_page.and_then(|master_page| master_page.attribute((XMLNS_ODF_STYLE, "page-layout-name"))) {
+                        self.page_layout_name = Some(String::from(name));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn read_style_elements(&mut self, section: xml::Node, automatic: bool) {
+        for element in section.children().filter(xml::Node::is_element) {
+            let name = element.attribute((XMLNS_ODF_STYLE, "name"));
+            match (element.tag_name().namespace(), element.tag_name().name()) {
+                (Some(XMLNS_ODF_STYLE), "style" | "default-style") => {
+                    let Some(family) = element.attribute((XMLNS_ODF_STYLE, "family")).and_then(StyleFamily::parse) else {
+                        continue;
+                    };
+
+                    let name = match element.tag_name().name() {
+                        "default-style" => String::new(),
+                        _ => match name {
+                            Some(name) => String::from(name),
+                            None => continue,
+                        },
+                    };
+
+                    let style = self.parse_style(&element, automatic);
+                    self.styles.insert((family, name), style);
+                }
+                (Some(XMLNS_ODF_TEXT), "list-style") => {
+                    if let Some(name) = name {
+                        self.list_styles.insert(String::from(name), parse_list_style(&element));
+                    }
+                }
+                (Some(XMLNS_ODF_STYLE), "page-layout") => {
+                    if let (Some(name), Some(page_layout)) = (name, PageLayout::parse(&element)) {
+                        self.page_layouts.insert(String::from(name), page_layout);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn parse_style(&self, element: &xml::Node, automatic: bool) -> Style {
+        let mut style = Style {
+            parent: element.attribute((XMLNS_ODF_STYLE, "parent-style-name")).map(String::from),
+            display_name: element.attribute((XMLNS_ODF_STYLE, "display-name")).map(String::from),
+            automatic,
+            outline_level: element.attribute((XMLNS_ODF_STYLE, "default-outline-level")).and_then(|level| level.parse().ok()),
+            ..Default::default()
+        };
+
+        for properties in element.children().filter(|child| child.tag_name().namespace() == Some(XMLNS_ODF_STYLE)) {
+            match properties.tag_name().name() {
+                "paragraph-properties" => style.paragraph = ParagraphProperties::parse(&properties),
+                "text-properties" => style.text = TextProperties::parse(&properties, &self.font_faces),
+                "table-cell-properties" => style.cell = CellProperties::parse(&properties),
+                "table-column-properties" => style.column_width = length_attribute(&properties, XMLNS_ODF_STYLE, "column-width"),
+                _ => (),
+            }
+        }
+
+        style
+    }
+
+    /// Finds the style, with the properties of its parents and of the
+    /// default style of its family.
+    fn resolve_style(&self, family: StyleFamily, name: Option<&str>) -> Style {
+        let mut style = Style::default();
+
+        let mut name = name.map(String::from);
+        for _ in 0..MAX_STYLE_DEPTH {
+            let Some(current) = name.take().and_then(|name| self.styles.get(&(family, name))) else {
+                break;
+            };
+
+            style.inherit_from(current);
+            name = current.parent.clone();
+        }
+
+        if let Some(default_style) = self.styles.get(&(family, String::new())) {
+            style.inherit_from(default_style);
+        }
+
+        style
+    }
+
+    /// Finds the paragraph style the user chose, which is the first one that
+    /// isn't automatic.
+    fn find_paragraph_style_name(&self, name: Option<&str>) -> Option<String> {
+        let mut name = name.map(String::from);
+        for _ in 0..MAX_STYLE_DEPTH {
+            let current = name.take()?;
+            let style = self.styles.get(&(StyleFamily::Paragraph, current.clone()))?;
+            if !style.automatic {
+                return Some(current);
+            }
+            name = style.parent.clone();
+        }
+        None
+    }
+
+    fn convert_blocks(&mut self, parent: xml::Node) {
+        for child in parent.children().filter(xml::Node::is_element) {
+            self.convert_block(child, None);
+        }
+    }
+
+    fn convert_block(&mut self, element: xml::Node, list_item: Option<ListItem>) {
+        match (element.tag_name().namespace(), element.tag_name().name()) {
+            (Some(XMLNS_ODF_TEXT), "p" | "h") => self.convert_paragraph(element, list_item),
+            (Some(XMLNS_ODF_TEXT), "list") => self.convert_list(element, list_item),
+            (Some(XMLNS_ODF_TABLE), "table") => self.convert_table(element),
+            (Some(XMLNS_ODF_TEXT), name) if CONTAINER_ELEMENTS.contains(&name) => self.convert_blocks(element),
+            _ => (),
+        }
+    }
+
+    /// Converts the list (5.3.1 text:list), of which the nested lists are
+    /// levels of the `outer` list.
+    fn convert_list(&mut self, element: xml::Node, outer: Option<ListItem>) {
+        let (list, level) = match outer {
+            Some(outer) => (outer.list, (outer.level + 1).min(LIST_LEVELS - 1)),
+            None => (self.start_list(&element), 0),
+        };
+
+        for item in element.children().filter(xml::Node::is_element) {
+            let numbered = item.has_tag_name((XMLNS_ODF_TEXT, "list-item"));
+            if !numbered && !item.has_tag_name((XMLNS_ODF_TEXT, "list-header")) {
+                continue;
+            }
+
+            let mut first_paragraph = true;
+            for child in item.children().filter(xml::Node::is_element) {
+                let list_item = ListItem { list, level, numbered: numbered && first_paragraph };
+                if child.has_tag_name((XMLNS_ODF_TEXT, "p")) || child.has_tag_name((XMLNS_ODF_TEXT, "h")) {
+                    first_paragraph = false;
+                }
+                self.convert_block(child, Some(list_item));
+            }
+        }
+    }
+
+    /// Finds the list the top-level list continues, or adds a new one.
+    fn start_list(&mut self, element: &xml::Node) -> usize {
+        let style = element.attribute((XMLNS_ODF_TEXT, "style-name")).map(String::from);
+
+        let continued = match element.attribute((XMLNS_ODF_TEXT, "continue-list")) {
+            Some(id) => self.list_ids.get(id).copied(),
+            None if element.attribute((XMLNS_ODF_TEXT, "continue-numbering")) == Some("true") => self.last_lists.get(&style).copied(),
+            None => None,
+        };
+
+        let list = continued.unwrap_or_else(|| {
+            self.lists.push(style.clone());
+            self.lists.len() - 1
+        });
+
+        if let Some(id) = element.attribute((XMLNS_XML, "id")) {
+            self.list_ids.insert(String::from(id), list);
+        }
+        self.last_lists.insert(style, list);
+        list
+    }
+
+    fn list_level(&self, list: usize, level: usize) -> ListLevel {
+        self.lists[list].as_ref()
+            .and_then(|style| self.list_styles.get(style))
+            .and_then(|levels| levels.get(level))
+            .cloned()
+            .unwrap_or_else(|| ListLevel::bullet(level))
+    }
+
+    /// Converts the paragraph (5.1.3 text:p) or heading (5.1.2 text:h).
+    fn convert_paragraph(&mut self, element: xml::Node, list_item: Option<ListItem>) {
+        let style_name = element.attribute((XMLNS_ODF_TEXT, "style-name"));
+        let style = self.resolve_style(StyleFamily::Paragraph, style_name);
+        let mut paragraph = style.paragraph.clone();
+
+        let mut properties = String::new();
+        if let Some(name) = self.find_paragraph_style_name(style_name) {
+            properties += &format!(r#"<w:pStyle w:val="{}"/>"#, escape(&name));
+        }
+
+        // The paragraphs of lists are indented by the list style.
+        if let Some(list_item) = list_item {
+            let list_level = self.list_level(list_item.list, list_item.level);
+            paragraph.margin_left = Some(list_level.indentation);
+            paragraph.text_indent = None;
+
+            if list_item.numbered {
+                properties += &format!(r#"<w:numPr><w:ilvl w:val="{}"/><w:numId w:val="{}"/></w:numPr>"#, list_item.level, list_item.list + 1);
+                paragraph.text_indent = Some(-list_level.hanging);
+            }
+        }
+
+        paragraph.write(&mut properties);
+
+        if element.has_tag_name((XMLNS_ODF_TEXT, "h")) {
+            let level = element.attribute((XMLNS_ODF_TEXT, "outline-level"))
+                .and_then(|level| level.parse::<u8>().ok())
+                .or(style.outline_level)
+                .unwrap_or(1)
+                .clamp(1, 9);
+            properties += &format!(r#"<w:outlineLvl w:val="{}"/>"#, level - 1);
+        }
+
+        let mut runs = Vec::new();
+        let mut after_space = true;
+        self.collect_runs(element, &style.text, None, &mut runs, &mut after_space);
+
+        let page_break = paragraph.break_before == Some(true) && !self.in_table_cell && !self.body.is_empty();
+        self.write_paragraph(&properties, page_break, &runs);
+    }
+
+    /// Collects the text of the paragraph, with the properties of the spans
+    /// (6.1.7 text:span) it is in.
+    fn collect_runs(&mut self, parent: xml::Node, properties: &TextProperties, link: Option<usize>,
+            runs: &mut Vec<Run>, after_space: &mut bool) {
+        for child in parent.children() {
+            if child.is_text() {
+                let text = collapse_white_space(child.text().unwrap_or_default(), after_space);
+                push_run(runs, properties, link, &text);
+                continue;
+            }
+
+            if !child.is_element() {
+                continue;
+            }
+
+            match (child.tag_name().namespace(), child.tag_name().name()) {
+                // 6.1.3 text:s
+                (Some(XMLNS_ODF_TEXT), "s") => {
+                    let count = child.attribute((XMLNS_ODF_TEXT, "c"))
+                        .and_then(|count| count.parse::<usize>().ok())
+                        .unwrap_or(1);
+                    push_run(runs, properties, link, &" ".repeat(count.min(MAX_REPEAT_COUNT)));
+                    *after_space = false;
+                }
+                (Some(XMLNS_ODF_TEXT), "tab") => {
+                    push_run(runs, properties, link, "\t");
+                    *after_space = false;
+                }
+                (Some(XMLNS_ODF_TEXT), "line-break") => {
+                    push_run(runs, properties, link, "\n");
+                    *after_space = true;
+                }
+                (Some(XMLNS_ODF_TEXT), "span") => {
+                    let properties = self.span_properties(child.attribute((XMLNS_ODF_TEXT, "style-name")), properties);
+                    self.collect_runs(child, &properties, link, runs, after_space);
+                }
+
+                // 6.1.8 text:a, of which the links to places in the document
+                // aren't followed.
+                (Some(XMLNS_ODF_TEXT), "a") => {
+                    let style_name = child.attribute((XMLNS_ODF_TEXT, "style-name"))
+                        .filter(|name| self.styles.contains_key(&(StyleFamily::Text, String::from(*name))));
+                    let properties = match style_name {
+                        Some(name) => self.span_properties(Some(name), properties),
+                        None => {
+                            let mut link_properties = TextProperties {
+                                underline: Some(true),
+                                color: Some(String::from(DEFAULT_LINK_COLOR)),
+                                ..Default::default()
+                            };
+                            link_properties.inherit_from(properties);
+                            link_properties
+                        }
+                    };
+
+                    let link = match child.attribute((XMLNS_XLINK, "href")) {
+                        Some(target) if !target.is_empty() && !target.starts_with('#') => {
+                            self.hyperlinks.push(String::from(target));
+                            Some(self.hyperlinks.len() - 1)
+                        }
+                        _ => link,
+                    };
+                    self.collect_runs(child, &properties, link, runs, after_space);
+                }
+
+                // The elements that aren't shown in the text.
+                (Some(XMLNS_ODF_TEXT), "note" | "soft-page-break" | "bookmark" | "bookmark-start" | "bookmark-end"
+                    | "reference-mark" | "reference-mark-start" | "reference-mark-end" | "change" | "change-start" | "change-end") => (),
+                (Some(XMLNS_ODF_OFFICE), "annotation" | "annotation-end") => (),
+
+                // Fields such as the page number (7.3.5 text:page-number)
+                // contain the text they showed when the document was saved.
+                (Some(XMLNS_ODF_TEXT), _) => self.collect_runs(child, properties, link, runs, after_space),
+
+                // Frames, pictures and shapes.
+                _ => (),
+            }
+        }
+    }
+
+    fn span_properties(&self, style_name: Option<&str>, parent: &TextProperties) -> TextProperties {
+        let mut properties = self.resolve_style(StyleFamily::Text, style_name).text;
+        properties.inherit_from(parent);
+        properties
+    }
+
+    fn write_paragraph(&mut self, properties: &str, page_break: bool, runs: &[Run]) {
+        self.body += "<w:p>";
+        if !properties.is_empty() {
+            self.body += &format!("<w:pPr>{}</w:pPr>", properties);
+        }
+
+        if page_break {
+            self.body += r#"<w:r><w:br w:type="page"/></w:r>"#;
+        }
+
+        let mut link = None;
+        for run in runs {
+            if run.link != link {
+                if link.is_some() {
+                    self.body += "</w:hyperlink>";
+                }
+                if let Some(index) = run.link {
+                    self.body += &format!(r#"<w:hyperlink r:id="{}">"#, hyperlink_relationship_id(index));
+                }
+                link = run.link;
+            }
+
+            self.body += "<w:r>";
+            if !run.properties.is_empty() {
+                self.body += &format!("<w:rPr>{}</w:rPr>", run.properties);
+            }
+            write_text(&mut self.body, &run.text);
+            self.body += "</w:r>";
+        }
+
+        if link.is_some() {
+            self.body += "</w:hyperlink>";
+        }
+        self.body += "</w:p>";
+    }
+
+    /// Converts the table (9.1.2 table:table), of which the columns without
+    /// a width share the width between the margins of the page.
+    fn convert_table(&mut self, table: xml::Node) {
+        // Tables in tables aren't supported by the layout, so the text of
+        // their cells is added to the cell instead.
+        if self.in_table_cell {
+            for row in table_children(table, "table-row") {
+                for cell in row.children().filter(|child| child.has_tag_name((XMLNS_ODF_TABLE, "table-cell"))) {
+                    self.convert_blocks(cell);
+                }
+            }
+            return;
+        }
+
+        let mut widths = Vec::new();
+        for column in table_children(table, "table-column") {
+            let width = self.resolve_style(StyleFamily::TableColumn, column.attribute((XMLNS_ODF_TABLE, "style-name"))).column_width;
+            widths.extend(std::iter::repeat(width).take(repeat_count(&column, "number-columns-repeated")));
+        }
+
+        let text_width = self.page_layout()
+            .map(|layout| layout.width - layout.margin_left - layout.margin_right)
+            .unwrap_or(DEFAULT_TEXT_WIDTH);
+        let fallback_width = text_width / widths.len().max(1) as f32;
+        let widths: Vec<f32> = widths.into_iter().map(|width| width.unwrap_or(fallback_width)).collect();
+
+        let mut output = String::from("<w:tbl><w:tblGrid>");
+        for width in &widths {
+            output += &format!(r#"<w:gridCol w:w="{}"/>"#, twips(*width).max(0));
+        }
+        output += "</w:tblGrid>";
+
+        for row in table_children(table, "table-row") {
+            let mut row_output = String::from("<w:tr>");
+            let mut column = 0;
+
+            // The number of cells covered by the cell spanning multiple
+            // columns before them (9.1.5 table:covered-table-cell).
+            let mut covered = 0;
+
+            for cell in row.children().filter(xml::Node::is_element) {
+                let is_covered = cell.has_tag_name((XMLNS_ODF_TABLE, "covered-table-cell"));
+                if !is_covered && !cell.has_tag_name((XMLNS_ODF_TABLE, "table-cell")) {
+                    continue;
+                }
+
+                for _ in 0..repeat_count(&cell, "number-columns-repeated") {
+                    if is_covered && covered > 0 {
+                        covered -= 1;
+                        column += 1;
+                        continue;
+                    }
+
+                    // The cells covered by the cells spanning multiple rows
+                    // are shown as empty cells.
+                    let span = if is_covered { 1 } else { repeat_count(&cell, "number-columns-spanned") };
+                    let width = widths.iter().skip(column).take(span).sum();
+                    row_output += &self.convert_table_cell(cell, span, width, is_covered);
+
+                    column += span;
+                    covered = span - 1;
+                }
+            }
+
+            row_output += "</w:tr>";
+            for _ in 0..repeat_count(&row, "number-rows-repeated") {
+                output += &row_output;
+            }
+        }
+
+        output += "</w:tbl>";
+        self.body += &output;
+    }
+
+    fn convert_table_cell(&mut self, cell: xml::Node, span: usize, width: f32, covered: bool) -> String {
+        let style = self.resolve_style(StyleFamily::TableCell, cell.attribute((XMLNS_ODF_TABLE, "style-name")));
+
+        let mut output = format!(r#"<w:tc><w:tcPr><w:tcW w:w="{}" w:type="dxa"/>"#, twips(width).max(0));
+        if span > 1 {
+            output += &format!(r#"<w:gridSpan w:val="{}"/>"#, span);
+        }
+        style.cell.write(&mut output);
+        output += "</w:tcPr>";
+
+        let body = std::mem::take(&mut self.body);
+        let in_table_cell = std::mem::replace(&mut self.in_table_cell, true);
+        if !covered {
+            self.convert_blocks(cell);
+        }
+        let content = std::mem::replace(&mut self.body, body);
+        self.in_table_cell = in_table_cell;
+
+        // A cell contains at least one paragraph (17.4.66 tc).
+        if content.is_empty() {
+            output += "<w:p/>";
+        } else {
+            output += &content;
+        }
+
+        output += "</w:tc>";
+        output
+    }
+
+    fn page_layout(&self) -> Option<PageLayout> {
+        self.page_layout_name.as_ref().and_then(|name| self.page_layouts.get(name)).copied()
+    }
+
+    fn document_part(&self) -> String {
+        match self.page_layout() {
+            Some(page_layout) => format!("{}{}{}</w:body></w:document>", CONVERTED_DOCUMENT_START, self.body, page_layout.section_properties()),
+            None => format!("{}{}{}", CONVERTED_DOCUMENT_START, self.body, CONVERTED_DOCUMENT_END),
+        }
+    }
+
+    /// Writes the styles part, of which the styles only have a name, since
+    /// the properties of the styles are written to the paragraphs and runs.
+    fn styles_part(&self) -> String {
+        let defaults = self.resolve_style(StyleFamily::Paragraph, None).text;
+        let defaults = TextProperties {
+            font: defaults.font,
+            size: defaults.size.or(Some(DEFAULT_FONT_SIZE)),
+            ..Default::default()
+        };
+
+        let mut part = String::from(r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#);
+        part += "<w:docDefaults><w:rPrDefault><w:rPr>";
+        defaults.write(&mut part);
+        part += "</w:rPr></w:rPrDefault></w:docDefaults>";
+
+        let mut styles: Vec<_> = self.styles.iter()
+            .filter(|((family, name), style)| *family == StyleFamily::Paragraph && !name.is_empty() && !style.automatic)
+            .map(|((_, name), style)| (name, style.display_name.as_ref().unwrap_or(name)))
+            .collect();
+        styles.sort();
+
+        for (name, display_name) in styles {
+            part += &format!(r#"<w:style w:type="paragraph" w:styleId="{}"><w:name w:val="{}"/></w:style>"#,
+                escape(name), escape(display_name));
+        }
+
+        part += "</w:styles>";
+        part
+    }
+
+    fn numbering_part(&self) -> Option<String> {
+        if self.lists.is_empty() {
+            return None;
+        }
+
+        let mut part = String::from(r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#);
+        for index in 0..self.lists.len() {
+            part += &format!(r#"<w:abstractNum w:abstractNumId="{}">"#, index);
+            for level in 0..LIST_LEVELS {
+                let list_level = self.list_level(index, level);
+                part += &format!(
+                    r#"<w:lvl w:ilvl="{}"><w:start w:val="{}"/><w:numFmt w:val="{}"/><w:lvlText w:val="{}"/><w:pPr><w:ind w:left="{}" w:hanging="{}"/></w:pPr></w:lvl>"#,
+                    level, list_level.start, list_level.format, escape(&list_level.text),
+                    twips(list_level.indentation).max(0), twips(list_level.hanging).max(0)
+                );
+            }
+            part += "</w:abstractNum>";
+        }
+
+        for index in 0..self.lists.len() {
+            part += &format!(r#"<w:num w:numId="{}"><w:abstractNumId w:val="{}"/></w:num>"#, index + 1, index);
+        }
+
+        part += "</w:numbering>";
+        Some(part)
+    }
+
+    fn relationships(&self) -> Relationships {
+        let mut relationships = Relationships::empty();
+        for (index, target) in self.hyperlinks.iter().enumerate() {
+            relationships.insert(Relationship {
+                id: Rc::from(hyperlink_relationship_id(index)),
+                relation_type: RelationshipType::Hyperlink,
+                target: target.clone(),
+                data: Vec::new(),
+            });
+        }
+        relationships
+    }
+}
+
+/// Adds the text to the runs, merging it with the last run when it has the
+/// same formatting.
+fn push_run(runs: &mut Vec<Run>, properties: &TextProperties, link: Option<usize>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+
+    let mut written_properties = String::new();
+    properties.write(&mut written_properties);
+
+    match runs.last_mut() {
+        Some(last) if last.properties == written_properties && last.link == link => last.text += text,
+        _ => runs.push(Run { properties: written_properties, text: String::from(text), link }),
+    }
+}
+
+/// Collapses the white space of the text of a paragraph (6.1.2), of which the
+/// white space at the start is left out. Other spaces are written as
+/// elements, such as <text:s>.
+fn collapse_white_space(text: &str, after_space: &mut bool) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    for character in text.chars() {
+        if matches!(character, ' ' | '\t' | '\r' | '\n') {
+            if !*after_space {
+                collapsed.push(' ');
+                *after_space = true;
+            }
+        } else {
+            collapsed.push(character);
+            *after_space = false;
+        }
+    }
+    collapsed
+}
+
+/// Finds the columns or rows of the table, which can be grouped, e.g. as the
+/// header rows (9.1.7 table:table-header-rows).
+fn table_children<'a, 'input>(table: xml::Node<'a, 'input>, name: &str) -> Vec<xml::Node<'a, 'input>> {
+    let mut children = Vec::new();
+    for child in table.children().filter(|child| child.tag_name().namespace() == Some(XMLNS_ODF_TABLE)) {
+        match child.tag_name().name() {
+            "table-columns" | "table-header-columns" | "table-column-group"
+                | "table-rows" | "table-header-rows" | "table-row-group" => children.extend(table_children(child, name)),
+            child_name if child_name == name => children.push(child),
+            _ => (),
+        }
+    }
+    children
+}
+
+/// The number of times the column, row or cell is repeated, or the number of
+/// columns the cell spans.
+fn repeat_count(node: &xml::Node, attribute: &str) -> usize {
+    node.attribute((XMLNS_ODF_TABLE, attribute))
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_REPEAT_COUNT)
+}
+
+fn parse_list_style(element: &xml::Node) -> Vec<ListLevel> {
+    let mut levels: Vec<_> = (0..LIST_LEVELS).map(ListLevel::bullet).collect();
+
+    for level_style in element.children().filter(|child| child.tag_name().namespace() == Some(XMLNS_ODF_TEXT)) {
+        let level = level_style.attribute((XMLNS_ODF_TEXT, "level"))
+            .and_then(|level| level.parse::<usize>().ok())
+            .and_then(|level| level.checked_sub(1));
+        if let Some(level) = level.filter(|level| *level < LIST_LEVELS) {
+            levels[level] = ListLevel::parse(&level_style, level);
+        }
+    }
+
+    levels
+}
+
+/// Parses a length (18.3.18 length), returning it in points.
+fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let unit_start = value.find(|character: char| character.is_ascii_alphabetic())?;
+    let number: f32 = value[..unit_start].parse().ok()?;
+
+    let points_per_unit = match &value[unit_start..] {
+        "pt" => 1.0,
+        "pc" => 12.0,
+        "in" => 72.0,
+        "cm" => 72.0 / 2.54,
+        "mm" => 72.0 / 25.4,
+        "px" => 0.75,
+        _ => return None,
+    };
+    Some(number * points_per_unit)
+}
+
+fn length_attribute(node: &xml::Node, namespace: &str, name: &str) -> Option<f32> {
+    node.attribute((namespace, name)).and_then(parse_length)
+}
+
+fn twips(points: f32) -> i32 {
+    (points * 20.0).round() as i32
+}
+
+/// Parses a color such as "#1F3864", returning the hexadecimal digits.
+fn parse_color(value: &str) -> Option<String> {
+    value.strip_prefix('#')
+        .filter(|digits| digits.len() == 6 && digits.chars().all(|character| character.is_ascii_hexdigit()))
+        .map(str::to_ascii_uppercase)
+}
+
+/// Finds the first family of a list of font families, such as
+/// "'Liberation Serif', serif".
+fn parse_font_family(value: &str) -> String {
+    let family = value.split(',').next().unwrap_or_default().trim();
+    String::from(family.trim_matches(['\'', '"']))
+}
+
+/// Parses the value of style:text-position (20.374), which is "super", "sub"
+/// or a percentage, followed by the relative font size.
+fn parse_text_position(value: &str) -> VerticalPosition {
+    match value.split_whitespace().next().unwrap_or_default() {
+        "super" => VerticalPosition::Superscript,
+        "sub" => VerticalPosition::Subscript,
+        offset => match offset.trim_end_matches('%').parse::<f32>() {
+            Ok(offset) if offset > 0.0 => VerticalPosition::Superscript,
+            Ok(offset) if offset < 0.0 => VerticalPosition::Subscript,
+            _ => VerticalPosition::Baseline,
+        },
+    }
+}
+
+/// Converts the value of a fo:border attribute, such as "0.5pt solid
+/// #000000", to the attributes of a border element (17.3.4 ST_Border).
+fn convert_border(value: &str) -> String {
+    let mut border_type = "single";
+    let mut width = 0.5;
+    let mut color = String::from("auto");
+
+    for part in value.split_whitespace() {
+        match part {
+            "none" | "hidden" => border_type = "nil",
+            "solid" | "groove" | "ridge" | "inset" | "outset" => (),
+            "double" => border_type = "double",
+            "dotted" => border_type = "dotted",
+            "dashed" => border_type = "dashed",
+            _ => {
+                if let Some(hex) = parse_color(part) {
+                    color = hex;
+                } else if let Some(length) = parse_length(part) {
+                    width = length;
+                }
+            }
+        }
+    }
+
+    // The width is in eighths of a point, of which 2 is the minimum.
+    let size = (width * 8.0).round().max(2.0) as u32;
+    format!(r#"w:val="{}" w:sz="{}" w:space="0" w:color="{}""#, border_type, size, color)
+}
+
+/// Checks if the file is an OpenDocument Text document, by its extension.
+pub fn is_open_document_text_path(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("odt"))
+}
+
+/// Reads the file of the package, if it exists.
+fn read_package_file(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Result<Option<String>, OdfError> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    Ok(Some(text))
+}
+
+/// Converts the content.xml and styles.xml parts of an OpenDocument Text
+/// package and lays them out.
+pub fn process_odt(content: &str, styles: Option<&str>, text_calculator: &mut dyn TextCalculator, fallback_font: &str,
+        revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let converter = Converter::convert(content, styles)?;
+
+    let mut result = process_converted_document(&converter.document_part(), &converter.styles_part(),
+        converter.numbering_part().as_deref(), &converter.relationships(), text_calculator, fallback_font,
+        revision_view, observer)?;
+    result.diagnostics.insert(0, Diagnostic::info("content.xml",
+        String::from("OpenDocument Text document, opened read-only without its frames, pictures, notes, headers and footers")));
+    Ok(result)
+}
+
+/// Reads the OpenDocument Text file and lays it out. The document has no
+/// source package, so it can't be saved.
+pub fn process_odt_file(path: &Path, text_calculator: &mut dyn TextCalculator, fallback_font: &str,
+        revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+
+    // The mimetype file is optional, but tells the text documents apart from
+    // the other kinds of OpenDocument files.
+    if let Some(media_type) = read_package_file(&mut archive, "mimetype")? {
+        if !media_type.trim().starts_with(ODT_MEDIA_TYPE) {
+            return Err(OdfError::NotATextDocument.into());
+        }
+    }
+
+    let content = read_package_file(&mut archive, "content.xml")?.ok_or(OdfError::MissingContent)?;
+    let styles = read_package_file(&mut archive, "styles.xml")?;
+    process_odt(&content, styles.as_deref(), text_calculator, fallback_font, revision_view, observer)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{gui::painter::mock::MockTextCalculator, wp};
+
+    use super::*;
+
+    const STYLES: &str = r#"<office:document-styles
+            xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0"
+            xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0"
+            xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0"
+            xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0"
+            xmlns:svg="urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0">
+        <office:font-face-decls>
+            <style:font-face style:name="Liberation Serif" svg:font-family="'Liberation Serif'"/>
+        </office:font-face-decls>
+        <office:styles>
+            <style:default-style style:family="paragraph">
+                <style:text-properties style:font-name="Liberation Serif" fo:font-size="12pt"/>
+            </style:default-style>
+            <style:style style:name="Standard" style:family="paragraph"/>
+            <style:style style:name="Heading_20_1" style:display-name="Heading 1" style:family="paragraph"
+                    style:parent-style-name="Standard" style:default-outline-level="1">
+                <style:paragraph-properties fo:margin-top="0.423cm" fo:margin-bottom="0.212cm"/>
+                <style:text-properties fo:font-size="130%" fo:font-weight="bold"/>
+            </style:style>
+            <style:style style:name="Strong_20_Emphasis" style:display-name="Strong Emphasis" style:family="text">
+                <style:text-properties fo:font-weight="bold"/>
+            </style:style>
+            <text:list-style style:name="Numbering_20_123">
+                <text:list-level-style-number text:level="1" style:num-suffix="." style:num-format="1">
+                    <style:list-level-properties text:list-level-position-and-space-mode="label-alignment">
+                        <style:list-level-label-alignment fo:text-indent="-0.635cm" fo:margin-left="1.27cm"/>
+                    </style:list-level-properties>
+                </text:list-level-style-number>
+                <text:list-level-style-bullet text:level="2" text:bullet-char="-"/>
+            </text:list-style>
+        </office:styles>
+        <office:automatic-styles>
+            <style:page-layout style:name="pm1">
+                <style:page-layout-properties fo:page-width="8.5in" fo:page-height="11in"
+                    fo:margin-top="1in" fo:margin-bottom="1in" fo:margin-left="1in" fo:margin-right="1in"/>
+            </style:page-layout>
+        </office:automatic-styles>
+        <office:master-styles>
+            <style:master-page style:name="Standard" style:page-layout-name="pm1"/>
+        </office:master-styles>
+    </office:document-styles>"#;
+
+    fn content(body: &str) -> String {
+        format!(
+            r##"<office:document-content
+                    xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0"
+                    xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0"
+                    xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0"
+                    xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0"
+                    xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0"
+                    xmlns:xlink="http://www.w3.org/1999/xlink">
+                <office:automatic-styles>
+                    <style:style style:name="P1" style:family="paragraph" style:parent-style-name="Standard">
+                        <style:paragraph-properties fo:text-align="center"/>
+                        <style:text-properties fo:font-style="italic"/>
+                    </style:style>
+                    <style:style style:name="T1" style:family="text">
+                        <style:text-properties fo:color="#c9211e" style:text-position="super 58%"/>
+                    </style:style>
+                    <style:style style:name="Table1.A" style:family="table-column">
+                        <style:table-column-properties style:column-width="2in"/>
+                    </style:style>
+                    <style:style style:name="Table1.A1" style:family="table-cell">
+                        <style:table-cell-properties fo:border="0.5pt solid #000000" fo:background-color="#dddddd"/>
+                    </style:style>
+                </office:automatic-styles>
+                <office:body><office:text>{}</office:text></office:body>
+            </office:document-content>"##,
+            body
+        )
+    }
+
+    fn convert(body: &str) -> Converter {
+        Converter::convert(&content(body), Some(STYLES)).unwrap()
+    }
+
+    /// The properties and text of the paragraphs of the document part.
+    fn paragraphs(converter: &Converter) -> Vec<(String, String)> {
+        let document_text = converter.document_part();
+        let document = xml::Document::parse(&document_text).unwrap();
+        document.descendants()
+            .filter(|node| node.tag_name().name() == "p")
+            .map(|paragraph| {
+                let properties = paragraph.children()
+                    .find(|node| node.tag_name().name() == "pPr")
+                    .map(|properties| document_text[properties.range()].to_owned())
+                    .unwrap_or_default();
+                let text = paragraph.descendants()
+                    .filter_map(|node| match node.tag_name().name() {
+                        "t" => node.text(),
+                        "tab" => Some("\t"),
+                        "br" => Some("\n"),
+                        _ => None,
+                    })
+                    .collect();
+                (properties, text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lengths_are_converted_to_points() {
+        assert_eq!(parse_length("12pt"), Some(12.0));
+        assert_eq!(parse_length("1in"), Some(72.0));
+        assert_eq!(parse_length("-0.5in"), Some(-36.0));
+        assert!((parse_length("2.54cm").unwrap() - 72.0).abs() < 0.001);
+        assert!((parse_length("25.4mm").unwrap() - 72.0).abs() < 0.001);
+        assert_eq!(parse_length("130%"), None);
+        assert_eq!(parse_length("12"), None);
+    }
+
+    #[test]
+    fn white_space_is_collapsed() {
+        let converter = convert(concat!(
+            r#"<text:p>  Two   spaces<text:s text:c="2"/>and<text:tab/>a  "#,
+            r#"<text:line-break/> break</text:p>"#,
+        ));
+        assert_eq!(paragraphs(&converter)[0].1, "Two spaces  and\ta \nbreak");
+    }
+
+    #[test]
+    fn styles_are_written_to_the_paragraphs_and_runs() {
+        let converter = convert(concat!(
+            r#"<text:h text:style-name="Heading_20_1" text:outline-level="1">Title</text:h>"#,
+            r#"<text:p text:style-name="P1">Centered <text:span text:style-name="T1">red</text:span> "#,
+            r#"<text:span text:style-name="Strong_20_Emphasis">strong</text:span></text:p>"#,
+        ));
+
+        let paragraphs = paragraphs(&converter);
+        assert_eq!(paragraphs[0].0, concat!(
+            r#"<w:pPr><w:pStyle w:val="Heading_20_1"/><w:spacing w:before="240" w:after="120"/>"#,
+            r#"<w:outlineLvl w:val="0"/></w:pPr>"#,
+        ));
+        assert_eq!(paragraphs[1].0, r#"<w:pPr><w:pStyle w:val="Standard"/><w:jc w:val="center"/></w:pPr>"#);
+
+        let font = r#"<w:rFonts w:ascii="Liberation Serif" w:hAnsi="Liberation Serif" w:cs="Liberation Serif"/>"#;
+        let size = r#"<w:sz w:val="24"/><w:szCs w:val="24"/>"#;
+        let body = &converter.body;
+        assert!(body.contains(&format!(r#"<w:r><w:rPr>{}<w:b/>{}</w:rPr><w:t xml:space="preserve">Title</w:t></w:r>"#, font, size)),
+            "the relative font size isn't converted, and the bold is written once: {}", body);
+        assert!(body.contains(&format!(r#"<w:rPr>{}<w:i/><w:color w:val="C9211E"/>{}<w:vertAlign w:val="superscript"/></w:rPr><w:t xml:space="preserve">red</w:t>"#, font, size)));
+        assert!(body.contains(&format!(r#"<w:rPr>{}<w:b/><w:i/>{}</w:rPr><w:t xml:space="preserve">strong</w:t>"#, font, size)));
+
+        let styles = converter.styles_part();
+        assert!(xml::Document::parse(&styles).is_ok());
+        assert!(styles.contains(r#"<w:style w:type="paragraph" w:styleId="Heading_20_1"><w:name w:val="Heading 1"/></w:style>"#));
+        assert!(!styles.contains("P1"));
+    }
+
+    #[test]
+    fn lists_are_numbered_and_nested() {
+        let converter = convert(concat!(
+            r#"<text:list xml:id="list1" text:style-name="Numbering_20_123">"#,
+            r#"<text:list-item><text:p>One</text:p><text:p>More of one</text:p>"#,
+            r#"<text:list><text:list-item><text:p>Nested</text:p></text:list-item></text:list></text:list-item>"#,
+            r#"</text:list>"#,
+            r#"<text:p>Between</text:p>"#,
+            r#"<text:list text:continue-list="list1" text:style-name="Numbering_20_123">"#,
+            r#"<text:list-item><text:p>Two</text:p></text:list-item></text:list>"#,
+            r#"<text:list><text:list-item><text:p>Bullet</text:p></text:list-item></text:list>"#,
+        ));
+
+        let paragraphs = paragraphs(&converter);
+        let numbering = r#"<w:pPr><w:numPr><w:ilvl w:val="{}"/><w:numId w:val="{}"/></w:numPr><w:ind w:left="{}" w:hanging="{}"/></w:pPr>"#;
+        let numbered = |level: u32, id: u32, left: u32, hanging: u32| numbering
+            .replacen("{}", &level.to_string(), 1)
+            .replacen("{}", &id.to_string(), 1)
+            .replacen("{}", &left.to_string(), 1)
+            .replacen("{}", &hanging.to_string(), 1);
+
+        assert_eq!(paragraphs[0], (numbered(0, 1, 720, 360), String::from("One")));
+        assert_eq!(paragraphs[1], (String::from(r#"<w:pPr><w:ind w:left="720"/></w:pPr>"#), String::from("More of one")));
+        assert_eq!(paragraphs[2], (numbered(1, 1, 1440, 360), String::from("Nested")));
+        assert_eq!(paragraphs[3], (String::new(), String::from("Between")));
+        assert_eq!(paragraphs[4], (numbered(0, 1, 720, 360), String::from("Two")));
+        assert_eq!(paragraphs[5], (numbered(0, 2, 720, 360), String::from("Bullet")));
+
+        let numbering_part = converter.numbering_part().unwrap();
+        assert!(xml::Document::parse(&numbering_part).is_ok());
+        assert!(numbering_part.contains(r#"<w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/>"#));
+        assert!(numbering_part.contains(r#"<w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="bullet"/><w:lvlText w:val="-"/>"#));
+    }
+
+    #[test]
+    fn tables_get_a_grid_and_cell_properties() {
+        let converter = convert(concat!(
+            r#"<table:table><table:table-column table:style-name="Table1.A" table:number-columns-repeated="2"/>"#,
+            r#"<table:table-header-rows><table:table-row>"#,
+            r#"<table:table-cell table:style-name="Table1.A1" table:number-columns-spanned="2"><text:p>Header</text:p></table:table-cell>"#,
+            r#"<table:covered-table-cell/></table:table-row></table:table-header-rows>"#,
+            r#"<table:table-row><table:table-cell><text:p>A</text:p></table:table-cell><table:table-cell/></table:table-row>"#,
+            r#"</table:table>"#,
+        ));
+
+        let body = &converter.body;
+        assert!(body.starts_with(r#"<w:tbl><w:tblGrid><w:gridCol w:w="2880"/><w:gridCol w:w="2880"/></w:tblGrid><w:tr>"#));
+        assert!(body.contains(concat!(
+            r#"<w:tc><w:tcPr><w:tcW w:w="5760" w:type="dxa"/><w:gridSpan w:val="2"/><w:tcBorders>"#,
+            r#"<w:top w:val="single" w:sz="4" w:space="0" w:color="000000"/>"#,
+        )));
+        assert!(body.contains(r#"<w:shd w:val="clear" w:color="auto" w:fill="DDDDDD"/></w:tcPr>"#));
+        assert!(body.ends_with(r#"<w:tc><w:tcPr><w:tcW w:w="2880" w:type="dxa"/></w:tcPr><w:p/></w:tc></w:tr></w:tbl>"#));
+        assert_eq!(body.matches("<w:tc>").count(), 3);
+    }
+
+    #[test]
+    fn documents_are_laid_out_with_their_links_and_page_size() {
+        let document = content(concat!(
+            r#"<text:h text:style-name="Heading_20_1">Title</text:h>"#,
+            r#"<text:p>See <text:a xlink:type="simple" xlink:href="https://example.com/">the site</text:a>"#,
+            r##" or <text:a xlink:href="#Title">the title</text:a>.</text:p>"##,
+        ));
+
+        let converter = Converter::convert(&document, Some(STYLES)).unwrap();
+        assert!(converter.document_part().ends_with(concat!(
+            r#"<w:sectPr><w:pgSz w:w="12240" w:h="15840"/>"#,
+            r#"<w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr>"#,
+            r#"</w:body></w:document>"#,
+        )));
+        assert_eq!(converter.hyperlinks, ["https://example.com/"]);
+
+        let mut text_calculator = MockTextCalculator::new(10.0, 2.0);
+        let result = process_odt(&document, Some(STYLES), &mut text_calculator, "Times New Roman", RevisionView::Markup, &mut |_: f32| ()).unwrap();
+
+        let heading = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::Paragraph(..))).unwrap();
+        assert_eq!(heading.text_settings.heading_level(), Some(0));
+
+        let link = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::Hyperlink(..))).unwrap();
+        let wp::NodeData::Hyperlink(hyperlink) = &link.data else {
+            unreachable!();
+        };
+        assert_eq!(hyperlink.relationship.as_ref().unwrap().borrow().target, "https://example.com/");
+    }
+
+    #[test]
+    fn other_documents_are_refused() {
+        let spreadsheet = r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0">
+            <office:body><office:spreadsheet/></office:body></office:document-content>"#;
+        assert!(matches!(Converter::convert(spreadsheet, None), Err(OdfError::NotATextDocument)));
+        assert!(is_open_document_text_path(Path::new("Report.ODT")));
+        assert!(!is_open_document_text_path(Path::new("report.ods")));
+    }
+}
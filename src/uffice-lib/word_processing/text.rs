@@ -0,0 +1,810 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Opening plain text (.txt) and Markdown (.md) files, which are converted to
+// a WordprocessingML main document part and then laid out like any other
+// document.
+//
+// Plain text becomes a paragraph per line. Of Markdown, the headings, lists,
+// code blocks, emphasis, code spans and links are converted, following
+// CommonMark loosely; other syntax is shown as-is.
+
+use std::{path::Path, rc::Rc};
+
+use crate::{
+    error::Error,
+    gui::painter::TextCalculator,
+    relationships::{
+        Relationship,
+        RelationshipType,
+        Relationships,
+    },
+    wp::{
+        revisions::RevisionView,
+        save::escape,
+    },
+};
+
+use super::{
+    CONVERTED_DOCUMENT_END,
+    CONVERTED_DOCUMENT_START,
+    DocumentResult,
+    LoadObserver,
+    process_converted_document,
+};
+
+/// The number of levels of a list, which is the number of levels a numbering
+/// definition can have (17.9.3 ilvl).
+const LIST_LEVELS: usize = 9;
+
+/// The indentation of each level of a list, and the part of it the bullet or
+/// number hangs into, in twentieths of a point.
+const LIST_INDENTATION: u32 = 720;
+const LIST_HANGING_INDENTATION: u32 = 360;
+
+/// The bullets of the levels of unordered lists, starting again after the
+/// last one.
+const LIST_BULLETS: [&str; 3] = ["\u{2022}", "\u{25E6}", "\u{25AA}"];
+
+/// The font sizes of the headings, in half-points, from level 1 to 6.
+const HEADING_FONT_SIZES: [u32; 6] = [32, 28, 26, 24, 22, 22];
+
+const CODE_FONT: &str = r#"<w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/>"#;
+
+const STYLES_PART_START: &str = concat!(
+    r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+    r#"<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:cs="Calibri"/>"#,
+    r#"<w:sz w:val="22"/><w:szCs w:val="22"/></w:rPr></w:rPrDefault></w:docDefaults>"#,
+    r#"<w:style w:type="paragraph" w:styleId="BodyText"><w:name w:val="Body Text"/><w:pPr><w:spacing w:after="160"/></w:pPr></w:style>"#,
+    r#"<w:style w:type="paragraph" w:styleId="Code"><w:name w:val="Code"/><w:rPr>"#,
+    r#"<w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/><w:sz w:val="20"/><w:szCs w:val="20"/></w:rPr></w:style>"#,
+    r#"<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr>"#,
+    r#"<w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style>"#,
+);
+
+/// The formats of the text files that can be opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    PlainText,
+    Markdown,
+}
+
+impl TextFormat {
+    /// Finds the format of the file by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_string_lossy().to_ascii_lowercase().as_str() {
+            "txt" | "text" => Some(Self::PlainText),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// The marker of a list item (CommonMark 5.2), of which the first one of a
+/// level decides the numbering of that level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ListMarker {
+    ordered: bool,
+
+    /// The number of an item of an ordered list.
+    number: u32,
+}
+
+/// The formatting of a piece of the text of a paragraph.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SpanStyle {
+    bold: bool,
+    italic: bool,
+    code: bool,
+
+    /// The index of the target of the link in [Converter::hyperlinks].
+    link: Option<usize>,
+}
+
+/// Text with the same formatting, of which the tabs and line breaks are kept
+/// as characters.
+#[derive(Debug, PartialEq)]
+struct Span {
+    style: SpanStyle,
+    text: String,
+}
+
+impl Span {
+    fn write_run(&self, output: &mut String) {
+        let mut properties = String::new();
+        if self.style.link.is_some() {
+            properties += r#"<w:rStyle w:val="Hyperlink"/>"#;
+        }
+        if self.style.code {
+            properties += CODE_FONT;
+        }
+        if self.style.bold {
+            properties += "<w:b/>";
+        }
+        if self.style.italic {
+            properties += "<w:i/>";
+        }
+
+        *output += "<w:r>";
+        if !properties.is_empty() {
+            *output += &format!("<w:rPr>{}</w:rPr>", properties);
+        }
+        write_text(output, &self.text);
+        *output += "</w:r>";
+    }
+}
+
+/// The paragraphs of which the lines are collected until they end.
+#[derive(Debug)]
+enum PendingParagraph {
+    /// A paragraph of text, which is indented as the `level` of the list it
+    /// is part of.
+    Body { level: Option<usize> },
+
+    ListItem { list: usize, level: usize },
+}
+
+/// Converts the text file to the body of a main document part.
+#[derive(Debug, Default)]
+struct Converter {
+    body: String,
+
+    /// The targets of the links, of which the relationship id is "rId"
+    /// followed by the index plus one.
+    hyperlinks: Vec<String>,
+
+    /// The markers of the levels of the lists, each of which is numbered by
+    /// the numbering definition with the index plus one as its id.
+    lists: Vec<[Option<ListMarker>; LIST_LEVELS]>,
+}
+
+impl Converter {
+    fn convert_plain_text(text: &str) -> Self {
+        let mut converter = Self::default();
+        for line in text.lines() {
+            converter.body += "<w:p>";
+            if !line.is_empty() {
+                Span { style: SpanStyle::default(), text: String::from(line) }.write_run(&mut converter.body);
+            }
+            converter.body += "</w:p>";
+        }
+        converter
+    }
+
+    fn convert_markdown(text: &str) -> Self {
+        let mut converter = Self::default();
+
+        let mut pending: Option<(PendingParagraph, String)> = None;
+
+        // The list the items are added to, and the indentation of the
+        // markers of its levels.
+        let mut list = None;
+        let mut list_indentations: Vec<usize> = Vec::new();
+
+        // The fence of the code block the lines are in.
+        let mut fence: Option<&str> = None;
+        let mut after_blank_line = false;
+
+        for line in text.lines() {
+            if let Some(marker) = fence {
+                if line.trim_start().starts_with(marker) {
+                    fence = None;
+                } else {
+                    converter.write_paragraph(r#"<w:pStyle w:val="Code"/>"#, &[Span { style: SpanStyle::default(), text: String::from(line) }]);
+                }
+                continue;
+            }
+
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() {
+                converter.finish_paragraph(pending.take());
+                after_blank_line = true;
+                continue;
+            }
+
+            let indentation = indentation_of(line);
+            let continues_paragraph = pending.is_some() && !after_blank_line;
+            after_blank_line = false;
+
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+                converter.finish_paragraph(pending.take());
+                list = None;
+                fence = Some(marker);
+                continue;
+            }
+
+            if let Some((level, heading)) = parse_heading(trimmed) {
+                converter.finish_paragraph(pending.take());
+                list = None;
+                converter.write_heading(level, heading);
+                continue;
+            }
+
+            // A line of equals signs or hyphens underlines the paragraph
+            // before it as a heading (CommonMark 4.3).
+            if continues_paragraph && matches!(pending, Some((PendingParagraph::Body { level: None }, _))) {
+                let underline = trimmed.trim_end();
+                if let Some(level) = ['=', '-'].into_iter().position(|c| underline.chars().all(|character| character == c)) {
+                    let (_, heading) = pending.take().unwrap();
+                    converter.write_heading(level + 1, heading.trim_end());
+                    continue;
+                }
+            }
+
+            if is_thematic_break(trimmed) {
+                converter.finish_paragraph(pending.take());
+                list = None;
+                continue;
+            }
+
+            if let Some((marker, content)) = parse_list_marker(trimmed) {
+                converter.finish_paragraph(pending.take());
+
+                let list = *list.get_or_insert_with(|| {
+                    converter.lists.push(Default::default());
+                    list_indentations.clear();
+                    converter.lists.len() - 1
+                });
+
+                while list_indentations.last().is_some_and(|last| *last > indentation) {
+                    list_indentations.pop();
+                }
+                if list_indentations.last() != Some(&indentation) {
+                    list_indentations.push(indentation);
+                }
+
+                let level = (list_indentations.len() - 1).min(LIST_LEVELS - 1);
+                converter.lists[list][level].get_or_insert(marker);
+                pending = Some((PendingParagraph::ListItem { list, level }, String::from(content)));
+                continue;
+            }
+
+            if continues_paragraph {
+                let (_, text) = pending.as_mut().unwrap();
+                append_line(text, trimmed);
+                continue;
+            }
+
+            // An indented paragraph after a list item is part of that item,
+            // otherwise it ends the list.
+            let level = match list {
+                Some(..) if indentation > 0 => list_indentations.iter()
+                    .filter(|marker_indentation| **marker_indentation < indentation)
+                    .count()
+                    .checked_sub(1),
+                _ => None,
+            };
+            if level.is_none() {
+                list = None;
+            }
+
+            converter.finish_paragraph(pending.take());
+            pending = Some((PendingParagraph::Body { level }, String::from(trimmed)));
+        }
+
+        converter.finish_paragraph(pending);
+        converter
+    }
+
+    fn finish_paragraph(&mut self, paragraph: Option<(PendingParagraph, String)>) {
+        let Some((paragraph, text)) = paragraph else {
+            return;
+        };
+
+        let properties = match paragraph {
+            PendingParagraph::Body { level: None } => String::from(r#"<w:pStyle w:val="BodyText"/>"#),
+            PendingParagraph::Body { level: Some(level) } => {
+                format!(r#"<w:pStyle w:val="BodyText"/><w:ind w:left="{}"/>"#, list_indentation(level))
+            }
+            PendingParagraph::ListItem { list, level } => format!(
+                r#"<w:numPr><w:ilvl w:val="{}"/><w:numId w:val="{}"/></w:numPr><w:ind w:left="{}" w:hanging="{}"/>"#,
+                level, list + 1, list_indentation(level), LIST_HANGING_INDENTATION
+            ),
+        };
+
+        let spans = self.parse_inlines(text.trim_end());
+        self.write_paragraph(&properties, &spans);
+    }
+
+    fn write_heading(&mut self, level: usize, text: &str) {
+        let spans = self.parse_inlines(text);
+        self.write_paragraph(&format!(r#"<w:pStyle w:val="Heading{}"/>"#, level), &spans);
+    }
+
+    fn write_paragraph(&mut self, properties: &str, spans: &[Span]) {
+        self.body += "<w:p>";
+        if !properties.is_empty() {
+            self.body += &format!("<w:pPr>{}</w:pPr>", properties);
+        }
+
+        let mut link = None;
+        for span in spans {
+            if span.style.link != link {
+                if link.is_some() {
+                    self.body += "</w:hyperlink>";
+                }
+                if let Some(index) = span.style.link {
+                    self.body += &format!(r#"<w:hyperlink r:id="{}">"#, hyperlink_relationship_id(index));
+                }
+                link = span.style.link;
+            }
+
+            span.write_run(&mut self.body);
+        }
+
+        if link.is_some() {
+            self.body += "</w:hyperlink>";
+        }
+        self.body += "</w:p>";
+    }
+
+    fn parse_inlines(&mut self, text: &str) -> Vec<Span> {
+        let mut spans = Vec::new();
+        parse_inlines(text, SpanStyle::default(), &mut self.hyperlinks, &mut spans);
+        spans
+    }
+
+    fn document_part(&self) -> String {
+        format!("{}{}{}", CONVERTED_DOCUMENT_START, self.body, CONVERTED_DOCUMENT_END)
+    }
+
+    fn numbering_part(&self) -> Option<String> {
+        if self.lists.is_empty() {
+            return None;
+        }
+
+        let mut part = String::from(r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#);
+        for (index, levels) in self.lists.iter().enumerate() {
+            part += &format!(r#"<w:abstractNum w:abstractNumId="{}">"#, index);
+            for (level, marker) in levels.iter().enumerate() {
+                let (start, format, text) = match marker {
+                    Some(ListMarker { ordered: true, number }) => (*number, "decimal", format!("%{}.", level + 1)),
+                    _ => (1, "bullet", String::from(LIST_BULLETS[level % LIST_BULLETS.len()])),
+                };
+
+                part += &format!(
+                    r#"<w:lvl w:ilvl="{}"><w:start w:val="{}"/><w:numFmt w:val="{}"/><w:lvlText w:val="{}"/><w:pPr><w:ind w:left="{}" w:hanging="{}"/></w:pPr></w:lvl>"#,
+                    level, start, format, text, list_indentation(level), LIST_HANGING_INDENTATION
+                );
+            }
+            part += "</w:abstractNum>";
+        }
+
+        for index in 0..self.lists.len() {
+            part += &format!(r#"<w:num w:numId="{}"><w:abstractNumId w:val="{}"/></w:num>"#, index + 1, index);
+        }
+
+        part += "</w:numbering>";
+        Some(part)
+    }
+
+    fn relationships(&self) -> Relationships {
+        let mut relationships = Relationships::empty();
+        for (index, target) in self.hyperlinks.iter().enumerate() {
+            relationships.insert(Relationship {
+                id: Rc::from(hyperlink_relationship_id(index)),
+                relation_type: RelationshipType::Hyperlink,
+                target: target.clone(),
+                data: Vec::new(),
+            });
+        }
+        relationships
+    }
+}
+
+pub(super) fn hyperlink_relationship_id(index: usize) -> String {
+    format!("rId{}", index + 1)
+}
+
+fn list_indentation(level: usize) -> u32 {
+    LIST_INDENTATION * (level as u32 + 1)
+}
+
+/// The width of the whitespace at the start of the line, of which a tab
+/// counts as four spaces (CommonMark 2.2).
+fn indentation_of(line: &str) -> usize {
+    line.chars()
+        .take_while(|character| character.is_whitespace())
+        .map(|character| if character == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Adds the line to the text of a paragraph, which is broken when the line
+/// before it ends with two spaces or a backslash (CommonMark 6.7).
+fn append_line(text: &mut String, line: &str) {
+    let hard_break = text.ends_with("  ") || text.ends_with('\\');
+    if text.ends_with('\\') {
+        text.pop();
+    }
+    text.truncate(text.trim_end().len());
+    text.push(if hard_break { '\n' } else { ' ' });
+    *text += line;
+}
+
+/// Parses an ATX heading (CommonMark 4.2), returning its level and text.
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|character| *character == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    // The heading can be closed by number signs as well.
+    let text = rest.trim();
+    let unclosed = text.trim_end_matches('#');
+    if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        return Some((level, unclosed.trim_end()));
+    }
+    Some((level, text))
+}
+
+/// Checks if the line is a thematic break (CommonMark 4.1), which isn't shown.
+fn is_thematic_break(line: &str) -> bool {
+    let mut characters = line.chars().filter(|character| !character.is_whitespace());
+    let Some(first) = characters.next().filter(|first| matches!(first, '-' | '*' | '_')) else {
+        return false;
+    };
+
+    let mut count = 1;
+    for character in characters {
+        if character != first {
+            return false;
+        }
+        count += 1;
+    }
+    count >= 3
+}
+
+/// Parses the marker of a list item, returning the text after it.
+fn parse_list_marker(line: &str) -> Option<(ListMarker, &str)> {
+    let (marker, rest) = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => (ListMarker { ordered: false, number: 1 }, rest),
+        None => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            if !(1..=9).contains(&digits) {
+                return None;
+            }
+
+            let rest = line[digits..].strip_prefix(['.', ')'])?;
+            (ListMarker { ordered: true, number: line[..digits].parse().ok()? }, rest)
+        }
+    };
+
+    if rest.is_empty() {
+        return Some((marker, rest));
+    }
+    rest.starts_with([' ', '\t']).then(|| (marker, rest.trim_start()))
+}
+
+/// Parses a link after its opening bracket (CommonMark 6.3), returning its
+/// text, its target and the length of the rest of the link.
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let mut depth = 0;
+    let mut label_end = None;
+    let mut escaped = false;
+    for (index, character) in text.char_indices() {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' if depth == 0 => {
+                label_end = Some(index);
+                break;
+            }
+            ']' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    let label_end = label_end?;
+    let destination = text[label_end + 1..].strip_prefix('(')?;
+    let destination_end = destination.find(')')?;
+
+    // The destination can be followed by a title, which isn't shown.
+    let target = destination[..destination_end].split_whitespace().next().unwrap_or_default();
+    let target = target.strip_prefix('<').and_then(|target| target.strip_suffix('>')).unwrap_or(target);
+    Some((&text[..label_end], target, label_end + 2 + destination_end + 1))
+}
+
+fn push_text(spans: &mut Vec<Span>, style: SpanStyle, text: &str) {
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.text += text,
+        _ => spans.push(Span { style, text: String::from(text) }),
+    }
+}
+
+/// Parses the emphasis, code spans and links of the text of a paragraph
+/// (CommonMark 6), adding the targets of the links to `hyperlinks`.
+fn parse_inlines(text: &str, mut style: SpanStyle, hyperlinks: &mut Vec<String>, spans: &mut Vec<Span>) {
+    let mut index = 0;
+    while let Some(character) = text[index..].chars().next() {
+        let rest = &text[index..];
+        let after = &rest[character.len_utf8()..];
+        let previous = text[..index].chars().next_back();
+
+        match character {
+            '\\' => if let Some(escaped) = after.chars().next().filter(char::is_ascii_punctuation) {
+                push_text(spans, style, &after[..1]);
+                index += 1 + escaped.len_utf8();
+                continue;
+            }
+
+            '`' => if let Some(end) = after.find('`') {
+                push_text(spans, SpanStyle { code: true, ..style }, &after[..end]);
+                index += 1 + end + 1;
+                continue;
+            }
+
+            '*' | '_' => {
+                let length = if after.starts_with(character) { 2 } else { 1 };
+                let delimiter = &rest[..length];
+                let next = rest[length..].chars().next();
+
+                // Underscores within words, as in snake_case, aren't
+                // emphasis.
+                let within_word = |neighbour: Option<char>| character == '_' && neighbour.is_some_and(char::is_alphanumeric);
+                let can_open = next.is_some_and(|next| !next.is_whitespace()) && !within_word(previous);
+                let can_close = previous.is_some_and(|previous| !previous.is_whitespace()) && !within_word(next);
+
+                let active = if length == 2 { &mut style.bold } else { &mut style.italic };
+                if *active && can_close {
+                    *active = false;
+                } else if !*active && can_open && rest[length..].contains(delimiter) {
+                    *active = true;
+                } else {
+                    push_text(spans, style, delimiter);
+                }
+
+                index += length;
+                continue;
+            }
+
+            // The text of images is shown instead of the images.
+            '!' if after.starts_with('[') => if let Some((label, _, length)) = parse_link(&after[1..]) {
+                push_text(spans, style, label);
+                index += 2 + length;
+                continue;
+            }
+
+            '[' => if let Some((label, target, length)) = parse_link(after) {
+                hyperlinks.push(String::from(target));
+                parse_inlines(label, SpanStyle { link: Some(hyperlinks.len() - 1), ..style }, hyperlinks, spans);
+                index += 1 + length;
+                continue;
+            }
+
+            // 6.5 Autolinks
+            '<' => if let Some(end) = after.find('>') {
+                let target = &after[..end];
+                if target.contains(':') && !target.contains(char::is_whitespace) {
+                    hyperlinks.push(String::from(target));
+                    push_text(spans, SpanStyle { link: Some(hyperlinks.len() - 1), ..style }, target);
+                    index += 1 + end + 1;
+                    continue;
+                }
+            }
+
+            _ => (),
+        }
+
+        push_text(spans, style, &rest[..character.len_utf8()]);
+        index += character.len_utf8();
+    }
+}
+
+/// Writes the text of a run, of which the tabs and line breaks are elements.
+/// Other control characters can't be part of XML documents, so are left out.
+pub(super) fn write_text(output: &mut String, text: &str) {
+    for (index, line) in text.split('\n').enumerate() {
+        if index != 0 {
+            *output += "<w:br/>";
+        }
+
+        for (index, part) in line.split('\t').enumerate() {
+            if index != 0 {
+                *output += "<w:tab/>";
+            }
+
+            let part = part.replace(char::is_control, "");
+            if !part.is_empty() {
+                *output += &format!(r#"<w:t xml:space="preserve">{}</w:t>"#, escape(&part));
+            }
+        }
+    }
+}
+
+fn styles_part() -> String {
+    let mut part = String::from(STYLES_PART_START);
+    for (index, size) in HEADING_FONT_SIZES.into_iter().enumerate() {
+        part += &format!(
+            concat!(
+                r#"<w:style w:type="paragraph" w:styleId="Heading{level}"><w:name w:val="heading {level}"/>"#,
+                r#"<w:pPr><w:spacing w:before="240" w:after="120"/><w:outlineLvl w:val="{index}"/></w:pPr>"#,
+                r#"<w:rPr><w:b/><w:sz w:val="{size}"/><w:szCs w:val="{size}"/></w:rPr></w:style>"#,
+            ),
+            level = index + 1, index = index, size = size
+        );
+    }
+    part += "</w:styles>";
+    part
+}
+
+/// Converts the text in the given format and lays it out.
+pub fn process_text(text: &str, format: TextFormat, text_calculator: &mut dyn TextCalculator, fallback_font: &str,
+        revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    let converter = match format {
+        TextFormat::PlainText => Converter::convert_plain_text(text),
+        TextFormat::Markdown => Converter::convert_markdown(text),
+    };
+
+    process_converted_document(&converter.document_part(), &styles_part(), converter.numbering_part().as_deref(),
+        &converter.relationships(), text_calculator, fallback_font, revision_view, observer)
+}
+
+/// Reads the text file and lays it out. Text that isn't valid UTF-8 is shown
+/// with replacement characters.
+pub fn process_text_file(path: &Path, format: TextFormat, text_calculator: &mut dyn TextCalculator, fallback_font: &str,
+        revision_view: RevisionView, observer: &mut dyn LoadObserver) -> Result<DocumentResult, Error> {
+    let data = std::fs::read(path)?;
+    process_text(&String::from_utf8_lossy(&data), format, text_calculator, fallback_font, revision_view, observer)
+}
+
+#[cfg(test)]
+mod tests {
+    use roxmltree as xml;
+
+    use crate::{gui::painter::mock::MockTextCalculator, wp};
+
+    use super::*;
+
+    fn paragraph_texts(converter: &Converter) -> Vec<(Option<String>, String)> {
+        let document_text = converter.document_part();
+        let document = xml::Document::parse(&document_text).unwrap();
+        document.descendants()
+            .filter(|node| node.tag_name().name() == "p")
+            .map(|paragraph| {
+                let style = paragraph.descendants()
+                    .find(|node| node.tag_name().name() == "pStyle")
+                    .and_then(|style| style.attribute((crate::WORD_PROCESSING_XML_NAMESPACE, "val")))
+                    .map(String::from);
+                let text = paragraph.descendants()
+                    .filter_map(|node| match node.tag_name().name() {
+                        "t" => node.text(),
+                        "tab" => Some("\t"),
+                        "br" => Some("\n"),
+                        _ => None,
+                    })
+                    .collect();
+                (style, text)
+            })
+            .collect()
+    }
+
+    fn spans_of(text: &str) -> (Vec<(String, SpanStyle)>, Vec<String>) {
+        let mut hyperlinks = Vec::new();
+        let mut spans = Vec::new();
+        parse_inlines(text, SpanStyle::default(), &mut hyperlinks, &mut spans);
+        (spans.into_iter().map(|span| (span.text, span.style)).collect(), hyperlinks)
+    }
+
+    #[test]
+    fn text_formats_are_found_by_extension() {
+        assert_eq!(TextFormat::from_path(Path::new("notes.txt")), Some(TextFormat::PlainText));
+        assert_eq!(TextFormat::from_path(Path::new("README.MD")), Some(TextFormat::Markdown));
+        assert_eq!(TextFormat::from_path(Path::new("report.docx")), None);
+    }
+
+    #[test]
+    fn plain_text_lines_become_paragraphs() {
+        let converter = Converter::convert_plain_text("One\tTwo\r\n\r\n# 1 < 2 & **3**\x0C");
+        assert_eq!(paragraph_texts(&converter), [
+            (None, String::from("One\tTwo")),
+            (None, String::new()),
+            (None, String::from("# 1 < 2 & **3**")),
+        ]);
+        assert!(converter.numbering_part().is_none());
+    }
+
+    #[test]
+    fn markdown_blocks_become_styled_paragraphs() {
+        let converter = Converter::convert_markdown(concat!(
+            "# Title #\n",
+            "First line\n",
+            "second line  \n",
+            "after break\n",
+            "\n",
+            "Underlined\n",
+            "---\n",
+            "```\n",
+            "let *x* = 1;\n",
+            "```\n",
+            "***\n",
+            "snake_case",
+        ));
+
+        assert_eq!(paragraph_texts(&converter), [
+            (Some(String::from("Heading1")), String::from("Title")),
+            (Some(String::from("BodyText")), String::from("First line second line\nafter break")),
+            (Some(String::from("Heading2")), String::from("Underlined")),
+            (Some(String::from("Code")), String::from("let *x* = 1;")),
+            (Some(String::from("BodyText")), String::from("snake_case")),
+        ]);
+    }
+
+    #[test]
+    fn markdown_lists_are_numbered_separately() {
+        let converter = Converter::convert_markdown(concat!(
+            "3. Three\n",
+            "   - Nested\n",
+            "4. Four\n",
+            "\n",
+            "Between\n",
+            "\n",
+            "* Bullet\n",
+        ));
+
+        assert_eq!(converter.lists.len(), 2);
+        assert_eq!(converter.lists[0][0], Some(ListMarker { ordered: true, number: 3 }));
+        assert_eq!(converter.lists[0][1], Some(ListMarker { ordered: false, number: 1 }));
+        assert_eq!(converter.lists[1][0], Some(ListMarker { ordered: false, number: 1 }));
+
+        let document_text = converter.document_part();
+        let document = xml::Document::parse(&document_text).unwrap();
+        let numbering: Vec<_> = document.descendants()
+            .filter(|node| node.tag_name().name() == "numPr")
+            .map(|numbering| {
+                let value = |name: &str| numbering.children()
+                    .find(|node| node.tag_name().name() == name)
+                    .and_then(|node| node.attribute((crate::WORD_PROCESSING_XML_NAMESPACE, "val")))
+                    .unwrap()
+                    .to_owned();
+                (value("numId"), value("ilvl"))
+            })
+            .collect();
+        assert_eq!(numbering, [("1", "0"), ("1", "1"), ("1", "0"), ("2", "0")].map(|(id, level)| (id.to_owned(), level.to_owned())));
+
+        let numbering_part = converter.numbering_part().unwrap();
+        assert!(xml::Document::parse(&numbering_part).is_ok());
+        assert!(numbering_part.contains(r#"<w:lvl w:ilvl="0"><w:start w:val="3"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/>"#));
+    }
+
+    #[test]
+    fn markdown_emphasis_code_and_links() {
+        let bold = SpanStyle { bold: true, ..Default::default() };
+        let italic = SpanStyle { italic: true, ..Default::default() };
+        let code = SpanStyle { code: true, ..Default::default() };
+        let link = SpanStyle { link: Some(0), ..Default::default() };
+
+        let (spans, hyperlinks) = spans_of(r#"**Bold** _it_ `*x*` [a *b*](https://example.com/ "Title") 2 * 3 \*"#);
+        assert_eq!(spans, [
+            (String::from("Bold"), bold),
+            (String::from(" "), SpanStyle::default()),
+            (String::from("it"), italic),
+            (String::from(" "), SpanStyle::default()),
+            (String::from("*x*"), code),
+            (String::from(" "), SpanStyle::default()),
+            (String::from("a "), link),
+            (String::from("b"), SpanStyle { italic: true, ..link }),
+            (String::from(" 2 * 3 *"), SpanStyle::default()),
+        ]);
+        assert_eq!(hyperlinks, ["https://example.com/"]);
+
+        let (spans, hyperlinks) = spans_of("See <https://example.org> or ![logo](logo.png)");
+        assert_eq!(spans.iter().map(|(text, _)| text.as_str()).collect::<String>(), "See https://example.org or logo");
+        assert_eq!(hyperlinks, ["https://example.org"]);
+    }
+
+    #[test]
+    fn markdown_links_get_relationships() {
+        let mut text_calculator = MockTextCalculator::new(10.0, 2.0);
+        let result = process_text("Go to [the site](https://example.com).", TextFormat::Markdown, &mut text_calculator,
+            "Times New Roman", RevisionView::Markup, &mut |_: f32| ()).unwrap();
+
+        let link = result.root_node.find_first(|node| matches!(node.data, wp::NodeData::Hyperlink(..))).unwrap();
+        let wp::NodeData::Hyperlink(hyperlink) = &link.data else {
+            unreachable!();
+        };
+        assert_eq!(hyperlink.relationship.as_ref().unwrap().borrow().target, "https://example.com");
+    }
+}
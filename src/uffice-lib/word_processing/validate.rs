@@ -0,0 +1,417 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Checks the integrity of a WordprocessingML package before it is loaded:
+// whether the parts the document can't be shown without are present, whether
+// the XML of the parts is well-formed, whether the relationships refer to
+// parts that exist, and whether the document uses features we don't support.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use roxmltree as xml;
+use crate::package::{
+    normalize_part_name,
+    relationships_part_name,
+    resolve_relationship_target,
+    source_part_name,
+    CONTENT_TYPES_PART,
+    PACKAGE_RELATIONSHIPS_PART,
+    RELATIONSHIP_TYPE_OFFICE_DOCUMENT,
+};
+
+const RELATIONSHIP_TYPE_STYLES: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles";
+const RELATIONSHIP_TYPE_NUMBERING: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering";
+const RELATIONSHIP_TYPE_VBA_PROJECT: &str = "http://schemas.microsoft.com/office/2006/relationships/vbaProject";
+
+/// The part name used by Microsoft Word, which is assumed when the package
+/// relationships don't refer to the main document part.
+const DEFAULT_MAIN_DOCUMENT_PART: &str = "word/document.xml";
+
+/// The elements of the main document part that aren't rendered, with a
+/// description of what the user is missing.
+const UNSUPPORTED_ELEMENTS: &[(&str, &str)] = &[
+    ("altChunk", "imported documents (w:altChunk)"),
+    ("object", "embedded objects (w:object)"),
+    ("oMath", "equations (m:oMath)"),
+    ("pict", "VML pictures (w:pict)"),
+    ("subDoc", "subdocuments (w:subDoc)"),
+    ("txbxContent", "text boxes (w:txbxContent)"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// The file isn't a ZIP package at all.
+    NotAPackage,
+
+    /// A part the package should have is missing.
+    MissingPart,
+
+    /// The XML of the part isn't well-formed, or the part couldn't be read.
+    MalformedXml,
+
+    /// A relationship refers to a part that isn't in the package.
+    BrokenRelationship,
+
+    /// The document uses a feature that isn't supported, so it won't look as
+    /// intended.
+    UnsupportedFeature,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::NotAPackage => "not a package",
+            Self::MissingPart => "missing part",
+            Self::MalformedXml => "malformed XML",
+            Self::BrokenRelationship => "broken relationship",
+            Self::UnsupportedFeature => "unsupported feature",
+        })
+    }
+}
+
+/// A problem found in the package.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Issue {
+    pub kind: IssueKind,
+
+    /// The part the issue was found in, or is about.
+    pub part: String,
+
+    pub message: String,
+
+    /// Whether the document can't be loaded because of this issue.
+    pub fatal: bool,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.fatal { "error" } else { "warning" };
+        write!(f, "{} ({}) [{}]: {}", severity, self.kind, self.part, self.message)
+    }
+}
+
+/// The result of [validate_package].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    /// The name of the main document part, if it is in the package.
+    pub main_document_part: Option<String>,
+
+    /// The number of parts in the package.
+    pub part_count: usize,
+
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// Checks if the document can be loaded, although it might not look as
+    /// intended if there are other issues.
+    pub fn is_loadable(&self) -> bool {
+        !self.issues.iter().any(|issue| issue.fatal)
+    }
+
+    fn push(&mut self, kind: IssueKind, part: &str, message: String, fatal: bool) {
+        self.issues.push(Issue { kind, part: String::from(part), message, fatal });
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Main document part: {}", self.main_document_part.as_deref().unwrap_or("(missing)"))?;
+        writeln!(f, "Parts: {}", self.part_count)?;
+
+        if self.issues.is_empty() {
+            return writeln!(f, "No issues found");
+        }
+
+        writeln!(f, "Issues: {}", self.issues.len())?;
+        for issue in &self.issues {
+            writeln!(f, "    {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates the package at the given path, see [validate_package].
+pub fn validate_file(path: &Path) -> ValidationReport {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+    let mut archive = match std::fs::File::open(path) {
+        Ok(file) => match zip::ZipArchive::new(file) {
+            Ok(archive) => archive,
+            Err(e) => {
+                let mut report = ValidationReport::default();
+                report.push(IssueKind::NotAPackage, &file_name, format!("the file isn't a ZIP package: {}", e), true);
+                return report;
+            }
+        },
+        Err(e) => {
+            let mut report = ValidationReport::default();
+            report.push(IssueKind::NotAPackage, &file_name, format!("the file couldn't be opened: {}", e), true);
+            return report;
+        }
+    };
+
+    validate_package(&mut archive)
+}
+
+/// Checks the package for missing parts, malformed XML, broken relationships
+/// and unsupported features. Issues that prevent the document from loading
+/// are marked as fatal.
+pub fn validate_package<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> ValidationReport {
+    let mut report = ValidationReport {
+        part_count: archive.len(),
+        ..Default::default()
+    };
+
+    let part_names: HashSet<String> = archive.file_names().map(String::from).collect();
+
+    // Read the XML parts first, so the malformed ones are known before the
+    // relationships are followed.
+    let mut texts = HashMap::new();
+    let mut malformed = Vec::new();
+    let mut xml_part_names: Vec<&String> = part_names.iter()
+        .filter(|name| name.ends_with(".xml") || name.ends_with(".rels"))
+        .collect();
+    xml_part_names.sort();
+    for name in xml_part_names {
+        let mut text = String::new();
+        let result = archive.by_name(name)
+            .map_err(|e| e.to_string())
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(|e| e.to_string()));
+
+        match result.and_then(|_| xml::Document::parse(&text).map(|_| ()).map_err(|e| e.to_string())) {
+            Ok(()) => _ = texts.insert(name.clone(), text),
+            Err(error) => malformed.push((name.clone(), error)),
+        }
+    }
+
+    if !part_names.contains(CONTENT_TYPES_PART) {
+        report.push(IssueKind::MissingPart, CONTENT_TYPES_PART, String::from("the package has no content types"), false);
+    }
+
+    let main_document_part = match texts.get(PACKAGE_RELATIONSHIPS_PART) {
+        Some(text) => find_target(text, RELATIONSHIP_TYPE_OFFICE_DOCUMENT).map(|target| normalize_part_name(&target)),
+        None => {
+            if !part_names.contains(PACKAGE_RELATIONSHIPS_PART) {
+                report.push(IssueKind::MissingPart, PACKAGE_RELATIONSHIPS_PART, String::from("the package has no relationships"), false);
+            }
+            None
+        }
+    };
+    let main_document_part = main_document_part.unwrap_or_else(|| String::from(DEFAULT_MAIN_DOCUMENT_PART));
+
+    // The parts without which the document can't be loaded.
+    let mut required_parts = vec![main_document_part.clone()];
+
+    if part_names.contains(&main_document_part) {
+        report.main_document_part = Some(main_document_part.clone());
+    } else {
+        report.push(IssueKind::MissingPart, &main_document_part, String::from("the main document part is missing"), true);
+    }
+
+    let main_relationships_part = relationships_part_name(&main_document_part);
+    required_parts.push(main_relationships_part.clone());
+    if let Some(text) = texts.get(&main_relationships_part) {
+        // The default styles are used without the style definitions, so a
+        // missing styles part is reported by the check of the relationships.
+        if find_target(text, RELATIONSHIP_TYPE_STYLES).is_none() {
+            report.push(IssueKind::MissingPart, &main_relationships_part,
+                String::from("the document has no style definitions, the default styles are used"), false);
+        }
+
+        if let Some(target) = find_target(text, RELATIONSHIP_TYPE_NUMBERING) {
+            required_parts.push(resolve_relationship_target(&main_document_part, &target));
+        }
+    } else if !part_names.contains(&main_relationships_part) && report.main_document_part.is_some() {
+        report.push(IssueKind::MissingPart, &main_relationships_part, String::from("the main document part has no relationships"), true);
+    }
+
+    for (name, error) in malformed {
+        let fatal = required_parts.contains(&name);
+        report.push(IssueKind::MalformedXml, &name, error, fatal);
+    }
+
+    let mut relationships_parts: Vec<&String> = texts.keys().filter(|name| name.ends_with(".rels")).collect();
+    relationships_parts.sort();
+    for name in relationships_parts {
+        let Some(source) = source_part_name(name) else {
+            continue;
+        };
+
+        let document = xml::Document::parse(&texts[name]).unwrap();
+        for relationship in document.root_element().children().filter(|child| child.tag_name().name() == "Relationship") {
+            if relationship.attribute("TargetMode") == Some("External") {
+                continue;
+            }
+
+            let id = relationship.attribute("Id").unwrap_or("?");
+            let Some(target) = relationship.attribute("Target") else {
+                report.push(IssueKind::BrokenRelationship, name, format!("relationship {} has no target", id), false);
+                continue;
+            };
+
+            let target_part = resolve_relationship_target(&source, target);
+            if !part_names.contains(&target_part) {
+                let fatal = required_parts.contains(&target_part);
+                report.push(IssueKind::BrokenRelationship, name, format!("relationship {} refers to {}, which is missing", id, target_part), fatal);
+            }
+
+            if relationship.attribute("Type") == Some(RELATIONSHIP_TYPE_VBA_PROJECT) {
+                report.push(IssueKind::UnsupportedFeature, name, String::from("the macros of the document aren't run"), false);
+            }
+        }
+    }
+
+    if let Some(text) = texts.get(&main_document_part) {
+        find_unsupported_features(&mut report, &main_document_part, text);
+    }
+
+    report
+}
+
+/// Finds the target of the first relationship of the given type in the text of
+/// a relationships part.
+fn find_target(text: &str, relationship_type: &str) -> Option<String> {
+    let document = xml::Document::parse(text).ok()?;
+    let target = document.root_element()
+        .children()
+        .filter(|child| child.tag_name().name() == "Relationship")
+        .find(|child| child.attribute("Type") == Some(relationship_type))?
+        .attribute("Target")?;
+    Some(String::from(target))
+}
+
+/// Reports the elements of [UNSUPPORTED_ELEMENTS] in the main document part,
+/// once per kind.
+fn find_unsupported_features(report: &mut ValidationReport, part: &str, text: &str) {
+    let Ok(document) = xml::Document::parse(text) else {
+        return;
+    };
+
+    let mut counts = vec![0; UNSUPPORTED_ELEMENTS.len()];
+    for node in document.descendants().filter(|node| node.is_element()) {
+        let name = node.tag_name().name();
+        if let Some(index) = UNSUPPORTED_ELEMENTS.iter().position(|(element, _)| *element == name) {
+            counts[index] += 1;
+        }
+    }
+
+    for ((_, description), count) in UNSUPPORTED_ELEMENTS.iter().zip(counts) {
+        if count != 0 {
+            report.push(IssueKind::UnsupportedFeature, part, format!("{} are not shown ({} found)", description, count), false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+
+    const PACKAGE_RELATIONSHIPS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+        <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+    </Relationships>"#;
+
+    const DOCUMENT_RELATIONSHIPS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+        <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+        <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/" TargetMode="External"/>
+    </Relationships>"#;
+
+    const DOCUMENT: &str = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p/></w:body></w:document>"#;
+
+    const STYLES: &str = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"/>"#;
+
+    fn create_package(parts: &[(&str, &str)]) -> zip::ZipArchive<Cursor<Vec<u8>>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, text) in parts {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(text.as_bytes()).unwrap();
+        }
+        zip::ZipArchive::new(writer.finish().unwrap()).unwrap()
+    }
+
+    fn valid_parts() -> Vec<(&'static str, &'static str)> {
+        vec![
+            (CONTENT_TYPES_PART, "<Types/>"),
+            (PACKAGE_RELATIONSHIPS_PART, PACKAGE_RELATIONSHIPS),
+            ("word/_rels/document.xml.rels", DOCUMENT_RELATIONSHIPS),
+            ("word/document.xml", DOCUMENT),
+            ("word/styles.xml", STYLES),
+        ]
+    }
+
+    #[test]
+    fn valid_package_has_no_issues() {
+        let report = validate_package(&mut create_package(&valid_parts()));
+        assert_eq!(report.issues, Vec::new());
+        assert_eq!(report.main_document_part.as_deref(), Some("word/document.xml"));
+        assert_eq!(report.part_count, 5);
+        assert!(report.is_loadable());
+    }
+
+    #[test]
+    fn missing_styles_part_is_a_warning() {
+        let mut parts = valid_parts();
+        parts.retain(|(name, _)| *name != "word/styles.xml");
+        parts[2].1 = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+            <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="definitions/styles1.xml"/>
+        </Relationships>"#;
+
+        let report = validate_package(&mut create_package(&parts));
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::BrokenRelationship);
+        assert_eq!(report.issues[0].message, "relationship rId1 refers to word/definitions/styles1.xml, which is missing");
+        assert!(!report.issues[0].fatal);
+        assert!(report.is_loadable());
+
+        // Without a relationship to the style definitions at all.
+        parts[2].1 = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"/>"#;
+        let report = validate_package(&mut create_package(&parts));
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::MissingPart);
+        assert_eq!(report.issues[0].part, "word/_rels/document.xml.rels");
+        assert!(report.is_loadable());
+    }
+
+    #[test]
+    fn malformed_optional_part_is_not_fatal() {
+        let mut parts = valid_parts();
+        parts.push(("word/footnotes.xml", "<w:footnotes>"));
+
+        let report = validate_package(&mut create_package(&parts));
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::MalformedXml);
+        assert_eq!(report.issues[0].part, "word/footnotes.xml");
+        assert!(report.is_loadable());
+
+        parts[3].1 = "<w:document>";
+        let report = validate_package(&mut create_package(&parts));
+        assert!(report.issues.iter().any(|issue| issue.part == "word/document.xml" && issue.fatal));
+    }
+
+    #[test]
+    fn unsupported_features_are_counted() {
+        let mut parts = valid_parts();
+        parts[3].1 = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+                xmlns:m="http://schemas.openxmlformats.org/officeDocument/2006/math">
+            <w:body><w:p><m:oMath/></w:p><w:p><m:oMath/></w:p><w:altChunk/></w:body>
+        </w:document>"#;
+
+        let report = validate_package(&mut create_package(&parts));
+        let messages: Vec<_> = report.issues.iter()
+            .filter(|issue| issue.kind == IssueKind::UnsupportedFeature)
+            .map(|issue| issue.message.as_str())
+            .collect();
+        assert_eq!(messages, [
+            "imported documents (w:altChunk) are not shown (1 found)",
+            "equations (m:oMath) are not shown (2 found)",
+        ]);
+        assert!(report.is_loadable());
+    }
+}
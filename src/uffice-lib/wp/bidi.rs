@@ -0,0 +1,157 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Bidirectional text (UAX #9). The embedding levels are resolved per run,
+// which is embedded in the direction of the run (<w:rtl>), and the text parts
+// of every line are reordered after the paragraph is laid out.
+
+use std::ops::Range;
+
+use unicode_bidi::{BidiInfo, Level};
+
+use crate::text_settings::TextSettings;
+
+/// The embedding level of the paragraph, which is 1 for right-to-left
+/// paragraphs (<w:bidi>).
+pub fn paragraph_level(text_settings: &TextSettings) -> u8 {
+    text_settings.bidi.unwrap_or(false) as u8
+}
+
+/// The level the text of the run is embedded at. Right-to-left runs are at
+/// level 1, and left-to-right runs in a right-to-left paragraph at level 2,
+/// so the text around them flows in the direction of the paragraph.
+pub fn run_level(text_settings: &TextSettings) -> u8 {
+    match (text_settings.right_to_left.unwrap_or(false), paragraph_level(text_settings)) {
+        (true, _) => 1,
+        (false, 1) => 2,
+        (false, _) => 0,
+    }
+}
+
+/// Splits the text into the ranges of bytes that share an embedding level,
+/// with the text embedded at the `run_level`.
+pub fn level_runs(text: &str, run_level: u8) -> Vec<(Range<usize>, u8)> {
+    // Without right-to-left characters, left-to-right text stays at the
+    // level it is embedded at.
+    if run_level % 2 == 0 && text.is_ascii() {
+        return vec![(0..text.len(), run_level)];
+    }
+
+    // The levels are resolved in the direction of the run, and then raised to
+    // the level of the run, e.g. by 2 for left-to-right text in a right-to-left
+    // paragraph.
+    let direction = if run_level % 2 == 1 { Level::rtl() } else { Level::ltr() };
+    let raise = run_level - direction.number();
+    let bidi_info = BidiInfo::new(text, Some(direction));
+
+    let mut runs: Vec<(Range<usize>, u8)> = Vec::new();
+    for (index, character) in text.char_indices() {
+        let level = bidi_info.levels[index].number() + raise;
+        let end = index + character.len_utf8();
+        match runs.last_mut() {
+            Some((range, run_level)) if *run_level == level => range.end = end,
+            _ => runs.push((index..end, level)),
+        }
+    }
+    runs
+}
+
+/// A text part on a line, with its horizontal bounds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinePart {
+    pub level: u8,
+    pub left: f32,
+    pub width: f32,
+
+    /// Whether the text part only contains whitespace.
+    pub is_whitespace: bool,
+}
+
+/// Reorders the text parts of a line, which are in logical order, by moving
+/// them to their visual positions. The line is between the `line_start` and
+/// the `line_end`, which are the bounds the lines of right-to-left paragraphs
+/// are mirrored within, so they start at the right.
+pub fn reorder_line(parts: &mut [LinePart], paragraph_level: u8, line_start: f32, line_end: f32) {
+    // L1: the whitespace at the end of the line is at the paragraph level.
+    for part in parts.iter_mut().rev() {
+        if !part.is_whitespace {
+            break;
+        }
+        part.level = paragraph_level;
+    }
+
+    for part in parts.iter_mut() {
+        part.level = part.level.max(paragraph_level);
+    }
+
+    // L2: from the highest level to the lowest odd level, every sequence of
+    // parts at that level or higher is reversed. Mirroring the positions
+    // within the sequence keeps the gaps between them, e.g. of tabs.
+    let highest_level = parts.iter().map(|part| part.level).max().unwrap_or(0);
+    for level in (1..=highest_level).rev() {
+        let mut start = 0;
+        while start < parts.len() {
+            if parts[start].level < level {
+                start += 1;
+                continue;
+            }
+
+            let end = parts[start..].iter().position(|part| part.level < level).map_or(parts.len(), |length| start + length);
+            let sequence = &mut parts[start..end];
+
+            let (left, right) = if level == 1 && paragraph_level == 1 {
+                (line_start, line_end)
+            } else {
+                sequence.iter().fold((f32::MAX, f32::MIN), |(left, right), part| (left.min(part.left), right.max(part.left + part.width)))
+            };
+
+            for part in sequence {
+                part.left = left + right - part.left - part.width;
+            }
+            start = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(level: u8, left: f32, width: f32) -> LinePart {
+        LinePart { level, left, width, is_whitespace: false }
+    }
+
+    #[test]
+    fn level_runs_split_at_direction_changes() {
+        let text = "ab \u{5D0}\u{5D1} \u{5D2} cd";
+        let hebrew_end = text.find(" cd").unwrap();
+
+        assert_eq!(level_runs(text, 0), vec![(0..3, 0), (3..hebrew_end, 1), (hebrew_end..text.len(), 0)]);
+
+        // In a right-to-left run, the numbers and latin text are embedded one
+        // level deeper.
+        assert_eq!(level_runs("\u{5D0} 12", 1), vec![(0..3, 1), (3..5, 2)]);
+        assert_eq!(level_runs("ab cd", 2), vec![(0..5, 2)]);
+    }
+
+    #[test]
+    fn right_to_left_sequences_are_reversed() {
+        let mut parts = [part(0, 0.0, 10.0), part(1, 10.0, 20.0), part(1, 30.0, 30.0), part(0, 60.0, 10.0)];
+        reorder_line(&mut parts, 0, 0.0, 100.0);
+
+        let lefts: Vec<f32> = parts.iter().map(|part| part.left).collect();
+        assert_eq!(lefts, vec![0.0, 40.0, 10.0, 60.0]);
+    }
+
+    #[test]
+    fn right_to_left_lines_start_at_the_right() {
+        // The left-to-right text at level 2 keeps its order, but the line is
+        // mirrored, and the space at the end of the line ends up at the left.
+        let mut parts = [part(1, 0.0, 10.0), part(2, 10.0, 20.0), part(2, 30.0, 20.0),
+            LinePart { level: 2, left: 50.0, width: 10.0, is_whitespace: true }];
+        reorder_line(&mut parts, 1, 0.0, 100.0);
+
+        let lefts: Vec<f32> = parts.iter().map(|part| part.left).collect();
+        assert_eq!(lefts, vec![90.0, 50.0, 70.0, 40.0]);
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The comments of the document (17.13.4), which are stored in the comments
+// part and anchored to the text between their commentRangeStart and
+// commentRangeEnd elements in the main document part.
+
+use chrono::NaiveDateTime;
+use roxmltree as xml;
+
+use crate::WORD_PROCESSING_XML_NAMESPACE;
+
+use super::{instructions, Node, NodeData};
+
+/// A comment on a range of the document (17.13.4.2 comment).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comment {
+    pub id: String,
+    pub author: String,
+    pub date: Option<NaiveDateTime>,
+
+    /// The text of the paragraphs of the comment, separated by newlines.
+    pub text: String,
+}
+
+impl Comment {
+    fn from_element(node: &xml::Node) -> Option<Self> {
+        let id = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "id"))?;
+        let date = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "date"))
+            .and_then(instructions::parse_date_time);
+
+        let paragraphs: Vec<String> = node.children()
+            .filter(|child| child.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "p")))
+            .map(|paragraph| paragraph_text(&paragraph))
+            .collect();
+
+        Some(Self {
+            id: String::from(id),
+            author: String::from(node.attribute((WORD_PROCESSING_XML_NAMESPACE, "author")).unwrap_or_default()),
+            date,
+            text: paragraphs.join("\n"),
+        })
+    }
+
+    /// The date as shown to the user, or an empty string when the comment
+    /// isn't dated.
+    pub fn date_text(&self) -> String {
+        self.date.map(|date| instructions::format_date_time(instructions::ANNOTATION_DATE_PICTURE, &date)).unwrap_or_default()
+    }
+}
+
+/// The text of a paragraph of a comment, where tabs are shown as spaces.
+fn paragraph_text(paragraph: &xml::Node) -> String {
+    let mut text = String::new();
+    for node in paragraph.descendants() {
+        if node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "t")) {
+            text.push_str(node.text().unwrap_or_default());
+        } else if node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "tab")) {
+            text.push(' ');
+        }
+    }
+    text
+}
+
+/// Parses the comments of the comments part (17.13.4.6 comments), in the
+/// order they're stored in.
+pub fn parse_comments_part(document: &xml::Document) -> Vec<Comment> {
+    document.root_element().children()
+        .filter(|child| child.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "comment")))
+        .filter_map(|child| Comment::from_element(&child))
+        .collect()
+}
+
+/// The part of the tree a comment is anchored to.
+#[derive(Debug)]
+pub struct CommentRange<'a> {
+    pub id: &'a str,
+
+    /// The commentRangeStart, which is where the range is shown when it
+    /// doesn't contain any text.
+    pub start: &'a Node,
+
+    /// The text parts between the start and the end of the range.
+    pub text_parts: Vec<&'a Node>,
+}
+
+/// Finds the ranges of the comments in the tree, in the order they start. A
+/// range without an end continues up to the end of the document.
+pub fn find_comment_ranges(root: &Node) -> Vec<CommentRange<'_>> {
+    let mut ranges = Vec::new();
+    let mut open_ranges = Vec::new();
+    collect_comment_ranges(root, &mut ranges, &mut open_ranges);
+    ranges
+}
+
+fn collect_comment_ranges<'a>(node: &'a Node, ranges: &mut Vec<CommentRange<'a>>, open_ranges: &mut Vec<usize>) {
+    match &node.data {
+        NodeData::CommentRangeStart(id) => {
+            open_ranges.push(ranges.len());
+            ranges.push(CommentRange { id, start: node, text_parts: Vec::new() });
+        }
+        NodeData::CommentRangeEnd(id) => open_ranges.retain(|index| ranges[*index].id != id),
+        NodeData::TextPart(..) => {
+            for index in open_ranges.iter() {
+                ranges[*index].text_parts.push(node);
+            }
+        }
+        _ => (),
+    }
+
+    for child in &node.children {
+        collect_comment_ranges(child, ranges, open_ranges);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wp::{create_child, TextPart};
+
+    fn create_text_part(parent: &mut Node, text: &str) {
+        create_child(parent, NodeData::TextPart(TextPart { text: String::from(text), ascent: 0.0, character_spacing: 0.0, word_spacing: 0.0, grapheme_ends: Vec::new(), bidi_level: 0 }));
+    }
+
+    fn text_of(range: &CommentRange<'_>) -> Vec<String> {
+        range.text_parts.iter()
+            .map(|node| match &node.data {
+                NodeData::TextPart(part) => part.text.clone(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_comments() {
+        let text = concat!(
+            r#"<w:comments xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:comment w:id="0" w:author="Jane Doe" w:date="2023-03-05T14:07:00Z">"#,
+            r#"<w:p><w:r><w:annotationRef/></w:r><w:r><w:t>Check</w:t></w:r><w:r><w:tab/><w:t>this</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>Second line</w:t></w:r></w:p>"#,
+            r#"</w:comment>"#,
+            r#"<w:comment w:id="1"><w:p/></w:comment>"#,
+            r#"<w:comment w:author="Without id"/>"#,
+            r#"</w:comments>"#,
+        );
+        let document = xml::Document::parse(text).unwrap();
+
+        let comments = parse_comments_part(&document);
+        assert_eq!(comments.len(), 2);
+
+        assert_eq!(comments[0].id, "0");
+        assert_eq!(comments[0].author, "Jane Doe");
+        assert_eq!(comments[0].text, "Check this\nSecond line");
+        assert_eq!(comments[0].date_text(), "05-03-2023 14:07");
+
+        assert_eq!(comments[1].author, "");
+        assert_eq!(comments[1].text, "");
+        assert_eq!(comments[1].date_text(), "");
+    }
+
+    #[test]
+    fn ranges_contain_the_text_between_their_start_and_end() {
+        let mut root = Node::new(NodeData::Document);
+        let paragraph = create_child(&mut root, NodeData::Paragraph(Default::default()));
+        let paragraph = root.nth_child_mut(paragraph);
+
+        create_text_part(paragraph, "Before");
+        create_child(paragraph, NodeData::CommentRangeStart(String::from("0")));
+        create_text_part(paragraph, "Both");
+        create_child(paragraph, NodeData::CommentRangeStart(String::from("1")));
+        create_text_part(paragraph, "Inner");
+        create_child(paragraph, NodeData::CommentRangeEnd(String::from("1")));
+        create_child(paragraph, NodeData::CommentRangeStart(String::from("2")));
+        create_child(paragraph, NodeData::CommentRangeEnd(String::from("2")));
+        create_text_part(paragraph, "Outer");
+        create_child(paragraph, NodeData::CommentRangeEnd(String::from("0")));
+        create_text_part(paragraph, "After");
+
+        let ranges = find_comment_ranges(&root);
+        assert_eq!(ranges.iter().map(|range| range.id).collect::<Vec<_>>(), ["0", "1", "2"]);
+        assert_eq!(text_of(&ranges[0]), ["Both", "Inner", "Outer"]);
+        assert_eq!(text_of(&ranges[1]), ["Inner"]);
+        assert!(ranges[2].text_parts.is_empty());
+    }
+}
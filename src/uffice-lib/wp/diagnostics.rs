@@ -0,0 +1,68 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Something that is noteworthy, but doesn't affect the rendering.
+    Info,
+
+    /// Something that couldn't be fully rendered, e.g. an unsupported element.
+    Warning,
+
+    /// Something that is invalid in the document.
+    Error,
+}
+
+/// A problem encountered while processing the document, which is shown to the
+/// user in the diagnostics panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// The element (or part) the diagnostic originated from, e.g. `w:tbl`.
+    pub source: String,
+
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, source: &str, message: String) -> Self {
+        Self {
+            severity,
+            source: String::from(source),
+            message,
+        }
+    }
+
+    pub fn info(source: &str, message: String) -> Self {
+        Self::new(Severity::Info, source, message)
+    }
+
+    pub fn warning(source: &str, message: String) -> Self {
+        Self::new(Severity::Warning, source, message)
+    }
+
+    pub fn error(source: &str, message: String) -> Self {
+        Self::new(Severity::Error, source, message)
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} <{}>: {}", self.severity, self.source, self.message)
+    }
+}
+
+impl From<&crate::word_processing::validate::Issue> for Diagnostic {
+    /// The issues of the package are errors, except for the features that
+    /// aren't supported.
+    fn from(issue: &crate::word_processing::validate::Issue) -> Self {
+        use crate::word_processing::validate::IssueKind;
+
+        let severity = match issue.kind {
+            IssueKind::UnsupportedFeature => Severity::Warning,
+            _ => Severity::Error,
+        };
+        Self::new(severity, &issue.part, format!("{}: {}", issue.kind, issue.message))
+    }
+}
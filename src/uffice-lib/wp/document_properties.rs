@@ -0,0 +1,60 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use roxmltree as xml;
+
+#[derive(Clone, Default, Debug)]
+pub struct DocumentProperties {
+    pub category: Option<String>,
+    pub creator: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Option<String>,
+    pub last_modified_by: Option<String>,
+    pub subject: Option<String>,
+    pub title: Option<String>,
+}
+
+impl DocumentProperties {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn import_core_file_properties_part(&mut self, document: &xml::Document) {
+        for child in document.root_element().children() {
+            let property = match child.tag_name().name() {
+                "category" => &mut self.category,
+                "creator" => &mut self.creator,
+                "description" => &mut self.description,
+                "keywords" => &mut self.keywords,
+                "lastModifiedBy" => &mut self.last_modified_by,
+                "subject" => &mut self.subject,
+                "title" => &mut self.title,
+                _ => continue,
+            };
+
+            *property = Some(String::new());
+            for child in child.children() {
+                if child.is_text() && child.text().is_some() {
+                    *property = Some(String::from(child.text().unwrap()));
+                }
+            }
+        }
+    }
+
+    /// Get a property by the name used by the DOCPROPERTY field (17.16.5.16),
+    /// e.g. "Author" or "Title".
+    pub fn get_by_name(&self, name: &str) -> Option<&str> {
+        let property = match name.to_ascii_lowercase().as_str() {
+            "author" => &self.creator,
+            "category" => &self.category,
+            "comments" => &self.description,
+            "keywords" => &self.keywords,
+            "lastsavedby" => &self.last_modified_by,
+            "subject" => &self.subject,
+            "title" => &self.title,
+            _ => return None,
+        };
+
+        Some(property.as_deref().unwrap_or_default())
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+// Mutations of the text in the tree, for editing the document. These only
+// change the text of the text parts; the caller lays out the paragraph again
+// afterwards, see [crate::word_processing::relayout_paragraph].
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+    create_child,
+    Node,
+    NodeData,
+    selection::TextPosition,
+    TextPart,
+};
+
+/// A position in the text of a paragraph, counted in graphemes from the start
+/// of its first text part. Unlike a [TextPosition], this stays valid when the
+/// text parts of the paragraph are split or merged by the layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParagraphPosition {
+    /// The indices of the children from the root to the paragraph.
+    pub path: Vec<usize>,
+
+    /// The index of the grapheme boundary in the text of the paragraph.
+    pub offset: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditError {
+    /// The position isn't in the text of a paragraph.
+    NotInParagraph,
+
+    /// The paragraph contains content that can't be laid out again from the
+    /// tree, e.g. tabs, breaks, drawings or fields.
+    ParagraphNotEditable,
+
+    /// The text at the position belongs to the numbering, not the paragraph.
+    NotEditableText,
+}
+
+/// A text part of a paragraph, with where it is in the text of the paragraph.
+struct ParagraphTextPart {
+    /// The indices of the children from the paragraph to the text part.
+    path: Vec<usize>,
+
+    /// The graphemes of the paragraph in this text part.
+    graphemes: Range<usize>,
+
+    /// Whether the text is part of a run, instead of e.g. the numbering.
+    editable: bool,
+}
+
+impl ParagraphTextPart {
+    fn contains_boundary(&self, offset: usize) -> bool {
+        self.graphemes.start <= offset && offset <= self.graphemes.end
+    }
+}
+
+fn collect_text_parts(node: &Node, path: &mut Vec<usize>, editable: bool, parts: &mut Vec<ParagraphTextPart>) {
+    for (index, child) in node.children.iter().enumerate() {
+        path.push(index);
+
+        match &child.data {
+            NodeData::TextPart(part) => {
+                let start = parts.last().map(|part| part.graphemes.end).unwrap_or(0);
+                parts.push(ParagraphTextPart {
+                    path: path.clone(),
+                    graphemes: start..start + part.text.graphemes(true).count(),
+                    editable,
+                });
+            }
+            NodeData::NumberingParent => collect_text_parts(child, path, false, parts),
+            _ => collect_text_parts(child, path, editable, parts),
+        }
+
+        path.pop();
+    }
+}
+
+fn text_parts_of_paragraph(paragraph: &Node) -> Vec<ParagraphTextPart> {
+    let mut parts = Vec::new();
+    collect_text_parts(paragraph, &mut Vec::new(), true, &mut parts);
+    parts
+}
+
+fn count_text_parts(node: &Node) -> usize {
+    node.find_all(|node| matches!(node.data, NodeData::TextPart(..))).len()
+}
+
+/// Finds the innermost paragraph containing the text part with the given
+/// index, and returns the path to it with the index of the text part in that
+/// paragraph.
+fn find_paragraph(node: &Node, text_part: usize) -> Option<(Vec<usize>, usize)> {
+    let mut first_text_part = 0;
+    for (index, child) in node.children.iter().enumerate() {
+        let count = count_text_parts(child);
+        if text_part >= first_text_part + count {
+            first_text_part += count;
+            continue;
+        }
+
+        let text_part = text_part - first_text_part;
+        if let Some((mut path, text_part)) = find_paragraph(child, text_part) {
+            path.insert(0, index);
+            return Some((path, text_part));
+        }
+
+        return match child.data {
+            NodeData::Paragraph(..) => Some((vec![index], text_part)),
+            _ => None,
+        };
+    }
+
+    None
+}
+
+pub fn node_at_path<'a>(root: &'a Node, path: &[usize]) -> Option<&'a Node> {
+    path.iter().try_fold(root, |node, index| node.children.get(*index))
+}
+
+pub fn node_at_path_mut<'a>(root: &'a mut Node, path: &[usize]) -> Option<&'a mut Node> {
+    path.iter().try_fold(root, |node, index| node.children.get_mut(*index))
+}
+
+/// Converts the position to a position in the text of its paragraph.
+pub fn paragraph_position(root: &Node, position: TextPosition) -> Option<ParagraphPosition> {
+    let (path, text_part) = find_paragraph(root, position.text_part)?;
+    let parts = text_parts_of_paragraph(node_at_path(root, &path)?);
+
+    Some(ParagraphPosition {
+        offset: parts.get(text_part)?.graphemes.start + position.offset,
+        path,
+    })
+}
+
+/// Converts the position in the text of a paragraph back to a [TextPosition].
+/// On the boundary of two text parts, the end of the first is preferred, so
+/// that text typed there gets the formatting of the text before it.
+pub fn text_position(root: &Node, position: &ParagraphPosition) -> Option<TextPosition> {
+    let paragraph = node_at_path(root, &position.path)?;
+    let parts = text_parts_of_paragraph(paragraph);
+    let offset = position.offset.min(parts.last()?.graphemes.end);
+
+    let index = parts.iter().position(|part| part.editable && part.contains_boundary(offset))
+        .or_else(|| parts.iter().position(|part| part.contains_boundary(offset)))?;
+
+    Some(TextPosition {
+        text_part: text_parts_before(root, &position.path) + index,
+        offset: offset - parts[index].graphemes.start,
+    })
+}
+
+/// Counts the text parts before the node at the path, in document order.
+fn text_parts_before(root: &Node, path: &[usize]) -> usize {
+    let mut count = 0;
+    let mut node = root;
+    for index in path {
+        count += node.children[..*index].iter().map(count_text_parts).sum::<usize>();
+        node = &node.children[*index];
+    }
+    count
+}
+
+/// The number of graphemes in the text of the paragraph.
+pub fn paragraph_length(root: &Node, path: &[usize]) -> Option<usize> {
+    let paragraph = node_at_path(root, path)?;
+    Some(text_parts_of_paragraph(paragraph).last().map(|part| part.graphemes.end).unwrap_or(0))
+}
+
+/// The number of graphemes in the text of the paragraph before the node at
+/// the path relative to the paragraph.
+pub fn graphemes_before(paragraph: &Node, path: &[usize]) -> usize {
+    text_parts_of_paragraph(paragraph).iter()
+        .filter(|part| part.path.as_slice() < path)
+        .map(|part| part.graphemes.len())
+        .sum()
+}
+
+/// Whether the paragraph only contains text that can be laid out again after
+/// it is edited.
+pub fn is_editable(paragraph: &Node) -> bool {
+    paragraph.children.iter().all(|child| match &child.data {
+        NodeData::TextRun(run) => {
+            run.instruction.is_none() && !run.has_layout_content && child.children.iter().all(is_run_text)
+        }
+        NodeData::Hyperlink(..) => is_editable(child),
+        NodeData::BookmarkStart(..) | NodeData::BookmarkEnd(..) | NodeData::NumberingParent => true,
+        NodeData::CommentRangeStart(..) | NodeData::CommentRangeEnd(..) => true,
+        _ => false,
+    })
+}
+
+/// Whether the child of a run is only text: a text part, or the text element
+/// (17.3.3.31 t) holding the text parts of the first layout.
+fn is_run_text(node: &Node) -> bool {
+    match node.data {
+        NodeData::TextPart(..) => true,
+        NodeData::Text => node.children.iter().all(|child| matches!(child.data, NodeData::TextPart(..))),
+        _ => false,
+    }
+}
+
+/// The text of the run, from its text parts.
+pub fn run_text(run: &Node) -> String {
+    run.find_all(|node| matches!(node.data, NodeData::TextPart(..)))
+        .into_iter()
+        .filter_map(|node| match &node.data {
+            NodeData::TextPart(part) => Some(part.text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn editable_paragraph<'a>(root: &'a mut Node, path: &[usize]) -> Result<&'a mut Node, EditError> {
+    let paragraph = node_at_path_mut(root, path).ok_or(EditError::NotInParagraph)?;
+    if !matches!(paragraph.data, NodeData::Paragraph(..)) {
+        return Err(EditError::NotInParagraph);
+    }
+
+    if !is_editable(paragraph) {
+        return Err(EditError::ParagraphNotEditable);
+    }
+
+    Ok(paragraph)
+}
+
+fn text_of_part_mut<'a>(paragraph: &'a mut Node, path: &[usize]) -> &'a mut String {
+    match &mut node_at_path_mut(paragraph, path).unwrap().data {
+        NodeData::TextPart(part) => &mut part.text,
+        _ => unreachable!(),
+    }
+}
+
+/// The byte index of the grapheme boundary with the given index.
+fn byte_index_of_grapheme(text: &str, grapheme: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(grapheme)
+        .map(|(index, _)| index)
+        .unwrap_or(text.len())
+}
+
+/// Inserts the text at the position, and returns the position after the
+/// inserted text.
+pub fn insert_text(root: &mut Node, position: &ParagraphPosition, text: &str) -> Result<ParagraphPosition, EditError> {
+    let paragraph = editable_paragraph(root, &position.path)?;
+    let parts = text_parts_of_paragraph(paragraph);
+    let part = parts.iter()
+        .find(|part| part.editable && part.contains_boundary(position.offset))
+        .ok_or(EditError::NotEditableText)?;
+
+    let part_text = text_of_part_mut(paragraph, &part.path);
+    let index = byte_index_of_grapheme(part_text, position.offset - part.graphemes.start);
+    part_text.insert_str(index, text);
+
+    Ok(ParagraphPosition {
+        path: position.path.clone(),
+        offset: position.offset + text.graphemes(true).count(),
+    })
+}
+
+/// Removes the graphemes in the range of the text of the paragraph, and
+/// returns the position where they were.
+pub fn delete_text(root: &mut Node, path: &[usize], graphemes: Range<usize>) -> Result<ParagraphPosition, EditError> {
+    let paragraph = editable_paragraph(root, path)?;
+    let parts = text_parts_of_paragraph(paragraph);
+
+    let overlapping = parts.iter()
+        .filter(|part| part.graphemes.start < graphemes.end && graphemes.start < part.graphemes.end)
+        .collect::<Vec<_>>();
+    if overlapping.iter().any(|part| !part.editable) {
+        return Err(EditError::NotEditableText);
+    }
+
+    for part in overlapping {
+        let part_text = text_of_part_mut(paragraph, &part.path);
+        let start = byte_index_of_grapheme(part_text, graphemes.start.saturating_sub(part.graphemes.start));
+        let end = byte_index_of_grapheme(part_text, graphemes.end.min(part.graphemes.end) - part.graphemes.start);
+        part_text.replace_range(start..end, "");
+    }
+
+    Ok(ParagraphPosition {
+        path: path.to_vec(),
+        offset: graphemes.start,
+    })
+}
+
+/// The text of every run of the paragraph, with the path of the run relative
+/// to the paragraph. Unlike the text parts, the runs stay the same when the
+/// paragraph is laid out again.
+pub fn run_texts(paragraph: &Node) -> Vec<(Vec<usize>, String)> {
+    let mut runs = Vec::new();
+    collect_run_texts(paragraph, &mut Vec::new(), &mut runs);
+    runs
+}
+
+fn collect_run_texts(node: &Node, path: &mut Vec<usize>, runs: &mut Vec<(Vec<usize>, String)>) {
+    for (index, child) in node.children.iter().enumerate() {
+        path.push(index);
+
+        match &child.data {
+            NodeData::TextRun(..) => runs.push((path.clone(), run_text(child))),
+            NodeData::Hyperlink(..) => collect_run_texts(child, path, runs),
+            _ => (),
+        }
+
+        path.pop();
+    }
+}
+
+/// Replaces the text of the run at the path relative to the paragraph. The
+/// text is put in a single text part, which is split again by the layout.
+pub fn set_run_text(paragraph: &mut Node, path: &[usize], text: &str) {
+    let Some(run) = node_at_path_mut(paragraph, path) else {
+        return;
+    };
+
+    run.children.truncate(1);
+    match run.children.first_mut().map(|child| &mut child.data) {
+        Some(NodeData::TextPart(part)) => part.text = String::from(text),
+        _ => {
+            run.children.clear();
+            if !text.is_empty() {
+                create_child(run, NodeData::TextPart(TextPart {
+                    text: String::from(text),
+                    ascent: 0.0,
+                    character_spacing: 0.0,
+                    word_spacing: 0.0,
+                    grapheme_ends: Vec::new(),
+                    bidi_level: 0,
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_text_part(parent: &mut Node, text: &str) {
+        create_child(parent, NodeData::TextPart(TextPart { text: String::from(text), ascent: 0.0, character_spacing: 0.0, word_spacing: 0.0, grapheme_ends: Vec::new(), bidi_level: 0 }));
+    }
+
+    /// Creates a document with a numbered paragraph with two runs, and a
+    /// paragraph with a single run.
+    fn create_tree() -> Node {
+        let mut document = Node::new(NodeData::Document);
+
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        let paragraph = document.nth_child_mut(paragraph);
+        let numbering = create_child(paragraph, NodeData::NumberingParent);
+        create_text_part(paragraph.nth_child_mut(numbering), "1.");
+        let run = create_child(paragraph, NodeData::TextRun(Default::default()));
+        create_text_part(paragraph.nth_child_mut(run), "Hello ");
+        create_text_part(paragraph.nth_child_mut(run), "world");
+        let run = create_child(paragraph, NodeData::TextRun(Default::default()));
+        create_text_part(paragraph.nth_child_mut(run), "!");
+
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        let run = create_child(document.nth_child_mut(paragraph), NodeData::TextRun(Default::default()));
+        create_text_part(document.nth_child_mut(paragraph).nth_child_mut(run), "Bye");
+
+        document
+    }
+
+    fn texts(root: &Node) -> Vec<&str> {
+        root.find_all(|node| matches!(node.data, NodeData::TextPart(..)))
+            .into_iter()
+            .map(|node| match &node.data {
+                NodeData::TextPart(part) => part.text.as_str(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn positions_round_trip_through_the_paragraph() {
+        let document = create_tree();
+
+        let position = paragraph_position(&document, TextPosition { text_part: 2, offset: 3 }).unwrap();
+        assert_eq!(position, ParagraphPosition { path: vec![0], offset: 11 });
+        assert_eq!(text_position(&document, &position), Some(TextPosition { text_part: 2, offset: 3 }));
+
+        // The start of a text part is the end of the one before it.
+        let position = paragraph_position(&document, TextPosition { text_part: 2, offset: 0 }).unwrap();
+        assert_eq!(text_position(&document, &position), Some(TextPosition { text_part: 1, offset: 6 }));
+
+        // The start of the text is after the numbering.
+        let position = paragraph_position(&document, TextPosition { text_part: 1, offset: 0 }).unwrap();
+        assert_eq!(text_position(&document, &position), Some(TextPosition { text_part: 1, offset: 0 }));
+
+        let position = paragraph_position(&document, TextPosition { text_part: 4, offset: 1 }).unwrap();
+        assert_eq!(position, ParagraphPosition { path: vec![1], offset: 1 });
+        assert_eq!(text_position(&document, &position), Some(TextPosition { text_part: 4, offset: 1 }));
+    }
+
+    #[test]
+    fn insert_text_at_the_end_of_a_text_part() {
+        let mut document = create_tree();
+
+        let position = ParagraphPosition { path: vec![0], offset: 13 };
+        let position = insert_text(&mut document, &position, "s").unwrap();
+        assert_eq!(position.offset, 14);
+        assert_eq!(texts(&document), vec!["1.", "Hello ", "worlds", "!", "Bye"]);
+
+        // The numbering isn't part of the text that can be edited.
+        let position = ParagraphPosition { path: vec![0], offset: 1 };
+        assert_eq!(insert_text(&mut document, &position, "x"), Err(EditError::NotEditableText));
+    }
+
+    #[test]
+    fn delete_text_across_text_parts() {
+        let mut document = create_tree();
+
+        let position = delete_text(&mut document, &[0], 6..14).unwrap();
+        assert_eq!(position, ParagraphPosition { path: vec![0], offset: 6 });
+        assert_eq!(texts(&document), vec!["1.", "Hell", "", "", "Bye"]);
+
+        assert_eq!(delete_text(&mut document, &[0], 1..3), Err(EditError::NotEditableText));
+        assert_eq!(paragraph_length(&document, &[0]), Some(6));
+    }
+
+    #[test]
+    fn paragraphs_with_fields_are_not_editable() {
+        let mut document = create_tree();
+        if let NodeData::TextRun(run) = &mut document.nth_child_mut(1).nth_child_mut(0).data {
+            run.has_layout_content = true;
+        }
+
+        let position = ParagraphPosition { path: vec![1], offset: 0 };
+        assert_eq!(insert_text(&mut document, &position, "x"), Err(EditError::ParagraphNotEditable));
+    }
+
+    #[test]
+    fn run_texts_survive_setting_them_again() {
+        let mut document = create_tree();
+        let paragraph = document.nth_child_mut(0);
+
+        let runs = run_texts(paragraph);
+        assert_eq!(runs, vec![(vec![1], String::from("Hello world")), (vec![2], String::from("!"))]);
+
+        set_run_text(paragraph, &[1], "Bye");
+        assert_eq!(texts(&document), vec!["1.", "Bye", "!", "Bye"]);
+
+        let paragraph = document.nth_child_mut(0);
+        for (path, text) in &runs {
+            set_run_text(paragraph, path, text);
+        }
+        assert_eq!(run_texts(paragraph), runs);
+    }
+}
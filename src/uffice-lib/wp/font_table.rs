@@ -0,0 +1,327 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The font table part (17.8.3.10 fonts), which describes the fonts used in
+// the document, so a similar font can be chosen when one isn't installed, and
+// references the fonts embedded in the package (17.8.1).
+
+use roxmltree as xml;
+use crate::namespaces::XMLNS_RELATIONSHIPS;
+
+use crate::{word_processing::parse_on_off, WORD_PROCESSING_XML_NAMESPACE};
+
+use super::diagnostics::Diagnostic;
+
+/// The kind of font a font belongs to, which is used to choose an installed
+/// font when neither the font nor its alternative name is available.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenericFontFamily {
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+}
+
+/// The PANOSE-1 classification of a font (17.8.3.13 panose1), which
+/// describes what the glyphs look like in ten digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Panose(pub [u8; 10]);
+
+impl Panose {
+    /// Parses the 20 hexadecimal digits of the ST_Panose type.
+    pub fn parse(value: &str) -> Option<Self> {
+        let bytes = parse_hex_bytes::<10>(value)?;
+        Some(Self(bytes))
+    }
+
+    pub fn generic_family(&self) -> Option<GenericFontFamily> {
+        let [family_kind, serif_style, _weight, proportion, ..] = self.0;
+        match family_kind {
+            // Latin Text
+            2 if proportion == 9 => Some(GenericFontFamily::Monospace),
+            2 => match serif_style {
+                // The sans serif styles, and the flared and rounded ones
+                // such as Optima and Calibri.
+                11..=15 => Some(GenericFontFamily::SansSerif),
+                2..=10 => Some(GenericFontFamily::Serif),
+                _ => None,
+            },
+
+            // Latin Hand Written
+            3 => Some(GenericFontFamily::Cursive),
+
+            // Latin Decorative
+            4 => Some(GenericFontFamily::Fantasy),
+
+            _ => None,
+        }
+    }
+}
+
+/// The style of an embedded font, i.e. which of the embedRegular,
+/// embedBold, embedItalic and embedBoldItalic elements references it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmbeddedFontStyle {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+}
+
+impl EmbeddedFontStyle {
+    pub fn is_bold(&self) -> bool {
+        matches!(self, Self::Bold | Self::BoldItalic)
+    }
+
+    pub fn is_italic(&self) -> bool {
+        matches!(self, Self::Italic | Self::BoldItalic)
+    }
+}
+
+/// A reference to a font embedded in the package (17.8.3.3 embedRegular),
+/// which is a relationship of the font table part.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmbeddedFontReference {
+    pub style: EmbeddedFontStyle,
+    pub relationship_id: String,
+
+    /// The key the font was obfuscated with, see [deobfuscate_font], or None
+    /// when the part is a plain font file.
+    pub font_key: Option<[u8; 16]>,
+
+    /// Whether only the glyphs used in the document are embedded.
+    pub subsetted: bool,
+}
+
+/// The description of a font (17.8.3.9 font).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FontDefinition {
+    pub name: String,
+
+    /// Another name of the font, e.g. in another language (17.8.3.1 altName).
+    pub alt_name: Option<String>,
+
+    pub panose: Option<Panose>,
+
+    /// The kind of font according to the family element (17.8.3.6 family),
+    /// which is None for "auto".
+    pub family: Option<GenericFontFamily>,
+
+    /// Whether the glyphs have the same width (17.8.3.15 pitch).
+    pub fixed_pitch: bool,
+
+    pub embedded: Vec<EmbeddedFontReference>,
+}
+
+impl FontDefinition {
+    /// The kind of installed font that resembles this font the most, which is
+    /// taken from the PANOSE classification when it has one, since that
+    /// describes the glyphs instead of the intended use.
+    pub fn generic_family(&self) -> Option<GenericFontFamily> {
+        if self.fixed_pitch {
+            return Some(GenericFontFamily::Monospace);
+        }
+
+        self.panose.and_then(|panose| panose.generic_family())
+            .or(self.family)
+    }
+
+    fn from_element(element: &xml::Node, diagnostics: &mut Vec<Diagnostic>) -> Option<Self> {
+        let Some(name) = element.attribute((WORD_PROCESSING_XML_NAMESPACE, "name")) else {
+            diagnostics.push(Diagnostic::error("w:font", String::from("missing name")));
+            return None;
+        };
+
+        let mut font = Self {
+            name: String::from(name),
+            ..Default::default()
+        };
+
+        for child in element.children() {
+            if child.tag_name().namespace() != Some(WORD_PROCESSING_XML_NAMESPACE) {
+                continue;
+            }
+
+            let value = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"));
+            match child.tag_name().name() {
+                "altName" => font.alt_name = value.filter(|value| !value.is_empty()).map(String::from),
+
+                "panose1" => {
+                    font.panose = value.and_then(Panose::parse);
+                    if font.panose.is_none() {
+                        diagnostics.push(Diagnostic::warning("w:panose1", format!("invalid classification of font \"{}\": {:?}", name, value)));
+                    }
+                }
+
+                "family" => font.family = match value {
+                    Some("roman") => Some(GenericFontFamily::Serif),
+                    Some("swiss") => Some(GenericFontFamily::SansSerif),
+                    Some("modern") => Some(GenericFontFamily::Monospace),
+                    Some("script") => Some(GenericFontFamily::Cursive),
+                    Some("decorative") => Some(GenericFontFamily::Fantasy),
+                    _ => None,
+                },
+
+                "pitch" => font.fixed_pitch = value == Some("fixed"),
+
+                "embedRegular" => font.embedded.extend(parse_embedded_font(&child, EmbeddedFontStyle::Regular, diagnostics)),
+                "embedBold" => font.embedded.extend(parse_embedded_font(&child, EmbeddedFontStyle::Bold, diagnostics)),
+                "embedItalic" => font.embedded.extend(parse_embedded_font(&child, EmbeddedFontStyle::Italic, diagnostics)),
+                "embedBoldItalic" => font.embedded.extend(parse_embedded_font(&child, EmbeddedFontStyle::BoldItalic, diagnostics)),
+
+                _ => (),
+            }
+        }
+
+        Some(font)
+    }
+}
+
+fn parse_embedded_font(element: &xml::Node, style: EmbeddedFontStyle, diagnostics: &mut Vec<Diagnostic>) -> Option<EmbeddedFontReference> {
+    let source = format!("w:{}", element.tag_name().name());
+    let Some(relationship_id) = element.attribute((XMLNS_RELATIONSHIPS, "id")) else {
+        diagnostics.push(Diagnostic::error(&source, String::from("missing relationship id")));
+        return None;
+    };
+
+    let font_key = match element.attribute((WORD_PROCESSING_XML_NAMESPACE, "fontKey")) {
+        Some(font_key) => match parse_font_key(font_key) {
+            Some(font_key) => Some(font_key),
+            None => {
+                diagnostics.push(Diagnostic::error(&source, format!("invalid font key: \"{}\"", font_key)));
+                return None;
+            }
+        },
+        None => None,
+    };
+
+    let subsetted = element.attribute((WORD_PROCESSING_XML_NAMESPACE, "subsetted"))
+        .and_then(|value| parse_on_off(value, &source, diagnostics))
+        .unwrap_or(false);
+
+    Some(EmbeddedFontReference {
+        style,
+        relationship_id: String::from(relationship_id),
+        font_key,
+        subsetted,
+    })
+}
+
+/// The fonts of the font table part, in document order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FontTable {
+    fonts: Vec<FontDefinition>,
+}
+
+impl FontTable {
+    pub fn from_xml(document: &xml::Document, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let fonts = document.root_element().children()
+            .filter(|child| child.tag_name().namespace() == Some(WORD_PROCESSING_XML_NAMESPACE) && child.tag_name().name() == "font")
+            .filter_map(|child| FontDefinition::from_element(&child, diagnostics))
+            .collect();
+
+        Self { fonts }
+    }
+
+    pub fn fonts(&self) -> &[FontDefinition] {
+        &self.fonts
+    }
+
+    /// Finds the font by its name, which is compared case-insensitively like
+    /// the names of installed fonts.
+    pub fn find(&self, name: &str) -> Option<&FontDefinition> {
+        self.fonts.iter().find(|font| font.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Parses the GUID of the fontKey attribute, e.g.
+/// `{4F5A1B2C-0000-0000-0000-000000000000}`, into the key the font was
+/// obfuscated with: the bytes of its hexadecimal digits in reverse order.
+pub fn parse_font_key(font_key: &str) -> Option<[u8; 16]> {
+    let digits: String = font_key.trim_start_matches('{').trim_end_matches('}')
+        .chars()
+        .filter(|character| *character != '-')
+        .collect();
+
+    let mut key = parse_hex_bytes::<16>(&digits)?;
+    key.reverse();
+    Some(key)
+}
+
+/// Undoes the obfuscation of an embedded font (17.8.1), of which the first
+/// 32 bytes are XOR'ed with the font key twice over.
+pub fn deobfuscate_font(data: &mut [u8], font_key: &[u8; 16]) {
+    for (index, byte) in data.iter_mut().take(32).enumerate() {
+        *byte ^= font_key[index % 16];
+    }
+}
+
+fn parse_hex_bytes<const N: usize>(digits: &str) -> Option<[u8; N]> {
+    if digits.len() != N * 2 || !digits.is_ascii() {
+        return None;
+    }
+
+    let mut bytes = [0; N];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_table_is_parsed() {
+        let text = format!(concat!(
+            r#"<w:fonts xmlns:w="{}" xmlns:r="{}">"#,
+            r#"<w:font w:name="Calibri"><w:panose1 w:val="020F0502020204030204"/><w:family w:val="swiss"/><w:pitch w:val="variable"/>"#,
+            r#"<w:embedRegular r:id="rId1" w:fontKey="{{00112233-4455-6677-8899-AABBCCDDEEFF}}" w:subsetted="1"/></w:font>"#,
+            r#"<w:font w:name="MS Mincho"><w:altName w:val="ＭＳ 明朝"/><w:family w:val="modern"/><w:pitch w:val="fixed"/></w:font>"#,
+            r#"<w:font w:name="Cambria"><w:panose1 w:val="02040503050406030204"/><w:family w:val="auto"/></w:font>"#,
+            r#"</w:fonts>"#,
+        ), WORD_PROCESSING_XML_NAMESPACE, XMLNS_RELATIONSHIPS);
+        let document = xml::Document::parse(&text).unwrap();
+        let mut diagnostics = Vec::new();
+        let font_table = FontTable::from_xml(&document, &mut diagnostics);
+
+        assert_eq!(diagnostics, Vec::new());
+        assert_eq!(font_table.fonts().len(), 3);
+
+        let calibri = font_table.find("calibri").unwrap();
+        assert_eq!(calibri.generic_family(), Some(GenericFontFamily::SansSerif));
+        assert_eq!(calibri.embedded, vec![EmbeddedFontReference {
+            style: EmbeddedFontStyle::Regular,
+            relationship_id: String::from("rId1"),
+            font_key: Some([0xFF, 0xEE, 0xDD, 0xCC, 0xBB, 0xAA, 0x99, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00]),
+            subsetted: true,
+        }]);
+
+        let mincho = font_table.find("MS Mincho").unwrap();
+        assert_eq!(mincho.alt_name.as_deref(), Some("ＭＳ 明朝"));
+        assert_eq!(mincho.generic_family(), Some(GenericFontFamily::Monospace));
+
+        assert_eq!(font_table.find("Cambria").unwrap().generic_family(), Some(GenericFontFamily::Serif));
+        assert!(font_table.find("Arial").is_none());
+    }
+
+    #[test]
+    fn fonts_are_deobfuscated() {
+        let font_key = parse_font_key("{00112233-4455-6677-8899-AABBCCDDEEFF}").unwrap();
+        let original: Vec<u8> = (0..40).collect();
+
+        let mut data = original.clone();
+        deobfuscate_font(&mut data, &font_key);
+        assert_eq!(data[0], 0xFF);
+        assert_eq!(data[16], 16 ^ 0xFF);
+        assert_eq!(data[32..], original[32..]);
+
+        // The obfuscation is its own inverse.
+        deobfuscate_font(&mut data, &font_key);
+        assert_eq!(data, original);
+
+        assert!(parse_font_key("{0011}").is_none());
+    }
+}
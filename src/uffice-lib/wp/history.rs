@@ -0,0 +1,433 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The history of the changes made to the tree, for undoing and redoing them.
+// Every change is a command that knows how to revert itself, see [Command].
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+    editing::{self, EditError, ParagraphPosition},
+    Node,
+    NodeData,
+};
+
+/// A change to the tree that can be reverted. Commands are applied again
+/// after they are reverted when the change is redone, so they must keep
+/// what they need to revert on every apply.
+///
+/// Both return the position of the caret after the change. The caller lays
+/// out the paragraph at that position again.
+pub trait Command: std::fmt::Debug {
+    fn apply(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError>;
+
+    fn revert(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError>;
+}
+
+/// Inserts the text at the position in a paragraph.
+#[derive(Debug)]
+pub struct InsertText {
+    position: ParagraphPosition,
+    text: String,
+}
+
+impl InsertText {
+    pub fn new(position: ParagraphPosition, text: String) -> Self {
+        Self { position, text }
+    }
+}
+
+impl Command for InsertText {
+    fn apply(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        editing::insert_text(root, &self.position, &self.text)
+    }
+
+    fn revert(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        let end = self.position.offset + self.text.graphemes(true).count();
+        editing::delete_text(root, &self.position.path, self.position.offset..end)
+    }
+}
+
+/// Removes the graphemes in the range of the text of a paragraph.
+#[derive(Debug)]
+pub struct DeleteText {
+    path: Vec<usize>,
+    graphemes: Range<usize>,
+
+    /// The text of the runs of the paragraph before the deletion, because the
+    /// deleted text can span multiple runs with their own formatting.
+    previous_run_texts: Vec<(Vec<usize>, String)>,
+}
+
+impl DeleteText {
+    pub fn new(path: Vec<usize>, graphemes: Range<usize>) -> Self {
+        Self {
+            path,
+            graphemes,
+            previous_run_texts: Vec::new(),
+        }
+    }
+}
+
+impl Command for DeleteText {
+    fn apply(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        let paragraph = editing::node_at_path(root, &self.path).ok_or(EditError::NotInParagraph)?;
+        let previous_run_texts = editing::run_texts(paragraph);
+
+        let position = editing::delete_text(root, &self.path, self.graphemes.clone())?;
+        self.previous_run_texts = previous_run_texts;
+        Ok(position)
+    }
+
+    fn revert(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        let paragraph = editing::node_at_path_mut(root, &self.path).ok_or(EditError::NotInParagraph)?;
+        for (run_path, text) in &self.previous_run_texts {
+            editing::set_run_text(paragraph, run_path, text);
+        }
+
+        Ok(ParagraphPosition {
+            path: self.path.clone(),
+            offset: self.graphemes.end,
+        })
+    }
+}
+
+/// Inserts a node as a child of a paragraph or of one of its descendants.
+#[derive(Debug)]
+pub struct InsertNode {
+    /// The path from the root to where the node is inserted.
+    path: Vec<usize>,
+
+    /// The node while it isn't in the tree.
+    node: Option<Node>,
+}
+
+impl InsertNode {
+    pub fn new(path: Vec<usize>, node: Node) -> Self {
+        Self { path, node: Some(node) }
+    }
+}
+
+impl Command for InsertNode {
+    fn apply(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        insert_node(root, &self.path, &mut self.node)?;
+        position_of_node(root, &self.path)
+    }
+
+    fn revert(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        self.node = Some(remove_node(root, &self.path)?);
+        position_of_node(root, &self.path)
+    }
+}
+
+/// Removes a node from a paragraph or from one of its descendants.
+#[derive(Debug)]
+pub struct RemoveNode {
+    /// The path from the root to the node.
+    path: Vec<usize>,
+
+    /// The node while it isn't in the tree.
+    node: Option<Node>,
+}
+
+impl RemoveNode {
+    pub fn new(path: Vec<usize>) -> Self {
+        Self { path, node: None }
+    }
+}
+
+impl Command for RemoveNode {
+    fn apply(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        self.node = Some(remove_node(root, &self.path)?);
+        position_of_node(root, &self.path)
+    }
+
+    fn revert(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        insert_node(root, &self.path, &mut self.node)?;
+        position_of_node(root, &self.path)
+    }
+}
+
+/// Commands that are undone and redone together, e.g. deleting the selected
+/// text and typing the text that replaces it.
+#[derive(Debug)]
+pub struct CommandGroup(pub Vec<Box<dyn Command>>);
+
+impl Command for CommandGroup {
+    fn apply(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        let mut position = Err(EditError::NotInParagraph);
+        for (index, command) in self.0.iter_mut().enumerate() {
+            position = command.apply(root);
+            if position.is_err() {
+                // Leave the tree as it was before the group.
+                for command in self.0[..index].iter_mut().rev() {
+                    _ = command.revert(root);
+                }
+                break;
+            }
+        }
+        position
+    }
+
+    fn revert(&mut self, root: &mut Node) -> Result<ParagraphPosition, EditError> {
+        let mut position = Err(EditError::NotInParagraph);
+        for command in self.0.iter_mut().rev() {
+            position = command.revert(root);
+            if position.is_err() {
+                break;
+            }
+        }
+        position
+    }
+}
+
+/// The length of the path to the innermost paragraph containing the node at
+/// the path.
+fn paragraph_path_length(root: &Node, path: &[usize]) -> Result<usize, EditError> {
+    (0..path.len())
+        .rev()
+        .find(|length| editing::node_at_path(root, &path[..*length])
+            .is_some_and(|node| matches!(node.data, NodeData::Paragraph(..))))
+        .ok_or(EditError::NotInParagraph)
+}
+
+/// Inserts the node at the path, taking it out of the option only when it is
+/// inserted.
+fn insert_node(root: &mut Node, path: &[usize], node: &mut Option<Node>) -> Result<(), EditError> {
+    paragraph_path_length(root, path)?;
+
+    let (index, parent_path) = path.split_last().ok_or(EditError::NotInParagraph)?;
+    let parent = editing::node_at_path_mut(root, parent_path).ok_or(EditError::NotInParagraph)?;
+    if *index > parent.children.len() {
+        return Err(EditError::NotInParagraph);
+    }
+
+    parent.children.insert(*index, node.take().ok_or(EditError::NotInParagraph)?);
+    Ok(())
+}
+
+fn remove_node(root: &mut Node, path: &[usize]) -> Result<Node, EditError> {
+    paragraph_path_length(root, path)?;
+
+    let (index, parent_path) = path.split_last().ok_or(EditError::NotInParagraph)?;
+    let parent = editing::node_at_path_mut(root, parent_path).ok_or(EditError::NotInParagraph)?;
+    if *index >= parent.children.len() {
+        return Err(EditError::NotInParagraph);
+    }
+
+    Ok(parent.children.remove(*index))
+}
+
+/// The position in the text of the paragraph where the node at the path is,
+/// or would be when it was removed.
+fn position_of_node(root: &Node, path: &[usize]) -> Result<ParagraphPosition, EditError> {
+    let length = paragraph_path_length(root, path)?;
+    let (paragraph_path, node_path) = path.split_at(length);
+    let paragraph = editing::node_at_path(root, paragraph_path).ok_or(EditError::NotInParagraph)?;
+
+    Ok(ParagraphPosition {
+        path: paragraph_path.to_vec(),
+        offset: editing::graphemes_before(paragraph, node_path),
+    })
+}
+
+/// The commands that can be undone and redone, of a single document.
+#[derive(Debug)]
+pub struct History {
+    undo_stack: Vec<Box<dyn Command>>,
+    redo_stack: Vec<Box<dyn Command>>,
+
+    /// The number of commands on the undo stack when the document was loaded
+    /// or last saved, or None when that state can't be reached anymore.
+    saved_depth: Option<usize>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            saved_depth: Some(0),
+        }
+    }
+}
+
+impl History {
+    /// Applies the command and adds it to the history. The commands that
+    /// were undone can't be redone anymore after this.
+    pub fn apply(&mut self, root: &mut Node, mut command: Box<dyn Command>) -> Result<ParagraphPosition, EditError> {
+        let position = command.apply(root)?;
+        self.undo_stack.push(command);
+        self.redo_stack.clear();
+
+        // The saved state was undone, and can't be redone anymore.
+        if self.saved_depth.is_some_and(|depth| depth >= self.undo_stack.len()) {
+            self.saved_depth = None;
+        }
+
+        Ok(position)
+    }
+
+    /// Whether the document differs from when it was loaded or last saved.
+    pub fn is_modified(&self) -> bool {
+        self.saved_depth != Some(self.undo_stack.len())
+    }
+
+    /// Remembers the current state as saved, which is no longer modified.
+    pub fn mark_saved(&mut self) {
+        self.saved_depth = Some(self.undo_stack.len());
+    }
+
+    /// Reverts the last command, or returns None when there is nothing to
+    /// undo.
+    pub fn undo(&mut self, root: &mut Node) -> Option<Result<ParagraphPosition, EditError>> {
+        let mut command = self.undo_stack.pop()?;
+        let position = command.revert(root);
+        if position.is_ok() {
+            self.redo_stack.push(command);
+        }
+        Some(position)
+    }
+
+    /// Applies the last undone command again, or returns None when there is
+    /// nothing to redo.
+    pub fn redo(&mut self, root: &mut Node) -> Option<Result<ParagraphPosition, EditError>> {
+        let mut command = self.redo_stack.pop()?;
+        let position = command.apply(root);
+        if position.is_ok() {
+            self.undo_stack.push(command);
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wp::{create_child, TextPart};
+
+    fn create_text_part(parent: &mut Node, text: &str) {
+        create_child(parent, NodeData::TextPart(TextPart { text: String::from(text), ascent: 0.0, character_spacing: 0.0, word_spacing: 0.0, grapheme_ends: Vec::new(), bidi_level: 0 }));
+    }
+
+    /// Creates a document with a paragraph with the runs "Hello " and
+    /// "world".
+    fn create_tree() -> Node {
+        let mut document = Node::new(NodeData::Document);
+
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        let paragraph = document.nth_child_mut(paragraph);
+        let run = create_child(paragraph, NodeData::TextRun(Default::default()));
+        create_text_part(paragraph.nth_child_mut(run), "Hello ");
+        let run = create_child(paragraph, NodeData::TextRun(Default::default()));
+        create_text_part(paragraph.nth_child_mut(run), "world");
+
+        document
+    }
+
+    fn run_texts(root: &Node) -> Vec<String> {
+        editing::run_texts(&root.children[0]).into_iter()
+            .map(|(_, text)| text)
+            .collect()
+    }
+
+    #[test]
+    fn undo_and_redo_text_edits() {
+        let mut document = create_tree();
+        let mut history = History::default();
+
+        let position = ParagraphPosition { path: vec![0], offset: 11 };
+        let position = history.apply(&mut document, Box::new(InsertText::new(position, String::from("!")))).unwrap();
+        assert_eq!(position.offset, 12);
+
+        // Deleting across the runs keeps the text of both when undone.
+        history.apply(&mut document, Box::new(DeleteText::new(vec![0], 3..8))).unwrap();
+        assert_eq!(run_texts(&document), vec!["Hel", "rld!"]);
+
+        let position = history.undo(&mut document).unwrap().unwrap();
+        assert_eq!(position.offset, 8);
+        assert_eq!(run_texts(&document), vec!["Hello ", "world!"]);
+
+        history.undo(&mut document).unwrap().unwrap();
+        assert_eq!(run_texts(&document), vec!["Hello ", "world"]);
+        assert!(history.undo(&mut document).is_none());
+
+        history.redo(&mut document).unwrap().unwrap();
+        history.redo(&mut document).unwrap().unwrap();
+        assert_eq!(run_texts(&document), vec!["Hel", "rld!"]);
+        assert!(history.redo(&mut document).is_none());
+    }
+
+    #[test]
+    fn applying_a_command_discards_the_undone_commands() {
+        let mut document = create_tree();
+        let mut history = History::default();
+
+        let position = ParagraphPosition { path: vec![0], offset: 0 };
+        history.apply(&mut document, Box::new(InsertText::new(position.clone(), String::from("A")))).unwrap();
+        history.undo(&mut document).unwrap().unwrap();
+
+        history.apply(&mut document, Box::new(InsertText::new(position, String::from("B")))).unwrap();
+        assert!(history.redo(&mut document).is_none());
+        assert_eq!(run_texts(&document), vec!["BHello ", "world"]);
+    }
+
+    #[test]
+    fn undoing_to_the_saved_state_is_unmodified() {
+        let mut document = create_tree();
+        let mut history = History::default();
+        assert!(!history.is_modified());
+
+        let position = ParagraphPosition { path: vec![0], offset: 0 };
+        history.apply(&mut document, Box::new(InsertText::new(position.clone(), String::from("A")))).unwrap();
+        assert!(history.is_modified());
+
+        history.mark_saved();
+        assert!(!history.is_modified());
+
+        history.undo(&mut document).unwrap().unwrap();
+        assert!(history.is_modified());
+        history.redo(&mut document).unwrap().unwrap();
+        assert!(!history.is_modified());
+
+        // Undoing and then making another edit loses the saved state.
+        history.undo(&mut document).unwrap().unwrap();
+        history.apply(&mut document, Box::new(InsertText::new(position, String::from("B")))).unwrap();
+        history.undo(&mut document).unwrap().unwrap();
+        assert!(history.is_modified());
+    }
+
+    #[test]
+    fn undo_and_redo_node_mutations() {
+        let mut document = create_tree();
+        let mut history = History::default();
+
+        let position = history.apply(&mut document, Box::new(RemoveNode::new(vec![0, 0]))).unwrap();
+        assert_eq!(position, ParagraphPosition { path: vec![0], offset: 0 });
+        assert_eq!(run_texts(&document), vec!["world"]);
+
+        let mut run = Node::new(NodeData::TextRun(Default::default()));
+        create_text_part(&mut run, "Bye ");
+        let position = history.apply(&mut document, Box::new(InsertNode::new(vec![0, 1], run))).unwrap();
+        assert_eq!(position, ParagraphPosition { path: vec![0], offset: 5 });
+        assert_eq!(run_texts(&document), vec!["world", "Bye "]);
+
+        history.undo(&mut document).unwrap().unwrap();
+        assert_eq!(run_texts(&document), vec!["world"]);
+        history.undo(&mut document).unwrap().unwrap();
+        assert_eq!(run_texts(&document), vec!["Hello ", "world"]);
+
+        // The removed node is inserted again, and the inserted node is kept
+        // to be inserted again.
+        history.redo(&mut document).unwrap().unwrap();
+        history.redo(&mut document).unwrap().unwrap();
+        assert_eq!(run_texts(&document), vec!["world", "Bye "]);
+        assert!(history.redo(&mut document).is_none());
+
+        // Nodes can't be inserted outside of a paragraph.
+        let result = history.apply(&mut document, Box::new(InsertNode::new(vec![1], Node::new(NodeData::Document))));
+        assert_eq!(result, Err(EditError::NotInParagraph));
+    }
+}
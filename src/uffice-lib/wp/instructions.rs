@@ -0,0 +1,495 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// 17.16 Fields and Hyperlinks
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDateTime, Timelike};
+
+use crate::unicode::alphabet::{self, LetterCase};
+
+use super::{
+    document_properties::DocumentProperties,
+    numbering::{format_letter, format_ordinal, format_roman},
+};
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday",
+];
+
+/// How the dates of comments and tracked changes are shown to the user.
+pub const ANNOTATION_DATE_PICTURE: &str = "dd-MM-yyyy H:mm";
+
+/// The fields that don't have a result, but mark content for other fields,
+/// such as index entries (XE) and table of contents entries (TC).
+const HIDDEN_FIELD_TYPES: [&str; 7] = ["ADDIN", "PRIVATE", "RD", "SET", "TA", "TC", "XE"];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FieldType {
+    /// Write the current date.
+    Date,
+
+    /// Write a property of the document, by the name used by the
+    /// DOCPROPERTY field, e.g. "Author". The AUTHOR, TITLE, etc. fields are
+    /// parsed as their document property.
+    DocumentProperty(String),
+
+    /// Write the number of pages of the document.
+    NumberOfPages,
+
+    /// Write the number of the page the field is on.
+    Page,
+
+    /// Write the page number of the specified bookmark.
+    PageReference(String),
+
+    /// Write the text of the specified bookmark.
+    Reference(String),
+
+    SequentiallyNumber,
+
+    TableOfContents,
+
+    /// Write the current time.
+    Time,
+
+    /// A field that isn't supported, with the name of the field.
+    Unknown(String),
+}
+
+/// A switch of a field instruction, e.g. `\@ "d MMMM yyyy"` or `\h`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FieldSwitch {
+    /// The name of the switch, without the backslash.
+    pub name: String,
+    pub argument: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// 17.16.1 Syntax
+pub struct Field {
+    field: FieldType,
+    switches: Vec<FieldSwitch>,
+
+    /// The instruction the field was parsed from, which is written back when
+    /// the document is saved.
+    instruction: String,
+}
+
+/// What the result of a field can depend on.
+pub struct FieldContext<'a> {
+    /// The index of the page the field is on.
+    pub page: usize,
+    pub page_count: usize,
+
+    pub document_properties: &'a DocumentProperties,
+
+    /// The text of the bookmarks by their name.
+    pub bookmarks: &'a HashMap<String, String>,
+
+    /// The current date and time, in local time.
+    pub now: NaiveDateTime,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Plain(String),
+
+    /// An argument between double quotes, which can contain spaces and
+    /// backslashes.
+    Quoted(String),
+}
+
+/// Splits the instruction into its tokens. Within quotes, a backslash escapes
+/// a double quote or another backslash (17.16.1).
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        if c == '"' {
+            let mut text = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' if matches!(chars.peek(), Some('"' | '\\')) => text.push(chars.next().unwrap()),
+                    c => text.push(c),
+                }
+            }
+            tokens.push(Token::Quoted(text));
+            continue;
+        }
+
+        let mut text = String::from(c);
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == '"' {
+                break;
+            }
+            text.push(c);
+            chars.next();
+        }
+        tokens.push(Token::Plain(text));
+    }
+
+    tokens
+}
+
+impl Field {
+    pub fn parse(input: &str) -> Self {
+        let mut tokens = tokenize(input).into_iter().peekable();
+        // The arguments after the field type, which aren't arguments of a
+        // switch.
+        let mut arguments = Vec::new();
+        let mut switches = Vec::new();
+
+        let field_type = match tokens.next() {
+            Some(Token::Plain(field_type)) | Some(Token::Quoted(field_type)) => field_type.to_ascii_uppercase(),
+            None => {
+                println!("[Instructions] Empty instruction: \"{}\"", input);
+                String::new()
+            }
+        };
+
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Plain(text) if text.starts_with('\\') => {
+                    // The token after a switch is its argument, unless it is
+                    // another switch.
+                    let argument = match tokens.peek() {
+                        Some(Token::Plain(next)) if next.starts_with('\\') => None,
+                        Some(_) => match tokens.next() {
+                            Some(Token::Plain(argument)) | Some(Token::Quoted(argument)) => Some(argument),
+                            None => None,
+                        },
+                        None => None,
+                    };
+
+                    switches.push(FieldSwitch { name: text[1..].to_string(), argument });
+                }
+                Token::Plain(text) | Token::Quoted(text) => arguments.push(text),
+            }
+        }
+
+        let first_argument = || arguments.first().cloned().unwrap_or_default();
+        let field = match field_type.as_str() {
+            "AUTHOR" => FieldType::DocumentProperty(String::from("Author")),
+            "COMMENTS" => FieldType::DocumentProperty(String::from("Comments")),
+            "DATE" => FieldType::Date,
+            "DOCPROPERTY" => FieldType::DocumentProperty(first_argument()),
+            "KEYWORDS" => FieldType::DocumentProperty(String::from("Keywords")),
+            "LASTSAVEDBY" => FieldType::DocumentProperty(String::from("LastSavedBy")),
+            "NUMPAGES" => FieldType::NumberOfPages,
+            "PAGE" => FieldType::Page,
+            "PAGEREF" => FieldType::PageReference(first_argument()),
+            "REF" => FieldType::Reference(first_argument()),
+            "SEQ" => FieldType::SequentiallyNumber,
+            "SUBJECT" => FieldType::DocumentProperty(String::from("Subject")),
+            "TIME" => FieldType::Time,
+            "TITLE" => FieldType::DocumentProperty(String::from("Title")),
+            "TOC" => FieldType::TableOfContents,
+            _ => {
+                if !field_type.is_empty() && !HIDDEN_FIELD_TYPES.contains(&field_type.as_str()) {
+                    println!("[Instructions] Unknown field_type: \"{}\" in instruction \"{}\"", field_type, input);
+                }
+                FieldType::Unknown(field_type)
+            }
+        };
+
+        Self {
+            field,
+            switches,
+            instruction: String::from(input),
+        }
+    }
+
+    pub fn instruction(&self) -> &str {
+        &self.instruction
+    }
+
+    /// Get the argument of the first switch with the given name, e.g. "@"
+    /// for the date-time picture switch.
+    pub fn switch_argument(&self, name: &str) -> Option<&str> {
+        self.switches.iter()
+            .find(|switch| switch.name == name)
+            .and_then(|switch| switch.argument.as_deref())
+    }
+
+    /// Whether the field marks content for other fields, and doesn't have a
+    /// result to show.
+    pub fn is_hidden(&self) -> bool {
+        matches!(&self.field, FieldType::Unknown(name) if HIDDEN_FIELD_TYPES.contains(&name.as_str()))
+    }
+
+    /// Whether the result depends on the page the field is on, or on the
+    /// number of pages, which are only known after pagination.
+    pub fn is_page_dependent(&self) -> bool {
+        matches!(self.field, FieldType::NumberOfPages | FieldType::Page)
+    }
+
+    /// Whether the field can be evaluated by [Field::evaluate]. The results of
+    /// the other fields are taken from the document.
+    pub fn is_supported(&self) -> bool {
+        matches!(self.field, FieldType::Date | FieldType::DocumentProperty(..) | FieldType::NumberOfPages
+            | FieldType::Page | FieldType::Reference(..) | FieldType::Time)
+    }
+
+    /// Evaluate the field. Returns None when the field isn't supported, or
+    /// refers to something that doesn't exist, in which case the result
+    /// stored in the document should be shown instead.
+    pub fn evaluate(&self, context: &FieldContext) -> Option<String> {
+        if self.is_page_dependent() {
+            return self.evaluate_page_field(context.page, context.page_count);
+        }
+
+        let result = match &self.field {
+            // When no format is specified, the current date is formatted in
+            // an implementation-defined manner.
+            FieldType::Date => format_date_time(self.switch_argument("@").unwrap_or("dd-MM-yyyy"), &context.now),
+            FieldType::DocumentProperty(name) => context.document_properties.get_by_name(name)?.to_string(),
+            FieldType::Reference(bookmark) => context.bookmarks.get(bookmark)?.clone(),
+            FieldType::Time => format_date_time(self.switch_argument("@").unwrap_or("H:mm"), &context.now),
+            _ => return None,
+        };
+
+        Some(self.format_text(result))
+    }
+
+    /// Evaluate a page-dependent field (see [Field::is_page_dependent]) for
+    /// the page with the given index.
+    pub fn evaluate_page_field(&self, page: usize, page_count: usize) -> Option<String> {
+        let value = match self.field {
+            FieldType::NumberOfPages => page_count,
+            FieldType::Page => page + 1,
+            _ => return None,
+        };
+
+        Some(self.format_text(self.format_number(value)))
+    }
+
+    /// 17.16.4.3 General formatting
+    /// Format a numeric result using the first numbering format of the `\*`
+    /// switches, e.g. `\* ROMAN`.
+    fn format_number(&self, value: usize) -> String {
+        let formats = self.switches.iter()
+            .filter(|switch| switch.name == "*")
+            .filter_map(|switch| switch.argument.as_deref());
+
+        let Ok(value) = i32::try_from(value) else {
+            return value.to_string();
+        };
+
+        for format in formats {
+            match format {
+                "alphabetic" if value > 0 => return format_letter(alphabet::latin_alphabet_for_language(None), value, LetterCase::Lower),
+                "hex" => return format!("{:x}", value),
+                "roman" => return format_roman(value, LetterCase::Lower),
+                _ if format.eq_ignore_ascii_case("alphabetic") && value > 0 => {
+                    return format_letter(alphabet::latin_alphabet_for_language(None), value, LetterCase::Upper);
+                }
+                _ if format.eq_ignore_ascii_case("arabicdash") => return format!("- {} -", value),
+                _ if format.eq_ignore_ascii_case("hex") => return format!("{:X}", value),
+                _ if format.eq_ignore_ascii_case("ordinal") => return format_ordinal(value, None),
+                _ if format.eq_ignore_ascii_case("roman") => return format_roman(value, LetterCase::Upper),
+                _ => (),
+            }
+        }
+
+        value.to_string()
+    }
+
+    /// Apply the capitalization formats of the `\*` switches, e.g.
+    /// `\* Upper`.
+    fn format_text(&self, text: String) -> String {
+        let formats = self.switches.iter()
+            .filter(|switch| switch.name == "*")
+            .filter_map(|switch| switch.argument.as_deref());
+
+        let mut text = text;
+        for format in formats {
+            text = match format.to_ascii_lowercase().as_str() {
+                "caps" => capitalize_words(&text, true),
+                "firstcap" => capitalize_words(&text, false),
+                "lower" => text.to_lowercase(),
+                "upper" => text.to_uppercase(),
+
+                // MERGEFORMAT and CHARFORMAT are about the formatting of the
+                // result, which is taken from the runs.
+                _ => text,
+            };
+        }
+
+        text
+    }
+}
+
+/// Capitalize the first letter of every word, or only of the first word.
+fn capitalize_words(text: &str, every_word: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut at_word_start = true;
+    let mut capitalized = false;
+
+    for c in text.chars() {
+        if at_word_start && c.is_alphabetic() && !(capitalized && !every_word) {
+            result.extend(c.to_uppercase());
+            capitalized = true;
+        } else {
+            result.push(c);
+        }
+
+        at_word_start = c.is_whitespace();
+    }
+
+    result
+}
+
+/// Parses a date and time (22.9.2.4 ST_DateTime), like the date of a comment
+/// or a tracked change. Word writes the local time with a 'Z' suffix, so the
+/// time zone is ignored.
+pub fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+/// 17.16.4.1 Date and time formatting
+/// Format the date and time using a picture like "dddd, MMMM d, yyyy", in
+/// which text between apostrophes is copied literally.
+pub fn format_date_time(picture: &str, time: &NaiveDateTime) -> String {
+    let chars: Vec<char> = picture.chars().collect();
+    let mut result = String::new();
+
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+
+        if c == '\'' {
+            let literal: String = chars[index + 1..].iter().take_while(|c| **c != '\'').collect();
+            index += literal.chars().count() + 2;
+            result += &literal;
+            continue;
+        }
+
+        let rest: String = chars[index..].iter().collect();
+        if let Some(marker) = ["AM/PM", "am/pm", "A/P", "a/p"].into_iter().find(|marker| rest.starts_with(marker)) {
+            let (before, after) = marker.split_once('/').unwrap();
+            result += if time.hour() < 12 { before } else { after };
+            index += marker.len();
+            continue;
+        }
+
+        let count = chars[index..].iter().take_while(|other| **other == c).count();
+        let twelve_hour = (time.hour() + 11) % 12 + 1;
+
+        match (c, count) {
+            ('d', 1) => result += &time.day().to_string(),
+            ('d', 2) => result += &format!("{:02}", time.day()),
+            ('d', 3) => result += &WEEKDAY_NAMES[time.weekday().num_days_from_monday() as usize][..3],
+            ('d', _) => result += WEEKDAY_NAMES[time.weekday().num_days_from_monday() as usize],
+
+            ('M', 1) => result += &time.month().to_string(),
+            ('M', 2) => result += &format!("{:02}", time.month()),
+            ('M', 3) => result += &MONTH_NAMES[time.month0() as usize][..3],
+            ('M', _) => result += MONTH_NAMES[time.month0() as usize],
+
+            ('y', 1 | 2) => result += &format!("{:02}", time.year() % 100),
+            ('y', _) => result += &time.year().to_string(),
+
+            ('h', 1) => result += &twelve_hour.to_string(),
+            ('h', _) => result += &format!("{:02}", twelve_hour),
+            ('H', 1) => result += &time.hour().to_string(),
+            ('H', _) => result += &format!("{:02}", time.hour()),
+
+            ('m', 1) => result += &time.minute().to_string(),
+            ('m', _) => result += &format!("{:02}", time.minute()),
+            ('s', 1) => result += &time.second().to_string(),
+            ('s', _) => result += &format!("{:02}", time.second()),
+
+            _ => result += &c.to_string().repeat(count),
+        }
+
+        index += count;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time() -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2023, 3, 5).unwrap().and_hms_opt(14, 7, 9).unwrap()
+    }
+
+    fn evaluate(instruction: &str, page: usize, page_count: usize) -> Option<String> {
+        let document_properties = DocumentProperties {
+            creator: Some(String::from("Jane Doe")),
+            title: Some(String::from("Annual Report")),
+            ..Default::default()
+        };
+        let bookmarks = HashMap::from([(String::from("Summary"), String::from("a short summary"))]);
+
+        Field::parse(instruction).evaluate(&FieldContext {
+            page,
+            page_count,
+            document_properties: &document_properties,
+            bookmarks: &bookmarks,
+            now: time(),
+        })
+    }
+
+    #[test]
+    fn parse_arguments_and_switches() {
+        let field = Field::parse(r#" DATE \@ "dddd, MMMM d" \* MERGEFORMAT "#);
+        assert_eq!(field.field, FieldType::Date);
+        assert_eq!(field.switch_argument("@"), Some("dddd, MMMM d"));
+        assert_eq!(field.switch_argument("*"), Some("MERGEFORMAT"));
+
+        let field = Field::parse(r#"REF _Ref123 \h \* Upper"#);
+        assert_eq!(field.field, FieldType::Reference(String::from("_Ref123")));
+        assert_eq!(field.switch_argument("h"), None);
+        assert_eq!(field.switch_argument("*"), Some("Upper"));
+
+        let field = Field::parse(r#"DOCPROPERTY "Last \"Saved\" By""#);
+        assert_eq!(field.field, FieldType::DocumentProperty(String::from(r#"Last "Saved" By"#)));
+
+        let field = Field::parse("XE \"Entry\"");
+        assert!(field.is_hidden());
+        assert!(!field.is_supported());
+    }
+
+    #[test]
+    fn format_date_time_pictures() {
+        assert_eq!(format_date_time("dddd, MMMM d, yyyy", &time()), "Sunday, March 5, 2023");
+        assert_eq!(format_date_time("ddd dd-MMM-yy", &time()), "Sun 05-Mar-23");
+        assert_eq!(format_date_time("dd/MM/yyyy HH:mm:ss", &time()), "05/03/2023 14:07:09");
+        assert_eq!(format_date_time("h:mm am/pm", &time()), "2:07 pm");
+        assert_eq!(format_date_time("'Day' d", &time()), "Day 5");
+    }
+
+    #[test]
+    fn evaluate_fields() {
+        assert_eq!(evaluate("PAGE", 2, 10).as_deref(), Some("3"));
+        assert_eq!(evaluate(r"PAGE \* ROMAN", 3, 10).as_deref(), Some("IV"));
+        assert_eq!(evaluate(r"PAGE \* alphabetic", 27, 30).as_deref(), Some("bb"));
+        assert_eq!(evaluate(r"NUMPAGES \* Ordinal", 0, 22).as_deref(), Some("22nd"));
+        assert_eq!(evaluate(r#"DATE \@ "d MMMM yyyy""#, 0, 1).as_deref(), Some("5 March 2023"));
+        assert_eq!(evaluate("TIME", 0, 1).as_deref(), Some("14:07"));
+        assert_eq!(evaluate(r"AUTHOR \* Upper", 0, 1).as_deref(), Some("JANE DOE"));
+        assert_eq!(evaluate("DOCPROPERTY Title", 0, 1).as_deref(), Some("Annual Report"));
+        assert_eq!(evaluate("DOCPROPERTY Subject", 0, 1).as_deref(), Some(""));
+        assert_eq!(evaluate(r"REF Summary \* FirstCap", 0, 1).as_deref(), Some("A short summary"));
+
+        assert_eq!(evaluate("REF Missing", 0, 1), None);
+        assert_eq!(evaluate("DOCPROPERTY Unknown", 0, 1), None);
+        assert_eq!(evaluate(r"TOC \o 1-3", 0, 1), None);
+    }
+}
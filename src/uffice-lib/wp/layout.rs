@@ -0,0 +1,166 @@
+// Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use crate::{
+    gui::Position,
+    text_settings::PageSettings,
+};
+
+/// A column of a section (17.6.3 col), as the horizontal bounds of its text
+/// on the page, in points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Column {
+    pub start: f32,
+    pub end: f32,
+}
+
+pub struct LineLayout {
+    line_height: f32,
+
+    pub position_on_line: Position<f32>,
+
+    pub page_horizontal_start: f32,
+    pub page_horizontal_end: f32,
+    pub page_vertical_start: f32,
+    pub page_vertical_end: f32,
+
+    /// The distance between the start of the text margin and the start of
+    /// the lines of the paragraph (<w:ind w:start>).
+    pub indentation_start: f32,
+
+    /// The distance between the end of the lines and the end of the text
+    /// margin (<w:ind w:end>).
+    pub indentation_end: f32,
+
+    /// How much further the first line starts than the other lines, which is
+    /// negative for a hanging indentation.
+    pub first_line_offset: f32,
+
+    /// The columns the text flows through before continuing on the next
+    /// page, which is empty when the text isn't laid out in columns.
+    columns: Vec<Column>,
+    column: usize,
+}
+
+impl LineLayout {
+    pub fn new(page_settings: &PageSettings, y: f32) -> Self {
+        Self {
+            line_height: 0.0,
+            position_on_line: Position::new(page_settings.margins.left().get_pts(), y),
+
+            page_horizontal_start: page_settings.margins.left().get_pts(),
+            page_horizontal_end: page_settings.size.width().get_pts() - page_settings.margins.right().get_pts(),
+
+            page_vertical_start: page_settings.margins.top().get_pts(),
+            page_vertical_end: page_settings.size.height().get_pts() - page_settings.margins.bottom().get_pts(),
+
+            indentation_start: 0.0,
+            indentation_end: 0.0,
+            first_line_offset: 0.0,
+
+            columns: Vec::new(),
+            column: 0,
+        }
+    }
+
+    /// Lays out the text in the `column` of the `columns`.
+    pub fn set_columns(&mut self, columns: Vec<Column>, column: usize) {
+        self.columns = columns;
+        self.column = column;
+        self.apply_column();
+        self.position_on_line = Position::new(self.line_start(), self.position_on_line.y());
+    }
+
+    /// The index of the column the text is laid out in.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    fn apply_column(&mut self) {
+        if let Some(column) = self.columns.get(self.column) {
+            self.page_horizontal_start = column.start;
+            self.page_horizontal_end = column.end;
+        }
+    }
+
+    /// Continues at the top of the next column. Returns true when the last
+    /// column of the page was full, in which case the text continues in the
+    /// first column of the next page.
+    pub fn break_column(&mut self) -> bool {
+        let is_last_column = self.column + 1 >= self.columns.len();
+        self.column = if is_last_column { 0 } else { self.column + 1 };
+        self.apply_column();
+        self.reset();
+        is_last_column
+    }
+
+    /// Continues at the top of the first column of the next page.
+    pub fn break_page(&mut self) {
+        self.column = 0;
+        self.apply_column();
+        self.reset();
+    }
+
+    /// Where the lines that start at `x` end, which depends on the column
+    /// they are in.
+    pub fn line_end_at(&self, x: f32) -> f32 {
+        match self.columns.iter().find(|column| x < column.end) {
+            Some(column) => column.end - self.indentation_end,
+            None => self.line_end(),
+        }
+    }
+
+    /// The horizontal bounds of the column the text at `x` is in, without the
+    /// indentation of the paragraph.
+    pub fn column_bounds_at(&self, x: f32) -> (f32, f32) {
+        match self.columns.iter().find(|column| x < column.end) {
+            Some(column) => (column.start, column.end),
+            None => (self.page_horizontal_start, self.page_horizontal_end),
+        }
+    }
+
+    /// Where the lines of the paragraph start, except for the first one.
+    pub fn line_start(&self) -> f32 {
+        self.page_horizontal_start + self.indentation_start
+    }
+
+    /// Where the first line of the paragraph starts.
+    pub fn first_line_start(&self) -> f32 {
+        self.line_start() + self.first_line_offset
+    }
+
+    /// Where the lines of the paragraph end.
+    pub fn line_end(&self) -> f32 {
+        self.page_horizontal_end - self.indentation_end
+    }
+
+    /// Adds a line-height candidate. When the supplied height is smaller than
+    /// the current height, nothing will happen.
+    pub fn add_line_height_candidate(&mut self, height: f32) {
+        if height > self.line_height {
+            self.line_height = height;
+        }
+    }
+
+    pub fn new_line(&mut self) {
+        let new_y = self.position_on_line.y() + self.line_height;
+        self.position_on_line = Position::new(self.line_start(), new_y);
+        self.line_height = 0.0;
+    }
+
+    /// Starts a new, empty line at the given vertical position, e.g. below a
+    /// drawing object the text doesn't flow around.
+    pub fn new_line_at(&mut self, y: f32) {
+        self.position_on_line = Position::new(self.line_start(), y);
+        self.line_height = 0.0;
+    }
+
+    pub fn reset(&mut self) {
+        self.position_on_line = Position::new(self.line_start(), self.page_vertical_start);
+        self.line_height = 0.0;
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+}
@@ -0,0 +1,1236 @@
+// Copyright (C) 2022 - 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+pub mod bidi;
+pub mod comments;
+pub mod diagnostics;
+pub mod document_properties;
+pub mod editing;
+pub mod font_table;
+pub mod history;
+pub mod instructions;
+pub mod layout;
+pub mod numbering;
+pub mod revisions;
+pub mod save;
+pub mod search;
+pub mod selection;
+pub mod settings;
+pub mod statistics;
+pub mod table;
+
+use std::{
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+    cell::RefCell,
+    sync::Arc,
+};
+
+use winit::window::CursorIcon;
+
+use crate::{
+    text_settings::{
+        ParagraphBorders,
+        TextSettings,
+        PageSettings,
+    },
+    gui::{
+        Position,
+        Size,
+        Rect,
+    },
+    fonts::DocumentFontSource,
+    relationships::Relationship,
+};
+
+use self::diagnostics::Diagnostic;
+use self::table::{
+    TableCellProperties,
+    TableGrid,
+    TableProperties,
+};
+
+#[derive(Debug, strum_macros::IntoStaticStr)]
+pub enum NodeData {
+    /// The start of a bookmark (17.13.6.2 bookmarkStart), positioned where the
+    /// content after it starts.
+    BookmarkStart(Bookmark),
+
+    /// The end of the bookmark with the given id (17.13.6.1 bookmarkEnd).
+    BookmarkEnd(String),
+
+    /// Line, column or page break.
+    Break(BreakType),
+
+    /// The start of the range of the comment with the given id (17.13.4.4
+    /// commentRangeStart).
+    CommentRangeStart(String),
+
+    /// The end of the range of the comment with the given id (17.13.4.3
+    /// commentRangeEnd).
+    CommentRangeEnd(String),
+
+    Document,
+    Drawing(crate::drawing_ml::DrawingObject),
+    Hyperlink(Hyperlink),
+
+    /// The numbering parent is an invisible parent which contains a single
+    /// TextPart child.
+    ///
+    /// The reason this TextPart has a parent and isn't just a child of the
+    /// upper Paragraph, is to combine the TextSettings of the NumberingStyle
+    /// and the upperlying Paragraph.
+    NumberingParent,
+    Paragraph(Paragraph),
+
+    /// A tracked change of the runs inside of it.
+    Revision(revisions::Revision),
+
+    StructuredDocumentTag(StructuredDocumentTag),
+    Table{
+        grid: TableGrid,
+        properties: TableProperties,
+    },
+    TableRow,
+    TableCell(TableCellProperties),
+    Text,
+    TextPart(TextPart),
+    TextRun(TextRun),
+}
+
+impl NodeData {
+    pub fn is_document(&self) -> bool {
+        matches!(self, Self::Document)
+    }
+}
+
+#[derive(Debug)]
+pub enum HoverState {
+    HoveringOver,
+    NotHoveringOn,
+}
+
+#[derive(Debug)]
+pub struct InteractionStates {
+    pub hover: HoverState,
+
+    /// The graphemes of a text part that are selected by the user.
+    pub selected_graphemes: Option<Range<usize>>,
+}
+
+impl Default for InteractionStates {
+    fn default() -> Self {
+        Self {
+            hover: HoverState::NotHoveringOn,
+            selected_graphemes: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Node {
+    /// Can be None when this element isn't allowed to have children
+    pub children: Vec<Node>,
+    pub data: NodeData,
+
+    /// The page number this node is starting on.
+    /// (from 0)
+    pub page_first: usize,
+    pub page_last: usize,
+
+    /// The position this node is starting from.
+    pub position: Position<f32>,
+
+    pub text_settings: TextSettings,
+
+    pub size: Size<f32>,
+
+    pub interaction_states: InteractionStates,
+
+}
+
+impl Node {
+    pub fn new(data: NodeData) -> Self {
+        Self {
+            children: Vec::new(),
+
+            data,
+            page_first: 0,
+            page_last: 0,
+            position: Position::new(0.0, 0.0),
+            text_settings: TextSettings::new(),
+            size: Default::default(),
+            interaction_states: Default::default(),
+        }
+    }
+    /// Run the `callback` function recursively on itself and it's descendants.
+    pub fn apply_recursively(&mut self, callback: &dyn Fn(&mut Node, usize), depth: usize) {
+        callback(self, depth);
+
+        for child in &mut self.children {
+            child.apply_recursively(callback, depth + 1);
+        }
+    }
+
+    /// Run the `callback` function recursively on itself and it's descendants.
+    pub fn apply_recursively_mut(&mut self, callback: &mut dyn FnMut(&mut Node, usize), depth: usize) {
+        callback(self, depth);
+
+        for child in &mut self.children {
+            child.apply_recursively_mut(callback, depth + 1);
+        }
+    }
+
+    /// Collects itself and the descendants matching the `predicate`, in
+    /// document order.
+    pub fn find_all(&self, predicate: impl Fn(&Node) -> bool) -> Vec<&Node> {
+        let mut nodes = Vec::new();
+        self.collect_matching(&predicate, &mut nodes);
+        nodes
+    }
+
+    fn collect_matching<'a>(&'a self, predicate: &dyn Fn(&Node) -> bool, nodes: &mut Vec<&'a Node>) {
+        if predicate(self) {
+            nodes.push(self);
+        }
+
+        for child in &self.children {
+            child.collect_matching(predicate, nodes);
+        }
+    }
+
+    /// Finds the first node in document order (starting with itself) that
+    /// matches the `predicate`.
+    pub fn find_first(&self, predicate: impl Fn(&Node) -> bool) -> Option<&Node> {
+        self.find_first_matching(&predicate)
+    }
+
+    /// Finds the start of the bookmark with the given name.
+    pub fn find_bookmark(&self, name: &str) -> Option<&Node> {
+        self.find_first(|node| matches!(&node.data, NodeData::BookmarkStart(bookmark) if bookmark.name == name))
+    }
+
+    fn find_first_matching(&self, predicate: &dyn Fn(&Node) -> bool) -> Option<&Node> {
+        if predicate(self) {
+            return Some(self);
+        }
+
+        self.children.iter().find_map(|child| child.find_first_matching(predicate))
+    }
+
+    /// Formats the node and its descendants as an indented tree, with one
+    /// line per node containing its type, position, size and page range. The
+    /// output is deterministic, so it can be used in tests and bug reports.
+    pub fn to_debug_string(&self) -> String {
+        let mut output = String::new();
+        self.write_debug_string(&mut output, 0);
+        output
+    }
+
+    fn write_debug_string(&self, output: &mut String, depth: usize) {
+        use std::fmt::Write;
+
+        let name: &'static str = (&self.data).into();
+        _ = write!(output, "{}{}", "    ".repeat(depth), name);
+
+        if let NodeData::TextPart(part) = &self.data {
+            _ = write!(output, " {:?}", part.text);
+        }
+
+        _ = writeln!(output, " @ ({}, {}) sized ({}x{}) pages {}..={}",
+            self.position.x(), self.position.y(), self.size.width(), self.size.height(), self.page_first, self.page_last);
+
+        for child in &self.children {
+            child.write_debug_string(output, depth + 1);
+        }
+    }
+
+    pub fn on_event(&mut self, event: &mut Event) {
+        for child in &mut self.children {
+            child.on_event(event);
+        }
+
+        match &self.data {
+            NodeData::Hyperlink(hyperlink) => hyperlink.on_event(event),
+            NodeData::Revision(revision) => revision.on_event(event),
+            _ => (),
+        }
+    }
+
+    /// Calculates the absolute rect of this node, using the rects of the
+    /// pages as they are painted and the zoom factor. The position of a node is
+    /// relative to the page it starts on, so this returns None when that page
+    /// isn't in `page_rects`.
+    pub fn bounding_box(&self, page_rects: &[Rect<f32>], zoom: f32) -> Option<Rect<f32>> {
+        let page_rect = page_rects.get(self.page_first)?;
+        let position = Position::new(
+            page_rect.left + self.position.x() * zoom,
+            page_rect.top + self.position.y() * zoom,
+        );
+
+        Some(Rect::from_position_and_size(position, self.size * zoom))
+    }
+
+    /// Returns whether or not the absolute `position` hits this node. The
+    /// `callback` is invoked for the nodes in the hit path, from innermost to
+    /// outermost.
+    pub fn hit_test(&self, position: Position<f32>, page_rects: &[Rect<f32>], zoom: f32, callback: &mut dyn FnMut(&Node)) -> bool {
+        for child in &self.children {
+            if child.hit_test(position, page_rects, zoom, callback) {
+                callback(self);
+                return true;
+            }
+        }
+
+        match self.data {
+            NodeData::TextPart(..) => {
+                if self.bounding_box(page_rects, zoom).is_some_and(|rect| rect.is_inside_inclusive(position)) {
+                    callback(self);
+                    return true;
+                }
+            }
+            _ => ()
+        }
+
+        false
+    }
+
+    /// Finds the position in the text nearest to the absolute `position`,
+    /// considering only the text on the page under it. Text parts on the same
+    /// line are preferred over those that are horizontally closer, so that
+    /// dragging in the margins or between the lines still selects text.
+    pub fn nearest_text_position(&self, position: Position<f32>, page_rects: &[Rect<f32>], zoom: f32) -> Option<selection::TextPosition> {
+        let page = page_rects.iter().position(|page_rect| page_rect.is_inside_inclusive(position))?;
+
+        let distance = |start: f32, end: f32, value: f32| (start - value).max(value - end).max(0.0);
+
+        let (text_part, node, rect) = self.find_all(|node| matches!(node.data, NodeData::TextPart(..)))
+            .into_iter()
+            .enumerate()
+            .filter(|(_, node)| node.page_first == page)
+            .filter_map(|(index, node)| Some((index, node, node.bounding_box(page_rects, zoom)?)))
+            .min_by(|(_, _, a), (_, _, b)| {
+                let distance_a = (distance(a.top, a.bottom, position.y()), distance(a.left, a.right, position.x()));
+                let distance_b = (distance(b.top, b.bottom, position.y()), distance(b.left, b.right, position.x()));
+                distance_a.partial_cmp(&distance_b).unwrap_or(std::cmp::Ordering::Equal)
+            })?;
+
+        let NodeData::TextPart(part) = &node.data else {
+            unreachable!();
+        };
+
+        Some(selection::TextPosition {
+            text_part,
+            offset: part.nearest_grapheme_boundary((position.x() - rect.left) / zoom),
+        })
+    }
+
+    pub fn nth_child_mut(&mut self, index: usize) -> &mut Node {
+        &mut self.children[index]
+    }
+
+    pub fn update_page_last(&mut self) -> usize {
+        let mut last_page = self.page_last;
+        for child in &mut self.children {
+            let child_last_page = child.update_page_last();
+            if last_page < child_last_page {
+                last_page = child_last_page;
+            }
+        }
+
+        self.propose_last_page_number(last_page);
+
+        last_page
+    }
+
+    pub fn propose_last_page_number(&mut self, last_page: usize) {
+        if self.page_last < last_page {
+            self.page_last = last_page;
+        }
+    }
+
+    pub fn check_last_page_number_from_new_child(&mut self) {
+        let mut last_page = self.page_last;
+
+        if let Some(last) = self.children.last() {
+            last_page = last.page_last;
+        }
+
+        self.propose_last_page_number(last_page);
+    }
+
+    /// Evaluates the page-dependent fields (see
+    /// [instructions::Field::is_page_dependent]) again, for the page they are
+    /// on, or for the given page in headers and footers, which are shown on
+    /// multiple pages. The text parts of which the text changed are passed to
+    /// `on_changed`, e.g. to measure them again.
+    pub fn update_page_fields(&mut self, page: Option<usize>, page_count: usize, on_changed: &mut dyn FnMut(&mut Node)) {
+        if let NodeData::TextRun(TextRun { instruction: Some(field), .. }) = &self.data {
+            if let Some(value) = field.evaluate_page_field(page.unwrap_or(self.page_first), page_count) {
+                // The value is put in the first text part, in case it was
+                // wrapped over multiple lines.
+                let mut value = Some(value);
+                for child in &mut self.children {
+                    let NodeData::TextPart(part) = &mut child.data else {
+                        continue;
+                    };
+
+                    let text = value.take().unwrap_or_default();
+                    if part.text != text {
+                        part.text = text;
+                        on_changed(child);
+                    }
+                }
+            }
+
+            return;
+        }
+
+        for child in &mut self.children {
+            child.update_page_fields(page, page_count, on_changed);
+        }
+    }
+}
+
+pub fn append_child(parent: &mut Node, mut node: Node) -> usize {
+    node.text_settings = parent.text_settings.clone();
+    node.page_first = parent.page_last;
+    node.page_last = parent.page_last;
+    node.position = parent.position;
+
+    parent.children.push(node);
+    parent.children.len() - 1
+}
+
+pub fn create_child<'b>(parent: &mut Node, data: NodeData) -> usize {
+    let node = Node {
+        children: Vec::new(),
+        data,
+        page_first: parent.page_last,
+        page_last: parent.page_last,
+        position: parent.position,
+        text_settings: parent.text_settings.clone(),
+        size: Default::default(),
+        interaction_states: Default::default(),
+    };
+
+    append_child(parent, node)
+}
+
+impl Document {
+    pub fn new(text_settings: TextSettings) -> Node {
+        let mut node = Node::new(NodeData::Document);
+
+        node.text_settings = text_settings;
+
+        node
+    }
+}
+
+pub struct MouseEvent {
+    pub position: Position<f32>,
+    pub new_cursor: Option<CursorIcon>,
+
+    /// The bookmark to scroll to, when a link to a bookmark in the document
+    /// was clicked.
+    pub bookmark: Option<String>,
+
+    /// The URL to open in the browser, when a link to outside the document
+    /// was clicked.
+    pub url: Option<String>,
+
+    /// Describes what is under the mouse, e.g. who made a tracked change,
+    /// which is shown to the user while hovering over it. The lines are shown
+    /// in a tooltip, and after each other in the status bar.
+    pub hover_text: Option<String>,
+
+    /// Whether clicking follows links, which requires holding Ctrl, like in
+    /// Microsoft Word, so the text of links can be clicked on as well.
+    pub follows_links: bool,
+}
+
+impl MouseEvent {
+    pub fn new(position: Position<f32>) -> MouseEvent {
+        Self {
+            position,
+            new_cursor: None,
+            bookmark: None,
+            url: None,
+            hover_text: None,
+            follows_links: false,
+        }
+    }
+}
+
+pub enum Event {
+    Click(MouseEvent),
+    Hover(MouseEvent),
+}
+
+#[derive(Debug, Default)]
+pub struct Paragraph {
+    /// Whether or not the paragraph contains tracked changes (revisions), for
+    /// which a change bar is drawn in the margin.
+    pub has_revisions: bool,
+
+    /// Where the text of the paragraph starts, after the spacing above it and
+    /// the numbering.
+    pub text_start: Position<f32>,
+
+    /// The area the lines were laid out in, which is used to lay out the
+    /// paragraph again after it is edited.
+    pub line_bounds: Rect<f32>,
+
+    /// The borders drawn around the paragraph.
+    pub borders: ParagraphBorders,
+
+    /// The outer edges of the borders, relative to the position of the
+    /// paragraph, so the borders move along with the paragraph.
+    pub border_rect: Rect<f32>,
+
+    /// The markup of the section properties of the section this paragraph
+    /// ends (17.6.18 sectPr), which is written back unchanged when saving.
+    pub section_properties: Option<Rc<str>>,
+}
+
+#[derive(Debug)]
+pub struct Document {
+    /// The size and margins of the pages of the last section, see
+    /// [Document::page_settings_for_page] for those of the other sections.
+    pub page_settings: PageSettings,
+    pub document_properties: document_properties::DocumentProperties,
+
+    /// The sections of the document, ordered by their first page.
+    pub sections: Vec<Section>,
+
+    /// The footnotes at the bottom of the pages, ordered by page.
+    pub footnote_areas: Vec<FootnoteArea>,
+
+    /// The comments of the comments part, which are anchored to the ranges
+    /// between the CommentRangeStart and CommentRangeEnd nodes.
+    pub comments: Vec<comments::Comment>,
+
+    /// Which text of the tracked changes the tree was laid out with.
+    pub revision_view: revisions::RevisionView,
+
+    /// The lines above the footnotes and the endnotes.
+    pub note_separators: Vec<NoteSeparator>,
+
+    /// The package the document was loaded from, which is None for documents
+    /// that weren't loaded from a file.
+    pub source: Option<SourcePackage>,
+
+    /// The namespace declarations of the root of the main document part, so
+    /// the markup that is written back unchanged keeps its prefixes.
+    pub namespaces: Vec<(Option<Rc<str>>, Rc<str>)>,
+
+    /// The markup of the section properties of the body (17.6.17 sectPr),
+    /// which is written back unchanged when saving, since the references to
+    /// the headers and footers aren't kept in the tree.
+    pub body_section_properties: Option<Rc<str>>,
+
+    /// The settings of the settings part.
+    pub settings: settings::DocumentSettings,
+
+    /// The fonts embedded in the package and the fallbacks of the font table,
+    /// which the painters use when painting the document.
+    pub fonts: Option<Arc<DocumentFontSource>>,
+}
+
+/// The package a document was loaded from, of which the parts the editor
+/// doesn't change are copied when the document is saved.
+#[derive(Clone, Debug)]
+pub struct SourcePackage {
+    pub path: PathBuf,
+    pub main_document_part: String,
+}
+
+impl Document {
+    /// Get the section the page with the given index belongs to.
+    pub fn section_for_page(&self, page: usize) -> Option<&Section> {
+        self.sections.iter()
+            .rev()
+            .find(|section| section.page_first <= page)
+    }
+
+    pub fn section_for_page_mut(&mut self, page: usize) -> Option<&mut Section> {
+        self.sections.iter_mut()
+            .rev()
+            .find(|section| section.page_first <= page)
+    }
+
+    /// Get the size and margins of the page with the given index, which are
+    /// those of the section it belongs to.
+    pub fn page_settings_for_page(&self, page: usize) -> PageSettings {
+        self.section_for_page(page)
+            .map(|section| section.page_settings)
+            .unwrap_or(self.page_settings)
+    }
+
+    /// How far the content of the page is moved to the right, since the left
+    /// and right margins of the even pages are swapped when the margins are
+    /// mirrored (17.15.1.57 mirrorMargins).
+    pub fn mirror_offset_for_page(&self, page: usize) -> f32 {
+        // The page numbers start at one, so the even pages have odd indices.
+        if !self.settings.mirror_margins || page % 2 == 0 {
+            return 0.0;
+        }
+
+        let margins = self.page_settings_for_page(page).margins;
+        margins.right.get_pts() - margins.left.get_pts()
+    }
+}
+
+/// The footnotes referenced on a page, which are shown at the bottom of it
+/// (17.11.10).
+#[derive(Debug)]
+pub struct FootnoteArea {
+    pub page: usize,
+
+    /// The footnotes, laid out as if they were on the first page.
+    pub notes: Node,
+}
+
+/// The line separating the footnotes or endnotes from the text above them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteSeparator {
+    pub page: usize,
+
+    /// The rect of the line in points, relative to the page.
+    pub rect: Rect<f32>,
+}
+
+/// The headers or the footers of a section (17.10.1). Their nodes are laid
+/// out as if they were on the first page, so they can be painted on every
+/// page.
+#[derive(Debug, Default)]
+pub struct HeaderFooterSet {
+    /// Used for the pages that don't have a header or footer of their own.
+    pub default: Option<Node>,
+
+    /// Used for the first page of the section, when it has a title page.
+    pub first: Option<Node>,
+
+    /// Used for the even pages, when the document has different headers and
+    /// footers for the even and odd pages.
+    pub even: Option<Node>,
+}
+
+impl HeaderFooterSet {
+    fn for_page(&self, page_kind: HeaderFooterPageKind) -> Option<&Node> {
+        match page_kind {
+            HeaderFooterPageKind::First => self.first.as_ref(),
+            HeaderFooterPageKind::Even => self.even.as_ref(),
+            HeaderFooterPageKind::Default => self.default.as_ref(),
+        }
+    }
+
+    fn for_page_mut(&mut self, page_kind: HeaderFooterPageKind) -> Option<&mut Node> {
+        match page_kind {
+            HeaderFooterPageKind::First => self.first.as_mut(),
+            HeaderFooterPageKind::Even => self.even.as_mut(),
+            HeaderFooterPageKind::Default => self.default.as_mut(),
+        }
+    }
+}
+
+/// Which of the headers and footers of a [HeaderFooterSet] a page uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HeaderFooterPageKind {
+    Default,
+    First,
+    Even,
+}
+
+#[derive(Debug, Default)]
+pub struct Section {
+    /// The page number this section is starting on.
+    /// (from 0)
+    pub page_first: usize,
+
+    /// The size and margins of the pages of the section.
+    pub page_settings: PageSettings,
+
+    /// The columns the text of the section is laid out in.
+    pub columns: Vec<layout::Column>,
+
+    /// Whether a line is drawn between the columns (17.6.4 cols).
+    pub column_separator: bool,
+
+    /// Whether the first page of the section has its own header and footer
+    /// (17.10.6 titlePg).
+    pub title_page: bool,
+
+    /// Whether the even pages use the even headers and footers (17.10.1
+    /// evenAndOddHeaders), which is a setting of the whole document.
+    pub even_and_odd_headers: bool,
+
+    pub headers: HeaderFooterSet,
+    pub footers: HeaderFooterSet,
+}
+
+impl Section {
+    pub fn header_for_page(&self, page: usize) -> Option<&Node> {
+        self.headers.for_page(self.page_kind(page))
+    }
+
+    pub fn footer_for_page(&self, page: usize) -> Option<&Node> {
+        self.footers.for_page(self.page_kind(page))
+    }
+
+    /// Updates the page-dependent fields of the header and footer shown on the
+    /// page with the given index. Their text isn't measured again, since the
+    /// page numbers are mostly as wide on every page.
+    pub fn update_page_fields(&mut self, page: usize, page_count: usize) {
+        let page_kind = self.page_kind(page);
+        for set in [&mut self.headers, &mut self.footers] {
+            if let Some(node) = set.for_page_mut(page_kind) {
+                node.update_page_fields(Some(page), page_count, &mut |_| ());
+            }
+        }
+    }
+
+    fn page_kind(&self, page: usize) -> HeaderFooterPageKind {
+        if self.title_page && page == self.page_first {
+            HeaderFooterPageKind::First
+        } else if self.even_and_odd_headers && page % 2 == 1 {
+            // The page numbers start at one, so the even pages have odd
+            // indices.
+            HeaderFooterPageKind::Even
+        } else {
+            HeaderFooterPageKind::Default
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TextPart {
+    pub text: String,
+
+    /// The ascent of the font of the text, used to align the baselines of
+    /// the text parts on a line. For superscript and subscript text, the
+    /// shift of the baseline is included.
+    pub ascent: f32,
+
+    /// The extra space after each character, used by distributed
+    /// justification.
+    pub character_spacing: f32,
+
+    /// The extra space after each space between the words, used by full
+    /// justification.
+    pub word_spacing: f32,
+
+    /// The horizontal offset of the end of every grapheme, relative to the
+    /// start of the text part.
+    pub grapheme_ends: Vec<f32>,
+
+    /// The embedding level of the text (UAX #9), which is odd for
+    /// right-to-left text. The text of a part has a single level, so the
+    /// parts of a line can be reordered as a whole.
+    pub bidi_level: u8,
+}
+
+impl TextPart {
+    /// Whether the text flows from right to left, in which case the
+    /// graphemes are painted in reverse order.
+    pub fn is_right_to_left(&self) -> bool {
+        self.bidi_level % 2 == 1
+    }
+
+    /// The horizontal offset of the boundary before the grapheme at the
+    /// `index`, which is the end of the text part when the index is the
+    /// number of graphemes.
+    pub fn grapheme_boundary(&self, index: usize) -> f32 {
+        match index {
+            0 => 0.0,
+            index => self.grapheme_ends[index - 1],
+        }
+    }
+
+    /// The horizontal offset of the boundary before the grapheme at the
+    /// `index`, relative to the left of the text part, which is mirrored for
+    /// right-to-left text, of which the first grapheme is at the right.
+    pub fn visual_grapheme_boundary(&self, index: usize) -> f32 {
+        if !self.is_right_to_left() {
+            return self.grapheme_boundary(index);
+        }
+
+        let width = self.grapheme_ends.last().copied().unwrap_or(0.0);
+        width - self.grapheme_boundary(index)
+    }
+
+    /// Finds the index of the grapheme boundary nearest to the horizontal
+    /// offset `x`, relative to the left of the text part.
+    pub fn nearest_grapheme_boundary(&self, x: f32) -> usize {
+        let x = if self.is_right_to_left() {
+            self.grapheme_ends.last().copied().unwrap_or(0.0) - x
+        } else {
+            x
+        };
+
+        (0..self.grapheme_ends.len())
+            .find(|index| x < (self.grapheme_boundary(*index) + self.grapheme_ends[*index]) / 2.0)
+            .unwrap_or(self.grapheme_ends.len())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TextRun {
+    pub instruction: Option<crate::wp::instructions::Field>,
+
+    /// Whether the run contains content that is positioned by the layout
+    /// without leaving a text part behind, such as tabs, breaks and drawings.
+    /// The text of such runs can't be laid out again from the tree.
+    pub has_layout_content: bool,
+
+    /// The id of the comment of which the run contains the reference mark
+    /// (17.13.4.5 commentReference).
+    pub comment_reference: Option<String>,
+}
+
+/// A bookmark, which links and fields can refer to by its name (17.13.6).
+#[derive(Debug, Default)]
+pub struct Bookmark {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Hyperlink {
+    pub relationship: Option<Rc<RefCell<Relationship>>>,
+
+    /// The bookmark the link goes to (17.16.22 anchor). When the link has a
+    /// relationship, this is the location within its target instead.
+    pub anchor: Option<String>,
+}
+
+/// Shown below the target of a link while hovering over it.
+const FOLLOW_LINK_HINT: &str = "Ctrl+Click to follow link";
+
+impl Hyperlink {
+    pub fn on_event(&self, event: &mut Event) {
+        match event {
+            Event::Click(mouse_event) if !mouse_event.follows_links => (),
+
+            Event::Click(mouse_event) => {
+                if let Some(relationship) = &self.relationship {
+                    let url = &relationship.borrow().target;
+                    match url::Url::parse(url) {
+                        Err(e) => println!("[Interactable] (Link): \"{}\": {:?}", url, e),
+                        Ok(mut url) => {
+                            if let Some(anchor) = &self.anchor {
+                                url.set_fragment(Some(anchor));
+                            }
+                            mouse_event.url = Some(String::from(url));
+                        }
+                    }
+                } else if let Some(anchor) = &self.anchor {
+                    mouse_event.bookmark = Some(anchor.clone());
+                } else {
+                    println!("[WARNING] Clicked on a link but no relationship was bound :(");
+                }
+            }
+
+            Event::Hover(mouse_event) => {
+                mouse_event.new_cursor = Some(CursorIcon::Hand);
+                if let Some(target) = self.target_description() {
+                    mouse_event.hover_text = Some(format!("{}\n{}", target, FOLLOW_LINK_HINT));
+                }
+            }
+        }
+    }
+
+    /// Describes where the link goes to, which is the URL of its target, or
+    /// the name of the bookmark prefixed with `#`.
+    pub fn target_description(&self) -> Option<String> {
+        match (self.get_url(), &self.anchor) {
+            (Some(url), Some(anchor)) => Some(format!("{}#{}", url, anchor)),
+            (Some(url), None) => Some(url),
+            (None, Some(anchor)) => Some(format!("#{}", anchor)),
+            (None, None) => None,
+        }
+    }
+
+    pub fn get_url(&self) -> Option<String> {
+        if let Some(relationship) = &self.relationship {
+            return Some(relationship.borrow().target.clone());
+        }
+
+        None
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum StructuredDocumentTagLevel {
+    /// The tag is found in the document <w:body>.
+    Block,
+
+    #[allow(dead_code)]
+    Row,
+
+    /// The tag is found in a <w:p> element.
+    Inline,
+
+    #[allow(dead_code)]
+    Cell,
+}
+
+#[derive(Debug)]
+pub struct StructuredDocumentTag {
+    pub level: StructuredDocumentTagLevel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakType {
+    Column,
+
+    Page,
+
+    /// Line break
+    TextWrapping,
+}
+
+/// Where a section starts, relative to the section before it (17.18.77
+/// ST_SectionMark).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SectionType {
+    Continuous,
+    NextColumn,
+
+    #[default]
+    NextPage,
+
+    EvenPage,
+    OddPage,
+}
+
+impl SectionType {
+    /// Parses the `w:val` of the <w:type> element, where an unknown type is
+    /// reported and treated like a missing one.
+    pub fn from_string(string: Option<&str>, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        match string {
+            None => SectionType::NextPage,
+            Some(string) => match string {
+                "continuous" => SectionType::Continuous,
+                "nextColumn" => SectionType::NextColumn,
+                "nextPage" => SectionType::NextPage,
+                "evenPage" => SectionType::EvenPage,
+                "oddPage" => SectionType::OddPage,
+                _ => {
+                    diagnostics.push(Diagnostic::warning("w:type", format!("unknown section type: \"{}\"", string)));
+                    SectionType::NextPage
+                }
+            }
+        }
+    }
+}
+
+impl BreakType {
+
+    pub fn from_string(string: Option<&str>) -> Self {
+        match string {
+            None => BreakType::TextWrapping,
+            Some(string) => match string {
+                "column" => BreakType::Column,
+                "page" => BreakType::Page,
+                "textWrapping" => BreakType::TextWrapping,
+                _ => {
+                    println!("[WP] Warning: unknown BreakType for string \"{}\"", string);
+                    BreakType::TextWrapping
+                }
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakType::Column => "column",
+            BreakType::Page => "page",
+            BreakType::TextWrapping => "textWrapping",
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_text_part(parent: &mut Node, text: &str) {
+        create_child(parent, NodeData::TextPart(TextPart { text: String::from(text), ascent: 0.0, character_spacing: 0.0, word_spacing: 0.0, grapheme_ends: Vec::new(), bidi_level: 0 }));
+    }
+
+    /// Creates a document with two paragraphs, of which the first contains
+    /// two text parts and the second a single one.
+    fn create_tree() -> Node {
+        let mut document = Node::new(NodeData::Document);
+
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        let run = create_child(document.nth_child_mut(paragraph), NodeData::TextRun(Default::default()));
+        create_text_part(document.nth_child_mut(paragraph).nth_child_mut(run), "Hello");
+        create_text_part(document.nth_child_mut(paragraph).nth_child_mut(run), "world");
+
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        create_text_part(document.nth_child_mut(paragraph), "Bye");
+
+        document
+    }
+
+    fn text_of(node: &Node) -> &str {
+        match &node.data {
+            NodeData::TextPart(part) => &part.text,
+            _ => panic!("not a text part: {:?}", node.data),
+        }
+    }
+
+    /// Two pages of 100x200 with a gap of 10 in between, at 2x zoom.
+    const PAGE_RECTS: [Rect<f32>; 2] = [
+        Rect { left: 50.0, right: 250.0, top: 20.0, bottom: 420.0 },
+        Rect { left: 50.0, right: 250.0, top: 440.0, bottom: 840.0 },
+    ];
+
+    fn create_positioned_text_part(page: usize, position: Position<f32>) -> Node {
+        let mut node = Node::new(NodeData::TextPart(TextPart {
+            text: String::from("Text"),
+            ascent: 0.0,
+            character_spacing: 0.0,
+            word_spacing: 0.0,
+            grapheme_ends: vec![7.5, 15.0, 22.5, 30.0],
+            bidi_level: 0,
+        }));
+        node.page_first = page;
+        node.page_last = page;
+        node.position = position;
+        node.size = Size::new(30.0, 10.0);
+        node
+    }
+
+    #[test]
+    fn bounding_box_is_relative_to_the_page() {
+        let node = create_positioned_text_part(1, Position::new(10.0, 5.0));
+        assert_eq!(node.bounding_box(&PAGE_RECTS, 2.0), Some(Rect::from_positions(70.0, 130.0, 450.0, 470.0)));
+
+        let node = create_positioned_text_part(2, Position::new(10.0, 5.0));
+        assert_eq!(node.bounding_box(&PAGE_RECTS, 2.0), None);
+    }
+
+    #[test]
+    fn hit_test_uses_absolute_positions() {
+        let mut document = Node::new(NodeData::Document);
+        document.page_last = 1;
+        document.children.push(create_positioned_text_part(1, Position::new(10.0, 5.0)));
+
+        let mut hit_count = 0;
+
+        // The local position of the text part isn't hit...
+        assert!(!document.hit_test(Position::new(15.0, 10.0), &PAGE_RECTS, 2.0, &mut |_| hit_count += 1));
+        assert_eq!(hit_count, 0);
+
+        // ...but the position on the second page is.
+        assert!(document.hit_test(Position::new(80.0, 460.0), &PAGE_RECTS, 2.0, &mut |_| hit_count += 1));
+        assert_eq!(hit_count, 2);
+    }
+
+    #[test]
+    fn nearest_grapheme_boundary() {
+        let node = create_positioned_text_part(0, Position::new(0.0, 0.0));
+        let NodeData::TextPart(part) = &node.data else {
+            unreachable!();
+        };
+
+        assert_eq!(part.nearest_grapheme_boundary(-5.0), 0);
+        assert_eq!(part.nearest_grapheme_boundary(3.0), 0);
+        assert_eq!(part.nearest_grapheme_boundary(4.0), 1);
+        assert_eq!(part.nearest_grapheme_boundary(20.0), 3);
+        assert_eq!(part.nearest_grapheme_boundary(100.0), 4);
+    }
+
+    #[test]
+    fn grapheme_boundaries_of_right_to_left_text_are_mirrored() {
+        let mut node = create_positioned_text_part(0, Position::new(0.0, 0.0));
+        let NodeData::TextPart(part) = &mut node.data else {
+            unreachable!();
+        };
+        part.bidi_level = 1;
+
+        assert_eq!(part.visual_grapheme_boundary(0), 30.0);
+        assert_eq!(part.visual_grapheme_boundary(1), 22.5);
+        assert_eq!(part.visual_grapheme_boundary(4), 0.0);
+
+        assert_eq!(part.nearest_grapheme_boundary(28.0), 0);
+        assert_eq!(part.nearest_grapheme_boundary(20.0), 1);
+        assert_eq!(part.nearest_grapheme_boundary(-5.0), 4);
+    }
+
+    #[test]
+    fn nearest_text_position_prefers_the_same_line() {
+        let mut document = Node::new(NodeData::Document);
+        document.page_last = 1;
+        document.children.push(create_positioned_text_part(1, Position::new(10.0, 5.0)));
+        document.children.push(create_positioned_text_part(1, Position::new(50.0, 5.0)));
+        document.children.push(create_positioned_text_part(1, Position::new(10.0, 20.0)));
+
+        // Inside the second text part (at 2x zoom), between its second and
+        // third grapheme.
+        assert_eq!(document.nearest_text_position(Position::new(180.0, 460.0), &PAGE_RECTS, 2.0),
+            Some(selection::TextPosition { text_part: 1, offset: 2 }));
+
+        // In the left margin next to the third text part.
+        assert_eq!(document.nearest_text_position(Position::new(55.0, 485.0), &PAGE_RECTS, 2.0),
+            Some(selection::TextPosition { text_part: 2, offset: 0 }));
+
+        // The first page doesn't contain any text.
+        assert_eq!(document.nearest_text_position(Position::new(80.0, 40.0), &PAGE_RECTS, 2.0), None);
+    }
+
+    #[test]
+    fn links_are_followed_with_ctrl_click() {
+        let hyperlink = Hyperlink { relationship: None, anchor: Some(String::from("Intro")) };
+
+        let mut event = Event::Hover(MouseEvent::new(Position::new(0.0, 0.0)));
+        hyperlink.on_event(&mut event);
+        let Event::Hover(mouse_event) = event else {
+            unreachable!();
+        };
+        assert_eq!(mouse_event.new_cursor, Some(CursorIcon::Hand));
+        assert_eq!(mouse_event.hover_text.as_deref(), Some("#Intro\nCtrl+Click to follow link"));
+
+        let click = |follows_links| {
+            let mut mouse_event = MouseEvent::new(Position::new(0.0, 0.0));
+            mouse_event.follows_links = follows_links;
+
+            let mut event = Event::Click(mouse_event);
+            hyperlink.on_event(&mut event);
+            match event {
+                Event::Click(mouse_event) => mouse_event.bookmark,
+                Event::Hover(..) => unreachable!(),
+            }
+        };
+        assert_eq!(click(false), None);
+        assert_eq!(click(true).as_deref(), Some("Intro"));
+    }
+
+    #[test]
+    fn debug_string() {
+        let mut document = create_tree();
+        document.nth_child_mut(1).position = Position::new(10.0, 22.5);
+        document.nth_child_mut(1).nth_child_mut(0).size = Size::new(30.0, 11.0);
+        document.nth_child_mut(1).page_last = 1;
+
+        assert_eq!(document.to_debug_string(), concat!(
+            "Document @ (0, 0) sized (0x0) pages 0..=0\n",
+            "    Paragraph @ (0, 0) sized (0x0) pages 0..=0\n",
+            "        TextRun @ (0, 0) sized (0x0) pages 0..=0\n",
+            "            TextPart \"Hello\" @ (0, 0) sized (0x0) pages 0..=0\n",
+            "            TextPart \"world\" @ (0, 0) sized (0x0) pages 0..=0\n",
+            "    Paragraph @ (10, 22.5) sized (0x0) pages 0..=1\n",
+            "        TextPart \"Bye\" @ (0, 0) sized (30x11) pages 0..=0\n",
+        ));
+    }
+
+    #[test]
+    fn find_all_returns_nodes_in_document_order() {
+        let document = create_tree();
+
+        let text_parts = document.find_all(|node| matches!(node.data, NodeData::TextPart(..)));
+        assert_eq!(text_parts.iter().map(|node| text_of(node)).collect::<Vec<_>>(), vec!["Hello", "world", "Bye"]);
+
+        assert_eq!(document.find_all(|node| matches!(node.data, NodeData::Paragraph(..))).len(), 2);
+        assert!(document.find_all(|node| matches!(node.data, NodeData::Table { .. })).is_empty());
+    }
+
+    #[test]
+    fn find_first_returns_the_first_match() {
+        let document = create_tree();
+
+        assert!(document.find_first(|node| node.data.is_document()).unwrap().data.is_document());
+
+        let text_part = document.find_first(|node| matches!(node.data, NodeData::TextPart(..))).unwrap();
+        assert_eq!(text_of(text_part), "Hello");
+
+        assert!(document.find_first(|node| matches!(node.data, NodeData::Break(..))).is_none());
+    }
+
+    #[test]
+    fn title_page_has_its_own_header() {
+        let section = Section {
+            page_first: 2,
+            page_settings: Default::default(),
+            columns: Vec::new(),
+            column_separator: false,
+            title_page: true,
+            even_and_odd_headers: false,
+            headers: HeaderFooterSet {
+                default: Some(create_tree()),
+                first: None,
+                even: None,
+            },
+            footers: Default::default(),
+        };
+
+        assert!(section.header_for_page(2).is_none());
+        assert!(section.header_for_page(3).is_some());
+        assert!(section.footer_for_page(3).is_none());
+    }
+
+    #[test]
+    fn even_pages_have_their_own_header() {
+        let mut section = Section {
+            headers: HeaderFooterSet {
+                default: Some(create_tree()),
+                first: None,
+                even: None,
+            },
+            ..Default::default()
+        };
+
+        // Without the setting, the even header isn't used.
+        assert!(section.header_for_page(1).is_some());
+
+        section.even_and_odd_headers = true;
+        assert!(section.header_for_page(0).is_some());
+        assert!(section.header_for_page(1).is_none());
+        assert!(section.header_for_page(2).is_some());
+    }
+
+    #[test]
+    fn clicked_links_to_urls_are_opened_with_their_anchor() {
+        let hyperlink = Hyperlink {
+            relationship: Some(Rc::new(RefCell::new(Relationship {
+                id: Rc::from("rId1"),
+                relation_type: crate::relationships::RelationshipType::Hyperlink,
+                target: String::from("https://example.com/page"),
+                data: Vec::new(),
+            }))),
+            anchor: Some(String::from("section")),
+        };
+
+        let click = |follows_links| {
+            let mut mouse_event = MouseEvent::new(Position::new(0.0, 0.0));
+            mouse_event.follows_links = follows_links;
+
+            let mut event = Event::Click(mouse_event);
+            hyperlink.on_event(&mut event);
+            let Event::Click(mouse_event) = event else {
+                unreachable!();
+            };
+            mouse_event.url
+        };
+
+        assert_eq!(click(true).as_deref(), Some("https://example.com/page#section"));
+        assert_eq!(click(false), None);
+    }
+
+    #[test]
+    fn unknown_section_types_are_reported() {
+        let mut diagnostics = Vec::new();
+        assert_eq!(SectionType::from_string(Some("continuous"), &mut diagnostics), SectionType::Continuous);
+        assert_eq!(SectionType::from_string(None, &mut diagnostics), SectionType::NextPage);
+        assert!(diagnostics.is_empty());
+
+        assert_eq!(SectionType::from_string(Some("everyPage"), &mut diagnostics), SectionType::NextPage);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].source, "w:type");
+    }
+}
@@ -0,0 +1,1011 @@
+// Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{
+    collections::HashMap,
+    rc::Rc,
+    cell::RefCell
+};
+
+use roxmltree as xml;
+use crate::HalfPoint;
+
+use crate::{
+    WORD_PROCESSING_XML_NAMESPACE,
+    error::Error,
+    text_settings::TextSettings,
+    unicode::{alphabet::{self, Alphabet, LetterCase}, digits::DigitForm, primary_language_subtag},
+    wp::diagnostics::Diagnostic,
+};
+
+#[derive(Clone, Debug)]
+#[allow(dead_code)]
+pub enum NumberingFormat {
+    Aiueo,
+    AiueoFullWidth,
+    ArabicAbjad,
+    ArabicAlpha,
+    BahtText,
+    Bullet,
+    CardinalText,
+    Chicago,
+    ChineseCounting,
+    ChineseCountingThousand,
+    ChineseLegalSimplified,
+    Chosung,
+    Custom(String),
+
+    /// Specifies that the sequence shall consist of decimal numbering.
+    ///
+    /// To determine the text that is displayed for any value, this sequence
+    /// specifies a set of characters that represent positions 1–9 and then
+    /// those same characters are combined with each other and 0 (represents the
+    /// number zero) to construct the remaining values.
+    ///
+    /// The set of characters used by this numbering format for values 0–9 is
+    /// U+0030–U+0039, respectively.
+    ///
+    /// Continue the sequence by using the following steps:
+    ///     1.  Increment the rightmost position.
+    ///     2.  Every time the end of the set is reached, for a given position,
+    ///         increment the position to the immediate left (if there is no
+    ///         position to the immediate left, create a new position and start
+    ///         the sequence of the new position at 1) and reset the current
+    ///         position to 0.
+    Decimal,
+    DecimalEnclosedCircle,
+    DecimalEnclosedCircleChinese,
+    DecimalEnclosedFullstop,
+    DecimalEnclosedParen,
+    DecimalFullWidth,
+    DecimalHalfWidth,
+
+    /// Specifies that the sequence shall consist of decimal numbering, where
+    /// the values 1–9 are written with a leading zero, e.g. 01, 02, ..., 10.
+    DecimalZero,
+    DollarText,
+    Ganada,
+    Hebrew1,
+    Hebrew2,
+    Hex,
+    HindiConsonants,
+    HindiCounting,
+    HindiNumbers,
+    HindiVowels,
+    IdeographDigital,
+    IdeographEnclosedCircle,
+    IdeographLegalTraditional,
+    IdeographTraditional,
+    IdeographZodiac,
+    IdeographZodiacTraditional,
+    Iroha,
+    IrohaFullWidth,
+    JapaneseCounting,
+    JapaneseDigitalTenThousand,
+    JapaneseLegal,
+    KoreanCounting,
+    KoreanDigital,
+    KoreanDigital2,
+    KoreanLegal,
+    LowerLetter,
+
+    /// Specifies that the sequence shall consist of lowercase roman numerals.
+    ///
+    /// This system uses a set of characters to represent the numbers 1, 5, 10,
+    /// 50, 100, 500, and 1000 and then those are combined with each other to
+    /// construct the remaining values.
+    ///
+    /// The set of characters used by this numbering format is U+0069, U+0076,
+    /// U+0078, U+006C, U+0063, U+0064, U+006D, respectively.
+    ///
+    /// To construct a number that is outside the set, you work from largest
+    /// groups to smallest following these steps:
+    /// 1.  Create as many groups as possible that contain one thousand in each
+    ///     group.
+    ///     *   The symbol representing one thousand (the power of ten
+    ///         represented by that position): m is repeated for the number of
+    ///         groups formed.
+    ///         If no groups are formed, do not write any symbol.
+    /// 2.  Repeat this for groups of nine hundred (cm), five-hundred (d),
+    ///     four-hundred (cd), one-hundred (c), ninety (xc), fifty (l), forty
+    ///     (xl), ten (x), nine (ix), five (v), four (iv) and finally one (i)
+    ///     using the corresponding symbol to indicate the groups (so
+    ///     four-hundred fifty would be cdl and forty-five would be xlv).
+    LowerRoman,
+
+    None,
+    NumberInDash,
+
+    /// Specifies that the sequence shall consist of ordinal numbers in the
+    /// language of the lang element, e.g. 1st, 2nd, 3rd in English.
+    Ordinal,
+    OrdinalText,
+    RussianLower,
+    RussianUpper,
+    TaiwaneseCounting,
+    TaiwaneseCountingThousand,
+    TaiwaneseDigital,
+    ThaiCounting,
+    ThaiLetters,
+    ThaiNumbers,
+
+    /// Specifies that the sequence shall consist of one or more occurrences of
+    /// a single letter of the Latin alphabet in upper case, from the set listed
+    /// below.
+    ///
+    /// This system uses a set of characters to represent the numbers 1 to the
+    /// length of the language of the alphabet and then those same characters
+    /// are combined to construct the remaining values.
+    ///
+    /// The characters used by this numbering format is determined by using the
+    /// language of the lang element (§17.3.2.20). Specifically:
+    /// *   When the script in use is derived from the Latin alphabet (A–Z),
+    ///     that alphabet is used.
+    ///     [Example: For Norwegian (Nyorsk), the following Unicode characters
+    ///     are used by this numbering format: U+0041–U+005A, U+00C6, U+00D8,
+    ///     U+00C5. end example]
+    /// *   When the language in use is based on any other system, the
+    ///     characters U+0041–U+005A are used.
+    ///
+    /// For values greater than the size of the set, the number is constructed
+    /// by following these steps:
+    /// 1.  Repeatedly subtract the size of the set from the value until the
+    ///     result is equal to or less than the size of the set.
+    /// 2.  The result value determines which character to use, and the same
+    ///     character is written once and then repeated for each time the size
+    ///     of the set was subtracted from the original value.
+    UpperLetter,
+    UpperRoman,
+    VietnameseCounting,
+}
+
+impl NumberingFormat {
+
+    pub fn parse(value: &str) -> Option<NumberingFormat> {
+        match value {
+            "aiueo" => Some(NumberingFormat::Aiueo),
+            "aiueoFullWidth" => Some(NumberingFormat::AiueoFullWidth),
+            "arabicAbjad" => Some(NumberingFormat::ArabicAbjad),
+            "arabicAlpha" => Some(NumberingFormat::ArabicAlpha),
+            "bahtText" => Some(NumberingFormat::BahtText),
+            "bullet" => Some(NumberingFormat::Bullet),
+            "cardinalText" => Some(NumberingFormat::CardinalText),
+            "chicago" => Some(NumberingFormat::Chicago),
+            "chineseCounting" => Some(NumberingFormat::ChineseCounting),
+            "chineseCountingThousand" => Some(NumberingFormat::ChineseCountingThousand),
+            "chineseLegalSimplified" => Some(NumberingFormat::ChineseLegalSimplified),
+            "chosung" => Some(NumberingFormat::Chosung),
+            "decimal" => Some(NumberingFormat::Decimal),
+            "decimalEnclosedCircle" => Some(NumberingFormat::DecimalEnclosedCircle),
+            "decimalEnclosedCircleChinese" => Some(NumberingFormat::DecimalEnclosedCircleChinese),
+            "decimalEnclosedFullstop" => Some(NumberingFormat::DecimalEnclosedFullstop),
+            "decimalEnclosedParen" => Some(NumberingFormat::DecimalEnclosedParen),
+            "decimalFullWidth" => Some(NumberingFormat::DecimalFullWidth),
+            "decimalHalfWidth" => Some(NumberingFormat::DecimalHalfWidth),
+            "decimalZero" => Some(NumberingFormat::DecimalZero),
+            "dollarText" => Some(NumberingFormat::DollarText),
+            "ganada" => Some(NumberingFormat::Ganada),
+            "hebrew1" => Some(NumberingFormat::Hebrew1),
+            "hebrew2" => Some(NumberingFormat::Hebrew2),
+            "hex" => Some(NumberingFormat::Hex),
+            "hindiConsonants" => Some(NumberingFormat::HindiConsonants),
+            "hindiCounting" => Some(NumberingFormat::HindiCounting),
+            "hindiNumbers" => Some(NumberingFormat::HindiNumbers),
+            "hindiVowels" => Some(NumberingFormat::HindiVowels),
+            "ideographDigital" => Some(NumberingFormat::IdeographDigital),
+            "ideographEnclosedCircle" => Some(NumberingFormat::IdeographEnclosedCircle),
+            "ideographLegalTraditional" => Some(NumberingFormat::IdeographLegalTraditional),
+            "ideographTraditional" => Some(NumberingFormat::IdeographTraditional),
+            "ideographZodiac" => Some(NumberingFormat::IdeographZodiac),
+            "ideographZodiacTraditional" => Some(NumberingFormat::IdeographZodiacTraditional),
+            "iroha" => Some(NumberingFormat::Iroha),
+            "irohaFullWidth" => Some(NumberingFormat::IrohaFullWidth),
+            "japaneseCounting" => Some(NumberingFormat::JapaneseCounting),
+            "japaneseDigitalTenThousand" => Some(NumberingFormat::JapaneseDigitalTenThousand),
+            "japaneseLegal" => Some(NumberingFormat::JapaneseLegal),
+            "koreanCounting" => Some(NumberingFormat::KoreanCounting),
+            "koreanDigital" => Some(NumberingFormat::KoreanDigital),
+            "koreanDigital2" => Some(NumberingFormat::KoreanDigital2),
+            "koreanLegal" => Some(NumberingFormat::KoreanLegal),
+            "lowerLetter" => Some(NumberingFormat::LowerLetter),
+            "lowerRoman" => Some(NumberingFormat::LowerRoman),
+            "none" => Some(NumberingFormat::None),
+            "numberInDash" => Some(NumberingFormat::NumberInDash),
+            "ordinal" => Some(NumberingFormat::Ordinal),
+            "ordinalText" => Some(NumberingFormat::OrdinalText),
+            "russianLower" => Some(NumberingFormat::RussianLower),
+            "russianUpper" => Some(NumberingFormat::RussianUpper),
+            "taiwaneseCounting" => Some(NumberingFormat::TaiwaneseCounting),
+            "taiwaneseCountingThousand" => Some(NumberingFormat::TaiwaneseCountingThousand),
+            "taiwaneseDigital" => Some(NumberingFormat::TaiwaneseDigital),
+            "thaiCounting" => Some(NumberingFormat::ThaiCounting),
+            "thaiLetters" => Some(NumberingFormat::ThaiLetters),
+            "thaiNumbers" => Some(NumberingFormat::ThaiNumbers),
+            "upperLetter" => Some(NumberingFormat::UpperLetter),
+            "upperRoman" => Some(NumberingFormat::UpperRoman),
+            "vietnameseCounting" => Some(NumberingFormat::VietnameseCounting),
+            _ => None
+        }
+    }
+
+}
+
+/// Format the value as one or more occurrences of a single letter of the
+/// alphabet, e.g. A, B, ..., Z, AA, BB, etc. for the Latin alphabet. Values
+/// that can't be written with letters are formatted as decimal numbers.
+pub fn format_letter(alphabet: &dyn Alphabet, value: i32, case: LetterCase) -> String {
+    if value <= 0 || alphabet.is_empty() {
+        return value.to_string();
+    }
+
+    let index = (value as usize - 1) % alphabet.len();
+    let repetitions = (value as usize - 1) / alphabet.len() + 1;
+
+    alphabet.nth_in_case(index, case).to_string().repeat(repetitions)
+}
+
+/// Format the value using the sample of a custom numbering format (the
+/// `w:format` of <w:numFmt w:val="custom">), which lists the first values,
+/// e.g. `001, 002, 003, ...` or `α, β, γ, ...`. Returns [None] when the sample
+/// isn't understood.
+pub fn format_custom(sample: &str, value: i32, language: Option<&str>) -> Option<String> {
+    let first = sample.split(',').next()?.trim();
+
+    // Decimal numbers padded with zeros to the width of the sample.
+    if !first.is_empty() && first.bytes().all(|byte| byte.is_ascii_digit()) {
+        let number = format!("{:0width$}", value, width = first.len());
+        return Some(DigitForm::for_language(language).apply(&number));
+    }
+
+    let mut characters = first.chars();
+    let (Some(letter), None) = (characters.next(), characters.next()) else {
+        return None;
+    };
+
+    let alphabet = alphabet::alphabet_starting_with(letter, language)?;
+    let case = if letter.is_uppercase() { LetterCase::Upper } else { LetterCase::Lower };
+    Some(format_letter(alphabet, value, case))
+}
+
+/// Format the value as a roman numeral, e.g. XLII for 42. Values that can't
+/// be written as a roman numeral are formatted as decimal numbers.
+pub fn format_roman(value: i32, case: LetterCase) -> String {
+    const NUMERALS: [(i32, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"),
+        (100, "c"), (90, "xc"), (50, "l"), (40, "xl"),
+        (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+
+    if value <= 0 {
+        return value.to_string();
+    }
+
+    let mut remainder = value;
+    let mut result = String::new();
+    for (numeral_value, numeral) in NUMERALS {
+        while remainder >= numeral_value {
+            result += numeral;
+            remainder -= numeral_value;
+        }
+    }
+
+    match case {
+        LetterCase::Lower => result,
+        LetterCase::Upper => result.to_uppercase(),
+    }
+}
+
+/// Format the value as an ordinal number in the given language, e.g. 1st, 2nd
+/// or 11th in English, 1e in Dutch and 1. in German. Languages without rules
+/// of their own use the English suffixes.
+pub fn format_ordinal(value: i32, language: Option<&str>) -> String {
+    match primary_language_subtag(language).as_deref() {
+        Some("nl") => format!("{}e", value),
+        Some("fr") if value == 1 => String::from("1er"),
+        Some("fr") => format!("{}e", value),
+        Some("es" | "it" | "pt") => format!("{}º", value),
+        Some("cs" | "da" | "de" | "fi" | "hu" | "nb" | "nn" | "no" | "pl" | "sk") => format!("{}.", value),
+        _ => {
+            let suffix = match (value % 10, value % 100) {
+                (_, 11..=13) => "th",
+                (1, _) => "st",
+                (2, _) => "nd",
+                (3, _) => "rd",
+                _ => "th",
+            };
+            format!("{}{}", value, suffix)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NumberingLevelDefinition {
+    display_all_levels_using_arabic_numerals: bool,
+    format: NumberingFormat,
+
+    /// The level (<w:lvlRestart>), counted from 1, after which this level
+    /// restarts. When [None], the level restarts after any higher level, and
+    /// when 0, it is never restarted.
+    restart_after_level: Option<i32>,
+    starting_value: i32,
+    text: String,
+    pub text_settings: TextSettings,
+
+    pub current_value: Option<i32>,
+}
+
+impl NumberingLevelDefinition {
+    pub fn load_xml(node: &xml::Node, diagnostics: &mut Vec<Diagnostic>) -> Result<Self, Error> {
+        let mut definition = Self {
+            display_all_levels_using_arabic_numerals: false,
+            format: NumberingFormat::Decimal,
+            restart_after_level: None,
+            starting_value: 0,
+            text: String::new(),
+            text_settings: TextSettings::new(),
+            current_value: None,
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+
+                // 17.9.4 isLgl (Display All Levels Using Arabic Numerals)
+                //
+                // This element specifies whether or not all levels displayed
+                // for a given numbering level's text shall be displayed using
+                // the decimal number format, regardless of the actual number
+                // format of that level in the list.
+                //
+                // Note: This numbering style is often referred to as the legal
+                //       numbering style.
+                "isLgl" => {
+                    definition.display_all_levels_using_arabic_numerals = true;
+                }
+
+                // 17.9.7 lvlJc (Justification)
+                //
+                // This element specifies the type of justification used on a
+                // numbering level's text within a given numbering level.
+                "lvlJc" => {
+                    // TODO
+                }
+
+                // 17.9.10 lvlRestart (Restart Numbering Level Symbol)
+                //
+                // This element specifies a one-based index which determines
+                // when a numbering level should restart to its start value. A
+                // numbering level restarts when an instance of the specified
+                // numbering level, which shall be higher (earlier than this
+                // level) is used in the given document's contents.
+                "lvlRestart" => {
+                    definition.restart_after_level = Some(child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"))
+                        .ok_or(Error::MissingAttribute { element: "w:lvlRestart", attribute: "w:val" })?.parse()?);
+                }
+
+                // 17.9.11 lvlText (Numbering Level Text)
+                //
+                // This element specifies the textual content which shall be
+                // displayed when displaying a paragraph with the given
+                // numbering level.
+                //
+                // The text contains placeholders %1 to %9, which are replaced
+                // by the current value of the first to ninth level.
+                "lvlText" => {
+                    definition.text = String::from(
+                        child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"))
+                                .ok_or(Error::MissingAttribute { element: "w:lvlText", attribute: "w:val" })?
+                    );
+                }
+
+                // 17.9.17 numFmt (Numbering Format)
+                "numFmt" => {
+                    let val = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"))
+                        .ok_or(Error::MissingAttribute { element: "w:numFmt", attribute: "w:val" })?;
+
+                    if val == "custom" {
+                        definition.format = NumberingFormat::Custom(String::from(
+                            child.attribute((WORD_PROCESSING_XML_NAMESPACE, "format"))
+                                .ok_or(Error::MissingAttribute { element: "w:numFmt", attribute: "w:format" })?
+                        ));
+                    } else {
+                        definition.format = NumberingFormat::parse(val)
+                            .ok_or_else(|| Error::InvalidValue { element: "w:numFmt", value: String::from(val) })?;
+                    }
+                }
+
+                "pPr" => definition.parse_number_level_associated_paragraph_properties(&child),
+
+                // 17.9.24 rPr (Numbering Symbol Run Properties)
+                "rPr" => definition.parse_number_level_run_properties(&child, diagnostics),
+
+                // 17.9.25 start (Starting Value)
+                "start" => {
+                    definition.starting_value = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"))
+                        .ok_or(Error::MissingAttribute { element: "w:start", attribute: "w:val" })?.parse()?;
+                }
+
+                _ => ()
+            }
+        }
+
+        Ok(definition)
+    }
+
+    /// Format the value using the numbering format of this level. The
+    /// language is the `<w:lang>` of the paragraph, which decides the
+    /// alphabet of the letter formats and the digits of the decimal formats.
+    pub fn format(&self, value: i32, language: Option<&str>) -> String {
+        match &self.format {
+            NumberingFormat::ArabicAbjad => format_letter(&alphabet::ArabicAbjad, value, LetterCase::Lower),
+            NumberingFormat::ArabicAlpha => format_letter(&alphabet::ArabicAlpha, value, LetterCase::Lower),
+            NumberingFormat::Bullet => self.text.clone(),
+            NumberingFormat::Custom(sample) => format_custom(sample, value, language).unwrap_or_else(|| {
+                println!("[Numbering] Unsupported custom numbering format: {:?}", sample);
+                DigitForm::for_language(language).format(value)
+            }),
+            NumberingFormat::Decimal => DigitForm::for_language(language).format(value),
+            NumberingFormat::DecimalFullWidth => DigitForm::FullWidth.format(value),
+            NumberingFormat::DecimalHalfWidth => DigitForm::Western.format(value),
+            NumberingFormat::DecimalZero => DigitForm::for_language(language).apply(&format!("{:02}", value)),
+            NumberingFormat::Hebrew2 => format_letter(&alphabet::Hebrew, value, LetterCase::Lower),
+            NumberingFormat::HindiNumbers => DigitForm::Devanagari.format(value),
+            NumberingFormat::LowerLetter => format_letter(alphabet::latin_alphabet_for_language(language), value, LetterCase::Lower),
+            NumberingFormat::LowerRoman => format_roman(value, LetterCase::Lower),
+            NumberingFormat::None => String::new(),
+            NumberingFormat::Ordinal => format_ordinal(value, language),
+            NumberingFormat::RussianLower => format_letter(&alphabet::Russian, value, LetterCase::Lower),
+            NumberingFormat::RussianUpper => format_letter(&alphabet::Russian, value, LetterCase::Upper),
+            NumberingFormat::ThaiLetters => format_letter(&alphabet::Thai, value, LetterCase::Lower),
+            NumberingFormat::ThaiNumbers => DigitForm::Thai.format(value),
+            NumberingFormat::UpperLetter => format_letter(alphabet::latin_alphabet_for_language(language), value, LetterCase::Upper),
+            NumberingFormat::UpperRoman => format_roman(value, LetterCase::Upper),
+            _ => {
+                println!("[Numbering] Unsupported numbering format: {:?}", self.format);
+                if !self.text.is_empty() {
+                    return self.text.clone();
+                }
+
+                String::from("UNSUPPORTED FORMAT")
+            }
+        }
+    }
+
+    /// Format the text of a paragraph at the level with the given index, by
+    /// replacing the placeholders of the level text with the current values
+    /// of the `levels` they refer to, e.g. `%1.%2.` becomes `2.3.`.
+    pub fn format_text(&self, index: i32, levels: &HashMap<i32, Rc<RefCell<NumberingLevelDefinition>>>,
+                       language: Option<&str>) -> String {
+        // Without a level text, only the number of this level is shown.
+        let text = match (&self.format, self.text.is_empty()) {
+            (NumberingFormat::Bullet, _) | (_, false) => self.text.clone(),
+            (_, true) => format!("%{}.", index + 1),
+        };
+
+        let mut result = String::new();
+        let mut characters = text.chars().peekable();
+        while let Some(character) = characters.next() {
+            let placeholder = characters.peek()
+                .and_then(|digit| digit.to_digit(10))
+                .filter(|digit| character == '%' && (1..=9).contains(digit));
+
+            let Some(placeholder) = placeholder else {
+                result.push(character);
+                continue;
+            };
+
+            characters.next();
+            if let Some(level) = levels.get(&(placeholder as i32 - 1)) {
+                let level = level.borrow();
+                if self.display_all_levels_using_arabic_numerals {
+                    result += &level.current_value().to_string();
+                } else {
+                    result += &level.format(level.current_value(), language);
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether this level, with the given index, restarts when the level
+    /// with the `used_index` is used.
+    fn restarts_after(&self, index: i32, used_index: i32) -> bool {
+        match self.restart_after_level {
+            None => used_index < index,
+            Some(level) => used_index < index && used_index < level,
+        }
+    }
+
+    pub fn current_value(&self) -> i32 {
+        self.current_value.unwrap_or(self.starting_value)
+    }
+
+    pub fn next_value(&mut self) -> i32 {
+        match self.current_value {
+            Some(value) => {
+                self.current_value = Some(value + 1);
+                value + 1
+            }
+            None => {
+                self.current_value = Some(self.starting_value);
+                self.starting_value
+            }
+        }
+    }
+
+    /// Parse the run properties of the numbering text, of which the font is
+    /// the most important, since bullets are usually in a symbol font, such
+    /// as Symbol or Wingdings.
+    fn parse_number_level_run_properties(&mut self, node: &xml::Node, diagnostics: &mut Vec<Diagnostic>) {
+        for child in node.children() {
+            let value = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"));
+            match child.tag_name().name() {
+                "b" => self.text_settings.bold = Some(!matches!(value, Some("0" | "off" | "false"))),
+                "i" => self.text_settings.italic = Some(!matches!(value, Some("0" | "off" | "false"))),
+
+                "rFonts" => {
+                    let font = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "ascii"))
+                        .or_else(|| child.attribute((WORD_PROCESSING_XML_NAMESPACE, "hAnsi")));
+                    if let Some(font) = font {
+                        self.text_settings.font = Some(Rc::from(font));
+                    }
+                }
+
+                "sz" => {
+                    if let Some(size) = crate::word_processing::parse_val_attribute(&child, "w:sz", diagnostics) {
+                        self.text_settings.non_complex_text_size = Some(HalfPoint(size));
+                    }
+                }
+
+                _ => ()
+            }
+        }
+    }
+
+    fn parse_number_level_associated_paragraph_properties(&mut self, node: &xml::Node) {
+        for child in node.children() {
+            match child.tag_name().name() {
+                "ind" => self.text_settings.parse_element_ind(node),
+
+                _ => ()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AbstractNumberingDefinition {
+    pub levels: HashMap<i32, Rc<RefCell<NumberingLevelDefinition>>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NumberingDefinitionInstance {
+    /// The `w:numId` by which paragraphs refer to the instance.
+    pub id: i32,
+
+    pub abstract_numbering_definition: Option<Rc<RefCell<AbstractNumberingDefinition>>>,
+
+    /// The values the levels start at when the instance is first used
+    /// (<w:lvlOverride><w:startOverride>), by the index of the level.
+    pub start_overrides: HashMap<i32, i32>,
+
+    /// Whether the start overrides are applied to the levels.
+    pub is_started: bool,
+}
+
+impl NumberingDefinitionInstance {
+    /// Advances the numbering of the level with the given index, restarting
+    /// the levels that restart after it, and formats the text of the paragraph
+    /// at that level. Returns [None] when the level isn't defined.
+    pub fn next_text(&mut self, index: i32, language: Option<&str>) -> Option<String> {
+        let abstract_definition = self.abstract_numbering_definition.clone()?;
+        let abstract_definition = abstract_definition.borrow();
+        let level = abstract_definition.levels.get(&index)?;
+
+        // The overrides restart the levels of the abstract numbering
+        // definition, which is shared by the instances that continue its
+        // numbering.
+        if !self.is_started {
+            self.is_started = true;
+            for (overridden_index, value) in &self.start_overrides {
+                if let Some(level) = abstract_definition.levels.get(overridden_index) {
+                    level.borrow_mut().current_value = Some(value - 1);
+                }
+            }
+        }
+
+        level.borrow_mut().next_value();
+
+        for (other_index, other_level) in &abstract_definition.levels {
+            let mut other_level = other_level.borrow_mut();
+            if other_level.restarts_after(*other_index, index) {
+                other_level.current_value = None;
+            }
+        }
+
+        let level = level.borrow();
+        Some(level.format_text(index, &abstract_definition.levels, language))
+    }
+}
+
+/// Cloning the manager shares the definitions, including the current values
+/// of the levels, see [NumberingManager::reset_values].
+#[derive(Clone, Debug)]
+pub struct NumberingManager {
+    pub abstract_numbering_definitions: HashMap<i32, Rc<RefCell<AbstractNumberingDefinition>>>,
+    pub numbering_definition_instances: HashMap<i32, Rc<RefCell<NumberingDefinitionInstance>>>,
+
+    pub values: Vec<i32>,
+
+    /// The problems encountered while loading the definitions.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl NumberingManager {
+    /// Restart the numbering of every level, so the definitions can be used
+    /// to process a document again.
+    pub fn reset_values(&mut self) {
+        for definition in self.abstract_numbering_definitions.values() {
+            for level in definition.borrow().levels.values() {
+                level.borrow_mut().current_value = None;
+            }
+        }
+
+        for instance in self.numbering_definition_instances.values() {
+            instance.borrow_mut().is_started = false;
+        }
+
+        self.values.clear();
+    }
+
+    pub fn new() -> Self {
+        Self {
+            abstract_numbering_definitions: HashMap::new(),
+            numbering_definition_instances: HashMap::new(),
+
+            values: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn from_xml(doc: &xml::Document) -> Result<Self, Error> {
+        let mut manager = Self {
+            abstract_numbering_definitions: HashMap::new(),
+            numbering_definition_instances: HashMap::new(),
+            values: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+
+        for node in doc.root_element().children() {
+            match node.tag_name().name() {
+                // 17.9.1 abstractNum (Abstract Numbering Definition)
+                "abstractNum" => manager.parse_abstract_numbering_definition(&node)?,
+
+                // 17.9.15 num (Numbering Definition Instance)
+                "num" => manager.parse_numbering_definition_instance(&node)?,
+
+                _ => ()
+            }
+        }
+
+        Ok(manager)
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn find_definition_instance(&self, id: i32) -> Option<Rc<RefCell<NumberingDefinitionInstance>>> {
+        self.numbering_definition_instances.get(&id).cloned()
+    }
+
+    fn parse_abstract_numbering_definition(&mut self, node: &xml::Node) -> Result<(), Error> {
+        let abstract_num_id: i32 = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "abstractNumId"))
+                .ok_or(Error::MissingAttribute { element: "w:abstractNum", attribute: "w:abstractNumId" })?.parse()?;
+
+        if self.abstract_numbering_definitions.contains_key(&abstract_num_id) {
+            return Err(Error::InvalidValue { element: "w:abstractNum", value: format!("duplicate abstractNumId {}", abstract_num_id) });
+        }
+
+        let mut definition = AbstractNumberingDefinition{
+            levels: HashMap::new()
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+
+                // 17.9.6 lvl (Numbering Level Definition)
+                "lvl" => {
+                    let id: i32 = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "ilvl"))
+                        .ok_or(Error::MissingAttribute { element: "w:lvl", attribute: "w:ilvl" })?.parse()?;
+
+                    let level = NumberingLevelDefinition::load_xml(&child, &mut self.diagnostics)?;
+                    definition.levels.insert(id, Rc::new(RefCell::new(level)));
+                }
+
+                _ => ()
+            }
+        }
+
+        self.abstract_numbering_definitions.insert(abstract_num_id, Rc::new(RefCell::new(definition)));
+        Ok(())
+    }
+
+    fn parse_numbering_definition_instance(&mut self, node: &xml::Node) -> Result<(), Error> {
+        let id: i32 = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "numId"))
+                .ok_or(Error::MissingAttribute { element: "w:num", attribute: "w:numId" })?.parse()?;
+
+        if self.numbering_definition_instances.contains_key(&id) {
+            return Err(Error::InvalidValue { element: "w:num", value: format!("duplicate numId {}", id) });
+        }
+
+        let mut instance = NumberingDefinitionInstance{
+            id,
+            abstract_numbering_definition: None,
+            start_overrides: HashMap::new(),
+            is_started: false,
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                // 17.9.2 abstractNumId (Abstract Numbering Definition Reference)
+                "abstractNumId" => {
+                    let abstract_num_id: i32 = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"))
+                        .ok_or(Error::MissingAttribute { element: "w:abstractNumId", attribute: "w:val" })?.parse()?;
+
+                    instance.abstract_numbering_definition = Some(
+                        self.abstract_numbering_definitions.get(&abstract_num_id)
+                            .ok_or_else(|| Error::InvalidValue { element: "w:abstractNumId", value: abstract_num_id.to_string() })?
+                            .clone()
+                    );
+                }
+
+                // 17.9.8 lvlOverride (Numbering Level Definition Override)
+                "lvlOverride" => {
+                    let level: i32 = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "ilvl"))
+                        .ok_or(Error::MissingAttribute { element: "w:lvlOverride", attribute: "w:ilvl" })?.parse()?;
+
+                    // 17.9.26 startOverride (Numbering Level Starting Value Override)
+                    for start_override in child.children().filter(|node| node.tag_name().name() == "startOverride") {
+                        let value: i32 = start_override.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"))
+                            .ok_or(Error::MissingAttribute { element: "w:startOverride", attribute: "w:val" })?.parse()?;
+                        instance.start_overrides.insert(level, value);
+                    }
+                }
+
+                _ => ()
+            }
+        }
+
+        self.numbering_definition_instances.insert(id, Rc::new(RefCell::new(instance)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level_with_format(format: NumberingFormat) -> NumberingLevelDefinition {
+        NumberingLevelDefinition {
+            display_all_levels_using_arabic_numerals: false,
+            format,
+            restart_after_level: None,
+            starting_value: 1,
+            text: String::new(),
+            text_settings: TextSettings::new(),
+            current_value: None,
+        }
+    }
+
+    #[test]
+    fn upper_letter_uses_alphabet_of_language() {
+        let level = level_with_format(NumberingFormat::UpperLetter);
+        assert_eq!(level.format(1, None), "A");
+        assert_eq!(level.format(26, Some("en-US")), "Z");
+        assert_eq!(level.format(27, Some("en-US")), "AA");
+        assert_eq!(level.format(27, Some("nb-NO")), "Æ");
+        assert_eq!(level.format(29, Some("nb-NO")), "Å");
+        assert_eq!(level.format(30, Some("nb-NO")), "AA");
+        assert_eq!(level.format(2, Some("ru-RU")), "B");
+    }
+
+    #[test]
+    fn russian_letters() {
+        assert_eq!(level_with_format(NumberingFormat::RussianUpper).format(1, None), "А");
+        assert_eq!(level_with_format(NumberingFormat::RussianLower).format(10, None), "к");
+        assert_eq!(level_with_format(NumberingFormat::RussianLower).format(29, None), "аа");
+    }
+
+    #[test]
+    fn roman_numerals() {
+        let lower = level_with_format(NumberingFormat::LowerRoman);
+        assert_eq!(lower.format(4, None), "iv");
+        assert_eq!(lower.format(14, None), "xiv");
+        assert_eq!(lower.format(49, None), "xlix");
+        assert_eq!(lower.format(450, None), "cdl");
+        assert_eq!(lower.format(1994, None), "mcmxciv");
+
+        let upper = level_with_format(NumberingFormat::UpperRoman);
+        assert_eq!(upper.format(45, None), "XLV");
+        assert_eq!(upper.format(3999, None), "MMMCMXCIX");
+        assert_eq!(upper.format(0, None), "0");
+    }
+
+    #[test]
+    fn letters_repeat_after_the_end_of_the_alphabet() {
+        let lower = level_with_format(NumberingFormat::LowerLetter);
+        assert_eq!(lower.format(1, None), "a");
+        assert_eq!(lower.format(28, None), "bb");
+        assert_eq!(lower.format(52, None), "zz");
+        assert_eq!(lower.format(53, None), "aaa");
+        assert_eq!(lower.format(0, None), "0");
+    }
+
+    #[test]
+    fn decimal_zero_pads_single_digits() {
+        let level = level_with_format(NumberingFormat::DecimalZero);
+        assert_eq!(level.format(1, None), "01");
+        assert_eq!(level.format(9, None), "09");
+        assert_eq!(level.format(10, None), "10");
+        assert_eq!(level.format(123, None), "123");
+    }
+
+    #[test]
+    fn ordinals() {
+        let level = level_with_format(NumberingFormat::Ordinal);
+        let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 101, 111].iter()
+            .map(|value| level.format(*value, Some("en-US")))
+            .collect();
+        assert_eq!(ordinals, ["1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "101st", "111th"]);
+
+        assert_eq!(level.format(3, Some("nl-NL")), "3e");
+        assert_eq!(level.format(3, Some("de-DE")), "3.");
+        assert_eq!(level.format(1, Some("fr-FR")), "1er");
+    }
+
+    #[test]
+    fn decimal_digits_of_language() {
+        let level = level_with_format(NumberingFormat::Decimal);
+        assert_eq!(level.format(12, Some("ar-EG")), "١٢");
+        assert_eq!(level.format(12, Some("he-IL")), "12");
+        assert_eq!(level_with_format(NumberingFormat::ThaiNumbers).format(3, None), "๓");
+        assert_eq!(level_with_format(NumberingFormat::DecimalFullWidth).format(10, None), "１０");
+    }
+
+    #[test]
+    fn letters_of_other_scripts() {
+        assert_eq!(level_with_format(NumberingFormat::Hebrew2).format(2, None), "ב");
+        assert_eq!(level_with_format(NumberingFormat::ArabicAbjad).format(3, None), "ج");
+        assert_eq!(level_with_format(NumberingFormat::ArabicAlpha).format(3, None), "ت");
+    }
+
+    #[test]
+    fn custom_formats_follow_their_sample() {
+        assert_eq!(format_custom("001, 002, 003, ...", 7, None).as_deref(), Some("007"));
+        assert_eq!(format_custom("α, β, γ, ...", 3, Some("el-GR")).as_deref(), Some("γ"));
+        assert_eq!(format_custom("Α, Β, Γ, ...", 25, None).as_deref(), Some("ΑΑ"));
+        assert_eq!(format_custom("а, б, в, ...", 5, Some("uk-UA")).as_deref(), Some("ґ"));
+        assert_eq!(format_custom("1st, 2nd, 3rd, ...", 1, None), None);
+    }
+
+    #[test]
+    fn reset_values_restarts_shared_definitions() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/></w:lvl></w:abstractNum>"#,
+            r#"<w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>"#,
+            r#"</w:numbering>"#,
+        )).unwrap();
+
+        let mut manager = NumberingManager::from_xml(&document).unwrap();
+        let level = manager.abstract_numbering_definitions[&0].borrow().levels[&0].clone();
+        assert_eq!(level.borrow_mut().next_value(), 1);
+        assert_eq!(level.borrow_mut().next_value(), 2);
+
+        manager.clone().reset_values();
+        assert_eq!(level.borrow_mut().next_value(), 1);
+
+        manager.reset_values();
+        assert_eq!(level.borrow().current_value(), 1);
+    }
+
+    fn numbering_texts(manager: &NumberingManager, paragraphs: &[(i32, i32)]) -> Vec<String> {
+        paragraphs.iter()
+            .map(|(instance, level)| manager.numbering_definition_instances[instance].borrow_mut().next_text(*level, None).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn level_text_shows_the_values_of_higher_levels() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:abstractNum w:abstractNumId="0">"#,
+            r#"<w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl>"#,
+            r#"<w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="lowerLetter"/><w:lvlText w:val="%1.%2"/></w:lvl>"#,
+            r#"<w:lvl w:ilvl="2"><w:start w:val="1"/><w:numFmt w:val="lowerRoman"/><w:isLgl/><w:lvlText w:val="%1.%2.%3"/></w:lvl>"#,
+            r#"</w:abstractNum>"#,
+            r#"<w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>"#,
+            r#"</w:numbering>"#,
+        )).unwrap();
+
+        let manager = NumberingManager::from_xml(&document).unwrap();
+        assert_eq!(
+            numbering_texts(&manager, &[(1, 0), (1, 1), (1, 1), (1, 0), (1, 1), (1, 2), (1, 2), (1, 1), (1, 2)]),
+            ["1.", "1.a", "1.b", "2.", "2.a", "2.1.1", "2.1.2", "2.b", "2.2.1"]
+        );
+    }
+
+    #[test]
+    fn restarts_and_start_overrides() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:abstractNum w:abstractNumId="0">"#,
+            r#"<w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl>"#,
+            r#"<w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%2)"/></w:lvl>"#,
+            r#"<w:lvl w:ilvl="2"><w:start w:val="1"/><w:lvlRestart w:val="0"/><w:numFmt w:val="decimal"/><w:lvlText w:val="(%3)"/></w:lvl>"#,
+            r#"</w:abstractNum>"#,
+            r#"<w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>"#,
+            r#"<w:num w:numId="2"><w:abstractNumId w:val="0"/><w:lvlOverride w:ilvl="0"><w:startOverride w:val="5"/></w:lvlOverride></w:num>"#,
+            r#"</w:numbering>"#,
+        )).unwrap();
+
+        let mut manager = NumberingManager::from_xml(&document).unwrap();
+
+        // The third level is never restarted, and the second instance
+        // restarts the first level at 5, after which the first instance
+        // continues its numbering.
+        let paragraphs = [(1, 0), (1, 2), (1, 1), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (1, 0)];
+        let texts = ["1.", "(1)", "1)", "2.", "1)", "(2)", "5.", "1)", "6."];
+        assert_eq!(numbering_texts(&manager, &paragraphs), texts);
+
+        manager.reset_values();
+        assert_eq!(numbering_texts(&manager, &paragraphs), texts);
+    }
+
+    #[test]
+    fn bullets_are_written_in_the_font_of_the_level() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="bullet"/><w:lvlText w:val="&#xF0B7;"/>"#,
+            r#"<w:rPr><w:rFonts w:ascii="Symbol" w:hAnsi="Symbol" w:hint="default"/><w:sz w:val="20"/></w:rPr></w:lvl></w:abstractNum>"#,
+            r#"<w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>"#,
+            r#"</w:numbering>"#,
+        )).unwrap();
+
+        let manager = NumberingManager::from_xml(&document).unwrap();
+        assert_eq!(numbering_texts(&manager, &[(1, 0), (1, 0)]), ["\u{F0B7}", "\u{F0B7}"]);
+
+        let level = manager.abstract_numbering_definitions[&0].borrow().levels[&0].clone();
+        let level = level.borrow();
+        assert_eq!(level.text_settings.font.as_deref(), Some("Symbol"));
+        assert_eq!(level.text_settings.non_complex_text_size_pts(), 10.0);
+    }
+
+    #[test]
+    fn invalid_sizes_of_the_level_text_are_reported() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/>"#,
+            r#"<w:rPr><w:sz w:val="10.5"/></w:rPr></w:lvl></w:abstractNum>"#,
+            r#"</w:numbering>"#,
+        )).unwrap();
+
+        let manager = NumberingManager::from_xml(&document).unwrap();
+        assert_eq!(manager.diagnostics().len(), 1);
+        assert_eq!(manager.diagnostics()[0].source, "w:sz");
+
+        let level = manager.abstract_numbering_definitions[&0].borrow().levels[&0].clone();
+        assert_eq!(level.borrow().text_settings.non_complex_text_size, None);
+    }
+
+    #[test]
+    fn references_to_missing_definitions_are_errors() {
+        let document = xml::Document::parse(concat!(
+            r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:num w:numId="1"><w:abstractNumId w:val="7"/></w:num>"#,
+            r#"</w:numbering>"#,
+        )).unwrap();
+
+        assert!(matches!(NumberingManager::from_xml(&document), Err(Error::InvalidValue { element: "w:abstractNumId", .. })));
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// 17.13.5 Revisions
+// The tracked changes of the runs of a paragraph, which are shown marked up
+// in the color of their author, or as the document was before or after the
+// changes.
+
+use std::{rc::Rc, str::FromStr};
+
+use chrono::NaiveDateTime;
+use roxmltree as xml;
+
+use crate::{gui::Color, WORD_PROCESSING_XML_NAMESPACE};
+
+use super::{instructions, Event};
+
+/// The colors of the authors of the tracked changes, which are assigned in
+/// the order the authors appear in the document.
+const AUTHOR_COLORS: [Color; 6] = [
+    Color::from_rgb(0xC0, 0x00, 0x00),
+    Color::from_rgb(0x00, 0x5A, 0xB5),
+    Color::from_rgb(0x00, 0x80, 0x3C),
+    Color::from_rgb(0x8E, 0x24, 0xAA),
+    Color::from_rgb(0xD8, 0x6A, 0x00),
+    Color::from_rgb(0x00, 0x83, 0x8F),
+];
+
+/// Which text of the tracked changes is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RevisionView {
+    /// The inserted and the deleted text, marked up in the color of their
+    /// author.
+    #[default]
+    Markup,
+
+    /// The document as it is with the changes: the deleted text is hidden.
+    Final,
+
+    /// The document as it was before the changes: the inserted text is
+    /// hidden.
+    Original,
+}
+
+impl RevisionView {
+    /// The view after this one, for switching between the views using a
+    /// single key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Markup => Self::Final,
+            Self::Final => Self::Original,
+            Self::Original => Self::Markup,
+        }
+    }
+
+    /// Describes the view to the user.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Markup => "All markup",
+            Self::Final => "Final",
+            Self::Original => "Original",
+        }
+    }
+
+    /// Whether the text of the revisions of the given kind is shown.
+    pub fn shows(self, kind: RevisionKind) -> bool {
+        match self {
+            Self::Markup => true,
+            Self::Final => kind.is_insertion(),
+            Self::Original => !kind.is_insertion(),
+        }
+    }
+}
+
+/// The kind of change, by the name of its element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevisionKind {
+    /// 17.13.5.18 ins (Inserted Run Content)
+    Insertion,
+
+    /// 17.13.5.14 del (Deleted Run Content)
+    Deletion,
+
+    /// 17.13.5.22 moveFrom (Move Source Run Content)
+    MoveFrom,
+
+    /// 17.13.5.25 moveTo (Move Destination Run Content)
+    MoveTo,
+}
+
+impl RevisionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Insertion => "ins",
+            Self::Deletion => "del",
+            Self::MoveFrom => "moveFrom",
+            Self::MoveTo => "moveTo",
+        }
+    }
+
+    /// Whether the content is new in the document, as opposed to removed from
+    /// it.
+    pub fn is_insertion(self) -> bool {
+        matches!(self, Self::Insertion | Self::MoveTo)
+    }
+}
+
+impl FromStr for RevisionKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ins" => Ok(Self::Insertion),
+            "del" => Ok(Self::Deletion),
+            "moveFrom" => Ok(Self::MoveFrom),
+            "moveTo" => Ok(Self::MoveTo),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A tracked change of the runs inside of it.
+#[derive(Debug)]
+pub struct Revision {
+    pub kind: RevisionKind,
+    pub id: String,
+    pub author: String,
+    pub date: Option<NaiveDateTime>,
+
+    /// The markup of the revision when its text isn't shown in the view the
+    /// document was laid out with, which is written back unchanged when
+    /// saving.
+    pub hidden_markup: Option<Rc<str>>,
+}
+
+impl Revision {
+    pub fn from_element(node: &xml::Node) -> Option<Self> {
+        Some(Self {
+            kind: node.tag_name().name().parse().ok()?,
+            id: String::from(node.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")).unwrap_or_default()),
+            author: String::from(node.attribute((WORD_PROCESSING_XML_NAMESPACE, "author")).unwrap_or_default()),
+            date: node.attribute((WORD_PROCESSING_XML_NAMESPACE, "date")).and_then(instructions::parse_date_time),
+            hidden_markup: None,
+        })
+    }
+
+    /// Describes the change for the user hovering over it, e.g. "Inserted by
+    /// Jane Doe, 05-03-2023 14:07".
+    pub fn description(&self) -> String {
+        let action = match self.kind {
+            RevisionKind::Insertion => "Inserted",
+            RevisionKind::Deletion => "Deleted",
+            RevisionKind::MoveFrom => "Moved away",
+            RevisionKind::MoveTo => "Moved here",
+        };
+
+        let mut description = String::from(action);
+        if !self.author.is_empty() {
+            description += &format!(" by {}", self.author);
+        }
+        if let Some(date) = &self.date {
+            description += &format!(", {}", instructions::format_date_time(instructions::ANNOTATION_DATE_PICTURE, date));
+        }
+        description
+    }
+
+    pub fn on_event(&self, event: &mut Event) {
+        if let Event::Hover(mouse_event) = event {
+            mouse_event.hover_text = Some(self.description());
+        }
+    }
+}
+
+/// The color of the changes of the author. The `authors` are those seen so
+/// far, to which the author is added when it wasn't seen before.
+pub fn author_color(authors: &mut Vec<String>, author: &str) -> Color {
+    let index = match authors.iter().position(|known| known == author) {
+        Some(index) => index,
+        None => {
+            authors.push(String::from(author));
+            authors.len() - 1
+        }
+    };
+
+    AUTHOR_COLORS[index % AUTHOR_COLORS.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_show_the_text_of_their_changes() {
+        assert!(RevisionView::Markup.shows(RevisionKind::Deletion));
+        assert!(RevisionView::Markup.shows(RevisionKind::Insertion));
+        assert!(RevisionView::Final.shows(RevisionKind::MoveTo));
+        assert!(!RevisionView::Final.shows(RevisionKind::Deletion));
+        assert!(RevisionView::Original.shows(RevisionKind::MoveFrom));
+        assert!(!RevisionView::Original.shows(RevisionKind::Insertion));
+
+        assert_eq!(RevisionView::Markup.next().next().next(), RevisionView::Markup);
+    }
+
+    #[test]
+    fn revisions_are_described_with_their_author_and_date() {
+        let text = r#"<w:del xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" w:id="4" w:author="Jane Doe" w:date="2023-03-05T14:07:00Z"/>"#;
+        let document = xml::Document::parse(text).unwrap();
+
+        let revision = Revision::from_element(&document.root_element()).unwrap();
+        assert_eq!(revision.kind, RevisionKind::Deletion);
+        assert_eq!(revision.id, "4");
+        assert_eq!(revision.description(), "Deleted by Jane Doe, 05-03-2023 14:07");
+
+        let anonymous = Revision { author: String::new(), date: None, ..revision };
+        assert_eq!(anonymous.description(), "Deleted");
+    }
+
+    #[test]
+    fn authors_keep_their_color() {
+        let mut authors = Vec::new();
+        let first = author_color(&mut authors, "Jane");
+        let second = author_color(&mut authors, "John");
+        assert_ne!(first, second);
+        assert_eq!(author_color(&mut authors, "Jane"), first);
+        assert_eq!(authors, ["Jane", "John"]);
+    }
+}
@@ -0,0 +1,957 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Saving documents back to a WordprocessingML package. The main document part
+// is written from the tree, and the other parts are copied unchanged from the
+// package the document was loaded from.
+//
+// The formatting is written as the direct formatting of the paragraphs and
+// runs, since the tree only contains the text settings after the styles are
+// applied. Content that isn't kept in the tree, like tabs, the results of
+// unsupported fields and the properties of table rows, is lost.
+
+use std::{
+    io::{Cursor, Write},
+    path::Path,
+};
+
+use crate::namespaces::XMLNS_RELATIONSHIPS;
+
+use crate::{
+    error::Error,
+    gui::Color,
+    package::{self, load_archive_file_to_string},
+    style::{BorderProperties, HexColor},
+    text_settings::{
+        LineSpacing,
+        TabStopAlignment,
+        TextJustification,
+        TextSettings,
+    },
+    WORD_PROCESSING_XML_NAMESPACE,
+};
+
+use super::{
+    instructions,
+    revisions::Revision,
+    table::{
+        TableCellProperties,
+        TableGrid,
+        TableProperties,
+    },
+    Document,
+    Node,
+    NodeData,
+};
+
+/// The name of the part containing the content types of the parts of the
+/// package.
+const CONTENT_TYPES_PART_NAME: &str = "[Content_Types].xml";
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// The document wasn't loaded from a package, so the parts other than
+    /// the main document part aren't available.
+    NoSourcePackage,
+
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+
+    /// A part of the source package couldn't be read.
+    Package(Error),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoSourcePackage => write!(f, "the document wasn't loaded from a package"),
+            Self::Io(error) => write!(f, "the file couldn't be written: {}", error),
+            Self::Zip(error) => write!(f, "the package couldn't be written: {}", error),
+            Self::Package(error) => write!(f, "the source package couldn't be read: {}", error),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<zip::result::ZipError> for SaveError {
+    fn from(error: zip::result::ZipError) -> Self {
+        Self::Zip(error)
+    }
+}
+
+impl From<Error> for SaveError {
+    fn from(error: Error) -> Self {
+        Self::Package(error)
+    }
+}
+
+impl Document {
+    /// Saves the document with the contents of the tree to `path`, which may
+    /// be the package the document was loaded from. The main document part is
+    /// stored as a template or as a document depending on the extension of
+    /// the path, so a document can be created from a template.
+    pub fn save_to(&self, root_node: &Node, path: &Path) -> Result<(), SaveError> {
+        let Some(source) = &self.source else {
+            return Err(SaveError::NoSourcePackage);
+        };
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&source.path)?)?;
+        let content_types = load_archive_file_to_string(&mut archive, CONTENT_TYPES_PART_NAME)?
+            .map(|text| package::convert_main_document_content_type(&text, package::is_template_path(path)));
+
+        // The package is built in memory, since the source package can't be
+        // overwritten while the parts are copied from it.
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index)?;
+            if file.name() != source.main_document_part && file.name() != CONTENT_TYPES_PART_NAME {
+                writer.raw_copy_file(file)?;
+            }
+        }
+
+        if let Some(content_types) = content_types {
+            writer.start_file(CONTENT_TYPES_PART_NAME, zip::write::FileOptions::default())?;
+            writer.write_all(content_types.as_bytes())?;
+        }
+
+        writer.start_file(source.main_document_part.as_str(), zip::write::FileOptions::default())?;
+        writer.write_all(write_document_part(self, root_node).as_bytes())?;
+
+        let package = writer.finish()?.into_inner();
+        std::fs::write(path, package)?;
+        Ok(())
+    }
+}
+
+/// The parts of an empty document, which is created by the "create new
+/// document" button of the welcome page. The styles part is required by us,
+/// and contains the defaults Microsoft Word uses for new documents.
+const BLANK_PACKAGE_PARTS: &[(&str, &str)] = &[
+    ("[Content_Types].xml", concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+        r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+        r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+        r#"<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>"#,
+        r#"<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>"#,
+        r#"</Types>"#,
+    )),
+    ("_rels/.rels", concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>"#,
+        r#"</Relationships>"#,
+    )),
+    ("word/_rels/document.xml.rels", concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+        r#"</Relationships>"#,
+    )),
+    ("word/styles.xml", concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+        r#"<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Calibri" w:cs="Calibri"/>"#,
+        r#"<w:sz w:val="22"/><w:szCs w:val="22"/><w:lang w:val="en-US"/></w:rPr></w:rPrDefault>"#,
+        r#"<w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="259" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults>"#,
+        r#"<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>"#,
+        r#"</w:styles>"#,
+    )),
+    ("word/document.xml", concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+        r#"<w:body><w:p/><w:sectPr><w:pgSz w:w="11906" w:h="16838"/>"#,
+        r#"<w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr>"#,
+        r#"</w:body></w:document>"#,
+    )),
+];
+
+/// Creates a package at `path` containing an empty document.
+pub fn create_blank_package(path: &Path) -> Result<(), SaveError> {
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(path)?);
+    for (name, contents) in BLANK_PACKAGE_PARTS {
+        writer.start_file(*name, zip::write::FileOptions::default())?;
+        writer.write_all(contents.as_bytes())?;
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+/// Writes the main document part (17.2.3 document) of the document.
+pub fn write_document_part(document: &Document, root_node: &Node) -> String {
+    let mut output = String::from(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#);
+    output += "\n<w:document";
+
+    for (prefix, uri) in &document.namespaces {
+        match prefix.as_deref() {
+            // The xml prefix is bound by definition and mustn't be declared.
+            Some("xml") => (),
+            Some(prefix) => output += &format!(r#" xmlns:{}="{}""#, prefix, escape(uri)),
+            None => output += &format!(r#" xmlns="{}""#, escape(uri)),
+        }
+    }
+
+    for (prefix, uri) in [("w", WORD_PROCESSING_XML_NAMESPACE), ("r", XMLNS_RELATIONSHIPS)] {
+        if !document.namespaces.iter().any(|(declared, _)| declared.as_deref() == Some(prefix)) {
+            output += &format!(r#" xmlns:{}="{}""#, prefix, uri);
+        }
+    }
+
+    output += "><w:body>";
+    write_children(&mut output, root_node);
+
+    match &document.body_section_properties {
+        Some(section_properties) => output.push_str(section_properties),
+        None => {
+            let page = &document.page_settings;
+            output += &format!(r#"<w:sectPr><w:pgSz w:w="{}" w:h="{}"/>"#, page.size.width().0, page.size.height().0);
+            output += &format!(r#"<w:pgMar w:top="{}" w:right="{}" w:bottom="{}" w:left="{}" w:header="{}" w:footer="{}" w:gutter="0"/></w:sectPr>"#,
+                page.margins.top.0, page.margins.right.0, page.margins.bottom.0, page.margins.left.0,
+                page.offset_header.0, page.offset_footer.0);
+        }
+    }
+
+    output += "</w:body></w:document>";
+    output
+}
+
+fn write_children(output: &mut String, node: &Node) {
+    for child in &node.children {
+        write_node(output, child);
+    }
+}
+
+fn write_node(output: &mut String, node: &Node) {
+    match &node.data {
+        NodeData::Document => write_children(output, node),
+
+        NodeData::Paragraph(paragraph) => {
+            *output += "<w:p>";
+            write_paragraph_properties(output, &node.text_settings, paragraph.section_properties.as_deref());
+            write_children(output, node);
+            *output += "</w:p>";
+        }
+
+        // The numbering is written as the numPr of the paragraph.
+        NodeData::NumberingParent => (),
+
+        NodeData::Hyperlink(hyperlink) => {
+            *output += "<w:hyperlink";
+            if let Some(relationship) = &hyperlink.relationship {
+                *output += &format!(r#" r:id="{}""#, escape(&relationship.borrow().id));
+            }
+            if let Some(anchor) = &hyperlink.anchor {
+                *output += &format!(r#" w:anchor="{}""#, escape(anchor));
+            }
+            *output += ">";
+            write_children(output, node);
+            *output += "</w:hyperlink>";
+        }
+
+        NodeData::BookmarkStart(bookmark) => {
+            *output += &format!(r#"<w:bookmarkStart w:id="{}" w:name="{}"/>"#, escape(&bookmark.id), escape(&bookmark.name));
+        }
+
+        NodeData::BookmarkEnd(id) => *output += &format!(r#"<w:bookmarkEnd w:id="{}"/>"#, escape(id)),
+
+        NodeData::CommentRangeStart(id) => *output += &format!(r#"<w:commentRangeStart w:id="{}"/>"#, escape(id)),
+        NodeData::CommentRangeEnd(id) => *output += &format!(r#"<w:commentRangeEnd w:id="{}"/>"#, escape(id)),
+
+        NodeData::Revision(revision) => write_revision(output, node, revision),
+
+        NodeData::StructuredDocumentTag(..) => {
+            *output += "<w:sdt><w:sdtContent>";
+            write_children(output, node);
+            *output += "</w:sdtContent></w:sdt>";
+        }
+
+        NodeData::Table { grid, properties } => write_table(output, node, grid, properties),
+
+        // Rows and cells are written by their table.
+        NodeData::TableRow | NodeData::TableCell(..) => (),
+
+        NodeData::TextRun(run) => {
+            *output += "<w:r>";
+            write_run_properties(output, &node.text_settings);
+            if let Some(id) = &run.comment_reference {
+                *output += &format!(r#"<w:commentReference w:id="{}"/>"#, escape(id));
+            }
+            match &run.instruction {
+                Some(field) => write_field(output, node, field),
+                None => write_run_content(output, node),
+            }
+            *output += "</w:r>";
+        }
+
+        // Floating drawings and page breaks can end up outside of a run.
+        NodeData::Break(..) | NodeData::Drawing(..) | NodeData::Text | NodeData::TextPart(..) => {
+            *output += "<w:r>";
+            write_run_content(output, node);
+            *output += "</w:r>";
+        }
+    }
+}
+
+/// Writes a tracked change with its runs. The runs of a deletion store their
+/// text as deleted text (17.3.3.7 delText), and a revision that wasn't shown is
+/// written as it was loaded.
+fn write_revision(output: &mut String, node: &Node, revision: &Revision) {
+    if let Some(markup) = &revision.hidden_markup {
+        *output += markup;
+        return;
+    }
+
+    let name = revision.kind.as_str();
+    *output += &format!(r#"<w:{name} w:id="{}" w:author="{}""#, escape(&revision.id), escape(&revision.author));
+    if let Some(date) = &revision.date {
+        *output += &format!(r#" w:date="{}""#, date.format("%Y-%m-%dT%H:%M:%SZ"));
+    }
+    *output += ">";
+
+    let mut content = String::new();
+    write_children(&mut content, node);
+    if revision.kind.is_insertion() {
+        *output += &content;
+    } else {
+        *output += &content
+            .replace("<w:t ", "<w:delText ")
+            .replace("</w:t>", "</w:delText>")
+            .replace("<w:instrText ", "<w:delInstrText ")
+            .replace("</w:instrText>", "</w:delInstrText>");
+    }
+
+    *output += &format!("</w:{name}>");
+}
+
+/// Writes the field of a run as a complex field (17.16.18 fldChar), with the
+/// content of the run as its result. The fields that aren't supported only
+/// show their instruction, which isn't a result to store.
+fn write_field(output: &mut String, node: &Node, field: &instructions::Field) {
+    *output += r#"<w:fldChar w:fldCharType="begin"/>"#;
+    *output += &format!(r#"<w:instrText xml:space="preserve">{}</w:instrText>"#, escape(field.instruction()));
+    if field.is_supported() {
+        *output += r#"<w:fldChar w:fldCharType="separate"/>"#;
+        write_run_content(output, node);
+    }
+    *output += r#"<w:fldChar w:fldCharType="end"/>"#;
+}
+
+/// Writes the content of a run, where the text of consecutive text parts is
+/// combined into a single `<w:t>`.
+fn write_run_content(output: &mut String, node: &Node) {
+    let mut text = String::new();
+    collect_run_content(output, node, &mut text);
+    flush_text(output, &mut text);
+}
+
+fn collect_run_content(output: &mut String, node: &Node, text: &mut String) {
+    match &node.data {
+        NodeData::TextPart(part) => text.push_str(&part.text),
+
+        NodeData::Break(break_type) => {
+            flush_text(output, text);
+            *output += &format!(r#"<w:br w:type="{}"/>"#, break_type.as_str());
+        }
+
+        NodeData::Drawing(drawing) => {
+            flush_text(output, text);
+            *output += "<w:drawing>";
+            *output += drawing.markup();
+            *output += "</w:drawing>";
+        }
+
+        _ => {
+            for child in &node.children {
+                collect_run_content(output, child, text);
+            }
+        }
+    }
+}
+
+fn flush_text(output: &mut String, text: &mut String) {
+    if !text.is_empty() {
+        *output += &format!(r#"<w:t xml:space="preserve">{}</w:t>"#, escape(text));
+        text.clear();
+    }
+}
+
+/// Writes the paragraph properties (17.3.1.26 pPr), in the order of the
+/// schema.
+fn write_paragraph_properties(output: &mut String, settings: &TextSettings, section_properties: Option<&str>) {
+    let mut properties = String::new();
+
+    if let Some(style_id) = &settings.paragraph_style_id {
+        properties += &format!(r#"<w:pStyle w:val="{}"/>"#, escape(style_id));
+    }
+
+    if let Some(numbering) = &settings.numbering {
+        if let Some(definition) = &numbering.definition {
+            properties += &format!(r#"<w:numPr><w:ilvl w:val="{}"/><w:numId w:val="{}"/></w:numPr>"#,
+                numbering.level.unwrap_or(0), definition.borrow().id);
+        }
+    }
+
+    if let Some(borders) = &settings.paragraph_borders {
+        let mut border_elements = String::new();
+        for (name, border) in [("top", borders.top), ("left", borders.left), ("bottom", borders.bottom), ("right", borders.right)] {
+            if let Some(border) = border {
+                write_border(&mut border_elements, name, &border);
+            }
+        }
+        if !border_elements.is_empty() {
+            properties += &format!("<w:pBdr>{}</w:pBdr>", border_elements);
+        }
+    }
+
+    if let Some(tab_stops) = &settings.tab_stops {
+        properties += "<w:tabs>";
+        for tab_stop in tab_stops {
+            let alignment = match tab_stop.alignment {
+                TabStopAlignment::Start => "start",
+                TabStopAlignment::Center => "center",
+                TabStopAlignment::End => "end",
+            };
+            properties += &format!(r#"<w:tab w:val="{}" w:pos="{}"/>"#, alignment, tab_stop.position.0);
+        }
+        properties += "</w:tabs>";
+    }
+
+    if let Some(bidi) = settings.bidi {
+        properties += &format!(r#"<w:bidi w:val="{}"/>"#, on_off(bidi));
+    }
+
+    let mut spacing = String::new();
+    if let Some(above) = settings.spacing_above_paragraph {
+        spacing += &format!(r#" w:before="{}""#, above.0);
+    }
+    if let Some(automatic) = settings.spacing_above_paragraph_automatic {
+        spacing += &format!(r#" w:beforeAutospacing="{}""#, on_off(automatic));
+    }
+    if let Some(below) = settings.spacing_below_paragraph {
+        spacing += &format!(r#" w:after="{}""#, below.0);
+    }
+    if let Some(automatic) = settings.spacing_below_paragraph_automatic {
+        spacing += &format!(r#" w:afterAutospacing="{}""#, on_off(automatic));
+    }
+    match settings.line_spacing {
+        // The automatic line spacing is in 240ths of a line.
+        Some(LineSpacing::Auto(factor)) => spacing += &format!(r#" w:line="{}" w:lineRule="auto""#, (factor * 240.0).round() as u32),
+        Some(LineSpacing::Exact(height)) => spacing += &format!(r#" w:line="{}" w:lineRule="exact""#, height.0),
+        Some(LineSpacing::AtLeast(height)) => spacing += &format!(r#" w:line="{}" w:lineRule="atLeast""#, height.0),
+        None => (),
+    }
+    if !spacing.is_empty() {
+        properties += &format!("<w:spacing{}/>", spacing);
+    }
+
+    let mut indentation = String::new();
+    if let Some(left) = settings.indentation_left {
+        indentation += &format!(r#" w:left="{}""#, left.0);
+    }
+    if let Some(right) = settings.indentation_right {
+        indentation += &format!(r#" w:right="{}""#, right.0);
+    }
+    if let Some(first_line) = settings.indentation_first_line {
+        indentation += &format!(r#" w:firstLine="{}""#, first_line.0);
+    }
+    if let Some(hanging) = settings.indentation_hanging {
+        indentation += &format!(r#" w:hanging="{}""#, hanging.0);
+    }
+    if !indentation.is_empty() {
+        properties += &format!("<w:ind{}/>", indentation);
+    }
+
+    if let Some(contextual_spacing) = settings.contextual_spacing {
+        properties += &format!(r#"<w:contextualSpacing w:val="{}"/>"#, on_off(contextual_spacing));
+    }
+
+    if let Some(justify) = settings.justify {
+        let value = match justify {
+            TextJustification::Start => "start",
+            TextJustification::Center => "center",
+            TextJustification::End => "end",
+            TextJustification::Both => "both",
+            TextJustification::Distribute => "distribute",
+        };
+        properties += &format!(r#"<w:jc w:val="{}"/>"#, value);
+    }
+
+    if let Some(outline_level) = settings.outline_level {
+        properties += &format!(r#"<w:outlineLvl w:val="{}"/>"#, outline_level);
+    }
+
+    if let Some(section_properties) = section_properties {
+        properties += section_properties;
+    }
+
+    if !properties.is_empty() {
+        *output += &format!("<w:pPr>{}</w:pPr>", properties);
+    }
+}
+
+/// Writes the run properties (17.3.2.28 rPr), in the order of the schema.
+fn write_run_properties(output: &mut String, settings: &TextSettings) {
+    let mut properties = String::new();
+
+    if let Some(font) = &settings.font {
+        let font = escape(font);
+        properties += &format!(r#"<w:rFonts w:ascii="{0}" w:hAnsi="{0}" w:cs="{0}" w:eastAsia="{0}"/>"#, font);
+    }
+
+    if let Some(bold) = settings.bold {
+        properties += &format!(r#"<w:b w:val="{}"/>"#, on_off(bold));
+    }
+
+    if let Some(italic) = settings.italic {
+        properties += &format!(r#"<w:i w:val="{}"/>"#, on_off(italic));
+    }
+
+    if let Some(color) = settings.color {
+        properties += &format!(r#"<w:color w:val="{}"/>"#, hex(color));
+    }
+
+    if let Some(spacing) = settings.character_spacing {
+        properties += &format!(r#"<w:spacing w:val="{}"/>"#, spacing.0);
+    }
+
+    if let Some(scale) = settings.horizontal_scale {
+        properties += &format!(r#"<w:w w:val="{}"/>"#, scale);
+    }
+
+    if let Some(kerning) = settings.kerning {
+        properties += &format!(r#"<w:kern w:val="{}"/>"#, kerning.0);
+    }
+
+    if let Some(size) = settings.non_complex_text_size {
+        properties += &format!(r#"<w:sz w:val="{}"/>"#, size.0);
+    }
+
+    if let Some(underline) = settings.underline {
+        match settings.underline_color {
+            Some(color) => properties += &format!(r#"<w:u w:val="{}" w:color="{}"/>"#, underline.as_str(), hex(color)),
+            None => properties += &format!(r#"<w:u w:val="{}"/>"#, underline.as_str()),
+        }
+    }
+
+    // The highlight is only read as a color, so it is written as shading,
+    // which looks the same.
+    if let Some(color) = settings.highlight_color {
+        properties += &format!(r#"<w:shd w:val="clear" w:color="auto" w:fill="{}"/>"#, hex(color));
+    }
+
+    if let Some(vertical_alignment) = settings.vertical_alignment {
+        properties += &format!(r#"<w:vertAlign w:val="{}"/>"#, vertical_alignment.as_str());
+    }
+
+    if let Some(right_to_left) = settings.right_to_left {
+        properties += &format!(r#"<w:rtl w:val="{}"/>"#, on_off(right_to_left));
+    }
+
+    let mut languages = String::new();
+    if let Some(language) = &settings.language {
+        languages += &format!(r#" w:val="{}""#, escape(language));
+    }
+    if let Some(language) = &settings.complex_script_language {
+        languages += &format!(r#" w:bidi="{}""#, escape(language));
+    }
+    if !languages.is_empty() {
+        properties += &format!("<w:lang{}/>", languages);
+    }
+
+    if !properties.is_empty() {
+        *output += &format!("<w:rPr>{}</w:rPr>", properties);
+    }
+}
+
+fn write_table(output: &mut String, table: &Node, grid: &TableGrid, properties: &TableProperties) {
+    *output += "<w:tbl><w:tblPr><w:tblBorders>";
+    let borders = &properties.borders;
+    write_border(output, "top", &borders.top);
+    write_border(output, "left", &borders.left);
+    write_border(output, "bottom", &borders.bottom);
+    write_border(output, "right", &borders.right);
+    write_border(output, "insideH", &borders.inside_horizontal);
+    write_border(output, "insideV", &borders.inside_vertical);
+    *output += "</w:tblBorders><w:tblCellMar>";
+
+    let margins = &properties.cell_margins;
+    for (name, margin) in [("top", margins.top), ("left", margins.left), ("bottom", margins.bottom), ("right", margins.right)] {
+        *output += &format!(r#"<w:{} w:w="{}" w:type="dxa"/>"#, name, margin.0);
+    }
+    *output += "</w:tblCellMar></w:tblPr><w:tblGrid>";
+
+    for column in &grid.0 {
+        *output += &format!(r#"<w:gridCol w:w="{}"/>"#, column.width.0);
+    }
+    *output += "</w:tblGrid>";
+
+    for row in &table.children {
+        *output += "<w:tr>";
+        for cell in &row.children {
+            if let NodeData::TableCell(properties) = &cell.data {
+                write_table_cell(output, cell, properties);
+            }
+        }
+        *output += "</w:tr>";
+    }
+
+    *output += "</w:tbl>";
+}
+
+fn write_table_cell(output: &mut String, cell: &Node, properties: &TableCellProperties) {
+    *output += "<w:tc><w:tcPr>";
+
+    if let Some(width) = properties.width {
+        *output += &format!(r#"<w:tcW w:w="{}" w:type="dxa"/>"#, width.0);
+    }
+
+    if properties.grid_span != 1 {
+        *output += &format!(r#"<w:gridSpan w:val="{}"/>"#, properties.grid_span);
+    }
+
+    // The borders were resolved against the table during layout, so writing
+    // them all keeps the cell looking the same.
+    let borders = &properties.borders;
+    let borders = [("top", borders.top), ("left", borders.left), ("bottom", borders.bottom), ("right", borders.right)];
+    if borders.iter().any(|(_, border)| border.is_some()) {
+        *output += "<w:tcBorders>";
+        for (name, border) in &borders {
+            if let Some(border) = border {
+                write_border(output, name, border);
+            }
+        }
+        *output += "</w:tcBorders>";
+    }
+
+    if let Some(shading) = properties.shading {
+        *output += &format!(r#"<w:shd w:val="clear" w:color="auto" w:fill="{}"/>"#, hex(shading));
+    }
+
+    *output += "</w:tcPr>";
+
+    // A cell must end with a paragraph (17.4.66 tc).
+    write_children(output, cell);
+    if !matches!(cell.children.last().map(|child| &child.data), Some(NodeData::Paragraph(..))) {
+        *output += "<w:p/>";
+    }
+
+    *output += "</w:tc>";
+}
+
+fn write_border(output: &mut String, name: &str, border: &BorderProperties) {
+    let color = match border.color {
+        HexColor::Auto => String::from("auto"),
+        HexColor::Color(color) => hex(color),
+    };
+
+    *output += &format!(r#"<w:{} w:val="{}" w:sz="{}" w:space="{}" w:color="{}"/>"#,
+        name, border.border_type.as_str(), border.width.0, border.spacing.0, color);
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "1" } else { "0" }
+}
+
+/// Formats the color as an ST_HexColorRGB, e.g. `FF0000`.
+fn hex(color: Color) -> String {
+    format!("{:02X}{:02X}{:02X}", color.red(), color.green(), color.blue())
+}
+
+/// Escapes the characters that can't appear as-is in text and attribute
+/// values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use roxmltree as xml;
+
+    use crate::TwelfteenthPoint;
+
+    use super::*;
+    use crate::{
+        gui::{Rect, Size},
+        text_settings::{PageSettings, UnderlineStyle},
+        wp::{self, document_properties::DocumentProperties, revisions::RevisionKind},
+    };
+
+    fn create_document() -> Document {
+        Document {
+            page_settings: PageSettings::new(
+                Size::new(TwelfteenthPoint(11906), TwelfteenthPoint(16838)),
+                Rect::from_positions(TwelfteenthPoint(1440), TwelfteenthPoint(1440), TwelfteenthPoint(1440), TwelfteenthPoint(1440)),
+                TwelfteenthPoint(708),
+                TwelfteenthPoint(708),
+            ),
+            document_properties: DocumentProperties::new(),
+            sections: Vec::new(),
+            footnote_areas: Vec::new(),
+            comments: Vec::new(),
+            revision_view: Default::default(),
+            note_separators: Vec::new(),
+            source: None,
+            namespaces: Vec::new(),
+            body_section_properties: None,
+            settings: Default::default(),
+            fonts: None,
+        }
+    }
+
+    fn create_paragraph(root: &mut Node, text: &str) -> usize {
+        let paragraph = wp::create_child(root, NodeData::Paragraph(Default::default()));
+        let paragraph_node = root.nth_child_mut(paragraph);
+
+        let run = wp::create_child(paragraph_node, NodeData::TextRun(Default::default()));
+        let run = paragraph_node.nth_child_mut(run);
+        run.text_settings.bold = Some(true);
+
+        wp::create_child(run, NodeData::TextPart(wp::TextPart {
+            text: String::from(text),
+            ascent: 0.0,
+            character_spacing: 0.0,
+            word_spacing: 0.0,
+            grapheme_ends: Vec::new(),
+            bidi_level: 0,
+        }));
+
+        paragraph
+    }
+
+    #[test]
+    fn document_part_contains_text_and_formatting() {
+        let mut root = Node::new(NodeData::Document);
+        let paragraph = create_paragraph(&mut root, "Fish & <chips>");
+        root.nth_child_mut(paragraph).text_settings.justify = Some(TextJustification::Center);
+
+        let text = write_document_part(&create_document(), &root);
+        let document = xml::Document::parse(&text).unwrap();
+
+        let paragraph = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "p"))).unwrap();
+        let justification = paragraph.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "jc"))).unwrap();
+        assert_eq!(justification.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")), Some("center"));
+
+        let bold = paragraph.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "b"))).unwrap();
+        assert_eq!(bold.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")), Some("1"));
+
+        let text = paragraph.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "t"))).unwrap();
+        assert_eq!(text.text(), Some("Fish & <chips>"));
+    }
+
+    #[test]
+    fn document_part_contains_section_properties_of_paragraphs() {
+        let mut root = Node::new(NodeData::Document);
+        let paragraph = create_paragraph(&mut root, "End of the section");
+        if let NodeData::Paragraph(data) = &mut root.nth_child_mut(paragraph).data {
+            data.section_properties = Some(std::rc::Rc::from(r#"<w:sectPr><w:pgSz w:w="16838" w:h="11906" w:orient="landscape"/></w:sectPr>"#));
+        }
+
+        let text = write_document_part(&create_document(), &root);
+        let document = xml::Document::parse(&text).unwrap();
+
+        let paragraph = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "p"))).unwrap();
+        let page_size = paragraph.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "pgSz"))).unwrap();
+        assert_eq!(page_size.parent().unwrap().parent().unwrap().tag_name().name(), "pPr");
+        assert_eq!(page_size.attribute((WORD_PROCESSING_XML_NAMESPACE, "orient")), Some("landscape"));
+    }
+
+    #[test]
+    fn document_part_contains_underline_style_and_color() {
+        let mut root = Node::new(NodeData::Document);
+        let paragraph = create_paragraph(&mut root, "Underlined");
+        let run = root.nth_child_mut(paragraph).nth_child_mut(0);
+        run.text_settings.underline = Some(UnderlineStyle::WavyDouble);
+        run.text_settings.underline_color = Some(Color::from_rgb(0xFF, 0x00, 0x00));
+
+        let text = write_document_part(&create_document(), &root);
+        let document = xml::Document::parse(&text).unwrap();
+
+        let underline = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "u"))).unwrap();
+        assert_eq!(underline.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")), Some("wavyDouble"));
+        assert_eq!(underline.attribute((WORD_PROCESSING_XML_NAMESPACE, "color")), Some("FF0000"));
+    }
+
+    #[test]
+    fn document_part_contains_fields_of_runs() {
+        let mut root = Node::new(NodeData::Document);
+        let paragraph = create_paragraph(&mut root, "7");
+        let run = root.nth_child_mut(paragraph).nth_child_mut(0);
+        if let NodeData::TextRun(data) = &mut run.data {
+            data.instruction = Some(instructions::Field::parse(r"PAGE \* MERGEFORMAT"));
+        }
+
+        let text = write_document_part(&create_document(), &root);
+        let document = xml::Document::parse(&text).unwrap();
+
+        let run = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "r"))).unwrap();
+        let content: Vec<_> = run.children()
+            .filter(|child| !child.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "rPr")))
+            .map(|child| (child.tag_name().name(), child.attribute((WORD_PROCESSING_XML_NAMESPACE, "fldCharType")), child.text()))
+            .collect();
+        assert_eq!(content, [
+            ("fldChar", Some("begin"), None),
+            ("instrText", None, Some(r"PAGE \* MERGEFORMAT")),
+            ("fldChar", Some("separate"), None),
+            ("t", None, Some("7")),
+            ("fldChar", Some("end"), None),
+        ]);
+    }
+
+    #[test]
+    fn document_part_contains_comment_ranges_and_references() {
+        let mut root = Node::new(NodeData::Document);
+        let paragraph = create_paragraph(&mut root, "Commented");
+        let paragraph_node = root.nth_child_mut(paragraph);
+        paragraph_node.children.insert(0, Node::new(NodeData::CommentRangeStart(String::from("3"))));
+        wp::create_child(paragraph_node, NodeData::CommentRangeEnd(String::from("3")));
+        wp::create_child(paragraph_node, NodeData::TextRun(wp::TextRun {
+            comment_reference: Some(String::from("3")),
+            ..Default::default()
+        }));
+
+        let text = write_document_part(&create_document(), &root);
+        let document = xml::Document::parse(&text).unwrap();
+
+        let paragraph = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "p"))).unwrap();
+        let content: Vec<_> = paragraph.children()
+            .filter(|child| !child.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "pPr")))
+            .map(|child| (child.tag_name().name(), child.attribute((WORD_PROCESSING_XML_NAMESPACE, "id"))))
+            .collect();
+        assert_eq!(content, [("commentRangeStart", Some("3")), ("r", None), ("commentRangeEnd", Some("3")), ("r", None)]);
+
+        let reference = paragraph.last_element_child().unwrap().last_element_child().unwrap();
+        assert!(reference.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "commentReference")));
+        assert_eq!(reference.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")), Some("3"));
+    }
+
+    #[test]
+    fn document_part_contains_revisions() {
+        let mut root = Node::new(NodeData::Document);
+        let paragraph = create_paragraph(&mut root, "Kept");
+        let paragraph_node = root.nth_child_mut(paragraph);
+
+        let deletion = wp::create_child(paragraph_node, NodeData::Revision(Revision {
+            kind: RevisionKind::Deletion,
+            id: String::from("1"),
+            author: String::from("Jane"),
+            date: None,
+            hidden_markup: None,
+        }));
+        let deletion = paragraph_node.nth_child_mut(deletion);
+        let run = wp::create_child(deletion, NodeData::TextRun(Default::default()));
+        wp::create_child(deletion.nth_child_mut(run), NodeData::TextPart(wp::TextPart {
+            text: String::from("Removed"),
+            ascent: 0.0,
+            character_spacing: 0.0,
+            word_spacing: 0.0,
+            grapheme_ends: Vec::new(),
+            bidi_level: 0,
+        }));
+        wp::create_child(paragraph_node, NodeData::Revision(Revision {
+            kind: RevisionKind::Insertion,
+            id: String::from("2"),
+            author: String::from("John"),
+            date: None,
+            hidden_markup: Some(Rc::from(r#"<w:ins w:id="2" w:author="John"><w:r><w:t>Hidden</w:t></w:r></w:ins>"#)),
+        }));
+
+        let text = write_document_part(&create_document(), &root);
+        let document = xml::Document::parse(&text).unwrap();
+
+        let deletion = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "del"))).unwrap();
+        assert_eq!(deletion.attribute((WORD_PROCESSING_XML_NAMESPACE, "author")), Some("Jane"));
+        let deleted_text = deletion.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "delText"))).unwrap();
+        assert_eq!(deleted_text.text(), Some("Removed"));
+        assert!(!deletion.descendants().any(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "t"))));
+
+        let insertion = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "ins"))).unwrap();
+        assert_eq!(insertion.attribute((WORD_PROCESSING_XML_NAMESPACE, "id")), Some("2"));
+        assert!(text.contains("<w:t>Hidden</w:t>"));
+    }
+
+    #[test]
+    fn document_part_keeps_section_properties_and_namespaces() {
+        let mut document = create_document();
+        document.namespaces = vec![
+            (Some("w".into()), WORD_PROCESSING_XML_NAMESPACE.into()),
+            (Some("w14".into()), "http://schemas.microsoft.com/office/word/2010/wordml".into()),
+        ];
+        document.body_section_properties = Some(r#"<w:sectPr w14:foo="1"><w:titlePg/></w:sectPr>"#.into());
+
+        let text = write_document_part(&document, &Node::new(NodeData::Document));
+        let document = xml::Document::parse(&text).unwrap();
+
+        let body = document.root_element().first_element_child().unwrap();
+        let section_properties = body.last_element_child().unwrap();
+        assert!(section_properties.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "sectPr")));
+        assert!(section_properties.first_element_child().unwrap().has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "titlePg")));
+    }
+
+    #[test]
+    fn document_part_without_section_properties_uses_page_settings() {
+        let text = write_document_part(&create_document(), &Node::new(NodeData::Document));
+        let document = xml::Document::parse(&text).unwrap();
+
+        let page_size = document.descendants().find(|node| node.has_tag_name((WORD_PROCESSING_XML_NAMESPACE, "pgSz"))).unwrap();
+        assert_eq!(page_size.attribute((WORD_PROCESSING_XML_NAMESPACE, "w")), Some("11906"));
+        assert_eq!(page_size.attribute((WORD_PROCESSING_XML_NAMESPACE, "h")), Some("16838"));
+    }
+
+    #[test]
+    fn blank_package_has_a_main_document_part_and_styles() {
+        let directory = std::env::temp_dir().join("uffice-blank-package-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("blank.docx");
+        create_blank_package(&path).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(crate::package::find_main_document_part(&mut archive), "word/document.xml");
+
+        for (name, _) in BLANK_PACKAGE_PARTS {
+            let text = std::io::read_to_string(archive.by_name(name).unwrap()).unwrap();
+            assert!(xml::Document::parse(&text).is_ok(), "part {} isn't well-formed", name);
+        }
+    }
+
+    #[test]
+    fn saving_a_template_as_a_document_changes_its_content_type() {
+        let directory = std::env::temp_dir().join("uffice-save-template-test");
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let content_type = |path: &Path| {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+            std::io::read_to_string(archive.by_name(CONTENT_TYPES_PART_NAME).unwrap()).unwrap()
+        };
+        let save = |source: &Path, target: &Path| {
+            let mut document = create_document();
+            document.source = Some(wp::SourcePackage {
+                path: source.to_path_buf(),
+                main_document_part: String::from("word/document.xml"),
+            });
+            document.save_to(&Node::new(NodeData::Document), target).unwrap();
+        };
+
+        let blank = directory.join("blank.docx");
+        create_blank_package(&blank).unwrap();
+
+        let template = directory.join("Letter.dotx");
+        save(&blank, &template);
+        assert!(content_type(&template).contains("wordprocessingml.template.main+xml"));
+
+        let document = directory.join("Letter.docx");
+        save(&template, &document);
+        assert!(content_type(&document).contains("wordprocessingml.document.main+xml"));
+        assert!(content_type(&document).contains("wordprocessingml.styles+xml"));
+    }
+}
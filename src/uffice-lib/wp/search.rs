@@ -0,0 +1,155 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Finding text in the document. The text of the text parts is indexed by
+// grapheme, so the matches can be expressed as text positions, which are
+// highlighted like the selection.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+    selection::{Selection, TextPosition},
+    Node,
+    NodeData,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+struct IndexedGrapheme {
+    /// The grapheme in lowercase, so the search is case-insensitive.
+    folded: String,
+
+    /// The position before the grapheme.
+    position: TextPosition,
+}
+
+/// The text of the document, grapheme by grapheme in document order, with a
+/// break at the end of every paragraph, since matches don't span paragraphs.
+#[derive(Debug, Default)]
+pub struct TextIndex {
+    graphemes: Vec<Option<IndexedGrapheme>>,
+}
+
+impl TextIndex {
+    /// Indexes the text parts of the tree, counting them the same way as the
+    /// [TextPosition] does.
+    pub fn new(root: &Node) -> Self {
+        let mut index = Self::default();
+        index.add_node(root, &mut 0);
+        index
+    }
+
+    fn add_node(&mut self, node: &Node, text_part: &mut usize) {
+        if let NodeData::TextPart(part) = &node.data {
+            for (offset, grapheme) in part.text.graphemes(true).enumerate() {
+                self.graphemes.push(Some(IndexedGrapheme {
+                    folded: grapheme.to_lowercase(),
+                    position: TextPosition { text_part: *text_part, offset },
+                }));
+            }
+            *text_part += 1;
+        }
+
+        for child in &node.children {
+            self.add_node(child, text_part);
+        }
+
+        if matches!(node.data, NodeData::Paragraph(..)) {
+            self.graphemes.push(None);
+        }
+    }
+
+    /// Finds the occurrences of the query, ignoring case, in document order.
+    /// The matches don't overlap, and are expressed as the text they would
+    /// select.
+    pub fn find_all(&self, query: &str) -> Vec<Selection> {
+        let query: Vec<String> = query.graphemes(true).map(str::to_lowercase).collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start + query.len() <= self.graphemes.len() {
+            let candidate = &self.graphemes[start..start + query.len()];
+            let is_match = candidate.iter()
+                .zip(&query)
+                .all(|(grapheme, query)| grapheme.as_ref().is_some_and(|grapheme| grapheme.folded == *query));
+
+            if !is_match {
+                start += 1;
+                continue;
+            }
+
+            let (Some(first), Some(last)) = (&candidate[0], &candidate[query.len() - 1]) else {
+                unreachable!("matches don't contain paragraph breaks");
+            };
+
+            matches.push(Selection {
+                anchor: first.position,
+                focus: TextPosition { offset: last.position.offset + 1, ..last.position },
+            });
+            start += query.len();
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wp::{create_child, TextPart};
+
+    fn create_text_part(parent: &mut Node, text: &str) {
+        create_child(parent, NodeData::TextPart(TextPart { text: String::from(text), ascent: 0.0, character_spacing: 0.0, word_spacing: 0.0, grapheme_ends: Vec::new(), bidi_level: 0 }));
+    }
+
+    /// Creates a document with a paragraph of which the text is split over
+    /// two text parts, and a paragraph with a single text part.
+    fn create_tree() -> Node {
+        let mut document = Node::new(NodeData::Document);
+
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        let run = create_child(document.nth_child_mut(paragraph), NodeData::TextRun(Default::default()));
+        create_text_part(document.nth_child_mut(paragraph).nth_child_mut(run), "The Quick bro");
+        create_text_part(document.nth_child_mut(paragraph).nth_child_mut(run), "wn fox");
+
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        let run = create_child(document.nth_child_mut(paragraph), NodeData::TextRun(Default::default()));
+        create_text_part(document.nth_child_mut(paragraph).nth_child_mut(run), "the end");
+
+        document
+    }
+
+    fn position(text_part: usize, offset: usize) -> TextPosition {
+        TextPosition { text_part, offset }
+    }
+
+    #[test]
+    fn matches_ignore_case() {
+        let index = TextIndex::new(&create_tree());
+        assert_eq!(index.find_all("THE"), vec![
+            Selection { anchor: position(0, 0), focus: position(0, 3) },
+            Selection { anchor: position(2, 0), focus: position(2, 3) },
+        ]);
+        assert_eq!(index.find_all("quick"), vec![Selection { anchor: position(0, 4), focus: position(0, 9) }]);
+    }
+
+    #[test]
+    fn matches_span_text_parts_but_not_paragraphs() {
+        let index = TextIndex::new(&create_tree());
+        assert_eq!(index.find_all("brown"), vec![Selection { anchor: position(0, 10), focus: position(1, 2) }]);
+        assert!(index.find_all("foxthe").is_empty());
+    }
+
+    #[test]
+    fn matches_do_not_overlap() {
+        let mut document = Node::new(NodeData::Document);
+        let paragraph = create_child(&mut document, NodeData::Paragraph(Default::default()));
+        create_text_part(document.nth_child_mut(paragraph), "aaaa");
+
+        let index = TextIndex::new(&document);
+        assert_eq!(index.find_all("aa").len(), 2);
+        assert!(index.find_all("").is_empty());
+    }
+}
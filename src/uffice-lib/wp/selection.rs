@@ -0,0 +1,164 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::ops::Range;
+
+use super::{Node, NodeData};
+
+/// A position between two graphemes in the text of the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPosition {
+    /// The index of the text part, counting the text parts of the document in
+    /// document order.
+    pub text_part: usize,
+
+    /// The index of the grapheme boundary in the text part, where 0 is before
+    /// the first grapheme.
+    pub offset: usize,
+}
+
+/// The text selected by the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    /// Where the selection was started.
+    pub anchor: TextPosition,
+
+    /// Where the selection was extended to, which is before the anchor when
+    /// the user selected backwards.
+    pub focus: TextPosition,
+}
+
+impl Selection {
+    /// Creates a selection without any selected text at the given position.
+    pub fn new_collapsed(position: TextPosition) -> Self {
+        Self {
+            anchor: position,
+            focus: position,
+        }
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    pub fn start(&self) -> TextPosition {
+        self.anchor.min(self.focus)
+    }
+
+    pub fn end(&self) -> TextPosition {
+        self.anchor.max(self.focus)
+    }
+
+    /// The range of the graphemes of the text part with the given index that
+    /// are selected, or None when none of them are.
+    pub fn selected_graphemes(&self, text_part: usize, grapheme_count: usize) -> Option<Range<usize>> {
+        let start = self.start();
+        let end = self.end();
+        if text_part < start.text_part || text_part > end.text_part {
+            return None;
+        }
+
+        let from = if text_part == start.text_part { start.offset } else { 0 };
+        let to = if text_part == end.text_part { end.offset } else { grapheme_count };
+        if from < to {
+            Some(from..to)
+        } else {
+            None
+        }
+    }
+}
+
+/// Stores the selected graphemes of every text part of the tree in its
+/// interaction states, so they can be highlighted when painting. Returns the
+/// pages of the text parts of which the selected graphemes changed, in
+/// ascending order, since only those pages look different.
+pub fn apply_selection(root: &mut Node, selection: Option<&Selection>) -> Vec<usize> {
+    let mut text_part_index = 0;
+    let mut changed_pages = Vec::new();
+    root.apply_recursively_mut(&mut |node, _depth| {
+        if let NodeData::TextPart(part) = &node.data {
+            let selected_graphemes = selection
+                .and_then(|selection| selection.selected_graphemes(text_part_index, part.grapheme_ends.len()));
+            if node.interaction_states.selected_graphemes != selected_graphemes {
+                changed_pages.push(node.page_first);
+                node.interaction_states.selected_graphemes = selected_graphemes;
+            }
+            text_part_index += 1;
+        }
+    }, 0);
+
+    changed_pages.sort_unstable();
+    changed_pages.dedup();
+    changed_pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(text_part: usize, offset: usize) -> TextPosition {
+        TextPosition { text_part, offset }
+    }
+
+    #[test]
+    fn selected_graphemes_across_text_parts() {
+        let selection = Selection { anchor: position(1, 2), focus: position(3, 1) };
+
+        assert_eq!(selection.selected_graphemes(0, 5), None);
+        assert_eq!(selection.selected_graphemes(1, 5), Some(2..5));
+        assert_eq!(selection.selected_graphemes(2, 5), Some(0..5));
+        assert_eq!(selection.selected_graphemes(3, 5), Some(0..1));
+        assert_eq!(selection.selected_graphemes(4, 5), None);
+    }
+
+    fn text_part(page: usize) -> Node {
+        let mut node = Node::new(NodeData::TextPart(crate::wp::TextPart {
+            text: String::from("Text"),
+            ascent: 0.0,
+            character_spacing: 0.0,
+            word_spacing: 0.0,
+            grapheme_ends: vec![1.0, 2.0, 3.0, 4.0],
+            bidi_level: 0,
+        }));
+        node.page_first = page;
+        node.page_last = page;
+        node
+    }
+
+    #[test]
+    fn applying_a_selection_returns_the_changed_pages() {
+        let mut root = Node::new(NodeData::Document);
+        root.children = vec![text_part(0), text_part(1), text_part(1), text_part(2)];
+
+        let selection = Selection { anchor: position(1, 2), focus: position(2, 1) };
+        assert_eq!(apply_selection(&mut root, Some(&selection)), vec![1]);
+        assert_eq!(root.children[1].interaction_states.selected_graphemes, Some(2..4));
+
+        // Only the pages of which the selected text changed.
+        let selection = Selection { anchor: position(1, 2), focus: position(3, 1) };
+        assert_eq!(apply_selection(&mut root, Some(&selection)), vec![1, 2]);
+        assert_eq!(apply_selection(&mut root, Some(&selection)), Vec::<usize>::new());
+
+        assert_eq!(apply_selection(&mut root, None), vec![1, 2]);
+    }
+
+    #[test]
+    fn backward_selection() {
+        let selection = Selection { anchor: position(0, 4), focus: position(0, 1) };
+        assert_eq!(selection.start(), position(0, 1));
+        assert_eq!(selection.selected_graphemes(0, 5), Some(1..4));
+    }
+
+    #[test]
+    fn collapsed_selection_selects_nothing() {
+        let selection = Selection::new_collapsed(position(2, 3));
+        assert!(selection.is_collapsed());
+        assert_eq!(selection.selected_graphemes(2, 5), None);
+
+        // The end of a text part is the same place as the start of the next,
+        // so nothing is selected.
+        let selection = Selection { anchor: position(0, 5), focus: position(1, 0) };
+        assert_eq!(selection.selected_graphemes(0, 5), None);
+        assert_eq!(selection.selected_graphemes(1, 5), None);
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// The document settings part (17.15 Settings), with the settings that apply
+// to the document as a whole instead of to a section or paragraph.
+
+use roxmltree as xml;
+use crate::TwelfteenthPoint;
+
+use crate::{word_processing::parse_on_off, WORD_PROCESSING_XML_NAMESPACE};
+
+use super::diagnostics::Diagnostic;
+
+/// The distance between the default tab stops when the settings don't specify
+/// it (17.15.1.25 defaultTabStop).
+pub const DEFAULT_TAB_STOP: TwelfteenthPoint<i32> = TwelfteenthPoint(720);
+
+/// The spacing above and below a paragraph with automatic spacing, in points,
+/// when [CompatibilitySettings::fixed_automatic_spacing] is set.
+pub const FIXED_AUTOMATIC_SPACING: f32 = 14.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DocumentSettings {
+    /// The distance between the tab stops that are used after the custom tab
+    /// stops of a paragraph (17.15.1.25 defaultTabStop).
+    pub default_tab_stop: TwelfteenthPoint<i32>,
+
+    /// The zoom factor the document was last viewed at (17.15.1.94 zoom),
+    /// where 1.0 is 100%.
+    pub zoom: Option<f32>,
+
+    /// Whether the even pages have headers and footers of their own, instead
+    /// of the default ones (17.10.1 evenAndOddHeaders).
+    pub even_and_odd_headers: bool,
+
+    /// Whether the left and right margins of the even pages are swapped, for
+    /// printing on both sides of the paper (17.15.1.57 mirrorMargins).
+    pub mirror_margins: bool,
+
+    pub compatibility: CompatibilitySettings,
+}
+
+impl Default for DocumentSettings {
+    fn default() -> Self {
+        Self {
+            default_tab_stop: DEFAULT_TAB_STOP,
+            zoom: None,
+            even_and_odd_headers: false,
+            mirror_margins: false,
+            compatibility: Default::default(),
+        }
+    }
+}
+
+/// The options that make the layout compatible with older versions of Word
+/// (17.15.1.21 compat).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompatibilitySettings {
+    /// The version of Word the document was laid out with, e.g. 15 for Word
+    /// 2013 and later (the compatibilityMode compatSetting).
+    pub compatibility_mode: Option<u32>,
+
+    /// Whether automatic paragraph spacing is a fixed 14 points, instead of a
+    /// line of the paragraph's font (doNotUseHTMLParagraphAutoSpacing).
+    pub fixed_automatic_spacing: bool,
+}
+
+impl DocumentSettings {
+    /// Reads the settings of the settings part. Invalid settings are reported
+    /// and keep their default value.
+    pub fn from_xml(document: &xml::Document, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let mut settings = Self::default();
+
+        for child in document.root_element().children() {
+            if child.tag_name().namespace() != Some(WORD_PROCESSING_XML_NAMESPACE) {
+                continue;
+            }
+
+            match child.tag_name().name() {
+                "defaultTabStop" => {
+                    match child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")).map(str::parse::<i32>) {
+                        Some(Ok(value)) if value > 0 => settings.default_tab_stop = TwelfteenthPoint(value),
+                        value => diagnostics.push(Diagnostic::error("w:defaultTabStop", format!("invalid value: {:?}", value))),
+                    }
+                }
+
+                "zoom" => {
+                    // ST_DecimalNumberOrPercent, e.g. "120" or "120%".
+                    let percent = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "percent"))
+                        .map(|percent| percent.trim_end_matches('%').parse::<f32>());
+                    match percent {
+                        Some(Ok(percent)) if percent > 0.0 => settings.zoom = Some(percent / 100.0),
+                        None => (),
+                        Some(value) => diagnostics.push(Diagnostic::error("w:zoom", format!("invalid percentage: {:?}", value))),
+                    }
+                }
+
+                "evenAndOddHeaders" => settings.even_and_odd_headers = parse_on_off_element(&child, "w:evenAndOddHeaders", diagnostics),
+                "mirrorMargins" => settings.mirror_margins = parse_on_off_element(&child, "w:mirrorMargins", diagnostics),
+                "compat" => settings.compatibility = CompatibilitySettings::from_xml(&child, diagnostics),
+
+                _ => (),
+            }
+        }
+
+        settings
+    }
+}
+
+impl CompatibilitySettings {
+    fn from_xml(element: &xml::Node, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let mut settings = Self::default();
+
+        for child in element.children() {
+            match child.tag_name().name() {
+                "compatSetting" => {
+                    let name = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "name"));
+                    let value = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val"));
+                    if let (Some("compatibilityMode"), Some(value)) = (name, value) {
+                        match value.parse() {
+                            Ok(mode) => settings.compatibility_mode = Some(mode),
+                            Err(e) => diagnostics.push(Diagnostic::error("w:compatSetting", format!("invalid compatibility mode \"{}\": {}", value, e))),
+                        }
+                    }
+                }
+
+                "doNotUseHTMLParagraphAutoSpacing" => {
+                    settings.fixed_automatic_spacing = parse_on_off_element(&child, "w:doNotUseHTMLParagraphAutoSpacing", diagnostics);
+                }
+
+                _ => (),
+            }
+        }
+
+        settings
+    }
+}
+
+/// An element of the CT_OnOff type is on when its value is missing.
+fn parse_on_off_element(element: &xml::Node, source: &str, diagnostics: &mut Vec<Diagnostic>) -> bool {
+    match element.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+        Some(val) => parse_on_off(val, source, diagnostics).unwrap_or(false),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(settings: &str) -> (DocumentSettings, Vec<Diagnostic>) {
+        let text = format!(r#"<w:settings xmlns:w="{}">{}</w:settings>"#, WORD_PROCESSING_XML_NAMESPACE, settings);
+        let document = xml::Document::parse(&text).unwrap();
+        let mut diagnostics = Vec::new();
+        (DocumentSettings::from_xml(&document, &mut diagnostics), diagnostics)
+    }
+
+    #[test]
+    fn settings_are_parsed() {
+        let (settings, diagnostics) = parse(concat!(
+            r#"<w:zoom w:percent="120"/><w:mirrorMargins/><w:defaultTabStop w:val="708"/><w:evenAndOddHeaders w:val="1"/>"#,
+            r#"<w:compat><w:doNotUseHTMLParagraphAutoSpacing/>"#,
+            r#"<w:compatSetting w:name="compatibilityMode" w:uri="http://schemas.microsoft.com/office/word" w:val="15"/></w:compat>"#,
+        ));
+
+        assert_eq!(diagnostics, Vec::new());
+        assert_eq!(settings, DocumentSettings {
+            default_tab_stop: TwelfteenthPoint(708),
+            zoom: Some(1.2),
+            even_and_odd_headers: true,
+            mirror_margins: true,
+            compatibility: CompatibilitySettings {
+                compatibility_mode: Some(15),
+                fixed_automatic_spacing: true,
+            },
+        });
+    }
+
+    #[test]
+    fn invalid_settings_keep_their_defaults() {
+        let (settings, diagnostics) = parse(r#"<w:defaultTabStop w:val="-1"/><w:zoom w:percent="big"/><w:mirrorMargins w:val="0"/>"#);
+
+        assert_eq!(settings, DocumentSettings::default());
+        assert_eq!(diagnostics.len(), 2);
+    }
+}
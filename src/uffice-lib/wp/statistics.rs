@@ -0,0 +1,108 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+//
+// Counting the words, characters and paragraphs of the document, for the
+// status bar. Like Word, the numbers of numbered paragraphs aren't counted.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Node, NodeData};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DocumentStatistics {
+    pub words: usize,
+
+    /// The number of graphemes, including the spaces.
+    pub characters: usize,
+
+    /// The number of paragraphs containing text.
+    pub paragraphs: usize,
+}
+
+impl DocumentStatistics {
+    /// Counts the text of the paragraphs in the tree.
+    pub fn count(root: &Node) -> Self {
+        let mut statistics = Self::default();
+        statistics.add_node(root);
+        statistics
+    }
+
+    fn add_node(&mut self, node: &Node) {
+        if !matches!(node.data, NodeData::Paragraph(..)) {
+            for child in &node.children {
+                self.add_node(child);
+            }
+            return;
+        }
+
+        // A word can be split over multiple text parts, e.g. when part of it
+        // is bold, so the text of the paragraph is counted as a whole.
+        let mut text = String::new();
+        collect_text(node, &mut text);
+
+        self.words += text.unicode_words().count();
+        self.characters += text.graphemes(true).count();
+        if !text.trim().is_empty() {
+            self.paragraphs += 1;
+        }
+    }
+}
+
+fn collect_text(node: &Node, text: &mut String) {
+    for child in &node.children {
+        match &child.data {
+            NodeData::NumberingParent => (),
+            NodeData::TextPart(part) => text.push_str(&part.text),
+            _ => collect_text(child, text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wp::{create_child, TextPart};
+
+    fn create_text_part(parent: &mut Node, text: &str) {
+        create_child(parent, NodeData::TextPart(TextPart { text: String::from(text), ascent: 0.0, character_spacing: 0.0, word_spacing: 0.0, grapheme_ends: Vec::new(), bidi_level: 0 }));
+    }
+
+    fn create_paragraph(document: &mut Node, texts: &[&str]) {
+        let paragraph = create_child(document, NodeData::Paragraph(Default::default()));
+        let run = create_child(document.nth_child_mut(paragraph), NodeData::TextRun(Default::default()));
+        for text in texts {
+            create_text_part(document.nth_child_mut(paragraph).nth_child_mut(run), text);
+        }
+    }
+
+    #[test]
+    fn words_split_over_text_parts_are_counted_once() {
+        let mut document = Node::new(NodeData::Document);
+        create_paragraph(&mut document, &["Hello wo", "rld, it's ", "me."]);
+        create_paragraph(&mut document, &["Bye"]);
+
+        assert_eq!(DocumentStatistics::count(&document), DocumentStatistics {
+            words: 5,
+            characters: 24,
+            paragraphs: 2,
+        });
+    }
+
+    #[test]
+    fn empty_paragraphs_and_numbering_are_not_counted() {
+        let mut document = Node::new(NodeData::Document);
+        create_paragraph(&mut document, &[]);
+        create_paragraph(&mut document, &["  "]);
+        create_paragraph(&mut document, &["Item"]);
+
+        let paragraph = document.nth_child_mut(2);
+        let numbering = create_child(paragraph, NodeData::NumberingParent);
+        create_text_part(paragraph.nth_child_mut(numbering), "1.");
+
+        assert_eq!(DocumentStatistics::count(&document), DocumentStatistics {
+            words: 1,
+            characters: 6,
+            paragraphs: 1,
+        });
+    }
+}
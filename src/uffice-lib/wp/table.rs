@@ -0,0 +1,627 @@
+// Copyright (C) 2023 Tristan Gerritsen <tristan@thewoosh.org>
+// All Rights Reserved.
+
+use std::{num::ParseIntError, str::FromStr};
+
+use crate::TwelfteenthPoint;
+
+use crate::{
+    gui::Color,
+    style::{
+        BorderProperties,
+        BorderPropertiesParseError,
+        BorderType,
+        HexColor,
+        HexColorParseError,
+    },
+    serialize::FromXmlStandalone, WORD_PROCESSING_XML_NAMESPACE,
+    text_settings::TextSettings,
+};
+
+#[derive(Clone, Debug,  Default)]
+pub struct GridColumnDefinition {
+    pub width: TwelfteenthPoint<u32>,
+}
+
+impl FromXmlStandalone for GridColumnDefinition {
+    type ParseError = ParseIntError;
+
+    fn from_xml(node: &roxmltree::Node) -> Result<Self, Self::ParseError>
+            where Self: Sized {
+        Ok(Self {
+            width: match node.attribute((WORD_PROCESSING_XML_NAMESPACE, "w")) {
+                Some(width) => TwelfteenthPoint(width.parse()?),
+                None => TwelfteenthPoint(0)
+            }
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TableBorderProperties {
+    pub top: BorderProperties,
+    pub left: BorderProperties,
+    pub bottom: BorderProperties,
+    pub right: BorderProperties,
+    pub inside_horizontal: BorderProperties,
+    pub inside_vertical: BorderProperties,
+}
+
+impl TableBorderProperties {
+    /// Resolves the borders of a cell, where the edges the cell doesn't
+    /// specify are inherited from the outer borders of the table, or from the
+    /// inside borders when the edge is between two cells.
+    pub fn resolve_for_cell(&self, cell: &TableCellBorderProperties, first_row: bool, last_row: bool,
+                            first_column: bool, last_column: bool) -> TableCellBorderProperties {
+        TableCellBorderProperties {
+            top: cell.top.or(Some(if first_row { self.top } else { self.inside_horizontal })),
+            left: cell.left.or(Some(if first_column { self.left } else { self.inside_vertical })),
+            bottom: cell.bottom.or(Some(if last_row { self.bottom } else { self.inside_horizontal })),
+            right: cell.right.or(Some(if last_column { self.right } else { self.inside_vertical })),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TableGrid(pub Vec<GridColumnDefinition>);
+
+impl FromXmlStandalone for TableGrid {
+    type ParseError = ParseIntError;
+
+    fn from_xml(node: &roxmltree::Node) -> Result<Self, Self::ParseError>
+            where Self: Sized {
+        let mut grid = Vec::new();
+
+        for child in node.children() {
+            if child.tag_name().name() == "gridCol" {
+                grid.push(GridColumnDefinition::from_xml(&child)?);
+            } else {
+                println!("[WARNING] Invalid table grid child: \"{}\"", child.tag_name().name());
+            }
+        }
+
+        Ok(Self(grid))
+    }
+}
+
+/// The default margins between the edges of the cells and their contents
+/// (17.4.42 tblCellMar).
+#[derive(Copy, Clone, Debug)]
+pub struct TableCellMargins {
+    pub top: TwelfteenthPoint<u32>,
+    pub left: TwelfteenthPoint<u32>,
+    pub bottom: TwelfteenthPoint<u32>,
+    pub right: TwelfteenthPoint<u32>,
+}
+
+impl Default for TableCellMargins {
+    /// Microsoft Word uses 0.08" on the left and right when the table doesn't
+    /// specify the margins.
+    fn default() -> Self {
+        Self {
+            top: TwelfteenthPoint(0),
+            left: TwelfteenthPoint(108),
+            bottom: TwelfteenthPoint(0),
+            right: TwelfteenthPoint(108),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TableProperties {
+    pub borders: TableBorderProperties,
+    pub cell_margins: TableCellMargins,
+
+    /// The number of rows and columns in a band of the conditional formatting
+    /// of a table style (17.7.6.7 tblStyleRowBandSize and 17.7.6.5
+    /// tblStyleColBandSize), which is one when [None].
+    pub row_band_size: Option<usize>,
+    pub column_band_size: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum TablePropertiesParseError {
+    UnknownTableProperty(String),
+    BorderPropertiesParseError(BorderPropertiesParseError),
+    ParseIntError(ParseIntError),
+}
+
+impl From<ParseIntError> for TablePropertiesParseError {
+    fn from(error: ParseIntError) -> Self {
+        TablePropertiesParseError::ParseIntError(error)
+    }
+}
+
+impl From<BorderPropertiesParseError> for TablePropertiesParseError {
+    fn from(error: BorderPropertiesParseError) -> Self {
+        TablePropertiesParseError::BorderPropertiesParseError(error)
+    }
+}
+
+impl FromXmlStandalone for TableProperties {
+    type ParseError = TablePropertiesParseError;
+    fn from_xml(node: &roxmltree::Node) -> Result<Self, TablePropertiesParseError>
+            where Self: Sized {
+        let mut properties = TableProperties::default();
+        properties.apply_xml(node)?;
+        Ok(properties)
+    }
+}
+
+impl TableProperties {
+    /// Overrides the properties the `<w:tblPr>` element specifies, e.g. the
+    /// properties of the table style with the properties of the table.
+    pub fn apply_xml(&mut self, node: &roxmltree::Node) -> Result<(), TablePropertiesParseError> {
+        let properties = self;
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "tblBorders" => {
+                    for border in child.children() {
+                        match border.tag_name().name() {
+                            "top" => properties.borders.top = BorderProperties::from_xml(&border)?,
+                            "left" => properties.borders.left = BorderProperties::from_xml(&border)?,
+                            "bottom" => properties.borders.bottom = BorderProperties::from_xml(&border)?,
+                            "right" => properties.borders.right = BorderProperties::from_xml(&border)?,
+                            "insideH" => properties.borders.inside_horizontal = BorderProperties::from_xml(&border)?,
+                            "insideV" => properties.borders.inside_vertical = BorderProperties::from_xml(&border)?,
+                            _ => ()//return Err(TablePropertiesParseError::UnknownTableProperty(border.tag_name().name().to_string()))
+                        }
+                    }
+                }
+                "tblCellMar" => {
+                    for margin in child.children() {
+                        match margin.tag_name().name() {
+                            "top" => properties.cell_margins.top = parse_width(&margin)?,
+                            "left" | "start" => properties.cell_margins.left = parse_width(&margin)?,
+                            "bottom" => properties.cell_margins.bottom = parse_width(&margin)?,
+                            "right" | "end" => properties.cell_margins.right = parse_width(&margin)?,
+                            _ => ()
+                        }
+                    }
+                }
+                "tblStyleRowBandSize" => {
+                    if let Some(value) = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                        properties.row_band_size = Some(value.parse()?);
+                    }
+                }
+                "tblStyleColBandSize" => {
+                    if let Some(value) = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                        properties.column_band_size = Some(value.parse()?);
+                    }
+                }
+                _ => ()
+                //_ => return Err(TablePropertiesParseError::UnknownTableProperty(child.tag_name().name().to_string()))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// ST_TblStyleOverrideType, the parts of a table that a table style can
+/// format differently (17.7.6.6 tblStylePr).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TableStyleOverrideType {
+    WholeTable,
+    FirstRow,
+    LastRow,
+    FirstColumn,
+    LastColumn,
+    Band1Vertical,
+    Band2Vertical,
+    Band1Horizontal,
+    Band2Horizontal,
+    NorthEastCell,
+    NorthWestCell,
+    SouthEastCell,
+    SouthWestCell,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableStyleOverrideTypeParseError {
+    UnknownOverrideType(String),
+}
+
+impl FromStr for TableStyleOverrideType {
+    type Err = TableStyleOverrideTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wholeTable" => Ok(Self::WholeTable),
+            "firstRow" => Ok(Self::FirstRow),
+            "lastRow" => Ok(Self::LastRow),
+            "firstCol" => Ok(Self::FirstColumn),
+            "lastCol" => Ok(Self::LastColumn),
+            "band1Vert" => Ok(Self::Band1Vertical),
+            "band2Vert" => Ok(Self::Band2Vertical),
+            "band1Horz" => Ok(Self::Band1Horizontal),
+            "band2Horz" => Ok(Self::Band2Horizontal),
+            "neCell" => Ok(Self::NorthEastCell),
+            "nwCell" => Ok(Self::NorthWestCell),
+            "seCell" => Ok(Self::SouthEastCell),
+            "swCell" => Ok(Self::SouthWestCell),
+            _ => Err(TableStyleOverrideTypeParseError::UnknownOverrideType(s.to_string()))
+        }
+    }
+}
+
+/// The formatting a table style gives to a part of the table.
+#[derive(Clone, Debug)]
+pub struct TableConditionalFormatting {
+    /// The paragraph and run properties of the paragraphs in the cells.
+    pub text_settings: TextSettings,
+    pub cell_borders: TableCellBorderProperties,
+    pub cell_shading: Option<Color>,
+}
+
+impl Default for TableConditionalFormatting {
+    fn default() -> Self {
+        Self {
+            text_settings: TextSettings::new(),
+            cell_borders: Default::default(),
+            cell_shading: None,
+        }
+    }
+}
+
+impl TableConditionalFormatting {
+    /// Overrides the properties the other formatting specifies.
+    pub fn apply(&mut self, other: &TableConditionalFormatting) {
+        self.text_settings.inherit_from(&other.text_settings);
+        self.cell_borders = other.cell_borders.or(&self.cell_borders);
+        self.cell_shading = other.cell_shading.or(self.cell_shading);
+    }
+}
+
+/// Which conditional formats of the table style are applied to the table
+/// (17.4.56 tblLook).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableLook {
+    pub first_row: bool,
+    pub last_row: bool,
+    pub first_column: bool,
+    pub last_column: bool,
+    pub no_horizontal_banding: bool,
+    pub no_vertical_banding: bool,
+}
+
+impl FromXmlStandalone for TableLook {
+    type ParseError = ParseIntError;
+
+    fn from_xml(node: &roxmltree::Node) -> Result<Self, Self::ParseError>
+            where Self: Sized {
+        let mut look = TableLook::default();
+
+        // Older documents write the flags as a hexadecimal bitmask, which the
+        // attributes of the flags override.
+        if let Some(value) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+            let mask = u16::from_str_radix(value, 16)?;
+            look.first_row = mask & 0x0020 != 0;
+            look.last_row = mask & 0x0040 != 0;
+            look.first_column = mask & 0x0080 != 0;
+            look.last_column = mask & 0x0100 != 0;
+            look.no_horizontal_banding = mask & 0x0200 != 0;
+            look.no_vertical_banding = mask & 0x0400 != 0;
+        }
+
+        for (name, flag) in [
+            ("firstRow", &mut look.first_row),
+            ("lastRow", &mut look.last_row),
+            ("firstColumn", &mut look.first_column),
+            ("lastColumn", &mut look.last_column),
+            ("noHBand", &mut look.no_horizontal_banding),
+            ("noVBand", &mut look.no_vertical_banding),
+        ] {
+            if let Some(value) = node.attribute((WORD_PROCESSING_XML_NAMESPACE, name)) {
+                *flag = matches!(value, "1" | "true" | "on");
+            }
+        }
+
+        Ok(look)
+    }
+}
+
+/// Where a cell is in its table, for selecting the conditional formats.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TableCellLocation {
+    pub row: usize,
+    pub row_count: usize,
+
+    /// The grid columns the cell spans, where the end is exclusive.
+    pub column_start: usize,
+    pub column_end: usize,
+    pub column_count: usize,
+}
+
+impl TableLook {
+    /// The conditional formats applied to the cell, from the lowest to the
+    /// highest priority. The header rows and columns don't take part in the
+    /// banding.
+    pub fn conditional_formats(&self, location: TableCellLocation, properties: &TableProperties) -> Vec<TableStyleOverrideType> {
+        let first_row = self.first_row && location.row == 0;
+        let last_row = self.last_row && location.row + 1 == location.row_count;
+        let first_column = self.first_column && location.column_start == 0;
+        let last_column = self.last_column && location.column_end >= location.column_count;
+
+        let mut formats = vec![TableStyleOverrideType::WholeTable];
+
+        if !self.no_vertical_banding && !first_column && !last_column {
+            let column = location.column_start - self.first_column as usize;
+            formats.push(match (column / properties.column_band_size.unwrap_or(1).max(1)) % 2 {
+                0 => TableStyleOverrideType::Band1Vertical,
+                _ => TableStyleOverrideType::Band2Vertical,
+            });
+        }
+
+        if !self.no_horizontal_banding && !first_row && !last_row {
+            let row = location.row - self.first_row as usize;
+            formats.push(match (row / properties.row_band_size.unwrap_or(1).max(1)) % 2 {
+                0 => TableStyleOverrideType::Band1Horizontal,
+                _ => TableStyleOverrideType::Band2Horizontal,
+            });
+        }
+
+        for (applies, format) in [
+            (first_column, TableStyleOverrideType::FirstColumn),
+            (last_column, TableStyleOverrideType::LastColumn),
+            (first_row, TableStyleOverrideType::FirstRow),
+            (last_row, TableStyleOverrideType::LastRow),
+            (first_row && first_column, TableStyleOverrideType::NorthWestCell),
+            (first_row && last_column, TableStyleOverrideType::NorthEastCell),
+            (last_row && first_column, TableStyleOverrideType::SouthWestCell),
+            (last_row && last_column, TableStyleOverrideType::SouthEastCell),
+        ] {
+            if applies {
+                formats.push(format);
+            }
+        }
+
+        formats
+    }
+}
+
+/// Parses the `w:w` attribute of a CT_TblWidth, which is in twentieths of a
+/// point for the `dxa` type.
+fn parse_width(node: &roxmltree::Node) -> Result<TwelfteenthPoint<u32>, ParseIntError> {
+    match node.attribute((WORD_PROCESSING_XML_NAMESPACE, "w")) {
+        Some(width) => Ok(TwelfteenthPoint(width.parse()?)),
+        None => Ok(TwelfteenthPoint(0)),
+    }
+}
+
+/// The properties of a table row (`<w:trPr>`).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TableRowProperties {
+    pub height: Option<TwelfteenthPoint<u32>>,
+
+    /// Whether the row is exactly `height` high, instead of at least.
+    pub exact_height: bool,
+}
+
+impl FromXmlStandalone for TableRowProperties {
+    type ParseError = ParseIntError;
+
+    fn from_xml(node: &roxmltree::Node) -> Result<Self, ParseIntError>
+            where Self: Sized {
+        let mut properties = TableRowProperties::default();
+
+        for child in node.children() {
+            if child.tag_name().name() == "trHeight" {
+                if let Some(value) = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                    properties.height = Some(TwelfteenthPoint(value.parse()?));
+                }
+
+                properties.exact_height = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "hRule")) == Some("exact");
+            }
+        }
+
+        Ok(properties)
+    }
+}
+
+/// The borders of a single cell. The edges that are None are inherited from
+/// the table.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TableCellBorderProperties {
+    pub top: Option<BorderProperties>,
+    pub left: Option<BorderProperties>,
+    pub bottom: Option<BorderProperties>,
+    pub right: Option<BorderProperties>,
+}
+
+impl TableCellBorderProperties {
+    /// Takes the edges this cell doesn't specify from the other borders.
+    pub fn or(&self, other: &TableCellBorderProperties) -> TableCellBorderProperties {
+        TableCellBorderProperties {
+            top: self.top.or(other.top),
+            left: self.left.or(other.left),
+            bottom: self.bottom.or(other.bottom),
+            right: self.right.or(other.right),
+        }
+    }
+}
+
+/// The properties of a table cell (`<w:tcPr>`).
+#[derive(Clone, Debug)]
+pub struct TableCellProperties {
+    /// The preferred width, only used when the table has no grid.
+    pub width: Option<TwelfteenthPoint<u32>>,
+
+    /// The number of grid columns this cell spans.
+    pub grid_span: usize,
+
+    /// The borders of the cell. After layout, these include the borders
+    /// inherited from the table.
+    pub borders: TableCellBorderProperties,
+
+    /// The background color of the cell.
+    pub shading: Option<Color>,
+}
+
+impl Default for TableCellProperties {
+    fn default() -> Self {
+        Self {
+            width: None,
+            grid_span: 1,
+            borders: Default::default(),
+            shading: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TableCellPropertiesParseError {
+    BorderPropertiesParseError(BorderPropertiesParseError),
+    HexColorParseError(HexColorParseError),
+    ParseIntError(ParseIntError),
+}
+
+impl From<BorderPropertiesParseError> for TableCellPropertiesParseError {
+    fn from(error: BorderPropertiesParseError) -> Self {
+        TableCellPropertiesParseError::BorderPropertiesParseError(error)
+    }
+}
+
+impl From<HexColorParseError> for TableCellPropertiesParseError {
+    fn from(error: HexColorParseError) -> Self {
+        TableCellPropertiesParseError::HexColorParseError(error)
+    }
+}
+
+impl From<ParseIntError> for TableCellPropertiesParseError {
+    fn from(error: ParseIntError) -> Self {
+        TableCellPropertiesParseError::ParseIntError(error)
+    }
+}
+
+impl FromXmlStandalone for TableCellProperties {
+    type ParseError = TableCellPropertiesParseError;
+
+    fn from_xml(node: &roxmltree::Node) -> Result<Self, TableCellPropertiesParseError>
+            where Self: Sized {
+        let mut properties = TableCellProperties::default();
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "tcW" => {
+                    // Percentages and automatic widths are left to the grid.
+                    if child.attribute((WORD_PROCESSING_XML_NAMESPACE, "type")).unwrap_or("dxa") == "dxa" {
+                        properties.width = Some(parse_width(&child)?);
+                    }
+                }
+                "gridSpan" => {
+                    if let Some(value) = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "val")) {
+                        properties.grid_span = value.parse::<usize>()?.max(1);
+                    }
+                }
+                "tcBorders" => {
+                    for border in child.children() {
+                        match border.tag_name().name() {
+                            "top" => properties.borders.top = Some(BorderProperties::from_xml(&border)?),
+                            "left" | "start" => properties.borders.left = Some(BorderProperties::from_xml(&border)?),
+                            "bottom" => properties.borders.bottom = Some(BorderProperties::from_xml(&border)?),
+                            "right" | "end" => properties.borders.right = Some(BorderProperties::from_xml(&border)?),
+                            _ => ()
+                        }
+                    }
+                }
+                "shd" => {
+                    if let Some(fill) = child.attribute((WORD_PROCESSING_XML_NAMESPACE, "fill")) {
+                        properties.shading = match fill.parse()? {
+                            HexColor::Auto => None,
+                            HexColor::Color(color) => Some(color),
+                        };
+                    }
+                }
+                _ => ()
+            }
+        }
+
+        Ok(properties)
+    }
+}
+
+/// Returns whether a border of this type is drawn at all.
+pub fn is_border_visible(border: &BorderProperties) -> bool {
+    !matches!(border.border_type, BorderType::Nil | BorderType::None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn border(width: u32) -> BorderProperties {
+        BorderProperties {
+            width: crate::EighteenthPoint(width),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cell_borders_inherit_outer_and_inside_borders() {
+        let table = TableBorderProperties {
+            top: border(1),
+            left: border(2),
+            bottom: border(3),
+            right: border(4),
+            inside_horizontal: border(5),
+            inside_vertical: border(6),
+        };
+
+        let cell = TableCellBorderProperties {
+            right: Some(border(7)),
+            ..Default::default()
+        };
+
+        // A cell in the top-left corner of a larger table.
+        let resolved = table.resolve_for_cell(&cell, true, false, true, false);
+        assert_eq!(resolved.top.unwrap().width.0, 1);
+        assert_eq!(resolved.left.unwrap().width.0, 2);
+        assert_eq!(resolved.bottom.unwrap().width.0, 5);
+        assert_eq!(resolved.right.unwrap().width.0, 7);
+    }
+
+    #[test]
+    fn cell_properties_are_parsed() {
+        let document = roxmltree::Document::parse(concat!(
+            r#"<w:tcPr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:tcW w:w="2400" w:type="dxa"/><w:gridSpan w:val="2"/><w:tcBorders><w:bottom w:val="nil"/></w:tcBorders>"#,
+            r#"<w:shd w:val="clear" w:color="auto" w:fill="FF0000"/></w:tcPr>"#,
+        )).unwrap();
+
+        let properties = TableCellProperties::from_xml(&document.root_element()).unwrap();
+        assert_eq!(properties.width, Some(TwelfteenthPoint(2400)));
+        assert_eq!(properties.grid_span, 2);
+        assert!(properties.borders.top.is_none());
+        assert!(!is_border_visible(&properties.borders.bottom.unwrap()));
+        assert_eq!(properties.shading, Some(Color::from_rgb(0xFF, 0, 0)));
+    }
+
+    #[test]
+    fn table_look_selects_the_conditional_formats() {
+        let document = roxmltree::Document::parse(concat!(
+            r#"<w:tblLook xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" "#,
+            r#"w:val="04A0" w:lastRow="1" w:noVBand="0"/>"#,
+        )).unwrap();
+
+        let look = TableLook::from_xml(&document.root_element()).unwrap();
+        assert_eq!(look, TableLook {
+            first_row: true,
+            last_row: true,
+            first_column: true,
+            last_column: false,
+            no_horizontal_banding: false,
+            no_vertical_banding: false,
+        });
+
+        let properties = TableProperties { row_band_size: Some(2), ..Default::default() };
+        let location = |row, column| TableCellLocation { row, row_count: 6, column_start: column, column_end: column + 1, column_count: 3 };
+
+        use TableStyleOverrideType::*;
+        assert_eq!(look.conditional_formats(location(0, 0), &properties), [WholeTable, FirstColumn, FirstRow, NorthWestCell]);
+        assert_eq!(look.conditional_formats(location(2, 1), &properties), [WholeTable, Band1Vertical, Band1Horizontal]);
+        assert_eq!(look.conditional_formats(location(3, 2), &properties), [WholeTable, Band2Vertical, Band2Horizontal]);
+        assert_eq!(look.conditional_formats(location(5, 2), &properties), [WholeTable, Band2Vertical, LastRow]);
+    }
+}
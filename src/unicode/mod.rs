@@ -1,4 +0,0 @@
-// Copyright (C) 2022 Tristan Gerritsen <tristan@thewoosh.org>
-// All Rights Reserved.
-
-pub mod alphabet;